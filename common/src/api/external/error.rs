@@ -64,6 +64,11 @@ pub enum Error {
 
     #[error("Conflict: {internal_message}")]
     Conflict { internal_message: String },
+
+    /// A precondition supplied by the client (e.g., an "If-Match" header) was
+    /// not satisfied.
+    #[error("Precondition Failed: {internal_message}")]
+    PreconditionFailed { internal_message: String },
 }
 
 /// Indicates how an object was looked up (for an `ObjectNotFound` error)
@@ -122,7 +127,8 @@ impl Error {
             | Error::MethodNotAllowed { .. }
             | Error::InternalError { .. }
             | Error::TypeVersionMismatch { .. }
-            | Error::Conflict { .. } => false,
+            | Error::Conflict { .. }
+            | Error::PreconditionFailed { .. } => false,
         }
     }
 
@@ -190,6 +196,16 @@ impl Error {
         Error::Conflict { internal_message: message.to_owned() }
     }
 
+    /// Generates an [`Error::PreconditionFailed`] with a specific message.
+    ///
+    /// This is used when a conditional request (e.g., one with an "If-Match"
+    /// header) is rejected because the target resource no longer matches the
+    /// condition.  The caller generally needs to fetch the resource again
+    /// before deciding whether to retry.
+    pub fn precondition_failed(message: &str) -> Error {
+        Error::PreconditionFailed { internal_message: message.to_owned() }
+    }
+
     /// Given an [`Error`] with an internal message, return the same error with
     /// `context` prepended to it to provide more context
     ///
@@ -242,6 +258,14 @@ impl Error {
             Error::Conflict { internal_message } => Error::Conflict {
                 internal_message: format!("{}: {}", context, internal_message),
             },
+            Error::PreconditionFailed { internal_message } => {
+                Error::PreconditionFailed {
+                    internal_message: format!(
+                        "{}: {}",
+                        context, internal_message
+                    ),
+                }
+            }
         }
     }
 }
@@ -344,6 +368,14 @@ impl From<Error> for HttpError {
                     internal_message,
                 )
             }
+
+            Error::PreconditionFailed { internal_message } => {
+                HttpError::for_client_error(
+                    Some(String::from("PreconditionFailed")),
+                    http::StatusCode::PRECONDITION_FAILED,
+                    internal_message,
+                )
+            }
         }
     }
 }
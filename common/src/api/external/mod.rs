@@ -28,6 +28,7 @@ use semver;
 use serde::Deserialize;
 use serde::Serialize;
 use serde_with::{DeserializeFromStr, SerializeDisplay};
+use std::collections::BTreeMap;
 use std::convert::TryFrom;
 use std::fmt::Debug;
 use std::fmt::Display;
@@ -683,17 +684,22 @@ impl TryFrom<i64> for Generation {
 pub enum ResourceType {
     AddressLot,
     AddressLotBlock,
+    BgpAnnounceSet,
+    BgpConfig,
     Fleet,
     Silo,
     SiloUser,
     SiloGroup,
     IdentityProvider,
     SamlIdentityProvider,
+    OidcIdentityProvider,
     SshKey,
+    WebhookReceiver,
     Certificate,
     ConsoleSession,
     DeviceAuthRequest,
     DeviceAccessToken,
+    ScimClientBearerToken,
     Project,
     Dataset,
     Disk,
@@ -701,6 +707,8 @@ pub enum ResourceType {
     SiloImage,
     ProjectImage,
     Instance,
+    FloatingIp,
+    AffinityGroup,
     LoopbackAddress,
     SwitchPortSettings,
     IpPool,
@@ -714,11 +722,13 @@ pub enum ResourceType {
     Switch,
     SagaDbg,
     Snapshot,
+    SnapshotSchedule,
     Volume,
     Vpc,
     VpcFirewallRule,
     VpcSubnet,
     VpcRouter,
+    VpcPeering,
     RouterRoute,
     Oximeter,
     MetricProducer,
@@ -765,6 +775,233 @@ pub struct IdentityMetadataUpdateParams {
     pub description: Option<String>,
 }
 
+// LABELS
+
+/// User-defined key/value pairs attached to a resource
+///
+/// Labels are not interpreted by the system.  They're intended for organizing
+/// resources (e.g., for cost attribution) and for selecting resources when
+/// listing them (see [`LabelSelector`]).
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(try_from = "BTreeMap<String, String>")]
+pub struct Labels(BTreeMap<String, String>);
+
+impl Labels {
+    /// Maximum number of labels attached to any one resource
+    pub const MAX_LABELS: usize = 64;
+    /// Maximum length of a label key
+    pub const MAX_KEY_LENGTH: usize = 63;
+    /// Maximum length of a label value
+    pub const MAX_VALUE_LENGTH: usize = 255;
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).map(|v| v.as_str())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.0.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    fn validate_key(key: &str) -> Result<(), String> {
+        if key.is_empty() || key.len() > Self::MAX_KEY_LENGTH {
+            return Err(format!(
+                "label key {:?} must be between 1 and {} characters long",
+                key,
+                Self::MAX_KEY_LENGTH
+            ));
+        }
+        if !key.starts_with(|c: char| c.is_ascii_lowercase()) {
+            return Err(format!(
+                "label key {:?} must begin with a lower case ASCII letter",
+                key
+            ));
+        }
+        if !key.chars().all(|c| {
+            c.is_ascii_lowercase()
+                || c.is_ascii_digit()
+                || matches!(c, '-' | '_' | '.' | '/')
+        }) {
+            return Err(format!(
+                "label key {:?} may contain only lower case ASCII letters, \
+                 digits, '-', '_', '.', and '/'",
+                key
+            ));
+        }
+        Ok(())
+    }
+
+    fn validate_value(key: &str, value: &str) -> Result<(), String> {
+        if value.len() > Self::MAX_VALUE_LENGTH {
+            return Err(format!(
+                "value for label {:?} must be at most {} characters long",
+                key,
+                Self::MAX_VALUE_LENGTH
+            ));
+        }
+        if !value.chars().all(|c| {
+            c.is_ascii_alphanumeric()
+                || matches!(c, '-' | '_' | '.' | '/' | ':' | '@')
+        }) {
+            return Err(format!(
+                "value for label {:?} may contain only ASCII letters, \
+                 digits, '-', '_', '.', '/', ':', and '@'",
+                key
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl TryFrom<BTreeMap<String, String>> for Labels {
+    type Error = String;
+
+    fn try_from(labels: BTreeMap<String, String>) -> Result<Self, Self::Error> {
+        if labels.len() > Self::MAX_LABELS {
+            return Err(format!(
+                "at most {} labels may be attached to a resource",
+                Self::MAX_LABELS
+            ));
+        }
+        for (key, value) in &labels {
+            Self::validate_key(key)?;
+            Self::validate_value(key, value)?;
+        }
+        Ok(Labels(labels))
+    }
+}
+
+impl JsonSchema for Labels {
+    fn schema_name() -> String {
+        "Labels".to_string()
+    }
+    fn json_schema(
+        gen: &mut schemars::gen::SchemaGenerator,
+    ) -> schemars::schema::Schema {
+        schemars::schema::SchemaObject {
+            metadata: Some(Box::new(schemars::schema::Metadata {
+                title: Some(
+                    "User-defined key/value pairs attached to a resource"
+                        .to_string(),
+                ),
+                description: Some(
+                    "Label keys must begin with a lower case ASCII letter and \
+                     be composed exclusively of lower case ASCII letters, \
+                     digits, '-', '_', '.', and '/'.  Label values may \
+                     contain ASCII letters, digits, '-', '_', '.', '/', ':', \
+                     and '@'."
+                        .to_string(),
+                ),
+                ..Default::default()
+            })),
+            instance_type: Some(schemars::schema::InstanceType::Object.into()),
+            object: Some(Box::new(schemars::schema::ObjectValidation {
+                max_properties: Some(Labels::MAX_LABELS as u32),
+                additional_properties: Some(Box::new(
+                    gen.subschema_for::<String>(),
+                )),
+                ..Default::default()
+            })),
+            ..Default::default()
+        }
+        .into()
+    }
+}
+
+/// Selects resources having all of a given set of labels
+///
+/// The string form is a comma-separated list of `key=value` terms, as in
+/// `env=prod,team=storage`.  A resource matches if it has every one of these
+/// labels (it may have others, too).
+#[derive(
+    Clone, Debug, Default, DeserializeFromStr, Eq, PartialEq, SerializeDisplay,
+)]
+pub struct LabelSelector(Labels);
+
+impl LabelSelector {
+    /// Returns the labels that a resource must have to match this selector
+    pub fn labels(&self) -> &Labels {
+        &self.0
+    }
+
+    /// Returns whether a resource with the given labels matches this selector
+    pub fn matches(&self, labels: &Labels) -> bool {
+        self.0.iter().all(|(k, v)| labels.get(k) == Some(v))
+    }
+}
+
+impl FromStr for LabelSelector {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut labels = BTreeMap::new();
+        for term in value.split(',').map(|t| t.trim()) {
+            if term.is_empty() {
+                continue;
+            }
+            let (key, value) = term.split_once('=').ok_or_else(|| {
+                format!(
+                    "label selector term {:?} is not of the form key=value",
+                    term
+                )
+            })?;
+            let (key, value) = (key.trim(), value.trim());
+            if let Some(old) = labels.insert(key.to_string(), value.to_string())
+            {
+                if old != value {
+                    return Err(format!(
+                        "label selector requires conflicting values for {:?}",
+                        key
+                    ));
+                }
+            }
+        }
+        Labels::try_from(labels).map(LabelSelector)
+    }
+}
+
+impl Display for LabelSelector {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FormatResult {
+        let mut sep = "";
+        for (key, value) in self.0.iter() {
+            write!(f, "{}{}={}", sep, key, value)?;
+            sep = ",";
+        }
+        Ok(())
+    }
+}
+
+impl JsonSchema for LabelSelector {
+    fn schema_name() -> String {
+        "LabelSelector".to_string()
+    }
+    fn json_schema(
+        _: &mut schemars::gen::SchemaGenerator,
+    ) -> schemars::schema::Schema {
+        schemars::schema::SchemaObject {
+            metadata: Some(Box::new(schemars::schema::Metadata {
+                title: Some(
+                    "Selects resources having all of a given set of labels"
+                        .to_string(),
+                ),
+                description: Some(
+                    "A comma-separated list of `key=value` terms, as in \
+                     `env=prod,team=storage`.  A resource matches if it has \
+                     every one of these labels."
+                        .to_string(),
+                ),
+                ..Default::default()
+            })),
+            instance_type: Some(schemars::schema::InstanceType::String.into()),
+            ..Default::default()
+        }
+        .into()
+    }
+}
+
 // Specific API resources
 
 // INSTANCES
@@ -880,6 +1117,30 @@ impl InstanceState {
     }
 }
 
+/// Whether an Instance that fails should be restarted automatically
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    Default,
+    Deserialize,
+    Eq,
+    PartialEq,
+    Serialize,
+    JsonSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum InstanceAutoRestartPolicy {
+    /// The instance is never restarted automatically.
+    #[default]
+    Never,
+    /// If the instance fails (for example, because its sled or its Propolis
+    /// server failed), the system tries to restart it, on another sled if
+    /// possible.  Successive restarts of an instance that keeps failing are
+    /// spaced further and further apart.
+    BestEffort,
+}
+
 /// The number of CPUs in an Instance
 #[derive(Copy, Clone, Debug, Deserialize, Serialize, JsonSchema)]
 pub struct InstanceCpuCount(pub u16);
@@ -932,6 +1193,16 @@ pub struct Instance {
     pub memory: ByteCount,
     /// RFC1035-compliant hostname for the Instance.
     pub hostname: String, // TODO-cleanup different type?
+    /// user-defined labels attached to this Instance
+    pub labels: Labels,
+    /// whether this Instance is restarted automatically if it fails
+    pub auto_restart_policy: InstanceAutoRestartPolicy,
+    /// the number of times the system has tried to restart this Instance
+    /// since it last ran without failing for a while
+    pub auto_restart_attempts: u32,
+    /// the time at which the system last tried to restart this Instance, if
+    /// it ever has
+    pub time_last_auto_restarted: Option<DateTime<Utc>>,
 
     #[serde(flatten)]
     pub runtime: InstanceRuntimeState,
@@ -951,6 +1222,8 @@ pub struct Disk {
     pub block_size: ByteCount,
     pub state: DiskState,
     pub device_path: String,
+    /// user-defined labels attached to this Disk
+    pub labels: Labels,
 }
 
 /// State of a Disk
@@ -979,6 +1252,9 @@ pub enum DiskState {
     ImportingFromBulkWrites,
     /// Disk is being finalized to state Detached
     Finalizing,
+    /// Disk is attached to a Pantry so that its blocks can be exported with
+    /// bulk reads
+    Exporting,
     /// Disk is undergoing maintenance
     Maintenance,
     /// Disk is being attached to the given Instance
@@ -1014,6 +1290,7 @@ impl TryFrom<(&str, Option<Uuid>)> for DiskState {
                 Ok(DiskState::ImportingFromBulkWrites)
             }
             ("finalizing", None) => Ok(DiskState::Finalizing),
+            ("exporting", None) => Ok(DiskState::Exporting),
             ("maintenance", None) => Ok(DiskState::Maintenance),
             ("destroyed", None) => Ok(DiskState::Destroyed),
             ("faulted", None) => Ok(DiskState::Faulted),
@@ -1038,6 +1315,7 @@ impl DiskState {
             DiskState::ImportingFromUrl => "importing_from_url",
             DiskState::ImportingFromBulkWrites => "importing_from_bulk_writes",
             DiskState::Finalizing => "finalizing",
+            DiskState::Exporting => "exporting",
             DiskState::Maintenance => "maintenance",
             DiskState::Attaching(_) => "attaching",
             DiskState::Attached(_) => "attached",
@@ -1067,6 +1345,7 @@ impl DiskState {
             DiskState::ImportingFromUrl => None,
             DiskState::ImportingFromBulkWrites => None,
             DiskState::Finalizing => None,
+            DiskState::Exporting => None,
             DiskState::Maintenance => None,
             DiskState::Destroyed => None,
             DiskState::Faulted => None,
@@ -2493,6 +2772,12 @@ pub struct SwitchPortBgpPeerConfig {
 
     /// The address of the peer.
     pub addr: IpAddr,
+
+    /// Whether the peer has been configured on the switch's routing daemon.
+    /// Nexus can't configure BGP peers on the switches yet, so this is always
+    /// false: the peer is stored, and checked against the port's addresses
+    /// when the settings are applied, but no session is established.
+    pub applied: bool,
 }
 
 /// A base BGP configuration.
@@ -2554,6 +2839,8 @@ mod test {
     use serde::Serialize;
 
     use super::IpNet;
+    use super::LabelSelector;
+    use super::Labels;
     use super::RouteDestination;
     use super::RouteTarget;
     use super::SemverVersion;
@@ -2568,6 +2855,7 @@ mod test {
     };
     use crate::api::external::Error;
     use crate::api::external::ResourceType;
+    use std::collections::BTreeMap;
     use std::convert::TryFrom;
     use std::str::FromStr;
 
@@ -2777,6 +3065,65 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_labels() {
+        let labels: Labels =
+            serde_json::from_str(r#"{"env": "prod", "team/owner": "a-b_c"}"#)
+                .unwrap();
+        assert_eq!(labels.get("env"), Some("prod"));
+        assert_eq!(labels.get("team/owner"), Some("a-b_c"));
+        assert_eq!(
+            serde_json::to_string(&labels).unwrap(),
+            r#"{"env":"prod","team/owner":"a-b_c"}"#
+        );
+        assert!(serde_json::from_str::<Labels>("{}").unwrap().is_empty());
+
+        let bad = [
+            r#"{"": "x"}"#,
+            r#"{"Env": "x"}"#,
+            r#"{"1env": "x"}"#,
+            r#"{"env": "a,b"}"#,
+            r#"{"env": "a=b"}"#,
+            r#"{"env space": "x"}"#,
+        ];
+        for input in bad {
+            assert!(
+                serde_json::from_str::<Labels>(input).is_err(),
+                "unexpectedly parsed {}",
+                input
+            );
+        }
+        let too_many: BTreeMap<_, _> = (0..=Labels::MAX_LABELS)
+            .map(|i| (format!("key{}", i), String::new()))
+            .collect();
+        assert!(Labels::try_from(too_many).is_err());
+    }
+
+    #[test]
+    fn test_label_selector() {
+        let selector: LabelSelector =
+            " env=prod, team=storage,".parse().unwrap();
+        assert_eq!(selector.to_string(), "env=prod,team=storage");
+        assert_eq!(selector.labels().get("team"), Some("storage"));
+
+        let labels = |json: &str| serde_json::from_str::<Labels>(json).unwrap();
+        assert!(selector.matches(&labels(
+            r#"{"env": "prod", "team": "storage", "x": "y"}"#
+        )));
+        assert!(!selector.matches(&labels(r#"{"env": "prod"}"#)));
+        assert!(
+            !selector.matches(&labels(r#"{"env": "dev", "team": "storage"}"#))
+        );
+        assert!(LabelSelector::default().matches(&Labels::default()));
+        assert!("".parse::<LabelSelector>().unwrap().labels().is_empty());
+        assert!("env=".parse::<LabelSelector>().is_ok());
+
+        assert!("env".parse::<LabelSelector>().is_err());
+        assert!("Env=prod".parse::<LabelSelector>().is_err());
+        assert!("env=prod,env=dev".parse::<LabelSelector>().is_err());
+        assert!("env=prod,env=prod".parse::<LabelSelector>().is_ok());
+    }
+
     #[test]
     fn test_bytecount() {
         // Smallest supported value: all constructors
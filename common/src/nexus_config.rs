@@ -298,6 +298,16 @@ pub struct BackgroundTaskConfig {
     pub dns_external: DnsTasksConfig,
     /// configuration for external endpoint list watcher
     pub external_endpoints: ExternalEndpointsConfig,
+    /// configuration for audit log retention
+    pub audit_log: AuditLogConfig,
+    /// configuration for automatically restarting failed instances
+    pub instance_auto_restart: InstanceAutoRestartConfig,
+    /// configuration for running scheduled snapshots
+    pub snapshot_schedules: SnapshotSchedulesConfig,
+    /// configuration for delivering webhook events
+    pub webhook_deliveries: WebhookDeliveriesConfig,
+    /// configuration for sending the DNS zones of VPCs to sled agents
+    pub vpc_dns: VpcDnsConfig,
 }
 
 #[serde_as]
@@ -332,6 +342,87 @@ pub struct ExternalEndpointsConfig {
     // allow/disallow wildcard certs, don't serve expired certs, etc.)
 }
 
+#[serde_as]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct AuditLogConfig {
+    /// period (in seconds) for periodic activations of the background task that
+    /// prunes old entries from the audit log
+    #[serde_as(as = "DurationSeconds<u64>")]
+    pub period_secs: Duration,
+
+    /// how long (in days) entries are kept in the audit log before they're
+    /// pruned
+    pub retention_days: u32,
+}
+
+#[serde_as]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct InstanceAutoRestartConfig {
+    /// period (in seconds) for periodic activations of the background task that
+    /// restarts failed instances
+    #[serde_as(as = "DurationSeconds<u64>")]
+    pub period_secs: Duration,
+
+    /// how long (in seconds) to wait after automatically restarting an
+    /// instance before restarting it again if it fails again
+    ///
+    /// This wait doubles with each further attempt, up to `max_backoff_secs`.
+    #[serde_as(as = "DurationSeconds<u64>")]
+    pub cooldown_secs: Duration,
+
+    /// the longest (in seconds) to wait between automatic restarts of an
+    /// instance that keeps failing
+    ///
+    /// An instance that runs for longer than this before failing is treated as
+    /// though it had never been restarted.
+    #[serde_as(as = "DurationSeconds<u64>")]
+    pub max_backoff_secs: Duration,
+}
+
+#[serde_as]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct SnapshotSchedulesConfig {
+    /// period (in seconds) for periodic activations of the background task that
+    /// runs the snapshot schedules that are due
+    #[serde_as(as = "DurationSeconds<u64>")]
+    pub period_secs: Duration,
+}
+
+#[serde_as]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct WebhookDeliveriesConfig {
+    /// period (in seconds) for periodic activations of the background task that
+    /// delivers webhook events that are due to be sent
+    #[serde_as(as = "DurationSeconds<u64>")]
+    pub period_secs: Duration,
+
+    /// how long (in seconds) to wait after a failed delivery of a webhook event
+    /// before trying again
+    ///
+    /// This wait doubles with each further attempt.
+    #[serde_as(as = "DurationSeconds<u64>")]
+    pub retry_backoff_secs: Duration,
+
+    /// how many times to try to deliver a webhook event before giving up
+    pub max_attempts: u32,
+
+    /// whether webhook receivers' endpoints may be at loopback, private,
+    /// link-local, or other non-global addresses
+    ///
+    /// This is only meant for testing, where receivers run locally.
+    #[serde(default)]
+    pub allow_private_endpoints: bool,
+}
+
+#[serde_as]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct VpcDnsConfig {
+    /// period (in seconds) for periodic activations of the background task that
+    /// sends the DNS zones of VPCs to the sleds running their instances
+    #[serde_as(as = "DurationSeconds<u64>")]
+    pub period_secs: Duration,
+}
+
 /// Configuration for a nexus server
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct PackageConfig {
@@ -430,9 +521,10 @@ mod test {
     use crate::address::{Ipv6Subnet, RACK_PREFIX};
     use crate::api::internal::shared::SwitchLocation;
     use crate::nexus_config::{
-        BackgroundTaskConfig, ConfigDropshotWithTls, Database,
+        AuditLogConfig, BackgroundTaskConfig, ConfigDropshotWithTls, Database,
         DeploymentConfig, DnsTasksConfig, DpdConfig, ExternalEndpointsConfig,
-        InternalDns, LoadErrorKind,
+        InstanceAutoRestartConfig, InternalDns, LoadErrorKind,
+        SnapshotSchedulesConfig, VpcDnsConfig, WebhookDeliveriesConfig,
     };
     use dropshot::ConfigDropshot;
     use dropshot::ConfigLogging;
@@ -577,6 +669,16 @@ mod test {
             dns_external.period_secs_propagation = 7
             dns_external.max_concurrent_server_updates = 8
             external_endpoints.period_secs = 9
+            audit_log.period_secs = 10
+            audit_log.retention_days = 11
+            instance_auto_restart.period_secs = 12
+            instance_auto_restart.cooldown_secs = 13
+            instance_auto_restart.max_backoff_secs = 14
+            snapshot_schedules.period_secs = 15
+            webhook_deliveries.period_secs = 16
+            webhook_deliveries.retry_backoff_secs = 17
+            webhook_deliveries.max_attempts = 18
+            vpc_dns.period_secs = 19
             "##,
         )
         .unwrap();
@@ -653,7 +755,28 @@ mod test {
                         },
                         external_endpoints: ExternalEndpointsConfig {
                             period_secs: Duration::from_secs(9),
-                        }
+                        },
+                        audit_log: AuditLogConfig {
+                            period_secs: Duration::from_secs(10),
+                            retention_days: 11,
+                        },
+                        instance_auto_restart: InstanceAutoRestartConfig {
+                            period_secs: Duration::from_secs(12),
+                            cooldown_secs: Duration::from_secs(13),
+                            max_backoff_secs: Duration::from_secs(14),
+                        },
+                        snapshot_schedules: SnapshotSchedulesConfig {
+                            period_secs: Duration::from_secs(15),
+                        },
+                        webhook_deliveries: WebhookDeliveriesConfig {
+                            period_secs: Duration::from_secs(16),
+                            retry_backoff_secs: Duration::from_secs(17),
+                            max_attempts: 18,
+                            allow_private_endpoints: false,
+                        },
+                        vpc_dns: VpcDnsConfig {
+                            period_secs: Duration::from_secs(19),
+                        },
                     },
                 },
             }
@@ -701,6 +824,16 @@ mod test {
             dns_external.period_secs_propagation = 7
             dns_external.max_concurrent_server_updates = 8
             external_endpoints.period_secs = 9
+            audit_log.period_secs = 10
+            audit_log.retention_days = 11
+            instance_auto_restart.period_secs = 12
+            instance_auto_restart.cooldown_secs = 13
+            instance_auto_restart.max_backoff_secs = 14
+            snapshot_schedules.period_secs = 15
+            webhook_deliveries.period_secs = 16
+            webhook_deliveries.retry_backoff_secs = 17
+            webhook_deliveries.max_attempts = 18
+            vpc_dns.period_secs = 19
             "##,
         )
         .unwrap();
@@ -366,6 +366,29 @@ CREATE TABLE omicron.public.virtual_provisioning_resource (
     ram_provisioned INT8 NOT NULL
 );
 
+-- Limits on the virtual resources which may be provisioned within a
+-- collection. These are compared against the running totals in
+-- 'virtual_provisioning_collection' whenever a new resource is provisioned.
+--
+-- A missing row, or a NULL limit, means that the collection is unbounded.
+CREATE TABLE omicron.public.virtual_provisioning_quota (
+    -- Should match the UUID of the corresponding collection.
+    id UUID PRIMARY KEY,
+    time_modified TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    -- Identifies the type of the collection.
+    collection_type STRING(63) NOT NULL,
+
+    -- The maximum amount of disk space which may be provisioned.
+    virtual_disk_bytes INT8 CHECK (virtual_disk_bytes >= 0),
+
+    -- The maximum number of CPUs which may be provisioned by VMs.
+    cpus INT8 CHECK (cpus >= 0),
+
+    -- The maximum amount of RAM which may be provisioned by VMs.
+    ram_bytes INT8 CHECK (ram_bytes >= 0)
+);
+
 /*
  * ZPools of Storage, attached to Sleds.
  * These are backed by a single physical disk.
@@ -550,12 +573,14 @@ CREATE INDEX on omicron.public.volume (
 
 CREATE TYPE omicron.public.authentication_mode AS ENUM (
   'local',
-  'saml'
+  'saml',
+  'oidc'
 );
 
 CREATE TYPE omicron.public.user_provision_type AS ENUM (
   'api_only',
-  'jit'
+  'jit',
+  'scim'
 );
 
 CREATE TABLE omicron.public.silo (
@@ -592,7 +617,13 @@ CREATE TABLE omicron.public.silo_user (
     time_deleted TIMESTAMPTZ,
 
     silo_id UUID NOT NULL,
-    external_id TEXT NOT NULL
+    external_id TEXT NOT NULL,
+
+    /*
+     * Users deactivated by a SCIM client remain in the database (so that they
+     * can be reactivated later) but cannot authenticate.
+     */
+    active BOOL NOT NULL
 );
 
 /* This index lets us quickly find users for a given silo. */
@@ -651,12 +682,36 @@ CREATE INDEX ON omicron.public.silo_group_membership (
     silo_group_id
 );
 
+/*
+ * Bearer tokens used by SCIM clients (generally, an external identity
+ * provider) to provision the users and groups of a Silo
+ */
+
+CREATE TABLE omicron.public.scim_client_bearer_token (
+    id UUID PRIMARY KEY,
+    time_created TIMESTAMPTZ NOT NULL,
+    time_expires TIMESTAMPTZ,
+
+    silo_id UUID NOT NULL,
+    bearer_token TEXT NOT NULL
+);
+
+CREATE UNIQUE INDEX ON omicron.public.scim_client_bearer_token (
+    bearer_token
+);
+
+CREATE INDEX ON omicron.public.scim_client_bearer_token (
+    silo_id,
+    id
+);
+
 /*
  * Silo identity provider list
  */
 
 CREATE TYPE omicron.public.provider_type AS ENUM (
-  'saml'
+  'saml',
+  'oidc'
 );
 
 CREATE TABLE omicron.public.identity_provider (
@@ -724,6 +779,48 @@ CREATE UNIQUE INDEX ON omicron.public.saml_identity_provider (
 ) WHERE
     time_deleted IS NULL;
 
+/*
+ * Silo OpenID Connect identity provider
+ */
+CREATE TABLE omicron.public.oidc_identity_provider (
+    /* Identity metadata */
+    id UUID PRIMARY KEY,
+    name STRING(63) NOT NULL,
+    description STRING(512) NOT NULL,
+    time_created TIMESTAMPTZ NOT NULL,
+    time_modified TIMESTAMPTZ NOT NULL,
+    time_deleted TIMESTAMPTZ,
+
+    silo_id UUID NOT NULL,
+
+    /* From the provider's discovery document */
+    issuer TEXT NOT NULL,
+    authorization_endpoint TEXT NOT NULL,
+    token_endpoint TEXT NOT NULL,
+    /* JSON Web Key Set used to verify ID tokens */
+    jwks TEXT NOT NULL,
+
+    client_id TEXT NOT NULL,
+    client_secret TEXT NOT NULL,
+    redirect_uri TEXT NOT NULL,
+    /* Space-separated scopes to request in addition to "openid" */
+    scopes TEXT NOT NULL,
+
+    group_claim_name TEXT
+);
+
+CREATE UNIQUE INDEX ON omicron.public.oidc_identity_provider (
+    silo_id,
+    id
+) WHERE
+    time_deleted IS NULL;
+
+CREATE UNIQUE INDEX ON omicron.public.oidc_identity_provider (
+    silo_id,
+    name
+) WHERE
+    time_deleted IS NULL;
+
 /*
  * Users' public SSH keys, per RFD 44
  */
@@ -751,6 +848,17 @@ CREATE UNIQUE INDEX ON omicron.public.ssh_key (
 ) WHERE
     time_deleted IS NULL;
 
+/*
+ * The SSH public keys chosen for an instance when it was created. These are
+ * the keys injected into the instance via cloud-init.
+ */
+CREATE TABLE omicron.public.instance_ssh_key (
+    instance_id UUID NOT NULL,
+    ssh_key_id UUID NOT NULL,
+
+    PRIMARY KEY (instance_id, ssh_key_id)
+);
+
 /*
  * Projects
  */
@@ -769,7 +877,10 @@ CREATE TABLE omicron.public.project (
     rcgen INT NOT NULL,
 
     /* Which silo this project belongs to */
-    silo_id UUID NOT NULL /* foreign key into "silo" table */
+    silo_id UUID NOT NULL, /* foreign key into "silo" table */
+
+    /* User-defined key/value labels (a JSON object of strings) */
+    labels JSONB NOT NULL
 );
 
 CREATE UNIQUE INDEX ON omicron.public.project (
@@ -795,6 +906,13 @@ CREATE TYPE omicron.public.instance_state AS ENUM (
     'destroyed'
 );
 
+CREATE TYPE omicron.public.instance_auto_restart_policy AS ENUM (
+    /* The instance is never restarted automatically. */
+    'never',
+    /* The instance is restarted, with backoff, if it fails. */
+    'best_effort'
+);
+
 /*
  * TODO consider how we want to manage multiple sagas operating on the same
  * Instance -- e.g., reboot concurrent with destroy or concurrent reboots or the
@@ -858,7 +976,21 @@ CREATE TABLE omicron.public.instance (
     /* Instance configuration */
     ncpus INT NOT NULL,
     memory INT NOT NULL,
-    hostname STRING(63) NOT NULL
+    hostname STRING(63) NOT NULL,
+
+    /* User-defined key/value labels (a JSON object of strings) */
+    labels JSONB NOT NULL,
+
+    /* Whether the instance should be restarted automatically if it fails */
+    auto_restart_policy omicron.public.instance_auto_restart_policy NOT NULL,
+    /*
+     * The number of automatic restarts attempted since the instance last ran
+     * without failing for a while, which determines how long Nexus waits
+     * before trying again
+     */
+    auto_restart_attempts INT8 NOT NULL,
+    /* When Nexus last tried to restart the instance automatically */
+    time_last_auto_restarted TIMESTAMPTZ
 );
 
 -- Names for instances within a project should be unique
@@ -903,6 +1035,58 @@ FROM
 WHERE
     instance.time_deleted IS NULL;
 
+/*
+ * Affinity groups constrain the sleds on which their member instances are
+ * placed, relative to one another.
+ */
+
+CREATE TYPE omicron.public.affinity_group_kind AS ENUM (
+    'affinity',
+    'anti_affinity'
+);
+
+CREATE TYPE omicron.public.affinity_policy AS ENUM (
+    'hard',
+    'soft'
+);
+
+CREATE TABLE omicron.public.affinity_group (
+    /* Identity metadata (resource) */
+    id UUID PRIMARY KEY,
+    name STRING(63) NOT NULL,
+    description STRING(512) NOT NULL,
+    time_created TIMESTAMPTZ NOT NULL,
+    time_modified TIMESTAMPTZ NOT NULL,
+    /* Indicates that the object has been deleted */
+    time_deleted TIMESTAMPTZ,
+
+    /* Every affinity group is in exactly one Project at a time. */
+    project_id UUID NOT NULL,
+
+    /* Whether members are placed together or apart. */
+    kind omicron.public.affinity_group_kind NOT NULL,
+    /* Whether that placement is required or merely preferred. */
+    policy omicron.public.affinity_policy NOT NULL
+);
+
+CREATE UNIQUE INDEX ON omicron.public.affinity_group (
+    project_id,
+    name
+) WHERE
+    time_deleted IS NULL;
+
+CREATE TABLE omicron.public.affinity_group_instance_membership (
+    affinity_group_id UUID NOT NULL,
+    instance_id UUID NOT NULL,
+
+    PRIMARY KEY (affinity_group_id, instance_id)
+);
+
+/* Allow looking up the affinity groups of an instance. */
+CREATE INDEX ON omicron.public.affinity_group_instance_membership (
+    instance_id
+);
+
 
 /*
  * Guest-Visible, Virtual Disks
@@ -977,7 +1161,10 @@ CREATE TABLE omicron.public.disk (
     origin_snapshot UUID,
     origin_image UUID,
 
-    pantry_address TEXT
+    pantry_address TEXT,
+
+    /* User-defined key/value labels (a JSON object of strings) */
+    labels JSONB NOT NULL
 );
 
 CREATE UNIQUE INDEX ON omicron.public.disk (
@@ -1012,7 +1199,10 @@ CREATE TABLE omicron.public.image (
     version STRING(64) NOT NULL,
     digest TEXT,
     block_size omicron.public.block_size NOT NULL,
-    size_bytes INT NOT NULL
+    size_bytes INT NOT NULL,
+
+    /* User-defined key/value labels (a JSON object of strings) */
+    labels JSONB NOT NULL
 );
 
 CREATE VIEW omicron.public.project_image AS
@@ -1031,7 +1221,8 @@ SELECT
     version,
     digest,
     block_size,
-    size_bytes
+    size_bytes,
+    labels
 FROM 
     omicron.public.image
 WHERE 
@@ -1052,7 +1243,8 @@ SELECT
     version,
     digest,
     block_size,
-    size_bytes
+    size_bytes,
+    labels
 FROM 
     omicron.public.image
 WHERE 
@@ -1075,6 +1267,22 @@ CREATE UNIQUE INDEX on omicron.public.image (
     time_deleted is NULL AND
     project_id is NOT NULL;
 
+/*
+ * Grants sharing a Silo image with another Silo.  Projects in that Silo can
+ * create disks from the image, which are backed by the image's own volume.
+ */
+CREATE TABLE omicron.public.image_silo_grant (
+    image_id UUID NOT NULL,
+    silo_id UUID NOT NULL,
+
+    PRIMARY KEY (image_id, silo_id)
+);
+
+/* Allow looking up the images shared with a Silo. */
+CREATE INDEX ON omicron.public.image_silo_grant (
+    silo_id
+);
+
 CREATE TYPE omicron.public.snapshot_state AS ENUM (
   'creating',
   'ready',
@@ -1109,7 +1317,23 @@ CREATE TABLE omicron.public.snapshot (
     block_size omicron.public.block_size NOT NULL,
 
     /* Disk configuration (from the time the snapshot was taken) */
-    size_bytes INT NOT NULL
+    size_bytes INT NOT NULL,
+
+    /* User-defined key/value labels (a JSON object of strings) */
+    labels JSONB NOT NULL,
+
+    /* The Pantry this snapshot is attached to while it is being exported */
+    pantry_address TEXT,
+
+    /* The snapshot schedule that took this snapshot, if any */
+    snapshot_schedule_id UUID,
+
+    /*
+     * Whether Nexus took this snapshot for its own use while copying or
+     * resizing a disk.  Such snapshots are deleted once that's done, aren't
+     * listed, and aren't charged to the project.
+     */
+    transient BOOL NOT NULL
 );
 
 CREATE UNIQUE INDEX ON omicron.public.snapshot (
@@ -1118,6 +1342,65 @@ CREATE UNIQUE INDEX ON omicron.public.snapshot (
 ) WHERE
     time_deleted IS NULL;
 
+/* Allow finding the snapshots of a disk taken by a snapshot schedule. */
+CREATE INDEX ON omicron.public.snapshot (
+    snapshot_schedule_id,
+    disk_id
+) WHERE
+    snapshot_schedule_id IS NOT NULL AND time_deleted IS NULL;
+
+CREATE TYPE omicron.public.snapshot_schedule_run_status AS ENUM (
+    'running',
+    'succeeded',
+    'failed'
+);
+
+CREATE TABLE omicron.public.snapshot_schedule (
+    /* Identity metadata (resource) */
+    id UUID PRIMARY KEY,
+    name STRING(63) NOT NULL,
+    description STRING(512) NOT NULL,
+    time_created TIMESTAMPTZ NOT NULL,
+    time_modified TIMESTAMPTZ NOT NULL,
+    /* Indicates that the object has been deleted */
+    time_deleted TIMESTAMPTZ,
+
+    /* Every snapshot schedule is in exactly one Project at a time. */
+    project_id UUID NOT NULL,
+
+    /*
+     * The disks to snapshot: either a single disk, or every disk in the
+     * project having all of the given labels (a JSON object of strings).
+     */
+    target_disk_id UUID,
+    target_labels JSONB,
+
+    /* How often to snapshot each disk, in seconds */
+    interval_secs INT8 NOT NULL,
+    /* How many of the snapshots of each disk taken by the schedule to keep */
+    retain INT8 NOT NULL,
+
+    /* The most recent run of the schedule, if it has run */
+    time_last_run TIMESTAMPTZ,
+    last_run_status omicron.public.snapshot_schedule_run_status,
+    last_run_snapshots_created INT8 NOT NULL,
+    last_run_snapshots_deleted INT8 NOT NULL,
+    last_run_error TEXT,
+
+    CONSTRAINT exactly_one_target CHECK (
+        (target_disk_id IS NULL) != (target_labels IS NULL)
+    ),
+    CONSTRAINT run_status_if_run CHECK (
+        (time_last_run IS NULL) = (last_run_status IS NULL)
+    )
+);
+
+CREATE UNIQUE INDEX ON omicron.public.snapshot_schedule (
+    project_id,
+    name
+) WHERE
+    time_deleted IS NULL;
+
 /*
  * Oximeter collector servers.
  */
@@ -1183,7 +1466,10 @@ CREATE TABLE omicron.public.vpc (
     firewall_gen INT NOT NULL,
 
     /* Child-resource generation number for VPC Subnets. */
-    subnet_gen INT8 NOT NULL
+    subnet_gen INT8 NOT NULL,
+
+    /* User-defined key/value labels (a JSON object of strings) */
+    labels JSONB NOT NULL
 );
 
 CREATE UNIQUE INDEX ON omicron.public.vpc (
@@ -1467,6 +1753,51 @@ CREATE UNIQUE INDEX ON omicron.public.router_route (
 ) WHERE
     time_deleted IS NULL;
 
+CREATE TYPE omicron.public.vpc_peering_state AS ENUM (
+    'pending',
+    'active'
+);
+
+/*
+ * One VPC's side of a peering with another VPC in the same silo. The peering
+ * is active only once each of the two VPCs has a peering naming the other.
+ */
+CREATE TABLE omicron.public.vpc_peering (
+    /* Identity metadata (resource) */
+    id UUID PRIMARY KEY,
+    name STRING(63) NOT NULL,
+    description STRING(512) NOT NULL,
+    time_created TIMESTAMPTZ NOT NULL,
+    time_modified TIMESTAMPTZ NOT NULL,
+    /* Indicates that the object has been deleted */
+    time_deleted TIMESTAMPTZ,
+
+    /* The VPC to which this side of the peering belongs */
+    vpc_id UUID NOT NULL,
+    /* The VPC being peered with */
+    peer_vpc_id UUID NOT NULL,
+    state omicron.public.vpc_peering_state NOT NULL
+);
+
+CREATE UNIQUE INDEX ON omicron.public.vpc_peering (
+    vpc_id,
+    name
+) WHERE
+    time_deleted IS NULL;
+
+/* A VPC may only create one peering with any other VPC. */
+CREATE UNIQUE INDEX ON omicron.public.vpc_peering (
+    vpc_id,
+    peer_vpc_id
+) WHERE
+    time_deleted IS NULL;
+
+/* Allow looking up the other side of a peering. */
+CREATE INDEX ON omicron.public.vpc_peering (
+    peer_vpc_id
+) WHERE
+    time_deleted IS NULL;
+
 /*
  * An IP Pool, a collection of zero or more IP ranges for external IPs.
  */
@@ -1483,6 +1814,18 @@ CREATE TABLE omicron.public.ip_pool (
     /* Identifies if the IP Pool is dedicated to Control Plane services */
     internal BOOL NOT NULL,
 
+    /*
+     * The Silo whose instances may draw addresses from this pool.  If NULL,
+     * the pool is available to every Silo.
+     */
+    silo_id UUID,
+
+    /*
+     * Whether this is the default pool for its Silo, or for the whole fleet if
+     * it isn't linked to a Silo.
+     */
+    is_default BOOL NOT NULL,
+
     /* The collection's child-resource generation number */
     rcgen INT8 NOT NULL
 );
@@ -1495,6 +1838,15 @@ CREATE UNIQUE INDEX ON omicron.public.ip_pool (
 ) WHERE
     time_deleted IS NULL;
 
+/*
+ * Index ensuring that there is at most one default pool for each Silo, and at
+ * most one default pool for the fleet (among the pools linked to no Silo).
+ */
+CREATE UNIQUE INDEX ip_pool_default_per_silo ON omicron.public.ip_pool (
+    COALESCE(silo_id, '00000000-0000-0000-0000-000000000000'::uuid)
+) WHERE
+    is_default = true AND time_deleted IS NULL;
+
 /*
  * IP Pools are made up of a set of IP ranges, which are start/stop addresses.
  * Note that these need not be CIDR blocks or well-behaved subnets with a
@@ -1594,6 +1946,12 @@ CREATE TABLE omicron.public.external_ip (
     /* The last port in the allowed range, also inclusive. */
     last_port INT4 NOT NULL,
 
+    /*
+     * FK to the `project` table, for floating IPs which are API resources
+     * owned by a project.
+     */
+    project_id UUID,
+
     /* The name must be non-NULL iff this is a floating IP. */
     CONSTRAINT null_fip_name CHECK (
         (kind != 'floating' AND name IS NULL) OR
@@ -1617,6 +1975,11 @@ CREATE TABLE omicron.public.external_ip (
     /* Ephemeral IPs are not supported for services. */
     CONSTRAINT ephemeral_kind_service CHECK (
         (kind = 'ephemeral' AND is_service = FALSE) OR (kind != 'ephemeral')
+    ),
+
+    /* Only floating IPs for instances may belong to a project. */
+    CONSTRAINT null_non_fip_project_id CHECK (
+        (kind = 'floating' AND is_service = FALSE) OR (project_id IS NULL)
     )
 );
 
@@ -1649,6 +2012,36 @@ CREATE UNIQUE INDEX ON omicron.public.external_ip (
 )
     WHERE parent_id IS NOT NULL AND time_deleted IS NULL;
 
+/* Index for looking up floating IPs by name within a project. */
+CREATE UNIQUE INDEX ON omicron.public.external_ip (
+    project_id,
+    name
+)
+    WHERE project_id IS NOT NULL AND time_deleted IS NULL;
+
+/*
+ * Floating IPs which are API resources, i.e., those which belong to a project
+ * rather than a service.
+ */
+CREATE VIEW omicron.public.floating_ip AS
+SELECT
+    id,
+    name,
+    description,
+    time_created,
+    time_modified,
+    time_deleted,
+    ip_pool_id,
+    ip_pool_range_id,
+    parent_id,
+    ip,
+    project_id
+FROM
+    omicron.public.external_ip
+WHERE
+    omicron.public.external_ip.kind = 'floating' AND
+    project_id IS NOT NULL;
+
 /*******************************************************************/
 
 /*
@@ -2112,17 +2505,34 @@ CREATE TABLE omicron.public.device_auth_request (
     time_expires TIMESTAMPTZ NOT NULL
 );
 
--- Access tokens granted in response to successful device authorization flows.
+-- Access tokens granted in response to successful device authorization flows
+-- or created directly by users through the API.
 CREATE TABLE omicron.public.device_access_token (
     token STRING(40) PRIMARY KEY,
-    client_id UUID NOT NULL,
-    device_code STRING(40) NOT NULL,
+    /* These are only set for tokens granted by a device authorization flow. */
+    client_id UUID,
+    device_code STRING(40),
     silo_user_id UUID NOT NULL,
     time_requested TIMESTAMPTZ NOT NULL,
     time_created TIMESTAMPTZ NOT NULL,
-    time_expires TIMESTAMPTZ
+    time_expires TIMESTAMPTZ,
+    /* Identifies the token in the API without revealing it. */
+    id UUID NOT NULL,
+    /* This is only set for tokens created through the API. */
+    name STRING(63),
+    time_last_used TIMESTAMPTZ
 );
 
+-- Tokens are listed and deleted by id.
+CREATE UNIQUE INDEX ON omicron.public.device_access_token (
+    id
+);
+
+-- Users can't give two of their tokens the same name.
+CREATE UNIQUE INDEX ON omicron.public.device_access_token (
+    silo_user_id, name
+) WHERE name IS NOT NULL;
+
 -- This UNIQUE constraint is critical for ensuring that at most
 -- one token is ever created for a given device authorization flow.
 CREATE UNIQUE INDEX ON omicron.public.device_access_token (
@@ -2509,6 +2919,140 @@ CREATE TABLE omicron.public.switch_port_settings_address_config (
     PRIMARY KEY (port_settings_id, address, interface_name)
 );
 
+/*
+ * Audit log
+ *
+ * Each entry describes one authenticated request to the external API that may
+ * have modified the system (i.e., any request other than a GET).  Entries are
+ * written once Nexus has finished handling the request and are pruned by a
+ * background task after the configured retention period.
+ */
+CREATE TABLE omicron.public.audit_log_entry (
+    id UUID PRIMARY KEY,
+    /* when Nexus started and finished handling the request */
+    time_started TIMESTAMPTZ NOT NULL,
+    time_completed TIMESTAMPTZ NOT NULL,
+    /* request id assigned by the API server */
+    request_id STRING(63) NOT NULL,
+    /* the authenticated user and its Silo (NULL for built-in users) */
+    actor_id UUID NOT NULL,
+    actor_silo_id UUID,
+    /* the API operation invoked and the resource it was invoked on */
+    operation_id STRING(63) NOT NULL,
+    http_method STRING(15) NOT NULL,
+    resource_path TEXT NOT NULL,
+    /* HTTP status code of the response */
+    result_status INT4 NOT NULL
+);
+
+/* Used for listing the audit log in order and for pruning old entries. */
+CREATE INDEX ON omicron.public.audit_log_entry (
+    time_completed,
+    id
+);
+
+/* Used for listing the audit log for a particular user. */
+CREATE INDEX ON omicron.public.audit_log_entry (
+    actor_id,
+    time_completed,
+    id
+);
+
+/*
+ * Webhooks
+ *
+ * A Silo's webhook receivers are sent the events of the classes they
+ * subscribe to.  Each event is recorded once, along with a delivery to each
+ * receiver subscribed to it when it happened.  Deliveries are made (and
+ * retried, with backoff) by a background task.
+ */
+CREATE TYPE omicron.public.webhook_event_class AS ENUM (
+    'instance_state_change',
+    'disk_attach',
+    'disk_detach',
+    'snapshot_complete',
+    'update_deployment_progress'
+);
+
+CREATE TABLE omicron.public.webhook_receiver (
+    /* Identity metadata (resource) */
+    id UUID PRIMARY KEY,
+    name STRING(63) NOT NULL,
+    description STRING(512) NOT NULL,
+    time_created TIMESTAMPTZ NOT NULL,
+    time_modified TIMESTAMPTZ NOT NULL,
+    /* Indicates that the object has been deleted */
+    time_deleted TIMESTAMPTZ,
+
+    silo_id UUID NOT NULL,
+
+    /* The URL to which events are delivered */
+    endpoint STRING(512) NOT NULL,
+    /* The secret with which each delivery is signed */
+    secret STRING(512) NOT NULL,
+    /* The classes of event the receiver is sent */
+    event_classes omicron.public.webhook_event_class[] NOT NULL
+);
+
+CREATE UNIQUE INDEX ON omicron.public.webhook_receiver (
+    silo_id,
+    name
+) WHERE
+    time_deleted IS NULL;
+
+CREATE TABLE omicron.public.webhook_event (
+    id UUID PRIMARY KEY,
+    time_created TIMESTAMPTZ NOT NULL,
+    /*
+     * The Silo whose resources the event concerns, or NULL for events that
+     * concern the whole system (which are sent to receivers in every Silo)
+     */
+    silo_id UUID,
+    event_class omicron.public.webhook_event_class NOT NULL,
+    /* Details of the event, which depend on its class */
+    data JSONB NOT NULL
+);
+
+CREATE TYPE omicron.public.webhook_delivery_state AS ENUM (
+    'pending',
+    'delivered',
+    'failed'
+);
+
+CREATE TABLE omicron.public.webhook_delivery (
+    id UUID PRIMARY KEY,
+    event_id UUID NOT NULL,
+    receiver_id UUID NOT NULL,
+    time_created TIMESTAMPTZ NOT NULL,
+    state omicron.public.webhook_delivery_state NOT NULL,
+
+    /* How many attempts have been made to deliver the event */
+    attempts INT8 NOT NULL,
+    /* When the next attempt is due, if the delivery is pending */
+    time_next_attempt TIMESTAMPTZ,
+
+    /* The outcome of the most recent attempt, if there has been one */
+    time_last_attempt TIMESTAMPTZ,
+    last_response_status INT4,
+    last_error TEXT,
+
+    CONSTRAINT next_attempt_iff_pending CHECK (
+        (state = 'pending') = (time_next_attempt IS NOT NULL)
+    )
+);
+
+/* Used for listing the deliveries to a receiver. */
+CREATE INDEX ON omicron.public.webhook_delivery (
+    receiver_id,
+    id
+);
+
+/* Used for finding the deliveries that are due to be attempted. */
+CREATE INDEX ON omicron.public.webhook_delivery (
+    time_next_attempt
+) WHERE
+    state = 'pending';
+
 
 /*******************************************************************/
 
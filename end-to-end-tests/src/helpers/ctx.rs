@@ -43,6 +43,7 @@ impl Context {
             .body(ProjectCreate {
                 name: generate_name("proj")?,
                 description: String::new(),
+                labels: Default::default(),
             })
             .send()
             .await?
@@ -6,8 +6,8 @@ use async_trait::async_trait;
 use omicron_test_utils::dev::poll::{wait_for_condition, CondCheckError};
 use oxide_client::types::{
     ByteCount, DiskCreate, DiskSource, ExternalIpCreate, ImageCreate,
-    ImageSource, InstanceCpuCount, InstanceCreate, InstanceDiskAttachment,
-    InstanceNetworkInterfaceAttachment, SshKeyCreate,
+    ImageSource, InstanceAutoRestartPolicy, InstanceCpuCount, InstanceCreate,
+    InstanceDiskAttachment, InstanceNetworkInterfaceAttachment, SshKeyCreate,
 };
 use oxide_client::{
     ClientDisksExt, ClientImagesExt, ClientInstancesExt, ClientSessionExt,
@@ -53,6 +53,7 @@ async fn instance_launch() -> Result<()> {
                         .into(),
                 block_size: 512.try_into().map_err(anyhow::Error::msg)?,
             },
+            labels: Default::default(),
         })
         .send()
         .await?
@@ -69,6 +70,7 @@ async fn instance_launch() -> Result<()> {
             description: String::new(),
             disk_source: DiskSource::Image { image_id },
             size: ByteCount(2048 * 1024 * 1024),
+            labels: Default::default(),
         })
         .send()
         .await?
@@ -92,7 +94,11 @@ async fn instance_launch() -> Result<()> {
             network_interfaces: InstanceNetworkInterfaceAttachment::Default,
             external_ips: vec![ExternalIpCreate::Ephemeral { pool_name: None }],
             user_data: String::new(),
+            affinity_groups: vec![],
+            ssh_public_keys: None,
             start: true,
+            labels: Default::default(),
+            auto_restart_policy: InstanceAutoRestartPolicy::Never,
         })
         .send()
         .await?;
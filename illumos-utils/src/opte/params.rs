@@ -30,6 +30,33 @@ pub struct VpcFirewallRule {
     pub priority: external::VpcFirewallRulePriority,
 }
 
+/// Update the DNS zone served to the instances in a VPC
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct VpcDnsEnsureBody {
+    pub vni: external::Vni,
+    pub zone: VpcDnsZone,
+}
+
+/// The DNS zone holding the names of the instances in a VPC
+///
+/// This is only served to the instances in the VPC itself, at their subnet's
+/// gateway address.
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct VpcDnsZone {
+    /// The fully-qualified name of the zone
+    pub name: String,
+    pub records: Vec<VpcDnsRecord>,
+}
+
+/// The address of an instance within a VPC, by the instance's name
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct VpcDnsRecord {
+    /// The name of the instance, relative to the zone
+    pub name: String,
+    /// The address of the instance's primary network interface
+    pub ip: IpAddr,
+}
+
 /// A mapping from a virtual NIC to a physical host
 #[derive(Clone, Debug, Serialize, Deserialize, JsonSchema, PartialEq)]
 pub struct SetVirtualNetworkInterfaceHost {
@@ -7,6 +7,7 @@
 use crate::opte::default_boundary_services;
 use crate::opte::opte_firewall_rules;
 use crate::opte::params::SetVirtualNetworkInterfaceHost;
+use crate::opte::params::VpcDnsZone;
 use crate::opte::params::VpcFirewallRule;
 use crate::opte::Error;
 use crate::opte::Gateway;
@@ -437,6 +438,41 @@ impl PortManager {
         Ok(())
     }
 
+    #[cfg(target_os = "illumos")]
+    pub fn vpc_dns_ensure(
+        &self,
+        vni: external::Vni,
+        zone: &VpcDnsZone,
+    ) -> Result<(), Error> {
+        info!(
+            self.inner.log,
+            "Ensuring VPC DNS zone";
+            "vni" => ?vni,
+            "zone" => &zone.name,
+            "records" => zone.records.len(),
+        );
+        // TODO-completeness: OPTE doesn't yet answer DNS queries sent to the
+        // virtual gateway, so there's nowhere to install the zone.
+        slog::warn!(self.inner.log, "vpc_dns_ensure unimplemented");
+        Ok(())
+    }
+
+    #[cfg(not(target_os = "illumos"))]
+    pub fn vpc_dns_ensure(
+        &self,
+        vni: external::Vni,
+        zone: &VpcDnsZone,
+    ) -> Result<(), Error> {
+        info!(
+            self.inner.log,
+            "Ensuring VPC DNS zone (ignored)";
+            "vni" => ?vni,
+            "zone" => &zone.name,
+            "records" => zone.records.len(),
+        );
+        Ok(())
+    }
+
     #[cfg(target_os = "illumos")]
     pub fn set_virtual_nic_host(
         &self,
@@ -51,6 +51,7 @@ impl From<types::DiskState> for omicron_common::api::external::DiskState {
                 Self::ImportingFromBulkWrites
             }
             types::DiskState::Finalizing => Self::Finalizing,
+            types::DiskState::Exporting => Self::Exporting,
             types::DiskState::Maintenance => Self::Maintenance,
             types::DiskState::Attaching(u) => Self::Attaching(u),
             types::DiskState::Attached(u) => Self::Attached(u),
@@ -159,6 +160,7 @@ impl From<omicron_common::api::external::DiskState> for types::DiskState {
             DiskState::ImportingFromUrl => Self::ImportingFromUrl,
             DiskState::ImportingFromBulkWrites => Self::ImportingFromBulkWrites,
             DiskState::Finalizing => Self::Finalizing,
+            DiskState::Exporting => Self::Exporting,
             DiskState::Maintenance => Self::Maintenance,
             DiskState::Attaching(u) => Self::Attaching(u),
             DiskState::Attached(u) => Self::Attached(u),
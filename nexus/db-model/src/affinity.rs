@@ -0,0 +1,132 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Database representation of affinity groups
+
+use crate::impl_enum_type;
+use crate::schema::{affinity_group, affinity_group_instance_membership};
+use db_macros::Resource;
+use nexus_types::external_api::params;
+use nexus_types::external_api::shared;
+use nexus_types::external_api::views;
+use nexus_types::identity::Resource;
+use uuid::Uuid;
+
+impl_enum_type!(
+    #[derive(SqlType, Debug, QueryId)]
+    #[diesel(postgres_type(name = "affinity_group_kind"))]
+    pub struct AffinityGroupKindEnum;
+
+    #[derive(Copy, Clone, Debug, AsExpression, FromSqlRow, PartialEq, Eq)]
+    #[diesel(sql_type = AffinityGroupKindEnum)]
+    pub enum AffinityGroupKind;
+
+    // Enum values
+    Affinity => b"affinity"
+    AntiAffinity => b"anti_affinity"
+);
+
+impl From<shared::AffinityGroupKind> for AffinityGroupKind {
+    fn from(params: shared::AffinityGroupKind) -> Self {
+        match params {
+            shared::AffinityGroupKind::Affinity => AffinityGroupKind::Affinity,
+            shared::AffinityGroupKind::AntiAffinity => {
+                AffinityGroupKind::AntiAffinity
+            }
+        }
+    }
+}
+
+impl From<AffinityGroupKind> for shared::AffinityGroupKind {
+    fn from(model: AffinityGroupKind) -> Self {
+        match model {
+            AffinityGroupKind::Affinity => Self::Affinity,
+            AffinityGroupKind::AntiAffinity => Self::AntiAffinity,
+        }
+    }
+}
+
+impl_enum_type!(
+    #[derive(SqlType, Debug, QueryId)]
+    #[diesel(postgres_type(name = "affinity_policy"))]
+    pub struct AffinityPolicyEnum;
+
+    #[derive(Copy, Clone, Debug, AsExpression, FromSqlRow, PartialEq, Eq)]
+    #[diesel(sql_type = AffinityPolicyEnum)]
+    pub enum AffinityPolicy;
+
+    // Enum values
+    Hard => b"hard"
+    Soft => b"soft"
+);
+
+impl From<shared::AffinityPolicy> for AffinityPolicy {
+    fn from(params: shared::AffinityPolicy) -> Self {
+        match params {
+            shared::AffinityPolicy::Hard => AffinityPolicy::Hard,
+            shared::AffinityPolicy::Soft => AffinityPolicy::Soft,
+        }
+    }
+}
+
+impl From<AffinityPolicy> for shared::AffinityPolicy {
+    fn from(model: AffinityPolicy) -> Self {
+        match model {
+            AffinityPolicy::Hard => Self::Hard,
+            AffinityPolicy::Soft => Self::Soft,
+        }
+    }
+}
+
+/// A group of instances whose placement on sleds is constrained relative to
+/// one another.
+#[derive(Queryable, Insertable, Selectable, Clone, Debug, Resource)]
+#[diesel(table_name = affinity_group)]
+pub struct AffinityGroup {
+    #[diesel(embed)]
+    pub identity: AffinityGroupIdentity,
+
+    pub project_id: Uuid,
+    pub kind: AffinityGroupKind,
+    pub policy: AffinityPolicy,
+}
+
+impl AffinityGroup {
+    pub fn new(project_id: Uuid, params: params::AffinityGroupCreate) -> Self {
+        Self {
+            identity: AffinityGroupIdentity::new(
+                Uuid::new_v4(),
+                params.identity,
+            ),
+            project_id,
+            kind: params.kind.into(),
+            policy: params.policy.into(),
+        }
+    }
+}
+
+impl From<AffinityGroup> for views::AffinityGroup {
+    fn from(group: AffinityGroup) -> Self {
+        Self {
+            identity: group.identity(),
+            project_id: group.project_id,
+            kind: group.kind.into(),
+            policy: group.policy.into(),
+        }
+    }
+}
+
+/// Records that an instance is a member of an affinity group.
+#[derive(Queryable, Insertable, Selectable, Clone, Copy, Debug)]
+#[diesel(table_name = affinity_group_instance_membership)]
+pub struct AffinityGroupInstanceMembership {
+    pub affinity_group_id: Uuid,
+    pub instance_id: Uuid,
+}
+
+impl AffinityGroupInstanceMembership {
+    pub fn new(affinity_group_id: Uuid, instance_id: Uuid) -> Self {
+        Self { affinity_group_id, instance_id }
+    }
+}
@@ -0,0 +1,42 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use crate::schema::audit_log_entry;
+use crate::SqlU16;
+use chrono::{DateTime, Utc};
+use nexus_types::external_api::views;
+use uuid::Uuid;
+
+/// Describes one authenticated, mutating request to the external API
+#[derive(Queryable, Insertable, Clone, Debug, Selectable)]
+#[diesel(table_name = audit_log_entry)]
+pub struct AuditLogEntry {
+    pub id: Uuid,
+    pub time_started: DateTime<Utc>,
+    pub time_completed: DateTime<Utc>,
+    pub request_id: String,
+    pub actor_id: Uuid,
+    pub actor_silo_id: Option<Uuid>,
+    pub operation_id: String,
+    pub http_method: String,
+    pub resource_path: String,
+    pub result_status: SqlU16,
+}
+
+impl From<AuditLogEntry> for views::AuditLogEntry {
+    fn from(entry: AuditLogEntry) -> Self {
+        Self {
+            id: entry.id,
+            time_started: entry.time_started,
+            time_completed: entry.time_completed,
+            request_id: entry.request_id,
+            actor_id: entry.actor_id,
+            actor_silo_id: entry.actor_silo_id,
+            operation_id: entry.operation_id,
+            http_method: entry.http_method,
+            resource_path: entry.resource_path,
+            result_status: *entry.result_status,
+        }
+    }
+}
@@ -6,6 +6,7 @@ use crate::schema::{bgp_announce_set, bgp_announcement, bgp_config};
 use crate::SqlU32;
 use db_macros::Resource;
 use ipnetwork::IpNetwork;
+use nexus_types::external_api::params;
 use nexus_types::identity::Resource;
 use omicron_common::api::external;
 use serde::{Deserialize, Serialize};
@@ -29,6 +30,19 @@ pub struct BgpConfig {
     pub vrf: Option<String>,
 }
 
+impl BgpConfig {
+    pub fn new(c: &params::CreateBgpConfig) -> Self {
+        Self {
+            identity: BgpConfigIdentity::new(
+                Uuid::new_v4(),
+                c.identity.clone(),
+            ),
+            asn: c.asn.into(),
+            vrf: c.vrf.as_ref().map(|v| v.to_string()),
+        }
+    }
+}
+
 impl Into<external::BgpConfig> for BgpConfig {
     fn into(self) -> external::BgpConfig {
         external::BgpConfig {
@@ -55,6 +69,14 @@ pub struct BgpAnnounceSet {
     pub identity: BgpAnnounceSetIdentity,
 }
 
+impl BgpAnnounceSet {
+    pub fn new(id: &external::IdentityMetadataCreateParams) -> Self {
+        Self {
+            identity: BgpAnnounceSetIdentity::new(Uuid::new_v4(), id.clone()),
+        }
+    }
+}
+
 impl Into<external::BgpAnnounceSet> for BgpAnnounceSet {
     fn into(self) -> external::BgpAnnounceSet {
         external::BgpAnnounceSet { identity: self.identity() }
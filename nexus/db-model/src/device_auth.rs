@@ -8,6 +8,7 @@
 //! used.
 
 use crate::schema::{device_access_token, device_auth_request};
+use crate::Name;
 
 use chrono::{DateTime, Duration, Utc};
 use nexus_types::external_api::views;
@@ -58,7 +59,7 @@ const TOKEN_LENGTH: usize = 20;
 /// Generate a random token/device code.
 // TODO: this should be merged with session::generate_session_token,
 // and probably also the key generation in the disk creation saga.
-fn generate_token() -> String {
+pub(crate) fn generate_token() -> String {
     let mut bytes: [u8; TOKEN_LENGTH] = [0; TOKEN_LENGTH];
     let mut rng = StdRng::from_entropy();
     rng.fill_bytes(&mut bytes);
@@ -112,18 +113,22 @@ impl DeviceAuthRequest {
     }
 }
 
-/// An access token granted in response to a successful device authorization flow.
+/// An access token granted in response to a successful device authorization
+/// flow, or created by a user through the API.
 // TODO-security: wrap token in an opaque struct to avoid accidental leaks.
 #[derive(Clone, Debug, Insertable, Queryable, Selectable)]
 #[diesel(table_name = device_access_token)]
 pub struct DeviceAccessToken {
     pub token: String,
-    pub client_id: Uuid,
-    pub device_code: String,
+    pub client_id: Option<Uuid>,
+    pub device_code: Option<String>,
     pub silo_user_id: Uuid,
     pub time_requested: DateTime<Utc>,
     pub time_created: DateTime<Utc>,
     pub time_expires: Option<DateTime<Utc>>,
+    pub id: Uuid,
+    pub name: Option<Name>,
+    pub time_last_used: Option<DateTime<Utc>>,
 }
 
 impl DeviceAccessToken {
@@ -137,12 +142,33 @@ impl DeviceAccessToken {
         assert!(time_requested <= now);
         Self {
             token: generate_token(),
-            client_id,
-            device_code,
+            client_id: Some(client_id),
+            device_code: Some(device_code),
             silo_user_id,
             time_requested,
             time_created: now,
             time_expires: None,
+            id: Uuid::new_v4(),
+            name: None,
+            time_last_used: None,
+        }
+    }
+
+    /// Creates a token requested directly by a user through the API (rather
+    /// than through a device authorization flow)
+    pub fn new_named(silo_user_id: Uuid, name: Name) -> Self {
+        let now = Utc::now();
+        Self {
+            token: generate_token(),
+            client_id: None,
+            device_code: None,
+            silo_user_id,
+            time_requested: now,
+            time_created: now,
+            time_expires: None,
+            id: Uuid::new_v4(),
+            name: Some(name),
+            time_last_used: None,
         }
     }
 
@@ -150,12 +176,38 @@ impl DeviceAccessToken {
         self.token.clone()
     }
 
+    /// Returns whether the token can no longer be used as of `now`
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.time_expires.map_or(false, |time_expires| time_expires <= now)
+    }
+
     pub fn expires(mut self, time: DateTime<Utc>) -> Self {
         self.time_expires = Some(time);
         self
     }
 }
 
+impl From<DeviceAccessToken> for views::DeviceAccessToken {
+    fn from(access_token: DeviceAccessToken) -> Self {
+        Self {
+            id: access_token.id,
+            name: access_token.name.map(|name| name.into()),
+            time_created: access_token.time_created,
+            time_expires: access_token.time_expires,
+            time_last_used: access_token.time_last_used,
+        }
+    }
+}
+
+impl From<DeviceAccessToken> for views::DeviceAccessTokenCreated {
+    fn from(access_token: DeviceAccessToken) -> Self {
+        Self {
+            access_token: format!("oxide-token-{}", access_token.token),
+            token: access_token.into(),
+        }
+    }
+}
+
 impl From<DeviceAccessToken> for views::DeviceAccessTokenGrant {
     fn from(access_token: DeviceAccessToken) -> Self {
         Self {
@@ -2,7 +2,7 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-use super::{BlockSize, ByteCount, DiskState, Generation};
+use super::{BlockSize, ByteCount, DiskState, Generation, Labels};
 use crate::{schema::disk, unsigned::SqlU8};
 use chrono::{DateTime, Utc};
 use db_macros::Resource;
@@ -75,6 +75,9 @@ pub struct Disk {
     /// saga, then this field will contain the serialized SocketAddrV6 of that
     /// Pantry.
     pub pantry_address: Option<String>,
+
+    /// user-defined labels
+    pub labels: Labels,
 }
 
 impl Disk {
@@ -111,6 +114,7 @@ impl Disk {
             create_snapshot_id,
             create_image_id,
             pantry_address: None,
+            labels: params.labels.into(),
         })
     }
 
@@ -144,6 +148,7 @@ impl Into<external::Disk> for Disk {
             block_size: self.block_size.into(),
             state: self.state().into(),
             device_path,
+            labels: self.labels.0,
         }
     }
 }
@@ -253,6 +258,15 @@ impl DiskRuntimeState {
         }
     }
 
+    pub fn exporting(self) -> Self {
+        Self {
+            disk_state: external::DiskState::Exporting.label().to_string(),
+            attach_instance_id: None,
+            gen: self.gen.next().into(),
+            time_updated: Utc::now(),
+        }
+    }
+
     pub fn state(&self) -> DiskState {
         // TODO: If we could store disk state in-line, we could avoid the
         // unwrap. Would prefer to parse it as such.
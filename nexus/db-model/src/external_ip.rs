@@ -7,15 +7,18 @@
 
 use crate::impl_enum_type;
 use crate::schema::external_ip;
+use crate::schema::floating_ip;
 use crate::Name;
 use crate::SqlU16;
 use chrono::DateTime;
 use chrono::Utc;
+use db_macros::Resource;
 use diesel::Queryable;
 use diesel::Selectable;
 use ipnetwork::IpNetwork;
 use nexus_types::external_api::shared;
 use nexus_types::external_api::views;
+use nexus_types::identity::Resource;
 use omicron_common::address::NUM_SOURCE_NAT_PORTS;
 use omicron_common::api::external::Error;
 use std::convert::TryFrom;
@@ -69,6 +72,71 @@ pub struct ExternalIp {
     pub ip: IpNetwork,
     pub first_port: SqlU16,
     pub last_port: SqlU16,
+    // Only Some(_) for Floating IPs which belong to a project
+    pub project_id: Option<Uuid>,
+}
+
+/// A Floating IP, viewed as an API resource owned by a project.
+///
+/// This is backed by a view of the `external_ip` table, selecting only those
+/// Floating IPs which belong to a project (i.e., not service IPs).
+#[derive(Debug, Clone, Selectable, Queryable, Resource)]
+#[diesel(table_name = floating_ip)]
+pub struct FloatingIp {
+    #[diesel(embed)]
+    pub identity: FloatingIpIdentity,
+
+    pub ip_pool_id: Uuid,
+    pub ip_pool_range_id: Uuid,
+    // The instance to which this Floating IP is attached, if any.
+    pub parent_id: Option<Uuid>,
+    pub ip: IpNetwork,
+    pub project_id: Uuid,
+}
+
+impl TryFrom<ExternalIp> for FloatingIp {
+    type Error = Error;
+
+    fn try_from(ip: ExternalIp) -> Result<Self, Self::Error> {
+        if ip.kind != IpKind::Floating || ip.is_service {
+            return Err(Error::internal_error(
+                "only instance Floating IPs may be viewed as API resources",
+            ));
+        }
+        let (Some(name), Some(description), Some(project_id)) =
+            (ip.name, ip.description, ip.project_id)
+        else {
+            return Err(Error::internal_error(
+                "Floating IP is missing its name, description, or project",
+            ));
+        };
+        Ok(FloatingIp {
+            identity: FloatingIpIdentity {
+                id: ip.id,
+                name,
+                description,
+                time_created: ip.time_created,
+                time_modified: ip.time_modified,
+                time_deleted: ip.time_deleted,
+            },
+            ip_pool_id: ip.ip_pool_id,
+            ip_pool_range_id: ip.ip_pool_range_id,
+            parent_id: ip.parent_id,
+            ip: ip.ip,
+            project_id,
+        })
+    }
+}
+
+impl From<FloatingIp> for views::FloatingIp {
+    fn from(ip: FloatingIp) -> Self {
+        views::FloatingIp {
+            identity: ip.identity(),
+            ip: ip.ip.ip(),
+            project_id: ip.project_id,
+            instance_id: ip.parent_id,
+        }
+    }
 }
 
 impl From<ExternalIp> for sled_agent_client::types::SourceNatConfig {
@@ -93,6 +161,7 @@ pub struct IncompleteExternalIp {
     is_service: bool,
     parent_id: Option<Uuid>,
     pool_id: Uuid,
+    project_id: Option<Uuid>,
     // Optional address requesting that a specific IP address be allocated.
     explicit_ip: Option<IpNetwork>,
     // Optional range when requesting a specific SNAT range be allocated.
@@ -114,6 +183,7 @@ impl IncompleteExternalIp {
             is_service: false,
             parent_id: Some(instance_id),
             pool_id,
+            project_id: None,
             explicit_ip: None,
             explicit_port_range: None,
         }
@@ -129,6 +199,7 @@ impl IncompleteExternalIp {
             is_service: false,
             parent_id: Some(instance_id),
             pool_id,
+            project_id: None,
             explicit_ip: None,
             explicit_port_range: None,
         }
@@ -138,7 +209,9 @@ impl IncompleteExternalIp {
         id: Uuid,
         name: &Name,
         description: &str,
+        project_id: Uuid,
         pool_id: Uuid,
+        explicit_ip: Option<IpAddr>,
     ) -> Self {
         Self {
             id,
@@ -149,7 +222,8 @@ impl IncompleteExternalIp {
             is_service: false,
             parent_id: None,
             pool_id,
-            explicit_ip: None,
+            project_id: Some(project_id),
+            explicit_ip: explicit_ip.map(IpNetwork::from),
             explicit_port_range: None,
         }
     }
@@ -171,6 +245,7 @@ impl IncompleteExternalIp {
             is_service: true,
             parent_id: Some(service_id),
             pool_id,
+            project_id: None,
             explicit_ip: Some(IpNetwork::from(address)),
             explicit_port_range: None,
         }
@@ -199,6 +274,7 @@ impl IncompleteExternalIp {
             is_service: true,
             parent_id: Some(service_id),
             pool_id,
+            project_id: None,
             explicit_ip: Some(IpNetwork::from(address)),
             explicit_port_range,
         }
@@ -220,6 +296,7 @@ impl IncompleteExternalIp {
             is_service: true,
             parent_id: Some(service_id),
             pool_id,
+            project_id: None,
             explicit_ip: None,
             explicit_port_range: None,
         }
@@ -235,6 +312,7 @@ impl IncompleteExternalIp {
             is_service: true,
             parent_id: Some(service_id),
             pool_id,
+            project_id: None,
             explicit_ip: None,
             explicit_port_range: None,
         }
@@ -272,6 +350,10 @@ impl IncompleteExternalIp {
         &self.pool_id
     }
 
+    pub fn project_id(&self) -> &Option<Uuid> {
+        &self.project_id
+    }
+
     pub fn explicit_ip(&self) -> &Option<IpNetwork> {
         &self.explicit_ip
     }
@@ -3,7 +3,9 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
 use crate::impl_enum_type;
-use crate::schema::{identity_provider, saml_identity_provider};
+use crate::schema::{
+    identity_provider, oidc_identity_provider, saml_identity_provider,
+};
 use db_macros::Resource;
 use nexus_types::identity::Resource;
 
@@ -22,12 +24,14 @@ impl_enum_type!(
 
     // Enum values
     Saml => b"saml"
+    Oidc => b"oidc"
 );
 
 impl From<IdentityProviderType> for views::IdentityProviderType {
     fn from(idp_type: IdentityProviderType) -> Self {
         match idp_type {
             IdentityProviderType::Saml => views::IdentityProviderType::Saml,
+            IdentityProviderType::Oidc => views::IdentityProviderType::Oidc,
         }
     }
 }
@@ -101,3 +105,59 @@ impl From<SamlIdentityProvider> for views::SamlIdentityProvider {
         }
     }
 }
+
+#[derive(Queryable, Insertable, Clone, Debug, Selectable, Resource)]
+#[diesel(table_name = oidc_identity_provider)]
+pub struct OidcIdentityProvider {
+    #[diesel(embed)]
+    pub identity: OidcIdentityProviderIdentity,
+
+    pub silo_id: Uuid,
+
+    /// idp's issuer identifier, which must match the "iss" claim of ID tokens
+    pub issuer: String,
+
+    /// idp endpoint where users are sent to log in
+    pub authorization_endpoint: String,
+
+    /// idp endpoint where authorization codes are exchanged for ID tokens
+    pub token_endpoint: String,
+
+    /// JSON Web Key Set used to verify the signatures of ID tokens
+    pub jwks: String,
+
+    /// client credentials assigned to us by the idp
+    pub client_id: String,
+    pub client_secret: String,
+
+    /// our endpoint where the idp sends users after they log in
+    pub redirect_uri: String,
+
+    /// space-separated scopes to request in addition to "openid"
+    pub scopes: String,
+
+    /// if set, claims with this name will be considered to denote a user's
+    /// group membership, where the values will be the group names.
+    pub group_claim_name: Option<String>,
+}
+
+impl OidcIdentityProvider {
+    pub fn scopes(&self) -> Vec<String> {
+        self.scopes.split_whitespace().map(String::from).collect()
+    }
+}
+
+impl From<OidcIdentityProvider> for views::OidcIdentityProvider {
+    fn from(oidc_idp: OidcIdentityProvider) -> Self {
+        Self {
+            identity: oidc_idp.identity(),
+            scopes: oidc_idp.scopes(),
+            issuer: oidc_idp.issuer,
+            authorization_endpoint: oidc_idp.authorization_endpoint,
+            token_endpoint: oidc_idp.token_endpoint,
+            client_id: oidc_idp.client_id,
+            redirect_uri: oidc_idp.redirect_uri,
+            group_claim_name: oidc_idp.group_claim_name,
+        }
+    }
+}
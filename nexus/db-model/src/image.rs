@@ -8,8 +8,8 @@
 //! silo_id and a project_id, while SiloImage only has a silo_id. Image has a
 //! silo_id and an optional project_id to cover both possibilities.
 
-use super::{BlockSize, ByteCount, Digest};
-use crate::schema::{image, project_image, silo_image};
+use super::{BlockSize, ByteCount, Digest, Labels};
+use crate::schema::{image, image_silo_grant, project_image, silo_image};
 use db_macros::Resource;
 use nexus_types::external_api::views;
 use nexus_types::identity::Resource;
@@ -45,6 +45,8 @@ pub struct Image {
 
     #[diesel(column_name = size_bytes)]
     pub size: ByteCount,
+
+    pub labels: Labels,
 }
 
 #[derive(
@@ -66,6 +68,8 @@ pub struct ProjectImage {
 
     #[diesel(column_name = size_bytes)]
     pub size: ByteCount,
+
+    pub labels: Labels,
 }
 
 #[derive(
@@ -86,6 +90,8 @@ pub struct SiloImage {
 
     #[diesel(column_name = size_bytes)]
     pub size: ByteCount,
+
+    pub labels: Labels,
 }
 
 impl TryFrom<Image> for ProjectImage {
@@ -111,6 +117,7 @@ impl TryFrom<Image> for ProjectImage {
                 digest: image.digest,
                 block_size: image.block_size,
                 size: image.size,
+                labels: image.labels,
             }),
             None => Err(Error::internal_error(
                 "tried to convert non-project image to project image",
@@ -144,6 +151,7 @@ impl TryFrom<Image> for SiloImage {
                 digest: image.digest,
                 block_size: image.block_size,
                 size: image.size,
+                labels: image.labels,
             }),
         }
     }
@@ -169,6 +177,7 @@ impl From<ProjectImage> for Image {
             digest: image.digest,
             block_size: image.block_size,
             size: image.size,
+            labels: image.labels,
         }
     }
 }
@@ -193,6 +202,7 @@ impl From<SiloImage> for Image {
             digest: image.digest,
             block_size: image.block_size,
             size: image.size,
+            labels: image.labels,
         }
     }
 }
@@ -201,6 +211,7 @@ impl From<Image> for views::Image {
     fn from(image: Image) -> Self {
         Self {
             identity: image.identity(),
+            silo_id: image.silo_id,
             project_id: image.project_id,
             url: image.url,
             os: image.os,
@@ -208,6 +219,21 @@ impl From<Image> for views::Image {
             digest: image.digest.map(|x| x.into()),
             block_size: image.block_size.into(),
             size: image.size.into(),
+            labels: image.labels.0,
         }
     }
 }
+
+/// Records that a silo image has been shared with another silo.
+#[derive(Queryable, Insertable, Selectable, Clone, Copy, Debug)]
+#[diesel(table_name = image_silo_grant)]
+pub struct ImageSiloGrant {
+    pub image_id: Uuid,
+    pub silo_id: Uuid,
+}
+
+impl ImageSiloGrant {
+    pub fn new(image_id: Uuid, silo_id: Uuid) -> Self {
+        Self { image_id, silo_id }
+    }
+}
@@ -2,8 +2,12 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-use super::{ByteCount, Disk, Generation, InstanceCpuCount, InstanceState};
+use super::{
+    ByteCount, Disk, Generation, InstanceCpuCount, InstanceState, Labels,
+    SqlU32,
+};
 use crate::collection::DatastoreAttachTargetConfig;
+use crate::impl_enum_type;
 use crate::schema::{disk, instance};
 use chrono::{DateTime, Utc};
 use db_macros::Resource;
@@ -17,6 +21,48 @@ use serde::Serialize;
 use std::net::SocketAddr;
 use uuid::Uuid;
 
+impl_enum_type!(
+    #[derive(SqlType, Debug, QueryId)]
+    #[diesel(postgres_type(name = "instance_auto_restart_policy"))]
+    pub struct InstanceAutoRestartPolicyEnum;
+
+    #[derive(
+        Copy,
+        Clone,
+        Debug,
+        AsExpression,
+        FromSqlRow,
+        PartialEq,
+        Eq,
+        Serialize,
+        Deserialize,
+    )]
+    #[diesel(sql_type = InstanceAutoRestartPolicyEnum)]
+    pub enum InstanceAutoRestartPolicy;
+
+    // Enum values
+    Never => b"never"
+    BestEffort => b"best_effort"
+);
+
+impl From<external::InstanceAutoRestartPolicy> for InstanceAutoRestartPolicy {
+    fn from(policy: external::InstanceAutoRestartPolicy) -> Self {
+        match policy {
+            external::InstanceAutoRestartPolicy::Never => Self::Never,
+            external::InstanceAutoRestartPolicy::BestEffort => Self::BestEffort,
+        }
+    }
+}
+
+impl From<InstanceAutoRestartPolicy> for external::InstanceAutoRestartPolicy {
+    fn from(model: InstanceAutoRestartPolicy) -> Self {
+        match model {
+            InstanceAutoRestartPolicy::Never => Self::Never,
+            InstanceAutoRestartPolicy::BestEffort => Self::BestEffort,
+        }
+    }
+}
+
 /// An Instance (VM).
 #[derive(
     Queryable, Insertable, Debug, Selectable, Resource, Serialize, Deserialize,
@@ -35,6 +81,19 @@ pub struct Instance {
     /// runtime state of the Instance
     #[diesel(embed)]
     pub runtime_state: InstanceRuntimeState,
+
+    /// user-defined labels
+    pub labels: Labels,
+
+    /// whether the Instance is restarted automatically if it fails
+    pub auto_restart_policy: InstanceAutoRestartPolicy,
+
+    /// number of automatic restarts attempted since the Instance last ran
+    /// without failing for a while
+    pub auto_restart_attempts: SqlU32,
+
+    /// when an automatic restart of the Instance was last attempted
+    pub time_last_auto_restarted: Option<DateTime<Utc>>,
 }
 
 impl Instance {
@@ -51,6 +110,10 @@ impl Instance {
             project_id,
             user_data: params.user_data.clone(),
             runtime_state: runtime,
+            labels: params.labels.clone().into(),
+            auto_restart_policy: params.auto_restart_policy.into(),
+            auto_restart_attempts: SqlU32::new(0),
+            time_last_auto_restarted: None,
         }
     }
 
@@ -69,6 +132,10 @@ impl Into<external::Instance> for Instance {
             memory: self.runtime().memory.into(),
             hostname: self.runtime().hostname.clone(),
             runtime: self.runtime().clone().into(),
+            labels: self.labels.0,
+            auto_restart_policy: self.auto_restart_policy.into(),
+            auto_restart_attempts: *self.auto_restart_attempts,
+            time_last_auto_restarted: self.time_last_auto_restarted,
         }
     }
 }
@@ -34,30 +34,53 @@ pub struct IpPool {
     /// Otherwise, this IP pool is intended for usage by customer VMs.
     pub internal: bool,
 
+    /// The Silo whose instances may draw addresses from this pool.  If
+    /// `None`, the pool is available to every Silo.
+    pub silo_id: Option<Uuid>,
+
+    /// Whether this is the default pool for its Silo, or for the whole fleet
+    /// if it isn't linked to a Silo.
+    pub is_default: bool,
+
     /// Child resource generation number, for optimistic concurrency control of
     /// the contained ranges.
     pub rcgen: i64,
 }
 
 impl IpPool {
+    /// Create a new IP pool for customer VMs
     pub fn new(
         pool_identity: &external::IdentityMetadataCreateParams,
-        internal: bool,
+        silo_id: Option<Uuid>,
+        is_default: bool,
     ) -> Self {
         Self {
             identity: IpPoolIdentity::new(
                 Uuid::new_v4(),
                 pool_identity.clone(),
             ),
-            internal,
+            internal: false,
+            silo_id,
+            is_default,
             rcgen: 0,
         }
     }
+
+    /// Create a new IP pool for Oxide services
+    pub fn new_internal(
+        pool_identity: &external::IdentityMetadataCreateParams,
+    ) -> Self {
+        Self { internal: true, ..Self::new(pool_identity, None, false) }
+    }
 }
 
 impl From<IpPool> for views::IpPool {
     fn from(pool: IpPool) -> Self {
-        Self { identity: pool.identity() }
+        Self {
+            identity: pool.identity(),
+            silo_id: pool.silo_id,
+            is_default: pool.is_default,
+        }
     }
 }
 
@@ -10,6 +10,8 @@ extern crate diesel;
 extern crate newtype_derive;
 
 mod address_lot;
+mod affinity;
+mod audit_log;
 mod bgp;
 mod block_size;
 mod bytecount;
@@ -36,6 +38,7 @@ mod ipv4net;
 mod ipv6;
 mod ipv6net;
 mod l4_port_range;
+mod labels;
 mod macaddr;
 mod name;
 mod network_interface;
@@ -59,6 +62,7 @@ mod role_assignment;
 mod role_builtin;
 pub mod saga_types;
 pub mod schema;
+mod scim_client_bearer_token;
 mod service;
 mod service_kind;
 mod silo;
@@ -70,20 +74,24 @@ mod sled_instance;
 mod sled_resource;
 mod sled_resource_kind;
 mod snapshot;
+mod snapshot_schedule;
 mod ssh_key;
 mod switch;
 mod unsigned;
 mod update_artifact;
 mod user_builtin;
 mod virtual_provisioning_collection;
+mod virtual_provisioning_quota;
 mod virtual_provisioning_resource;
 mod vni;
 mod volume;
 mod vpc;
 mod vpc_firewall_rule;
+mod vpc_peering;
 mod vpc_route;
 mod vpc_router;
 mod vpc_subnet;
+mod webhook;
 mod zpool;
 
 // This module namespacing is a quirk to allow `db-macros` to refer to
@@ -97,6 +105,8 @@ mod db {
 pub use self::macaddr::*;
 pub use self::unsigned::*;
 pub use address_lot::*;
+pub use affinity::*;
+pub use audit_log::*;
 pub use bgp::*;
 pub use block_size::*;
 pub use bytecount::*;
@@ -123,6 +133,7 @@ pub use ipv4net::*;
 pub use ipv6::*;
 pub use ipv6net::*;
 pub use l4_port_range::*;
+pub use labels::*;
 pub use name::*;
 pub use network_interface::*;
 pub use oximeter_info::*;
@@ -135,6 +146,7 @@ pub use region::*;
 pub use region_snapshot::*;
 pub use role_assignment::*;
 pub use role_builtin::*;
+pub use scim_client_bearer_token::*;
 pub use semver_version::*;
 pub use service::*;
 pub use service_kind::*;
@@ -147,6 +159,7 @@ pub use sled_instance::*;
 pub use sled_resource::*;
 pub use sled_resource_kind::*;
 pub use snapshot::*;
+pub use snapshot_schedule::*;
 pub use ssh_key::*;
 pub use switch::*;
 pub use switch_interface::*;
@@ -155,14 +168,17 @@ pub use system_update::*;
 pub use update_artifact::*;
 pub use user_builtin::*;
 pub use virtual_provisioning_collection::*;
+pub use virtual_provisioning_quota::*;
 pub use virtual_provisioning_resource::*;
 pub use vni::*;
 pub use volume::*;
 pub use vpc::*;
 pub use vpc_firewall_rule::*;
+pub use vpc_peering::*;
 pub use vpc_route::*;
 pub use vpc_router::*;
 pub use vpc_subnet::*;
+pub use webhook::*;
 pub use zpool::*;
 
 // TODO: The existence of both impl_enum_type and impl_enum_wrapper is a
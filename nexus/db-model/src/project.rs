@@ -2,7 +2,7 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-use super::{Disk, Generation, Instance, Name, Snapshot, Vpc};
+use super::{Disk, Generation, Instance, Labels, Name, Snapshot, Vpc};
 use crate::collection::DatastoreCollectionConfig;
 use crate::schema::{disk, image, instance, project, snapshot, vpc};
 use crate::Image;
@@ -34,6 +34,9 @@ pub struct Project {
     /// child resource generation number, per RFD 192
     pub rcgen: Generation,
     pub silo_id: Uuid,
+
+    /// user-defined labels
+    pub labels: Labels,
 }
 
 impl Project {
@@ -52,13 +55,14 @@ impl Project {
             identity: ProjectIdentity::new(id, params.identity),
             rcgen: Generation::new(),
             silo_id,
+            labels: params.labels.into(),
         }
     }
 }
 
 impl From<Project> for views::Project {
     fn from(project: Project) -> Self {
-        Self { identity: project.identity() }
+        Self { identity: project.identity(), labels: project.labels.0 }
     }
 }
 
@@ -103,6 +107,7 @@ impl DatastoreCollectionConfig<Vpc> for Project {
 pub struct ProjectUpdate {
     pub name: Option<Name>,
     pub description: Option<String>,
+    pub labels: Option<Labels>,
     pub time_modified: DateTime<Utc>,
 }
 
@@ -111,6 +116,7 @@ impl From<params::ProjectUpdate> for ProjectUpdate {
         Self {
             name: params.identity.name.map(Name),
             description: params.identity.description,
+            labels: params.labels.map(Labels),
             time_modified: Utc::now(),
         }
     }
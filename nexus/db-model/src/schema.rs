@@ -29,6 +29,7 @@ table! {
         origin_snapshot -> Nullable<Uuid>,
         origin_image -> Nullable<Uuid>,
         pantry_address -> Nullable<Text>,
+        labels -> Jsonb,
     }
 }
 
@@ -49,6 +50,7 @@ table! {
         digest -> Nullable<Text>,
         block_size -> crate::BlockSizeEnum,
         size_bytes -> Int8,
+        labels -> Jsonb,
     }
 }
 
@@ -69,6 +71,7 @@ table! {
         digest -> Nullable<Text>,
         block_size -> crate::BlockSizeEnum,
         size_bytes -> Int8,
+        labels -> Jsonb,
     }
 }
 
@@ -88,6 +91,14 @@ table! {
         digest -> Nullable<Text>,
         block_size -> crate::BlockSizeEnum,
         size_bytes -> Int8,
+        labels -> Jsonb,
+    }
+}
+
+table! {
+    image_silo_grant (image_id, silo_id) {
+        image_id -> Uuid,
+        silo_id -> Uuid,
     }
 }
 
@@ -331,6 +342,31 @@ table! {
         state -> crate::SnapshotStateEnum,
         block_size -> crate::BlockSizeEnum,
         size_bytes -> Int8,
+        labels -> Jsonb,
+        pantry_address -> Nullable<Text>,
+        snapshot_schedule_id -> Nullable<Uuid>,
+        transient -> Bool,
+    }
+}
+
+table! {
+    snapshot_schedule (id) {
+        id -> Uuid,
+        name -> Text,
+        description -> Text,
+        time_created -> Timestamptz,
+        time_modified -> Timestamptz,
+        time_deleted -> Nullable<Timestamptz>,
+        project_id -> Uuid,
+        target_disk_id -> Nullable<Uuid>,
+        target_labels -> Nullable<Jsonb>,
+        interval_secs -> Int8,
+        retain -> Int8,
+        time_last_run -> Nullable<Timestamptz>,
+        last_run_status -> Nullable<crate::SnapshotScheduleRunStatusEnum>,
+        last_run_snapshots_created -> Int8,
+        last_run_snapshots_deleted -> Int8,
+        last_run_error -> Nullable<Text>,
     }
 }
 
@@ -356,6 +392,31 @@ table! {
         ncpus -> Int8,
         memory -> Int8,
         hostname -> Text,
+        labels -> Jsonb,
+        auto_restart_policy -> crate::InstanceAutoRestartPolicyEnum,
+        auto_restart_attempts -> Int8,
+        time_last_auto_restarted -> Nullable<Timestamptz>,
+    }
+}
+
+table! {
+    affinity_group (id) {
+        id -> Uuid,
+        name -> Text,
+        description -> Text,
+        time_created -> Timestamptz,
+        time_modified -> Timestamptz,
+        time_deleted -> Nullable<Timestamptz>,
+        project_id -> Uuid,
+        kind -> crate::AffinityGroupKindEnum,
+        policy -> crate::AffinityPolicyEnum,
+    }
+}
+
+table! {
+    affinity_group_instance_membership (affinity_group_id, instance_id) {
+        affinity_group_id -> Uuid,
+        instance_id -> Uuid,
     }
 }
 
@@ -452,6 +513,8 @@ table! {
         time_modified -> Timestamptz,
         time_deleted -> Nullable<Timestamptz>,
         internal -> Bool,
+        silo_id -> Nullable<Uuid>,
+        is_default -> Bool,
         rcgen -> Int8,
     }
 }
@@ -485,6 +548,23 @@ table! {
         ip -> Inet,
         first_port -> Int4,
         last_port -> Int4,
+        project_id -> Nullable<Uuid>,
+    }
+}
+
+table! {
+    floating_ip (id) {
+        id -> Uuid,
+        name -> Text,
+        description -> Text,
+        time_created -> Timestamptz,
+        time_modified -> Timestamptz,
+        time_deleted -> Nullable<Timestamptz>,
+        ip_pool_id -> Uuid,
+        ip_pool_range_id -> Uuid,
+        parent_id -> Nullable<Uuid>,
+        ip -> Inet,
+        project_id -> Uuid,
     }
 }
 
@@ -516,6 +596,7 @@ table! {
 
         silo_id -> Uuid,
         external_id -> Text,
+        active -> Bool,
     }
 }
 
@@ -554,6 +635,16 @@ allow_tables_to_appear_in_same_query!(
 );
 allow_tables_to_appear_in_same_query!(role_assignment, silo_group_membership);
 
+table! {
+    scim_client_bearer_token (id) {
+        id -> Uuid,
+        time_created -> Timestamptz,
+        time_expires -> Nullable<Timestamptz>,
+        silo_id -> Uuid,
+        bearer_token -> Text,
+    }
+}
+
 table! {
     identity_provider (silo_id, id) {
         id -> Uuid,
@@ -592,6 +683,31 @@ table! {
     }
 }
 
+table! {
+    oidc_identity_provider (id) {
+        id -> Uuid,
+        name -> Text,
+        description -> Text,
+        time_created -> Timestamptz,
+        time_modified -> Timestamptz,
+        time_deleted -> Nullable<Timestamptz>,
+
+        silo_id -> Uuid,
+
+        issuer -> Text,
+        authorization_endpoint -> Text,
+        token_endpoint -> Text,
+        jwks -> Text,
+
+        client_id -> Text,
+        client_secret -> Text,
+        redirect_uri -> Text,
+        scopes -> Text,
+
+        group_claim_name -> Nullable<Text>,
+    }
+}
+
 table! {
     ssh_key (id) {
         id -> Uuid,
@@ -605,6 +721,13 @@ table! {
     }
 }
 
+table! {
+    instance_ssh_key (instance_id, ssh_key_id) {
+        instance_id -> Uuid,
+        ssh_key_id -> Uuid,
+    }
+}
+
 table! {
     oximeter (id) {
         id -> Uuid,
@@ -625,6 +748,7 @@ table! {
         time_deleted -> Nullable<Timestamptz>,
         rcgen -> Int8,
         silo_id -> Uuid,
+        labels -> Jsonb,
     }
 }
 
@@ -672,6 +796,61 @@ table! {
     }
 }
 
+table! {
+    audit_log_entry (id) {
+        id -> Uuid,
+        time_started -> Timestamptz,
+        time_completed -> Timestamptz,
+        request_id -> Text,
+        actor_id -> Uuid,
+        actor_silo_id -> Nullable<Uuid>,
+        operation_id -> Text,
+        http_method -> Text,
+        resource_path -> Text,
+        result_status -> Int4,
+    }
+}
+
+table! {
+    webhook_receiver (id) {
+        id -> Uuid,
+        name -> Text,
+        description -> Text,
+        time_created -> Timestamptz,
+        time_modified -> Timestamptz,
+        time_deleted -> Nullable<Timestamptz>,
+        silo_id -> Uuid,
+        endpoint -> Text,
+        secret -> Text,
+        event_classes -> Array<crate::WebhookEventClassEnum>,
+    }
+}
+
+table! {
+    webhook_event (id) {
+        id -> Uuid,
+        time_created -> Timestamptz,
+        silo_id -> Nullable<Uuid>,
+        event_class -> crate::WebhookEventClassEnum,
+        data -> Jsonb,
+    }
+}
+
+table! {
+    webhook_delivery (id) {
+        id -> Uuid,
+        event_id -> Uuid,
+        receiver_id -> Uuid,
+        time_created -> Timestamptz,
+        state -> crate::WebhookDeliveryStateEnum,
+        attempts -> Int8,
+        time_next_attempt -> Nullable<Timestamptz>,
+        time_last_attempt -> Nullable<Timestamptz>,
+        last_response_status -> Nullable<Int4>,
+        last_error -> Nullable<Text>,
+    }
+}
+
 table! {
     sled (id) {
         id -> Uuid,
@@ -799,6 +978,17 @@ table! {
     }
 }
 
+table! {
+    virtual_provisioning_quota {
+        id -> Uuid,
+        time_modified -> Timestamptz,
+        collection_type -> Text,
+        virtual_disk_bytes -> Nullable<Int8>,
+        cpus -> Nullable<Int8>,
+        ram_bytes -> Nullable<Int8>,
+    }
+}
+
 table! {
     zpool (id) {
         id -> Uuid,
@@ -890,6 +1080,7 @@ table! {
         dns_name -> Text,
         firewall_gen -> Int8,
         subnet_gen -> Int8,
+        labels -> Jsonb,
     }
 }
 
@@ -937,6 +1128,20 @@ table! {
     }
 }
 
+table! {
+    vpc_peering (id) {
+        id -> Uuid,
+        name -> Text,
+        description -> Text,
+        time_created -> Timestamptz,
+        time_modified -> Timestamptz,
+        time_deleted -> Nullable<Timestamptz>,
+        vpc_id -> Uuid,
+        peer_vpc_id -> Uuid,
+        state -> crate::VpcPeeringStateEnum,
+    }
+}
+
 table! {
     use diesel::sql_types::*;
 
@@ -1012,12 +1217,15 @@ table! {
 table! {
     device_access_token (token) {
         token -> Text,
-        client_id -> Uuid,
-        device_code -> Text,
+        client_id -> Nullable<Uuid>,
+        device_code -> Nullable<Text>,
         silo_user_id -> Uuid,
         time_requested -> Timestamptz,
         time_created -> Timestamptz,
         time_expires -> Nullable<Timestamptz>,
+        id -> Uuid,
+        name -> Nullable<Text>,
+        time_last_used -> Nullable<Timestamptz>,
     }
 }
 
@@ -1135,12 +1343,17 @@ allow_tables_to_appear_in_same_query!(ip_pool_range, ip_pool);
 joinable!(ip_pool_range -> ip_pool (ip_pool_id));
 
 allow_tables_to_appear_in_same_query!(
+    affinity_group,
+    affinity_group_instance_membership,
     dataset,
     disk,
+    floating_ip,
     image,
     project_image,
     silo_image,
+    image_silo_grant,
     instance,
+    instance_ssh_key,
     metric_producer,
     network_interface,
     instance_network_interface,
@@ -1158,12 +1371,14 @@ allow_tables_to_appear_in_same_query!(
     service,
     sled,
     sled_resource,
+    ssh_key,
     router_route,
     volume,
     vpc,
     vpc_subnet,
     vpc_router,
     vpc_firewall_rule,
+    vpc_peering,
     user_builtin,
     role_builtin,
     role_assignment,
@@ -1171,6 +1386,11 @@ allow_tables_to_appear_in_same_query!(
 
 allow_tables_to_appear_in_same_query!(dns_zone, dns_version, dns_name);
 allow_tables_to_appear_in_same_query!(external_ip, service);
+allow_tables_to_appear_in_same_query!(
+    webhook_receiver,
+    webhook_event,
+    webhook_delivery
+);
 
 allow_tables_to_appear_in_same_query!(
     switch_port,
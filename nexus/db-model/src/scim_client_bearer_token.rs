@@ -0,0 +1,65 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use crate::device_auth::generate_token;
+use crate::schema::scim_client_bearer_token;
+use chrono::{DateTime, Utc};
+use nexus_types::external_api::views;
+use uuid::Uuid;
+
+/// Prefix of the bearer tokens handed out to SCIM clients, which
+/// distinguishes them from the access tokens used with the rest of the API.
+pub const SCIM_TOKEN_PREFIX: &str = "oxide-scim-";
+
+/// A bearer token that a SCIM client uses to provision the users and groups
+/// of one Silo
+// TODO-security: wrap token in an opaque struct to avoid accidental leaks.
+#[derive(Clone, Debug, Insertable, Queryable, Selectable)]
+#[diesel(table_name = scim_client_bearer_token)]
+pub struct ScimClientBearerToken {
+    pub id: Uuid,
+    pub time_created: DateTime<Utc>,
+    pub time_expires: Option<DateTime<Utc>>,
+    pub silo_id: Uuid,
+    pub bearer_token: String,
+}
+
+impl ScimClientBearerToken {
+    pub fn new(silo_id: Uuid, time_expires: Option<DateTime<Utc>>) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            time_created: Utc::now(),
+            time_expires,
+            silo_id,
+            bearer_token: generate_token(),
+        }
+    }
+
+    /// Returns whether the token can no longer be used as of `now`
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.time_expires.map_or(false, |time_expires| time_expires <= now)
+    }
+}
+
+impl From<ScimClientBearerToken> for views::ScimClientBearerToken {
+    fn from(token: ScimClientBearerToken) -> Self {
+        Self {
+            id: token.id,
+            time_created: token.time_created,
+            time_expires: token.time_expires,
+        }
+    }
+}
+
+impl From<ScimClientBearerToken> for views::ScimClientBearerTokenCreated {
+    fn from(token: ScimClientBearerToken) -> Self {
+        Self {
+            bearer_token: format!(
+                "{}{}",
+                SCIM_TOKEN_PREFIX, token.bearer_token
+            ),
+            token: token.into(),
+        }
+    }
+}
@@ -3,6 +3,7 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
 use crate::schema::silo_user;
+use chrono::{DateTime, Utc};
 use db_macros::Asset;
 use nexus_types::external_api::views;
 use nexus_types::identity::Asset;
@@ -20,6 +21,10 @@ pub struct SiloUser {
 
     /// The identity provider's ID for this user.
     pub external_id: String,
+
+    /// Whether this user may authenticate.  Only SCIM clients deactivate
+    /// users.
+    pub active: bool,
 }
 
 impl SiloUser {
@@ -29,10 +34,20 @@ impl SiloUser {
             time_deleted: None,
             silo_id,
             external_id,
+            active: true,
         }
     }
 }
 
+/// Changes that a SCIM client can make to an existing silo user
+#[derive(AsChangeset)]
+#[diesel(table_name = silo_user)]
+pub struct SiloUserUpdate {
+    pub external_id: Option<String>,
+    pub active: Option<bool>,
+    pub time_modified: DateTime<Utc>,
+}
+
 impl From<SiloUser> for views::User {
     fn from(user: SiloUser) -> Self {
         Self {
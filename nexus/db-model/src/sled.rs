@@ -153,12 +153,24 @@ impl DatastoreCollectionConfig<super::Service> for Sled {
 #[derive(Debug)]
 pub struct SledReservationConstraints {
     must_select_from: Vec<Uuid>,
+    must_not_select_from: Vec<Uuid>,
+    prefer: Vec<Uuid>,
+    avoid: Vec<Uuid>,
+    affinity_instance_id: Option<Uuid>,
+    affinity_groups: Vec<Uuid>,
 }
 
 impl SledReservationConstraints {
     /// Creates a constraint set with no constraints in it.
     pub fn none() -> Self {
-        Self { must_select_from: Vec::new() }
+        Self {
+            must_select_from: Vec::new(),
+            must_not_select_from: Vec::new(),
+            prefer: Vec::new(),
+            avoid: Vec::new(),
+            affinity_instance_id: None,
+            affinity_groups: Vec::new(),
+        }
     }
 
     /// If the constraints include a set of sleds that the caller must select
@@ -172,6 +184,41 @@ impl SledReservationConstraints {
             Some(&self.must_select_from)
         }
     }
+
+    /// Returns the set of sleds that the caller must not select, which may be
+    /// empty.
+    pub fn must_not_select_from(&self) -> &[Uuid] {
+        &self.must_not_select_from
+    }
+
+    /// Returns the set of sleds that should be selected in preference to
+    /// others, if any of them is suitable.
+    pub fn prefer(&self) -> &[Uuid] {
+        &self.prefer
+    }
+
+    /// Returns the set of sleds that should only be selected if no other sled
+    /// is suitable.
+    pub fn avoid(&self) -> &[Uuid] {
+        &self.avoid
+    }
+
+    /// If the sled is to be selected according to the affinity groups of an
+    /// instance, returns the ID of that instance along with the IDs of any
+    /// groups it is about to join.
+    pub fn affinity(&self) -> Option<(Uuid, &[Uuid])> {
+        self.affinity_instance_id
+            .map(|instance_id| (instance_id, self.affinity_groups.as_slice()))
+    }
+
+    /// Returns true if the "must" constraints in this set allow the sled
+    /// with the supplied ID to be selected.
+    ///
+    /// Preferences are not considered.
+    pub fn permits(&self, sled_id: Uuid) -> bool {
+        self.must_select_from().map_or(true, |ids| ids.contains(&sled_id))
+            && !self.must_not_select_from.contains(&sled_id)
+    }
 }
 
 #[derive(Debug)]
@@ -194,6 +241,44 @@ impl SledReservationConstraintBuilder {
         self
     }
 
+    /// Adds a "must not select any of the following sled IDs" constraint. If
+    /// such a constraint already exists, appends the supplied sled IDs to the
+    /// "must not select from" list.
+    pub fn must_not_select_from(mut self, sled_ids: &[Uuid]) -> Self {
+        self.constraints.must_not_select_from.extend(sled_ids);
+        self
+    }
+
+    /// Asks that one of the supplied sleds be selected if any of them is
+    /// suitable.
+    pub fn prefer(mut self, sled_ids: &[Uuid]) -> Self {
+        self.constraints.prefer.extend(sled_ids);
+        self
+    }
+
+    /// Asks that the supplied sleds be selected only if no other sled is
+    /// suitable.
+    pub fn avoid(mut self, sled_ids: &[Uuid]) -> Self {
+        self.constraints.avoid.extend(sled_ids);
+        self
+    }
+
+    /// Asks that the sled be selected according to the placement of the other
+    /// members of the affinity groups of which the instance `instance_id` is a
+    /// member, along with the groups `joining`, which it is about to join.
+    ///
+    /// Unlike the other constraints, these are resolved in the same
+    /// transaction that selects the sled.
+    pub fn affinity_groups(
+        mut self,
+        instance_id: Uuid,
+        joining: &[Uuid],
+    ) -> Self {
+        self.constraints.affinity_instance_id = Some(instance_id);
+        self.constraints.affinity_groups.extend(joining);
+        self
+    }
+
     /// Builds a set of constraints from this builder's current state.
     pub fn build(self) -> SledReservationConstraints {
         self.constraints
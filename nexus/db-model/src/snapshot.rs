@@ -2,7 +2,7 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-use super::{impl_enum_type, ByteCount};
+use super::{impl_enum_type, ByteCount, Labels};
 use crate::schema::snapshot;
 use crate::BlockSize;
 use crate::Generation;
@@ -10,6 +10,7 @@ use db_macros::Resource;
 use nexus_types::external_api::views;
 use nexus_types::identity::Resource;
 use serde::{Deserialize, Serialize};
+use std::net::SocketAddrV6;
 use uuid::Uuid;
 
 impl_enum_type!(
@@ -56,6 +57,25 @@ pub struct Snapshot {
 
     #[diesel(column_name = size_bytes)]
     pub size: ByteCount,
+
+    pub labels: Labels,
+
+    /// the Pantry this snapshot is attached to while it is being exported
+    pub pantry_address: Option<String>,
+
+    /// the snapshot schedule that took this snapshot, if any
+    pub snapshot_schedule_id: Option<Uuid>,
+
+    /// whether Nexus took this snapshot for its own use while copying or
+    /// resizing a disk, in which case it isn't listed or charged to the
+    /// project
+    pub transient: bool,
+}
+
+impl Snapshot {
+    pub fn pantry_address(&self) -> Option<SocketAddrV6> {
+        self.pantry_address.as_ref().map(|x| x.parse().unwrap())
+    }
 }
 
 impl From<Snapshot> for views::Snapshot {
@@ -66,6 +86,7 @@ impl From<Snapshot> for views::Snapshot {
             disk_id: snapshot.disk_id,
             state: snapshot.state.into(),
             size: snapshot.size.into(),
+            labels: snapshot.labels.0,
         }
     }
 }
@@ -0,0 +1,148 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Database representation of snapshot schedules
+
+use super::{impl_enum_type, Labels, SqlU32};
+use crate::schema::snapshot_schedule;
+use chrono::{DateTime, Utc};
+use db_macros::Resource;
+use nexus_types::external_api::params;
+use nexus_types::external_api::views;
+use nexus_types::identity::Resource;
+use uuid::Uuid;
+
+impl_enum_type!(
+    #[derive(SqlType, Debug, QueryId)]
+    #[diesel(postgres_type(name = "snapshot_schedule_run_status"))]
+    pub struct SnapshotScheduleRunStatusEnum;
+
+    #[derive(Copy, Clone, Debug, AsExpression, FromSqlRow, PartialEq, Eq)]
+    #[diesel(sql_type = SnapshotScheduleRunStatusEnum)]
+    pub enum SnapshotScheduleRunStatus;
+
+    // Enum values
+    Running => b"running"
+    Succeeded => b"succeeded"
+    Failed => b"failed"
+);
+
+impl From<SnapshotScheduleRunStatus> for views::SnapshotScheduleRunStatus {
+    fn from(model: SnapshotScheduleRunStatus) -> Self {
+        match model {
+            SnapshotScheduleRunStatus::Running => Self::Running,
+            SnapshotScheduleRunStatus::Succeeded => Self::Succeeded,
+            SnapshotScheduleRunStatus::Failed => Self::Failed,
+        }
+    }
+}
+
+/// A schedule on which a set of disks in a project is snapshotted.
+#[derive(Queryable, Insertable, Selectable, Clone, Debug, Resource)]
+#[diesel(table_name = snapshot_schedule)]
+pub struct SnapshotSchedule {
+    #[diesel(embed)]
+    pub identity: SnapshotScheduleIdentity,
+
+    pub project_id: Uuid,
+
+    /// the disk to snapshot, if the schedule targets a single disk
+    pub target_disk_id: Option<Uuid>,
+    /// the labels of the disks to snapshot, if the schedule targets disks by
+    /// label
+    pub target_labels: Option<Labels>,
+
+    pub interval_secs: SqlU32,
+    pub retain: SqlU32,
+
+    pub time_last_run: Option<DateTime<Utc>>,
+    pub last_run_status: Option<SnapshotScheduleRunStatus>,
+    pub last_run_snapshots_created: SqlU32,
+    pub last_run_snapshots_deleted: SqlU32,
+    pub last_run_error: Option<String>,
+}
+
+impl SnapshotSchedule {
+    /// Creates a new schedule, whose disk target (if any) has already been
+    /// resolved to an ID
+    pub fn new(
+        project_id: Uuid,
+        params: params::SnapshotScheduleCreate,
+        target: views::SnapshotScheduleTarget,
+    ) -> Self {
+        let (target_disk_id, target_labels) = match target {
+            views::SnapshotScheduleTarget::Disk { disk_id } => {
+                (Some(disk_id), None)
+            }
+            views::SnapshotScheduleTarget::Labels { labels } => {
+                (None, Some(Labels(labels)))
+            }
+        };
+        Self {
+            identity: SnapshotScheduleIdentity::new(
+                Uuid::new_v4(),
+                params.identity,
+            ),
+            project_id,
+            target_disk_id,
+            target_labels,
+            interval_secs: SqlU32::new(params.interval_secs),
+            retain: SqlU32::new(params.retain),
+            time_last_run: None,
+            last_run_status: None,
+            last_run_snapshots_created: SqlU32::new(0),
+            last_run_snapshots_deleted: SqlU32::new(0),
+            last_run_error: None,
+        }
+    }
+
+    pub fn target(&self) -> views::SnapshotScheduleTarget {
+        match (self.target_disk_id, &self.target_labels) {
+            (Some(disk_id), _) => {
+                views::SnapshotScheduleTarget::Disk { disk_id }
+            }
+            (None, labels) => views::SnapshotScheduleTarget::Labels {
+                labels: labels.clone().unwrap_or_default().0,
+            },
+        }
+    }
+
+    /// Returns whether the schedule is due to run at time `now`
+    pub fn is_due(&self, now: DateTime<Utc>) -> bool {
+        match self.time_last_run {
+            None => true,
+            Some(time_last_run) => {
+                now - time_last_run
+                    >= chrono::Duration::seconds(i64::from(*self.interval_secs))
+            }
+        }
+    }
+}
+
+impl From<SnapshotSchedule> for views::SnapshotSchedule {
+    fn from(schedule: SnapshotSchedule) -> Self {
+        let target = schedule.target();
+        let last_run = match (schedule.time_last_run, schedule.last_run_status)
+        {
+            (Some(time_started), Some(status)) => {
+                Some(views::SnapshotScheduleRun {
+                    time_started,
+                    status: status.into(),
+                    snapshots_created: *schedule.last_run_snapshots_created,
+                    snapshots_deleted: *schedule.last_run_snapshots_deleted,
+                    error: schedule.last_run_error.clone(),
+                })
+            }
+            _ => None,
+        };
+        Self {
+            identity: schedule.identity(),
+            project_id: schedule.project_id,
+            target,
+            interval_secs: *schedule.interval_secs,
+            retain: *schedule.retain,
+            last_run,
+        }
+    }
+}
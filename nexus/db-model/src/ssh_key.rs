@@ -2,7 +2,7 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-use crate::schema::ssh_key;
+use crate::schema::{instance_ssh_key, ssh_key};
 use db_macros::Resource;
 use nexus_types::external_api::params;
 use nexus_types::external_api::views;
@@ -47,3 +47,17 @@ impl From<SshKey> for views::SshKey {
         }
     }
 }
+
+/// Records that an SSH public key was chosen for an instance.
+#[derive(Queryable, Insertable, Selectable, Clone, Copy, Debug)]
+#[diesel(table_name = instance_ssh_key)]
+pub struct InstanceSshKey {
+    pub instance_id: Uuid,
+    pub ssh_key_id: Uuid,
+}
+
+impl InstanceSshKey {
+    pub fn new(instance_id: Uuid, ssh_key_id: Uuid) -> Self {
+        Self { instance_id, ssh_key_id }
+    }
+}
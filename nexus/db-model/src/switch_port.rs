@@ -435,6 +435,7 @@ impl Into<external::SwitchPortBgpPeerConfig> for SwitchPortBgpPeerConfig {
             bgp_config_id: self.bgp_config_id,
             interface_name: self.interface_name.clone(),
             addr: self.addr.ip(),
+            applied: false,
         }
     }
 }
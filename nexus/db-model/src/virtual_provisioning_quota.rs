@@ -0,0 +1,69 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use crate::schema::virtual_provisioning_quota;
+use crate::ByteCount;
+use crate::CollectionTypeProvisioned;
+use chrono::{DateTime, Utc};
+use nexus_types::external_api::params;
+use nexus_types::external_api::views;
+use uuid::Uuid;
+
+/// Describes the limits on virtual resources provisioned within a collection
+///
+/// A limit of `None` means that the corresponding resource is unbounded.
+#[derive(Clone, Selectable, Queryable, Insertable, Debug)]
+#[diesel(table_name = virtual_provisioning_quota)]
+pub struct VirtualProvisioningQuota {
+    pub id: Uuid,
+    pub time_modified: DateTime<Utc>,
+    pub collection_type: String,
+
+    pub virtual_disk_bytes: Option<ByteCount>,
+    pub cpus: Option<i64>,
+    pub ram_bytes: Option<ByteCount>,
+}
+
+impl VirtualProvisioningQuota {
+    /// Returns a quota for the collection `id` which places no limits on any
+    /// resource
+    pub fn unlimited(
+        id: Uuid,
+        collection_type: CollectionTypeProvisioned,
+    ) -> Self {
+        Self {
+            id,
+            time_modified: Utc::now(),
+            collection_type: collection_type.to_string(),
+            virtual_disk_bytes: None,
+            cpus: None,
+            ram_bytes: None,
+        }
+    }
+
+    pub fn new(
+        id: Uuid,
+        collection_type: CollectionTypeProvisioned,
+        params: params::QuotaUpdate,
+    ) -> Self {
+        Self {
+            id,
+            time_modified: Utc::now(),
+            collection_type: collection_type.to_string(),
+            virtual_disk_bytes: params.storage.map(ByteCount::from),
+            cpus: params.cpus.map(i64::from),
+            ram_bytes: params.memory.map(ByteCount::from),
+        }
+    }
+}
+
+impl From<VirtualProvisioningQuota> for views::Quota {
+    fn from(quota: VirtualProvisioningQuota) -> Self {
+        Self {
+            cpus: quota.cpus.map(|cpus| cpus as u32),
+            memory: quota.ram_bytes.map(|b| b.0),
+            storage: quota.virtual_disk_bytes.map(|b| b.0),
+        }
+    }
+}
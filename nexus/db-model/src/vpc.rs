@@ -2,7 +2,7 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-use super::{Generation, Ipv6Net, Name, VpcFirewallRule, VpcSubnet};
+use super::{Generation, Ipv6Net, Labels, Name, VpcFirewallRule, VpcSubnet};
 use crate::collection::DatastoreCollectionConfig;
 use crate::schema::{vpc, vpc_firewall_rule, vpc_subnet};
 use crate::Vni;
@@ -45,6 +45,9 @@ pub struct Vpc {
 
     /// VPC Subnet generation number
     pub subnet_gen: Generation,
+
+    /// user-defined labels
+    pub labels: Labels,
 }
 
 impl From<Vpc> for views::Vpc {
@@ -55,6 +58,7 @@ impl From<Vpc> for views::Vpc {
             system_router_id: vpc.system_router_id,
             ipv6_prefix: *vpc.ipv6_prefix,
             dns_name: vpc.dns_name.0,
+            labels: vpc.labels.0,
         }
     }
 }
@@ -73,6 +77,7 @@ pub struct IncompleteVpc {
     pub dns_name: Name,
     pub firewall_gen: Generation,
     pub subnet_gen: Generation,
+    pub labels: Labels,
 }
 
 impl IncompleteVpc {
@@ -108,6 +113,7 @@ impl IncompleteVpc {
             dns_name: params.dns_name.into(),
             firewall_gen: Generation::new(),
             subnet_gen: Generation::new(),
+            labels: params.labels.into(),
         })
     }
 }
@@ -133,6 +139,7 @@ pub struct VpcUpdate {
     pub description: Option<String>,
     pub time_modified: DateTime<Utc>,
     pub dns_name: Option<Name>,
+    pub labels: Option<Labels>,
 }
 
 impl From<params::VpcUpdate> for VpcUpdate {
@@ -142,6 +149,7 @@ impl From<params::VpcUpdate> for VpcUpdate {
             description: params.identity.description,
             time_modified: Utc::now(),
             dns_name: params.dns_name.map(Name),
+            labels: params.labels.map(Labels),
         }
     }
 }
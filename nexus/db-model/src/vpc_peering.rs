@@ -0,0 +1,77 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Database representation of VPC peerings
+
+use crate::impl_enum_type;
+use crate::schema::vpc_peering;
+use db_macros::Resource;
+use nexus_types::external_api::params;
+use nexus_types::external_api::shared;
+use nexus_types::external_api::views;
+use nexus_types::identity::Resource;
+use uuid::Uuid;
+
+impl_enum_type!(
+    #[derive(SqlType, Debug, QueryId)]
+    #[diesel(postgres_type(name = "vpc_peering_state"))]
+    pub struct VpcPeeringStateEnum;
+
+    #[derive(Copy, Clone, Debug, AsExpression, FromSqlRow, PartialEq, Eq)]
+    #[diesel(sql_type = VpcPeeringStateEnum)]
+    pub enum VpcPeeringState;
+
+    // Enum values
+    Pending => b"pending"
+    Active => b"active"
+);
+
+impl From<VpcPeeringState> for shared::VpcPeeringState {
+    fn from(model: VpcPeeringState) -> Self {
+        match model {
+            VpcPeeringState::Pending => Self::Pending,
+            VpcPeeringState::Active => Self::Active,
+        }
+    }
+}
+
+/// One VPC's side of a peering with another VPC.
+#[derive(Queryable, Insertable, Selectable, Clone, Debug, Resource)]
+#[diesel(table_name = vpc_peering)]
+pub struct VpcPeering {
+    #[diesel(embed)]
+    pub identity: VpcPeeringIdentity,
+
+    pub vpc_id: Uuid,
+    pub peer_vpc_id: Uuid,
+    pub state: VpcPeeringState,
+}
+
+impl VpcPeering {
+    /// Create a new, pending side of a peering between `vpc_id` and
+    /// `peer_vpc_id`.
+    pub fn new(
+        vpc_id: Uuid,
+        peer_vpc_id: Uuid,
+        params: params::VpcPeeringCreate,
+    ) -> Self {
+        Self {
+            identity: VpcPeeringIdentity::new(Uuid::new_v4(), params.identity),
+            vpc_id,
+            peer_vpc_id,
+            state: VpcPeeringState::Pending,
+        }
+    }
+}
+
+impl From<VpcPeering> for views::VpcPeering {
+    fn from(peering: VpcPeering) -> Self {
+        Self {
+            identity: peering.identity(),
+            vpc_id: peering.vpc_id,
+            peer_vpc_id: peering.peer_vpc_id,
+            state: peering.state.into(),
+        }
+    }
+}
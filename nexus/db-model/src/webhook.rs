@@ -0,0 +1,226 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Database representation of webhook receivers, events, and deliveries
+
+use super::{impl_enum_type, SqlU32};
+use crate::schema::{webhook_delivery, webhook_event, webhook_receiver};
+use chrono::{DateTime, Utc};
+use db_macros::Resource;
+use nexus_types::external_api::params;
+use nexus_types::external_api::shared;
+use nexus_types::external_api::views;
+use nexus_types::identity::Resource;
+use uuid::Uuid;
+
+impl_enum_type!(
+    #[derive(SqlType, Debug, QueryId)]
+    #[diesel(postgres_type(name = "webhook_event_class"))]
+    pub struct WebhookEventClassEnum;
+
+    #[derive(Copy, Clone, Debug, AsExpression, FromSqlRow, PartialEq, Eq)]
+    #[diesel(sql_type = WebhookEventClassEnum)]
+    pub enum WebhookEventClass;
+
+    // Enum values
+    InstanceStateChange => b"instance_state_change"
+    DiskAttach => b"disk_attach"
+    DiskDetach => b"disk_detach"
+    SnapshotComplete => b"snapshot_complete"
+    UpdateDeploymentProgress => b"update_deployment_progress"
+);
+
+impl From<shared::WebhookEventClass> for WebhookEventClass {
+    fn from(params: shared::WebhookEventClass) -> Self {
+        match params {
+            shared::WebhookEventClass::InstanceStateChange => {
+                WebhookEventClass::InstanceStateChange
+            }
+            shared::WebhookEventClass::DiskAttach => {
+                WebhookEventClass::DiskAttach
+            }
+            shared::WebhookEventClass::DiskDetach => {
+                WebhookEventClass::DiskDetach
+            }
+            shared::WebhookEventClass::SnapshotComplete => {
+                WebhookEventClass::SnapshotComplete
+            }
+            shared::WebhookEventClass::UpdateDeploymentProgress => {
+                WebhookEventClass::UpdateDeploymentProgress
+            }
+        }
+    }
+}
+
+impl From<WebhookEventClass> for shared::WebhookEventClass {
+    fn from(model: WebhookEventClass) -> Self {
+        match model {
+            WebhookEventClass::InstanceStateChange => Self::InstanceStateChange,
+            WebhookEventClass::DiskAttach => Self::DiskAttach,
+            WebhookEventClass::DiskDetach => Self::DiskDetach,
+            WebhookEventClass::SnapshotComplete => Self::SnapshotComplete,
+            WebhookEventClass::UpdateDeploymentProgress => {
+                Self::UpdateDeploymentProgress
+            }
+        }
+    }
+}
+
+impl_enum_type!(
+    #[derive(SqlType, Debug, QueryId)]
+    #[diesel(postgres_type(name = "webhook_delivery_state"))]
+    pub struct WebhookDeliveryStateEnum;
+
+    #[derive(Copy, Clone, Debug, AsExpression, FromSqlRow, PartialEq, Eq)]
+    #[diesel(sql_type = WebhookDeliveryStateEnum)]
+    pub enum WebhookDeliveryState;
+
+    // Enum values
+    Pending => b"pending"
+    Delivered => b"delivered"
+    Failed => b"failed"
+);
+
+impl From<WebhookDeliveryState> for views::WebhookDeliveryState {
+    fn from(model: WebhookDeliveryState) -> Self {
+        match model {
+            WebhookDeliveryState::Pending => Self::Pending,
+            WebhookDeliveryState::Delivered => Self::Delivered,
+            WebhookDeliveryState::Failed => Self::Failed,
+        }
+    }
+}
+
+/// A receiver to which the events in a Silo of the classes it subscribes to
+/// are delivered.
+#[derive(Queryable, Insertable, Selectable, Clone, Debug, Resource)]
+#[diesel(table_name = webhook_receiver)]
+pub struct WebhookReceiver {
+    #[diesel(embed)]
+    pub identity: WebhookReceiverIdentity,
+
+    pub silo_id: Uuid,
+    pub endpoint: String,
+    pub secret: String,
+    pub event_classes: Vec<WebhookEventClass>,
+}
+
+impl WebhookReceiver {
+    pub fn new(silo_id: Uuid, params: params::WebhookReceiverCreate) -> Self {
+        let mut event_classes: Vec<WebhookEventClass> = Vec::new();
+        for class in params.event_classes {
+            let class = WebhookEventClass::from(class);
+            if !event_classes.contains(&class) {
+                event_classes.push(class);
+            }
+        }
+        Self {
+            identity: WebhookReceiverIdentity::new(
+                Uuid::new_v4(),
+                params.identity,
+            ),
+            silo_id,
+            endpoint: params.endpoint,
+            secret: params.secret,
+            event_classes,
+        }
+    }
+}
+
+impl From<WebhookReceiver> for views::WebhookReceiver {
+    fn from(receiver: WebhookReceiver) -> Self {
+        Self {
+            identity: receiver.identity(),
+            endpoint: receiver.endpoint,
+            event_classes: receiver
+                .event_classes
+                .into_iter()
+                .map(shared::WebhookEventClass::from)
+                .collect(),
+        }
+    }
+}
+
+/// Something that happened, which is delivered to the webhook receivers
+/// subscribed to its class.
+#[derive(Queryable, Insertable, Selectable, Clone, Debug)]
+#[diesel(table_name = webhook_event)]
+pub struct WebhookEvent {
+    pub id: Uuid,
+    pub time_created: DateTime<Utc>,
+    /// the Silo whose resources the event concerns, if any
+    pub silo_id: Option<Uuid>,
+    pub event_class: WebhookEventClass,
+    pub data: serde_json::Value,
+}
+
+impl WebhookEvent {
+    pub fn new(
+        silo_id: Option<Uuid>,
+        event_class: WebhookEventClass,
+        data: serde_json::Value,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            time_created: Utc::now(),
+            silo_id,
+            event_class,
+            data,
+        }
+    }
+}
+
+/// The delivery of an event to a webhook receiver.
+#[derive(Queryable, Insertable, Selectable, Clone, Debug)]
+#[diesel(table_name = webhook_delivery)]
+pub struct WebhookDelivery {
+    pub id: Uuid,
+    pub event_id: Uuid,
+    pub receiver_id: Uuid,
+    pub time_created: DateTime<Utc>,
+    pub state: WebhookDeliveryState,
+    pub attempts: SqlU32,
+    pub time_next_attempt: Option<DateTime<Utc>>,
+    pub time_last_attempt: Option<DateTime<Utc>>,
+    pub last_response_status: Option<i32>,
+    pub last_error: Option<String>,
+}
+
+impl WebhookDelivery {
+    /// Creates a pending delivery of `event` to a receiver, which is due to be
+    /// attempted right away
+    pub fn new(event: &WebhookEvent, receiver_id: Uuid) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            event_id: event.id,
+            receiver_id,
+            time_created: event.time_created,
+            state: WebhookDeliveryState::Pending,
+            attempts: SqlU32::new(0),
+            time_next_attempt: Some(event.time_created),
+            time_last_attempt: None,
+            last_response_status: None,
+            last_error: None,
+        }
+    }
+}
+
+impl From<(WebhookDelivery, WebhookEvent)> for views::WebhookDelivery {
+    fn from((delivery, event): (WebhookDelivery, WebhookEvent)) -> Self {
+        Self {
+            id: delivery.id,
+            event_id: event.id,
+            event_class: event.event_class.into(),
+            time_created: event.time_created,
+            state: delivery.state.into(),
+            attempts: *delivery.attempts,
+            time_next_attempt: delivery.time_next_attempt,
+            time_last_attempt: delivery.time_last_attempt,
+            last_response_status: delivery
+                .last_response_status
+                .and_then(|status| u16::try_from(status).ok()),
+            last_error: delivery.last_error,
+        }
+    }
+}
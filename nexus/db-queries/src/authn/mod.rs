@@ -25,6 +25,7 @@
 //! authentication, but they'd all produce the same [`Context`] struct.
 
 pub mod external;
+pub mod oidc;
 pub mod saga;
 pub mod silos;
 
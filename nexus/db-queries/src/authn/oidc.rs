@@ -0,0 +1,611 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! OpenID Connect identity providers
+//!
+//! Nexus acts as an OpenID Connect "relying party" using the authorization
+//! code flow (see OpenID Connect Core 1.0, Section 3.1): the user is sent to
+//! the provider's authorization endpoint, the provider sends the user back to
+//! us with a one-time code, and we exchange that code (along with our client
+//! credentials) for an ID token describing the user.
+//!
+//! The provider's endpoints and signing keys are read from its discovery
+//! document when the identity provider is created and stored in the database
+//! from then on, the same way we handle SAML IdP metadata.
+
+use super::silos::AuthenticatedSubject;
+use crate::db::model;
+use anyhow::{anyhow, bail, Context, Result};
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use dropshot::HttpError;
+use serde::{Deserialize, Serialize};
+
+/// The parts of an OpenID Provider's discovery document that we use (see
+/// OpenID Connect Discovery 1.0, Section 3)
+#[derive(Debug, Deserialize)]
+pub struct DiscoveryDocument {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub jwks_uri: String,
+}
+
+/// A JSON Web Key Set (see RFC 7517, Section 5)
+#[derive(Debug, Deserialize, Serialize)]
+pub struct JsonWebKeySet {
+    pub keys: Vec<JsonWebKey>,
+}
+
+/// A JSON Web Key (see RFC 7517, Section 4)
+///
+/// Only the parameters for RSA and elliptic curve public keys (see RFC 7518,
+/// Section 6) are kept.  Anything else in the document is dropped so that it's
+/// never stored.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct JsonWebKey {
+    pub kty: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kid: Option<String>,
+    #[serde(rename = "use", default, skip_serializing_if = "Option::is_none")]
+    pub key_use: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alg: Option<String>,
+
+    // RSA public key parameters
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub n: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub e: Option<String>,
+
+    // Elliptic curve public key parameters
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub crv: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub x: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub y: Option<String>,
+}
+
+fn base64url_decode(value: &str) -> Result<Vec<u8>> {
+    Ok(base64::engine::general_purpose::URL_SAFE_NO_PAD
+        .decode(value.trim_end_matches('='))?)
+}
+
+impl JsonWebKeySet {
+    /// Returns only the keys that we could use to verify an ID token
+    fn signing_keys(&self) -> impl Iterator<Item = &JsonWebKey> {
+        self.keys.iter().filter(|key| key.is_signing_key())
+    }
+
+    fn verify(
+        &self,
+        header: &IdTokenHeader,
+        message: &[u8],
+        signature: &[u8],
+    ) -> Result<()> {
+        let mut candidates = self
+            .signing_keys()
+            .filter(|key| match (&header.kid, &key.kid) {
+                (Some(token_kid), Some(key_kid)) => token_kid == key_kid,
+                _ => true,
+            })
+            .peekable();
+        if candidates.peek().is_none() {
+            bail!("no signing key matches the ID token's key id");
+        }
+
+        for key in candidates {
+            if key.verify(&header.alg, message, signature).is_ok() {
+                return Ok(());
+            }
+        }
+
+        bail!("ID token signature could not be verified")
+    }
+}
+
+impl JsonWebKey {
+    fn is_signing_key(&self) -> bool {
+        let usable_for_signing = match &self.key_use {
+            Some(key_use) => key_use == "sig",
+            None => true,
+        };
+        let supported = match self.kty.as_str() {
+            "RSA" => self.n.is_some() && self.e.is_some(),
+            "EC" => {
+                self.crv.as_deref() == Some("P-256")
+                    && self.x.is_some()
+                    && self.y.is_some()
+            }
+            _ => false,
+        };
+        usable_for_signing && supported
+    }
+
+    fn verify(
+        &self,
+        alg: &str,
+        message: &[u8],
+        signature: &[u8],
+    ) -> Result<()> {
+        if let Some(key_alg) = &self.alg {
+            if key_alg != alg {
+                bail!("key is for algorithm {}, not {}", key_alg, alg);
+            }
+        }
+
+        // Only asymmetric algorithms are accepted.  In particular, "none"
+        // (an unsigned token) and the HMAC algorithms (which would use the
+        // client secret as the key) are rejected here.
+        match (alg, self.kty.as_str()) {
+            ("RS256", "RSA") => {
+                let n = base64url_decode(self.n.as_deref().unwrap_or(""))?;
+                let e = base64url_decode(self.e.as_deref().unwrap_or(""))?;
+                ring::signature::RsaPublicKeyComponents { n, e }
+                    .verify(
+                        &ring::signature::RSA_PKCS1_2048_8192_SHA256,
+                        message,
+                        signature,
+                    )
+                    .map_err(|_| anyhow!("bad RS256 signature"))
+            }
+            ("ES256", "EC") => {
+                let x = base64url_decode(self.x.as_deref().unwrap_or(""))?;
+                let y = base64url_decode(self.y.as_deref().unwrap_or(""))?;
+                // ring wants the uncompressed form of the point.
+                let mut point = vec![0x04];
+                point.extend_from_slice(&x);
+                point.extend_from_slice(&y);
+                ring::signature::UnparsedPublicKey::new(
+                    &ring::signature::ECDSA_P256_SHA256_FIXED,
+                    point,
+                )
+                .verify(message, signature)
+                .map_err(|_| anyhow!("bad ES256 signature"))
+            }
+            _ => bail!("unsupported signature algorithm {}", alg),
+        }
+    }
+}
+
+/// The JOSE header of an ID token (see RFC 7515, Section 4)
+#[derive(Debug, Deserialize)]
+struct IdTokenHeader {
+    alg: String,
+    kid: Option<String>,
+}
+
+/// The "aud" claim, which may be a single string or an array of them
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Audience {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Audience {
+    fn contains(&self, client_id: &str) -> bool {
+        match self {
+            Audience::One(aud) => aud == client_id,
+            Audience::Many(auds) => auds.iter().any(|aud| aud == client_id),
+        }
+    }
+
+    fn is_many(&self) -> bool {
+        matches!(self, Audience::Many(auds) if auds.len() > 1)
+    }
+}
+
+/// The claims of an ID token (see OpenID Connect Core 1.0, Section 2)
+#[derive(Debug, Deserialize)]
+pub struct IdTokenClaims {
+    iss: String,
+    sub: String,
+    aud: Audience,
+    exp: i64,
+    azp: Option<String>,
+    nonce: Option<String>,
+
+    #[serde(flatten)]
+    other: serde_json::Map<String, serde_json::Value>,
+}
+
+/// The parts of a successful token endpoint response that we use (see OpenID
+/// Connect Core 1.0, Section 3.1.3.3)
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    id_token: String,
+}
+
+pub struct OidcIdentityProvider {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub jwks: JsonWebKeySet,
+    pub client_id: String,
+    pub client_secret: String,
+    pub redirect_uri: String,
+    pub scopes: Vec<String>,
+    pub group_claim_name: Option<String>,
+}
+
+impl TryFrom<model::OidcIdentityProvider> for OidcIdentityProvider {
+    type Error = anyhow::Error;
+    fn try_from(
+        model: model::OidcIdentityProvider,
+    ) -> Result<Self, Self::Error> {
+        let jwks: JsonWebKeySet = serde_json::from_str(&model.jwks)
+            .context("parsing JSON Web Key Set")?;
+
+        // Do not accept IdPs that have no keys we can verify tokens with!
+        if jwks.signing_keys().next().is_none() {
+            bail!("no supported signing key found in JSON Web Key Set");
+        }
+
+        let scopes = model.scopes();
+        Ok(OidcIdentityProvider {
+            issuer: model.issuer,
+            authorization_endpoint: model.authorization_endpoint,
+            token_endpoint: model.token_endpoint,
+            jwks,
+            client_id: model.client_id,
+            client_secret: model.client_secret,
+            redirect_uri: model.redirect_uri,
+            scopes,
+            group_claim_name: model.group_claim_name,
+        })
+    }
+}
+
+impl OidcIdentityProvider {
+    /// Returns the URL at the provider's authorization endpoint where a user
+    /// should be sent to log in
+    ///
+    /// `state` is returned to us unchanged along with the authorization code,
+    /// while `nonce` is embedded in the resulting ID token.
+    pub fn sign_in_url(&self, state: &str, nonce: &str) -> Result<String> {
+        let scope = std::iter::once("openid")
+            .chain(
+                self.scopes
+                    .iter()
+                    .map(|s| s.as_str())
+                    .filter(|s| *s != "openid"),
+            )
+            .collect::<Vec<_>>()
+            .join(" ");
+        let query = serde_urlencoded::to_string([
+            ("response_type", "code"),
+            ("client_id", &self.client_id),
+            ("redirect_uri", &self.redirect_uri),
+            ("scope", &scope),
+            ("state", state),
+            ("nonce", nonce),
+        ])?;
+        let separator =
+            if self.authorization_endpoint.contains('?') { '&' } else { '?' };
+        Ok(format!("{}{}{}", self.authorization_endpoint, separator, query))
+    }
+
+    /// Exchanges an authorization code for an ID token, verifies it, and
+    /// returns the user that it describes
+    pub async fn authenticated_subject(
+        &self,
+        code: &str,
+        nonce: &str,
+    ) -> Result<AuthenticatedSubject, HttpError> {
+        let id_token = self.exchange_code(code).await?;
+        let claims = self
+            .verify_id_token(&id_token, nonce, Utc::now())
+            .map_err(|e| {
+                HttpError::for_bad_request(
+                    None,
+                    format!("invalid ID token: {:#}", e),
+                )
+            })?;
+        Ok(self.subject_from_claims(claims))
+    }
+
+    async fn exchange_code(&self, code: &str) -> Result<String, HttpError> {
+        let dur = std::time::Duration::from_secs(5);
+        let client = reqwest::ClientBuilder::new()
+            .connect_timeout(dur)
+            .timeout(dur)
+            .build()
+            .map_err(|e| {
+                HttpError::for_internal_error(format!(
+                    "failed to build reqwest client: {}",
+                    e
+                ))
+            })?;
+
+        let response = client
+            .post(&self.token_endpoint)
+            .form(&[
+                ("grant_type", "authorization_code"),
+                ("code", code),
+                ("redirect_uri", &self.redirect_uri),
+                ("client_id", &self.client_id),
+                ("client_secret", &self.client_secret),
+            ])
+            .send()
+            .await
+            .map_err(|e| {
+                HttpError::for_unavail(
+                    None,
+                    format!("error querying token endpoint: {}", e),
+                )
+            })?;
+
+        if !response.status().is_success() {
+            return Err(HttpError::for_bad_request(
+                None,
+                format!(
+                    "token endpoint rejected authorization code: {}",
+                    response.status()
+                ),
+            ));
+        }
+
+        let token_response: TokenResponse =
+            response.json().await.map_err(|e| {
+                HttpError::for_bad_request(
+                    None,
+                    format!("error parsing token endpoint response: {}", e),
+                )
+            })?;
+        Ok(token_response.id_token)
+    }
+
+    /// Checks the signature and claims of an ID token (see OpenID Connect Core
+    /// 1.0, Section 3.1.3.7)
+    pub fn verify_id_token(
+        &self,
+        id_token: &str,
+        nonce: &str,
+        now: DateTime<Utc>,
+    ) -> Result<IdTokenClaims> {
+        let mut parts = id_token.split('.');
+        let (Some(encoded_header), Some(payload), Some(signature), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            bail!("ID token is not a JSON Web Signature");
+        };
+
+        let header: IdTokenHeader =
+            serde_json::from_slice(&base64url_decode(encoded_header)?)
+                .context("parsing ID token header")?;
+        let signature = base64url_decode(signature)?;
+        // The signature covers the encoded "<header>.<payload>".
+        let signed_part = &id_token[..encoded_header.len() + 1 + payload.len()];
+        self.jwks.verify(&header, signed_part.as_bytes(), &signature)?;
+
+        // Only look at the claims once we know they came from the provider.
+        let claims: IdTokenClaims =
+            serde_json::from_slice(&base64url_decode(payload)?)
+                .context("parsing ID token claims")?;
+
+        if claims.iss != self.issuer {
+            bail!(
+                "issuer {:?} does not match configured issuer {:?}",
+                claims.iss,
+                self.issuer
+            );
+        }
+        if !claims.aud.contains(&self.client_id) {
+            bail!("ID token was not issued for this client");
+        }
+        if claims.aud.is_many()
+            && claims.azp.as_deref() != Some(self.client_id.as_str())
+        {
+            bail!("ID token was not authorized for this client");
+        }
+        if claims.exp <= now.timestamp() {
+            bail!("ID token has expired");
+        }
+        if claims.nonce.as_deref() != Some(nonce) {
+            bail!("ID token nonce does not match");
+        }
+
+        Ok(claims)
+    }
+
+    fn subject_from_claims(
+        &self,
+        claims: IdTokenClaims,
+    ) -> AuthenticatedSubject {
+        // Extract group membership claims.  Providers vary in whether they
+        // send an array of group names or a single comma separated string, so
+        // accept both.
+        let mut groups = vec![];
+
+        if let Some(group_claim_name) = &self.group_claim_name {
+            let values = match claims.other.get(group_claim_name) {
+                Some(serde_json::Value::Array(values)) => {
+                    values.iter().filter_map(|v| v.as_str()).collect::<Vec<_>>()
+                }
+                Some(serde_json::Value::String(value)) => vec![value.as_str()],
+                _ => vec![],
+            };
+
+            for value in values {
+                for group in value.split(',') {
+                    let group = group.trim();
+                    if !group.is_empty() {
+                        groups.push(group.to_string());
+                    }
+                }
+            }
+        }
+
+        AuthenticatedSubject { external_id: claims.sub, groups }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ring::rand::SystemRandom;
+    use ring::signature::{EcdsaKeyPair, KeyPair};
+
+    const ISSUER: &str = "https://idp.example.com";
+    const CLIENT_ID: &str = "nexus";
+    const NONCE: &str = "abc123";
+
+    fn encode(bytes: &[u8]) -> String {
+        base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(bytes)
+    }
+
+    /// Returns a key pair and the JSON Web Key for its public half
+    fn ec_key(kid: &str) -> (EcdsaKeyPair, JsonWebKey) {
+        let alg = &ring::signature::ECDSA_P256_SHA256_FIXED_SIGNING;
+        let pkcs8 =
+            EcdsaKeyPair::generate_pkcs8(alg, &SystemRandom::new()).unwrap();
+        let key_pair = EcdsaKeyPair::from_pkcs8(alg, pkcs8.as_ref()).unwrap();
+        let point = key_pair.public_key().as_ref();
+        let jwk = JsonWebKey {
+            kty: String::from("EC"),
+            kid: Some(String::from(kid)),
+            key_use: Some(String::from("sig")),
+            alg: Some(String::from("ES256")),
+            n: None,
+            e: None,
+            crv: Some(String::from("P-256")),
+            x: Some(encode(&point[1..33])),
+            y: Some(encode(&point[33..])),
+        };
+        (key_pair, jwk)
+    }
+
+    fn sign(
+        key_pair: &EcdsaKeyPair,
+        header: serde_json::Value,
+        claims: serde_json::Value,
+    ) -> String {
+        let signed = format!(
+            "{}.{}",
+            encode(header.to_string().as_bytes()),
+            encode(claims.to_string().as_bytes())
+        );
+        let signature =
+            key_pair.sign(&SystemRandom::new(), signed.as_bytes()).unwrap();
+        format!("{}.{}", signed, encode(signature.as_ref()))
+    }
+
+    fn provider(keys: Vec<JsonWebKey>) -> OidcIdentityProvider {
+        OidcIdentityProvider {
+            issuer: String::from(ISSUER),
+            authorization_endpoint: format!("{}/authorize", ISSUER),
+            token_endpoint: format!("{}/token", ISSUER),
+            jwks: JsonWebKeySet { keys },
+            client_id: String::from(CLIENT_ID),
+            client_secret: String::from("secret"),
+            redirect_uri: String::from("https://nexus/callback"),
+            scopes: vec![String::from("groups")],
+            group_claim_name: Some(String::from("groups")),
+        }
+    }
+
+    fn claims(now: DateTime<Utc>) -> serde_json::Value {
+        serde_json::json!({
+            "iss": ISSUER,
+            "sub": "user-1",
+            "aud": CLIENT_ID,
+            "exp": now.timestamp() + 60,
+            "iat": now.timestamp(),
+            "nonce": NONCE,
+            "groups": ["admins", "ops, dev"],
+        })
+    }
+
+    #[test]
+    fn test_verify_id_token() {
+        let now = Utc::now();
+        let (key_pair, jwk) = ec_key("key-1");
+        let (other_key_pair, other_jwk) = ec_key("key-2");
+        let provider = provider(vec![jwk, other_jwk]);
+        let header = serde_json::json!({ "alg": "ES256", "kid": "key-1" });
+
+        let token = sign(&key_pair, header.clone(), claims(now));
+        let claims_ok = provider.verify_id_token(&token, NONCE, now).unwrap();
+        let subject = provider.subject_from_claims(claims_ok);
+        assert_eq!(subject.external_id, "user-1");
+        assert_eq!(subject.groups, vec!["admins", "ops", "dev"]);
+
+        // A token signed by a different key than the one it names is rejected.
+        let token = sign(&other_key_pair, header.clone(), claims(now));
+        assert!(provider.verify_id_token(&token, NONCE, now).is_err());
+
+        // So is a token whose claims were modified after signing.
+        let token = sign(&key_pair, header.clone(), claims(now));
+        let mut parts: Vec<_> = token.split('.').map(String::from).collect();
+        let mut tampered = claims(now);
+        tampered["sub"] = serde_json::json!("user-2");
+        parts[1] = encode(tampered.to_string().as_bytes());
+        assert!(provider
+            .verify_id_token(&parts.join("."), NONCE, now)
+            .is_err());
+
+        // Unsigned tokens are rejected.
+        let unsigned = format!(
+            "{}.{}.",
+            encode(br#"{"alg":"none"}"#),
+            encode(claims(now).to_string().as_bytes())
+        );
+        assert!(provider.verify_id_token(&unsigned, NONCE, now).is_err());
+
+        // Each of the claims we check must be correct.
+        for (claim, value) in [
+            ("iss", serde_json::json!("https://evil.example.com")),
+            ("aud", serde_json::json!("somebody-else")),
+            ("aud", serde_json::json!([CLIENT_ID, "somebody-else"])),
+            ("exp", serde_json::json!(now.timestamp() - 1)),
+            ("nonce", serde_json::json!("replayed")),
+        ] {
+            let mut bad_claims = claims(now);
+            bad_claims[claim] = value;
+            let token = sign(&key_pair, header.clone(), bad_claims);
+            assert!(
+                provider.verify_id_token(&token, NONCE, now).is_err(),
+                "accepted token with bad {:?} claim",
+                claim
+            );
+        }
+
+        // Multiple audiences are fine if we're the authorized party.
+        let mut multi_claims = claims(now);
+        multi_claims["aud"] = serde_json::json!([CLIENT_ID, "somebody-else"]);
+        multi_claims["azp"] = serde_json::json!(CLIENT_ID);
+        let token = sign(&key_pair, header, multi_claims);
+        assert!(provider.verify_id_token(&token, NONCE, now).is_ok());
+    }
+
+    #[test]
+    fn test_sign_in_url() {
+        let (_, jwk) = ec_key("key-1");
+        let url = provider(vec![jwk]).sign_in_url("st ate", NONCE).unwrap();
+        assert_eq!(
+            url,
+            "https://idp.example.com/authorize?response_type=code\
+             &client_id=nexus&redirect_uri=https%3A%2F%2Fnexus%2Fcallback\
+             &scope=openid+groups&state=st+ate&nonce=abc123"
+        );
+    }
+
+    #[test]
+    fn test_jwks_keeps_only_public_parameters() {
+        let jwks: JsonWebKeySet = serde_json::from_value(serde_json::json!({
+            "keys": [
+                { "kty": "oct", "k": "c2VjcmV0" },
+                { "kty": "RSA", "use": "enc", "n": "AQAB", "e": "AQAB" },
+                { "kty": "RSA", "n": "AQAB", "e": "AQAB", "d": "private" },
+            ]
+        }))
+        .unwrap();
+        assert_eq!(jwks.signing_keys().count(), 1);
+        let stored = serde_json::to_string(&jwks).unwrap();
+        assert!(!stored.contains("private"));
+        assert!(!stored.contains("c2VjcmV0"));
+    }
+}
@@ -4,6 +4,7 @@
 
 //! Silo related authentication types and functions
 
+use super::oidc::OidcIdentityProvider;
 use crate::authz;
 use crate::context::OpContext;
 use crate::db::lookup::LookupPath;
@@ -66,6 +67,7 @@ impl TryFrom<model::SamlIdentityProvider> for SamlIdentityProvider {
 
 pub enum IdentityProviderType {
     Saml(SamlIdentityProvider),
+    Oidc(OidcIdentityProvider),
 }
 
 impl IdentityProviderType {
@@ -115,6 +117,32 @@ impl IdentityProviderType {
 
                 Ok((authz_silo, db_silo, saml_identity_provider))
             }
+
+            model::IdentityProviderType::Oidc => {
+                let (.., oidc_identity_provider) =
+                    LookupPath::new(opctx, datastore)
+                        .silo_name(silo_name)
+                        .oidc_identity_provider_name(provider_name)
+                        .fetch()
+                        .await?;
+
+                let oidc_identity_provider = IdentityProviderType::Oidc(
+                    oidc_identity_provider.try_into().map_err(
+                        |e: anyhow::Error| {
+                            // As above, this was validated before it went
+                            // into the DB, so this is a server error.
+                            omicron_common::api::external::Error::internal_error(
+                                &format!(
+                                    "oidc_identity_provider.try_into() failed! {}",
+                                    &e.to_string()
+                                ),
+                            )
+                        },
+                    )?,
+                );
+
+                Ok((authz_silo, db_silo, oidc_identity_provider))
+            }
         }
     }
 }
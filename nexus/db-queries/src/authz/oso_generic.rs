@@ -103,6 +103,7 @@ pub fn make_omicron_oso(log: &slog::Logger) -> Result<OsoInit, anyhow::Error> {
         Action::get_polar_class(),
         AnyActor::get_polar_class(),
         AuthenticatedActor::get_polar_class(),
+        AuditLog::get_polar_class(),
         Database::get_polar_class(),
         DnsConfig::get_polar_class(),
         Fleet::get_polar_class(),
@@ -122,17 +123,22 @@ pub fn make_omicron_oso(log: &slog::Logger) -> Result<OsoInit, anyhow::Error> {
         Project::init(),
         Disk::init(),
         Snapshot::init(),
+        SnapshotSchedule::init(),
         ProjectImage::init(),
         Instance::init(),
+        FloatingIp::init(),
+        AffinityGroup::init(),
         IpPool::init(),
         InstanceNetworkInterface::init(),
         Vpc::init(),
         VpcRouter::init(),
         RouterRoute::init(),
         VpcSubnet::init(),
+        VpcPeering::init(),
         // Silo-level resources
         Image::init(),
         SiloImage::init(),
+        WebhookReceiver::init(),
         // Fleet-level resources
         AddressLot::init(),
         LoopbackAddress::init(),
@@ -149,6 +155,7 @@ pub fn make_omicron_oso(log: &slog::Logger) -> Result<OsoInit, anyhow::Error> {
         SiloGroup::init(),
         IdentityProvider::init(),
         SamlIdentityProvider::init(),
+        OidcIdentityProvider::init(),
         Sled::init(),
         Service::init(),
         UpdateArtifact::init(),
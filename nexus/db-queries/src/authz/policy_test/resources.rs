@@ -68,6 +68,7 @@ pub async fn make_resources(
     builder.new_resource(authz::DNS_CONFIG);
     builder.new_resource(authz::DEVICE_AUTH_REQUEST_LIST);
     builder.new_resource(authz::IP_POOL_LIST);
+    builder.new_resource(authz::AUDIT_LOG);
 
     // Silo/organization/project hierarchy
     make_silo(&mut builder, "silo1", main_silo_id, true).await;
@@ -202,6 +203,11 @@ async fn make_silo(
         idp_id,
         LookupType::ByName(format!("{}-saml-identity-provider", silo_name)),
     ));
+    builder.new_resource(authz::OidcIdentityProvider::new(
+        silo.clone(),
+        idp_id,
+        LookupType::ByName(format!("{}-oidc-identity-provider", silo_name)),
+    ));
 
     builder.new_resource(authz::SiloUserList::new(silo.clone()));
     let silo_user_id = Uuid::new_v4();
@@ -239,6 +245,12 @@ async fn make_silo(
         LookupType::ByName(format!("{}-image", silo_name)),
     ));
 
+    builder.new_resource(authz::WebhookReceiver::new(
+        silo.clone(),
+        Uuid::new_v4(),
+        LookupType::ByName(format!("{}-webhook-receiver", silo_name)),
+    ));
+
     let nprojects = if first_branch { 2 } else { 1 };
     for i in 0..nprojects {
         let project_name = format!("{}-proj{}", silo_name, i + 1);
@@ -294,10 +306,15 @@ async fn make_project(
     builder.new_resource(vpc1.clone());
     // Test a resource nested two levels below Project
     builder.new_resource(authz::VpcSubnet::new(
-        vpc1,
+        vpc1.clone(),
         Uuid::new_v4(),
         LookupType::ByName(format!("{}-subnet1", vpc1_name)),
     ));
+    builder.new_resource(authz::VpcPeering::new(
+        vpc1,
+        Uuid::new_v4(),
+        LookupType::ByName(format!("{}-peering1", vpc1_name)),
+    ));
 
     builder.new_resource(authz::Snapshot::new(
         project.clone(),
@@ -311,6 +328,24 @@ async fn make_project(
         Uuid::new_v4(),
         LookupType::ByName(image_name),
     ));
+
+    builder.new_resource(authz::FloatingIp::new(
+        project.clone(),
+        Uuid::new_v4(),
+        LookupType::ByName(format!("{}-fip1", project_name)),
+    ));
+
+    builder.new_resource(authz::AffinityGroup::new(
+        project.clone(),
+        Uuid::new_v4(),
+        LookupType::ByName(format!("{}-affinity-group1", project_name)),
+    ));
+
+    builder.new_resource(authz::SnapshotSchedule::new(
+        project.clone(),
+        Uuid::new_v4(),
+        LookupType::ByName(format!("{}-snapshot-schedule1", project_name)),
+    ));
 }
 
 /// Returns the set of authz classes exempted from the coverage test
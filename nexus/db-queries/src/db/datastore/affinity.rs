@@ -0,0 +1,452 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! [`DataStore`] methods on [`AffinityGroup`]s.
+
+use super::DataStore;
+use crate::authz;
+use crate::authz::ApiResource;
+use crate::context::OpContext;
+use crate::db;
+use crate::db::error::public_error_from_diesel_pool;
+use crate::db::error::ErrorHandler;
+use crate::db::error::TransactionError;
+use crate::db::identity::Resource;
+use crate::db::model::AffinityGroup;
+use crate::db::model::AffinityGroupInstanceMembership;
+use crate::db::model::AffinityGroupKind;
+use crate::db::model::AffinityPolicy;
+use crate::db::model::Instance;
+use crate::db::model::InstanceState;
+use crate::db::model::Name;
+use crate::db::model::SledReservationConstraintBuilder;
+use crate::db::pagination::paginated;
+use crate::db::pool::DbConnection;
+use async_bb8_diesel::AsyncConnection;
+use async_bb8_diesel::AsyncRunQueryDsl;
+use async_bb8_diesel::PoolError;
+use chrono::Utc;
+use diesel::prelude::*;
+use omicron_common::api::external::http_pagination::PaginatedBy;
+use omicron_common::api::external::CreateResult;
+use omicron_common::api::external::DeleteResult;
+use omicron_common::api::external::Error;
+use omicron_common::api::external::InstanceState as ApiInstanceState;
+use omicron_common::api::external::ListResultVec;
+use omicron_common::api::external::ResourceType;
+use ref_cast::RefCast;
+use std::collections::BTreeSet;
+use uuid::Uuid;
+
+/// Returns the states in which an instance is on its sled, or about to be
+///
+/// Only instances in these states count towards the placement of their
+/// affinity groups.  A stopped or failed instance doesn't hold its sled, and
+/// its groups' hard policies are checked again when it's started.
+fn instance_states_on_sled() -> Vec<InstanceState> {
+    vec![
+        InstanceState::new(ApiInstanceState::Creating),
+        InstanceState::new(ApiInstanceState::Starting),
+        InstanceState::new(ApiInstanceState::Running),
+        InstanceState::new(ApiInstanceState::Stopping),
+        InstanceState::new(ApiInstanceState::Rebooting),
+        InstanceState::new(ApiInstanceState::Migrating),
+        InstanceState::new(ApiInstanceState::Repairing),
+    ]
+}
+
+impl DataStore {
+    /// Create an affinity group within a project.
+    pub async fn affinity_group_create(
+        &self,
+        opctx: &OpContext,
+        authz_project: &authz::Project,
+        group: AffinityGroup,
+    ) -> CreateResult<AffinityGroup> {
+        assert_eq!(authz_project.id(), group.project_id);
+        opctx.authorize(authz::Action::CreateChild, authz_project).await?;
+        let name = group.name().to_string();
+
+        use db::schema::affinity_group::dsl;
+        diesel::insert_into(dsl::affinity_group)
+            .values(group)
+            .returning(AffinityGroup::as_returning())
+            .get_result_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel_pool(
+                    e,
+                    ErrorHandler::Conflict(ResourceType::AffinityGroup, &name),
+                )
+            })
+    }
+
+    /// List the affinity groups within a project.
+    pub async fn affinity_group_list(
+        &self,
+        opctx: &OpContext,
+        authz_project: &authz::Project,
+        pagparams: &PaginatedBy<'_>,
+    ) -> ListResultVec<AffinityGroup> {
+        opctx.authorize(authz::Action::ListChildren, authz_project).await?;
+
+        use db::schema::affinity_group::dsl;
+        match pagparams {
+            PaginatedBy::Id(pagparams) => {
+                paginated(dsl::affinity_group, dsl::id, &pagparams)
+            }
+            PaginatedBy::Name(pagparams) => paginated(
+                dsl::affinity_group,
+                dsl::name,
+                &pagparams.map_name(|n| Name::ref_cast(n)),
+            ),
+        }
+        .filter(dsl::project_id.eq(authz_project.id()))
+        .filter(dsl::time_deleted.is_null())
+        .select(AffinityGroup::as_select())
+        .load_async(self.pool_authorized(opctx).await?)
+        .await
+        .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+    }
+
+    /// Delete an affinity group, along with the memberships of its instances.
+    pub async fn affinity_group_delete(
+        &self,
+        opctx: &OpContext,
+        authz_group: &authz::AffinityGroup,
+    ) -> DeleteResult {
+        opctx.authorize(authz::Action::Delete, authz_group).await?;
+
+        #[derive(Debug)]
+        enum AffinityGroupDeleteError {
+            NotFound,
+        }
+        type TxnError = TransactionError<AffinityGroupDeleteError>;
+
+        let group_id = authz_group.id();
+        self.pool_authorized(opctx)
+            .await?
+            .transaction_async(|conn| async move {
+                use db::schema::affinity_group::dsl as group_dsl;
+                use db::schema::affinity_group_instance_membership::dsl;
+
+                let updated = diesel::update(group_dsl::affinity_group)
+                    .filter(group_dsl::id.eq(group_id))
+                    .filter(group_dsl::time_deleted.is_null())
+                    .set(group_dsl::time_deleted.eq(Utc::now()))
+                    .execute_async(&conn)
+                    .await?;
+                if updated == 0 {
+                    return Err(TxnError::CustomError(
+                        AffinityGroupDeleteError::NotFound,
+                    ));
+                }
+
+                diesel::delete(dsl::affinity_group_instance_membership)
+                    .filter(dsl::affinity_group_id.eq(group_id))
+                    .execute_async(&conn)
+                    .await?;
+                Ok(())
+            })
+            .await
+            .map_err(|e| match e {
+                TxnError::CustomError(AffinityGroupDeleteError::NotFound) => {
+                    authz_group.not_found()
+                }
+                TxnError::Pool(e) => {
+                    public_error_from_diesel_pool(e, ErrorHandler::Server)
+                }
+            })
+    }
+
+    /// List the instances which are members of an affinity group.
+    pub async fn affinity_group_member_list(
+        &self,
+        opctx: &OpContext,
+        authz_group: &authz::AffinityGroup,
+        pagparams: &PaginatedBy<'_>,
+    ) -> ListResultVec<Instance> {
+        opctx.authorize(authz::Action::Read, authz_group).await?;
+
+        use db::schema::affinity_group_instance_membership::dsl as member_dsl;
+        use db::schema::instance::dsl;
+        match pagparams {
+            PaginatedBy::Id(pagparams) => {
+                paginated(dsl::instance, dsl::id, &pagparams)
+            }
+            PaginatedBy::Name(pagparams) => paginated(
+                dsl::instance,
+                dsl::name,
+                &pagparams.map_name(|n| Name::ref_cast(n)),
+            ),
+        }
+        .filter(
+            dsl::id.eq_any(
+                member_dsl::affinity_group_instance_membership
+                    .filter(member_dsl::affinity_group_id.eq(authz_group.id()))
+                    .select(member_dsl::instance_id),
+            ),
+        )
+        .filter(dsl::time_deleted.is_null())
+        .select(Instance::as_select())
+        .load_async(self.pool_authorized(opctx).await?)
+        .await
+        .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+    }
+
+    /// Add an instance to an affinity group.
+    ///
+    /// If the group has a hard policy and the instance is on its sled, that
+    /// sled must satisfy the policy with respect to the group's other members
+    /// that are on theirs. Adding an instance which is already a member
+    /// succeeds without any change.
+    pub async fn affinity_group_member_add(
+        &self,
+        opctx: &OpContext,
+        authz_group: &authz::AffinityGroup,
+        authz_instance: &authz::Instance,
+    ) -> Result<(), Error> {
+        opctx.authorize(authz::Action::Modify, authz_group).await?;
+        opctx.authorize(authz::Action::Modify, authz_instance).await?;
+
+        type TxnError = TransactionError<Error>;
+        let group_id = authz_group.id();
+        let instance_id = authz_instance.id();
+        let instance_not_found = authz_instance.not_found();
+        self.pool_authorized(opctx)
+            .await?
+            .transaction_async(|conn| async move {
+                use db::schema::affinity_group::dsl as group_dsl;
+                use db::schema::affinity_group_instance_membership::dsl;
+                use db::schema::instance::dsl as instance_dsl;
+
+                let group = group_dsl::affinity_group
+                    .filter(group_dsl::id.eq(group_id))
+                    .filter(group_dsl::time_deleted.is_null())
+                    .select(AffinityGroup::as_select())
+                    .get_result_async(&conn)
+                    .await?;
+
+                let (instance_sled, instance_state) = instance_dsl::instance
+                    .filter(instance_dsl::id.eq(instance_id))
+                    .filter(instance_dsl::time_deleted.is_null())
+                    .select((instance_dsl::active_sled_id, instance_dsl::state))
+                    .load_async::<(Uuid, InstanceState)>(&conn)
+                    .await?
+                    .pop()
+                    .ok_or(TxnError::CustomError(instance_not_found))?;
+
+                if group.policy == AffinityPolicy::Hard
+                    && instance_states_on_sled().contains(&instance_state)
+                {
+                    let member_sleds = dsl::affinity_group_instance_membership
+                        .inner_join(
+                            instance_dsl::instance
+                                .on(instance_dsl::id.eq(dsl::instance_id)),
+                        )
+                        .filter(dsl::affinity_group_id.eq(group_id))
+                        .filter(dsl::instance_id.ne(instance_id))
+                        .filter(instance_dsl::time_deleted.is_null())
+                        .filter(
+                            instance_dsl::state
+                                .eq_any(instance_states_on_sled()),
+                        )
+                        .select(instance_dsl::active_sled_id)
+                        .load_async::<Uuid>(&conn)
+                        .await?;
+                    let satisfied = match group.kind {
+                        AffinityGroupKind::Affinity => {
+                            member_sleds.iter().all(|s| *s == instance_sled)
+                        }
+                        AffinityGroupKind::AntiAffinity => {
+                            !member_sleds.contains(&instance_sled)
+                        }
+                    };
+                    if !satisfied {
+                        return Err(TxnError::CustomError(
+                            Error::invalid_request(&format!(
+                                "the placement of instance \"{}\" violates \
+                                the hard policy of affinity group \"{}\"",
+                                instance_id,
+                                group.name(),
+                            )),
+                        ));
+                    }
+                }
+
+                diesel::insert_into(dsl::affinity_group_instance_membership)
+                    .values(AffinityGroupInstanceMembership::new(
+                        group_id,
+                        instance_id,
+                    ))
+                    .on_conflict((dsl::affinity_group_id, dsl::instance_id))
+                    .do_nothing()
+                    .execute_async(&conn)
+                    .await?;
+                Ok(())
+            })
+            .await
+            .map_err(|e| match e {
+                TxnError::CustomError(e) => e,
+                TxnError::Pool(e) => public_error_from_diesel_pool(
+                    e,
+                    ErrorHandler::NotFoundByResource(authz_group),
+                ),
+            })
+    }
+
+    /// Remove an instance from an affinity group.
+    ///
+    /// Removing an instance which is not a member succeeds without any change.
+    pub async fn affinity_group_member_remove(
+        &self,
+        opctx: &OpContext,
+        authz_group: &authz::AffinityGroup,
+        authz_instance: &authz::Instance,
+    ) -> DeleteResult {
+        opctx.authorize(authz::Action::Modify, authz_group).await?;
+
+        use db::schema::affinity_group_instance_membership::dsl;
+        diesel::delete(dsl::affinity_group_instance_membership)
+            .filter(dsl::affinity_group_id.eq(authz_group.id()))
+            .filter(dsl::instance_id.eq(authz_instance.id()))
+            .execute_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel_pool(e, ErrorHandler::Server)
+            })?;
+        Ok(())
+    }
+
+    /// Remove an instance from all of the affinity groups of which it is a
+    /// member, returning the number of memberships removed.
+    pub async fn affinity_group_memberships_delete_by_instance_id(
+        &self,
+        opctx: &OpContext,
+        instance_id: Uuid,
+    ) -> Result<usize, Error> {
+        use db::schema::affinity_group_instance_membership::dsl;
+        diesel::delete(dsl::affinity_group_instance_membership)
+            .filter(dsl::instance_id.eq(instance_id))
+            .execute_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+    }
+
+    /// Returns the constraints that the affinity groups of which the instance
+    /// `instance_id` is a member place on the sled selected for it, given the
+    /// current placement of the groups' other members.
+    pub async fn instance_affinity_constraints(
+        &self,
+        opctx: &OpContext,
+        instance_id: Uuid,
+    ) -> Result<SledReservationConstraintBuilder, Error> {
+        opctx.authorize(authz::Action::Read, &authz::FLEET).await?;
+
+        type TxnError = TransactionError<Error>;
+        self.pool_authorized(opctx)
+            .await?
+            .transaction_async(|conn| async move {
+                self.affinity_constraints_on_connection(&conn, instance_id, &[])
+                    .await
+            })
+            .await
+            .map_err(|e| match e {
+                TxnError::CustomError(e) => e,
+                TxnError::Pool(e) => {
+                    public_error_from_diesel_pool(e, ErrorHandler::Server)
+                }
+            })
+    }
+
+    /// Returns the constraints that the affinity groups of which the instance
+    /// `instance_id` is a member, along with the groups `joining`, place on
+    /// the sled selected for it, given the current placement of the groups'
+    /// other members.
+    ///
+    /// Only members that are on their sleds, or about to be, are counted:
+    /// stopped and failed instances don't hold a sled.
+    ///
+    /// Hard policies become "must" constraints, and soft policies become
+    /// preferences.
+    pub(crate) async fn affinity_constraints_on_connection<ConnErr>(
+        &self,
+        conn: &(impl async_bb8_diesel::AsyncConnection<DbConnection, ConnErr>
+              + Sync),
+        instance_id: Uuid,
+        joining: &[Uuid],
+    ) -> Result<SledReservationConstraintBuilder, TransactionError<Error>>
+    where
+        ConnErr: From<diesel::result::Error> + Send + 'static,
+        PoolError: From<ConnErr>,
+        TransactionError<Error>: From<ConnErr>,
+    {
+        use db::schema::affinity_group::dsl as group_dsl;
+        use db::schema::affinity_group_instance_membership::dsl;
+        use db::schema::instance::dsl as instance_dsl;
+
+        let mut group_ids = dsl::affinity_group_instance_membership
+            .filter(dsl::instance_id.eq(instance_id))
+            .select(dsl::affinity_group_id)
+            .load_async::<Uuid>(conn)
+            .await?;
+        group_ids.extend(joining);
+
+        let mut builder = SledReservationConstraintBuilder::new();
+        if group_ids.is_empty() {
+            return Ok(builder);
+        }
+
+        // Each group is returned once for every member other than this
+        // instance that's on its sled, paired with the ID of that sled.
+        let member_sleds = dsl::affinity_group_instance_membership
+            .inner_join(
+                group_dsl::affinity_group
+                    .on(group_dsl::id.eq(dsl::affinity_group_id)),
+            )
+            .inner_join(
+                instance_dsl::instance
+                    .on(instance_dsl::id.eq(dsl::instance_id)),
+            )
+            .filter(dsl::affinity_group_id.eq_any(group_ids))
+            .filter(dsl::instance_id.ne(instance_id))
+            .filter(group_dsl::time_deleted.is_null())
+            .filter(instance_dsl::time_deleted.is_null())
+            .filter(instance_dsl::state.eq_any(instance_states_on_sled()))
+            .select((AffinityGroup::as_select(), instance_dsl::active_sled_id))
+            .load_async::<(AffinityGroup, Uuid)>(conn)
+            .await?;
+
+        let mut required_sleds = BTreeSet::new();
+        for (group, sled_id) in member_sleds {
+            builder = match (group.kind, group.policy) {
+                (AffinityGroupKind::Affinity, AffinityPolicy::Hard) => {
+                    required_sleds.insert(sled_id);
+                    builder
+                }
+                (AffinityGroupKind::Affinity, AffinityPolicy::Soft) => {
+                    builder.prefer(&[sled_id])
+                }
+                (AffinityGroupKind::AntiAffinity, AffinityPolicy::Hard) => {
+                    builder.must_not_select_from(&[sled_id])
+                }
+                (AffinityGroupKind::AntiAffinity, AffinityPolicy::Soft) => {
+                    builder.avoid(&[sled_id])
+                }
+            };
+        }
+
+        // Every member of a group with a hard affinity policy must be on the
+        // same sled, so there is nowhere to place this instance if those
+        // members are already spread across several sleds.
+        if required_sleds.len() > 1 {
+            return Err(TransactionError::CustomError(Error::invalid_request(
+                "members of the instance's hard affinity groups are placed on \
+                different sleds",
+            )));
+        }
+        let required_sleds = required_sleds.into_iter().collect::<Vec<_>>();
+        Ok(builder.must_select_from(&required_sleds))
+    }
+}
@@ -0,0 +1,252 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! [`DataStore`] methods related to the audit log.
+
+use super::DataStore;
+use crate::authz;
+use crate::context::OpContext;
+use crate::db;
+use crate::db::error::public_error_from_diesel_pool;
+use crate::db::error::ErrorHandler;
+use crate::db::model::AuditLogEntry;
+use crate::db::pagination::paginated_multicolumn;
+use async_bb8_diesel::AsyncRunQueryDsl;
+use chrono::DateTime;
+use chrono::Utc;
+use diesel::prelude::*;
+use nexus_types::external_api::params;
+use omicron_common::api::external::CreateResult;
+use omicron_common::api::external::DataPageParams;
+use omicron_common::api::external::Error;
+use omicron_common::api::external::ListResultVec;
+use std::num::NonZeroU32;
+use uuid::Uuid;
+
+impl DataStore {
+    /// Record an entry in the audit log
+    pub async fn audit_log_entry_insert(
+        &self,
+        opctx: &OpContext,
+        entry: AuditLogEntry,
+    ) -> CreateResult<()> {
+        opctx.authorize(authz::Action::CreateChild, &authz::AUDIT_LOG).await?;
+
+        use db::schema::audit_log_entry::dsl;
+        diesel::insert_into(dsl::audit_log_entry)
+            .values(entry)
+            .execute_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel_pool(e, ErrorHandler::Server)
+            })?;
+        Ok(())
+    }
+
+    /// List entries in the audit log, ordered by the time at which the request
+    /// completed (and then by id)
+    pub async fn audit_log_list(
+        &self,
+        opctx: &OpContext,
+        selector: &params::AuditLogSelector,
+        pagparams: &DataPageParams<'_, (DateTime<Utc>, Uuid)>,
+    ) -> ListResultVec<AuditLogEntry> {
+        opctx.authorize(authz::Action::ListChildren, &authz::AUDIT_LOG).await?;
+
+        use db::schema::audit_log_entry::dsl;
+        let mut query = paginated_multicolumn(
+            dsl::audit_log_entry,
+            (dsl::time_completed, dsl::id),
+            pagparams,
+        );
+        if let Some(start_time) = selector.start_time {
+            query = query.filter(dsl::time_completed.ge(start_time));
+        }
+        if let Some(end_time) = selector.end_time {
+            query = query.filter(dsl::time_completed.lt(end_time));
+        }
+        if let Some(actor_id) = selector.actor_id {
+            query = query.filter(dsl::actor_id.eq(actor_id));
+        }
+        query
+            .select(AuditLogEntry::as_select())
+            .load_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+    }
+
+    /// Delete up to `limit` entries from the audit log for requests that
+    /// completed before `cutoff`
+    ///
+    /// Returns the number of entries deleted.  Callers that want to delete all
+    /// such entries should call this until it returns fewer than `limit`.
+    pub async fn audit_log_prune(
+        &self,
+        opctx: &OpContext,
+        cutoff: DateTime<Utc>,
+        limit: NonZeroU32,
+    ) -> Result<usize, Error> {
+        opctx.authorize(authz::Action::Modify, &authz::AUDIT_LOG).await?;
+
+        use db::schema::audit_log_entry::dsl;
+        let conn = self.pool_authorized(opctx).await?;
+        let ids: Vec<Uuid> = dsl::audit_log_entry
+            .filter(dsl::time_completed.lt(cutoff))
+            .order((dsl::time_completed, dsl::id))
+            .limit(i64::from(limit.get()))
+            .select(dsl::id)
+            .load_async(conn)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel_pool(e, ErrorHandler::Server)
+            })?;
+        if ids.is_empty() {
+            return Ok(0);
+        }
+
+        diesel::delete(dsl::audit_log_entry)
+            .filter(dsl::id.eq_any(ids))
+            .execute_async(conn)
+            .await
+            .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::authn;
+    use crate::db::datastore::datastore_test;
+    use crate::db::model::SqlU16;
+    use chrono::Duration;
+    use dropshot::PaginationOrder;
+    use nexus_test_utils::db::test_setup_database;
+    use omicron_test_utils::dev;
+    use std::sync::Arc;
+
+    fn entry_for_test(
+        actor_id: Uuid,
+        time_completed: DateTime<Utc>,
+    ) -> AuditLogEntry {
+        AuditLogEntry {
+            id: Uuid::new_v4(),
+            time_started: time_completed - Duration::seconds(1),
+            time_completed,
+            request_id: Uuid::new_v4().to_string(),
+            actor_id,
+            actor_silo_id: None,
+            operation_id: String::from("project_create"),
+            http_method: String::from("POST"),
+            resource_path: String::from("/v1/projects"),
+            result_status: SqlU16::new(201),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_audit_log_list_and_prune() {
+        let logctx = dev::test_setup_log("test_audit_log_list_and_prune");
+        let mut db = test_setup_database(&logctx.log).await;
+        let (opctx, datastore) = datastore_test(&logctx, &db).await;
+
+        // Only Nexus's external authenticator may record entries.
+        let entry = entry_for_test(Uuid::new_v4(), Utc::now());
+        let error = datastore
+            .audit_log_entry_insert(&opctx, entry)
+            .await
+            .expect_err("test user should not be able to write the audit log");
+        assert!(matches!(error, Error::Forbidden));
+        let opctx_authn = OpContext::for_background(
+            logctx.log.new(o!()),
+            Arc::new(authz::Authz::new(&logctx.log)),
+            authn::Context::external_authn(),
+            Arc::clone(&datastore),
+        );
+
+        // Record a day's worth of entries from two different users.
+        let actor1 = Uuid::new_v4();
+        let actor2 = Uuid::new_v4();
+        let start = Utc::now() - Duration::days(1);
+        let mut entries = Vec::new();
+        for hour in 0..24 {
+            let actor = if hour % 2 == 0 { actor1 } else { actor2 };
+            let entry = entry_for_test(actor, start + Duration::hours(hour));
+            datastore
+                .audit_log_entry_insert(&opctx_authn, entry.clone())
+                .await
+                .unwrap();
+            entries.push(entry);
+        }
+
+        // Page through the whole log and make sure we see everything in order.
+        let selector = params::AuditLogSelector::default();
+        let mut marker = None;
+        let mut found = Vec::new();
+        loop {
+            let pagparams = DataPageParams {
+                marker: marker.as_ref(),
+                direction: PaginationOrder::Ascending,
+                limit: NonZeroU32::new(5).unwrap(),
+            };
+            let page = datastore
+                .audit_log_list(&opctx, &selector, &pagparams)
+                .await
+                .unwrap();
+            let Some(last) = page.last() else { break };
+            marker = Some((last.time_completed, last.id));
+            found.extend(page.into_iter().map(|e| e.id));
+        }
+        let expected: Vec<_> = entries.iter().map(|e| e.id).collect();
+        assert_eq!(found, expected);
+
+        // Filter by time range and by actor.
+        let pagparams = DataPageParams {
+            marker: None,
+            direction: PaginationOrder::Ascending,
+            limit: NonZeroU32::new(100).unwrap(),
+        };
+        let selector = params::AuditLogSelector {
+            start_time: Some(entries[4].time_completed - Duration::minutes(1)),
+            end_time: Some(entries[10].time_completed - Duration::minutes(1)),
+            actor_id: Some(actor2),
+        };
+        let found: Vec<_> = datastore
+            .audit_log_list(&opctx, &selector, &pagparams)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|e| e.id)
+            .collect();
+        assert_eq!(found, vec![entries[5].id, entries[7].id, entries[9].id]);
+
+        // Prune everything older than the thirteenth entry, a few at a time.
+        let cutoff = entries[12].time_completed - Duration::minutes(1);
+        let limit = NonZeroU32::new(5).unwrap();
+        let mut ndeleted = Vec::new();
+        loop {
+            let n =
+                datastore.audit_log_prune(&opctx, cutoff, limit).await.unwrap();
+            ndeleted.push(n);
+            if n < 5 {
+                break;
+            }
+        }
+        assert_eq!(ndeleted, vec![5, 5, 2]);
+        let found: Vec<_> = datastore
+            .audit_log_list(
+                &opctx,
+                &params::AuditLogSelector::default(),
+                &pagparams,
+            )
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|e| e.id)
+            .collect();
+        let expected: Vec<_> = entries[12..].iter().map(|e| e.id).collect();
+        assert_eq!(found, expected);
+
+        db.cleanup().await.unwrap();
+        logctx.cleanup_successful();
+    }
+}
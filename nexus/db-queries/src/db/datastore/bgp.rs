@@ -0,0 +1,428 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use super::DataStore;
+use crate::context::OpContext;
+use crate::db;
+use crate::db::error::public_error_from_diesel_pool;
+use crate::db::error::ErrorHandler;
+use crate::db::error::TransactionError;
+use crate::db::model::Name;
+use crate::db::model::{BgpAnnounceSet, BgpAnnouncement, BgpConfig};
+use crate::db::pagination::paginated;
+use async_bb8_diesel::{
+    AsyncConnection, AsyncRunQueryDsl, ConnectionError, PoolError,
+};
+use chrono::Utc;
+use diesel::result::Error as DieselError;
+use diesel::{ExpressionMethods, QueryDsl, SelectableHelper};
+use ipnetwork::IpNetwork;
+use nexus_types::external_api::params;
+use nexus_types::identity::Resource;
+use omicron_common::api::external::http_pagination::PaginatedBy;
+use omicron_common::api::external::{
+    CreateResult, DeleteResult, Error, ListResultVec, LookupResult, NameOrId,
+    ResourceType,
+};
+use ref_cast::RefCast;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct BgpAnnounceSetCreateResult {
+    pub announce_set: BgpAnnounceSet,
+    pub announcements: Vec<BgpAnnouncement>,
+}
+
+impl DataStore {
+    // bgp configs
+
+    pub async fn bgp_config_create(
+        &self,
+        opctx: &OpContext,
+        params: &params::CreateBgpConfig,
+    ) -> CreateResult<BgpConfig> {
+        use db::schema::bgp_config::dsl;
+
+        diesel::insert_into(dsl::bgp_config)
+            .values(BgpConfig::new(params))
+            .returning(BgpConfig::as_returning())
+            .get_result_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel_pool(
+                    e,
+                    ErrorHandler::Conflict(
+                        ResourceType::BgpConfig,
+                        params.identity.name.as_str(),
+                    ),
+                )
+            })
+    }
+
+    pub async fn bgp_config_list(
+        &self,
+        opctx: &OpContext,
+        pagparams: &PaginatedBy<'_>,
+    ) -> ListResultVec<BgpConfig> {
+        use db::schema::bgp_config::dsl;
+
+        match pagparams {
+            PaginatedBy::Id(pagparams) => {
+                paginated(dsl::bgp_config, dsl::id, &pagparams)
+            }
+            PaginatedBy::Name(pagparams) => paginated(
+                dsl::bgp_config,
+                dsl::name,
+                &pagparams.map_name(|n| Name::ref_cast(n)),
+            ),
+        }
+        .filter(dsl::time_deleted.is_null())
+        .select(BgpConfig::as_select())
+        .load_async(self.pool_authorized(opctx).await?)
+        .await
+        .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+    }
+
+    pub async fn bgp_config_get(
+        &self,
+        opctx: &OpContext,
+        name_or_id: &NameOrId,
+    ) -> LookupResult<BgpConfig> {
+        use db::schema::bgp_config::dsl;
+
+        let query =
+            dsl::bgp_config.filter(dsl::time_deleted.is_null()).into_boxed();
+        let query = match name_or_id {
+            NameOrId::Id(id) => query.filter(dsl::id.eq(*id)),
+            NameOrId::Name(name) => {
+                query.filter(dsl::name.eq(name.to_string()))
+            }
+        };
+
+        query
+            .select(BgpConfig::as_select())
+            .first_async::<BgpConfig>(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| match e {
+                PoolError::Connection(ConnectionError::Query(
+                    DieselError::NotFound,
+                )) => not_found(ResourceType::BgpConfig, name_or_id),
+                e => public_error_from_diesel_pool(e, ErrorHandler::Server),
+            })
+    }
+
+    pub async fn bgp_config_delete(
+        &self,
+        opctx: &OpContext,
+        sel: &params::BgpConfigSelector,
+    ) -> DeleteResult {
+        use db::schema::bgp_config::dsl as bgp_config_dsl;
+        use db::schema::switch_port_settings_bgp_peer_config::dsl as bgp_peer_dsl;
+
+        #[derive(Debug)]
+        enum BgpConfigDeleteError {
+            ConfigInUse,
+        }
+        type TxnError = TransactionError<BgpConfigDeleteError>;
+
+        let id = self.bgp_config_get(opctx, &sel.name_or_id).await?.id();
+
+        // TODO https://github.com/oxidecomputer/omicron/issues/2811
+        // Audit external networking database transaction usage
+        self.pool_authorized(opctx)
+            .await?
+            .transaction_async(|conn| async move {
+                let in_use: Vec<Uuid> =
+                    bgp_peer_dsl::switch_port_settings_bgp_peer_config
+                        .filter(bgp_peer_dsl::bgp_config_id.eq(id))
+                        .select(bgp_peer_dsl::port_settings_id)
+                        .limit(1)
+                        .load_async(&conn)
+                        .await?;
+
+                if !in_use.is_empty() {
+                    return Err(TxnError::CustomError(
+                        BgpConfigDeleteError::ConfigInUse,
+                    ));
+                }
+
+                diesel::update(bgp_config_dsl::bgp_config)
+                    .filter(bgp_config_dsl::time_deleted.is_null())
+                    .filter(bgp_config_dsl::id.eq(id))
+                    .set(bgp_config_dsl::time_deleted.eq(Utc::now()))
+                    .execute_async(&conn)
+                    .await?;
+
+                Ok(())
+            })
+            .await
+            .map_err(|e| match e {
+                TxnError::CustomError(BgpConfigDeleteError::ConfigInUse) => {
+                    Error::invalid_request(
+                        "BGP config is in use by switch port settings",
+                    )
+                }
+                TxnError::Pool(e) => {
+                    public_error_from_diesel_pool(e, ErrorHandler::Server)
+                }
+            })
+    }
+
+    // bgp announce sets
+
+    pub async fn bgp_announce_set_create(
+        &self,
+        opctx: &OpContext,
+        params: &params::CreateBgpAnnounceSet,
+    ) -> CreateResult<BgpAnnounceSetCreateResult> {
+        use db::schema::address_lot::dsl as lot_dsl;
+        use db::schema::address_lot_block::dsl as block_dsl;
+        use db::schema::bgp_announce_set::dsl as announce_set_dsl;
+        use db::schema::bgp_announcement::dsl as announcement_dsl;
+
+        #[derive(Debug)]
+        enum BgpAnnounceSetCreateError {
+            AddressLotNotFound,
+            NetworkNotInLot(IpNetwork),
+        }
+        type TxnError = TransactionError<BgpAnnounceSetCreateError>;
+
+        // TODO https://github.com/oxidecomputer/omicron/issues/2811
+        // Audit external networking database transaction usage
+        self.pool_authorized(opctx)
+            .await?
+            .transaction_async(|conn| async move {
+                let announce_set: BgpAnnounceSet =
+                    diesel::insert_into(announce_set_dsl::bgp_announce_set)
+                        .values(BgpAnnounceSet::new(&params.identity))
+                        .returning(BgpAnnounceSet::as_returning())
+                        .get_result_async(&conn)
+                        .await?;
+
+                let mut announcements = Vec::new();
+                for a in &params.announcement {
+                    let lot_id = match &a.address_lot_block {
+                        NameOrId::Id(id) => *id,
+                        NameOrId::Name(name) => lot_dsl::address_lot
+                            .filter(lot_dsl::time_deleted.is_null())
+                            .filter(lot_dsl::name.eq(name.to_string()))
+                            .select(lot_dsl::id)
+                            .limit(1)
+                            .first_async::<Uuid>(&conn)
+                            .await
+                            .map_err(|e| match e {
+                                ConnectionError::Query(_) => {
+                                    TxnError::CustomError(
+                                        BgpAnnounceSetCreateError::AddressLotNotFound,
+                                    )
+                                }
+                                e => e.into(),
+                            })?,
+                    };
+
+                    // The announced network must be drawn from one of the
+                    // blocks of the address lot.
+                    let network: IpNetwork = a.network.into();
+                    let first = IpNetwork::from(network.network());
+                    let last = IpNetwork::from(network.broadcast());
+                    let block_id = block_dsl::address_lot_block
+                        .filter(block_dsl::address_lot_id.eq(lot_id))
+                        .filter(block_dsl::first_address.le(first))
+                        .filter(block_dsl::last_address.ge(last))
+                        .select(block_dsl::id)
+                        .limit(1)
+                        .first_async::<Uuid>(&conn)
+                        .await
+                        .map_err(|e| match e {
+                            ConnectionError::Query(_) => TxnError::CustomError(
+                                BgpAnnounceSetCreateError::NetworkNotInLot(
+                                    network,
+                                ),
+                            ),
+                            e => e.into(),
+                        })?;
+
+                    announcements.push(BgpAnnouncement {
+                        announce_set_id: announce_set.id(),
+                        address_lot_block_id: block_id,
+                        network,
+                    });
+                }
+
+                let announcements =
+                    diesel::insert_into(announcement_dsl::bgp_announcement)
+                        .values(announcements)
+                        .returning(BgpAnnouncement::as_returning())
+                        .get_results_async(&conn)
+                        .await?;
+
+                Ok(BgpAnnounceSetCreateResult { announce_set, announcements })
+            })
+            .await
+            .map_err(|e| match e {
+                TxnError::CustomError(
+                    BgpAnnounceSetCreateError::AddressLotNotFound,
+                ) => Error::invalid_request("AddressLot not found"),
+                TxnError::CustomError(
+                    BgpAnnounceSetCreateError::NetworkNotInLot(network),
+                ) => Error::invalid_request(&format!(
+                    "announced network {network} is not in address lot"
+                )),
+                TxnError::Pool(e) => match e {
+                    PoolError::Connection(ConnectionError::Query(
+                        DieselError::DatabaseError(_, _),
+                    )) => public_error_from_diesel_pool(
+                        e,
+                        ErrorHandler::Conflict(
+                            ResourceType::BgpAnnounceSet,
+                            params.identity.name.as_str(),
+                        ),
+                    ),
+                    _ => public_error_from_diesel_pool(e, ErrorHandler::Server),
+                },
+            })
+    }
+
+    pub async fn bgp_announce_set_list(
+        &self,
+        opctx: &OpContext,
+        pagparams: &PaginatedBy<'_>,
+    ) -> ListResultVec<BgpAnnounceSet> {
+        use db::schema::bgp_announce_set::dsl;
+
+        match pagparams {
+            PaginatedBy::Id(pagparams) => {
+                paginated(dsl::bgp_announce_set, dsl::id, &pagparams)
+            }
+            PaginatedBy::Name(pagparams) => paginated(
+                dsl::bgp_announce_set,
+                dsl::name,
+                &pagparams.map_name(|n| Name::ref_cast(n)),
+            ),
+        }
+        .filter(dsl::time_deleted.is_null())
+        .select(BgpAnnounceSet::as_select())
+        .load_async(self.pool_authorized(opctx).await?)
+        .await
+        .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+    }
+
+    pub async fn bgp_announce_set_get(
+        &self,
+        opctx: &OpContext,
+        name_or_id: &NameOrId,
+    ) -> LookupResult<BgpAnnounceSet> {
+        use db::schema::bgp_announce_set::dsl;
+
+        let query = dsl::bgp_announce_set
+            .filter(dsl::time_deleted.is_null())
+            .into_boxed();
+        let query = match name_or_id {
+            NameOrId::Id(id) => query.filter(dsl::id.eq(*id)),
+            NameOrId::Name(name) => {
+                query.filter(dsl::name.eq(name.to_string()))
+            }
+        };
+
+        query
+            .select(BgpAnnounceSet::as_select())
+            .first_async::<BgpAnnounceSet>(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| match e {
+                PoolError::Connection(ConnectionError::Query(
+                    DieselError::NotFound,
+                )) => not_found(ResourceType::BgpAnnounceSet, name_or_id),
+                e => public_error_from_diesel_pool(e, ErrorHandler::Server),
+            })
+    }
+
+    pub async fn bgp_announcement_list(
+        &self,
+        opctx: &OpContext,
+        sel: &params::BgpAnnounceSetSelector,
+    ) -> ListResultVec<BgpAnnouncement> {
+        use db::schema::bgp_announcement::dsl;
+
+        let announce_set_id =
+            self.bgp_announce_set_get(opctx, &sel.name_or_id).await?.id();
+
+        dsl::bgp_announcement
+            .filter(dsl::announce_set_id.eq(announce_set_id))
+            .select(BgpAnnouncement::as_select())
+            .load_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+    }
+
+    pub async fn bgp_announce_set_delete(
+        &self,
+        opctx: &OpContext,
+        sel: &params::BgpAnnounceSetSelector,
+    ) -> DeleteResult {
+        use db::schema::bgp_announce_set::dsl as announce_set_dsl;
+        use db::schema::bgp_announcement::dsl as announcement_dsl;
+        use db::schema::switch_port_settings_bgp_peer_config::dsl as bgp_peer_dsl;
+
+        #[derive(Debug)]
+        enum BgpAnnounceSetDeleteError {
+            AnnounceSetInUse,
+        }
+        type TxnError = TransactionError<BgpAnnounceSetDeleteError>;
+
+        let id = self.bgp_announce_set_get(opctx, &sel.name_or_id).await?.id();
+
+        // TODO https://github.com/oxidecomputer/omicron/issues/2811
+        // Audit external networking database transaction usage
+        self.pool_authorized(opctx)
+            .await?
+            .transaction_async(|conn| async move {
+                let in_use: Vec<Uuid> =
+                    bgp_peer_dsl::switch_port_settings_bgp_peer_config
+                        .filter(bgp_peer_dsl::bgp_announce_set_id.eq(id))
+                        .select(bgp_peer_dsl::port_settings_id)
+                        .limit(1)
+                        .load_async(&conn)
+                        .await?;
+
+                if !in_use.is_empty() {
+                    return Err(TxnError::CustomError(
+                        BgpAnnounceSetDeleteError::AnnounceSetInUse,
+                    ));
+                }
+
+                diesel::update(announce_set_dsl::bgp_announce_set)
+                    .filter(announce_set_dsl::time_deleted.is_null())
+                    .filter(announce_set_dsl::id.eq(id))
+                    .set(announce_set_dsl::time_deleted.eq(Utc::now()))
+                    .execute_async(&conn)
+                    .await?;
+
+                diesel::delete(announcement_dsl::bgp_announcement)
+                    .filter(announcement_dsl::announce_set_id.eq(id))
+                    .execute_async(&conn)
+                    .await?;
+
+                Ok(())
+            })
+            .await
+            .map_err(|e| match e {
+                TxnError::CustomError(
+                    BgpAnnounceSetDeleteError::AnnounceSetInUse,
+                ) => Error::invalid_request(
+                    "BGP announce set is in use by switch port settings",
+                ),
+                TxnError::Pool(e) => {
+                    public_error_from_diesel_pool(e, ErrorHandler::Server)
+                }
+            })
+    }
+}
+
+fn not_found(resource_type: ResourceType, name_or_id: &NameOrId) -> Error {
+    match name_or_id {
+        NameOrId::Id(id) => Error::not_found_by_id(resource_type, id),
+        NameOrId::Name(name) => Error::not_found_by_name(resource_type, name),
+    }
+}
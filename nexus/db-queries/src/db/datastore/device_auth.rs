@@ -13,16 +13,26 @@ use crate::db::error::ErrorHandler;
 use crate::db::error::TransactionError;
 use crate::db::model::DeviceAccessToken;
 use crate::db::model::DeviceAuthRequest;
+use crate::db::pagination::paginated;
 use async_bb8_diesel::AsyncConnection;
 use async_bb8_diesel::AsyncRunQueryDsl;
+use chrono::Utc;
 use diesel::prelude::*;
 use omicron_common::api::external::CreateResult;
+use omicron_common::api::external::DataPageParams;
+use omicron_common::api::external::DeleteResult;
 use omicron_common::api::external::Error;
+use omicron_common::api::external::ListResultVec;
 use omicron_common::api::external::LookupResult;
 use omicron_common::api::external::LookupType;
 use omicron_common::api::external::ResourceType;
+use omicron_common::api::external::UpdateResult;
 use uuid::Uuid;
 
+/// How out of date (in seconds) a device access token's last-used time may get
+/// before using the token updates it
+const TOKEN_LAST_USED_UPDATE_INTERVAL_SECS: i64 = 60;
+
 impl DataStore {
     /// Start a device authorization grant flow by recording the request
     /// and initial response parameters.
@@ -141,4 +151,119 @@ impl DataStore {
                 )
             })
     }
+
+    /// Create an access token for a user directly, outside of any device
+    /// authorization flow.
+    pub async fn device_access_token_create_for_user(
+        &self,
+        opctx: &OpContext,
+        authz_user: &authz::SiloUser,
+        access_token: DeviceAccessToken,
+    ) -> CreateResult<DeviceAccessToken> {
+        assert_eq!(authz_user.id(), access_token.silo_user_id);
+        opctx.authorize(authz::Action::CreateChild, authz_user).await?;
+        let name = access_token
+            .name
+            .as_ref()
+            .map(|name| name.to_string())
+            .unwrap_or_default();
+
+        use db::schema::device_access_token::dsl;
+        diesel::insert_into(dsl::device_access_token)
+            .values(access_token)
+            .returning(DeviceAccessToken::as_returning())
+            .get_result_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel_pool(
+                    e,
+                    ErrorHandler::Conflict(
+                        ResourceType::DeviceAccessToken,
+                        &name,
+                    ),
+                )
+            })
+    }
+
+    /// List the access tokens belonging to a user, including expired ones.
+    pub async fn device_access_tokens_list(
+        &self,
+        opctx: &OpContext,
+        authz_user: &authz::SiloUser,
+        pagparams: &DataPageParams<'_, Uuid>,
+    ) -> ListResultVec<DeviceAccessToken> {
+        opctx.authorize(authz::Action::ListChildren, authz_user).await?;
+
+        use db::schema::device_access_token::dsl;
+        paginated(dsl::device_access_token, dsl::id, pagparams)
+            .filter(dsl::silo_user_id.eq(authz_user.id()))
+            .select(DeviceAccessToken::as_select())
+            .load_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+    }
+
+    /// Revoke one of a user's access tokens.  The token can no longer be
+    /// used as soon as this returns.
+    pub async fn device_access_token_delete(
+        &self,
+        opctx: &OpContext,
+        authz_user: &authz::SiloUser,
+        token_id: Uuid,
+    ) -> DeleteResult {
+        opctx.authorize(authz::Action::Modify, authz_user).await?;
+
+        use db::schema::device_access_token::dsl;
+        let deleted = diesel::delete(dsl::device_access_token)
+            .filter(dsl::id.eq(token_id))
+            .filter(dsl::silo_user_id.eq(authz_user.id()))
+            .execute_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel_pool(e, ErrorHandler::Server)
+            })?;
+        if deleted == 0 {
+            return Err(Error::ObjectNotFound {
+                type_name: ResourceType::DeviceAccessToken,
+                lookup_type: LookupType::ById(token_id),
+            });
+        }
+        Ok(())
+    }
+
+    /// Record that a device access token was just used to authenticate a
+    /// request.
+    ///
+    /// To avoid writing to the database on every request, the recorded time
+    /// is only changed if it's more than a minute old, so it may be behind by
+    /// up to that much.
+    pub async fn device_access_token_update_last_used(
+        &self,
+        opctx: &OpContext,
+        authz_token: &authz::DeviceAccessToken,
+    ) -> UpdateResult<()> {
+        opctx.authorize(authz::Action::Modify, authz_token).await?;
+
+        use db::schema::device_access_token::dsl;
+        let now = Utc::now();
+        let cutoff = now
+            - chrono::Duration::seconds(TOKEN_LAST_USED_UPDATE_INTERVAL_SECS);
+        diesel::update(dsl::device_access_token)
+            .filter(dsl::token.eq(authz_token.id()))
+            .filter(
+                dsl::time_last_used
+                    .is_null()
+                    .or(dsl::time_last_used.lt(cutoff)),
+            )
+            .set(dsl::time_last_used.eq(now))
+            .execute_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel_pool(
+                    e,
+                    ErrorHandler::NotFoundByResource(authz_token),
+                )
+            })?;
+        Ok(())
+    }
 }
@@ -4,6 +4,7 @@
 
 //! [`DataStore`] methods on [`Disk`]s.
 
+use super::virtual_provisioning_quota::QuotaRequest;
 use super::DataStore;
 use crate::authz;
 use crate::authz::ApiResource;
@@ -17,18 +18,24 @@ use crate::db::collection_insert::AsyncInsertError;
 use crate::db::collection_insert::DatastoreCollection;
 use crate::db::error::public_error_from_diesel_pool;
 use crate::db::error::ErrorHandler;
+use crate::db::error::TransactionError;
 use crate::db::identity::Resource;
 use crate::db::lookup::LookupPath;
+use crate::db::model::ByteCount;
 use crate::db::model::Disk;
 use crate::db::model::DiskRuntimeState;
 use crate::db::model::DiskUpdate;
 use crate::db::model::Instance;
+use crate::db::model::Labels;
 use crate::db::model::Name;
 use crate::db::model::Project;
+use crate::db::model::VirtualProvisioningCollection;
 use crate::db::pagination::paginated;
 use crate::db::queries::disk::DiskSetClauseForAttach;
+use crate::db::queries::virtual_provisioning_collection_update::VirtualProvisioningCollectionUpdate;
 use crate::db::update_and_check::UpdateAndCheck;
 use crate::db::update_and_check::UpdateStatus;
+use async_bb8_diesel::AsyncConnection;
 use async_bb8_diesel::AsyncRunQueryDsl;
 use chrono::Utc;
 use diesel::prelude::*;
@@ -36,6 +43,7 @@ use omicron_common::api;
 use omicron_common::api::external::http_pagination::PaginatedBy;
 use omicron_common::api::external::CreateResult;
 use omicron_common::api::external::Error;
+use omicron_common::api::external::LabelSelector;
 use omicron_common::api::external::ListResultVec;
 use omicron_common::api::external::LookupResult;
 use omicron_common::api::external::LookupType;
@@ -129,11 +137,12 @@ impl DataStore {
         opctx: &OpContext,
         authz_project: &authz::Project,
         pagparams: &PaginatedBy<'_>,
+        label_selector: Option<&LabelSelector>,
     ) -> ListResultVec<Disk> {
         opctx.authorize(authz::Action::ListChildren, authz_project).await?;
 
         use db::schema::disk::dsl;
-        match pagparams {
+        let mut query = match pagparams {
             PaginatedBy::Id(pagparams) => {
                 paginated(dsl::disk, dsl::id, &pagparams)
             }
@@ -144,11 +153,42 @@ impl DataStore {
             ),
         }
         .filter(dsl::time_deleted.is_null())
-        .filter(dsl::project_id.eq(authz_project.id()))
-        .select(Disk::as_select())
-        .load_async::<Disk>(self.pool_authorized(opctx).await?)
-        .await
-        .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+        .filter(dsl::project_id.eq(authz_project.id()));
+        if let Some(label_selector) = label_selector {
+            query = query.filter(
+                dsl::labels.contains(Labels(label_selector.labels().clone())),
+            );
+        }
+        query
+            .select(Disk::as_select())
+            .load_async::<Disk>(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+    }
+
+    /// Replaces the labels on a Disk
+    pub async fn disk_labels_update(
+        &self,
+        opctx: &OpContext,
+        authz_disk: &authz::Disk,
+        labels: Labels,
+    ) -> UpdateResult<Disk> {
+        opctx.authorize(authz::Action::Modify, authz_disk).await?;
+
+        use db::schema::disk::dsl;
+        diesel::update(dsl::disk)
+            .filter(dsl::time_deleted.is_null())
+            .filter(dsl::id.eq(authz_disk.id()))
+            .set((dsl::labels.eq(labels), dsl::time_modified.eq(Utc::now())))
+            .returning(Disk::as_returning())
+            .get_result_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel_pool(
+                    e,
+                    ErrorHandler::NotFoundByResource(authz_disk),
+                )
+            })
     }
 
     /// Attaches a disk to an instance, if both objects:
@@ -515,6 +555,146 @@ impl DataStore {
         Ok(updated)
     }
 
+    /// Replaces the volume backing a disk, as long as the disk is still backed
+    /// by `old_volume_id`.
+    ///
+    /// Returns `true` if the disk is backed by `new_volume_id` afterwards,
+    /// including when an earlier call already made this change.
+    pub async fn disk_set_volume(
+        &self,
+        opctx: &OpContext,
+        authz_disk: &authz::Disk,
+        old_volume_id: Uuid,
+        new_volume_id: Uuid,
+    ) -> UpdateResult<bool> {
+        opctx.authorize(authz::Action::Modify, authz_disk).await?;
+
+        let disk_id = authz_disk.id();
+        use db::schema::disk::dsl;
+        let updated = diesel::update(dsl::disk)
+            .filter(dsl::time_deleted.is_null())
+            .filter(dsl::id.eq(disk_id))
+            .filter(dsl::volume_id.eq(old_volume_id))
+            .set((
+                dsl::volume_id.eq(new_volume_id),
+                dsl::time_modified.eq(Utc::now()),
+            ))
+            .check_if_exists::<Disk>(disk_id)
+            .execute_and_check(self.pool_authorized(opctx).await?)
+            .await
+            .map(|r| match r.status {
+                UpdateStatus::Updated => true,
+                UpdateStatus::NotUpdatedButExists => {
+                    r.found.volume_id == new_volume_id
+                }
+            })
+            .map_err(|e| {
+                public_error_from_diesel_pool(
+                    e,
+                    ErrorHandler::NotFoundByResource(authz_disk),
+                )
+            })?;
+
+        Ok(updated)
+    }
+
+    /// Replaces the volume backing a disk with one of `new_size` bytes, as
+    /// long as the disk is still backed by `old_volume_id`.
+    ///
+    /// Within a single transaction, this checks that any growth fits within
+    /// the quotas of the disk's project and silo, moves the difference in
+    /// size into or out of the project (and its silo and the fleet), and
+    /// points the disk at the new volume with its new size.  The new volume
+    /// and the regions backing it must already exist.
+    ///
+    /// Returns `true` if the disk is backed by `new_volume_id` afterwards,
+    /// including when an earlier call already made this change.
+    pub async fn disk_resize(
+        &self,
+        opctx: &OpContext,
+        authz_disk: &authz::Disk,
+        old_volume_id: Uuid,
+        new_volume_id: Uuid,
+        new_size: api::external::ByteCount,
+    ) -> UpdateResult<bool> {
+        opctx.authorize(authz::Action::Modify, authz_disk).await?;
+
+        type TxnError = TransactionError<Error>;
+        let disk_id = authz_disk.id();
+        let disk_not_found = authz_disk.not_found();
+        let (resized, provisions) = self
+            .pool_authorized(opctx)
+            .await?
+            .transaction_async(|conn| async move {
+                use db::schema::disk::dsl;
+
+                let disk = dsl::disk
+                    .filter(dsl::time_deleted.is_null())
+                    .filter(dsl::id.eq(disk_id))
+                    .select(Disk::as_select())
+                    .load_async(&conn)
+                    .await?
+                    .pop()
+                    .ok_or(TxnError::CustomError(disk_not_found))?;
+
+                if disk.volume_id != old_volume_id {
+                    return Ok((disk.volume_id == new_volume_id, vec![]));
+                }
+
+                let old_size = disk.size.0;
+                if new_size.to_bytes() > old_size.to_bytes() {
+                    let size_delta = api::external::ByteCount::try_from(
+                        new_size.to_bytes() - old_size.to_bytes(),
+                    )
+                    .map_err(|e| {
+                        TxnError::CustomError(Error::internal_error(&format!(
+                            "computing disk size delta: {}",
+                            e
+                        )))
+                    })?;
+                    self.virtual_provisioning_quota_check_growth_on_connection(
+                        &conn,
+                        disk.project_id,
+                        QuotaRequest::storage(size_delta.into()),
+                    )
+                    .await?;
+                }
+                let provisions: Vec<VirtualProvisioningCollection> =
+                    VirtualProvisioningCollectionUpdate::new_resize_storage(
+                        disk_id,
+                        old_size.into(),
+                        new_size.into(),
+                        disk.project_id,
+                    )
+                    .get_results_async(&conn)
+                    .await?;
+
+                diesel::update(dsl::disk)
+                    .filter(dsl::id.eq(disk_id))
+                    .filter(dsl::volume_id.eq(old_volume_id))
+                    .set((
+                        dsl::volume_id.eq(new_volume_id),
+                        dsl::size_bytes.eq(ByteCount::from(new_size)),
+                        dsl::time_modified.eq(Utc::now()),
+                    ))
+                    .execute_async(&conn)
+                    .await?;
+
+                Ok((true, provisions))
+            })
+            .await
+            .map_err(|e| match e {
+                TxnError::CustomError(e) => e,
+                TxnError::Pool(e) => {
+                    public_error_from_diesel_pool(e, ErrorHandler::Server)
+                }
+            })?;
+
+        self.virtual_provisioning_collection_producer
+            .append_disk_metrics(&provisions);
+        Ok(resized)
+    }
+
     /// Fetches information about a Disk that the caller has previously fetched
     ///
     /// The only difference between this function and a new fetch by id is that
@@ -6,28 +6,39 @@
 
 use super::DataStore;
 use crate::authz;
+use crate::authz::ApiResource;
 use crate::context::OpContext;
 use crate::db;
 use crate::db::error::public_error_from_diesel_pool;
 use crate::db::error::ErrorHandler;
+use crate::db::error::TransactionError;
 use crate::db::model::ExternalIp;
+use crate::db::model::FloatingIp;
 use crate::db::model::IncompleteExternalIp;
+use crate::db::model::Instance;
 use crate::db::model::IpKind;
 use crate::db::model::Name;
+use crate::db::pagination::paginated;
 use crate::db::pool::DbConnection;
 use crate::db::queries::external_ip::NextExternalIp;
 use crate::db::update_and_check::UpdateAndCheck;
 use crate::db::update_and_check::UpdateStatus;
+use async_bb8_diesel::AsyncConnection;
 use async_bb8_diesel::{AsyncRunQueryDsl, PoolError};
 use chrono::Utc;
 use diesel::prelude::*;
+use nexus_types::external_api::params;
 use nexus_types::identity::Resource;
+use omicron_common::api::external;
+use omicron_common::api::external::http_pagination::PaginatedBy;
 use omicron_common::api::external::CreateResult;
 use omicron_common::api::external::Error;
+use omicron_common::api::external::ListResultVec;
 use omicron_common::api::external::LookupResult;
-use omicron_common::api::external::Name as ExternalName;
+use omicron_common::api::external::ResourceType;
+use omicron_common::api::external::UpdateResult;
+use ref_cast::RefCast;
 use std::net::IpAddr;
-use std::str::FromStr;
 use uuid::Uuid;
 
 impl DataStore {
@@ -48,6 +59,10 @@ impl DataStore {
     }
 
     /// Create an Ephemeral IP address for an instance.
+    ///
+    /// If no pool is named, the address comes from the default pool for the
+    /// caller's Silo.  A named pool must be linked to the caller's Silo, or to
+    /// no Silo at all.
     pub async fn allocate_instance_ephemeral_ip(
         &self,
         opctx: &OpContext,
@@ -55,11 +70,12 @@ impl DataStore {
         instance_id: Uuid,
         pool_name: Option<Name>,
     ) -> CreateResult<ExternalIp> {
-        let name = pool_name.unwrap_or_else(|| {
-            Name(ExternalName::from_str("default").unwrap())
-        });
         let (.., pool) = self
-            .ip_pools_fetch_for(opctx, authz::Action::CreateChild, &name)
+            .ip_pools_fetch_named_or_default(
+                opctx,
+                authz::Action::CreateChild,
+                pool_name.as_ref(),
+            )
             .await?;
         let pool_id = pool.identity.id;
 
@@ -68,6 +84,373 @@ impl DataStore {
         self.allocate_external_ip(opctx, data).await
     }
 
+    /// Allocates a Floating IP address within a project.
+    pub async fn allocate_floating_ip(
+        &self,
+        opctx: &OpContext,
+        authz_project: &authz::Project,
+        ip_id: Uuid,
+        params: params::FloatingIpCreate,
+    ) -> CreateResult<FloatingIp> {
+        opctx.authorize(authz::Action::CreateChild, authz_project).await?;
+
+        let (.., pool) = self
+            .ip_pools_fetch_named_or_default(
+                opctx,
+                authz::Action::CreateChild,
+                params.pool.map(Name).as_ref(),
+            )
+            .await?;
+        let pool_id = pool.identity.id;
+
+        let data = IncompleteExternalIp::for_floating(
+            ip_id,
+            &Name(params.identity.name),
+            &params.identity.description,
+            authz_project.id(),
+            pool_id,
+            params.address,
+        );
+        self.allocate_external_ip(opctx, data).await?.try_into()
+    }
+
+    /// List the Floating IPs within a project.
+    pub async fn floating_ips_list(
+        &self,
+        opctx: &OpContext,
+        authz_project: &authz::Project,
+        pagparams: &PaginatedBy<'_>,
+    ) -> ListResultVec<FloatingIp> {
+        use db::schema::floating_ip::dsl;
+
+        opctx.authorize(authz::Action::ListChildren, authz_project).await?;
+
+        match pagparams {
+            PaginatedBy::Id(pagparams) => {
+                paginated(dsl::floating_ip, dsl::id, &pagparams)
+            }
+            PaginatedBy::Name(pagparams) => paginated(
+                dsl::floating_ip,
+                dsl::name,
+                &pagparams.map_name(|n| Name::ref_cast(n)),
+            ),
+        }
+        .filter(dsl::project_id.eq(authz_project.id()))
+        .filter(dsl::time_deleted.is_null())
+        .select(FloatingIp::as_select())
+        .get_results_async(self.pool_authorized(opctx).await?)
+        .await
+        .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+    }
+
+    /// Delete a Floating IP, which must not be attached to an instance.
+    pub async fn floating_ip_delete(
+        &self,
+        opctx: &OpContext,
+        authz_fip: &authz::FloatingIp,
+    ) -> UpdateResult<FloatingIp> {
+        use db::schema::external_ip::dsl;
+
+        opctx.authorize(authz::Action::Delete, authz_fip).await?;
+
+        let now = Utc::now();
+        let result = diesel::update(dsl::external_ip)
+            .filter(dsl::id.eq(authz_fip.id()))
+            .filter(dsl::time_deleted.is_null())
+            .filter(dsl::parent_id.is_null())
+            .set(dsl::time_deleted.eq(now))
+            .check_if_exists::<ExternalIp>(authz_fip.id())
+            .execute_and_check(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel_pool(
+                    e,
+                    ErrorHandler::NotFoundByResource(authz_fip),
+                )
+            })?;
+
+        match result.status {
+            UpdateStatus::Updated => result.found.try_into(),
+            UpdateStatus::NotUpdatedButExists => {
+                if result.found.time_deleted.is_some() {
+                    Err(authz_fip.not_found())
+                } else {
+                    Err(Error::invalid_request(
+                        "Floating IP cannot be deleted while attached to an \
+                        instance",
+                    ))
+                }
+            }
+        }
+    }
+
+    /// Attach a Floating IP to an instance.
+    ///
+    /// The instance must be stopped, and may not already have more than
+    /// `max_external_ips` Ephemeral and Floating IPs. Attaching a Floating IP
+    /// to the instance to which it is already attached succeeds without any
+    /// change.
+    pub async fn floating_ip_attach(
+        &self,
+        opctx: &OpContext,
+        authz_fip: &authz::FloatingIp,
+        authz_instance: &authz::Instance,
+        max_external_ips: usize,
+    ) -> UpdateResult<FloatingIp> {
+        use db::schema::external_ip::dsl;
+
+        opctx.authorize(authz::Action::Modify, authz_fip).await?;
+        opctx.authorize(authz::Action::Modify, authz_instance).await?;
+
+        type TxnError = TransactionError<Error>;
+        let fip_id = authz_fip.id();
+        let instance_id = authz_instance.id();
+        self.pool_authorized(opctx)
+            .await?
+            .transaction_async(|conn| async move {
+                let fip = Self::external_ip_fetch_on_connection(&conn, fip_id)
+                    .await?;
+                match fip.parent_id {
+                    Some(parent_id) if parent_id == instance_id => {
+                        return Ok(fip);
+                    }
+                    Some(_) => {
+                        return Err(TxnError::CustomError(
+                            Error::invalid_request(
+                                "Floating IP is already attached to an \
+                                instance",
+                            ),
+                        ));
+                    }
+                    None => {}
+                }
+
+                Self::floating_ip_check_instance_stopped_on_connection(
+                    &conn,
+                    instance_id,
+                )
+                .await?;
+
+                let attached = dsl::external_ip
+                    .filter(dsl::parent_id.eq(instance_id))
+                    .filter(dsl::is_service.eq(false))
+                    .filter(dsl::kind.ne(IpKind::SNat))
+                    .filter(dsl::time_deleted.is_null())
+                    .count()
+                    .get_result_async::<i64>(&conn)
+                    .await?;
+                if attached >= max_external_ips as i64 {
+                    return Err(TxnError::CustomError(Error::invalid_request(
+                        &format!(
+                            "cannot attach more than {} external IPs to \
+                            instance",
+                            max_external_ips,
+                        ),
+                    )));
+                }
+
+                diesel::update(dsl::external_ip)
+                    .filter(dsl::id.eq(fip_id))
+                    .filter(dsl::time_deleted.is_null())
+                    .filter(dsl::parent_id.is_null())
+                    .set((
+                        dsl::parent_id.eq(instance_id),
+                        dsl::time_modified.eq(Utc::now()),
+                    ))
+                    .returning(ExternalIp::as_returning())
+                    .get_result_async(&conn)
+                    .await
+                    .map_err(TxnError::from)
+            })
+            .await
+            .map_err(|e| match e {
+                TxnError::CustomError(e) => e,
+                TxnError::Pool(e) => public_error_from_diesel_pool(
+                    e,
+                    ErrorHandler::NotFoundByResource(authz_fip),
+                ),
+            })?
+            .try_into()
+    }
+
+    /// Detach a Floating IP from the instance to which it is attached.
+    ///
+    /// The instance must be stopped. Detaching a Floating IP which is not
+    /// attached to any instance succeeds without any change.
+    pub async fn floating_ip_detach(
+        &self,
+        opctx: &OpContext,
+        authz_fip: &authz::FloatingIp,
+        authz_instance: &authz::Instance,
+    ) -> UpdateResult<FloatingIp> {
+        use db::schema::external_ip::dsl;
+
+        opctx.authorize(authz::Action::Modify, authz_fip).await?;
+        opctx.authorize(authz::Action::Modify, authz_instance).await?;
+
+        type TxnError = TransactionError<Error>;
+        let fip_id = authz_fip.id();
+        let instance_id = authz_instance.id();
+        self.pool_authorized(opctx)
+            .await?
+            .transaction_async(|conn| async move {
+                let fip = Self::external_ip_fetch_on_connection(&conn, fip_id)
+                    .await?;
+                match fip.parent_id {
+                    None => return Ok(fip),
+                    Some(parent_id) if parent_id != instance_id => {
+                        return Err(TxnError::CustomError(
+                            Error::invalid_request(
+                                "Floating IP is not attached to the target \
+                                instance",
+                            ),
+                        ));
+                    }
+                    Some(_) => {}
+                }
+
+                Self::floating_ip_check_instance_stopped_on_connection(
+                    &conn,
+                    instance_id,
+                )
+                .await?;
+
+                diesel::update(dsl::external_ip)
+                    .filter(dsl::id.eq(fip_id))
+                    .filter(dsl::time_deleted.is_null())
+                    .filter(dsl::parent_id.eq(instance_id))
+                    .set((
+                        dsl::parent_id.eq(Option::<Uuid>::None),
+                        dsl::time_modified.eq(Utc::now()),
+                    ))
+                    .returning(ExternalIp::as_returning())
+                    .get_result_async(&conn)
+                    .await
+                    .map_err(TxnError::from)
+            })
+            .await
+            .map_err(|e| match e {
+                TxnError::CustomError(e) => e,
+                TxnError::Pool(e) => public_error_from_diesel_pool(
+                    e,
+                    ErrorHandler::NotFoundByResource(authz_fip),
+                ),
+            })?
+            .try_into()
+    }
+
+    /// Unconditionally sets the instance to which a Floating IP is attached,
+    /// provided that it is currently attached to `from_instance_id`.
+    ///
+    /// This is intended for use in unwinding the attach and detach sagas,
+    /// which must restore the prior state of the Floating IP regardless of
+    /// the state of the instance.
+    pub async fn floating_ip_set_instance_no_check(
+        &self,
+        opctx: &OpContext,
+        authz_fip: &authz::FloatingIp,
+        from_instance_id: Option<Uuid>,
+        to_instance_id: Option<Uuid>,
+    ) -> Result<(), Error> {
+        use db::schema::external_ip::dsl;
+
+        opctx.authorize(authz::Action::Modify, authz_fip).await?;
+
+        diesel::update(dsl::external_ip)
+            .filter(dsl::id.eq(authz_fip.id()))
+            .filter(dsl::time_deleted.is_null())
+            .filter(dsl::parent_id.is_not_distinct_from(from_instance_id))
+            .set((
+                dsl::parent_id.eq(to_instance_id),
+                dsl::time_modified.eq(Utc::now()),
+            ))
+            .execute_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel_pool(e, ErrorHandler::Server)
+            })?;
+        Ok(())
+    }
+
+    /// Detach all Floating IPs from the provided instance.
+    ///
+    /// Unlike Ephemeral IPs, Floating IPs outlive the instance to which they
+    /// are attached. This returns the number of records detached.
+    pub async fn detach_floating_ips_by_instance_id(
+        &self,
+        opctx: &OpContext,
+        instance_id: Uuid,
+    ) -> Result<usize, Error> {
+        use db::schema::external_ip::dsl;
+        diesel::update(dsl::external_ip)
+            .filter(dsl::time_deleted.is_null())
+            .filter(dsl::is_service.eq(false))
+            .filter(dsl::parent_id.eq(instance_id))
+            .filter(dsl::kind.eq(IpKind::Floating))
+            .set((
+                dsl::parent_id.eq(Option::<Uuid>::None),
+                dsl::time_modified.eq(Utc::now()),
+            ))
+            .execute_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+    }
+
+    async fn external_ip_fetch_on_connection<ConnErr>(
+        conn: &(impl async_bb8_diesel::AsyncConnection<DbConnection, ConnErr>
+              + Sync),
+        ip_id: Uuid,
+    ) -> Result<ExternalIp, TransactionError<Error>>
+    where
+        ConnErr: From<diesel::result::Error> + Send + 'static,
+        PoolError: From<ConnErr>,
+        TransactionError<Error>: From<ConnErr>,
+    {
+        use db::schema::external_ip::dsl;
+        Ok(dsl::external_ip
+            .filter(dsl::id.eq(ip_id))
+            .filter(dsl::time_deleted.is_null())
+            .select(ExternalIp::as_select())
+            .get_result_async(conn)
+            .await?)
+    }
+
+    /// Returns an error unless the instance `instance_id` is in a state from
+    /// which its external IPs may be changed.
+    // TODO-completeness: Floating IPs may only be attached to or detached from
+    // instances which are not running, since the external IPs of an instance
+    // are only delivered to its sled when it starts.
+    async fn floating_ip_check_instance_stopped_on_connection<ConnErr>(
+        conn: &(impl async_bb8_diesel::AsyncConnection<DbConnection, ConnErr>
+              + Sync),
+        instance_id: Uuid,
+    ) -> Result<(), TransactionError<Error>>
+    where
+        ConnErr: From<diesel::result::Error> + Send + 'static,
+        PoolError: From<ConnErr>,
+        TransactionError<Error>: From<ConnErr>,
+    {
+        use db::schema::instance::dsl;
+        let instance = dsl::instance
+            .filter(dsl::id.eq(instance_id))
+            .filter(dsl::time_deleted.is_null())
+            .select(Instance::as_select())
+            .get_result_async(conn)
+            .await?;
+        match instance.runtime_state.state.state() {
+            external::InstanceState::Creating
+            | external::InstanceState::Stopped
+            | external::InstanceState::Failed => Ok(()),
+            state => Err(TransactionError::CustomError(
+                Error::invalid_request(&format!(
+                    "cannot change the external IPs of an instance in state \
+                    {}",
+                    state,
+                )),
+            )),
+        }
+    }
+
     /// Allocates an IP address for internal service usage.
     pub async fn allocate_service_ip(
         &self,
@@ -127,12 +510,31 @@ impl DataStore {
         PoolError: From<ConnErr>,
     {
         let explicit_ip = data.explicit_ip().is_some();
+        let floating_ip_name = match data.project_id() {
+            Some(_) => data.name().clone(),
+            None => None,
+        };
         NextExternalIp::new(data).get_result_async(conn).await.map_err(|e| {
             use async_bb8_diesel::ConnectionError::Query;
             use async_bb8_diesel::PoolError::Connection;
+            use diesel::result::DatabaseErrorKind::UniqueViolation;
+            use diesel::result::Error::DatabaseError;
             use diesel::result::Error::NotFound;
             let e = PoolError::from(e);
             match e {
+                // Floating IP names are unique within their project.
+                Connection(Query(DatabaseError(UniqueViolation, _))) => {
+                    match floating_ip_name {
+                        Some(name) => public_error_from_diesel_pool(
+                            e,
+                            ErrorHandler::Conflict(
+                                ResourceType::FloatingIp,
+                                name.as_str(),
+                            ),
+                        ),
+                        None => crate::db::queries::external_ip::from_pool(e),
+                    }
+                }
                 Connection(Query(NotFound)) => {
                     if explicit_ip {
                         Error::invalid_request(
@@ -233,8 +635,9 @@ impl DataStore {
     /// This method returns the number of records deleted, rather than the usual
     /// `DeleteResult`. That's mostly useful for tests, but could be important
     /// if callers have some invariants they'd like to check.
-    // TODO-correctness: This can't be used for Floating IPs, we'll need a
-    // _detatch_ method for that.
+    ///
+    /// Floating IPs are not deallocated; see
+    /// [Self::detach_floating_ips_by_instance_id].
     pub async fn deallocate_external_ip_by_instance_id(
         &self,
         opctx: &OpContext,
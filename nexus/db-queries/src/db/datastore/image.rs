@@ -6,12 +6,16 @@ use crate::db::collection_insert::AsyncInsertError;
 use crate::db::collection_insert::DatastoreCollection;
 use crate::db::error::public_error_from_diesel_pool;
 use crate::db::error::ErrorHandler;
+use crate::db::error::TransactionError;
 use crate::db::model::Image;
+use crate::db::model::ImageSiloGrant;
+use crate::db::model::Labels;
 use crate::db::model::Project;
 use crate::db::model::ProjectImage;
 use crate::db::model::Silo;
 use crate::db::model::SiloImage;
 use crate::db::pagination::paginated;
+use async_bb8_diesel::AsyncConnection;
 use async_bb8_diesel::AsyncRunQueryDsl;
 use chrono::Utc;
 use diesel::prelude::*;
@@ -19,7 +23,12 @@ use nexus_db_model::Name;
 use nexus_types::identity::Resource;
 use omicron_common::api::external::http_pagination::PaginatedBy;
 use omicron_common::api::external::CreateResult;
+use omicron_common::api::external::DeleteResult;
+use omicron_common::api::external::Error;
+use omicron_common::api::external::LabelSelector;
 use omicron_common::api::external::ListResultVec;
+use omicron_common::api::external::LookupResult;
+use omicron_common::api::external::LookupType;
 use omicron_common::api::external::ResourceType;
 use omicron_common::api::external::UpdateResult;
 use ref_cast::RefCast;
@@ -33,11 +42,12 @@ impl DataStore {
         opctx: &OpContext,
         authz_project: &authz::Project,
         pagparams: &PaginatedBy<'_>,
+        label_selector: Option<&LabelSelector>,
     ) -> ListResultVec<Image> {
         opctx.authorize(authz::Action::ListChildren, authz_project).await?;
 
         use db::schema::project_image::dsl as project_dsl;
-        match pagparams {
+        let mut query = match pagparams {
             PaginatedBy::Id(pagparams) => paginated(
                 project_dsl::project_image,
                 project_dsl::id,
@@ -50,24 +60,35 @@ impl DataStore {
             ),
         }
         .filter(project_dsl::time_deleted.is_null())
-        .filter(project_dsl::project_id.eq(authz_project.id()))
-        .select(ProjectImage::as_select())
-        .load_async::<ProjectImage>(self.pool_authorized(opctx).await?)
-        .await
-        .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
-        .map(|v| v.into_iter().map(|v| v.into()).collect())
+        .filter(project_dsl::project_id.eq(authz_project.id()));
+        if let Some(label_selector) = label_selector {
+            query = query.filter(
+                project_dsl::labels
+                    .contains(Labels(label_selector.labels().clone())),
+            );
+        }
+        query
+            .select(ProjectImage::as_select())
+            .load_async::<ProjectImage>(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+            .map(|v| v.into_iter().map(|v| v.into()).collect())
     }
 
+    /// List the silo images of a silo, including the images of other silos
+    /// that have been shared with it
     pub async fn silo_image_list(
         &self,
         opctx: &OpContext,
         authz_silo: &authz::Silo,
         pagparams: &PaginatedBy<'_>,
+        label_selector: Option<&LabelSelector>,
     ) -> ListResultVec<Image> {
         opctx.authorize(authz::Action::ListChildren, authz_silo).await?;
 
+        use db::schema::image_silo_grant::dsl as grant_dsl;
         use db::schema::silo_image::dsl;
-        match pagparams {
+        let mut query = match pagparams {
             PaginatedBy::Id(pagparams) => {
                 paginated(dsl::silo_image, dsl::id, &pagparams)
             }
@@ -78,12 +99,197 @@ impl DataStore {
             ),
         }
         .filter(dsl::time_deleted.is_null())
-        .filter(dsl::silo_id.eq(authz_silo.id()))
-        .select(SiloImage::as_select())
-        .load_async::<SiloImage>(self.pool_authorized(opctx).await?)
+        .filter(
+            dsl::silo_id.eq(authz_silo.id()).or(dsl::id.eq_any(
+                grant_dsl::image_silo_grant
+                    .filter(grant_dsl::silo_id.eq(authz_silo.id()))
+                    .select(grant_dsl::image_id),
+            )),
+        );
+        if let Some(label_selector) = label_selector {
+            query = query.filter(
+                dsl::labels.contains(Labels(label_selector.labels().clone())),
+            );
+        }
+        query
+            .select(SiloImage::as_select())
+            .load_async::<SiloImage>(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+            .map(|v| v.into_iter().map(|v| v.into()).collect())
+    }
+
+    /// Fetch a silo image of another silo that has been shared with
+    /// `authz_silo`
+    ///
+    /// Anyone who can list the silo images of `authz_silo` can read the
+    /// images shared with it, but they can't modify them.
+    pub async fn silo_image_granted_fetch(
+        &self,
+        opctx: &OpContext,
+        authz_silo: &authz::Silo,
+        image_id: Uuid,
+    ) -> LookupResult<Image> {
+        opctx.authorize(authz::Action::ListChildren, authz_silo).await?;
+
+        use db::schema::image::dsl;
+        use db::schema::image_silo_grant::dsl as grant_dsl;
+        dsl::image
+            .inner_join(
+                grant_dsl::image_silo_grant.on(grant_dsl::image_id.eq(dsl::id)),
+            )
+            .filter(grant_dsl::silo_id.eq(authz_silo.id()))
+            .filter(dsl::id.eq(image_id))
+            .filter(dsl::project_id.is_null())
+            .filter(dsl::time_deleted.is_null())
+            .select(Image::as_select())
+            .load_async::<Image>(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel_pool(e, ErrorHandler::Server)
+            })?
+            .pop()
+            .ok_or_else(|| {
+                LookupType::ById(image_id).into_not_found(ResourceType::Image)
+            })
+    }
+
+    /// List the silos with which a silo image has been shared
+    pub async fn image_silo_list(
+        &self,
+        opctx: &OpContext,
+        authz_silo_image: &authz::SiloImage,
+        pagparams: &PaginatedBy<'_>,
+    ) -> ListResultVec<Silo> {
+        opctx.authorize(authz::Action::Read, authz_silo_image).await?;
+        opctx.authorize(authz::Action::Read, &authz::FLEET).await?;
+
+        use db::schema::image_silo_grant::dsl as grant_dsl;
+        use db::schema::silo::dsl;
+        match pagparams {
+            PaginatedBy::Id(pagparams) => {
+                paginated(dsl::silo, dsl::id, &pagparams)
+            }
+            PaginatedBy::Name(pagparams) => paginated(
+                dsl::silo,
+                dsl::name,
+                &pagparams.map_name(|n| Name::ref_cast(n)),
+            ),
+        }
+        .filter(dsl::time_deleted.is_null())
+        .filter(
+            dsl::id.eq_any(
+                grant_dsl::image_silo_grant
+                    .filter(grant_dsl::image_id.eq(authz_silo_image.id()))
+                    .select(grant_dsl::silo_id),
+            ),
+        )
+        .select(Silo::as_select())
+        .load_async::<Silo>(self.pool_authorized(opctx).await?)
         .await
         .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
-        .map(|v| v.into_iter().map(|v| v.into()).collect())
+    }
+
+    /// Share a silo image with another silo.
+    ///
+    /// Sharing an image with a silo with which it's already shared succeeds
+    /// without any change.
+    pub async fn image_silo_add(
+        &self,
+        opctx: &OpContext,
+        authz_silo_image: &authz::SiloImage,
+        authz_silo: &authz::Silo,
+    ) -> Result<(), Error> {
+        opctx.authorize(authz::Action::Modify, authz_silo_image).await?;
+        opctx.authorize(authz::Action::Modify, &authz::FLEET).await?;
+
+        use db::schema::image_silo_grant::dsl;
+        diesel::insert_into(dsl::image_silo_grant)
+            .values(ImageSiloGrant::new(authz_silo_image.id(), authz_silo.id()))
+            .on_conflict((dsl::image_id, dsl::silo_id))
+            .do_nothing()
+            .execute_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel_pool(e, ErrorHandler::Server)
+            })?;
+        Ok(())
+    }
+
+    /// Stop sharing a silo image with another silo.
+    ///
+    /// Disks already created from the image in that silo are not affected.
+    /// Removing a silo with which the image isn't shared succeeds without any
+    /// change.
+    pub async fn image_silo_remove(
+        &self,
+        opctx: &OpContext,
+        authz_silo_image: &authz::SiloImage,
+        authz_silo: &authz::Silo,
+    ) -> DeleteResult {
+        opctx.authorize(authz::Action::Modify, authz_silo_image).await?;
+        opctx.authorize(authz::Action::Modify, &authz::FLEET).await?;
+
+        use db::schema::image_silo_grant::dsl;
+        diesel::delete(dsl::image_silo_grant)
+            .filter(dsl::image_id.eq(authz_silo_image.id()))
+            .filter(dsl::silo_id.eq(authz_silo.id()))
+            .execute_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel_pool(e, ErrorHandler::Server)
+            })?;
+        Ok(())
+    }
+
+    /// Replaces the labels on a project image
+    pub async fn project_image_labels_update(
+        &self,
+        opctx: &OpContext,
+        authz_project_image: &authz::ProjectImage,
+        labels: Labels,
+    ) -> UpdateResult<Image> {
+        opctx.authorize(authz::Action::Modify, authz_project_image).await?;
+
+        use db::schema::image::dsl;
+        diesel::update(dsl::image)
+            .filter(dsl::time_deleted.is_null())
+            .filter(dsl::id.eq(authz_project_image.id()))
+            .set((dsl::labels.eq(labels), dsl::time_modified.eq(Utc::now())))
+            .returning(Image::as_returning())
+            .get_result_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel_pool(
+                    e,
+                    ErrorHandler::NotFoundByResource(authz_project_image),
+                )
+            })
+    }
+
+    /// Replaces the labels on a silo image
+    pub async fn silo_image_labels_update(
+        &self,
+        opctx: &OpContext,
+        authz_silo_image: &authz::SiloImage,
+        labels: Labels,
+    ) -> UpdateResult<Image> {
+        opctx.authorize(authz::Action::Modify, authz_silo_image).await?;
+
+        use db::schema::image::dsl;
+        diesel::update(dsl::image)
+            .filter(dsl::time_deleted.is_null())
+            .filter(dsl::id.eq(authz_silo_image.id()))
+            .set((dsl::labels.eq(labels), dsl::time_modified.eq(Utc::now())))
+            .returning(Image::as_returning())
+            .get_result_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel_pool(
+                    e,
+                    ErrorHandler::NotFoundByResource(authz_silo_image),
+                )
+            })
     }
 
     pub async fn silo_image_create(
@@ -206,26 +412,44 @@ impl DataStore {
         opctx.authorize(authz::Action::Modify, authz_silo_image).await?;
         opctx.authorize(authz::Action::CreateChild, authz_project).await?;
 
-        use db::schema::image::dsl;
-        let image: Image = diesel::update(dsl::image)
-            .filter(dsl::time_deleted.is_null())
-            .filter(dsl::id.eq(authz_silo_image.id()))
-            .set((
-                dsl::project_id.eq(Some(authz_project.id())),
-                dsl::time_modified.eq(Utc::now()),
-            ))
-            .returning(Image::as_returning())
-            .get_result_async(self.pool_authorized(opctx).await?)
+        // A project image can't be shared with other silos, so demoting an
+        // image also stops sharing it.
+        type TxnError = TransactionError<Error>;
+        let image_id = authz_silo_image.id();
+        let project_id = authz_project.id();
+        self.pool_authorized(opctx)
+            .await?
+            .transaction_async(|conn| async move {
+                use db::schema::image::dsl;
+                use db::schema::image_silo_grant::dsl as grant_dsl;
+
+                diesel::delete(grant_dsl::image_silo_grant)
+                    .filter(grant_dsl::image_id.eq(image_id))
+                    .execute_async(&conn)
+                    .await?;
+
+                let image: Image = diesel::update(dsl::image)
+                    .filter(dsl::time_deleted.is_null())
+                    .filter(dsl::id.eq(image_id))
+                    .set((
+                        dsl::project_id.eq(Some(project_id)),
+                        dsl::time_modified.eq(Utc::now()),
+                    ))
+                    .returning(Image::as_returning())
+                    .get_result_async(&conn)
+                    .await?;
+                Ok(image)
+            })
             .await
-            .map_err(|e| {
-                public_error_from_diesel_pool(
+            .map_err(|e| match e {
+                TxnError::CustomError(e) => e,
+                TxnError::Pool(e) => public_error_from_diesel_pool(
                     e,
                     ErrorHandler::Conflict(
                         ResourceType::ProjectImage,
                         silo_image.name().as_str(),
                     ),
-                )
-            })?;
-        Ok(image)
+                ),
+            })
     }
 }
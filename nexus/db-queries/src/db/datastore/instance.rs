@@ -4,6 +4,7 @@
 
 //! [`DataStore`] methods on [`Instance`]s.
 
+use super::virtual_provisioning_quota::QuotaRequest;
 use super::DataStore;
 use crate::authz;
 use crate::authz::ApiResource;
@@ -15,27 +16,40 @@ use crate::db::collection_insert::AsyncInsertError;
 use crate::db::collection_insert::DatastoreCollection;
 use crate::db::error::public_error_from_diesel_pool;
 use crate::db::error::ErrorHandler;
+use crate::db::error::TransactionError;
 use crate::db::identity::Resource;
 use crate::db::lookup::LookupPath;
+use crate::db::model::ByteCount;
 use crate::db::model::Instance;
+use crate::db::model::InstanceAutoRestartPolicy;
+use crate::db::model::InstanceCpuCount;
 use crate::db::model::InstanceRuntimeState;
+use crate::db::model::Labels;
 use crate::db::model::Name;
 use crate::db::model::Project;
+use crate::db::model::Resources;
+use crate::db::model::SqlU32;
+use crate::db::model::VirtualProvisioningCollection;
 use crate::db::pagination::paginated;
+use crate::db::queries::virtual_provisioning_collection_update::VirtualProvisioningCollectionUpdate;
 use crate::db::update_and_check::UpdateAndCheck;
 use crate::db::update_and_check::UpdateStatus;
+use async_bb8_diesel::AsyncConnection;
 use async_bb8_diesel::AsyncRunQueryDsl;
 use chrono::Utc;
 use diesel::prelude::*;
 use omicron_common::api;
 use omicron_common::api::external::http_pagination::PaginatedBy;
 use omicron_common::api::external::CreateResult;
+use omicron_common::api::external::DataPageParams;
 use omicron_common::api::external::DeleteResult;
 use omicron_common::api::external::Error;
+use omicron_common::api::external::LabelSelector;
 use omicron_common::api::external::ListResultVec;
 use omicron_common::api::external::LookupResult;
 use omicron_common::api::external::LookupType;
 use omicron_common::api::external::ResourceType;
+use omicron_common::api::external::UpdateResult;
 use omicron_common::bail_unless;
 use ref_cast::RefCast;
 use uuid::Uuid;
@@ -118,11 +132,12 @@ impl DataStore {
         opctx: &OpContext,
         authz_project: &authz::Project,
         pagparams: &PaginatedBy<'_>,
+        label_selector: Option<&LabelSelector>,
     ) -> ListResultVec<Instance> {
         opctx.authorize(authz::Action::ListChildren, authz_project).await?;
 
         use db::schema::instance::dsl;
-        match pagparams {
+        let mut query = match pagparams {
             PaginatedBy::Id(pagparams) => {
                 paginated(dsl::instance, dsl::id, &pagparams)
             }
@@ -133,11 +148,70 @@ impl DataStore {
             ),
         }
         .filter(dsl::project_id.eq(authz_project.id()))
-        .filter(dsl::time_deleted.is_null())
-        .select(Instance::as_select())
-        .load_async::<Instance>(self.pool_authorized(opctx).await?)
-        .await
-        .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+        .filter(dsl::time_deleted.is_null());
+        if let Some(label_selector) = label_selector {
+            query = query.filter(
+                dsl::labels.contains(Labels(label_selector.labels().clone())),
+            );
+        }
+        query
+            .select(Instance::as_select())
+            .load_async::<Instance>(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+    }
+
+    /// Replaces the labels on an Instance
+    pub async fn instance_labels_update(
+        &self,
+        opctx: &OpContext,
+        authz_instance: &authz::Instance,
+        labels: Labels,
+    ) -> UpdateResult<Instance> {
+        opctx.authorize(authz::Action::Modify, authz_instance).await?;
+
+        use db::schema::instance::dsl;
+        diesel::update(dsl::instance)
+            .filter(dsl::time_deleted.is_null())
+            .filter(dsl::id.eq(authz_instance.id()))
+            .set((dsl::labels.eq(labels), dsl::time_modified.eq(Utc::now())))
+            .returning(Instance::as_returning())
+            .get_result_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel_pool(
+                    e,
+                    ErrorHandler::NotFoundByResource(authz_instance),
+                )
+            })
+    }
+
+    /// Replaces the user data of an Instance
+    pub async fn instance_user_data_update(
+        &self,
+        opctx: &OpContext,
+        authz_instance: &authz::Instance,
+        user_data: Vec<u8>,
+    ) -> UpdateResult<Instance> {
+        opctx.authorize(authz::Action::Modify, authz_instance).await?;
+
+        use db::schema::instance::dsl;
+        diesel::update(dsl::instance)
+            .filter(dsl::time_deleted.is_null())
+            .filter(dsl::id.eq(authz_instance.id()))
+            .set((
+                dsl::user_data.eq(user_data),
+                dsl::time_modified.eq(Utc::now()),
+            ))
+            .returning(Instance::as_returning())
+            .get_result_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel_pool(
+                    e,
+                    ErrorHandler::NotFoundByResource(authz_instance),
+                )
+            })
     }
 
     /// Fetches information about an Instance that the caller has previously
@@ -244,6 +318,199 @@ impl DataStore {
         Ok(updated)
     }
 
+    /// Changes the number of vCPUs and the amount of memory of a stopped
+    /// instance.
+    ///
+    /// Within a single transaction, this checks the new sizes against the
+    /// quotas of the instance's project and silo, updates the virtual
+    /// provisioning accounting from the project up to the fleet, and resizes
+    /// the instance's reservation on the sled to which it is assigned.
+    pub async fn instance_resize(
+        &self,
+        opctx: &OpContext,
+        authz_instance: &authz::Instance,
+        ncpus: api::external::InstanceCpuCount,
+        memory: api::external::ByteCount,
+    ) -> UpdateResult<Instance> {
+        opctx.authorize(authz::Action::Modify, authz_instance).await?;
+
+        type TxnError = TransactionError<Error>;
+        let instance_id = authz_instance.id();
+        let instance_not_found = authz_instance.not_found();
+        let (instance, provisions) = self
+            .pool_authorized(opctx)
+            .await?
+            .transaction_async(|conn| async move {
+                use db::schema::instance::dsl;
+
+                let instance = dsl::instance
+                    .filter(dsl::time_deleted.is_null())
+                    .filter(dsl::id.eq(instance_id))
+                    .select(Instance::as_select())
+                    .load_async(&conn)
+                    .await?
+                    .pop()
+                    .ok_or(TxnError::CustomError(instance_not_found))?;
+
+                let runtime = instance.runtime();
+                if runtime.state.state()
+                    != &api::external::InstanceState::Stopped
+                {
+                    return Err(TxnError::CustomError(Error::conflict(
+                        &format!(
+                            "instance cannot be resized in state \"{}\"",
+                            runtime.state.state()
+                        ),
+                    )));
+                }
+
+                let old_cpus = i64::from(runtime.ncpus.0 .0);
+                let old_ram = runtime.memory;
+                let new_cpus = i64::from(ncpus.0);
+                let new_ram = ByteCount::from(memory);
+
+                // Only growth needs to fit within the quotas.
+                let cpus_growth = (new_cpus - old_cpus).max(0);
+                let ram_growth =
+                    memory.to_bytes().saturating_sub(old_ram.to_bytes());
+                if cpus_growth > 0 || ram_growth > 0 {
+                    let ram_growth =
+                        api::external::ByteCount::try_from(ram_growth)
+                            .map_err(|e| {
+                                TxnError::CustomError(Error::internal_error(
+                                    &format!("computing memory growth: {}", e),
+                                ))
+                            })?;
+                    self.virtual_provisioning_quota_check_growth_on_connection(
+                        &conn,
+                        instance.project_id,
+                        QuotaRequest::instance(cpus_growth, ram_growth.into()),
+                    )
+                    .await?;
+                }
+
+                let provisions: Vec<VirtualProvisioningCollection> =
+                    VirtualProvisioningCollectionUpdate::new_resize_instance(
+                        instance_id,
+                        old_cpus,
+                        old_ram,
+                        new_cpus,
+                        new_ram,
+                        instance.project_id,
+                    )
+                    .get_results_async(&conn)
+                    .await?;
+
+                // The instance's sled reservation is keyed by its Propolis
+                // ID, and (as in the instance create saga) does not reserve
+                // any reservoir RAM.
+                self.sled_reservation_resize_on_connection(
+                    &conn,
+                    runtime.propolis_id,
+                    Resources::new(
+                        u32::from(ncpus.0),
+                        new_ram,
+                        ByteCount::from(api::external::ByteCount::from(0)),
+                    ),
+                )
+                .await?;
+
+                let instance = diesel::update(dsl::instance)
+                    .filter(dsl::id.eq(instance_id))
+                    .set((
+                        dsl::ncpus.eq(InstanceCpuCount::from(ncpus)),
+                        dsl::memory.eq(new_ram),
+                        dsl::time_modified.eq(Utc::now()),
+                    ))
+                    .returning(Instance::as_returning())
+                    .get_result_async(&conn)
+                    .await?;
+
+                Ok((instance, provisions))
+            })
+            .await
+            .map_err(|e| match e {
+                TxnError::CustomError(e) => e,
+                TxnError::Pool(e) => {
+                    public_error_from_diesel_pool(e, ErrorHandler::Server)
+                }
+            })?;
+
+        self.virtual_provisioning_collection_producer
+            .append_cpu_metrics(&provisions);
+        Ok(instance)
+    }
+
+    /// Lists failed instances whose auto-restart policy asks that they be
+    /// restarted
+    pub async fn instance_list_auto_restart_candidates(
+        &self,
+        opctx: &OpContext,
+        pagparams: &DataPageParams<'_, Uuid>,
+    ) -> ListResultVec<Instance> {
+        opctx.authorize(authz::Action::ListChildren, &authz::FLEET).await?;
+
+        use api::external::InstanceState as ApiInstanceState;
+        use db::model::InstanceState as DbInstanceState;
+        use db::schema::instance::dsl;
+
+        let failed = DbInstanceState::new(ApiInstanceState::Failed);
+        paginated(dsl::instance, dsl::id, pagparams)
+            .filter(dsl::time_deleted.is_null())
+            .filter(dsl::state.eq(failed))
+            .filter(
+                dsl::auto_restart_policy
+                    .eq(InstanceAutoRestartPolicy::BestEffort),
+            )
+            .select(Instance::as_select())
+            .load_async::<Instance>(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+    }
+
+    /// Records that an automatic restart of a failed instance is about to be
+    /// attempted, and that it's attempt number `attempts` since the instance
+    /// last ran without failing for a while
+    ///
+    /// The attempt is only recorded if the instance's state, and its record
+    /// of automatic restarts, haven't changed since `instance` was fetched.
+    /// Returns true if the attempt was recorded, in which case the caller
+    /// should go on to restart the instance, or false if some other update
+    /// (such as another Nexus restarting the same instance) got there first.
+    pub async fn instance_auto_restart_record_attempt(
+        &self,
+        opctx: &OpContext,
+        instance: &Instance,
+        attempts: u32,
+    ) -> Result<bool, Error> {
+        opctx.authorize(authz::Action::Modify, &authz::FLEET).await?;
+
+        use db::schema::instance::dsl;
+        let mut query = diesel::update(dsl::instance)
+            .filter(dsl::time_deleted.is_null())
+            .filter(dsl::id.eq(instance.id()))
+            .filter(dsl::state_generation.eq(instance.runtime().gen))
+            .filter(
+                dsl::auto_restart_attempts.eq(instance.auto_restart_attempts),
+            )
+            .into_boxed();
+        query = match instance.time_last_auto_restarted {
+            Some(time) => query.filter(dsl::time_last_auto_restarted.eq(time)),
+            None => query.filter(dsl::time_last_auto_restarted.is_null()),
+        };
+        let updated = query
+            .set((
+                dsl::auto_restart_attempts.eq(SqlU32::new(attempts)),
+                dsl::time_last_auto_restarted.eq(Utc::now()),
+            ))
+            .execute_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel_pool(e, ErrorHandler::Server)
+            })?;
+        Ok(updated > 0)
+    }
+
     pub async fn project_delete_instance(
         &self,
         opctx: &OpContext,
@@ -14,6 +14,7 @@ use crate::db::collection_insert::DatastoreCollection;
 use crate::db::error::diesel_pool_result_optional;
 use crate::db::error::public_error_from_diesel_pool;
 use crate::db::error::ErrorHandler;
+use crate::db::error::TransactionError;
 use crate::db::identity::Resource;
 use crate::db::lookup::LookupPath;
 use crate::db::model::IpPool;
@@ -23,11 +24,12 @@ use crate::db::model::Name;
 use crate::db::pagination::paginated;
 use crate::db::pool::DbConnection;
 use crate::db::queries::ip_pool::FilterOverlappingIpRanges;
-use async_bb8_diesel::{AsyncRunQueryDsl, PoolError};
+use async_bb8_diesel::{AsyncRunQueryDsl, ConnectionError, PoolError};
 use chrono::Utc;
 use diesel::prelude::*;
+use diesel::result::DatabaseErrorKind;
+use diesel::result::Error as DieselError;
 use ipnetwork::IpNetwork;
-use nexus_types::external_api::params;
 use nexus_types::external_api::shared::IpRange;
 use omicron_common::api::external::http_pagination::PaginatedBy;
 use omicron_common::api::external::CreateResult;
@@ -37,13 +39,25 @@ use omicron_common::api::external::Error;
 use omicron_common::api::external::ListResultVec;
 use omicron_common::api::external::LookupResult;
 use omicron_common::api::external::LookupType;
-use omicron_common::api::external::Name as ExternalName;
 use omicron_common::api::external::ResourceType;
 use omicron_common::api::external::UpdateResult;
 use ref_cast::RefCast;
-use std::str::FromStr;
 use uuid::Uuid;
 
+/// Name of the unique index that allows only one default IP pool for each
+/// Silo (and one for the fleet)
+const DEFAULT_IP_POOL_CONSTRAINT: &str = "ip_pool_default_per_silo";
+
+/// Returns the error for making a pool the default for a Silo (or for the
+/// fleet, if `silo_id` is `None`) that already has a default pool
+fn default_ip_pool_exists(silo_id: Option<Uuid>) -> Error {
+    if silo_id.is_some() {
+        Error::invalid_request("the Silo already has a default IP pool")
+    } else {
+        Error::invalid_request("the fleet already has a default IP pool")
+    }
+}
+
 impl DataStore {
     /// List IP Pools
     pub async fn ip_pools_list(
@@ -73,21 +87,68 @@ impl DataStore {
         .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
     }
 
-    /// Looks up the default IP pool by name.
+    /// Looks up the default IP pool for the caller's Silo.
+    ///
+    /// This is the Silo's own default pool, if it has one, and otherwise the
+    /// fleet's default pool.  Built-in users have no Silo, so for them this is
+    /// always the fleet's default pool.
     pub async fn ip_pools_fetch_default_for(
         &self,
         opctx: &OpContext,
         action: authz::Action,
     ) -> LookupResult<(authz::IpPool, IpPool)> {
-        self.ip_pools_fetch_for(
-            opctx,
-            action,
-            &Name(ExternalName::from_str("default").unwrap()),
+        use db::schema::ip_pool::dsl;
+
+        let silo_id = opctx.authn.silo_or_builtin()?.map(|silo| silo.id());
+        let query = dsl::ip_pool
+            .filter(dsl::internal.eq(false))
+            .filter(dsl::is_default.eq(true))
+            .filter(dsl::time_deleted.is_null())
+            .into_boxed();
+        let query = match silo_id {
+            Some(silo_id) => query
+                .filter(dsl::silo_id.eq(silo_id).or(dsl::silo_id.is_null())),
+            None => query.filter(dsl::silo_id.is_null()),
+        };
+        // The Silo's own default pool, if any, sorts before the fleet's.
+        let pool_id = diesel_pool_result_optional(
+            query
+                .order(dsl::silo_id.asc().nulls_last())
+                .select(dsl::id)
+                .first_async::<Uuid>(self.pool_authorized(opctx).await?)
+                .await,
         )
-        .await
+        .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))?
+        .ok_or_else(|| {
+            LookupType::ByCompositeId("default IP Pool".to_string())
+                .into_not_found(ResourceType::IpPool)
+        })?;
+
+        let (.., authz_pool, pool) = LookupPath::new(opctx, &self)
+            .ip_pool_id(pool_id)
+            .fetch_for(action)
+            .await?;
+        Ok((authz_pool, pool))
+    }
+
+    /// Looks up an IP pool by name if one is given, or else the default IP
+    /// pool for the caller's Silo.
+    pub(crate) async fn ip_pools_fetch_named_or_default(
+        &self,
+        opctx: &OpContext,
+        action: authz::Action,
+        name: Option<&Name>,
+    ) -> LookupResult<(authz::IpPool, IpPool)> {
+        match name {
+            Some(name) => self.ip_pools_fetch_for(opctx, action, name).await,
+            None => self.ip_pools_fetch_default_for(opctx, action).await,
+        }
     }
 
     /// Looks up an IP pool by name.
+    ///
+    /// Pools that are linked to a Silo other than the caller's are treated as
+    /// though they don't exist.
     pub(crate) async fn ip_pools_fetch_for(
         &self,
         opctx: &OpContext,
@@ -101,6 +162,12 @@ impl DataStore {
         if pool.internal {
             return Err(authz_pool.not_found());
         }
+        if let Some(pool_silo_id) = pool.silo_id {
+            let silo_id = opctx.authn.silo_or_builtin()?.map(|s| s.id());
+            if silo_id != Some(pool_silo_id) {
+                return Err(authz_pool.not_found());
+            }
+        }
 
         Ok((authz_pool, pool))
     }
@@ -152,30 +219,168 @@ impl DataStore {
 
     /// Creates a new IP pool.
     ///
-    /// - If `internal` is set, this IP pool is used for Oxide services.
+    /// If the pool is a default pool, it must be the only one for its Silo (or
+    /// for the fleet, if it isn't linked to a Silo).
     pub async fn ip_pool_create(
         &self,
         opctx: &OpContext,
-        new_pool: &params::IpPoolCreate,
-        internal: bool,
+        pool: IpPool,
     ) -> CreateResult<IpPool> {
         use db::schema::ip_pool::dsl;
         opctx
             .authorize(authz::Action::CreateChild, &authz::IP_POOL_LIST)
             .await?;
-        let pool = IpPool::new(&new_pool.identity, internal);
         let pool_name = pool.name().as_str().to_string();
+        let silo_id = pool.silo_id;
+
+        // This is also enforced by a unique index, but checking first gives a
+        // more useful error than a conflict on the pool's name.  (If the
+        // existing default pool has the same name, that conflict is the more
+        // useful error.)
+        if pool.is_default {
+            let query = dsl::ip_pool
+                .filter(dsl::is_default.eq(true))
+                .filter(dsl::name.ne(pool.name().as_str().to_string()))
+                .filter(dsl::time_deleted.is_null())
+                .into_boxed();
+            let query = match pool.silo_id {
+                Some(silo_id) => query.filter(dsl::silo_id.eq(silo_id)),
+                None => query.filter(dsl::silo_id.is_null()),
+            };
+            let existing = diesel_pool_result_optional(
+                query
+                    .select(dsl::id)
+                    .first_async::<Uuid>(self.pool_authorized(opctx).await?)
+                    .await,
+            )
+            .map_err(|e| {
+                public_error_from_diesel_pool(e, ErrorHandler::Server)
+            })?;
+            if existing.is_some() {
+                return Err(default_ip_pool_exists(silo_id));
+            }
+        }
 
         diesel::insert_into(dsl::ip_pool)
             .values(pool)
             .returning(IpPool::as_returning())
             .get_result_async(self.pool_authorized(opctx).await?)
             .await
-            .map_err(|e| {
-                public_error_from_diesel_pool(
+            .map_err(|e| match e {
+                // Another default pool was created after the check above.
+                PoolError::Connection(ConnectionError::Query(
+                    DieselError::DatabaseError(
+                        DatabaseErrorKind::UniqueViolation,
+                        ref info,
+                    ),
+                )) if info.constraint_name()
+                    == Some(DEFAULT_IP_POOL_CONSTRAINT) =>
+                {
+                    default_ip_pool_exists(silo_id)
+                }
+                e => public_error_from_diesel_pool(
                     e,
                     ErrorHandler::Conflict(ResourceType::IpPool, &pool_name),
-                )
+                ),
+            })
+    }
+
+    /// Links an IP pool to a Silo, or makes it available to every Silo if
+    /// `silo_id` is `None`, and sets whether it's the default pool for that
+    /// Silo (or for the fleet).
+    ///
+    /// As when creating a pool, a default pool must be the only one for its
+    /// Silo (or for the fleet).
+    pub async fn ip_pool_set_link(
+        &self,
+        opctx: &OpContext,
+        authz_pool: &authz::IpPool,
+        silo_id: Option<Uuid>,
+        is_default: bool,
+    ) -> UpdateResult<IpPool> {
+        use db::schema::ip_pool::dsl;
+        use db::schema::silo;
+        opctx.authorize(authz::Action::Modify, authz_pool).await?;
+
+        type TxnError = TransactionError<Error>;
+        let pool_id = authz_pool.id();
+        self.pool_authorized(opctx)
+            .await?
+            .transaction_async(|conn| async move {
+                // The Silo is checked in the same transaction so that it can't
+                // be deleted as the pool is linked to it.  (Deleting a Silo is
+                // refused while pools are linked to it.)
+                if let Some(silo_id) = silo_id {
+                    let silo_found = silo::dsl::silo
+                        .filter(silo::dsl::id.eq(silo_id))
+                        .filter(silo::dsl::time_deleted.is_null())
+                        .select(silo::dsl::id)
+                        .load_async::<Uuid>(&conn)
+                        .await?
+                        .pop();
+                    if silo_found.is_none() {
+                        return Err(TxnError::CustomError(
+                            Error::not_found_by_id(
+                                ResourceType::Silo,
+                                &silo_id,
+                            ),
+                        ));
+                    }
+                }
+
+                // This is also enforced by a unique index, but checking first
+                // gives the same error whichever way it's caught.
+                if is_default {
+                    let query = dsl::ip_pool
+                        .filter(dsl::is_default.eq(true))
+                        .filter(dsl::id.ne(pool_id))
+                        .filter(dsl::time_deleted.is_null())
+                        .into_boxed();
+                    let query = match silo_id {
+                        Some(silo_id) => query.filter(dsl::silo_id.eq(silo_id)),
+                        None => query.filter(dsl::silo_id.is_null()),
+                    };
+                    let existing =
+                        query.select(dsl::id).load_async::<Uuid>(&conn).await?;
+                    if !existing.is_empty() {
+                        return Err(TxnError::CustomError(
+                            default_ip_pool_exists(silo_id),
+                        ));
+                    }
+                }
+
+                diesel::update(dsl::ip_pool)
+                    .filter(dsl::id.eq(pool_id))
+                    .filter(dsl::internal.eq(false))
+                    .filter(dsl::time_deleted.is_null())
+                    .set((
+                        dsl::silo_id.eq(silo_id),
+                        dsl::is_default.eq(is_default),
+                        dsl::time_modified.eq(Utc::now()),
+                    ))
+                    .returning(IpPool::as_returning())
+                    .get_result_async(&conn)
+                    .await
+                    .map_err(TxnError::from)
+            })
+            .await
+            .map_err(|e| match e {
+                TxnError::CustomError(e) => e,
+                // Another default pool was set after the check above.
+                TxnError::Pool(PoolError::Connection(
+                    ConnectionError::Query(DieselError::DatabaseError(
+                        DatabaseErrorKind::UniqueViolation,
+                        ref info,
+                    )),
+                )) if info.constraint_name()
+                    == Some(DEFAULT_IP_POOL_CONSTRAINT) =>
+                {
+                    default_ip_pool_exists(silo_id)
+                }
+                TxnError::Pool(e) => public_error_from_diesel_pool(
+                    e,
+                    ErrorHandler::NotFoundByResource(authz_pool),
+                ),
             })
     }
 
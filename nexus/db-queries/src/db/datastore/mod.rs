@@ -47,6 +47,9 @@ use std::sync::Arc;
 use uuid::Uuid;
 
 mod address_lot;
+mod affinity;
+mod audit_log;
+mod bgp;
 mod certificate;
 mod console_session;
 mod dataset;
@@ -68,6 +71,7 @@ mod region;
 mod region_snapshot;
 mod role;
 mod saga;
+mod scim_client_bearer_token;
 mod service;
 mod silo;
 mod silo_group;
@@ -75,17 +79,22 @@ mod silo_user;
 mod sled;
 mod sled_instance;
 mod snapshot;
+mod snapshot_schedule;
 mod ssh_key;
 mod switch;
 mod switch_interface;
 mod switch_port;
 mod update;
 mod virtual_provisioning_collection;
+mod virtual_provisioning_quota;
 mod volume;
 mod vpc;
+mod vpc_peering;
+mod webhook;
 mod zpool;
 
 pub use address_lot::AddressLotCreateResult;
+pub use bgp::BgpAnnounceSetCreateResult;
 pub use dns::DnsVersionUpdateBuilder;
 pub use rack::RackInit;
 pub use silo::Discoverability;
@@ -307,6 +316,15 @@ pub enum RegionAllocationStrategy {
     Random(Option<u128>),
 }
 
+/// Returns the error for a conditional update or delete which found the
+/// resource at a version other than the ones that it was conditional on
+fn precondition_failed() -> Error {
+    Error::precondition_failed(
+        "resource has been modified since the version identified by the \
+         If-Match header",
+    )
+}
+
 /// Constructs a DataStore for use in test suites that has preloaded the
 /// built-in users, roles, and role assignments that are needed for basic
 /// operation
@@ -432,6 +450,7 @@ mod test {
                     name: "project".parse().unwrap(),
                     description: "desc".to_string(),
                 },
+                labels: Default::default(),
             },
         );
         datastore.project_create(&opctx, project).await.unwrap();
@@ -663,6 +682,7 @@ mod test {
                 block_size: params::BlockSize::try_from(4096).unwrap(),
             },
             size,
+            labels: Default::default(),
         }
     }
 
@@ -1405,6 +1425,7 @@ mod test {
                 ))),
                 first_port: crate::db::model::SqlU16(0),
                 last_port: crate::db::model::SqlU16(10),
+                project_id: None,
             })
             .collect::<Vec<_>>();
         diesel::insert_into(dsl::external_ip)
@@ -1464,6 +1485,7 @@ mod test {
             ))),
             first_port: crate::db::model::SqlU16(0),
             last_port: crate::db::model::SqlU16(10),
+            project_id: None,
         };
         diesel::insert_into(dsl::external_ip)
             .values(ip.clone())
@@ -1533,6 +1555,7 @@ mod test {
             ip: addresses.next().unwrap().into(),
             first_port: crate::db::model::SqlU16(0),
             last_port: crate::db::model::SqlU16(10),
+            project_id: None,
         };
 
         // Combinations of NULL and non-NULL for:
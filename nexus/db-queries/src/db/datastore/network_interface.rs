@@ -32,6 +32,7 @@ use chrono::Utc;
 use diesel::prelude::*;
 use omicron_common::api::external;
 use omicron_common::api::external::http_pagination::PaginatedBy;
+use omicron_common::api::external::DataPageParams;
 use omicron_common::api::external::DeleteResult;
 use omicron_common::api::external::Error;
 use omicron_common::api::external::ListResultVec;
@@ -362,6 +363,63 @@ impl DataStore {
         .await
     }
 
+    /// Return the names and addresses of the instances in a VPC required for
+    /// the sled agent to serve the VPC's DNS zone.
+    ///
+    /// Each instance whose primary network interface is in the VPC has a
+    /// record with the address of that interface.
+    pub async fn derive_vpc_dns_records(
+        &self,
+        opctx: &OpContext,
+        authz_vpc: &authz::Vpc,
+    ) -> ListResultVec<sled_client_types::VpcDnsRecord> {
+        opctx.authorize(authz::Action::ListChildren, authz_vpc).await?;
+
+        use db::schema::instance;
+        use db::schema::instance_network_interface as nic;
+        let rows = nic::table
+            .inner_join(instance::table.on(instance::id.eq(nic::instance_id)))
+            .filter(nic::vpc_id.eq(authz_vpc.id()))
+            .filter(nic::is_primary.eq(true))
+            .filter(nic::time_deleted.is_null())
+            .filter(instance::time_deleted.is_null())
+            .order_by(instance::name)
+            .select((instance::name, nic::ip))
+            .get_results_async::<(Name, ipnetwork::IpNetwork)>(
+                self.pool_authorized(opctx).await?,
+            )
+            .await
+            .map_err(|e| {
+                public_error_from_diesel_pool(e, ErrorHandler::Server)
+            })?;
+        Ok(rows
+            .into_iter()
+            .map(|(name, ip)| sled_client_types::VpcDnsRecord {
+                name: name.as_str().to_string(),
+                ip: ip.ip(),
+            })
+            .collect())
+    }
+
+    /// List the IDs of the VPCs in which any instance has its primary network
+    /// interface (paginated)
+    pub async fn vpc_dns_instance_vpcs_list(
+        &self,
+        opctx: &OpContext,
+        pagparams: &DataPageParams<'_, Uuid>,
+    ) -> ListResultVec<Uuid> {
+        opctx.authorize(authz::Action::Read, &authz::FLEET).await?;
+        use db::schema::instance_network_interface::dsl;
+        paginated(dsl::instance_network_interface, dsl::vpc_id, pagparams)
+            .filter(dsl::is_primary.eq(true))
+            .filter(dsl::time_deleted.is_null())
+            .select(dsl::vpc_id)
+            .distinct()
+            .load_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+    }
+
     /// List network interfaces associated with a given instance.
     pub async fn instance_list_network_interfaces(
         &self,
@@ -19,13 +19,17 @@ use crate::db::fixed_data::project::SERVICES_PROJECT;
 use crate::db::fixed_data::silo::INTERNAL_SILO_ID;
 use crate::db::identity::Resource;
 use crate::db::model::CollectionTypeProvisioned;
+use crate::db::model::Labels;
 use crate::db::model::Name;
 use crate::db::model::Project;
 use crate::db::model::ProjectUpdate;
 use crate::db::model::Silo;
 use crate::db::model::VirtualProvisioningCollection;
 use crate::db::pagination::paginated;
+use crate::db::update_and_check::UpdateAndCheck;
+use crate::db::update_and_check::UpdateStatus;
 use async_bb8_diesel::{AsyncConnection, AsyncRunQueryDsl, PoolError};
+use chrono::DateTime;
 use chrono::Utc;
 use diesel::prelude::*;
 use omicron_common::api::external::http_pagination::PaginatedBy;
@@ -33,6 +37,7 @@ use omicron_common::api::external::CreateResult;
 use omicron_common::api::external::DeleteResult;
 use omicron_common::api::external::Error;
 use omicron_common::api::external::InternalContext;
+use omicron_common::api::external::LabelSelector;
 use omicron_common::api::external::ListResultVec;
 use omicron_common::api::external::LookupType;
 use omicron_common::api::external::ResourceType;
@@ -213,13 +218,20 @@ impl DataStore {
     generate_fn_to_ensure_none_in_project!(project_image, name, String);
     generate_fn_to_ensure_none_in_project!(snapshot, name, String);
     generate_fn_to_ensure_none_in_project!(vpc, name, String);
+    generate_fn_to_ensure_none_in_project!(floating_ip, name, String);
+    generate_fn_to_ensure_none_in_project!(affinity_group, name, String);
+    generate_fn_to_ensure_none_in_project!(snapshot_schedule, name, String);
 
     /// Delete a project
+    ///
+    /// If `expected_time_modified` is provided, the project is only deleted if
+    /// its modification time is one of those given.
     pub async fn project_delete(
         &self,
         opctx: &OpContext,
         authz_project: &authz::Project,
         db_project: &db::model::Project,
+        expected_time_modified: Option<&[DateTime<Utc>]>,
     ) -> DeleteResult {
         opctx.authorize(authz::Action::Delete, authz_project).await?;
 
@@ -229,6 +241,10 @@ impl DataStore {
         self.ensure_no_project_images_in_project(opctx, authz_project).await?;
         self.ensure_no_snapshots_in_project(opctx, authz_project).await?;
         self.ensure_no_vpcs_in_project(opctx, authz_project).await?;
+        self.ensure_no_floating_ips_in_project(opctx, authz_project).await?;
+        self.ensure_no_affinity_groups_in_project(opctx, authz_project).await?;
+        self.ensure_no_snapshot_schedules_in_project(opctx, authz_project)
+            .await?;
 
         use db::schema::project::dsl;
 
@@ -237,22 +253,46 @@ impl DataStore {
             .await?
             .transaction_async(|conn| async move {
                 let now = Utc::now();
-                let updated_rows = diesel::update(dsl::project)
+                let delete_query = diesel::update(dsl::project)
                     .filter(dsl::time_deleted.is_null())
                     .filter(dsl::id.eq(authz_project.id()))
                     .filter(dsl::rcgen.eq(db_project.rcgen))
-                    .set(dsl::time_deleted.eq(now))
-                    .returning(Project::as_returning())
-                    .execute_async(&conn)
-                    .await
-                    .map_err(|e| {
-                        public_error_from_diesel_pool(
-                            PoolError::from(e),
-                            ErrorHandler::NotFoundByResource(authz_project),
-                        )
-                    })?;
+                    .set(dsl::time_deleted.eq(now));
+                let updated_rows = match expected_time_modified {
+                    None => delete_query.execute_async(&conn).await,
+                    Some(expected) => {
+                        delete_query
+                            .filter(
+                                dsl::time_modified.eq_any(expected.to_vec()),
+                            )
+                            .execute_async(&conn)
+                            .await
+                    }
+                }
+                .map_err(|e| {
+                    public_error_from_diesel_pool(
+                        PoolError::from(e),
+                        ErrorHandler::NotFoundByResource(authz_project),
+                    )
+                })?;
 
                 if updated_rows == 0 {
+                    // Distinguish a project that's been modified (or deleted)
+                    // since the version the caller expected from one that's
+                    // had a child resource created in it.
+                    if let Some(expected) = expected_time_modified {
+                        let time_modified = dsl::project
+                            .filter(dsl::time_deleted.is_null())
+                            .filter(dsl::id.eq(authz_project.id()))
+                            .select(dsl::time_modified)
+                            .load_async::<DateTime<Utc>>(&conn)
+                            .await?;
+                        if !time_modified.iter().any(|t| expected.contains(t)) {
+                            return Err(TxnError::CustomError(
+                                super::precondition_failed(),
+                            ));
+                        }
+                    }
                     return Err(TxnError::CustomError(Error::InvalidRequest {
                         message:
                             "deletion failed due to concurrent modification"
@@ -265,6 +305,11 @@ impl DataStore {
                     db_project.id(),
                 )
                 .await?;
+                self.virtual_provisioning_quota_delete_on_connection(
+                    &conn,
+                    db_project.id(),
+                )
+                .await?;
                 Ok(())
             })
             .await
@@ -281,13 +326,14 @@ impl DataStore {
         &self,
         opctx: &OpContext,
         pagparams: &PaginatedBy<'_>,
+        label_selector: Option<&LabelSelector>,
     ) -> ListResultVec<Project> {
         let authz_silo =
             opctx.authn.silo_required().internal_context("listing Projects")?;
         opctx.authorize(authz::Action::ListChildren, &authz_silo).await?;
 
         use db::schema::project::dsl;
-        match pagparams {
+        let mut query = match pagparams {
             PaginatedBy::Id(pagparams) => {
                 paginated(dsl::project, dsl::id, &pagparams)
             }
@@ -298,39 +344,75 @@ impl DataStore {
             ),
         }
         .filter(dsl::silo_id.eq(authz_silo.id()))
-        .filter(dsl::time_deleted.is_null())
-        .select(Project::as_select())
-        .load_async(self.pool_authorized(opctx).await?)
-        .await
-        .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+        .filter(dsl::time_deleted.is_null());
+        if let Some(label_selector) = label_selector {
+            query = query.filter(
+                dsl::labels.contains(Labels(label_selector.labels().clone())),
+            );
+        }
+        query
+            .select(Project::as_select())
+            .load_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
     }
 
-    /// Updates a project (clobbering update -- no etag)
+    /// Updates a project
+    ///
+    /// If `expected_time_modified` is provided, the project is only updated if
+    /// its modification time is one of those given.  Otherwise, this is a
+    /// clobbering update.
     pub async fn project_update(
         &self,
         opctx: &OpContext,
         authz_project: &authz::Project,
         updates: ProjectUpdate,
+        expected_time_modified: Option<&[DateTime<Utc>]>,
     ) -> UpdateResult<Project> {
         opctx.authorize(authz::Action::Modify, authz_project).await?;
 
         use db::schema::project::dsl;
-        diesel::update(dsl::project)
+        let update_query = diesel::update(dsl::project)
             .filter(dsl::time_deleted.is_null())
-            .filter(dsl::id.eq(authz_project.id()))
+            .filter(dsl::id.eq(authz_project.id()));
+        let Some(expected) = expected_time_modified else {
+            return update_query
+                .set(updates)
+                .returning(Project::as_returning())
+                .get_result_async(self.pool_authorized(opctx).await?)
+                .await
+                .map_err(|e| {
+                    public_error_from_diesel_pool(
+                        e,
+                        ErrorHandler::NotFoundByResource(authz_project),
+                    )
+                });
+        };
+
+        let result = update_query
+            .filter(dsl::time_modified.eq_any(expected.to_vec()))
             .set(updates)
-            .returning(Project::as_returning())
-            .get_result_async(self.pool_authorized(opctx).await?)
+            .check_if_exists::<Project>(authz_project.id())
+            .execute_and_check(self.pool_authorized(opctx).await?)
             .await
             .map_err(|e| {
                 public_error_from_diesel_pool(
                     e,
                     ErrorHandler::NotFoundByResource(authz_project),
                 )
-            })
+            })?;
+        match result.status {
+            UpdateStatus::Updated => Ok(result.found),
+            UpdateStatus::NotUpdatedButExists => {
+                Err(super::precondition_failed())
+            }
+        }
     }
 
     /// List IP Pools accessible to a project
+    ///
+    /// These are the pools linked to the caller's Silo, which is also the
+    /// project's Silo, and the pools linked to no Silo at all.
     pub async fn project_ip_pools_list(
         &self,
         opctx: &OpContext,
@@ -339,6 +421,7 @@ impl DataStore {
     ) -> ListResultVec<db::model::IpPool> {
         use db::schema::ip_pool::dsl;
         opctx.authorize(authz::Action::ListChildren, authz_project).await?;
+        let silo_id = opctx.authn.silo_required()?.id();
         match pagparams {
             PaginatedBy::Id(pagparams) => {
                 paginated(dsl::ip_pool, dsl::id, pagparams)
@@ -351,6 +434,7 @@ impl DataStore {
         }
         // TODO(2148, 2056): filter only pools accessible by the given
         // project, once specific projects for pools are implemented
+        .filter(dsl::silo_id.eq(silo_id).or(dsl::silo_id.is_null()))
         .filter(dsl::internal.eq(false))
         .filter(dsl::time_deleted.is_null())
         .select(db::model::IpPool::as_select())
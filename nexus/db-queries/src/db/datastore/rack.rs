@@ -556,13 +556,11 @@ impl DataStore {
 
         self.rack_insert(opctx, &db::model::Rack::new(rack_id)).await?;
 
-        let params = external_params::IpPoolCreate {
-            identity: IdentityMetadataCreateParams {
-                name: SERVICE_IP_POOL_NAME.parse::<Name>().unwrap(),
-                description: String::from("IP Pool for Oxide Services"),
-            },
+        let identity = IdentityMetadataCreateParams {
+            name: SERVICE_IP_POOL_NAME.parse::<Name>().unwrap(),
+            description: String::from("IP Pool for Oxide Services"),
         };
-        self.ip_pool_create(opctx, &params, /*internal=*/ true)
+        self.ip_pool_create(opctx, db::model::IpPool::new_internal(&identity))
             .await
             .map(|_| ())
             .or_else(|e| match e {
@@ -570,13 +568,14 @@ impl DataStore {
                 _ => Err(e),
             })?;
 
-        let params = external_params::IpPoolCreate {
-            identity: IdentityMetadataCreateParams {
-                name: "default".parse::<Name>().unwrap(),
-                description: String::from("default IP pool"),
-            },
+        let identity = IdentityMetadataCreateParams {
+            name: "default".parse::<Name>().unwrap(),
+            description: String::from("default IP pool"),
         };
-        self.ip_pool_create(opctx, &params, /*internal=*/ false)
+        let fleet_default_pool = db::model::IpPool::new(
+            &identity, /* silo_id= */ None, /* is_default= */ true,
+        );
+        self.ip_pool_create(opctx, fleet_default_pool)
             .await
             .map(|_| ())
             .or_else(|e| match e {
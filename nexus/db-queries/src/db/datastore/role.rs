@@ -247,20 +247,45 @@ impl DataStore {
     ///
     /// This function is generic over all resources that can accept roles (e.g.,
     /// Fleet, Silo, etc.).
-    // TODO-correctness As with the rest of the API, we're lacking an ability
-    // for an ETag precondition check here.
+    pub async fn role_assignment_replace_visible<T>(
+        &self,
+        opctx: &OpContext,
+        authz_resource: &T,
+        new_assignments: &[shared::RoleAssignment<T::AllowedRoles>],
+    ) -> ListResultVec<db::model::RoleAssignment>
+    where
+        T: authz::ApiResourceWithRolesType + AuthorizedResource + Clone,
+    {
+        self.role_assignment_replace_visible_conditional(
+            opctx,
+            authz_resource,
+            new_assignments,
+            |_| Ok(()),
+        )
+        .await
+    }
+
+    /// Like [`DataStore::role_assignment_replace_visible()`], but only
+    /// replaces the role assignments if `precondition` accepts the current
+    /// ones
+    ///
+    /// `precondition` is invoked with the current externally-visible role
+    /// assignments (in the order they're listed) inside the same transaction
+    /// that replaces them, so an error from it means that nothing was changed.
     // TODO-scalability In an ideal world, this would update in batches.  That's
     // tricky without first-classing the Policy in the database.  The impact is
     // mitigated because we cap the number of role assignments per resource
     // pretty tightly.
-    pub async fn role_assignment_replace_visible<T>(
+    pub async fn role_assignment_replace_visible_conditional<T, P>(
         &self,
         opctx: &OpContext,
         authz_resource: &T,
         new_assignments: &[shared::RoleAssignment<T::AllowedRoles>],
+        precondition: P,
     ) -> ListResultVec<db::model::RoleAssignment>
     where
         T: authz::ApiResourceWithRolesType + AuthorizedResource + Clone,
+        P: FnOnce(&[RoleAssignment]) -> Result<(), Error> + Send,
     {
         opctx.authorize(authz::Action::ModifyPolicy, authz_resource).await?;
 
@@ -286,6 +311,17 @@ impl DataStore {
         self.pool_authorized(opctx)
             .await?
             .transaction_async(|conn| async move {
+                let current_assignments = self
+                    .role_assignment_fetch_visible_conn(
+                        opctx,
+                        &authz_resource,
+                        &conn,
+                    )
+                    .await
+                    .map_err(TransactionError::CustomError)?;
+                precondition(&current_assignments)
+                    .map_err(TransactionError::CustomError)?;
+
                 delete_old_query.execute_async(&conn).await?;
                 Ok(insert_new_query.get_results_async(&conn).await?)
             })
@@ -0,0 +1,131 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! [`DataStore`] methods related to [`ScimClientBearerToken`]s.
+
+use super::DataStore;
+use crate::authz;
+use crate::context::OpContext;
+use crate::db;
+use crate::db::error::public_error_from_diesel_pool;
+use crate::db::error::ErrorHandler;
+use crate::db::model::ScimClientBearerToken;
+use crate::db::pagination::paginated;
+use async_bb8_diesel::AsyncRunQueryDsl;
+use async_bb8_diesel::OptionalExtension;
+use diesel::prelude::*;
+use omicron_common::api::external::CreateResult;
+use omicron_common::api::external::DataPageParams;
+use omicron_common::api::external::DeleteResult;
+use omicron_common::api::external::Error;
+use omicron_common::api::external::ListResultVec;
+use omicron_common::api::external::LookupResult;
+use omicron_common::api::external::LookupType;
+use omicron_common::api::external::ResourceType;
+use uuid::Uuid;
+
+impl DataStore {
+    /// Create a bearer token for a Silo's SCIM client
+    pub async fn scim_client_bearer_token_create(
+        &self,
+        opctx: &OpContext,
+        authz_silo: &authz::Silo,
+        token: ScimClientBearerToken,
+    ) -> CreateResult<ScimClientBearerToken> {
+        assert_eq!(authz_silo.id(), token.silo_id);
+        // Anybody with one of these tokens can create and remove users in the
+        // Silo, so we treat them as part of the Silo's configuration.
+        opctx.authorize(authz::Action::Modify, authz_silo).await?;
+
+        use db::schema::scim_client_bearer_token::dsl;
+        diesel::insert_into(dsl::scim_client_bearer_token)
+            .values(token)
+            .returning(ScimClientBearerToken::as_returning())
+            .get_result_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+    }
+
+    /// List the bearer tokens of a Silo's SCIM clients, including expired ones
+    pub async fn scim_client_bearer_tokens_list(
+        &self,
+        opctx: &OpContext,
+        authz_silo: &authz::Silo,
+        pagparams: &DataPageParams<'_, Uuid>,
+    ) -> ListResultVec<ScimClientBearerToken> {
+        opctx.authorize(authz::Action::Modify, authz_silo).await?;
+
+        use db::schema::scim_client_bearer_token::dsl;
+        paginated(dsl::scim_client_bearer_token, dsl::id, pagparams)
+            .filter(dsl::silo_id.eq(authz_silo.id()))
+            .select(ScimClientBearerToken::as_select())
+            .load_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+    }
+
+    /// Revoke one of a Silo's SCIM client bearer tokens.  The token can no
+    /// longer be used as soon as this returns.
+    pub async fn scim_client_bearer_token_delete(
+        &self,
+        opctx: &OpContext,
+        authz_silo: &authz::Silo,
+        token_id: Uuid,
+    ) -> DeleteResult {
+        opctx.authorize(authz::Action::Modify, authz_silo).await?;
+
+        use db::schema::scim_client_bearer_token::dsl;
+        let deleted = diesel::delete(dsl::scim_client_bearer_token)
+            .filter(dsl::id.eq(token_id))
+            .filter(dsl::silo_id.eq(authz_silo.id()))
+            .execute_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel_pool(e, ErrorHandler::Server)
+            })?;
+        if deleted == 0 {
+            return Err(Error::ObjectNotFound {
+                type_name: ResourceType::ScimClientBearerToken,
+                lookup_type: LookupType::ById(token_id),
+            });
+        }
+        Ok(())
+    }
+
+    /// Find the Silo whose SCIM client presented `bearer_token`
+    ///
+    /// Returns `Ok(None)` if there is no such token.  The caller is
+    /// responsible for checking whether the token has expired.
+    pub async fn scim_client_bearer_token_lookup(
+        &self,
+        opctx: &OpContext,
+        bearer_token: &str,
+    ) -> LookupResult<Option<(authz::Silo, ScimClientBearerToken)>> {
+        use db::schema::scim_client_bearer_token::dsl;
+        let Some(token) = dsl::scim_client_bearer_token
+            .filter(dsl::bearer_token.eq(bearer_token.to_string()))
+            .select(ScimClientBearerToken::as_select())
+            .first_async::<ScimClientBearerToken>(
+                self.pool_authorized(opctx).await?,
+            )
+            .await
+            .optional()
+            .map_err(|e| {
+                public_error_from_diesel_pool(e, ErrorHandler::Server)
+            })?
+        else {
+            return Ok(None);
+        };
+
+        // As with other authentication lookups, we can only check
+        // authorization once we know which Silo the token belongs to.
+        let authz_silo = authz::Silo::new(
+            authz::FLEET,
+            token.silo_id,
+            LookupType::ById(token.silo_id),
+        );
+        opctx.authorize(authz::Action::ListChildren, &authz_silo).await?;
+        Ok(Some((authz_silo, token)))
+    }
+}
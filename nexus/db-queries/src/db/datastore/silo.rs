@@ -337,6 +337,7 @@ impl DataStore {
         assert_eq!(authz_silo.id(), db_silo.id());
         opctx.authorize(authz::Action::Delete, authz_silo).await?;
 
+        use db::schema::ip_pool;
         use db::schema::project;
         use db::schema::silo;
         use db::schema::silo_group;
@@ -370,6 +371,25 @@ impl DataStore {
         self.pool_authorized(opctx)
             .await?
             .transaction_async(|conn| async move {
+                // IP pools linked to the silo would be left referring to a
+                // silo that no longer exists, so they must be unlinked or
+                // deleted first.  (Unlinking them here instead would make them
+                // available to every silo.)
+                let pool_found = ip_pool::dsl::ip_pool
+                    .filter(ip_pool::dsl::silo_id.eq(id))
+                    .filter(ip_pool::dsl::time_deleted.is_null())
+                    .select(ip_pool::dsl::id)
+                    .limit(1)
+                    .load_async::<Uuid>(&conn)
+                    .await?
+                    .pop();
+                if pool_found.is_some() {
+                    return Err(TxnError::CustomError(Error::InvalidRequest {
+                        message: "silo to be deleted has linked IP pools"
+                            .to_string(),
+                    }));
+                }
+
                 let updated_rows = diesel::update(silo::dsl::silo)
                     .filter(silo::dsl::time_deleted.is_null())
                     .filter(silo::dsl::id.eq(id))
@@ -395,6 +415,10 @@ impl DataStore {
                     &conn,
                     id,
                 ).await?;
+                self.virtual_provisioning_quota_delete_on_connection(
+                    &conn,
+                    id,
+                ).await?;
 
                 self.dns_update(dns_opctx, &conn, dns_update).await?;
 
@@ -487,6 +511,69 @@ impl DataStore {
             "deleted {} silo groups for silo {}", updated_rows, id
         );
 
+        // stop sharing images with this silo, and this silo's images with
+        // other silos
+        use db::schema::image::dsl as image_dsl;
+        use db::schema::image_silo_grant::dsl as grant_dsl;
+
+        let updated_rows = diesel::delete(grant_dsl::image_silo_grant)
+            .filter(
+                grant_dsl::silo_id.eq(id).or(grant_dsl::image_id.eq_any(
+                    image_dsl::image
+                        .filter(image_dsl::silo_id.eq(id))
+                        .select(image_dsl::id),
+                )),
+            )
+            .execute_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel_pool(e, ErrorHandler::Server)
+            })?;
+
+        debug!(
+            opctx.log,
+            "deleted {} image grants for silo {}", updated_rows, id
+        );
+
+        // delete all silo webhook receivers, and with them the deliveries yet
+        // to be made to them
+        use db::schema::webhook_delivery::dsl as delivery_dsl;
+        use db::schema::webhook_receiver::dsl as receiver_dsl;
+
+        let updated_rows = diesel::delete(delivery_dsl::webhook_delivery)
+            .filter(
+                delivery_dsl::receiver_id.eq_any(
+                    receiver_dsl::webhook_receiver
+                        .filter(receiver_dsl::silo_id.eq(id))
+                        .select(receiver_dsl::id),
+                ),
+            )
+            .execute_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel_pool(e, ErrorHandler::Server)
+            })?;
+
+        debug!(
+            opctx.log,
+            "deleted {} webhook deliveries for silo {}", updated_rows, id
+        );
+
+        let updated_rows = diesel::update(receiver_dsl::webhook_receiver)
+            .filter(receiver_dsl::silo_id.eq(id))
+            .filter(receiver_dsl::time_deleted.is_null())
+            .set(receiver_dsl::time_deleted.eq(now))
+            .execute_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel_pool(e, ErrorHandler::Server)
+            })?;
+
+        debug!(
+            opctx.log,
+            "deleted {} webhook receivers for silo {}", updated_rows, id
+        );
+
         // delete all silo identity providers
         use db::schema::identity_provider::dsl as idp_dsl;
 
@@ -519,6 +606,39 @@ impl DataStore {
             "deleted {} silo saml IdPs for silo {}", updated_rows, id
         );
 
+        use db::schema::oidc_identity_provider::dsl as oidc_idp_dsl;
+
+        let updated_rows = diesel::update(oidc_idp_dsl::oidc_identity_provider)
+            .filter(oidc_idp_dsl::silo_id.eq(id))
+            .filter(oidc_idp_dsl::time_deleted.is_null())
+            .set(oidc_idp_dsl::time_deleted.eq(Utc::now()))
+            .execute_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel_pool(e, ErrorHandler::Server)
+            })?;
+
+        debug!(
+            opctx.log,
+            "deleted {} silo oidc IdPs for silo {}", updated_rows, id
+        );
+
+        // delete SCIM client bearer tokens
+        use db::schema::scim_client_bearer_token::dsl as scim_dsl;
+
+        let updated_rows = diesel::delete(scim_dsl::scim_client_bearer_token)
+            .filter(scim_dsl::silo_id.eq(id))
+            .execute_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel_pool(e, ErrorHandler::Server)
+            })?;
+
+        debug!(
+            opctx.log,
+            "deleted {} SCIM client tokens for silo {}", updated_rows, id
+        );
+
         // delete certificates
         use db::schema::certificate::dsl as cert_dsl;
 
@@ -14,9 +14,11 @@ use crate::db::error::ErrorHandler;
 use crate::db::error::TransactionError;
 use crate::db::model::SiloGroup;
 use crate::db::model::SiloGroupMembership;
+use crate::db::model::SiloGroupUpdate;
 use crate::db::pagination::paginated;
 use async_bb8_diesel::AsyncRunQueryDsl;
 use async_bb8_diesel::{AsyncConnection, OptionalExtension};
+use async_bb8_diesel::{ConnectionError, PoolError};
 use chrono::Utc;
 use diesel::prelude::*;
 use omicron_common::api::external::CreateResult;
@@ -26,6 +28,7 @@ use omicron_common::api::external::Error;
 use omicron_common::api::external::InternalContext;
 use omicron_common::api::external::ListResultVec;
 use omicron_common::api::external::LookupResult;
+use omicron_common::api::external::ResourceType;
 use omicron_common::api::external::UpdateResult;
 use uuid::Uuid;
 
@@ -68,6 +71,72 @@ impl DataStore {
             .unwrap())
     }
 
+    /// Create a silo group, failing if one with the same external id already
+    /// exists
+    ///
+    /// Unlike [`DataStore::silo_group_ensure`], this is for callers that are
+    /// explicitly asked to create a group, like SCIM clients.
+    pub async fn silo_group_create(
+        &self,
+        opctx: &OpContext,
+        authz_silo: &authz::Silo,
+        silo_group: SiloGroup,
+    ) -> CreateResult<SiloGroup> {
+        opctx.authorize(authz::Action::CreateChild, authz_silo).await?;
+
+        let external_id = silo_group.external_id.clone();
+        use db::schema::silo_group::dsl;
+        diesel::insert_into(dsl::silo_group)
+            .values(silo_group)
+            .returning(SiloGroup::as_returning())
+            .get_result_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel_pool(
+                    e,
+                    ErrorHandler::Conflict(
+                        ResourceType::SiloGroup,
+                        &external_id,
+                    ),
+                )
+            })
+    }
+
+    /// Update a silo group's external id
+    pub async fn silo_group_update(
+        &self,
+        opctx: &OpContext,
+        authz_silo_group: &authz::SiloGroup,
+        updates: SiloGroupUpdate,
+    ) -> UpdateResult<SiloGroup> {
+        opctx.authorize(authz::Action::Modify, authz_silo_group).await?;
+
+        let external_id = updates.external_id.clone().unwrap_or_default();
+        use db::schema::silo_group::dsl;
+        diesel::update(dsl::silo_group)
+            .filter(dsl::id.eq(authz_silo_group.id()))
+            .filter(dsl::time_deleted.is_null())
+            .set(updates)
+            .returning(SiloGroup::as_returning())
+            .get_result_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| match e {
+                PoolError::Connection(ConnectionError::Query(
+                    diesel::result::Error::NotFound,
+                )) => public_error_from_diesel_pool(
+                    e,
+                    ErrorHandler::NotFoundByResource(authz_silo_group),
+                ),
+                e => public_error_from_diesel_pool(
+                    e,
+                    ErrorHandler::Conflict(
+                        ResourceType::SiloGroup,
+                        &external_id,
+                    ),
+                ),
+            })
+    }
+
     pub async fn silo_group_optional_lookup(
         &self,
         opctx: &OpContext,
@@ -106,6 +175,24 @@ impl DataStore {
             .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
     }
 
+    pub async fn silo_group_membership_for_group(
+        &self,
+        opctx: &OpContext,
+        authz_silo_group: &authz::SiloGroup,
+    ) -> ListResultVec<SiloGroupMembership> {
+        opctx.authorize(authz::Action::Read, authz_silo_group).await?;
+
+        // TODO-scalability SCIM clients expect to see all of a group's members
+        // at once, so this isn't paginated.
+        use db::schema::silo_group_membership::dsl;
+        dsl::silo_group_membership
+            .filter(dsl::silo_group_id.eq(authz_silo_group.id()))
+            .select(SiloGroupMembership::as_returning())
+            .get_results_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+    }
+
     pub async fn silo_groups_for_self(
         &self,
         opctx: &OpContext,
@@ -181,6 +268,110 @@ impl DataStore {
             .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
     }
 
+    /// Update a silo group's membership
+    ///
+    /// Each user in `add_silo_user_ids` becomes a member of the group (if they
+    /// weren't already), and each user in `remove_silo_user_ids` stops being
+    /// a member.  If `replace` is true, all existing members are removed
+    /// first, so the group ends up with exactly the members in
+    /// `add_silo_user_ids`.
+    pub async fn silo_group_membership_update_for_group(
+        &self,
+        opctx: &OpContext,
+        authz_silo_group: &authz::SiloGroup,
+        replace: bool,
+        add_silo_user_ids: Vec<Uuid>,
+        remove_silo_user_ids: Vec<Uuid>,
+    ) -> UpdateResult<()> {
+        opctx.authorize(authz::Action::Modify, authz_silo_group).await?;
+
+        let silo_group_id = authz_silo_group.id();
+        self.pool_authorized(opctx)
+            .await?
+            .transaction_async(|conn| async move {
+                use db::schema::silo_group_membership::dsl;
+
+                if replace {
+                    diesel::delete(dsl::silo_group_membership)
+                        .filter(dsl::silo_group_id.eq(silo_group_id))
+                        .execute_async(&conn)
+                        .await?;
+                } else if !remove_silo_user_ids.is_empty() {
+                    diesel::delete(dsl::silo_group_membership)
+                        .filter(dsl::silo_group_id.eq(silo_group_id))
+                        .filter(dsl::silo_user_id.eq_any(remove_silo_user_ids))
+                        .execute_async(&conn)
+                        .await?;
+                }
+
+                let silo_group_memberships: Vec<
+                    db::model::SiloGroupMembership,
+                > = add_silo_user_ids
+                    .iter()
+                    .map(|user_id| {
+                        db::model::SiloGroupMembership::new(
+                            silo_group_id,
+                            *user_id,
+                        )
+                    })
+                    .collect();
+
+                diesel::insert_into(dsl::silo_group_membership)
+                    .values(silo_group_memberships)
+                    .on_conflict_do_nothing()
+                    .execute_async(&conn)
+                    .await?;
+
+                Ok(())
+            })
+            .await
+            .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+    }
+
+    /// List the groups in a Silo ordered by id, starting at `offset`
+    ///
+    /// This supports SCIM clients, which page through resources by index
+    /// rather than by marker.
+    pub async fn silo_groups_list_by_offset(
+        &self,
+        opctx: &OpContext,
+        authz_silo: &authz::Silo,
+        offset: i64,
+        limit: i64,
+    ) -> ListResultVec<SiloGroup> {
+        use db::schema::silo_group::dsl;
+
+        opctx.authorize(authz::Action::ListChildren, authz_silo).await?;
+        dsl::silo_group
+            .filter(dsl::silo_id.eq(authz_silo.id()))
+            .filter(dsl::time_deleted.is_null())
+            .order(dsl::id.asc())
+            .offset(offset)
+            .limit(limit)
+            .select(SiloGroup::as_select())
+            .load_async::<SiloGroup>(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+    }
+
+    /// Count the groups in a Silo
+    pub async fn silo_groups_count(
+        &self,
+        opctx: &OpContext,
+        authz_silo: &authz::Silo,
+    ) -> LookupResult<i64> {
+        use db::schema::silo_group::dsl;
+
+        opctx.authorize(authz::Action::ListChildren, authz_silo).await?;
+        dsl::silo_group
+            .filter(dsl::silo_id.eq(authz_silo.id()))
+            .filter(dsl::time_deleted.is_null())
+            .count()
+            .get_result_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+    }
+
     pub async fn silo_group_delete(
         &self,
         opctx: &OpContext,
@@ -17,12 +17,15 @@ use crate::db::model::Silo;
 use crate::db::model::SiloUser;
 use crate::db::model::SiloUserPasswordHash;
 use crate::db::model::SiloUserPasswordUpdate;
+use crate::db::model::SiloUserUpdate;
 use crate::db::model::UserBuiltin;
 use crate::db::model::UserProvisionType;
 use crate::db::pagination::paginated;
 use crate::db::update_and_check::UpdateAndCheck;
 use async_bb8_diesel::AsyncConnection;
 use async_bb8_diesel::AsyncRunQueryDsl;
+use async_bb8_diesel::ConnectionError;
+use async_bb8_diesel::PoolError;
 use chrono::Utc;
 use diesel::prelude::*;
 use nexus_types::external_api::params;
@@ -242,6 +245,123 @@ impl DataStore {
             .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
     }
 
+    /// List the users in a Silo ordered by id, starting at `offset`
+    ///
+    /// This supports SCIM clients, which page through resources by index
+    /// rather than by marker.
+    pub async fn silo_users_list_by_offset(
+        &self,
+        opctx: &OpContext,
+        authz_silo_user_list: &authz::SiloUserList,
+        offset: i64,
+        limit: i64,
+    ) -> ListResultVec<SiloUser> {
+        use db::schema::silo_user::dsl;
+
+        opctx
+            .authorize(authz::Action::ListChildren, authz_silo_user_list)
+            .await?;
+
+        dsl::silo_user
+            .filter(dsl::silo_id.eq(authz_silo_user_list.silo().id()))
+            .filter(dsl::time_deleted.is_null())
+            .order(dsl::id.asc())
+            .offset(offset)
+            .limit(limit)
+            .select(SiloUser::as_select())
+            .load_async::<SiloUser>(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+    }
+
+    /// Count the users in a Silo
+    pub async fn silo_users_count(
+        &self,
+        opctx: &OpContext,
+        authz_silo_user_list: &authz::SiloUserList,
+    ) -> LookupResult<i64> {
+        use db::schema::silo_user::dsl;
+
+        opctx
+            .authorize(authz::Action::ListChildren, authz_silo_user_list)
+            .await?;
+
+        dsl::silo_user
+            .filter(dsl::silo_id.eq(authz_silo_user_list.silo().id()))
+            .filter(dsl::time_deleted.is_null())
+            .count()
+            .get_result_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+    }
+
+    /// Update a silo user's external id or whether they're active
+    ///
+    /// Deactivating a user also deletes their console sessions and device
+    /// access tokens so that they're logged out right away.
+    pub async fn silo_user_update(
+        &self,
+        opctx: &OpContext,
+        authz_silo_user: &authz::SiloUser,
+        updates: SiloUserUpdate,
+    ) -> UpdateResult<SiloUser> {
+        opctx.authorize(authz::Action::Modify, authz_silo_user).await?;
+
+        let authz_silo_user_id = authz_silo_user.id();
+        let deactivating = updates.active == Some(false);
+        let external_id = updates.external_id.clone().unwrap_or_default();
+        self.pool_authorized(opctx)
+            .await?
+            .transaction_async(|conn| async move {
+                let db_silo_user = {
+                    use db::schema::silo_user::dsl;
+                    diesel::update(dsl::silo_user)
+                        .filter(dsl::id.eq(authz_silo_user_id))
+                        .filter(dsl::time_deleted.is_null())
+                        .set(updates)
+                        .returning(SiloUser::as_returning())
+                        .get_result_async(&conn)
+                        .await?
+                };
+
+                if deactivating {
+                    {
+                        use db::schema::console_session::dsl;
+                        diesel::delete(dsl::console_session)
+                            .filter(dsl::silo_user_id.eq(authz_silo_user_id))
+                            .execute_async(&conn)
+                            .await?;
+                    }
+
+                    {
+                        use db::schema::device_access_token::dsl;
+                        diesel::delete(dsl::device_access_token)
+                            .filter(dsl::silo_user_id.eq(authz_silo_user_id))
+                            .execute_async(&conn)
+                            .await?;
+                    }
+                }
+
+                Ok(db_silo_user)
+            })
+            .await
+            .map_err(|e| match e {
+                PoolError::Connection(ConnectionError::Query(
+                    diesel::result::Error::NotFound,
+                )) => public_error_from_diesel_pool(
+                    e,
+                    ErrorHandler::NotFoundByResource(authz_silo_user),
+                ),
+                e => public_error_from_diesel_pool(
+                    e,
+                    ErrorHandler::Conflict(
+                        ResourceType::SiloUser,
+                        &external_id,
+                    ),
+                ),
+            })
+    }
+
     /// Updates or deletes the password hash for a given Silo user
     ///
     /// If `password_hash` is `Some(...)`, the provided value is stored as the
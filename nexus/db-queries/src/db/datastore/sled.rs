@@ -15,8 +15,10 @@ use crate::db::identity::Asset;
 use crate::db::model::Sled;
 use crate::db::model::SledResource;
 use crate::db::pagination::paginated;
+use crate::db::pool::DbConnection;
 use async_bb8_diesel::AsyncConnection;
 use async_bb8_diesel::AsyncRunQueryDsl;
+use async_bb8_diesel::PoolError;
 use chrono::Utc;
 use diesel::prelude::*;
 use omicron_common::api::external;
@@ -84,9 +86,11 @@ impl DataStore {
         #[derive(Debug)]
         enum SledReservationError {
             NotFound,
+            Affinity(external::Error),
         }
         type TxnError = TransactionError<SledReservationError>;
 
+        let has_affinity = constraints.affinity().is_some();
         self.pool_authorized(opctx)
             .await?
             .transaction_async(|conn| async move {
@@ -141,16 +145,71 @@ impl DataStore {
                     .select(sled_dsl::id)
                     .into_boxed();
 
+                // Resolve any affinity constraints against the current
+                // membership and placement of the instance's affinity groups.
+                // This happens here, rather than before the transaction, so
+                // that the sled is chosen according to the same view of the
+                // groups that the reservation is committed against.
+                let affinity = match constraints.affinity() {
+                    Some((instance_id, joining)) => self
+                        .affinity_constraints_on_connection(
+                            &conn,
+                            instance_id,
+                            joining,
+                        )
+                        .await
+                        .map_err(|e| match e {
+                            TransactionError::CustomError(e) => {
+                                TxnError::CustomError(
+                                    SledReservationError::Affinity(e),
+                                )
+                            }
+                            TransactionError::Pool(e) => TxnError::Pool(e),
+                        })?
+                        .build(),
+                    None => db::model::SledReservationConstraints::none(),
+                };
+
                 // Further constrain the sled IDs according to any caller-
-                // supplied constraints.
-                if let Some(must_select_from) = constraints.must_select_from() {
-                    sled_targets = sled_targets
-                        .filter(sled_dsl::id.eq_any(must_select_from.to_vec()));
+                // supplied constraints, and those of the affinity groups.
+                for constraints in [&constraints, &affinity] {
+                    if let Some(must_select_from) =
+                        constraints.must_select_from()
+                    {
+                        sled_targets = sled_targets.filter(
+                            sled_dsl::id.eq_any(must_select_from.to_vec()),
+                        );
+                    }
+                    if !constraints.must_not_select_from().is_empty() {
+                        sled_targets = sled_targets.filter(diesel::dsl::not(
+                            sled_dsl::id.eq_any(
+                                constraints.must_not_select_from().to_vec(),
+                            ),
+                        ));
+                    }
                 }
 
+                // Among the remaining sleds, pick preferred sleds first and
+                // sleds to be avoided last, choosing randomly among equals.
+                let prefer = constraints
+                    .prefer()
+                    .iter()
+                    .chain(affinity.prefer())
+                    .copied()
+                    .collect::<Vec<_>>();
+                let avoid = constraints
+                    .avoid()
+                    .iter()
+                    .chain(affinity.avoid())
+                    .copied()
+                    .collect::<Vec<_>>();
                 sql_function!(fn random() -> diesel::sql_types::Float);
                 let sled_targets = sled_targets
-                    .order(random())
+                    .order((
+                        sled_dsl::id.eq_any(prefer).desc(),
+                        sled_dsl::id.eq_any(avoid).asc(),
+                        random(),
+                    ))
                     .limit(1)
                     .get_results_async::<Uuid>(&conn)
                     .await?;
@@ -179,16 +238,104 @@ impl DataStore {
             .await
             .map_err(|e| match e {
                 TxnError::CustomError(SledReservationError::NotFound) => {
-                    external::Error::unavail(
-                        "No sleds can fit the requested instance",
-                    )
+                    if has_affinity {
+                        external::Error::unavail(
+                            "No sleds that satisfy the instance's affinity \
+                            groups can fit the requested instance",
+                        )
+                    } else {
+                        external::Error::unavail(
+                            "No sleds can fit the requested instance",
+                        )
+                    }
                 }
+                TxnError::CustomError(SledReservationError::Affinity(e)) => e,
                 TxnError::Pool(e) => {
                     public_error_from_diesel_pool(e, ErrorHandler::Server)
                 }
             })
     }
 
+    /// Changes the resources reserved by the existing reservation
+    /// `resource_id`, without moving it to a different sled.
+    ///
+    /// Fails with a "service unavailable" error if the sled hosting the
+    /// reservation cannot fit the new resources alongside its other
+    /// reservations. Does nothing if there is no such reservation.
+    pub(crate) async fn sled_reservation_resize_on_connection<ConnErr>(
+        &self,
+        conn: &(impl async_bb8_diesel::AsyncConnection<DbConnection, ConnErr>
+              + Sync),
+        resource_id: Uuid,
+        resources: db::model::Resources,
+    ) -> Result<(), TransactionError<external::Error>>
+    where
+        ConnErr: From<diesel::result::Error> + Send + 'static,
+        PoolError: From<ConnErr>,
+        TransactionError<external::Error>: From<ConnErr>,
+    {
+        use db::schema::sled::dsl as sled_dsl;
+        use db::schema::sled_resource::dsl as resource_dsl;
+
+        let old_resource = match resource_dsl::sled_resource
+            .filter(resource_dsl::id.eq(resource_id))
+            .select(SledResource::as_select())
+            .load_async(conn)
+            .await?
+            .pop()
+        {
+            Some(old_resource) => old_resource,
+            None => return Ok(()),
+        };
+
+        // These answer the same questions as in `sled_reservation_create`,
+        // but leave out the reservation being resized from the sums.
+        let sled_has_space_for_threads =
+            (diesel::dsl::sql::<diesel::sql_types::BigInt>(&format!(
+                "COALESCE(SUM(CAST({} as INT8)), 0)",
+                resource_dsl::hardware_threads::NAME
+            )) + resources.hardware_threads)
+                .le(sled_dsl::usable_hardware_threads);
+        let sled_has_space_for_rss =
+            (diesel::dsl::sql::<diesel::sql_types::BigInt>(&format!(
+                "COALESCE(SUM(CAST({} as INT8)), 0)",
+                resource_dsl::rss_ram::NAME
+            )) + resources.rss_ram)
+                .le(sled_dsl::usable_physical_ram);
+
+        let sled_targets = sled_dsl::sled
+            .left_join(
+                resource_dsl::sled_resource.on(resource_dsl::sled_id
+                    .eq(sled_dsl::id)
+                    .and(resource_dsl::id.ne(resource_id))),
+            )
+            .filter(sled_dsl::id.eq(old_resource.sled_id))
+            .group_by(sled_dsl::id)
+            .having(sled_has_space_for_threads.and(sled_has_space_for_rss))
+            .select(sled_dsl::id)
+            .get_results_async::<Uuid>(conn)
+            .await?;
+        if sled_targets.is_empty() {
+            return Err(TransactionError::CustomError(
+                external::Error::unavail(&format!(
+                    "sled {} cannot fit the requested resources",
+                    old_resource.sled_id
+                )),
+            ));
+        }
+
+        diesel::update(resource_dsl::sled_resource)
+            .filter(resource_dsl::id.eq(resource_id))
+            .set((
+                resource_dsl::hardware_threads.eq(resources.hardware_threads),
+                resource_dsl::rss_ram.eq(resources.rss_ram),
+                resource_dsl::reservoir_ram.eq(resources.reservoir_ram),
+            ))
+            .execute_async(conn)
+            .await?;
+        Ok(())
+    }
+
     pub async fn sled_reservation_delete(
         &self,
         opctx: &OpContext,
@@ -282,4 +429,101 @@ mod test {
         db.cleanup().await.unwrap();
         logctx.cleanup_successful();
     }
+
+    #[tokio::test]
+    async fn sled_reservation_honors_constraints() {
+        let logctx = dev::test_setup_log("sled_reservation_constraints");
+        let mut db = test_setup_database(&logctx.log).await;
+        let (opctx, datastore) = datastore_test(&logctx, &db).await;
+
+        let mut sled_ids = Vec::new();
+        for _ in 0..2 {
+            let sled = Sled::new(
+                Uuid::new_v4(),
+                SocketAddrV6::new(Ipv6Addr::LOCALHOST, 0, 0, 0),
+                sled_baseboard_for_test(),
+                sled_system_hardware_for_test(),
+                rack_id(),
+            );
+            sled_ids.push(
+                datastore
+                    .sled_upsert(sled)
+                    .await
+                    .expect("Could not upsert sled during test prep")
+                    .id(),
+            );
+        }
+
+        async fn reserve(
+            datastore: &DataStore,
+            opctx: &OpContext,
+            constraints: db::model::SledReservationConstraints,
+        ) -> CreateResult<SledResource> {
+            datastore
+                .sled_reservation_create(
+                    opctx,
+                    Uuid::new_v4(),
+                    db::model::SledResourceKind::Instance,
+                    db::model::Resources::new(
+                        1,
+                        ByteCount::from(external::ByteCount::from(0)),
+                        ByteCount::from(external::ByteCount::from(0)),
+                    ),
+                    constraints,
+                )
+                .await
+        }
+
+        // Excluded sleds are never selected, and preferred sleds are selected
+        // ahead of the others.
+        for _ in 0..4 {
+            let resource = reserve(
+                &datastore,
+                &opctx,
+                db::model::SledReservationConstraintBuilder::new()
+                    .must_not_select_from(&sled_ids[..1])
+                    .build(),
+            )
+            .await
+            .unwrap();
+            assert_eq!(resource.sled_id, sled_ids[1]);
+
+            let resource = reserve(
+                &datastore,
+                &opctx,
+                db::model::SledReservationConstraintBuilder::new()
+                    .prefer(&sled_ids[..1])
+                    .build(),
+            )
+            .await
+            .unwrap();
+            assert_eq!(resource.sled_id, sled_ids[0]);
+
+            let resource = reserve(
+                &datastore,
+                &opctx,
+                db::model::SledReservationConstraintBuilder::new()
+                    .avoid(&sled_ids[..1])
+                    .build(),
+            )
+            .await
+            .unwrap();
+            assert_eq!(resource.sled_id, sled_ids[1]);
+        }
+
+        // Excluding every sled leaves nowhere to place the resource.
+        let error = reserve(
+            &datastore,
+            &opctx,
+            db::model::SledReservationConstraintBuilder::new()
+                .must_not_select_from(&sled_ids)
+                .build(),
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(error, external::Error::ServiceUnavailable { .. }));
+
+        db.cleanup().await.unwrap();
+        logctx.cleanup_successful();
+    }
 }
@@ -13,24 +13,28 @@ use crate::db::collection_insert::DatastoreCollection;
 use crate::db::error::public_error_from_diesel_pool;
 use crate::db::error::ErrorHandler;
 use crate::db::model::Generation;
+use crate::db::model::Labels;
 use crate::db::model::Name;
 use crate::db::model::Project;
 use crate::db::model::Snapshot;
 use crate::db::model::SnapshotState;
 use crate::db::pagination::paginated;
 use crate::db::update_and_check::UpdateAndCheck;
+use crate::db::update_and_check::UpdateStatus;
 use async_bb8_diesel::AsyncRunQueryDsl;
 use chrono::Utc;
 use diesel::prelude::*;
 use omicron_common::api::external::http_pagination::PaginatedBy;
 use omicron_common::api::external::CreateResult;
 use omicron_common::api::external::Error;
+use omicron_common::api::external::LabelSelector;
 use omicron_common::api::external::ListResultVec;
 use omicron_common::api::external::LookupType;
 use omicron_common::api::external::ResourceType;
 use omicron_common::api::external::UpdateResult;
 use omicron_common::bail_unless;
 use ref_cast::RefCast;
+use std::net::SocketAddrV6;
 use uuid::Uuid;
 
 impl DataStore {
@@ -109,16 +113,88 @@ impl DataStore {
             })
     }
 
+    /// Records that a Snapshot is attached to the Pantry at `pantry_address`
+    /// so that it can be exported.
+    ///
+    /// Returns `false` if the Snapshot is not ready, or is already attached to
+    /// some other Pantry.
+    pub async fn snapshot_set_pantry(
+        &self,
+        opctx: &OpContext,
+        authz_snapshot: &authz::Snapshot,
+        pantry_address: SocketAddrV6,
+    ) -> UpdateResult<bool> {
+        opctx.authorize(authz::Action::Modify, authz_snapshot).await?;
+
+        let snapshot_id = authz_snapshot.id();
+        let pantry_address = pantry_address.to_string();
+        use db::schema::snapshot::dsl;
+        let updated = diesel::update(dsl::snapshot)
+            .filter(dsl::time_deleted.is_null())
+            .filter(dsl::id.eq(snapshot_id))
+            .filter(dsl::state.eq(SnapshotState::Ready))
+            .filter(dsl::pantry_address.is_null())
+            .set(dsl::pantry_address.eq(pantry_address.clone()))
+            .check_if_exists::<Snapshot>(snapshot_id)
+            .execute_and_check(self.pool_authorized(opctx).await?)
+            .await
+            .map(|r| match r.status {
+                UpdateStatus::Updated => true,
+                UpdateStatus::NotUpdatedButExists => {
+                    r.found.pantry_address.as_ref() == Some(&pantry_address)
+                }
+            })
+            .map_err(|e| {
+                public_error_from_diesel_pool(
+                    e,
+                    ErrorHandler::NotFoundByResource(authz_snapshot),
+                )
+            })?;
+
+        Ok(updated)
+    }
+
+    pub async fn snapshot_clear_pantry(
+        &self,
+        opctx: &OpContext,
+        authz_snapshot: &authz::Snapshot,
+    ) -> UpdateResult<bool> {
+        opctx.authorize(authz::Action::Modify, authz_snapshot).await?;
+
+        let snapshot_id = authz_snapshot.id();
+        use db::schema::snapshot::dsl;
+        let updated = diesel::update(dsl::snapshot)
+            .filter(dsl::time_deleted.is_null())
+            .filter(dsl::id.eq(snapshot_id))
+            .set(dsl::pantry_address.eq(None::<String>))
+            .check_if_exists::<Snapshot>(snapshot_id)
+            .execute_and_check(self.pool_authorized(opctx).await?)
+            .await
+            .map(|r| match r.status {
+                UpdateStatus::Updated => true,
+                UpdateStatus::NotUpdatedButExists => false,
+            })
+            .map_err(|e| {
+                public_error_from_diesel_pool(
+                    e,
+                    ErrorHandler::NotFoundByResource(authz_snapshot),
+                )
+            })?;
+
+        Ok(updated)
+    }
+
     pub async fn snapshot_list(
         &self,
         opctx: &OpContext,
         authz_project: &authz::Project,
         pagparams: &PaginatedBy<'_>,
+        label_selector: Option<&LabelSelector>,
     ) -> ListResultVec<Snapshot> {
         opctx.authorize(authz::Action::ListChildren, authz_project).await?;
 
         use db::schema::snapshot::dsl;
-        match pagparams {
+        let mut query = match pagparams {
             PaginatedBy::Id(pagparams) => {
                 paginated(dsl::snapshot, dsl::id, &pagparams)
             }
@@ -130,10 +206,42 @@ impl DataStore {
         }
         .filter(dsl::time_deleted.is_null())
         .filter(dsl::project_id.eq(authz_project.id()))
-        .select(Snapshot::as_select())
-        .load_async::<Snapshot>(self.pool_authorized(opctx).await?)
-        .await
-        .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+        .filter(dsl::transient.eq(false));
+        if let Some(label_selector) = label_selector {
+            query = query.filter(
+                dsl::labels.contains(Labels(label_selector.labels().clone())),
+            );
+        }
+        query
+            .select(Snapshot::as_select())
+            .load_async::<Snapshot>(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+    }
+
+    /// Replaces the labels on a Snapshot
+    pub async fn snapshot_labels_update(
+        &self,
+        opctx: &OpContext,
+        authz_snapshot: &authz::Snapshot,
+        labels: Labels,
+    ) -> UpdateResult<Snapshot> {
+        opctx.authorize(authz::Action::Modify, authz_snapshot).await?;
+
+        use db::schema::snapshot::dsl;
+        diesel::update(dsl::snapshot)
+            .filter(dsl::time_deleted.is_null())
+            .filter(dsl::id.eq(authz_snapshot.id()))
+            .set((dsl::labels.eq(labels), dsl::time_modified.eq(Utc::now())))
+            .returning(Snapshot::as_returning())
+            .get_result_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel_pool(
+                    e,
+                    ErrorHandler::NotFoundByResource(authz_snapshot),
+                )
+            })
     }
 
     pub async fn project_delete_snapshot(
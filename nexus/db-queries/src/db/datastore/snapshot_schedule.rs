@@ -0,0 +1,273 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! [`DataStore`] methods on [`SnapshotSchedule`]s.
+
+use super::DataStore;
+use crate::authz;
+use crate::authz::ApiResource;
+use crate::context::OpContext;
+use crate::db;
+use crate::db::error::public_error_from_diesel_pool;
+use crate::db::error::ErrorHandler;
+use crate::db::identity::Resource;
+use crate::db::model::Disk;
+use crate::db::model::Name;
+use crate::db::model::Snapshot;
+use crate::db::model::SnapshotSchedule;
+use crate::db::model::SnapshotScheduleRunStatus;
+use crate::db::model::SqlU32;
+use crate::db::pagination::paginated;
+use async_bb8_diesel::AsyncRunQueryDsl;
+use chrono::DateTime;
+use chrono::Utc;
+use diesel::prelude::*;
+use omicron_common::api::external::http_pagination::PaginatedBy;
+use omicron_common::api::external::CreateResult;
+use omicron_common::api::external::DataPageParams;
+use omicron_common::api::external::DeleteResult;
+use omicron_common::api::external::Error;
+use omicron_common::api::external::ListResultVec;
+use omicron_common::api::external::ResourceType;
+use ref_cast::RefCast;
+use uuid::Uuid;
+
+impl DataStore {
+    /// Create a snapshot schedule within a project.
+    pub async fn snapshot_schedule_create(
+        &self,
+        opctx: &OpContext,
+        authz_project: &authz::Project,
+        schedule: SnapshotSchedule,
+    ) -> CreateResult<SnapshotSchedule> {
+        assert_eq!(authz_project.id(), schedule.project_id);
+        opctx.authorize(authz::Action::CreateChild, authz_project).await?;
+        let name = schedule.name().to_string();
+
+        use db::schema::snapshot_schedule::dsl;
+        diesel::insert_into(dsl::snapshot_schedule)
+            .values(schedule)
+            .returning(SnapshotSchedule::as_returning())
+            .get_result_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel_pool(
+                    e,
+                    ErrorHandler::Conflict(
+                        ResourceType::SnapshotSchedule,
+                        &name,
+                    ),
+                )
+            })
+    }
+
+    /// List the snapshot schedules within a project.
+    pub async fn snapshot_schedule_list(
+        &self,
+        opctx: &OpContext,
+        authz_project: &authz::Project,
+        pagparams: &PaginatedBy<'_>,
+    ) -> ListResultVec<SnapshotSchedule> {
+        opctx.authorize(authz::Action::ListChildren, authz_project).await?;
+
+        use db::schema::snapshot_schedule::dsl;
+        match pagparams {
+            PaginatedBy::Id(pagparams) => {
+                paginated(dsl::snapshot_schedule, dsl::id, &pagparams)
+            }
+            PaginatedBy::Name(pagparams) => paginated(
+                dsl::snapshot_schedule,
+                dsl::name,
+                &pagparams.map_name(|n| Name::ref_cast(n)),
+            ),
+        }
+        .filter(dsl::project_id.eq(authz_project.id()))
+        .filter(dsl::time_deleted.is_null())
+        .select(SnapshotSchedule::as_select())
+        .load_async(self.pool_authorized(opctx).await?)
+        .await
+        .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+    }
+
+    /// Delete a snapshot schedule.  Snapshots it has already taken are not
+    /// affected.
+    pub async fn snapshot_schedule_delete(
+        &self,
+        opctx: &OpContext,
+        authz_schedule: &authz::SnapshotSchedule,
+    ) -> DeleteResult {
+        opctx.authorize(authz::Action::Delete, authz_schedule).await?;
+
+        use db::schema::snapshot_schedule::dsl;
+        let updated = diesel::update(dsl::snapshot_schedule)
+            .filter(dsl::id.eq(authz_schedule.id()))
+            .filter(dsl::time_deleted.is_null())
+            .set(dsl::time_deleted.eq(Utc::now()))
+            .execute_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel_pool(
+                    e,
+                    ErrorHandler::NotFoundByResource(authz_schedule),
+                )
+            })?;
+        if updated == 0 {
+            return Err(authz_schedule.not_found());
+        }
+        Ok(())
+    }
+
+    /// List a page of the snapshot schedules in all projects, for the
+    /// background task that runs them.
+    pub async fn snapshot_schedule_list_all(
+        &self,
+        opctx: &OpContext,
+        pagparams: &DataPageParams<'_, Uuid>,
+    ) -> ListResultVec<SnapshotSchedule> {
+        opctx.authorize(authz::Action::ListChildren, &authz::FLEET).await?;
+
+        use db::schema::snapshot_schedule::dsl;
+        paginated(dsl::snapshot_schedule, dsl::id, pagparams)
+            .filter(dsl::time_deleted.is_null())
+            .select(SnapshotSchedule::as_select())
+            .load_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+    }
+
+    /// Records that a run of a snapshot schedule started at `time_started`
+    ///
+    /// The run is only recorded if the schedule hasn't run since `schedule`
+    /// was fetched.  Returns true if the run was recorded, in which case the
+    /// caller should go on to run the schedule, or false if some other Nexus
+    /// got there first.
+    pub async fn snapshot_schedule_run_start(
+        &self,
+        opctx: &OpContext,
+        schedule: &SnapshotSchedule,
+        time_started: DateTime<Utc>,
+    ) -> Result<bool, Error> {
+        opctx.authorize(authz::Action::Modify, &authz::FLEET).await?;
+
+        use db::schema::snapshot_schedule::dsl;
+        let mut query = diesel::update(dsl::snapshot_schedule)
+            .filter(dsl::time_deleted.is_null())
+            .filter(dsl::id.eq(schedule.id()))
+            .into_boxed();
+        query = match schedule.time_last_run {
+            Some(time) => query.filter(dsl::time_last_run.eq(time)),
+            None => query.filter(dsl::time_last_run.is_null()),
+        };
+        let updated = query
+            .set((
+                dsl::time_last_run.eq(time_started),
+                dsl::last_run_status.eq(SnapshotScheduleRunStatus::Running),
+                dsl::last_run_snapshots_created.eq(SqlU32::new(0)),
+                dsl::last_run_snapshots_deleted.eq(SqlU32::new(0)),
+                dsl::last_run_error.eq(None::<String>),
+            ))
+            .execute_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel_pool(e, ErrorHandler::Server)
+            })?;
+        Ok(updated > 0)
+    }
+
+    /// Records the outcome of the run of a snapshot schedule that started at
+    /// `time_started`, unless a later run has started since
+    pub async fn snapshot_schedule_run_finish(
+        &self,
+        opctx: &OpContext,
+        schedule_id: Uuid,
+        time_started: DateTime<Utc>,
+        snapshots_created: u32,
+        snapshots_deleted: u32,
+        error: Option<String>,
+    ) -> Result<(), Error> {
+        opctx.authorize(authz::Action::Modify, &authz::FLEET).await?;
+
+        let status = if error.is_none() {
+            SnapshotScheduleRunStatus::Succeeded
+        } else {
+            SnapshotScheduleRunStatus::Failed
+        };
+
+        use db::schema::snapshot_schedule::dsl;
+        diesel::update(dsl::snapshot_schedule)
+            .filter(dsl::id.eq(schedule_id))
+            .filter(dsl::time_last_run.eq(time_started))
+            .set((
+                dsl::last_run_status.eq(status),
+                dsl::last_run_snapshots_created
+                    .eq(SqlU32::new(snapshots_created)),
+                dsl::last_run_snapshots_deleted
+                    .eq(SqlU32::new(snapshots_deleted)),
+                dsl::last_run_error.eq(error),
+            ))
+            .execute_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel_pool(e, ErrorHandler::Server)
+            })?;
+        Ok(())
+    }
+
+    /// List the disks currently targeted by a snapshot schedule
+    ///
+    /// A schedule that targets a single disk that has since been deleted
+    /// targets no disks.
+    pub async fn snapshot_schedule_disk_list(
+        &self,
+        opctx: &OpContext,
+        authz_project: &authz::Project,
+        schedule: &SnapshotSchedule,
+    ) -> ListResultVec<Disk> {
+        assert_eq!(authz_project.id(), schedule.project_id);
+        opctx.authorize(authz::Action::ListChildren, authz_project).await?;
+
+        use db::schema::disk::dsl;
+        let mut query = dsl::disk
+            .filter(dsl::time_deleted.is_null())
+            .filter(dsl::project_id.eq(authz_project.id()))
+            .into_boxed();
+        query = match (schedule.target_disk_id, &schedule.target_labels) {
+            (Some(disk_id), _) => query.filter(dsl::id.eq(disk_id)),
+            (None, Some(labels)) => {
+                query.filter(dsl::labels.contains(labels.clone()))
+            }
+            (None, None) => return Ok(vec![]),
+        };
+        query
+            .order(dsl::id)
+            .select(Disk::as_select())
+            .load_async::<Disk>(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+    }
+
+    /// List the snapshots of a disk taken by a snapshot schedule, newest
+    /// first
+    pub async fn snapshot_schedule_snapshot_list(
+        &self,
+        opctx: &OpContext,
+        authz_project: &authz::Project,
+        schedule_id: Uuid,
+        disk_id: Uuid,
+    ) -> ListResultVec<Snapshot> {
+        opctx.authorize(authz::Action::ListChildren, authz_project).await?;
+
+        use db::schema::snapshot::dsl;
+        dsl::snapshot
+            .filter(dsl::time_deleted.is_null())
+            .filter(dsl::project_id.eq(authz_project.id()))
+            .filter(dsl::snapshot_schedule_id.eq(schedule_id))
+            .filter(dsl::disk_id.eq(disk_id))
+            .order((dsl::time_created.desc(), dsl::id))
+            .select(Snapshot::as_select())
+            .load_async::<Snapshot>(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+    }
+}
@@ -11,6 +11,7 @@ use crate::db;
 use crate::db::error::public_error_from_diesel_pool;
 use crate::db::error::ErrorHandler;
 use crate::db::identity::Resource;
+use crate::db::model::InstanceSshKey;
 use crate::db::model::Name;
 use crate::db::model::SshKey;
 use crate::db::pagination::paginated;
@@ -21,9 +22,11 @@ use diesel::prelude::*;
 use omicron_common::api::external::http_pagination::PaginatedBy;
 use omicron_common::api::external::CreateResult;
 use omicron_common::api::external::DeleteResult;
+use omicron_common::api::external::Error;
 use omicron_common::api::external::ListResultVec;
 use omicron_common::api::external::ResourceType;
 use ref_cast::RefCast;
+use uuid::Uuid;
 
 impl DataStore {
     pub async fn ssh_keys_list(
@@ -102,4 +105,87 @@ impl DataStore {
             })?;
         Ok(())
     }
+
+    /// Record the SSH public keys chosen for an instance.
+    ///
+    /// Recording a key which is already associated with the instance succeeds
+    /// without any change.
+    pub async fn instance_ssh_keys_add(
+        &self,
+        opctx: &OpContext,
+        authz_instance: &authz::Instance,
+        ssh_key_ids: &[Uuid],
+    ) -> Result<(), Error> {
+        opctx.authorize(authz::Action::Modify, authz_instance).await?;
+
+        let instance_id = authz_instance.id();
+        let rows = ssh_key_ids
+            .iter()
+            .map(|ssh_key_id| InstanceSshKey::new(instance_id, *ssh_key_id))
+            .collect::<Vec<_>>();
+
+        use db::schema::instance_ssh_key::dsl;
+        diesel::insert_into(dsl::instance_ssh_key)
+            .values(rows)
+            .on_conflict((dsl::instance_id, dsl::ssh_key_id))
+            .do_nothing()
+            .execute_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel_pool(e, ErrorHandler::Server)
+            })?;
+        Ok(())
+    }
+
+    /// Forget all of the SSH public keys chosen for an instance, returning the
+    /// number of associations removed.
+    pub async fn instance_ssh_keys_delete_by_instance_id(
+        &self,
+        opctx: &OpContext,
+        instance_id: Uuid,
+    ) -> Result<usize, Error> {
+        use db::schema::instance_ssh_key::dsl;
+        diesel::delete(dsl::instance_ssh_key)
+            .filter(dsl::instance_id.eq(instance_id))
+            .execute_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+    }
+
+    /// List the SSH public keys chosen for an instance.
+    ///
+    /// Keys which have since been deleted by their owner are not included.
+    pub async fn instance_ssh_keys_list(
+        &self,
+        opctx: &OpContext,
+        authz_instance: &authz::Instance,
+        pagparams: &PaginatedBy<'_>,
+    ) -> ListResultVec<SshKey> {
+        opctx.authorize(authz::Action::Read, authz_instance).await?;
+
+        use db::schema::instance_ssh_key::dsl as assoc_dsl;
+        use db::schema::ssh_key::dsl;
+        match pagparams {
+            PaginatedBy::Id(pagparams) => {
+                paginated(dsl::ssh_key, dsl::id, &pagparams)
+            }
+            PaginatedBy::Name(pagparams) => paginated(
+                dsl::ssh_key,
+                dsl::name,
+                &pagparams.map_name(|n| Name::ref_cast(n)),
+            ),
+        }
+        .filter(
+            dsl::id.eq_any(
+                assoc_dsl::instance_ssh_key
+                    .filter(assoc_dsl::instance_id.eq(authz_instance.id()))
+                    .select(assoc_dsl::ssh_key_id),
+            ),
+        )
+        .filter(dsl::time_deleted.is_null())
+        .select(SshKey::as_select())
+        .load_async(self.pool_authorized(opctx).await?)
+        .await
+        .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+    }
 }
@@ -4,21 +4,24 @@
 
 //! [`DataStore`] methods on [`VirtualProvisioningCollection`]s.
 
+use super::virtual_provisioning_quota::QuotaRequest;
 use super::DataStore;
 use crate::context::OpContext;
 use crate::db;
 use crate::db::error::public_error_from_diesel_pool;
 use crate::db::error::ErrorHandler;
+use crate::db::error::TransactionError;
 use crate::db::model::ByteCount;
 use crate::db::model::VirtualProvisioningCollection;
 use crate::db::pool::DbConnection;
 use crate::db::queries::virtual_provisioning_collection_update::VirtualProvisioningCollectionUpdate;
-use async_bb8_diesel::{AsyncRunQueryDsl, PoolError};
+use async_bb8_diesel::{AsyncConnection, AsyncRunQueryDsl, PoolError};
 use diesel::prelude::*;
 use omicron_common::api::external::{DeleteResult, Error};
 use uuid::Uuid;
 
 /// The types of resources which can consume storage space.
+#[derive(Clone, Copy, Debug)]
 pub enum StorageType {
     Disk,
     Snapshot,
@@ -159,6 +162,9 @@ impl DataStore {
     // I think we just need to validate that the model exists when we make these
     // calls? Maybe it could be an optional helper?
 
+    /// Transitively updates all provisioned disk provisions from project ->
+    /// fleet, failing if doing so would exceed the quota of the project or
+    /// its silo.
     pub async fn virtual_provisioning_collection_insert_disk(
         &self,
         opctx: &OpContext,
@@ -172,6 +178,30 @@ impl DataStore {
             project_id,
             disk_byte_diff,
             StorageType::Disk,
+            true,
+        )
+        .await
+    }
+
+    /// Like [`Self::virtual_provisioning_collection_insert_disk`], but
+    /// without checking quotas.
+    ///
+    /// This is used to re-account for a disk when unwinding its deletion,
+    /// which must succeed even if quotas were lowered in the meantime.
+    pub async fn virtual_provisioning_collection_restore_disk(
+        &self,
+        opctx: &OpContext,
+        id: Uuid,
+        project_id: Uuid,
+        disk_byte_diff: ByteCount,
+    ) -> Result<Vec<VirtualProvisioningCollection>, Error> {
+        self.virtual_provisioning_collection_insert_storage(
+            opctx,
+            id,
+            project_id,
+            disk_byte_diff,
+            StorageType::Disk,
+            false,
         )
         .await
     }
@@ -189,11 +219,15 @@ impl DataStore {
             project_id,
             disk_byte_diff,
             StorageType::Snapshot,
+            false,
         )
         .await
     }
 
     /// Transitively updates all provisioned disk provisions from project -> fleet.
+    ///
+    /// If `check_quota` is set, this fails without modifying any collection
+    /// if the new resource would exceed the quota of the project or its silo.
     async fn virtual_provisioning_collection_insert_storage(
         &self,
         opctx: &OpContext,
@@ -201,18 +235,39 @@ impl DataStore {
         project_id: Uuid,
         disk_byte_diff: ByteCount,
         storage_type: StorageType,
+        check_quota: bool,
     ) -> Result<Vec<VirtualProvisioningCollection>, Error> {
-        let provisions =
-            VirtualProvisioningCollectionUpdate::new_insert_storage(
-                id,
-                disk_byte_diff,
-                project_id,
-                storage_type,
-            )
-            .get_results_async(self.pool_authorized(opctx).await?)
+        type TxnError = TransactionError<Error>;
+        let provisions = self
+            .pool_authorized(opctx)
+            .await?
+            .transaction_async(|conn| async move {
+                if check_quota {
+                    self.virtual_provisioning_quota_check_on_connection(
+                        &conn,
+                        id,
+                        project_id,
+                        QuotaRequest::storage(disk_byte_diff),
+                    )
+                    .await?;
+                }
+                let provisions: Vec<VirtualProvisioningCollection> =
+                    VirtualProvisioningCollectionUpdate::new_insert_storage(
+                        id,
+                        disk_byte_diff,
+                        project_id,
+                        storage_type,
+                    )
+                    .get_results_async(&conn)
+                    .await?;
+                Ok(provisions)
+            })
             .await
-            .map_err(|e| {
-                public_error_from_diesel_pool(e, ErrorHandler::Server)
+            .map_err(|e| match e {
+                TxnError::CustomError(e) => e,
+                TxnError::Pool(e) => {
+                    public_error_from_diesel_pool(e, ErrorHandler::Server)
+                }
             })?;
         self.virtual_provisioning_collection_producer
             .append_disk_metrics(&provisions);
@@ -275,7 +330,8 @@ impl DataStore {
         Ok(provisions)
     }
 
-    /// Transitively updates all CPU/RAM provisions from project -> fleet.
+    /// Transitively updates all CPU/RAM provisions from project -> fleet,
+    /// failing if doing so would exceed the quota of the project or its silo.
     pub async fn virtual_provisioning_collection_insert_instance(
         &self,
         opctx: &OpContext,
@@ -284,14 +340,32 @@ impl DataStore {
         cpus_diff: i64,
         ram_diff: ByteCount,
     ) -> Result<Vec<VirtualProvisioningCollection>, Error> {
-        let provisions =
-            VirtualProvisioningCollectionUpdate::new_insert_instance(
-                id, cpus_diff, ram_diff, project_id,
-            )
-            .get_results_async(self.pool_authorized(opctx).await?)
+        type TxnError = TransactionError<Error>;
+        let provisions = self
+            .pool_authorized(opctx)
+            .await?
+            .transaction_async(|conn| async move {
+                self.virtual_provisioning_quota_check_on_connection(
+                    &conn,
+                    id,
+                    project_id,
+                    QuotaRequest::instance(cpus_diff, ram_diff),
+                )
+                .await?;
+                let provisions: Vec<VirtualProvisioningCollection> =
+                    VirtualProvisioningCollectionUpdate::new_insert_instance(
+                        id, cpus_diff, ram_diff, project_id,
+                    )
+                    .get_results_async(&conn)
+                    .await?;
+                Ok(provisions)
+            })
             .await
-            .map_err(|e| {
-                public_error_from_diesel_pool(e, ErrorHandler::Server)
+            .map_err(|e| match e {
+                TxnError::CustomError(e) => e,
+                TxnError::Pool(e) => {
+                    public_error_from_diesel_pool(e, ErrorHandler::Server)
+                }
             })?;
         self.virtual_provisioning_collection_producer
             .append_cpu_metrics(&provisions);
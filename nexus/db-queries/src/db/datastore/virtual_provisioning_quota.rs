@@ -0,0 +1,362 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! [`DataStore`] methods on [`VirtualProvisioningQuota`]s.
+
+use super::DataStore;
+use crate::authz;
+use crate::context::OpContext;
+use crate::db;
+use crate::db::error::diesel_pool_result_optional;
+use crate::db::error::public_error_from_diesel_pool;
+use crate::db::error::ErrorHandler;
+use crate::db::error::TransactionError;
+use crate::db::model::ByteCount;
+use crate::db::model::CollectionTypeProvisioned;
+use crate::db::model::VirtualProvisioningCollection;
+use crate::db::model::VirtualProvisioningQuota;
+use crate::db::pool::DbConnection;
+use async_bb8_diesel::{AsyncRunQueryDsl, PoolError};
+use chrono::Utc;
+use diesel::prelude::*;
+use diesel::upsert::excluded;
+use nexus_types::external_api::params;
+use omicron_common::api::external;
+use omicron_common::api::external::DeleteResult;
+use omicron_common::api::external::Error;
+use omicron_common::api::external::LookupResult;
+use omicron_common::api::external::UpdateResult;
+use uuid::Uuid;
+
+/// The virtual resources requested by a single provisioning operation, which
+/// are compared against the quotas of the enclosing collections.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct QuotaRequest {
+    pub cpus: i64,
+    pub ram: ByteCount,
+    pub storage: ByteCount,
+}
+
+impl QuotaRequest {
+    pub fn storage(storage: ByteCount) -> Self {
+        Self {
+            cpus: 0,
+            ram: ByteCount::from(external::ByteCount::from(0)),
+            storage,
+        }
+    }
+
+    pub fn instance(cpus: i64, ram: ByteCount) -> Self {
+        Self {
+            cpus,
+            ram,
+            storage: ByteCount::from(external::ByteCount::from(0)),
+        }
+    }
+}
+
+impl DataStore {
+    /// Fetch the quota of a silo
+    pub async fn silo_quota_view(
+        &self,
+        opctx: &OpContext,
+        authz_silo: &authz::Silo,
+    ) -> LookupResult<VirtualProvisioningQuota> {
+        opctx.authorize(authz::Action::Read, authz_silo).await?;
+        self.virtual_provisioning_quota_get(
+            opctx,
+            authz_silo.id(),
+            CollectionTypeProvisioned::Silo,
+        )
+        .await
+    }
+
+    /// Replace the quota of a silo
+    ///
+    /// Only fleet administrators may change the resources available to a
+    /// silo.
+    pub async fn silo_quota_update(
+        &self,
+        opctx: &OpContext,
+        authz_silo: &authz::Silo,
+        update: params::QuotaUpdate,
+    ) -> UpdateResult<VirtualProvisioningQuota> {
+        opctx.authorize(authz::Action::Read, authz_silo).await?;
+        opctx.authorize(authz::Action::Modify, &authz::FLEET).await?;
+        self.virtual_provisioning_quota_upsert(
+            opctx,
+            VirtualProvisioningQuota::new(
+                authz_silo.id(),
+                CollectionTypeProvisioned::Silo,
+                update,
+            ),
+        )
+        .await
+    }
+
+    /// Fetch the quota of a project
+    pub async fn project_quota_view(
+        &self,
+        opctx: &OpContext,
+        authz_project: &authz::Project,
+    ) -> LookupResult<VirtualProvisioningQuota> {
+        opctx.authorize(authz::Action::Read, authz_project).await?;
+        self.virtual_provisioning_quota_get(
+            opctx,
+            authz_project.id(),
+            CollectionTypeProvisioned::Project,
+        )
+        .await
+    }
+
+    /// Replace the quota of a project
+    ///
+    /// Project quotas are set by administrators of the enclosing silo, not by
+    /// the project's own administrators.
+    pub async fn project_quota_update(
+        &self,
+        opctx: &OpContext,
+        authz_silo: &authz::Silo,
+        authz_project: &authz::Project,
+        update: params::QuotaUpdate,
+    ) -> UpdateResult<VirtualProvisioningQuota> {
+        opctx.authorize(authz::Action::Read, authz_project).await?;
+        opctx.authorize(authz::Action::Modify, authz_silo).await?;
+        self.virtual_provisioning_quota_upsert(
+            opctx,
+            VirtualProvisioningQuota::new(
+                authz_project.id(),
+                CollectionTypeProvisioned::Project,
+                update,
+            ),
+        )
+        .await
+    }
+
+    /// Returns the quota for collection `id`, or an unlimited quota if none
+    /// has been set.
+    async fn virtual_provisioning_quota_get(
+        &self,
+        opctx: &OpContext,
+        id: Uuid,
+        collection_type: CollectionTypeProvisioned,
+    ) -> LookupResult<VirtualProvisioningQuota> {
+        use db::schema::virtual_provisioning_quota::dsl;
+
+        let quota = diesel_pool_result_optional(
+            dsl::virtual_provisioning_quota
+                .find(id)
+                .select(VirtualProvisioningQuota::as_select())
+                .get_result_async(self.pool_authorized(opctx).await?)
+                .await,
+        )
+        .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))?;
+
+        Ok(quota.unwrap_or_else(|| {
+            VirtualProvisioningQuota::unlimited(id, collection_type)
+        }))
+    }
+
+    async fn virtual_provisioning_quota_upsert(
+        &self,
+        opctx: &OpContext,
+        quota: VirtualProvisioningQuota,
+    ) -> UpdateResult<VirtualProvisioningQuota> {
+        use db::schema::virtual_provisioning_quota::dsl;
+
+        diesel::insert_into(dsl::virtual_provisioning_quota)
+            .values(quota)
+            .on_conflict(dsl::id)
+            .do_update()
+            .set((
+                dsl::time_modified.eq(Utc::now()),
+                dsl::virtual_disk_bytes.eq(excluded(dsl::virtual_disk_bytes)),
+                dsl::cpus.eq(excluded(dsl::cpus)),
+                dsl::ram_bytes.eq(excluded(dsl::ram_bytes)),
+            ))
+            .returning(VirtualProvisioningQuota::as_returning())
+            .get_result_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+    }
+
+    /// Delete the quota (if any) of the collection `id`.
+    pub(crate) async fn virtual_provisioning_quota_delete_on_connection<
+        ConnErr,
+    >(
+        &self,
+        conn: &(impl async_bb8_diesel::AsyncConnection<DbConnection, ConnErr>
+              + Sync),
+        id: Uuid,
+    ) -> DeleteResult
+    where
+        ConnErr: From<diesel::result::Error> + Send + 'static,
+        PoolError: From<ConnErr>,
+    {
+        use db::schema::virtual_provisioning_quota::dsl;
+
+        diesel::delete(dsl::virtual_provisioning_quota)
+            .filter(dsl::id.eq(id))
+            .execute_async(conn)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel_pool(
+                    PoolError::from(e),
+                    ErrorHandler::Server,
+                )
+            })?;
+        Ok(())
+    }
+
+    /// Verifies that provisioning resource `id` within `project_id` would not
+    /// exceed the quota of the project or of its silo.
+    ///
+    /// This must be called within the same transaction that updates the
+    /// provisioning collections, so that concurrent requests cannot both
+    /// observe enough headroom for themselves.
+    ///
+    /// If the resource has already been provisioned (e.g., because a saga
+    /// action is being replayed), no check is performed: the provisioning
+    /// update itself is a no-op in that case.
+    pub(crate) async fn virtual_provisioning_quota_check_on_connection<
+        ConnErr,
+    >(
+        &self,
+        conn: &(impl async_bb8_diesel::AsyncConnection<DbConnection, ConnErr>
+              + Sync),
+        id: Uuid,
+        project_id: Uuid,
+        request: QuotaRequest,
+    ) -> Result<(), TransactionError<Error>>
+    where
+        ConnErr: From<diesel::result::Error> + Send + 'static,
+        PoolError: From<ConnErr>,
+        TransactionError<Error>: From<ConnErr>,
+    {
+        use db::schema::virtual_provisioning_resource::dsl as resource_dsl;
+
+        let existing = resource_dsl::virtual_provisioning_resource
+            .filter(resource_dsl::id.eq(id))
+            .select(resource_dsl::id)
+            .load_async::<Uuid>(conn)
+            .await?;
+        if !existing.is_empty() {
+            return Ok(());
+        }
+
+        self.virtual_provisioning_quota_check_growth_on_connection(
+            conn, project_id, request,
+        )
+        .await
+    }
+
+    /// Verifies that growing the resources provisioned within `project_id`
+    /// by `request` would not exceed the quota of the project or of its silo.
+    ///
+    /// Unlike [`Self::virtual_provisioning_quota_check_on_connection`], this
+    /// performs the check even for resources which have already been
+    /// provisioned, so callers are responsible for idempotency.
+    pub(crate) async fn virtual_provisioning_quota_check_growth_on_connection<
+        ConnErr,
+    >(
+        &self,
+        conn: &(impl async_bb8_diesel::AsyncConnection<DbConnection, ConnErr>
+              + Sync),
+        project_id: Uuid,
+        request: QuotaRequest,
+    ) -> Result<(), TransactionError<Error>>
+    where
+        ConnErr: From<diesel::result::Error> + Send + 'static,
+        PoolError: From<ConnErr>,
+        TransactionError<Error>: From<ConnErr>,
+    {
+        use db::schema::project::dsl as project_dsl;
+        use db::schema::virtual_provisioning_collection::dsl as collection_dsl;
+        use db::schema::virtual_provisioning_quota::dsl as quota_dsl;
+
+        let silo_id = project_dsl::project
+            .filter(project_dsl::id.eq(project_id))
+            .select(project_dsl::silo_id)
+            .get_result_async::<Uuid>(conn)
+            .await?;
+        let collection_ids = vec![project_id, silo_id];
+
+        let quotas = quota_dsl::virtual_provisioning_quota
+            .filter(quota_dsl::id.eq_any(collection_ids.clone()))
+            .select(VirtualProvisioningQuota::as_select())
+            .load_async(conn)
+            .await?;
+        if quotas.is_empty() {
+            return Ok(());
+        }
+
+        let collections = collection_dsl::virtual_provisioning_collection
+            .filter(collection_dsl::id.eq_any(collection_ids))
+            .select(VirtualProvisioningCollection::as_select())
+            .load_async(conn)
+            .await?;
+
+        for quota in &quotas {
+            let Some(collection) =
+                collections.iter().find(|c| c.id == quota.id)
+            else {
+                continue;
+            };
+            let kind = quota.collection_type.to_lowercase();
+
+            if let Some(limit) = quota.cpus {
+                let provisioned = collection.cpus_provisioned;
+                if request.cpus > 0 && provisioned + request.cpus > limit {
+                    return Err(TransactionError::CustomError(
+                        Error::InvalidRequest {
+                            message: format!(
+                                "{kind} quota exceeded: requested {} vCPUs, \
+                                but {provisioned} of {limit} are already \
+                                provisioned",
+                                request.cpus,
+                            ),
+                        },
+                    ));
+                }
+            }
+
+            if let Some(limit) = quota.ram_bytes {
+                let limit = i64::from(limit);
+                let requested = i64::from(request.ram);
+                let provisioned = i64::from(collection.ram_provisioned);
+                if requested > 0 && provisioned + requested > limit {
+                    return Err(TransactionError::CustomError(
+                        Error::InvalidRequest {
+                            message: format!(
+                                "{kind} quota exceeded: requested {requested} \
+                                bytes of memory, but {provisioned} of {limit} \
+                                bytes are already provisioned",
+                            ),
+                        },
+                    ));
+                }
+            }
+
+            if let Some(limit) = quota.virtual_disk_bytes {
+                let limit = i64::from(limit);
+                let requested = i64::from(request.storage);
+                let provisioned =
+                    i64::from(collection.virtual_disk_bytes_provisioned);
+                if requested > 0 && provisioned + requested > limit {
+                    return Err(TransactionError::CustomError(
+                        Error::InvalidRequest {
+                            message: format!(
+                                "{kind} quota exceeded: requested {requested} \
+                                bytes of storage, but {provisioned} of \
+                                {limit} bytes are already provisioned",
+                            ),
+                        },
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
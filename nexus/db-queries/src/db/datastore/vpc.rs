@@ -18,6 +18,7 @@ use crate::db::fixed_data::vpc::SERVICES_VPC_ID;
 use crate::db::identity::Resource;
 use crate::db::model::IncompleteVpc;
 use crate::db::model::InstanceNetworkInterface;
+use crate::db::model::Labels;
 use crate::db::model::Name;
 use crate::db::model::Project;
 use crate::db::model::RouterRoute;
@@ -26,6 +27,7 @@ use crate::db::model::Sled;
 use crate::db::model::Vni;
 use crate::db::model::Vpc;
 use crate::db::model::VpcFirewallRule;
+use crate::db::model::VpcPeeringState;
 use crate::db::model::VpcRouter;
 use crate::db::model::VpcRouterKind;
 use crate::db::model::VpcRouterUpdate;
@@ -37,8 +39,11 @@ use crate::db::pagination::paginated;
 use crate::db::queries::vpc::InsertVpcQuery;
 use crate::db::queries::vpc_subnet::FilterConflictingVpcSubnetRangesQuery;
 use crate::db::queries::vpc_subnet::SubnetError;
+use crate::db::update_and_check::UpdateAndCheck;
+use crate::db::update_and_check::UpdateStatus;
 use async_bb8_diesel::AsyncConnection;
 use async_bb8_diesel::AsyncRunQueryDsl;
+use chrono::DateTime;
 use chrono::Utc;
 use diesel::prelude::*;
 use ipnetwork::IpNetwork;
@@ -48,6 +53,7 @@ use omicron_common::api::external::DeleteResult;
 use omicron_common::api::external::Error;
 use omicron_common::api::external::IdentityMetadataCreateParams;
 use omicron_common::api::external::InternalContext;
+use omicron_common::api::external::LabelSelector;
 use omicron_common::api::external::ListResultVec;
 use omicron_common::api::external::LookupResult;
 use omicron_common::api::external::LookupType;
@@ -262,11 +268,12 @@ impl DataStore {
         opctx: &OpContext,
         authz_project: &authz::Project,
         pagparams: &PaginatedBy<'_>,
+        label_selector: Option<&LabelSelector>,
     ) -> ListResultVec<Vpc> {
         opctx.authorize(authz::Action::ListChildren, authz_project).await?;
 
         use db::schema::vpc::dsl;
-        match pagparams {
+        let mut query = match pagparams {
             PaginatedBy::Id(pagparams) => {
                 paginated(dsl::vpc, dsl::id, &pagparams)
             }
@@ -277,11 +284,17 @@ impl DataStore {
             ),
         }
         .filter(dsl::time_deleted.is_null())
-        .filter(dsl::project_id.eq(authz_project.id()))
-        .select(Vpc::as_select())
-        .load_async(self.pool_authorized(opctx).await?)
-        .await
-        .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+        .filter(dsl::project_id.eq(authz_project.id()));
+        if let Some(label_selector) = label_selector {
+            query = query.filter(
+                dsl::labels.contains(Labels(label_selector.labels().clone())),
+            );
+        }
+        query
+            .select(Vpc::as_select())
+            .load_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
     }
 
     pub async fn project_create_vpc(
@@ -340,39 +353,72 @@ impl DataStore {
         ))
     }
 
+    /// Updates a VPC
+    ///
+    /// If `expected_time_modified` is provided, the VPC is only updated if
+    /// its modification time is one of those given.
     pub async fn project_update_vpc(
         &self,
         opctx: &OpContext,
         authz_vpc: &authz::Vpc,
         updates: VpcUpdate,
+        expected_time_modified: Option<&[DateTime<Utc>]>,
     ) -> UpdateResult<Vpc> {
         opctx.authorize(authz::Action::Modify, authz_vpc).await?;
 
         use db::schema::vpc::dsl;
-        diesel::update(dsl::vpc)
+        let update_query = diesel::update(dsl::vpc)
             .filter(dsl::time_deleted.is_null())
-            .filter(dsl::id.eq(authz_vpc.id()))
+            .filter(dsl::id.eq(authz_vpc.id()));
+        let Some(expected) = expected_time_modified else {
+            return update_query
+                .set(updates)
+                .returning(Vpc::as_returning())
+                .get_result_async(self.pool_authorized(opctx).await?)
+                .await
+                .map_err(|e| {
+                    public_error_from_diesel_pool(
+                        e,
+                        ErrorHandler::NotFoundByResource(authz_vpc),
+                    )
+                });
+        };
+
+        let result = update_query
+            .filter(dsl::time_modified.eq_any(expected.to_vec()))
             .set(updates)
-            .returning(Vpc::as_returning())
-            .get_result_async(self.pool_authorized(opctx).await?)
+            .check_if_exists::<Vpc>(authz_vpc.id())
+            .execute_and_check(self.pool_authorized(opctx).await?)
             .await
             .map_err(|e| {
                 public_error_from_diesel_pool(
                     e,
                     ErrorHandler::NotFoundByResource(authz_vpc),
                 )
-            })
+            })?;
+        match result.status {
+            UpdateStatus::Updated => Ok(result.found),
+            UpdateStatus::NotUpdatedButExists => {
+                Err(super::precondition_failed())
+            }
+        }
     }
 
+    /// Deletes a VPC
+    ///
+    /// If `expected_time_modified` is provided, the VPC is only deleted if its
+    /// modification time is one of those given.
     pub async fn project_delete_vpc(
         &self,
         opctx: &OpContext,
         db_vpc: &Vpc,
         authz_vpc: &authz::Vpc,
+        expected_time_modified: Option<&[DateTime<Utc>]>,
     ) -> DeleteResult {
         opctx.authorize(authz::Action::Delete, authz_vpc).await?;
 
         use db::schema::vpc::dsl;
+        use db::schema::vpc_peering;
         use db::schema::vpc_subnet;
 
         // Note that we don't ensure the firewall rules are empty here, because
@@ -409,22 +455,69 @@ impl DataStore {
             });
         }
 
+        // Peerings are children of the VPC too, and deleting one may need to
+        // tear down routes in the peer VPC, so require that they be deleted
+        // explicitly first.
+        if diesel_pool_result_optional(
+            vpc_peering::dsl::vpc_peering
+                .filter(vpc_peering::dsl::vpc_id.eq(authz_vpc.id()))
+                .filter(vpc_peering::dsl::time_deleted.is_null())
+                .select(vpc_peering::dsl::id)
+                .limit(1)
+                .first_async::<Uuid>(self.pool_authorized(opctx).await?)
+                .await,
+        )
+        .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))?
+        .is_some()
+        {
+            return Err(Error::InvalidRequest {
+                message: String::from(
+                    "VPC cannot be deleted while VPC peerings exist",
+                ),
+            });
+        }
+
         // Delete the VPC, conditional on the subnet_gen not having changed.
         let now = Utc::now();
-        let updated_rows = diesel::update(dsl::vpc)
+        let delete_query = diesel::update(dsl::vpc)
             .filter(dsl::time_deleted.is_null())
             .filter(dsl::id.eq(authz_vpc.id()))
             .filter(dsl::subnet_gen.eq(db_vpc.subnet_gen))
-            .set(dsl::time_deleted.eq(now))
-            .execute_async(self.pool_authorized(opctx).await?)
-            .await
-            .map_err(|e| {
-                public_error_from_diesel_pool(
-                    e,
-                    ErrorHandler::NotFoundByResource(authz_vpc),
-                )
-            })?;
+            .set(dsl::time_deleted.eq(now));
+        let pool = self.pool_authorized(opctx).await?;
+        let updated_rows = match expected_time_modified {
+            None => delete_query.execute_async(pool).await,
+            Some(expected) => {
+                delete_query
+                    .filter(dsl::time_modified.eq_any(expected.to_vec()))
+                    .execute_async(pool)
+                    .await
+            }
+        }
+        .map_err(|e| {
+            public_error_from_diesel_pool(
+                e,
+                ErrorHandler::NotFoundByResource(authz_vpc),
+            )
+        })?;
         if updated_rows == 0 {
+            // Distinguish a VPC that's been modified (or deleted) since the
+            // version the caller expected from one that's had a subnet created
+            // in it.
+            if let Some(expected) = expected_time_modified {
+                let time_modified = dsl::vpc
+                    .filter(dsl::time_deleted.is_null())
+                    .filter(dsl::id.eq(authz_vpc.id()))
+                    .select(dsl::time_modified)
+                    .load_async::<DateTime<Utc>>(pool)
+                    .await
+                    .map_err(|e| {
+                        public_error_from_diesel_pool(e, ErrorHandler::Server)
+                    })?;
+                if !time_modified.iter().any(|t| expected.contains(t)) {
+                    return Err(super::precondition_failed());
+                }
+            }
             Err(Error::InvalidRequest {
                 message: String::from(
                     "deletion failed to to concurrent modification",
@@ -488,8 +581,33 @@ impl DataStore {
         &self,
         opctx: &OpContext,
         authz_vpc: &authz::Vpc,
-        mut rules: Vec<VpcFirewallRule>,
+        rules: Vec<VpcFirewallRule>,
     ) -> UpdateResult<Vec<VpcFirewallRule>> {
+        self.vpc_update_firewall_rules_conditional(
+            opctx,
+            authz_vpc,
+            rules,
+            |_| Ok(()),
+        )
+        .await
+    }
+
+    /// Replace all firewall rules with the given rules, provided that
+    /// `precondition` accepts the rules being replaced
+    ///
+    /// `precondition` is invoked with the current rules (in the order they're
+    /// listed) inside the same transaction that replaces them, so an error
+    /// from it means that nothing was changed.
+    pub async fn vpc_update_firewall_rules_conditional<P>(
+        &self,
+        opctx: &OpContext,
+        authz_vpc: &authz::Vpc,
+        mut rules: Vec<VpcFirewallRule>,
+        precondition: P,
+    ) -> UpdateResult<Vec<VpcFirewallRule>>
+    where
+        P: FnOnce(&[VpcFirewallRule]) -> Result<(), Error> + Send,
+    {
         opctx.authorize(authz::Action::Modify, authz_vpc).await?;
         for r in &rules {
             assert_eq!(r.vpc_id, authz_vpc.id());
@@ -503,6 +621,12 @@ impl DataStore {
 
         use db::schema::vpc_firewall_rule::dsl;
 
+        let current_rules_query = dsl::vpc_firewall_rule
+            .filter(dsl::time_deleted.is_null())
+            .filter(dsl::vpc_id.eq(authz_vpc.id()))
+            .order(dsl::name.asc())
+            .select(VpcFirewallRule::as_select());
+
         let now = Utc::now();
         let delete_old_query = diesel::update(dsl::vpc_firewall_rule)
             .filter(dsl::time_deleted.is_null())
@@ -518,6 +642,7 @@ impl DataStore {
         #[derive(Debug)]
         enum FirewallUpdateError {
             CollectionNotFound,
+            PreconditionFailed(Error),
         }
         type TxnError = TransactionError<FirewallUpdateError>;
 
@@ -528,6 +653,15 @@ impl DataStore {
         self.pool_authorized(opctx)
             .await?
             .transaction_async(|conn| async move {
+                let current_rules = current_rules_query
+                    .load_async::<VpcFirewallRule>(&conn)
+                    .await?;
+                precondition(&current_rules).map_err(|e| {
+                    TxnError::CustomError(
+                        FirewallUpdateError::PreconditionFailed(e),
+                    )
+                })?;
+
                 delete_old_query.execute_async(&conn).await?;
 
                 // The generation count update on the vpc table row will take a
@@ -553,6 +687,9 @@ impl DataStore {
                 TxnError::CustomError(
                     FirewallUpdateError::CollectionNotFound,
                 ) => Error::not_found_by_id(ResourceType::Vpc, &authz_vpc.id()),
+                TxnError::CustomError(
+                    FirewallUpdateError::PreconditionFailed(e),
+                ) => e,
                 TxnError::Pool(e) => public_error_from_diesel_pool(
                     e,
                     ErrorHandler::NotFoundByResource(authz_vpc),
@@ -611,6 +748,34 @@ impl DataStore {
             .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
     }
 
+    /// Return the IDs of running instances with network interfaces on the
+    /// provided VPC, along with the IDs of the sleds they're running on.
+    pub async fn vpc_resolve_to_running_instances(
+        &self,
+        vpc_id: Uuid,
+    ) -> Result<Vec<(Uuid, Uuid)>, Error> {
+        use db::model::InstanceState as DbInstanceState;
+        use db::schema::{instance, instance_network_interface};
+        use omicron_common::api::external::InstanceState as ApiInstanceState;
+
+        let running = DbInstanceState::new(ApiInstanceState::Running);
+        instance_network_interface::table
+            .inner_join(
+                instance::table
+                    .on(instance::id
+                        .eq(instance_network_interface::instance_id)),
+            )
+            .filter(instance_network_interface::vpc_id.eq(vpc_id))
+            .filter(instance_network_interface::time_deleted.is_null())
+            .filter(instance::time_deleted.is_null())
+            .filter(instance::state.eq(running))
+            .select((instance::id, instance::active_sled_id))
+            .distinct()
+            .get_results_async(self.pool())
+            .await
+            .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+    }
+
     pub async fn vpc_subnet_list(
         &self,
         opctx: &OpContext,
@@ -639,6 +804,10 @@ impl DataStore {
     }
 
     /// Insert a VPC Subnet, checking for unique IP address ranges.
+    ///
+    /// The subnet's IPv4 block also may not overlap those of the subnets of
+    /// VPCs that this VPC is actively peered with, since traffic between
+    /// peered VPCs is routed by address.
     pub async fn vpc_create_subnet(
         &self,
         opctx: &OpContext,
@@ -666,21 +835,76 @@ impl DataStore {
         &self,
         subnet: VpcSubnet,
     ) -> Result<VpcSubnet, SubnetError> {
-        use db::schema::vpc_subnet::dsl;
+        #[derive(Debug)]
+        enum SubnetCreateError {
+            OverlapsPeerSubnet(ipnetwork::Ipv4Network),
+        }
+        type TxnError = TransactionError<SubnetCreateError>;
+
+        let vpc_id = subnet.vpc_id;
+        let ipv4_block = subnet.ipv4_block.0 .0;
         let values = FilterConflictingVpcSubnetRangesQuery::new(subnet.clone());
-        diesel::insert_into(dsl::vpc_subnet)
-            .values(values)
-            .returning(VpcSubnet::as_returning())
-            .get_result_async(self.pool())
+        self.pool()
+            .transaction_async(|conn| async move {
+                use db::schema::vpc_peering::dsl as peering_dsl;
+                use db::schema::vpc_subnet::dsl;
+
+                let peer_vpc_ids = peering_dsl::vpc_peering
+                    .filter(peering_dsl::vpc_id.eq(vpc_id))
+                    .filter(peering_dsl::state.eq(VpcPeeringState::Active))
+                    .filter(peering_dsl::time_deleted.is_null())
+                    .select(peering_dsl::peer_vpc_id)
+                    .load_async::<Uuid>(&conn)
+                    .await?;
+                if !peer_vpc_ids.is_empty() {
+                    let peer_blocks = dsl::vpc_subnet
+                        .filter(dsl::vpc_id.eq_any(peer_vpc_ids))
+                        .filter(dsl::time_deleted.is_null())
+                        .select(dsl::ipv4_block)
+                        .load_async::<Ipv4Net>(&conn)
+                        .await?;
+                    let overlapping =
+                        peer_blocks.into_iter().map(|b| b.0 .0).find(|b| {
+                            b.contains(ipv4_block.network())
+                                || ipv4_block.contains(b.network())
+                        });
+                    if let Some(block) = overlapping {
+                        return Err(TxnError::CustomError(
+                            SubnetCreateError::OverlapsPeerSubnet(block),
+                        ));
+                    }
+                }
+
+                let db_subnet = diesel::insert_into(dsl::vpc_subnet)
+                    .values(values)
+                    .returning(VpcSubnet::as_returning())
+                    .get_result_async(&conn)
+                    .await?;
+                Ok(db_subnet)
+            })
             .await
-            .map_err(|e| SubnetError::from_pool(e, &subnet))
+            .map_err(|e| match e {
+                TxnError::CustomError(
+                    SubnetCreateError::OverlapsPeerSubnet(block),
+                ) => SubnetError::External(Error::invalid_request(&format!(
+                    "IPv4 block '{}' overlaps the block '{}' of a subnet in \
+                    a peered VPC",
+                    ipv4_block, block,
+                ))),
+                TxnError::Pool(e) => SubnetError::from_pool(e, &subnet),
+            })
     }
 
+    /// Deletes a VPC Subnet
+    ///
+    /// If `expected_time_modified` is provided, the subnet is only deleted if
+    /// its modification time is one of those given.
     pub async fn vpc_delete_subnet(
         &self,
         opctx: &OpContext,
         db_subnet: &VpcSubnet,
         authz_subnet: &authz::VpcSubnet,
+        expected_time_modified: Option<&[DateTime<Utc>]>,
     ) -> DeleteResult {
         opctx.authorize(authz::Action::Delete, authz_subnet).await?;
 
@@ -710,20 +934,45 @@ impl DataStore {
 
         // Delete the subnet, conditional on the rcgen not having changed.
         let now = Utc::now();
-        let updated_rows = diesel::update(dsl::vpc_subnet)
+        let delete_query = diesel::update(dsl::vpc_subnet)
             .filter(dsl::time_deleted.is_null())
             .filter(dsl::id.eq(authz_subnet.id()))
             .filter(dsl::rcgen.eq(db_subnet.rcgen))
-            .set(dsl::time_deleted.eq(now))
-            .execute_async(self.pool_authorized(opctx).await?)
-            .await
-            .map_err(|e| {
-                public_error_from_diesel_pool(
-                    e,
-                    ErrorHandler::NotFoundByResource(authz_subnet),
-                )
-            })?;
+            .set(dsl::time_deleted.eq(now));
+        let pool = self.pool_authorized(opctx).await?;
+        let updated_rows = match expected_time_modified {
+            None => delete_query.execute_async(pool).await,
+            Some(expected) => {
+                delete_query
+                    .filter(dsl::time_modified.eq_any(expected.to_vec()))
+                    .execute_async(pool)
+                    .await
+            }
+        }
+        .map_err(|e| {
+            public_error_from_diesel_pool(
+                e,
+                ErrorHandler::NotFoundByResource(authz_subnet),
+            )
+        })?;
         if updated_rows == 0 {
+            // Distinguish a subnet that's been modified (or deleted) since the
+            // version the caller expected from one that's had a child resource
+            // created in it.
+            if let Some(expected) = expected_time_modified {
+                let time_modified = dsl::vpc_subnet
+                    .filter(dsl::time_deleted.is_null())
+                    .filter(dsl::id.eq(authz_subnet.id()))
+                    .select(dsl::time_modified)
+                    .load_async::<DateTime<Utc>>(pool)
+                    .await
+                    .map_err(|e| {
+                        public_error_from_diesel_pool(e, ErrorHandler::Server)
+                    })?;
+                if !time_modified.iter().any(|t| expected.contains(t)) {
+                    return Err(super::precondition_failed());
+                }
+            }
             return Err(Error::InvalidRequest {
                 message: String::from(
                     "deletion failed to to concurrent modification",
@@ -734,28 +983,55 @@ impl DataStore {
         }
     }
 
+    /// Updates a VPC Subnet
+    ///
+    /// If `expected_time_modified` is provided, the subnet is only updated if
+    /// its modification time is one of those given.
     pub async fn vpc_update_subnet(
         &self,
         opctx: &OpContext,
         authz_subnet: &authz::VpcSubnet,
         updates: VpcSubnetUpdate,
+        expected_time_modified: Option<&[DateTime<Utc>]>,
     ) -> UpdateResult<VpcSubnet> {
         opctx.authorize(authz::Action::Modify, authz_subnet).await?;
 
         use db::schema::vpc_subnet::dsl;
-        diesel::update(dsl::vpc_subnet)
+        let update_query = diesel::update(dsl::vpc_subnet)
             .filter(dsl::time_deleted.is_null())
-            .filter(dsl::id.eq(authz_subnet.id()))
+            .filter(dsl::id.eq(authz_subnet.id()));
+        let Some(expected) = expected_time_modified else {
+            return update_query
+                .set(updates)
+                .returning(VpcSubnet::as_returning())
+                .get_result_async(self.pool_authorized(opctx).await?)
+                .await
+                .map_err(|e| {
+                    public_error_from_diesel_pool(
+                        e,
+                        ErrorHandler::NotFoundByResource(authz_subnet),
+                    )
+                });
+        };
+
+        let result = update_query
+            .filter(dsl::time_modified.eq_any(expected.to_vec()))
             .set(updates)
-            .returning(VpcSubnet::as_returning())
-            .get_result_async(self.pool_authorized(opctx).await?)
+            .check_if_exists::<VpcSubnet>(authz_subnet.id())
+            .execute_and_check(self.pool_authorized(opctx).await?)
             .await
             .map_err(|e| {
                 public_error_from_diesel_pool(
                     e,
                     ErrorHandler::NotFoundByResource(authz_subnet),
                 )
-            })
+            })?;
+        match result.status {
+            UpdateStatus::Updated => Ok(result.found),
+            UpdateStatus::NotUpdatedButExists => {
+                Err(super::precondition_failed())
+            }
+        }
     }
 
     pub async fn subnet_list_instance_network_interfaces(
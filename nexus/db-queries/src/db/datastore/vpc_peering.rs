@@ -0,0 +1,303 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! [`DataStore`] methods on [`VpcPeering`]s.
+
+use super::DataStore;
+use crate::authz;
+use crate::authz::ApiResource;
+use crate::context::OpContext;
+use crate::db;
+use crate::db::error::public_error_from_diesel_pool;
+use crate::db::error::ErrorHandler;
+use crate::db::error::TransactionError;
+use crate::db::identity::Resource;
+use crate::db::model::Ipv4Net;
+use crate::db::model::Name;
+use crate::db::model::RouterRoute;
+use crate::db::model::Vpc;
+use crate::db::model::VpcPeering;
+use crate::db::model::VpcPeeringState;
+use crate::db::pagination::paginated;
+use async_bb8_diesel::AsyncConnection;
+use async_bb8_diesel::AsyncRunQueryDsl;
+use chrono::Utc;
+use diesel::prelude::*;
+use nexus_types::external_api::params;
+use omicron_common::api::external::http_pagination::PaginatedBy;
+use omicron_common::api::external::CreateResult;
+use omicron_common::api::external::DeleteResult;
+use omicron_common::api::external::Error;
+use omicron_common::api::external::IdentityMetadataCreateParams;
+use omicron_common::api::external::ListResultVec;
+use omicron_common::api::external::ResourceType;
+use omicron_common::api::external::RouteDestination;
+use omicron_common::api::external::RouteTarget;
+use omicron_common::api::external::RouterRouteKind;
+use ref_cast::RefCast;
+use uuid::Uuid;
+
+/// Returns the route added to `vpc`'s system router while its side of a
+/// peering with `peer_vpc` is active.
+///
+/// The route shares its ID with that side of the peering, so that it can be
+/// found again when the peering is torn down.  Its name is derived from the ID,
+/// too: the peering's name might already be used by another of the router's
+/// routes.
+fn peering_route(
+    peering: &VpcPeering,
+    vpc: &Vpc,
+    peer_vpc: &Vpc,
+) -> RouterRoute {
+    RouterRoute::new(
+        peering.id(),
+        vpc.system_router_id,
+        RouterRouteKind::VpcPeering,
+        params::RouterRouteCreate {
+            identity: IdentityMetadataCreateParams {
+                name: format!("peer-{}", peering.id()).parse().unwrap(),
+                description: format!("route to peered VPC {}", peer_vpc.name()),
+            },
+            target: RouteTarget::Vpc(peer_vpc.name().clone()),
+            destination: RouteDestination::Vpc(peer_vpc.name().clone()),
+        },
+    )
+}
+
+impl DataStore {
+    /// List the peerings created by a VPC.
+    pub async fn vpc_peering_list(
+        &self,
+        opctx: &OpContext,
+        authz_vpc: &authz::Vpc,
+        pagparams: &PaginatedBy<'_>,
+    ) -> ListResultVec<VpcPeering> {
+        opctx.authorize(authz::Action::ListChildren, authz_vpc).await?;
+
+        use db::schema::vpc_peering::dsl;
+        match pagparams {
+            PaginatedBy::Id(pagparams) => {
+                paginated(dsl::vpc_peering, dsl::id, &pagparams)
+            }
+            PaginatedBy::Name(pagparams) => paginated(
+                dsl::vpc_peering,
+                dsl::name,
+                &pagparams.map_name(|n| Name::ref_cast(n)),
+            ),
+        }
+        .filter(dsl::vpc_id.eq(authz_vpc.id()))
+        .filter(dsl::time_deleted.is_null())
+        .select(VpcPeering::as_select())
+        .load_async(self.pool_authorized(opctx).await?)
+        .await
+        .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+    }
+
+    /// Create one VPC's side of a peering with another VPC.
+    ///
+    /// If the peer VPC has already created a peering with this VPC, both sides
+    /// become active, and a route to each VPC is added to the other's system
+    /// router. Two VPCs whose subnets have overlapping IPv4 blocks cannot be
+    /// peered.
+    pub async fn vpc_peering_create(
+        &self,
+        opctx: &OpContext,
+        authz_vpc: &authz::Vpc,
+        db_vpc: &Vpc,
+        db_peer_vpc: &Vpc,
+        peering: VpcPeering,
+    ) -> CreateResult<VpcPeering> {
+        assert_eq!(authz_vpc.id(), peering.vpc_id);
+        assert_eq!(db_vpc.id(), peering.vpc_id);
+        assert_eq!(db_peer_vpc.id(), peering.peer_vpc_id);
+        opctx.authorize(authz::Action::CreateChild, authz_vpc).await?;
+
+        #[derive(Debug)]
+        enum VpcPeeringCreateError {
+            OverlappingSubnets,
+        }
+        type TxnError = TransactionError<VpcPeeringCreateError>;
+
+        let name = peering.name().to_string();
+        let vpc = db_vpc.clone();
+        let peer_vpc = db_peer_vpc.clone();
+        self.pool_authorized(opctx)
+            .await?
+            .transaction_async(|conn| async move {
+                use db::schema::router_route::dsl as route_dsl;
+                use db::schema::vpc_peering::dsl;
+                use db::schema::vpc_subnet::dsl as subnet_dsl;
+
+                let mut peering = peering;
+                let reciprocal = dsl::vpc_peering
+                    .filter(dsl::vpc_id.eq(peer_vpc.id()))
+                    .filter(dsl::peer_vpc_id.eq(vpc.id()))
+                    .filter(dsl::time_deleted.is_null())
+                    .select(VpcPeering::as_select())
+                    .load_async(&conn)
+                    .await?
+                    .pop();
+
+                if let Some(reciprocal) = reciprocal {
+                    let blocks = subnet_dsl::vpc_subnet
+                        .filter(
+                            subnet_dsl::vpc_id
+                                .eq_any(vec![vpc.id(), peer_vpc.id()]),
+                        )
+                        .filter(subnet_dsl::time_deleted.is_null())
+                        .select((subnet_dsl::vpc_id, subnet_dsl::ipv4_block))
+                        .load_async::<(Uuid, Ipv4Net)>(&conn)
+                        .await?;
+                    let (ours, theirs): (Vec<_>, Vec<_>) = blocks
+                        .into_iter()
+                        .map(|(vpc_id, block)| (vpc_id, block.0 .0))
+                        .partition(|(vpc_id, _)| *vpc_id == vpc.id());
+                    let overlapping = ours.iter().any(|(_, a)| {
+                        theirs.iter().any(|(_, b)| {
+                            a.contains(b.network()) || b.contains(a.network())
+                        })
+                    });
+                    if overlapping {
+                        return Err(TxnError::CustomError(
+                            VpcPeeringCreateError::OverlappingSubnets,
+                        ));
+                    }
+
+                    peering.state = VpcPeeringState::Active;
+                    diesel::update(dsl::vpc_peering)
+                        .filter(dsl::id.eq(reciprocal.id()))
+                        .set((
+                            dsl::state.eq(VpcPeeringState::Active),
+                            dsl::time_modified.eq(Utc::now()),
+                        ))
+                        .execute_async(&conn)
+                        .await?;
+                    diesel::insert_into(route_dsl::router_route)
+                        .values(vec![
+                            peering_route(&peering, &vpc, &peer_vpc),
+                            peering_route(&reciprocal, &peer_vpc, &vpc),
+                        ])
+                        .execute_async(&conn)
+                        .await?;
+                }
+
+                let peering = diesel::insert_into(dsl::vpc_peering)
+                    .values(peering)
+                    .returning(VpcPeering::as_returning())
+                    .get_result_async(&conn)
+                    .await?;
+                Ok(peering)
+            })
+            .await
+            .map_err(|e| match e {
+                TxnError::CustomError(
+                    VpcPeeringCreateError::OverlappingSubnets,
+                ) => Error::invalid_request(
+                    "VPCs whose subnets have overlapping IPv4 blocks cannot \
+                    be peered",
+                ),
+                TxnError::Pool(e) => public_error_from_diesel_pool(
+                    e,
+                    ErrorHandler::Conflict(ResourceType::VpcPeering, &name),
+                ),
+            })
+    }
+
+    /// Delete one VPC's side of a peering.
+    ///
+    /// If the peering was active, the peer VPC's side reverts to pending and
+    /// the routes between the two VPCs are removed.
+    pub async fn vpc_peering_delete(
+        &self,
+        opctx: &OpContext,
+        authz_peering: &authz::VpcPeering,
+        db_peering: &VpcPeering,
+    ) -> DeleteResult {
+        opctx.authorize(authz::Action::Delete, authz_peering).await?;
+
+        #[derive(Debug)]
+        enum VpcPeeringDeleteError {
+            NotFound,
+        }
+        type TxnError = TransactionError<VpcPeeringDeleteError>;
+
+        let peering_id = authz_peering.id();
+        let vpc_id = db_peering.vpc_id;
+        let peer_vpc_id = db_peering.peer_vpc_id;
+        self.pool_authorized(opctx)
+            .await?
+            .transaction_async(|conn| async move {
+                use db::schema::router_route::dsl as route_dsl;
+                use db::schema::vpc_peering::dsl;
+
+                let now = Utc::now();
+                let updated = diesel::update(dsl::vpc_peering)
+                    .filter(dsl::id.eq(peering_id))
+                    .filter(dsl::time_deleted.is_null())
+                    .set(dsl::time_deleted.eq(now))
+                    .execute_async(&conn)
+                    .await?;
+                if updated == 0 {
+                    return Err(TxnError::CustomError(
+                        VpcPeeringDeleteError::NotFound,
+                    ));
+                }
+
+                let mut route_ids = diesel::update(dsl::vpc_peering)
+                    .filter(dsl::vpc_id.eq(peer_vpc_id))
+                    .filter(dsl::peer_vpc_id.eq(vpc_id))
+                    .filter(dsl::time_deleted.is_null())
+                    .set((
+                        dsl::state.eq(VpcPeeringState::Pending),
+                        dsl::time_modified.eq(now),
+                    ))
+                    .returning(dsl::id)
+                    .get_results_async::<Uuid>(&conn)
+                    .await?;
+                route_ids.push(peering_id);
+
+                diesel::update(route_dsl::router_route)
+                    .filter(route_dsl::id.eq_any(route_ids))
+                    .filter(route_dsl::time_deleted.is_null())
+                    .set(route_dsl::time_deleted.eq(now))
+                    .execute_async(&conn)
+                    .await?;
+                Ok(())
+            })
+            .await
+            .map_err(|e| match e {
+                TxnError::CustomError(VpcPeeringDeleteError::NotFound) => {
+                    authz_peering.not_found()
+                }
+                TxnError::Pool(e) => {
+                    public_error_from_diesel_pool(e, ErrorHandler::Server)
+                }
+            })
+    }
+
+    /// Return the VPCs with which the provided VPC has an active peering.
+    pub async fn resolve_vpc_to_peer_vpcs(
+        &self,
+        vpc: &Vpc,
+    ) -> Result<Vec<Vpc>, Error> {
+        use db::schema::vpc;
+        use db::schema::vpc_peering;
+        vpc::table
+            .filter(
+                vpc::id.eq_any(
+                    vpc_peering::table
+                        .filter(vpc_peering::vpc_id.eq(vpc.id()))
+                        .filter(vpc_peering::state.eq(VpcPeeringState::Active))
+                        .filter(vpc_peering::time_deleted.is_null())
+                        .select(vpc_peering::peer_vpc_id),
+                ),
+            )
+            .filter(vpc::time_deleted.is_null())
+            .order(vpc::id.asc())
+            .select(Vpc::as_select())
+            .get_results_async(self.pool())
+            .await
+            .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+    }
+}
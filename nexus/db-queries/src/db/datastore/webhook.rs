@@ -0,0 +1,348 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! [`DataStore`] methods on webhook receivers, events, and deliveries.
+
+use super::DataStore;
+use crate::authz;
+use crate::authz::ApiResource;
+use crate::context::OpContext;
+use crate::db;
+use crate::db::error::public_error_from_diesel_pool;
+use crate::db::error::ErrorHandler;
+use crate::db::error::TransactionError;
+use crate::db::identity::Resource;
+use crate::db::model::Name;
+use crate::db::model::SqlU32;
+use crate::db::model::WebhookDelivery;
+use crate::db::model::WebhookDeliveryState;
+use crate::db::model::WebhookEvent;
+use crate::db::model::WebhookReceiver;
+use crate::db::pagination::paginated;
+use async_bb8_diesel::AsyncConnection;
+use async_bb8_diesel::AsyncRunQueryDsl;
+use chrono::DateTime;
+use chrono::Utc;
+use diesel::prelude::*;
+use omicron_common::api::external::http_pagination::PaginatedBy;
+use omicron_common::api::external::CreateResult;
+use omicron_common::api::external::DataPageParams;
+use omicron_common::api::external::DeleteResult;
+use omicron_common::api::external::Error;
+use omicron_common::api::external::ListResultVec;
+use omicron_common::api::external::ResourceType;
+use ref_cast::RefCast;
+use uuid::Uuid;
+
+impl DataStore {
+    /// Create a webhook receiver within a silo.
+    pub async fn webhook_receiver_create(
+        &self,
+        opctx: &OpContext,
+        authz_silo: &authz::Silo,
+        receiver: WebhookReceiver,
+    ) -> CreateResult<WebhookReceiver> {
+        assert_eq!(authz_silo.id(), receiver.silo_id);
+        opctx.authorize(authz::Action::CreateChild, authz_silo).await?;
+        let name = receiver.name().to_string();
+
+        use db::schema::webhook_receiver::dsl;
+        diesel::insert_into(dsl::webhook_receiver)
+            .values(receiver)
+            .returning(WebhookReceiver::as_returning())
+            .get_result_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel_pool(
+                    e,
+                    ErrorHandler::Conflict(
+                        ResourceType::WebhookReceiver,
+                        &name,
+                    ),
+                )
+            })
+    }
+
+    /// List the webhook receivers within a silo.
+    pub async fn webhook_receiver_list(
+        &self,
+        opctx: &OpContext,
+        authz_silo: &authz::Silo,
+        pagparams: &PaginatedBy<'_>,
+    ) -> ListResultVec<WebhookReceiver> {
+        opctx.authorize(authz::Action::ListChildren, authz_silo).await?;
+
+        use db::schema::webhook_receiver::dsl;
+        match pagparams {
+            PaginatedBy::Id(pagparams) => {
+                paginated(dsl::webhook_receiver, dsl::id, &pagparams)
+            }
+            PaginatedBy::Name(pagparams) => paginated(
+                dsl::webhook_receiver,
+                dsl::name,
+                &pagparams.map_name(|n| Name::ref_cast(n)),
+            ),
+        }
+        .filter(dsl::silo_id.eq(authz_silo.id()))
+        .filter(dsl::time_deleted.is_null())
+        .select(WebhookReceiver::as_select())
+        .load_async(self.pool_authorized(opctx).await?)
+        .await
+        .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+    }
+
+    /// Delete a webhook receiver, along with the record of the deliveries
+    /// made to it.  Events that have yet to be delivered to it never will be.
+    pub async fn webhook_receiver_delete(
+        &self,
+        opctx: &OpContext,
+        authz_receiver: &authz::WebhookReceiver,
+    ) -> DeleteResult {
+        opctx.authorize(authz::Action::Delete, authz_receiver).await?;
+
+        #[derive(Debug)]
+        enum WebhookReceiverDeleteError {
+            NotFound,
+        }
+        type TxnError = TransactionError<WebhookReceiverDeleteError>;
+
+        let receiver_id = authz_receiver.id();
+        self.pool_authorized(opctx)
+            .await?
+            .transaction_async(|conn| async move {
+                use db::schema::webhook_delivery::dsl as delivery_dsl;
+                use db::schema::webhook_receiver::dsl;
+
+                let updated = diesel::update(dsl::webhook_receiver)
+                    .filter(dsl::id.eq(receiver_id))
+                    .filter(dsl::time_deleted.is_null())
+                    .set(dsl::time_deleted.eq(Utc::now()))
+                    .execute_async(&conn)
+                    .await?;
+                if updated == 0 {
+                    return Err(TxnError::CustomError(
+                        WebhookReceiverDeleteError::NotFound,
+                    ));
+                }
+
+                diesel::delete(delivery_dsl::webhook_delivery)
+                    .filter(delivery_dsl::receiver_id.eq(receiver_id))
+                    .execute_async(&conn)
+                    .await?;
+                Ok(())
+            })
+            .await
+            .map_err(|e| match e {
+                TxnError::CustomError(WebhookReceiverDeleteError::NotFound) => {
+                    authz_receiver.not_found()
+                }
+                TxnError::Pool(e) => {
+                    public_error_from_diesel_pool(e, ErrorHandler::Server)
+                }
+            })
+    }
+
+    /// List the deliveries of events to a webhook receiver, along with the
+    /// events themselves
+    pub async fn webhook_delivery_list(
+        &self,
+        opctx: &OpContext,
+        authz_receiver: &authz::WebhookReceiver,
+        pagparams: &DataPageParams<'_, Uuid>,
+    ) -> ListResultVec<(WebhookDelivery, WebhookEvent)> {
+        opctx.authorize(authz::Action::ListChildren, authz_receiver).await?;
+
+        use db::schema::webhook_delivery::dsl;
+        use db::schema::webhook_event;
+        paginated(dsl::webhook_delivery, dsl::id, pagparams)
+            .inner_join(
+                webhook_event::table.on(webhook_event::id.eq(dsl::event_id)),
+            )
+            .filter(dsl::receiver_id.eq(authz_receiver.id()))
+            .select((WebhookDelivery::as_select(), WebhookEvent::as_select()))
+            .load_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+    }
+
+    /// Record an event, along with a pending delivery of it to each webhook
+    /// receiver subscribed to it
+    ///
+    /// An event concerning a silo is delivered to that silo's receivers, while
+    /// one that concerns the whole system (with no silo) is delivered to
+    /// receivers in every silo.  An event no receiver is subscribed to isn't
+    /// recorded at all.  Returns the number of deliveries to be made.
+    pub async fn webhook_event_publish(
+        &self,
+        opctx: &OpContext,
+        event: WebhookEvent,
+    ) -> Result<usize, Error> {
+        opctx.authorize(authz::Action::Modify, &authz::FLEET).await?;
+
+        type TxnError = TransactionError<Error>;
+        self.pool_authorized(opctx)
+            .await?
+            .transaction_async(|conn| async move {
+                use db::schema::webhook_delivery::dsl as delivery_dsl;
+                use db::schema::webhook_event::dsl as event_dsl;
+                use db::schema::webhook_receiver::dsl;
+
+                let mut query = dsl::webhook_receiver
+                    .filter(dsl::time_deleted.is_null())
+                    .filter(
+                        dsl::event_classes.contains(vec![event.event_class]),
+                    )
+                    .into_boxed();
+                if let Some(silo_id) = event.silo_id {
+                    query = query.filter(dsl::silo_id.eq(silo_id));
+                }
+                let receiver_ids: Vec<Uuid> =
+                    query.select(dsl::id).load_async(&conn).await?;
+                if receiver_ids.is_empty() {
+                    return Ok(0);
+                }
+
+                let deliveries: Vec<WebhookDelivery> = receiver_ids
+                    .into_iter()
+                    .map(|receiver_id| {
+                        WebhookDelivery::new(&event, receiver_id)
+                    })
+                    .collect();
+                diesel::insert_into(event_dsl::webhook_event)
+                    .values(event)
+                    .execute_async(&conn)
+                    .await?;
+                let ndeliveries =
+                    diesel::insert_into(delivery_dsl::webhook_delivery)
+                        .values(deliveries)
+                        .execute_async(&conn)
+                        .await?;
+                Ok(ndeliveries)
+            })
+            .await
+            .map_err(|e| match e {
+                TxnError::CustomError(e) => e,
+                TxnError::Pool(e) => {
+                    public_error_from_diesel_pool(e, ErrorHandler::Server)
+                }
+            })
+    }
+
+    /// List up to `limit` of the pending deliveries that are due to be
+    /// attempted at time `now`, along with the event to deliver and the
+    /// receiver to deliver it to, for the background task that delivers them
+    pub async fn webhook_delivery_list_due(
+        &self,
+        opctx: &OpContext,
+        now: DateTime<Utc>,
+        limit: u32,
+    ) -> ListResultVec<(WebhookDelivery, WebhookEvent, WebhookReceiver)> {
+        opctx.authorize(authz::Action::ListChildren, &authz::FLEET).await?;
+
+        use db::schema::webhook_delivery::dsl;
+        use db::schema::webhook_event;
+        use db::schema::webhook_receiver;
+        dsl::webhook_delivery
+            .inner_join(
+                webhook_event::table.on(webhook_event::id.eq(dsl::event_id)),
+            )
+            .inner_join(
+                webhook_receiver::table
+                    .on(webhook_receiver::id.eq(dsl::receiver_id)),
+            )
+            .filter(dsl::state.eq(WebhookDeliveryState::Pending))
+            .filter(dsl::time_next_attempt.le(now))
+            .filter(webhook_receiver::time_deleted.is_null())
+            .order((dsl::time_next_attempt, dsl::id))
+            .limit(i64::from(limit))
+            .select((
+                WebhookDelivery::as_select(),
+                WebhookEvent::as_select(),
+                WebhookReceiver::as_select(),
+            ))
+            .load_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| public_error_from_diesel_pool(e, ErrorHandler::Server))
+    }
+
+    /// Records that an attempt to make a delivery started at `time_started`,
+    /// and that if it fails, the next attempt is due at `time_next_attempt`
+    ///
+    /// The attempt is only recorded if no other attempt has been made since
+    /// `delivery` was fetched.  Returns true if the attempt was recorded, in
+    /// which case the caller should go on to make it, or false if some other
+    /// Nexus got there first.
+    pub async fn webhook_delivery_attempt_start(
+        &self,
+        opctx: &OpContext,
+        delivery: &WebhookDelivery,
+        time_started: DateTime<Utc>,
+        time_next_attempt: DateTime<Utc>,
+    ) -> Result<bool, Error> {
+        opctx.authorize(authz::Action::Modify, &authz::FLEET).await?;
+
+        use db::schema::webhook_delivery::dsl;
+        let updated = diesel::update(dsl::webhook_delivery)
+            .filter(dsl::id.eq(delivery.id))
+            .filter(dsl::state.eq(WebhookDeliveryState::Pending))
+            .filter(dsl::attempts.eq(delivery.attempts))
+            .set((
+                dsl::attempts.eq(SqlU32::new(*delivery.attempts + 1)),
+                dsl::time_last_attempt.eq(time_started),
+                dsl::time_next_attempt.eq(time_next_attempt),
+            ))
+            .execute_async(self.pool_authorized(opctx).await?)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel_pool(e, ErrorHandler::Server)
+            })?;
+        Ok(updated > 0)
+    }
+
+    /// Records the outcome of attempt number `attempt` to make a delivery,
+    /// unless a later attempt has started since
+    ///
+    /// If the delivery is still pending, the next attempt remains due at the
+    /// time recorded when this attempt started.
+    pub async fn webhook_delivery_attempt_finish(
+        &self,
+        opctx: &OpContext,
+        delivery_id: Uuid,
+        attempt: u32,
+        state: WebhookDeliveryState,
+        response_status: Option<u16>,
+        error: Option<String>,
+    ) -> Result<(), Error> {
+        opctx.authorize(authz::Action::Modify, &authz::FLEET).await?;
+
+        use db::schema::webhook_delivery::dsl;
+        let query = diesel::update(dsl::webhook_delivery)
+            .filter(dsl::id.eq(delivery_id))
+            .filter(dsl::attempts.eq(SqlU32::new(attempt)));
+        let response_status = response_status.map(i32::from);
+        let result = if state == WebhookDeliveryState::Pending {
+            query
+                .set((
+                    dsl::last_response_status.eq(response_status),
+                    dsl::last_error.eq(error),
+                ))
+                .execute_async(self.pool_authorized(opctx).await?)
+                .await
+        } else {
+            query
+                .set((
+                    dsl::state.eq(state),
+                    dsl::time_next_attempt.eq(None::<DateTime<Utc>>),
+                    dsl::last_response_status.eq(response_status),
+                    dsl::last_error.eq(error),
+                ))
+                .execute_async(self.pool_authorized(opctx).await?)
+                .await
+        };
+        result.map_err(|e| {
+            public_error_from_diesel_pool(e, ErrorHandler::Server)
+        })?;
+        Ok(())
+    }
+}
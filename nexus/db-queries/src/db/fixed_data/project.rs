@@ -23,6 +23,7 @@ lazy_static! {
                 name: SERVICES_DB_NAME.parse().unwrap(),
                 description: "Built-in project for Oxide Services".to_string(),
             },
+            labels: Default::default(),
         },
     );
 }
@@ -39,6 +39,7 @@ lazy_static! {
             },
             ipv6_prefix: Some(*SERVICE_VPC_IPV6_PREFIX),
             dns_name: SERVICES_DB_NAME.parse().unwrap(),
+            labels: Default::default(),
         },
     )
     // `IncompleteVpc::new` only fails if given an invalid `ipv6_prefix`
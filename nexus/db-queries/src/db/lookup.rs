@@ -203,6 +203,21 @@ impl<'a> LookupPath<'a> {
         Snapshot::PrimaryKey(Root { lookup_root: self }, id)
     }
 
+    /// Select a resource of type FloatingIp, identified by its id
+    pub fn floating_ip_id(self, id: Uuid) -> FloatingIp<'a> {
+        FloatingIp::PrimaryKey(Root { lookup_root: self }, id)
+    }
+
+    /// Select a resource of type AffinityGroup, identified by its id
+    pub fn affinity_group_id(self, id: Uuid) -> AffinityGroup<'a> {
+        AffinityGroup::PrimaryKey(Root { lookup_root: self }, id)
+    }
+
+    /// Select a resource of type SnapshotSchedule, identified by its id
+    pub fn snapshot_schedule_id(self, id: Uuid) -> SnapshotSchedule<'a> {
+        SnapshotSchedule::PrimaryKey(Root { lookup_root: self }, id)
+    }
+
     /// Select a resource of type InstanceNetworkInterface, identified by its id
     pub fn instance_network_interface_id(
         self,
@@ -221,6 +236,11 @@ impl<'a> LookupPath<'a> {
         VpcSubnet::PrimaryKey(Root { lookup_root: self }, id)
     }
 
+    /// Select a resource of type VpcPeering, identified by its id
+    pub fn vpc_peering_id(self, id: Uuid) -> VpcPeering<'a> {
+        VpcPeering::PrimaryKey(Root { lookup_root: self }, id)
+    }
+
     /// Select a resource of type VpcRouter, identified by its id
     pub fn vpc_router_id(self, id: Uuid) -> VpcRouter<'a> {
         VpcRouter::PrimaryKey(Root { lookup_root: self }, id)
@@ -399,6 +419,11 @@ impl<'a> LookupPath<'a> {
         }
     }
 
+    /// Select a resource of type WebhookReceiver, identified by its id
+    pub fn webhook_receiver_id(self, id: Uuid) -> WebhookReceiver<'a> {
+        WebhookReceiver::PrimaryKey(Root { lookup_root: self }, id)
+    }
+
     pub fn address_lot_id(self, id: Uuid) -> AddressLot<'a> {
         AddressLot::PrimaryKey(Root { lookup_root: self }, id)
     }
@@ -525,6 +550,17 @@ impl<'a> LookupPath<'a> {
     {
         SamlIdentityProvider::PrimaryKey(Root { lookup_root: self }, id)
     }
+
+    /// Select a resource of type OidcIdentityProvider, identified by its id
+    pub fn oidc_identity_provider_id<'b>(
+        self,
+        id: Uuid,
+    ) -> OidcIdentityProvider<'b>
+    where
+        'a: 'b,
+    {
+        OidcIdentityProvider::PrimaryKey(Root { lookup_root: self }, id)
+    }
 }
 
 /// Represents the head of the selection path for a resource
@@ -548,7 +584,7 @@ impl<'a> Root<'a> {
 lookup_resource! {
     name = "Silo",
     ancestors = [],
-    children = [ "IdentityProvider", "SamlIdentityProvider", "Project", "SiloImage", "Certificate" ],
+    children = [ "IdentityProvider", "SamlIdentityProvider", "OidcIdentityProvider", "Project", "SiloImage", "Certificate", "WebhookReceiver" ],
     lookup_by_name = true,
     soft_deletes = true,
     primary_key_columns = [ { column_name = "id", rust_type = Uuid } ]
@@ -582,6 +618,15 @@ lookup_resource! {
     primary_key_columns = [ { column_name = "id", rust_type = Uuid } ]
 }
 
+lookup_resource! {
+    name = "WebhookReceiver",
+    ancestors = [ "Silo" ],
+    children = [],
+    lookup_by_name = true,
+    soft_deletes = true,
+    primary_key_columns = [ { column_name = "id", rust_type = Uuid } ]
+}
+
 lookup_resource! {
     name = "IdentityProvider",
     ancestors = [ "Silo" ],
@@ -615,6 +660,18 @@ lookup_resource! {
     visible_outside_silo = true
 }
 
+lookup_resource! {
+    name = "OidcIdentityProvider",
+    ancestors = [ "Silo" ],
+    children = [],
+    lookup_by_name = true,
+    soft_deletes = true,
+    primary_key_columns = [
+        { column_name = "id", rust_type = Uuid },
+    ],
+    visible_outside_silo = true
+}
+
 lookup_resource! {
     name = "SshKey",
     ancestors = [ "Silo", "SiloUser" ],
@@ -627,7 +684,16 @@ lookup_resource! {
 lookup_resource! {
     name = "Project",
     ancestors = [ "Silo" ],
-    children = [ "Disk", "Instance", "Vpc", "Snapshot", "ProjectImage" ],
+    children = [
+        "Disk",
+        "Instance",
+        "Vpc",
+        "Snapshot",
+        "ProjectImage",
+        "FloatingIp",
+        "AffinityGroup",
+        "SnapshotSchedule"
+    ],
     lookup_by_name = true,
     soft_deletes = true,
     primary_key_columns = [ { column_name = "id", rust_type = Uuid } ]
@@ -669,6 +735,15 @@ lookup_resource! {
     primary_key_columns = [ { column_name = "id", rust_type = Uuid } ]
 }
 
+lookup_resource! {
+    name = "SnapshotSchedule",
+    ancestors = [ "Silo", "Project" ],
+    children = [],
+    lookup_by_name = true,
+    soft_deletes = true,
+    primary_key_columns = [ { column_name = "id", rust_type = Uuid } ]
+}
+
 lookup_resource! {
     name = "Instance",
     ancestors = [ "Silo", "Project" ],
@@ -678,6 +753,24 @@ lookup_resource! {
     primary_key_columns = [ { column_name = "id", rust_type = Uuid } ]
 }
 
+lookup_resource! {
+    name = "FloatingIp",
+    ancestors = [ "Silo", "Project" ],
+    children = [],
+    lookup_by_name = true,
+    soft_deletes = true,
+    primary_key_columns = [ { column_name = "id", rust_type = Uuid } ]
+}
+
+lookup_resource! {
+    name = "AffinityGroup",
+    ancestors = [ "Silo", "Project" ],
+    children = [],
+    lookup_by_name = true,
+    soft_deletes = true,
+    primary_key_columns = [ { column_name = "id", rust_type = Uuid } ]
+}
+
 lookup_resource! {
     name = "InstanceNetworkInterface",
     ancestors = [ "Silo", "Project", "Instance" ],
@@ -690,7 +783,7 @@ lookup_resource! {
 lookup_resource! {
     name = "Vpc",
     ancestors = [ "Silo", "Project" ],
-    children = [ "VpcRouter", "VpcSubnet" ],
+    children = [ "VpcRouter", "VpcSubnet", "VpcPeering" ],
     lookup_by_name = true,
     soft_deletes = true,
     primary_key_columns = [ { column_name = "id", rust_type = Uuid } ]
@@ -723,6 +816,15 @@ lookup_resource! {
     primary_key_columns = [ { column_name = "id", rust_type = Uuid } ]
 }
 
+lookup_resource! {
+    name = "VpcPeering",
+    ancestors = [ "Silo", "Project", "Vpc" ],
+    children = [ ],
+    lookup_by_name = true,
+    soft_deletes = true,
+    primary_key_columns = [ { column_name = "id", rust_type = Uuid } ]
+}
+
 // Miscellaneous resources nested directly below "Fleet"
 
 lookup_resource! {
@@ -97,7 +97,8 @@ const MAX_PORT: u16 = u16::MAX;
 ///         <kind> AS kind,
 ///         candidate_ip AS ip,
 ///         CAST(candidate_first_port AS INT4) AS first_port,
-///         CAST(candidate_last_port AS INT4) AS last_port
+///         CAST(candidate_last_port AS INT4) AS last_port,
+///         <project_id> AS project_id
 ///     FROM
 ///         SELECT * FROM (
 ///             -- Select all IP addresses by pool and range.
@@ -370,6 +371,12 @@ impl NextExternalIp {
         out.push_identifier(dsl::first_port::NAME)?;
         out.push_sql(", CAST(candidate_last_port AS INT4) AS ");
         out.push_identifier(dsl::last_port::NAME)?;
+        out.push_sql(", ");
+
+        // Project ID, only set for Floating IPs
+        out.push_bind_param::<sql_types::Nullable<sql_types::Uuid>, Option<Uuid>>(self.ip.project_id())?;
+        out.push_sql(" AS ");
+        out.push_identifier(dsl::project_id::NAME)?;
         out.push_sql(" FROM (");
         self.push_address_sequence_subquery(out.reborrow())?;
         out.push_sql(") CROSS JOIN (");
@@ -861,14 +868,20 @@ mod tests {
             Self { logctx, opctx, db, db_datastore }
         }
 
-        async fn create_ip_pool(&self, name: &str, range: IpRange) {
-            let internal = false;
+        async fn create_ip_pool(
+            &self,
+            name: &str,
+            range: IpRange,
+            silo_id: Option<Uuid>,
+            is_default: bool,
+        ) {
             let pool = IpPool::new(
                 &IdentityMetadataCreateParams {
                     name: String::from(name).parse().unwrap(),
                     description: format!("ip pool {}", name),
                 },
-                internal,
+                silo_id,
+                is_default,
             );
 
             use crate::db::schema::ip_pool::dsl as ip_pool_dsl;
@@ -1706,7 +1719,7 @@ mod tests {
             Ipv4Addr::new(10, 0, 0, 6),
         ))
         .unwrap();
-        context.create_ip_pool("p1", second_range).await;
+        context.create_ip_pool("p1", second_range, None, false).await;
 
         // Allocating an address on an instance in the second pool should be
         // respected, even though there are IPs available in the first.
@@ -1732,6 +1745,69 @@ mod tests {
         context.success().await;
     }
 
+    #[tokio::test]
+    async fn test_next_external_ip_is_restricted_to_silo_pools() {
+        let context = TestContext::new(
+            "test_next_external_ip_is_restricted_to_silo_pools",
+        )
+        .await;
+        let silo_id = context.opctx.authn.silo_required().unwrap().id();
+
+        let default_range = IpRange::try_from((
+            Ipv4Addr::new(10, 0, 0, 1),
+            Ipv4Addr::new(10, 0, 0, 3),
+        ))
+        .unwrap();
+        context.initialize_ip_pool("default", default_range).await;
+
+        // A pool linked to some other Silo can't be used, even by name.
+        let other_range = IpRange::try_from((
+            Ipv4Addr::new(10, 0, 0, 4),
+            Ipv4Addr::new(10, 0, 0, 6),
+        ))
+        .unwrap();
+        context
+            .create_ip_pool("other", other_range, Some(Uuid::new_v4()), false)
+            .await;
+        let err = context
+            .db_datastore
+            .allocate_instance_ephemeral_ip(
+                &context.opctx,
+                Uuid::new_v4(),
+                Uuid::new_v4(),
+                Some(Name("other".parse().unwrap())),
+            )
+            .await
+            .expect_err("Should not use a pool linked to another Silo");
+        assert!(
+            matches!(err, Error::ObjectNotFound { .. }),
+            "Expected ObjectNotFound, found {:?}",
+            err
+        );
+
+        // The default pool of the caller's own Silo is used in preference to
+        // the fleet's default pool.
+        let silo_range = IpRange::try_from((
+            Ipv4Addr::new(10, 0, 0, 7),
+            Ipv4Addr::new(10, 0, 0, 9),
+        ))
+        .unwrap();
+        context.create_ip_pool("mine", silo_range, Some(silo_id), true).await;
+        let ip = context
+            .db_datastore
+            .allocate_instance_ephemeral_ip(
+                &context.opctx,
+                Uuid::new_v4(),
+                Uuid::new_v4(),
+                None,
+            )
+            .await
+            .expect("Failed to allocate instance ephemeral IP address");
+        assert_eq!(ip.ip.ip(), silo_range.first_address());
+
+        context.success().await;
+    }
+
     #[tokio::test]
     async fn test_ensure_pool_exhaustion_does_not_use_other_pool() {
         let context = TestContext::new(
@@ -1749,7 +1825,7 @@ mod tests {
         let last_address = Ipv4Addr::new(10, 0, 0, 6);
         let second_range =
             IpRange::try_from((first_address, last_address)).unwrap();
-        context.create_ip_pool("p1", second_range).await;
+        context.create_ip_pool("p1", second_range, None, false).await;
 
         // Allocate all available addresses in the second pool.
         let instance_id = Uuid::new_v4();
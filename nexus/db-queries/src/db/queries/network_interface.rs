@@ -1725,7 +1725,11 @@ mod tests {
             network_interfaces: InstanceNetworkInterfaceAttachment::None,
             external_ips: vec![],
             disks: vec![],
+            affinity_groups: vec![],
+            ssh_public_keys: None,
             start: true,
+            labels: Default::default(),
+            auto_restart_policy: Default::default(),
         };
         let runtime = InstanceRuntimeState {
             run_state: InstanceState::Creating,
@@ -1877,6 +1881,7 @@ mod tests {
                         name: "project".parse().unwrap(),
                         description: "desc".to_string(),
                     },
+                    labels: Default::default(),
                 },
             );
             let (.., project) =
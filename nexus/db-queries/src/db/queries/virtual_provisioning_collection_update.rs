@@ -99,6 +99,47 @@ impl DoUpdate {
         }
     }
 
+    fn new_for_resize_storage(id: uuid::Uuid, old_bytes: ByteCount) -> Self {
+        use virtual_provisioning_resource::dsl;
+
+        let not_resized = dsl::virtual_provisioning_resource
+            .filter(dsl::id.eq(id))
+            .filter(dsl::virtual_disk_bytes_provisioned.eq(old_bytes))
+            .count()
+            .single_value()
+            .assume_not_null()
+            .eq(1);
+
+        Self {
+            query: Box::new(diesel::select((ExpressionAlias::new::<
+                do_update::update,
+            >(not_resized),))),
+        }
+    }
+
+    fn new_for_resize_instance(
+        id: uuid::Uuid,
+        old_cpus: i64,
+        old_ram: ByteCount,
+    ) -> Self {
+        use virtual_provisioning_resource::dsl;
+
+        let not_resized = dsl::virtual_provisioning_resource
+            .filter(dsl::id.eq(id))
+            .filter(dsl::cpus_provisioned.eq(old_cpus))
+            .filter(dsl::ram_provisioned.eq(old_ram))
+            .count()
+            .single_value()
+            .assume_not_null()
+            .eq(1);
+
+        Self {
+            query: Box::new(diesel::select((ExpressionAlias::new::<
+                do_update::update,
+            >(not_resized),))),
+        }
+    }
+
     fn new_for_delete(id: uuid::Uuid) -> Self {
         use virtual_provisioning_resource::dsl;
 
@@ -325,6 +366,45 @@ impl VirtualProvisioningCollectionUpdate {
         )
     }
 
+    pub fn new_resize_storage(
+        id: uuid::Uuid,
+        old_bytes: ByteCount,
+        new_bytes: ByteCount,
+        project_id: uuid::Uuid,
+    ) -> Self {
+        use virtual_provisioning_collection::dsl as collection_dsl;
+        use virtual_provisioning_resource::dsl as resource_dsl;
+
+        Self::apply_update(
+            // We should update the record if it still has the old size.
+            DoUpdate::new_for_resize_storage(id, old_bytes),
+            // The query to actually update the record.
+            UnreferenceableSubquery(
+                diesel::update(resource_dsl::virtual_provisioning_resource)
+                    .filter(resource_dsl::id.eq(id))
+                    .filter(
+                        resource_dsl::virtual_disk_bytes_provisioned
+                            .eq(old_bytes),
+                    )
+                    .set(
+                        resource_dsl::virtual_disk_bytes_provisioned
+                            .eq(new_bytes),
+                    )
+                    .returning(virtual_provisioning_resource::all_columns),
+            ),
+            // Within this project, silo, fleet...
+            project_id,
+            // ... We replace the old disk usage with the new.
+            (
+                collection_dsl::time_modified.eq(diesel::dsl::now),
+                collection_dsl::virtual_disk_bytes_provisioned
+                    .eq(collection_dsl::virtual_disk_bytes_provisioned
+                        + new_bytes
+                        - old_bytes),
+            ),
+        )
+    }
+
     pub fn new_insert_instance(
         id: uuid::Uuid,
         cpus_diff: i64,
@@ -366,6 +446,45 @@ impl VirtualProvisioningCollectionUpdate {
         )
     }
 
+    pub fn new_resize_instance(
+        id: uuid::Uuid,
+        old_cpus: i64,
+        old_ram: ByteCount,
+        new_cpus: i64,
+        new_ram: ByteCount,
+        project_id: uuid::Uuid,
+    ) -> Self {
+        use virtual_provisioning_collection::dsl as collection_dsl;
+        use virtual_provisioning_resource::dsl as resource_dsl;
+
+        Self::apply_update(
+            // We should update the record if it still has the old resources.
+            DoUpdate::new_for_resize_instance(id, old_cpus, old_ram),
+            // The query to actually update the record.
+            UnreferenceableSubquery(
+                diesel::update(resource_dsl::virtual_provisioning_resource)
+                    .filter(resource_dsl::id.eq(id))
+                    .filter(resource_dsl::cpus_provisioned.eq(old_cpus))
+                    .filter(resource_dsl::ram_provisioned.eq(old_ram))
+                    .set((
+                        resource_dsl::cpus_provisioned.eq(new_cpus),
+                        resource_dsl::ram_provisioned.eq(new_ram),
+                    ))
+                    .returning(virtual_provisioning_resource::all_columns),
+            ),
+            // Within this project, silo, fleet...
+            project_id,
+            // ... We replace the old resource usage with the new.
+            (
+                collection_dsl::time_modified.eq(diesel::dsl::now),
+                collection_dsl::cpus_provisioned
+                    .eq(collection_dsl::cpus_provisioned + new_cpus - old_cpus),
+                collection_dsl::ram_provisioned
+                    .eq(collection_dsl::ram_provisioned + new_ram - old_ram),
+            ),
+        )
+    }
+
     pub fn new_delete_instance(
         id: uuid::Uuid,
         cpus_diff: i64,
@@ -6,6 +6,7 @@
 
 use crate::db::model::Generation;
 use crate::db::model::IncompleteVpc;
+use crate::db::model::Labels;
 use crate::db::model::Name;
 use crate::db::model::Vni;
 use crate::db::queries::next_item::DefaultShiftGenerator;
@@ -182,6 +183,11 @@ impl QueryFragment<Pg> for InsertVpcQuery {
         )?;
         out.push_sql(" AS ");
         out.push_identifier(dsl::subnet_gen::NAME)?;
+        out.push_sql(", ");
+
+        out.push_bind_param::<sql_types::Jsonb, Labels>(&self.vpc.labels)?;
+        out.push_sql(" AS ");
+        out.push_identifier(dsl::labels::NAME)?;
 
         Ok(())
     }
@@ -231,6 +237,8 @@ impl QueryFragment<Pg> for InsertVpcQueryValues {
         out.push_identifier(dsl::firewall_gen::NAME)?;
         out.push_sql(", ");
         out.push_identifier(dsl::subnet_gen::NAME)?;
+        out.push_sql(", ");
+        out.push_identifier(dsl::labels::NAME)?;
         out.push_sql(")");
         self.0.walk_ast(out)
     }
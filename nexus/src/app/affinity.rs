@@ -0,0 +1,209 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Affinity groups, which constrain the placement of instances on sleds
+
+use crate::authz;
+use crate::db;
+use crate::db::identity::Resource;
+use crate::db::lookup;
+use crate::db::lookup::LookupPath;
+use crate::db::model::SledReservationConstraintBuilder;
+use nexus_db_queries::context::OpContext;
+use nexus_types::external_api::params;
+use omicron_common::api::external::http_pagination::PaginatedBy;
+use omicron_common::api::external::CreateResult;
+use omicron_common::api::external::DeleteResult;
+use omicron_common::api::external::Error;
+use omicron_common::api::external::ListResultVec;
+use omicron_common::api::external::LookupResult;
+use omicron_common::api::external::NameOrId;
+use uuid::Uuid;
+
+impl super::Nexus {
+    pub fn affinity_group_lookup<'a>(
+        &'a self,
+        opctx: &'a OpContext,
+        group_selector: params::AffinityGroupSelector,
+    ) -> LookupResult<lookup::AffinityGroup<'a>> {
+        match group_selector {
+            params::AffinityGroupSelector {
+                affinity_group: NameOrId::Id(id),
+                project: None,
+            } => {
+                let group = LookupPath::new(opctx, &self.db_datastore)
+                    .affinity_group_id(id);
+                Ok(group)
+            }
+            params::AffinityGroupSelector {
+                affinity_group: NameOrId::Name(name),
+                project: Some(project),
+            } => {
+                let group = self
+                    .project_lookup(opctx, params::ProjectSelector { project })?
+                    .affinity_group_name_owned(name.into());
+                Ok(group)
+            }
+            params::AffinityGroupSelector {
+                affinity_group: NameOrId::Id(_),
+                ..
+            } => Err(Error::invalid_request(
+                "when providing affinity group as an ID project should not be specified",
+            )),
+            _ => Err(Error::invalid_request(
+                "affinity group should either be UUID or project should be specified",
+            )),
+        }
+    }
+
+    /// Resolves `group` to an affinity group within the project `authz_project`
+    pub(crate) async fn affinity_group_lookup_in_project(
+        &self,
+        opctx: &OpContext,
+        authz_project: &authz::Project,
+        group: &NameOrId,
+    ) -> LookupResult<authz::AffinityGroup> {
+        let group_lookup = match group {
+            NameOrId::Id(id) => LookupPath::new(opctx, &self.db_datastore)
+                .affinity_group_id(*id),
+            NameOrId::Name(name) => LookupPath::new(opctx, &self.db_datastore)
+                .project_id(authz_project.id())
+                .affinity_group_name_owned(name.clone().into()),
+        };
+        let (.., authz_group_project, authz_group) =
+            group_lookup.lookup_for(authz::Action::Modify).await?;
+        if authz_group_project.id() != authz_project.id() {
+            return Err(Error::invalid_request(
+                "affinity group must be in the same project as the instance",
+            ));
+        }
+        Ok(authz_group)
+    }
+
+    pub async fn affinity_group_create(
+        &self,
+        opctx: &OpContext,
+        project_lookup: &lookup::Project<'_>,
+        params: params::AffinityGroupCreate,
+    ) -> CreateResult<db::model::AffinityGroup> {
+        let (.., authz_project) =
+            project_lookup.lookup_for(authz::Action::CreateChild).await?;
+
+        let group = db::model::AffinityGroup::new(authz_project.id(), params);
+        self.db_datastore
+            .affinity_group_create(opctx, &authz_project, group)
+            .await
+    }
+
+    pub async fn affinity_group_list(
+        &self,
+        opctx: &OpContext,
+        project_lookup: &lookup::Project<'_>,
+        pagparams: &PaginatedBy<'_>,
+    ) -> ListResultVec<db::model::AffinityGroup> {
+        let (.., authz_project) =
+            project_lookup.lookup_for(authz::Action::ListChildren).await?;
+
+        self.db_datastore
+            .affinity_group_list(opctx, &authz_project, pagparams)
+            .await
+    }
+
+    pub async fn affinity_group_delete(
+        &self,
+        opctx: &OpContext,
+        group_lookup: &lookup::AffinityGroup<'_>,
+    ) -> DeleteResult {
+        let (.., authz_group) =
+            group_lookup.lookup_for(authz::Action::Delete).await?;
+
+        self.db_datastore.affinity_group_delete(opctx, &authz_group).await
+    }
+
+    pub async fn affinity_group_member_list(
+        &self,
+        opctx: &OpContext,
+        group_lookup: &lookup::AffinityGroup<'_>,
+        pagparams: &PaginatedBy<'_>,
+    ) -> ListResultVec<db::model::Instance> {
+        let (.., authz_group) =
+            group_lookup.lookup_for(authz::Action::Read).await?;
+
+        self.db_datastore
+            .affinity_group_member_list(opctx, &authz_group, pagparams)
+            .await
+    }
+
+    /// Add an instance in the same project to an affinity group.
+    pub async fn affinity_group_member_add(
+        &self,
+        opctx: &OpContext,
+        group_lookup: &lookup::AffinityGroup<'_>,
+        instance: NameOrId,
+    ) -> CreateResult<db::model::Instance> {
+        let (.., authz_project, authz_group) =
+            group_lookup.lookup_for(authz::Action::Modify).await?;
+        let (authz_instance, db_instance) = self
+            .affinity_group_member_fetch(opctx, &authz_project, instance)
+            .await?;
+
+        self.db_datastore
+            .affinity_group_member_add(opctx, &authz_group, &authz_instance)
+            .await?;
+        Ok(db_instance)
+    }
+
+    /// Remove an instance from an affinity group.
+    pub async fn affinity_group_member_remove(
+        &self,
+        opctx: &OpContext,
+        group_lookup: &lookup::AffinityGroup<'_>,
+        instance: NameOrId,
+    ) -> DeleteResult {
+        let (.., authz_project, authz_group) =
+            group_lookup.lookup_for(authz::Action::Modify).await?;
+        let (authz_instance, _) = self
+            .affinity_group_member_fetch(opctx, &authz_project, instance)
+            .await?;
+
+        self.db_datastore
+            .affinity_group_member_remove(opctx, &authz_group, &authz_instance)
+            .await
+    }
+
+    async fn affinity_group_member_fetch(
+        &self,
+        opctx: &OpContext,
+        authz_project: &authz::Project,
+        instance: NameOrId,
+    ) -> LookupResult<(authz::Instance, db::model::Instance)> {
+        let instance_lookup = match instance {
+            NameOrId::Id(id) => {
+                LookupPath::new(opctx, &self.db_datastore).instance_id(id)
+            }
+            NameOrId::Name(name) => LookupPath::new(opctx, &self.db_datastore)
+                .project_id(authz_project.id())
+                .instance_name_owned(name.into()),
+        };
+        let (.., authz_instance_project, authz_instance, db_instance) =
+            instance_lookup.fetch_for(authz::Action::Modify).await?;
+        if authz_instance_project.id() != authz_project.id() {
+            return Err(Error::invalid_request(
+                "instance must be in the same project as the affinity group",
+            ));
+        }
+        Ok((authz_instance, db_instance))
+    }
+
+    /// Returns the constraints that the affinity groups of which the instance
+    /// `instance_id` is a member place on the sled selected for it.
+    pub(crate) async fn instance_affinity_constraints(
+        &self,
+        instance_id: Uuid,
+    ) -> Result<SledReservationConstraintBuilder, Error> {
+        self.db_datastore
+            .instance_affinity_constraints(&self.opctx_alloc, instance_id)
+            .await
+    }
+}
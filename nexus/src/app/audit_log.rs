@@ -0,0 +1,49 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Audit log
+
+use crate::db;
+use chrono::DateTime;
+use chrono::Utc;
+use nexus_db_queries::context::OpContext;
+use nexus_types::external_api::params;
+use omicron_common::api::external::CreateResult;
+use omicron_common::api::external::DataPageParams;
+use omicron_common::api::external::Error;
+use omicron_common::api::external::ListResultVec;
+use uuid::Uuid;
+
+impl super::Nexus {
+    /// Records an entry in the audit log
+    ///
+    /// This is used by the external API server, which has no `OpContext` of its
+    /// own that's allowed to write to the audit log.
+    pub(crate) async fn audit_log_record(
+        &self,
+        entry: db::model::AuditLogEntry,
+    ) -> CreateResult<()> {
+        self.db_datastore
+            .audit_log_entry_insert(&self.opctx_external_authn, entry)
+            .await
+    }
+
+    pub async fn audit_log_list(
+        &self,
+        opctx: &OpContext,
+        selector: &params::AuditLogSelector,
+        pagparams: &DataPageParams<'_, (DateTime<Utc>, Uuid)>,
+    ) -> ListResultVec<db::model::AuditLogEntry> {
+        if let (Some(start_time), Some(end_time)) =
+            (selector.start_time, selector.end_time)
+        {
+            if start_time > end_time {
+                return Err(Error::invalid_request(
+                    "start_time must not be later than end_time",
+                ));
+            }
+        }
+        self.db_datastore.audit_log_list(opctx, selector, pagparams).await
+    }
+}
@@ -0,0 +1,93 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Background task for pruning old entries from the audit log
+
+use super::common::BackgroundTask;
+use chrono::Utc;
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use nexus_db_queries::context::OpContext;
+use nexus_db_queries::db::DataStore;
+use serde_json::json;
+use std::num::NonZeroU32;
+use std::sync::Arc;
+
+/// Maximum number of entries deleted by a single database query
+///
+/// Entries are deleted in batches like this to avoid one enormous transaction
+/// when there's a large backlog of entries to prune.
+const PRUNE_BATCH_SIZE: u32 = 1000;
+
+/// Background task that deletes audit log entries older than the configured
+/// retention period
+pub struct AuditLogPruner {
+    datastore: Arc<DataStore>,
+    retention: chrono::Duration,
+}
+
+impl AuditLogPruner {
+    pub fn new(datastore: Arc<DataStore>, retention_days: u32) -> Self {
+        AuditLogPruner {
+            datastore,
+            retention: chrono::Duration::days(i64::from(retention_days)),
+        }
+    }
+}
+
+impl BackgroundTask for AuditLogPruner {
+    fn activate<'a, 'b, 'c>(
+        &'a mut self,
+        opctx: &'b OpContext,
+    ) -> BoxFuture<'c, serde_json::Value>
+    where
+        'a: 'c,
+        'b: 'c,
+    {
+        async {
+            let cutoff = Utc::now() - self.retention;
+            let limit = NonZeroU32::new(PRUNE_BATCH_SIZE).unwrap();
+            let mut ndeleted = 0;
+            loop {
+                match self.datastore.audit_log_prune(opctx, cutoff, limit).await
+                {
+                    Ok(n) => {
+                        ndeleted += n;
+                        if n < usize::try_from(PRUNE_BATCH_SIZE).unwrap() {
+                            break;
+                        }
+                    }
+                    Err(error) => {
+                        warn!(
+                            &opctx.log,
+                            "failed to prune audit log";
+                            "cutoff" => %cutoff,
+                            "error" => format!("{:#}", error)
+                        );
+                        return json!({
+                            "cutoff": cutoff,
+                            "deleted": ndeleted,
+                            "error":
+                                format!(
+                                    "failed to prune audit log: {:#}",
+                                    error
+                                )
+                        });
+                    }
+                }
+            }
+
+            if ndeleted > 0 {
+                info!(
+                    &opctx.log,
+                    "pruned audit log";
+                    "cutoff" => %cutoff,
+                    "deleted" => ndeleted,
+                );
+            }
+            json!({ "cutoff": cutoff, "deleted": ndeleted })
+        }
+        .boxed()
+    }
+}
@@ -4,11 +4,16 @@
 
 //! Background task initialization
 
+use super::audit_log_retention;
 use super::common;
 use super::dns_config;
 use super::dns_propagation;
 use super::dns_servers;
 use super::external_endpoints;
+use super::instance_auto_restart;
+use super::snapshot_schedules;
+use super::vpc_dns;
+use super::webhook_deliveries;
 use nexus_db_model::DnsGroup;
 use nexus_db_queries::context::OpContext;
 use nexus_db_queries::db::DataStore;
@@ -42,14 +47,42 @@ pub struct BackgroundTasks {
     pub external_endpoints: tokio::sync::watch::Receiver<
         Option<external_endpoints::ExternalEndpoints>,
     >,
+
+    /// task handle for the task that prunes old entries from the audit log
+    pub task_audit_log_retention: common::TaskHandle,
+
+    /// task handle for the task that restarts failed instances
+    pub task_instance_auto_restart: common::TaskHandle,
+
+    /// task handle for the task that runs snapshot schedules
+    pub task_snapshot_schedules: common::TaskHandle,
+
+    /// task handle for the task that delivers webhook events
+    pub task_webhook_deliveries: common::TaskHandle,
+
+    /// task handle for the task that sends the DNS zones of VPCs to sled
+    /// agents
+    pub task_vpc_dns: common::TaskHandle,
 }
 
 impl BackgroundTasks {
     /// Kick off all background tasks
+    ///
+    /// Requests to restart failed instances are sent on `instance_restart_tx`,
+    /// requests to run snapshot schedules on `snapshot_schedule_tx`, and
+    /// requests to send the DNS zones of VPCs to sled agents on `vpc_dns_tx`,
+    /// for Nexus to carry out.
     pub fn start(
         opctx: &OpContext,
         datastore: Arc<DataStore>,
         config: &BackgroundTaskConfig,
+        instance_restart_tx: tokio::sync::mpsc::Sender<
+            instance_auto_restart::InstanceRestartRequest,
+        >,
+        snapshot_schedule_tx: tokio::sync::mpsc::Sender<
+            snapshot_schedules::SnapshotScheduleRunRequest,
+        >,
+        vpc_dns_tx: tokio::sync::mpsc::Sender<vpc_dns::VpcDnsRequest>,
     ) -> BackgroundTasks {
         let mut driver = common::Driver::new();
 
@@ -70,8 +103,9 @@ impl BackgroundTasks {
 
         // Background task: External endpoints list watcher
         let (task_external_endpoints, external_endpoints) = {
-            let watcher =
-                external_endpoints::ExternalEndpointsWatcher::new(datastore);
+            let watcher = external_endpoints::ExternalEndpointsWatcher::new(
+                datastore.clone(),
+            );
             let watcher_channel = watcher.watcher();
             let task = driver.register(
                 "external_endpoints".to_string(),
@@ -83,6 +117,83 @@ impl BackgroundTasks {
             (task, watcher_channel)
         };
 
+        // Background task: audit log retention
+        let task_audit_log_retention = {
+            let pruner = audit_log_retention::AuditLogPruner::new(
+                datastore.clone(),
+                config.audit_log.retention_days,
+            );
+            driver.register(
+                "audit_log_retention".to_string(),
+                config.audit_log.period_secs,
+                Box::new(pruner),
+                opctx.child(BTreeMap::new()),
+                vec![],
+            )
+        };
+
+        // Background task: restarting failed instances
+        let task_instance_auto_restart = {
+            let restarter = instance_auto_restart::InstanceAutoRestarter::new(
+                datastore.clone(),
+                instance_restart_tx,
+                config.instance_auto_restart.cooldown_secs,
+                config.instance_auto_restart.max_backoff_secs,
+            );
+            driver.register(
+                "instance_auto_restart".to_string(),
+                config.instance_auto_restart.period_secs,
+                Box::new(restarter),
+                opctx.child(BTreeMap::new()),
+                vec![],
+            )
+        };
+
+        // Background task: running snapshot schedules
+        let task_snapshot_schedules = {
+            let scheduler = snapshot_schedules::SnapshotScheduler::new(
+                datastore.clone(),
+                snapshot_schedule_tx,
+            );
+            driver.register(
+                "snapshot_schedules".to_string(),
+                config.snapshot_schedules.period_secs,
+                Box::new(scheduler),
+                opctx.child(BTreeMap::new()),
+                vec![],
+            )
+        };
+
+        // Background task: sending the DNS zones of VPCs to sled agents
+        let task_vpc_dns = {
+            let propagator =
+                vpc_dns::VpcDnsPropagator::new(datastore.clone(), vpc_dns_tx);
+            driver.register(
+                "vpc_dns".to_string(),
+                config.vpc_dns.period_secs,
+                Box::new(propagator),
+                opctx.child(BTreeMap::new()),
+                vec![],
+            )
+        };
+
+        // Background task: delivering webhook events
+        let task_webhook_deliveries = {
+            let deliverator = webhook_deliveries::WebhookDeliverator::new(
+                datastore,
+                config.webhook_deliveries.retry_backoff_secs,
+                config.webhook_deliveries.max_attempts,
+                config.webhook_deliveries.allow_private_endpoints,
+            );
+            driver.register(
+                "webhook_deliveries".to_string(),
+                config.webhook_deliveries.period_secs,
+                Box::new(deliverator),
+                opctx.child(BTreeMap::new()),
+                vec![],
+            )
+        };
+
         BackgroundTasks {
             driver,
             task_internal_dns_config,
@@ -91,6 +202,11 @@ impl BackgroundTasks {
             task_external_dns_servers,
             task_external_endpoints,
             external_endpoints,
+            task_audit_log_retention,
+            task_instance_auto_restart,
+            task_snapshot_schedules,
+            task_webhook_deliveries,
+            task_vpc_dns,
         }
     }
 
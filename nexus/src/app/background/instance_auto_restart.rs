@@ -0,0 +1,273 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Background task for restarting failed instances
+//!
+//! This task finds instances that have failed (for example, because their sled
+//! or their Propolis server failed) and whose auto-restart policy asks that
+//! they be restarted.  Restarting an instance means placing it on a new sled,
+//! which only Nexus itself can do, so the task asks Nexus to restart each
+//! instance over a channel and waits to hear how it went.
+//!
+//! An instance that fails is restarted right away.  If it fails again soon
+//! after, the task waits for a cooldown period before restarting it again, and
+//! that wait doubles with each further attempt, up to a limit.  An instance
+//! that runs for longer than that limit before failing is treated as though it
+//! had never been restarted.  Each attempt is recorded on the instance, where
+//! it can be seen by operators.
+
+use super::common::BackgroundTask;
+use chrono::Utc;
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use nexus_db_model::Instance;
+use nexus_db_queries::context::OpContext;
+use nexus_db_queries::db::identity::Resource;
+use nexus_db_queries::db::DataStore;
+use omicron_common::api::external::DataPageParams;
+use omicron_common::api::external::Error;
+use serde_json::json;
+use std::num::NonZeroU32;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::sync::oneshot;
+use uuid::Uuid;
+
+/// Maximum number of failed instances fetched by a single database query
+const CANDIDATE_BATCH_SIZE: u32 = 100;
+
+/// A request for Nexus to restart a failed instance
+pub struct InstanceRestartRequest {
+    pub instance_id: Uuid,
+    /// channel on which Nexus reports whether the restart succeeded
+    pub result: oneshot::Sender<Result<(), Error>>,
+}
+
+/// Background task that restarts failed instances, with backoff
+pub struct InstanceAutoRestarter {
+    datastore: Arc<DataStore>,
+    restart_tx: mpsc::Sender<InstanceRestartRequest>,
+    cooldown: Duration,
+    max_backoff: Duration,
+}
+
+impl InstanceAutoRestarter {
+    pub fn new(
+        datastore: Arc<DataStore>,
+        restart_tx: mpsc::Sender<InstanceRestartRequest>,
+        cooldown: Duration,
+        max_backoff: Duration,
+    ) -> Self {
+        InstanceAutoRestarter { datastore, restart_tx, cooldown, max_backoff }
+    }
+
+    /// Returns the number of the next restart attempt for the given failed
+    /// instance, or `None` if it's too soon to try again
+    fn next_attempt(&self, instance: &Instance) -> Option<u32> {
+        let Some(time_last_restarted) = instance.time_last_auto_restarted
+        else {
+            return Some(1);
+        };
+
+        // If the instance ran for a while after it was last restarted, start
+        // counting attempts afresh.
+        let time_failed = instance.runtime().time_updated;
+        let max_backoff = chrono::Duration::from_std(self.max_backoff)
+            .unwrap_or(chrono::Duration::max_value());
+        if time_failed - time_last_restarted > max_backoff {
+            return Some(1);
+        }
+
+        let attempts = *instance.auto_restart_attempts;
+        let delay = chrono::Duration::from_std(restart_delay(
+            attempts,
+            self.cooldown,
+            self.max_backoff,
+        ))
+        .unwrap_or(chrono::Duration::max_value());
+        if Utc::now() - time_last_restarted < delay {
+            None
+        } else {
+            Some(attempts.saturating_add(1))
+        }
+    }
+
+    /// Asks Nexus to restart an instance and waits for the result
+    async fn restart(&self, instance_id: Uuid) -> Result<(), Error> {
+        let (result_tx, result_rx) = oneshot::channel();
+        let request = InstanceRestartRequest { instance_id, result: result_tx };
+        self.restart_tx.send(request).await.map_err(|_| {
+            Error::unavail("Nexus is no longer accepting restart requests")
+        })?;
+        result_rx.await.map_err(|_| {
+            Error::internal_error("Nexus dropped the restart request")
+        })?
+    }
+}
+
+/// Returns how long to wait after the last automatic restart of an instance
+/// that has since failed again before restarting it again, given the number
+/// of restarts already attempted
+fn restart_delay(
+    attempts: u32,
+    cooldown: Duration,
+    max_backoff: Duration,
+) -> Duration {
+    if attempts == 0 {
+        return Duration::ZERO;
+    }
+    let factor = 1u32.checked_shl(attempts - 1).unwrap_or(u32::MAX);
+    cooldown.saturating_mul(factor).min(max_backoff)
+}
+
+impl BackgroundTask for InstanceAutoRestarter {
+    fn activate<'a, 'b, 'c>(
+        &'a mut self,
+        opctx: &'b OpContext,
+    ) -> BoxFuture<'c, serde_json::Value>
+    where
+        'a: 'c,
+        'b: 'c,
+    {
+        async {
+            let mut restarted = Vec::new();
+            let mut failed = Vec::new();
+            let mut nwaiting = 0;
+
+            let mut marker = None;
+            loop {
+                let pagparams = DataPageParams {
+                    marker: marker.as_ref(),
+                    direction: dropshot::PaginationOrder::Ascending,
+                    limit: NonZeroU32::new(CANDIDATE_BATCH_SIZE).unwrap(),
+                };
+                let batch = match self
+                    .datastore
+                    .instance_list_auto_restart_candidates(opctx, &pagparams)
+                    .await
+                {
+                    Ok(batch) => batch,
+                    Err(error) => {
+                        warn!(
+                            &opctx.log,
+                            "failed to list instances to restart";
+                            "error" => format!("{:#}", error)
+                        );
+                        return json!({
+                            "restarted": restarted,
+                            "failed": failed,
+                            "waiting": nwaiting,
+                            "error":
+                                format!(
+                                    "failed to list instances to restart: {:#}",
+                                    error
+                                )
+                        });
+                    }
+                };
+                let batch_size = batch.len();
+                marker = batch.last().map(|instance| instance.id());
+
+                for instance in batch {
+                    let instance_id = instance.id();
+                    let Some(attempt) = self.next_attempt(&instance) else {
+                        nwaiting += 1;
+                        continue;
+                    };
+
+                    // Record the attempt first, so that if several Nexus
+                    // instances find the same failed instance, only one of
+                    // them restarts it.
+                    match self
+                        .datastore
+                        .instance_auto_restart_record_attempt(
+                            opctx, &instance, attempt,
+                        )
+                        .await
+                    {
+                        Ok(true) => (),
+                        Ok(false) => continue,
+                        Err(error) => {
+                            warn!(
+                                &opctx.log,
+                                "failed to record instance restart attempt";
+                                "instance_id" => %instance_id,
+                                "error" => format!("{:#}", error)
+                            );
+                            failed.push(json!({
+                                "instance_id": instance_id,
+                                "error": format!("{:#}", error),
+                            }));
+                            continue;
+                        }
+                    }
+
+                    match self.restart(instance_id).await {
+                        Ok(()) => {
+                            info!(
+                                &opctx.log,
+                                "restarted failed instance";
+                                "instance_id" => %instance_id,
+                                "attempt" => attempt,
+                            );
+                            restarted.push(instance_id);
+                        }
+                        Err(error) => {
+                            warn!(
+                                &opctx.log,
+                                "failed to restart failed instance";
+                                "instance_id" => %instance_id,
+                                "attempt" => attempt,
+                                "error" => format!("{:#}", error)
+                            );
+                            failed.push(json!({
+                                "instance_id": instance_id,
+                                "error": format!("{:#}", error),
+                            }));
+                        }
+                    }
+                }
+
+                if batch_size < usize::try_from(CANDIDATE_BATCH_SIZE).unwrap() {
+                    break;
+                }
+            }
+
+            json!({
+                "restarted": restarted,
+                "failed": failed,
+                "waiting": nwaiting,
+            })
+        }
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::restart_delay;
+    use std::time::Duration;
+
+    #[test]
+    fn test_restart_delay() {
+        let cooldown = Duration::from_secs(60);
+        let max_backoff = Duration::from_secs(600);
+
+        // The first restart happens right away, and later ones back off from
+        // the cooldown period up to the limit.
+        assert_eq!(restart_delay(0, cooldown, max_backoff), Duration::ZERO);
+        assert_eq!(restart_delay(1, cooldown, max_backoff), cooldown);
+        assert_eq!(
+            restart_delay(2, cooldown, max_backoff),
+            Duration::from_secs(120)
+        );
+        assert_eq!(
+            restart_delay(4, cooldown, max_backoff),
+            Duration::from_secs(480)
+        );
+        assert_eq!(restart_delay(5, cooldown, max_backoff), max_backoff);
+        assert_eq!(restart_delay(u32::MAX, cooldown, max_backoff), max_backoff);
+    }
+}
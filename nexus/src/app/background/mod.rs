@@ -4,13 +4,23 @@
 
 //! Background tasks
 
+mod audit_log_retention;
 mod common;
 mod dns_config;
 mod dns_propagation;
 mod dns_servers;
 mod external_endpoints;
 mod init;
+mod instance_auto_restart;
+mod snapshot_schedules;
+mod vpc_dns;
+mod webhook_deliveries;
 
 pub use common::Driver;
 pub use common::TaskHandle;
 pub use init::BackgroundTasks;
+pub use instance_auto_restart::InstanceRestartRequest;
+pub use snapshot_schedules::SnapshotScheduleRunRequest;
+pub use snapshot_schedules::SnapshotScheduleRunResult;
+pub use vpc_dns::VpcDnsRequest;
+pub use vpc_dns::VpcDnsSent;
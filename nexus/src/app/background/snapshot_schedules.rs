@@ -0,0 +1,239 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Background task for running snapshot schedules
+//!
+//! This task finds the snapshot schedules that are due to run, records that
+//! each one has started a run (so that only one Nexus runs it), and asks Nexus
+//! to run it over a channel: taking and deleting snapshots is done with sagas,
+//! which only Nexus itself can run.  When Nexus reports how the run went, the
+//! task records the outcome on the schedule, where it can be seen through the
+//! API.
+
+use super::common::BackgroundTask;
+use chrono::DateTime;
+use chrono::Utc;
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use nexus_db_queries::context::OpContext;
+use nexus_db_queries::db::identity::Resource;
+use nexus_db_queries::db::DataStore;
+use omicron_common::api::external::DataPageParams;
+use serde_json::json;
+use std::num::NonZeroU32;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tokio::sync::oneshot;
+use uuid::Uuid;
+
+/// Maximum number of snapshot schedules fetched by a single database query
+const SCHEDULE_BATCH_SIZE: u32 = 100;
+
+/// A request for Nexus to run a snapshot schedule
+pub struct SnapshotScheduleRunRequest {
+    pub schedule_id: Uuid,
+    /// when the run started, which is used to name the snapshots it takes
+    pub time_started: DateTime<Utc>,
+    /// channel on which Nexus reports how the run went
+    pub result: oneshot::Sender<SnapshotScheduleRunResult>,
+}
+
+/// The outcome of a run of a snapshot schedule
+#[derive(Debug, Default)]
+pub struct SnapshotScheduleRunResult {
+    pub snapshots_created: u32,
+    pub snapshots_deleted: u32,
+    /// everything that went wrong during the run
+    pub errors: Vec<String>,
+}
+
+/// Background task that runs the snapshot schedules that are due
+pub struct SnapshotScheduler {
+    datastore: Arc<DataStore>,
+    run_tx: mpsc::Sender<SnapshotScheduleRunRequest>,
+}
+
+impl SnapshotScheduler {
+    pub fn new(
+        datastore: Arc<DataStore>,
+        run_tx: mpsc::Sender<SnapshotScheduleRunRequest>,
+    ) -> Self {
+        SnapshotScheduler { datastore, run_tx }
+    }
+
+    /// Asks Nexus to run a schedule and waits for the result
+    async fn run(
+        &self,
+        schedule_id: Uuid,
+        time_started: DateTime<Utc>,
+    ) -> SnapshotScheduleRunResult {
+        let (result_tx, result_rx) = oneshot::channel();
+        let request = SnapshotScheduleRunRequest {
+            schedule_id,
+            time_started,
+            result: result_tx,
+        };
+        if self.run_tx.send(request).await.is_err() {
+            return SnapshotScheduleRunResult {
+                errors: vec![String::from(
+                    "Nexus is no longer accepting snapshot schedule runs",
+                )],
+                ..Default::default()
+            };
+        }
+        result_rx.await.unwrap_or_else(|_| SnapshotScheduleRunResult {
+            errors: vec![String::from(
+                "Nexus dropped the snapshot schedule run",
+            )],
+            ..Default::default()
+        })
+    }
+}
+
+impl BackgroundTask for SnapshotScheduler {
+    fn activate<'a, 'b, 'c>(
+        &'a mut self,
+        opctx: &'b OpContext,
+    ) -> BoxFuture<'c, serde_json::Value>
+    where
+        'a: 'c,
+        'b: 'c,
+    {
+        async {
+            let mut succeeded = Vec::new();
+            let mut failed = Vec::new();
+
+            let mut marker = None;
+            loop {
+                let pagparams = DataPageParams {
+                    marker: marker.as_ref(),
+                    direction: dropshot::PaginationOrder::Ascending,
+                    limit: NonZeroU32::new(SCHEDULE_BATCH_SIZE).unwrap(),
+                };
+                let batch = match self
+                    .datastore
+                    .snapshot_schedule_list_all(opctx, &pagparams)
+                    .await
+                {
+                    Ok(batch) => batch,
+                    Err(error) => {
+                        warn!(
+                            &opctx.log,
+                            "failed to list snapshot schedules";
+                            "error" => format!("{:#}", error)
+                        );
+                        return json!({
+                            "succeeded": succeeded,
+                            "failed": failed,
+                            "error":
+                                format!(
+                                    "failed to list snapshot schedules: {:#}",
+                                    error
+                                )
+                        });
+                    }
+                };
+                let batch_size = batch.len();
+                marker = batch.last().map(|schedule| schedule.id());
+
+                for schedule in batch {
+                    let schedule_id = schedule.id();
+                    let time_started = Utc::now();
+                    if !schedule.is_due(time_started) {
+                        continue;
+                    }
+
+                    match self
+                        .datastore
+                        .snapshot_schedule_run_start(
+                            opctx,
+                            &schedule,
+                            time_started,
+                        )
+                        .await
+                    {
+                        Ok(true) => (),
+                        Ok(false) => continue,
+                        Err(error) => {
+                            warn!(
+                                &opctx.log,
+                                "failed to start snapshot schedule run";
+                                "schedule_id" => %schedule_id,
+                                "error" => format!("{:#}", error)
+                            );
+                            failed.push(json!({
+                                "schedule_id": schedule_id,
+                                "error": format!("{:#}", error),
+                            }));
+                            continue;
+                        }
+                    }
+
+                    let result = self.run(schedule_id, time_started).await;
+                    let error = if result.errors.is_empty() {
+                        None
+                    } else {
+                        Some(result.errors.join("; "))
+                    };
+                    if let Err(error) = self
+                        .datastore
+                        .snapshot_schedule_run_finish(
+                            opctx,
+                            schedule_id,
+                            time_started,
+                            result.snapshots_created,
+                            result.snapshots_deleted,
+                            error.clone(),
+                        )
+                        .await
+                    {
+                        warn!(
+                            &opctx.log,
+                            "failed to record snapshot schedule run";
+                            "schedule_id" => %schedule_id,
+                            "error" => format!("{:#}", error)
+                        );
+                    }
+
+                    match error {
+                        None => {
+                            info!(
+                                &opctx.log,
+                                "ran snapshot schedule";
+                                "schedule_id" => %schedule_id,
+                                "snapshots_created" => result.snapshots_created,
+                                "snapshots_deleted" => result.snapshots_deleted,
+                            );
+                            succeeded.push(schedule_id);
+                        }
+                        Some(error) => {
+                            warn!(
+                                &opctx.log,
+                                "snapshot schedule run failed";
+                                "schedule_id" => %schedule_id,
+                                "snapshots_created" => result.snapshots_created,
+                                "snapshots_deleted" => result.snapshots_deleted,
+                                "error" => &error,
+                            );
+                            failed.push(json!({
+                                "schedule_id": schedule_id,
+                                "error": error,
+                            }));
+                        }
+                    }
+                }
+
+                if batch_size < usize::try_from(SCHEDULE_BATCH_SIZE).unwrap() {
+                    break;
+                }
+            }
+
+            json!({
+                "succeeded": succeeded,
+                "failed": failed,
+            })
+        }
+        .boxed()
+    }
+}
@@ -0,0 +1,164 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Background task for keeping the DNS zones of VPCs up to date on sled agents
+//!
+//! Each VPC with instances has a DNS zone holding a name for each instance
+//! whose primary network interface is in the VPC, which the sled agents serve
+//! to the VPC's instances.  Rather than sending these zones to the sleds as
+//! part of the requests that create and delete instances, change their network
+//! interfaces, or rename VPCs and projects (which could leave the request
+//! failed after the change itself had been made), this task asks Nexus to send
+//! each VPC's zone to the sleds running its instances.  Nexus activates it
+//! after each such change, and it also runs periodically to pick up any that
+//! were missed.
+
+use super::common::BackgroundTask;
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use nexus_db_queries::context::OpContext;
+use nexus_db_queries::db::DataStore;
+use omicron_common::api::external::DataPageParams;
+use omicron_common::api::external::Error;
+use serde_json::json;
+use std::num::NonZeroU32;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tokio::sync::oneshot;
+use uuid::Uuid;
+
+/// Maximum number of VPCs fetched by a single database query
+const VPC_BATCH_SIZE: u32 = 100;
+
+/// A request for Nexus to send the DNS zone of a VPC to the sleds running its
+/// instances
+pub struct VpcDnsRequest {
+    pub vpc_id: Uuid,
+    /// channel on which Nexus reports what it sent
+    pub result: oneshot::Sender<Result<VpcDnsSent, Error>>,
+}
+
+/// Describes the DNS zone of a VPC that Nexus sent to sled agents
+pub struct VpcDnsSent {
+    /// number of sleds the zone was sent to
+    pub nsleds: usize,
+    /// fully-qualified names of instances that were left out of the zone
+    /// because they're too long to be DNS names
+    pub names_too_long: Vec<String>,
+}
+
+/// Background task that keeps the DNS zones of VPCs up to date on the sleds
+/// running their instances
+pub struct VpcDnsPropagator {
+    datastore: Arc<DataStore>,
+    dns_tx: mpsc::Sender<VpcDnsRequest>,
+}
+
+impl VpcDnsPropagator {
+    pub fn new(
+        datastore: Arc<DataStore>,
+        dns_tx: mpsc::Sender<VpcDnsRequest>,
+    ) -> Self {
+        VpcDnsPropagator { datastore, dns_tx }
+    }
+
+    /// Asks Nexus to send the DNS zone of a VPC and waits for the result
+    async fn send(&self, vpc_id: Uuid) -> Result<VpcDnsSent, Error> {
+        let (result_tx, result_rx) = oneshot::channel();
+        let request = VpcDnsRequest { vpc_id, result: result_tx };
+        self.dns_tx.send(request).await.map_err(|_| {
+            Error::unavail("Nexus is no longer accepting VPC DNS requests")
+        })?;
+        result_rx.await.map_err(|_| {
+            Error::internal_error("Nexus dropped the VPC DNS request")
+        })?
+    }
+}
+
+impl BackgroundTask for VpcDnsPropagator {
+    fn activate<'a, 'b, 'c>(
+        &'a mut self,
+        opctx: &'b OpContext,
+    ) -> BoxFuture<'c, serde_json::Value>
+    where
+        'a: 'c,
+        'b: 'c,
+    {
+        async {
+            let mut nvpcs = 0;
+            let mut nsleds = 0;
+            let mut names_too_long = Vec::new();
+            let mut errors = Vec::new();
+
+            let mut marker = None;
+            loop {
+                let pagparams = DataPageParams {
+                    marker: marker.as_ref(),
+                    direction: dropshot::PaginationOrder::Ascending,
+                    limit: NonZeroU32::new(VPC_BATCH_SIZE).unwrap(),
+                };
+                let batch = match self
+                    .datastore
+                    .vpc_dns_instance_vpcs_list(opctx, &pagparams)
+                    .await
+                {
+                    Ok(batch) => batch,
+                    Err(error) => {
+                        warn!(
+                            &opctx.log,
+                            "failed to list VPCs for DNS";
+                            "error" => format!("{:#}", error)
+                        );
+                        errors.push(format!(
+                            "failed to list VPCs for DNS: {:#}",
+                            error
+                        ));
+                        break;
+                    }
+                };
+                let done =
+                    batch.len() < usize::try_from(VPC_BATCH_SIZE).unwrap();
+                marker = batch.last().copied();
+
+                for vpc_id in batch {
+                    nvpcs += 1;
+                    match self.send(vpc_id).await {
+                        Ok(sent) => {
+                            nsleds += sent.nsleds;
+                            names_too_long.extend(sent.names_too_long);
+                        }
+                        Err(error) => {
+                            warn!(
+                                &opctx.log,
+                                "failed to send DNS zone of VPC to sleds";
+                                "vpc_id" => %vpc_id,
+                                "error" => format!("{:#}", error)
+                            );
+                            errors.push(format!("VPC {}: {:#}", vpc_id, error));
+                        }
+                    }
+                }
+
+                if done {
+                    break;
+                }
+            }
+
+            if !names_too_long.is_empty() {
+                warn!(
+                    &opctx.log,
+                    "left instance names that are too long out of VPC DNS";
+                    "names" => ?names_too_long,
+                );
+            }
+            json!({
+                "vpcs_sent": nvpcs,
+                "sled_updates": nsleds,
+                "names_too_long": names_too_long,
+                "errors": errors,
+            })
+        }
+        .boxed()
+    }
+}
@@ -0,0 +1,435 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Background task for delivering webhook events
+//!
+//! This task finds the deliveries of events to webhook receivers that are due
+//! to be attempted, records that each one is being attempted (so that only one
+//! Nexus attempts it, and so that it's retried later if this attempt never
+//! finishes), and POSTs the event to the receiver's endpoint.  Each request
+//! is signed with the receiver's secret.  A delivery the receiver doesn't
+//! accept is retried with backoff until it has been attempted the configured
+//! number of times, after which it's marked as failed.
+//!
+//! Receivers' endpoints are provided by users, so before each delivery the
+//! endpoint's host is resolved and the request is only made (directly, without
+//! following redirects) to the resulting addresses if they're all global ones.
+//! Up to [`MAX_CONCURRENT_DELIVERIES`] deliveries are made at once, so that a
+//! few slow receivers don't hold up deliveries to the others.
+
+use super::common::BackgroundTask;
+use crate::app::webhook::webhook_endpoint_resolve;
+use chrono::DateTime;
+use chrono::Utc;
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use futures::StreamExt;
+use nexus_db_model::WebhookDelivery;
+use nexus_db_model::WebhookDeliveryState;
+use nexus_db_model::WebhookEvent;
+use nexus_db_model::WebhookReceiver;
+use nexus_db_queries::context::OpContext;
+use nexus_db_queries::db::identity::Resource;
+use nexus_db_queries::db::DataStore;
+use nexus_types::external_api::shared;
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Maximum number of deliveries fetched by a single database query
+const DELIVERY_BATCH_SIZE: u32 = 100;
+
+/// How long to wait for a receiver to respond to a delivery
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Maximum number of deliveries in flight at once
+const MAX_CONCURRENT_DELIVERIES: usize = 16;
+
+/// Header carrying the signature of the body of a delivery
+const SIGNATURE_HEADER: &str = "x-oxide-webhook-signature";
+/// Header carrying the id of a delivery, which is the same for every attempt
+const DELIVERY_ID_HEADER: &str = "x-oxide-webhook-delivery-id";
+/// Header carrying the class of the event being delivered
+const EVENT_CLASS_HEADER: &str = "x-oxide-webhook-event-class";
+
+/// Background task that delivers webhook events
+pub struct WebhookDeliverator {
+    datastore: Arc<DataStore>,
+    retry_backoff: Duration,
+    max_attempts: u32,
+    allow_private_endpoints: bool,
+}
+
+/// What became of one due delivery
+enum DeliveryOutcome {
+    /// Another Nexus attempted the delivery first
+    NotStarted,
+    /// The attempt couldn't be recorded, so it wasn't made
+    StartFailed(serde_json::Value),
+    Delivered(Uuid),
+    Failed(serde_json::Value),
+    Retrying(serde_json::Value),
+}
+
+impl WebhookDeliverator {
+    pub fn new(
+        datastore: Arc<DataStore>,
+        retry_backoff: Duration,
+        max_attempts: u32,
+        allow_private_endpoints: bool,
+    ) -> Self {
+        WebhookDeliverator {
+            datastore,
+            retry_backoff,
+            max_attempts,
+            allow_private_endpoints,
+        }
+    }
+
+    /// Makes one attempt to deliver an event to a receiver, returning the
+    /// status of the receiver's response, if any, and whether the receiver
+    /// accepted the delivery
+    ///
+    /// The error describes why the delivery failed without passing on
+    /// anything else that the attempt might have revealed about the network
+    /// (the full error is logged instead), since it's shown to the receiver's
+    /// owner.
+    async fn deliver(
+        &self,
+        log: &slog::Logger,
+        delivery: &WebhookDelivery,
+        event: &WebhookEvent,
+        receiver: &WebhookReceiver,
+    ) -> (Option<u16>, Result<(), String>) {
+        let endpoint = match reqwest::Url::parse(&receiver.endpoint) {
+            Ok(endpoint) => endpoint,
+            Err(_) => {
+                return (None, Err(String::from("endpoint is not a valid URL")))
+            }
+        };
+        let addrs = match webhook_endpoint_resolve(
+            &endpoint,
+            self.allow_private_endpoints,
+        )
+        .await
+        {
+            Ok(addrs) => addrs,
+            Err(error) => return (None, Err(error.to_string())),
+        };
+
+        // Connect only to the addresses that were just checked, rather than
+        // letting the client resolve the host again (and perhaps get a
+        // different answer).
+        let mut builder = reqwest::ClientBuilder::new()
+            .connect_timeout(DELIVERY_TIMEOUT)
+            .timeout(DELIVERY_TIMEOUT)
+            .redirect(reqwest::redirect::Policy::none())
+            .no_proxy();
+        if let Some(domain) = endpoint.domain() {
+            builder = builder.resolve_to_addrs(domain, &addrs);
+        }
+        let client = match builder.build() {
+            Ok(client) => client,
+            Err(error) => {
+                warn!(
+                    log,
+                    "failed to build webhook delivery client";
+                    "error" => format!("{:#}", error),
+                );
+                return (None, Err(String::from("internal error")));
+            }
+        };
+
+        let event_class = shared::WebhookEventClass::from(event.event_class);
+        let body = json!({
+            "event_id": event.id,
+            "event_class": event_class,
+            "time_created": event.time_created,
+            "silo_id": event.silo_id,
+            "data": event.data,
+        })
+        .to_string();
+        let event_class = serde_json::to_value(event_class)
+            .ok()
+            .and_then(|v| v.as_str().map(String::from))
+            .unwrap_or_default();
+
+        let response = client
+            .post(endpoint)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(SIGNATURE_HEADER, signature(&receiver.secret, &body))
+            .header(DELIVERY_ID_HEADER, delivery.id.to_string())
+            .header(EVENT_CLASS_HEADER, event_class)
+            .body(body)
+            .send()
+            .await;
+        match response {
+            Ok(response) => {
+                let status = response.status();
+                if status.is_success() {
+                    (Some(status.as_u16()), Ok(()))
+                } else {
+                    (
+                        Some(status.as_u16()),
+                        Err(format!("receiver responded with {}", status)),
+                    )
+                }
+            }
+            Err(error) => {
+                debug!(
+                    log,
+                    "webhook delivery request failed";
+                    "delivery_id" => %delivery.id,
+                    "error" => format!("{:#}", error),
+                );
+                let error = if error.is_timeout() {
+                    "timed out waiting for receiver"
+                } else if error.is_connect() {
+                    "failed to connect to receiver"
+                } else {
+                    "request to receiver failed"
+                };
+                (None, Err(String::from(error)))
+            }
+        }
+    }
+
+    /// Records that a delivery is being attempted, attempts it, and records
+    /// the result
+    async fn attempt(
+        &self,
+        opctx: &OpContext,
+        delivery: WebhookDelivery,
+        event: WebhookEvent,
+        receiver: WebhookReceiver,
+    ) -> DeliveryOutcome {
+        let delivery_id = delivery.id;
+        let attempt = *delivery.attempts + 1;
+        let time_started = Utc::now();
+        let time_next_attempt = chrono::Duration::from_std(retry_delay(
+            attempt,
+            self.retry_backoff,
+        ))
+        .ok()
+        .and_then(|delay| time_started.checked_add_signed(delay))
+        .unwrap_or(DateTime::<Utc>::MAX_UTC);
+
+        match self
+            .datastore
+            .webhook_delivery_attempt_start(
+                opctx,
+                &delivery,
+                time_started,
+                time_next_attempt,
+            )
+            .await
+        {
+            Ok(true) => (),
+            Ok(false) => return DeliveryOutcome::NotStarted,
+            Err(error) => {
+                warn!(
+                    &opctx.log,
+                    "failed to start webhook delivery attempt";
+                    "delivery_id" => %delivery_id,
+                    "error" => format!("{:#}", error)
+                );
+                return DeliveryOutcome::StartFailed(json!({
+                    "delivery_id": delivery_id,
+                    "error": format!("{:#}", error),
+                }));
+            }
+        }
+
+        let (response_status, result) =
+            self.deliver(&opctx.log, &delivery, &event, &receiver).await;
+        let (state, error) = match result {
+            Ok(()) => (WebhookDeliveryState::Delivered, None),
+            Err(error) if attempt >= self.max_attempts => {
+                (WebhookDeliveryState::Failed, Some(error))
+            }
+            Err(error) => (WebhookDeliveryState::Pending, Some(error)),
+        };
+        if let Err(error) = self
+            .datastore
+            .webhook_delivery_attempt_finish(
+                opctx,
+                delivery_id,
+                attempt,
+                state,
+                response_status,
+                error.clone(),
+            )
+            .await
+        {
+            warn!(
+                &opctx.log,
+                "failed to record webhook delivery attempt";
+                "delivery_id" => %delivery_id,
+                "error" => format!("{:#}", error)
+            );
+        }
+
+        match (state, error) {
+            (WebhookDeliveryState::Delivered, _) => {
+                debug!(
+                    &opctx.log,
+                    "delivered webhook event";
+                    "delivery_id" => %delivery_id,
+                    "receiver_id" => %receiver.id(),
+                    "attempt" => attempt,
+                );
+                DeliveryOutcome::Delivered(delivery_id)
+            }
+            (state, error) => {
+                let error = error.unwrap_or_default();
+                warn!(
+                    &opctx.log,
+                    "failed to deliver webhook event";
+                    "delivery_id" => %delivery_id,
+                    "receiver_id" => %receiver.id(),
+                    "attempt" => attempt,
+                    "error" => &error,
+                );
+                let entry = json!({
+                    "delivery_id": delivery_id,
+                    "error": error,
+                });
+                if state == WebhookDeliveryState::Failed {
+                    DeliveryOutcome::Failed(entry)
+                } else {
+                    DeliveryOutcome::Retrying(entry)
+                }
+            }
+        }
+    }
+}
+
+/// Returns the hex-encoded HMAC-SHA256 of `body`, keyed with `secret`
+fn signature(secret: &str, body: &str) -> String {
+    let key = ring::hmac::Key::new(ring::hmac::HMAC_SHA256, secret.as_bytes());
+    hex::encode(ring::hmac::sign(&key, body.as_bytes()).as_ref())
+}
+
+/// Returns how long to wait after attempt number `attempt` to make a delivery
+/// (counting from 1) before trying again, should it fail
+fn retry_delay(attempt: u32, backoff: Duration) -> Duration {
+    let factor =
+        1u32.checked_shl(attempt.saturating_sub(1)).unwrap_or(u32::MAX);
+    backoff.saturating_mul(factor)
+}
+
+impl BackgroundTask for WebhookDeliverator {
+    fn activate<'a, 'b, 'c>(
+        &'a mut self,
+        opctx: &'b OpContext,
+    ) -> BoxFuture<'c, serde_json::Value>
+    where
+        'a: 'c,
+        'b: 'c,
+    {
+        async {
+            let mut delivered = Vec::new();
+            let mut failed = Vec::new();
+            let mut retrying = Vec::new();
+
+            // Deliveries that are attempted are no longer due at `now`, so each
+            // batch is made up of ones that haven't been tried yet, save for
+            // those whose attempts couldn't be recorded.
+            let now = Utc::now();
+            loop {
+                let batch = match self
+                    .datastore
+                    .webhook_delivery_list_due(opctx, now, DELIVERY_BATCH_SIZE)
+                    .await
+                {
+                    Ok(batch) => batch,
+                    Err(error) => {
+                        warn!(
+                            &opctx.log,
+                            "failed to list due webhook deliveries";
+                            "error" => format!("{:#}", error)
+                        );
+                        return json!({
+                            "delivered": delivered,
+                            "failed": failed,
+                            "retrying": retrying,
+                            "error":
+                                format!(
+                                    "failed to list due webhook deliveries: \
+                                     {:#}",
+                                    error
+                                )
+                        });
+                    }
+                };
+                let batch_size = batch.len();
+                let mut nstarted = 0;
+
+                let this = &*self;
+                let mut outcomes = futures::stream::iter(batch)
+                    .map(|(delivery, event, receiver)| {
+                        this.attempt(opctx, delivery, event, receiver)
+                    })
+                    .buffer_unordered(MAX_CONCURRENT_DELIVERIES);
+                while let Some(outcome) = outcomes.next().await {
+                    match outcome {
+                        DeliveryOutcome::NotStarted => continue,
+                        DeliveryOutcome::StartFailed(entry) => {
+                            retrying.push(entry);
+                            continue;
+                        }
+                        DeliveryOutcome::Delivered(delivery_id) => {
+                            delivered.push(delivery_id)
+                        }
+                        DeliveryOutcome::Failed(entry) => failed.push(entry),
+                        DeliveryOutcome::Retrying(entry) => {
+                            retrying.push(entry)
+                        }
+                    }
+                    nstarted += 1;
+                }
+
+                if nstarted == 0
+                    || batch_size
+                        < usize::try_from(DELIVERY_BATCH_SIZE).unwrap()
+                {
+                    break;
+                }
+            }
+
+            json!({
+                "delivered": delivered,
+                "failed": failed,
+                "retrying": retrying,
+            })
+        }
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::retry_delay;
+    use super::signature;
+    use std::time::Duration;
+
+    #[test]
+    fn test_retry_delay() {
+        let backoff = Duration::from_secs(30);
+        assert_eq!(retry_delay(1, backoff), backoff);
+        assert_eq!(retry_delay(2, backoff), Duration::from_secs(60));
+        assert_eq!(retry_delay(4, backoff), Duration::from_secs(240));
+        assert_eq!(retry_delay(u32::MAX, backoff), backoff * u32::MAX);
+    }
+
+    #[test]
+    fn test_signature() {
+        // RFC 4231, test case 2
+        assert_eq!(
+            signature("Jefe", "what do ya want for nothing?"),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+}
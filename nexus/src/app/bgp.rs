@@ -0,0 +1,87 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use crate::authz;
+use crate::db::datastore::BgpAnnounceSetCreateResult;
+use crate::db::model::{BgpAnnounceSet, BgpAnnouncement, BgpConfig};
+use crate::external_api::params;
+use nexus_db_queries::context::OpContext;
+use omicron_common::api::external::http_pagination::PaginatedBy;
+use omicron_common::api::external::{
+    CreateResult, DeleteResult, ListResultVec, LookupResult, NameOrId,
+};
+
+impl super::Nexus {
+    pub async fn bgp_config_create(
+        &self,
+        opctx: &OpContext,
+        config: &params::CreateBgpConfig,
+    ) -> CreateResult<BgpConfig> {
+        opctx.authorize(authz::Action::Modify, &authz::FLEET).await?;
+        self.db_datastore.bgp_config_create(opctx, config).await
+    }
+
+    pub async fn bgp_config_get(
+        &self,
+        opctx: &OpContext,
+        name_or_id: &NameOrId,
+    ) -> LookupResult<BgpConfig> {
+        opctx.authorize(authz::Action::Read, &authz::FLEET).await?;
+        self.db_datastore.bgp_config_get(opctx, name_or_id).await
+    }
+
+    pub async fn bgp_config_list(
+        &self,
+        opctx: &OpContext,
+        pagparams: &PaginatedBy<'_>,
+    ) -> ListResultVec<BgpConfig> {
+        opctx.authorize(authz::Action::ListChildren, &authz::FLEET).await?;
+        self.db_datastore.bgp_config_list(opctx, pagparams).await
+    }
+
+    pub async fn bgp_config_delete(
+        &self,
+        opctx: &OpContext,
+        sel: &params::BgpConfigSelector,
+    ) -> DeleteResult {
+        opctx.authorize(authz::Action::Modify, &authz::FLEET).await?;
+        self.db_datastore.bgp_config_delete(opctx, sel).await
+    }
+
+    pub async fn bgp_announce_set_create(
+        &self,
+        opctx: &OpContext,
+        announce: &params::CreateBgpAnnounceSet,
+    ) -> CreateResult<BgpAnnounceSetCreateResult> {
+        opctx.authorize(authz::Action::Modify, &authz::FLEET).await?;
+        self.db_datastore.bgp_announce_set_create(opctx, announce).await
+    }
+
+    pub async fn bgp_announce_set_list(
+        &self,
+        opctx: &OpContext,
+        pagparams: &PaginatedBy<'_>,
+    ) -> ListResultVec<BgpAnnounceSet> {
+        opctx.authorize(authz::Action::ListChildren, &authz::FLEET).await?;
+        self.db_datastore.bgp_announce_set_list(opctx, pagparams).await
+    }
+
+    pub async fn bgp_announcement_list(
+        &self,
+        opctx: &OpContext,
+        sel: &params::BgpAnnounceSetSelector,
+    ) -> ListResultVec<BgpAnnouncement> {
+        opctx.authorize(authz::Action::Read, &authz::FLEET).await?;
+        self.db_datastore.bgp_announcement_list(opctx, sel).await
+    }
+
+    pub async fn bgp_announce_set_delete(
+        &self,
+        opctx: &OpContext,
+        sel: &params::BgpAnnounceSetSelector,
+    ) -> DeleteResult {
+        opctx.authorize(authz::Action::Modify, &authz::FLEET).await?;
+        self.db_datastore.bgp_announce_set_delete(opctx, sel).await
+    }
+}
@@ -49,10 +49,14 @@ use crate::authz;
 use crate::db::lookup::LookupPath;
 use crate::db::model::{DeviceAccessToken, DeviceAuthRequest};
 use crate::external_api::device_auth::DeviceAccessTokenResponse;
+use crate::external_api::params;
 use nexus_db_queries::context::OpContext;
 
-use omicron_common::api::external::{CreateResult, Error};
+use omicron_common::api::external::{
+    CreateResult, DataPageParams, DeleteResult, Error, ListResultVec,
+};
 
+use anyhow::anyhow;
 use chrono::Utc;
 use uuid::Uuid;
 
@@ -156,16 +160,18 @@ impl super::Nexus {
         opctx: &OpContext,
         token: String,
     ) -> Result<Actor, Reason> {
-        let (.., db_access_token) = LookupPath::new(opctx, &self.db_datastore)
-            .device_access_token(&token)
-            .fetch()
-            .await
-            .map_err(|e| match e {
-                Error::ObjectNotFound { .. } => Reason::UnknownActor {
-                    actor: "from device access token".to_string(),
-                },
-                e => Reason::UnknownError { source: e },
-            })?;
+        // Revoked tokens are deleted, so they show up here as unknown.
+        let (.., authz_access_token, db_access_token) =
+            LookupPath::new(opctx, &self.db_datastore)
+                .device_access_token(&token)
+                .fetch()
+                .await
+                .map_err(|e| match e {
+                    Error::ObjectNotFound { .. } => Reason::UnknownActor {
+                        actor: "from device access token".to_string(),
+                    },
+                    e => Reason::UnknownError { source: e },
+                })?;
 
         let silo_user_id = db_access_token.silo_user_id;
         let (.., db_silo_user) = LookupPath::new(opctx, &self.db_datastore)
@@ -179,7 +185,82 @@ impl super::Nexus {
                 e => Reason::UnknownError { source: e },
             })?;
         let silo_id = db_silo_user.silo_id;
+        let actor = Actor::SiloUser { silo_user_id, silo_id };
+
+        if db_access_token.is_expired(Utc::now()) {
+            return Err(Reason::BadCredentials {
+                actor,
+                source: anyhow!("device access token has expired"),
+            });
+        }
+
+        self.db_datastore
+            .device_access_token_update_last_used(opctx, &authz_access_token)
+            .await
+            .map_err(|e| Reason::UnknownError { source: e })?;
+
+        Ok(actor)
+    }
+
+    /// Create an access token for the given user without going through the
+    /// device authorization flow.
+    pub async fn device_access_token_create_for_user(
+        &self,
+        opctx: &OpContext,
+        silo_user_id: Uuid,
+        params: params::DeviceAccessTokenCreate,
+    ) -> CreateResult<DeviceAccessToken> {
+        let (.., authz_user) = LookupPath::new(opctx, &self.datastore())
+            .silo_user_id(silo_user_id)
+            .lookup_for(authz::Action::CreateChild)
+            .await?;
+        assert_eq!(authz_user.id(), silo_user_id);
 
-        Ok(Actor::SiloUser { silo_user_id, silo_id })
+        let mut token =
+            DeviceAccessToken::new_named(silo_user_id, params.name.into());
+        if let Some(time_expires) = params.time_expires {
+            if time_expires <= token.time_created {
+                return Err(Error::invalid_request(
+                    "token expiration time must be in the future",
+                ));
+            }
+            token = token.expires(time_expires);
+        }
+
+        self.db_datastore
+            .device_access_token_create_for_user(opctx, &authz_user, token)
+            .await
+    }
+
+    pub async fn device_access_tokens_list(
+        &self,
+        opctx: &OpContext,
+        silo_user_id: Uuid,
+        pagparams: &DataPageParams<'_, Uuid>,
+    ) -> ListResultVec<DeviceAccessToken> {
+        let (.., authz_user) = LookupPath::new(opctx, &self.datastore())
+            .silo_user_id(silo_user_id)
+            .lookup_for(authz::Action::ListChildren)
+            .await?;
+        assert_eq!(authz_user.id(), silo_user_id);
+        self.db_datastore
+            .device_access_tokens_list(opctx, &authz_user, pagparams)
+            .await
+    }
+
+    pub async fn device_access_token_delete(
+        &self,
+        opctx: &OpContext,
+        silo_user_id: Uuid,
+        token_id: Uuid,
+    ) -> DeleteResult {
+        let (.., authz_user) = LookupPath::new(opctx, &self.datastore())
+            .silo_user_id(silo_user_id)
+            .lookup_for(authz::Action::Modify)
+            .await?;
+        assert_eq!(authz_user.id(), silo_user_id);
+        self.db_datastore
+            .device_access_token_delete(opctx, &authz_user, token_id)
+            .await
     }
 }
@@ -11,6 +11,7 @@ use crate::db;
 use crate::db::lookup;
 use crate::db::lookup::LookupPath;
 use crate::external_api::params;
+use crate::external_api::views;
 use nexus_db_queries::context::OpContext;
 use omicron_common::api::external::http_pagination::PaginatedBy;
 use omicron_common::api::external::ByteCount;
@@ -19,15 +20,19 @@ use omicron_common::api::external::DeleteResult;
 use omicron_common::api::external::DiskState;
 use omicron_common::api::external::Error;
 use omicron_common::api::external::InternalContext;
+use omicron_common::api::external::LabelSelector;
+use omicron_common::api::external::Labels;
 use omicron_common::api::external::ListResultVec;
 use omicron_common::api::external::LookupResult;
 use omicron_common::api::external::NameOrId;
 use omicron_common::api::external::UpdateResult;
 use omicron_common::api::internal::nexus::DiskRuntimeState;
 use sled_agent_client::Client as SledAgentClient;
+use std::net::SocketAddrV6;
 use std::sync::Arc;
 use uuid::Uuid;
 
+use super::MAX_BULK_READ_BYTES;
 use super::MAX_DISK_SIZE_BYTES;
 use super::MIN_DISK_SIZE_BYTES;
 
@@ -68,6 +73,7 @@ impl super::Nexus {
     pub(super) async fn validate_disk_create_params(
         self: &Arc<Self>,
         opctx: &OpContext,
+        authz_silo: &authz::Silo,
         authz_project: &authz::Project,
         params: &params::DiskCreate,
     ) -> Result<(), Error> {
@@ -106,13 +112,13 @@ impl super::Nexus {
                 db_snapshot.block_size.to_bytes().into()
             }
             params::DiskSource::Image { image_id } => {
-                let (.., db_image) = LookupPath::new(opctx, &self.db_datastore)
-                    .image_id(image_id)
-                    .fetch()
+                let db_image = self
+                    .image_fetch_for_silo(opctx, authz_silo, image_id)
                     .await?;
 
-                // The image either needs to belong to our project or be
-                // promoted to our silo. If not, return an error.
+                // The image either needs to belong to our project, or be
+                // promoted to our silo or shared with it by another silo. If
+                // not, return an error.
                 if let Some(project) = db_image.project_id {
                     if project != authz_project.id() {
                         return Err(Error::invalid_request(
@@ -135,6 +141,44 @@ impl super::Nexus {
 
                 db_image.block_size.to_bytes().into()
             }
+            params::DiskSource::Disk { disk_id } => {
+                let (.., db_disk) = LookupPath::new(opctx, &self.db_datastore)
+                    .disk_id(disk_id)
+                    .fetch()
+                    .await?;
+
+                // Return an error if the source disk does not belong to our
+                // project.
+                if db_disk.project_id != authz_project.id() {
+                    return Err(Error::invalid_request(
+                        "source disk does not belong to this project",
+                    ));
+                }
+
+                // The copy is taken with the Pantry, which requires that
+                // nothing else has the source disk attached.
+                let disk_state: DiskState = db_disk.state().into();
+                if disk_state != DiskState::Detached {
+                    return Err(Error::invalid_request(&format!(
+                        "source disk must be detached to be copied, but it is {}",
+                        disk_state.label(),
+                    )));
+                }
+
+                // If the size of the source disk is greater than the size of
+                // the disk, return an error.
+                if db_disk.size.to_bytes() > params.size.to_bytes() {
+                    return Err(Error::invalid_request(
+                        &format!(
+                            "disk size {} must be greater than or equal to source disk size {}",
+                            params.size.to_bytes(),
+                            db_disk.size.to_bytes(),
+                        ),
+                    ));
+                }
+
+                db_disk.block_size.to_bytes().into()
+            }
         };
 
         // Reject disks where the block size doesn't evenly divide the
@@ -193,12 +237,19 @@ impl super::Nexus {
         project_lookup: &lookup::Project<'_>,
         params: &params::DiskCreate,
     ) -> CreateResult<db::model::Disk> {
-        let (.., authz_project) =
+        let (authz_silo, authz_project) =
             project_lookup.lookup_for(authz::Action::CreateChild).await?;
-        self.validate_disk_create_params(opctx, &authz_project, params).await?;
+        self.validate_disk_create_params(
+            opctx,
+            &authz_silo,
+            &authz_project,
+            params,
+        )
+        .await?;
 
         let saga_params = sagas::disk_create::Params {
             serialized_authn: authn::saga::Serialized::for_opctx(opctx),
+            silo_id: authz_silo.id(),
             project_id: authz_project.id(),
             create_params: params.clone(),
         };
@@ -217,10 +268,27 @@ impl super::Nexus {
         opctx: &OpContext,
         project_lookup: &lookup::Project<'_>,
         pagparams: &PaginatedBy<'_>,
+        label_selector: Option<&LabelSelector>,
     ) -> ListResultVec<db::model::Disk> {
         let (.., authz_project) =
             project_lookup.lookup_for(authz::Action::ListChildren).await?;
-        self.db_datastore.disk_list(opctx, &authz_project, pagparams).await
+        self.db_datastore
+            .disk_list(opctx, &authz_project, pagparams, label_selector)
+            .await
+    }
+
+    /// Replaces the labels on a Disk
+    pub async fn disk_labels_update(
+        &self,
+        opctx: &OpContext,
+        disk_lookup: &lookup::Disk<'_>,
+        labels: &Labels,
+    ) -> UpdateResult<db::model::Disk> {
+        let (.., authz_disk) =
+            disk_lookup.lookup_for(authz::Action::Modify).await?;
+        self.db_datastore
+            .disk_labels_update(opctx, &authz_disk, labels.clone().into())
+            .await
     }
 
     /// Modifies the runtime state of the Disk as requested.  This generally
@@ -618,4 +686,302 @@ impl super::Nexus {
 
         Ok(())
     }
+
+    /// Attach a detached disk to a Pantry and move it to the "Exporting"
+    /// state, so that its blocks can be read out with bulk reads.
+    pub async fn disk_export_start(
+        self: &Arc<Self>,
+        opctx: &OpContext,
+        disk_lookup: &lookup::Disk<'_>,
+    ) -> UpdateResult<()> {
+        let (.., authz_disk, db_disk) =
+            disk_lookup.fetch_for(authz::Action::Modify).await?;
+
+        let disk_state: DiskState = db_disk.state().into();
+        if disk_state != DiskState::Detached {
+            return Err(Error::invalid_request(&format!(
+                "disk must be detached to be exported, but it is {}",
+                disk_state.label(),
+            )));
+        }
+
+        let saga_params = sagas::export_start::Params {
+            serialized_authn: authn::saga::Serialized::for_opctx(opctx),
+            source: sagas::export_start::ExportSource::Disk(authz_disk.id()),
+        };
+
+        self.execute_saga::<sagas::export_start::SagaExportStart>(saga_params)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Bulk read some bytes from a disk that's in state Exporting
+    pub async fn disk_bulk_read(
+        self: &Arc<Self>,
+        disk_lookup: &lookup::Disk<'_>,
+        param: params::ExportBlocksBulkRead,
+    ) -> LookupResult<views::ExportedBlocks> {
+        let (.., db_disk) = disk_lookup.fetch_for(authz::Action::Read).await?;
+
+        let disk_state: DiskState = db_disk.state().into();
+        if disk_state != DiskState::Exporting {
+            return Err(Error::invalid_request(&format!(
+                "cannot export blocks with a bulk read for disk in state {:?}",
+                disk_state,
+            )));
+        }
+
+        if let Some(endpoint) = db_disk.pantry_address() {
+            self.pantry_bulk_read(db_disk.id(), endpoint, param).await
+        } else {
+            error!(self.log, "disk {} has no pantry address!", db_disk.id());
+            Err(Error::internal_error(&format!(
+                "disk {} has no pantry address!",
+                db_disk.id(),
+            )))
+        }
+    }
+
+    /// Detach a disk that's being exported from its Pantry, and move it back
+    /// to the "Detached" state.
+    pub async fn disk_export_stop(
+        self: &Arc<Self>,
+        opctx: &OpContext,
+        disk_lookup: &lookup::Disk<'_>,
+    ) -> UpdateResult<()> {
+        let (.., authz_disk) =
+            disk_lookup.lookup_for(authz::Action::Modify).await?;
+
+        let saga_params = sagas::export_stop::Params {
+            serialized_authn: authn::saga::Serialized::for_opctx(opctx),
+            source: sagas::export_start::ExportSource::Disk(authz_disk.id()),
+        };
+
+        self.execute_saga::<sagas::export_stop::SagaExportStop>(saga_params)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Bulk read some bytes from the volume attached to a Pantry under `id`,
+    /// for either a disk or a snapshot being exported.
+    pub(crate) async fn pantry_bulk_read(
+        &self,
+        id: Uuid,
+        endpoint: SocketAddrV6,
+        param: params::ExportBlocksBulkRead,
+    ) -> LookupResult<views::ExportedBlocks> {
+        if param.size == 0 || param.size > MAX_BULK_READ_BYTES {
+            return Err(Error::invalid_request(&format!(
+                "bulk read size must be between 1 and {} bytes",
+                MAX_BULK_READ_BYTES,
+            )));
+        }
+
+        info!(
+            self.log,
+            "bulk read of {} bytes from offset {} of {} using pantry endpoint {:?}",
+            param.size,
+            param.offset,
+            id,
+            endpoint,
+        );
+
+        // As with bulk writes, nothing here stops the export from being
+        // stopped between the state check done by the caller and this read.
+        // If that happens, the volume will have been detached from the Pantry
+        // and the read will fail, which is propagated up to the user.
+
+        let client = crucible_pantry_client::Client::new(&format!(
+            "http://{}",
+            endpoint
+        ));
+        let request = crucible_pantry_client::types::BulkReadRequest {
+            offset: param.offset,
+            size: param.size,
+        };
+
+        let response = client
+            .bulk_read(&id.to_string(), &request)
+            .await
+            .map_err(|e| match e {
+                crucible_pantry_client::Error::ErrorResponse(rv) => {
+                    match rv.status() {
+                        status if status.is_client_error() => {
+                            Error::invalid_request(&rv.message)
+                        }
+
+                        _ => Error::internal_error(&rv.message),
+                    }
+                }
+
+                _ => Error::internal_error(&format!(
+                    "error sending bulk read to pantry: {}",
+                    e,
+                )),
+            })?;
+
+        Ok(views::ExportedBlocks {
+            base64_encoded_data: response.into_inner().base64_encoded_data,
+        })
+    }
+
+    /// Restore a detached disk in place from a snapshot
+    ///
+    /// The disk keeps its id, but its contents are replaced with those of the
+    /// snapshot.  Anything written to the disk since is lost.
+    pub async fn disk_rollback(
+        self: &Arc<Self>,
+        opctx: &OpContext,
+        disk_lookup: &lookup::Disk<'_>,
+        params: &params::DiskRollback,
+    ) -> UpdateResult<db::model::Disk> {
+        let (.., authz_project, authz_disk, db_disk) =
+            disk_lookup.fetch_for(authz::Action::Modify).await?;
+
+        let disk_state: DiskState = db_disk.state().into();
+        if disk_state != DiskState::Detached {
+            return Err(Error::invalid_request(&format!(
+                "disk must be detached to be rolled back, but it is {}",
+                disk_state.label(),
+            )));
+        }
+
+        let (.., db_snapshot) = LookupPath::new(opctx, &self.db_datastore)
+            .snapshot_id(params.snapshot_id)
+            .fetch()
+            .await?;
+
+        if db_snapshot.project_id != authz_project.id() {
+            return Err(Error::invalid_request(
+                "snapshot does not belong to this project",
+            ));
+        }
+
+        if db_snapshot.state != db::model::SnapshotState::Ready {
+            return Err(Error::invalid_request(&format!(
+                "snapshot must be ready to roll back to, but it is {:?}",
+                db_snapshot.state,
+            )));
+        }
+
+        if db_snapshot.block_size != db_disk.block_size {
+            return Err(Error::invalid_request(&format!(
+                "snapshot block size {} does not match disk block size {}",
+                db_snapshot.block_size.to_bytes(),
+                db_disk.block_size.to_bytes(),
+            )));
+        }
+
+        if db_snapshot.size.to_bytes() > db_disk.size.to_bytes() {
+            return Err(Error::invalid_request(&format!(
+                "disk size {} must be greater than or equal to snapshot size {}",
+                db_disk.size.to_bytes(),
+                db_snapshot.size.to_bytes(),
+            )));
+        }
+
+        let saga_params = sagas::disk_rollback::Params {
+            serialized_authn: authn::saga::Serialized::for_opctx(opctx),
+            disk_id: authz_disk.id(),
+            snapshot_id: params.snapshot_id,
+            old_volume_id: db_disk.volume_id,
+            size: db_disk.size.into(),
+        };
+        self.execute_saga::<sagas::disk_rollback::SagaDiskRollback>(
+            saga_params,
+        )
+        .await?;
+
+        let (.., db_disk) = LookupPath::new(opctx, &self.db_datastore)
+            .disk_id(authz_disk.id())
+            .fetch()
+            .await?;
+        Ok(db_disk)
+    }
+
+    /// Grow a detached disk to a new, larger size.
+    ///
+    /// The disk keeps its id and contents, but is backed by a new volume of
+    /// the new size.  Growing a disk that's attached to an instance isn't
+    /// supported yet: a running instance can't be switched over to the new
+    /// volume, and there's no way to tell the guest about the new size.
+    pub async fn disk_resize(
+        self: &Arc<Self>,
+        opctx: &OpContext,
+        disk_lookup: &lookup::Disk<'_>,
+        params: &params::DiskResize,
+    ) -> UpdateResult<db::model::Disk> {
+        let (authz_silo, authz_project, authz_disk, db_disk) =
+            disk_lookup.fetch_for(authz::Action::Modify).await?;
+
+        // Every supported block size evenly divides MIN_DISK_SIZE_BYTES, so
+        // this also guarantees that the new size is a whole number of blocks.
+        if (params.size.to_bytes() % MIN_DISK_SIZE_BYTES as u64) != 0 {
+            return Err(Error::InvalidValue {
+                label: String::from("size"),
+                message: format!(
+                    "total size must be a multiple of {}",
+                    ByteCount::from(MIN_DISK_SIZE_BYTES)
+                ),
+            });
+        }
+
+        if params.size.to_bytes() > MAX_DISK_SIZE_BYTES {
+            return Err(Error::InvalidValue {
+                label: String::from("size"),
+                message: format!(
+                    "total size must be less than {}",
+                    ByteCount::try_from(MAX_DISK_SIZE_BYTES).unwrap()
+                ),
+            });
+        }
+
+        let disk_state: DiskState = db_disk.state().into();
+        match disk_state {
+            DiskState::Detached => {}
+            DiskState::Attaching(_)
+            | DiskState::Attached(_)
+            | DiskState::Detaching(_) => {
+                return Err(Error::invalid_request(
+                    "resizing a disk attached to an instance is not \
+                    supported: detach the disk first",
+                ));
+            }
+            _ => {
+                return Err(Error::invalid_request(&format!(
+                    "disk must be detached to be resized, but it is {}",
+                    disk_state.label(),
+                )));
+            }
+        }
+
+        if params.size.to_bytes() < db_disk.size.to_bytes() {
+            return Err(Error::invalid_request(
+                "disks can only be grown, not shrunk",
+            ));
+        }
+        if params.size.to_bytes() == db_disk.size.to_bytes() {
+            return Ok(db_disk);
+        }
+
+        let saga_params = sagas::disk_resize::Params {
+            serialized_authn: authn::saga::Serialized::for_opctx(opctx),
+            silo_id: authz_silo.id(),
+            project_id: authz_project.id(),
+            disk_id: authz_disk.id(),
+            old_volume_id: db_disk.volume_id,
+            old_size: db_disk.size.into(),
+            new_size: params.size,
+        };
+        self.execute_saga::<sagas::disk_resize::SagaDiskResize>(saga_params)
+            .await?;
+
+        let (.., db_disk) = LookupPath::new(opctx, &self.db_datastore)
+            .disk_id(authz_disk.id())
+            .fetch()
+            .await?;
+        Ok(db_disk)
+    }
 }
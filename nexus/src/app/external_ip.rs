@@ -4,12 +4,28 @@
 
 //! External IP addresses for instances
 
+use std::sync::Arc;
+
+use crate::authn;
 use crate::authz;
+use crate::db;
 use crate::db::lookup;
+use crate::db::lookup::LookupPath;
 use crate::db::model::IpKind;
 use crate::external_api::views::ExternalIp;
 use nexus_db_queries::context::OpContext;
+use nexus_types::external_api::params;
+use omicron_common::api::external::http_pagination::PaginatedBy;
+use omicron_common::api::external::CreateResult;
+use omicron_common::api::external::DeleteResult;
+use omicron_common::api::external::Error;
 use omicron_common::api::external::ListResultVec;
+use omicron_common::api::external::LookupResult;
+use omicron_common::api::external::NameOrId;
+use omicron_common::api::external::UpdateResult;
+use uuid::Uuid;
+
+use super::sagas;
 
 impl super::Nexus {
     pub async fn instance_list_external_ips(
@@ -33,4 +49,159 @@ impl super::Nexus {
             })
             .collect::<Vec<_>>())
     }
+
+    // Floating IPs
+
+    pub fn floating_ip_lookup<'a>(
+        &'a self,
+        opctx: &'a OpContext,
+        fip_selector: params::FloatingIpSelector,
+    ) -> LookupResult<lookup::FloatingIp<'a>> {
+        match fip_selector {
+            params::FloatingIpSelector {
+                floating_ip: NameOrId::Id(id),
+                project: None,
+            } => {
+                let floating_ip =
+                    LookupPath::new(opctx, &self.db_datastore).floating_ip_id(id);
+                Ok(floating_ip)
+            }
+            params::FloatingIpSelector {
+                floating_ip: NameOrId::Name(name),
+                project: Some(project),
+            } => {
+                let floating_ip = self
+                    .project_lookup(opctx, params::ProjectSelector { project })?
+                    .floating_ip_name_owned(name.into());
+                Ok(floating_ip)
+            }
+            params::FloatingIpSelector {
+                floating_ip: NameOrId::Id(_),
+                ..
+            } => Err(Error::invalid_request(
+                "when providing Floating IP as an ID project should not be specified",
+            )),
+            _ => Err(Error::invalid_request(
+                "Floating IP should either be UUID or project should be specified",
+            )),
+        }
+    }
+
+    pub async fn floating_ip_create(
+        &self,
+        opctx: &OpContext,
+        project_lookup: &lookup::Project<'_>,
+        params: params::FloatingIpCreate,
+    ) -> CreateResult<db::model::FloatingIp> {
+        let (.., authz_project) =
+            project_lookup.lookup_for(authz::Action::CreateChild).await?;
+
+        self.db_datastore
+            .allocate_floating_ip(opctx, &authz_project, Uuid::new_v4(), params)
+            .await
+    }
+
+    pub async fn floating_ips_list(
+        &self,
+        opctx: &OpContext,
+        project_lookup: &lookup::Project<'_>,
+        pagparams: &PaginatedBy<'_>,
+    ) -> ListResultVec<db::model::FloatingIp> {
+        let (.., authz_project) =
+            project_lookup.lookup_for(authz::Action::ListChildren).await?;
+
+        self.db_datastore
+            .floating_ips_list(opctx, &authz_project, pagparams)
+            .await
+    }
+
+    pub async fn floating_ip_delete(
+        &self,
+        opctx: &OpContext,
+        fip_lookup: &lookup::FloatingIp<'_>,
+    ) -> DeleteResult {
+        let (.., authz_fip) =
+            fip_lookup.lookup_for(authz::Action::Delete).await?;
+
+        self.db_datastore.floating_ip_delete(opctx, &authz_fip).await?;
+        Ok(())
+    }
+
+    /// Attach a Floating IP to an instance in the same project.
+    pub async fn floating_ip_attach(
+        self: &Arc<Self>,
+        opctx: &OpContext,
+        fip_lookup: &lookup::FloatingIp<'_>,
+        attach: params::FloatingIpAttach,
+    ) -> UpdateResult<db::model::FloatingIp> {
+        let (.., authz_project, authz_fip) =
+            fip_lookup.lookup_for(authz::Action::Modify).await?;
+
+        let instance_lookup = match attach.instance {
+            NameOrId::Id(id) => {
+                LookupPath::new(opctx, &self.db_datastore).instance_id(id)
+            }
+            NameOrId::Name(name) => LookupPath::new(opctx, &self.db_datastore)
+                .project_id(authz_project.id())
+                .instance_name_owned(name.into()),
+        };
+        let (.., authz_instance_project, authz_instance) =
+            instance_lookup.lookup_for(authz::Action::Modify).await?;
+        if authz_instance_project.id() != authz_project.id() {
+            return Err(Error::invalid_request(
+                "Floating IP must be in the same project as the instance",
+            ));
+        }
+
+        let saga_params = sagas::instance_ip_attach::Params {
+            serialized_authn: authn::saga::Serialized::for_opctx(opctx),
+            authz_fip: authz_fip.clone(),
+            authz_instance,
+        };
+        self.execute_saga::<sagas::instance_ip_attach::SagaInstanceIpAttach>(
+            saga_params,
+        )
+        .await?;
+
+        let (.., db_fip) = LookupPath::new(opctx, &self.db_datastore)
+            .floating_ip_id(authz_fip.id())
+            .fetch()
+            .await?;
+        Ok(db_fip)
+    }
+
+    /// Detach a Floating IP from the instance to which it is attached, if
+    /// any.
+    pub async fn floating_ip_detach(
+        self: &Arc<Self>,
+        opctx: &OpContext,
+        fip_lookup: &lookup::FloatingIp<'_>,
+    ) -> UpdateResult<db::model::FloatingIp> {
+        let (.., authz_fip, db_fip) =
+            fip_lookup.fetch_for(authz::Action::Modify).await?;
+
+        let Some(instance_id) = db_fip.parent_id else {
+            return Ok(db_fip);
+        };
+        let (.., authz_instance) = LookupPath::new(opctx, &self.db_datastore)
+            .instance_id(instance_id)
+            .lookup_for(authz::Action::Modify)
+            .await?;
+
+        let saga_params = sagas::instance_ip_detach::Params {
+            serialized_authn: authn::saga::Serialized::for_opctx(opctx),
+            authz_fip: authz_fip.clone(),
+            authz_instance,
+        };
+        self.execute_saga::<sagas::instance_ip_detach::SagaInstanceIpDetach>(
+            saga_params,
+        )
+        .await?;
+
+        let (.., db_fip) = LookupPath::new(opctx, &self.db_datastore)
+            .floating_ip_id(authz_fip.id())
+            .fetch()
+            .await?;
+        Ok(db_fip)
+    }
 }
@@ -19,6 +19,8 @@ use omicron_common::api::external::http_pagination::PaginatedBy;
 use omicron_common::api::external::CreateResult;
 use omicron_common::api::external::DeleteResult;
 use omicron_common::api::external::Error;
+use omicron_common::api::external::LabelSelector;
+use omicron_common::api::external::Labels;
 use omicron_common::api::external::ListResultVec;
 use omicron_common::api::external::LookupResult;
 use omicron_common::api::external::NameOrId;
@@ -74,6 +76,62 @@ impl super::Nexus {
             }
     }
 
+    /// Fetches an image
+    ///
+    /// An image specified by ID may also be a silo image of another silo that
+    /// has been shared with the current silo.
+    pub async fn image_fetch(
+        &self,
+        opctx: &OpContext,
+        image_selector: params::ImageSelector,
+    ) -> LookupResult<db::model::Image> {
+        if let params::ImageSelector {
+            image: NameOrId::Id(id),
+            project: None,
+        } = image_selector
+        {
+            let (.., authz_silo) = self
+                .current_silo_lookup(opctx)?
+                .lookup_for(authz::Action::Read)
+                .await?;
+            return self.image_fetch_for_silo(opctx, &authz_silo, id).await;
+        }
+        match self.image_lookup(opctx, image_selector).await? {
+            ImageLookup::ProjectImage(image) => {
+                let (.., db_image) = image.fetch().await?;
+                Ok(db_image.into())
+            }
+            ImageLookup::SiloImage(image) => {
+                let (.., db_image) = image.fetch().await?;
+                Ok(db_image.into())
+            }
+        }
+    }
+
+    /// Fetches an image by ID on behalf of `authz_silo`: either an image that
+    /// can be read through the usual lookup, or a silo image of another silo
+    /// that has been shared with `authz_silo`
+    pub(crate) async fn image_fetch_for_silo(
+        &self,
+        opctx: &OpContext,
+        authz_silo: &authz::Silo,
+        image_id: Uuid,
+    ) -> LookupResult<db::model::Image> {
+        match LookupPath::new(opctx, &self.db_datastore)
+            .image_id(image_id)
+            .fetch()
+            .await
+        {
+            Ok((.., db_image)) => Ok(db_image),
+            Err(Error::ObjectNotFound { .. }) => {
+                self.db_datastore
+                    .silo_image_granted_fetch(opctx, authz_silo, image_id)
+                    .await
+            }
+            Err(error) => Err(error),
+        }
+    }
+
     /// Creates an image
     pub async fn image_create(
         self: &Arc<Self>,
@@ -206,6 +264,7 @@ impl super::Nexus {
                     digest: None, // not computed for URL type
                     block_size: db_block_size,
                     size: size.into(),
+                    labels: params.labels.clone().into(),
                 }
             }
 
@@ -252,6 +311,7 @@ impl super::Nexus {
                     digest: None, // TODO
                     block_size: db_snapshot.block_size,
                     size: db_snapshot.size,
+                    labels: params.labels.clone().into(),
                 }
             }
 
@@ -306,6 +366,7 @@ impl super::Nexus {
                     digest: None,
                     block_size: db_block_size,
                     size: size.into(),
+                    labels: params.labels.clone().into(),
                 }
             }
         };
@@ -337,20 +398,64 @@ impl super::Nexus {
         opctx: &OpContext,
         parent_lookup: &ImageParentLookup<'_>,
         pagparams: &PaginatedBy<'_>,
+        label_selector: Option<&LabelSelector>,
     ) -> ListResultVec<db::model::Image> {
         match parent_lookup {
             ImageParentLookup::Project(project) => {
                 let (.., authz_project) =
                     project.lookup_for(authz::Action::ListChildren).await?;
                 self.db_datastore
-                    .project_image_list(opctx, &authz_project, pagparams)
+                    .project_image_list(
+                        opctx,
+                        &authz_project,
+                        pagparams,
+                        label_selector,
+                    )
                     .await
             }
             ImageParentLookup::Silo(silo) => {
                 let (.., authz_silo) =
                     silo.lookup_for(authz::Action::ListChildren).await?;
                 self.db_datastore
-                    .silo_image_list(opctx, &authz_silo, pagparams)
+                    .silo_image_list(
+                        opctx,
+                        &authz_silo,
+                        pagparams,
+                        label_selector,
+                    )
+                    .await
+            }
+        }
+    }
+
+    /// Replaces the labels on an image
+    pub async fn image_labels_update(
+        &self,
+        opctx: &OpContext,
+        image_lookup: &ImageLookup<'_>,
+        labels: &Labels,
+    ) -> UpdateResult<db::model::Image> {
+        match image_lookup {
+            ImageLookup::ProjectImage(lookup) => {
+                let (.., authz_project_image) =
+                    lookup.lookup_for(authz::Action::Modify).await?;
+                self.db_datastore
+                    .project_image_labels_update(
+                        opctx,
+                        &authz_project_image,
+                        labels.clone().into(),
+                    )
+                    .await
+            }
+            ImageLookup::SiloImage(lookup) => {
+                let (.., authz_silo_image) =
+                    lookup.lookup_for(authz::Action::Modify).await?;
+                self.db_datastore
+                    .silo_image_labels_update(
+                        opctx,
+                        &authz_silo_image,
+                        labels.clone().into(),
+                    )
                     .await
             }
         }
@@ -433,4 +538,85 @@ impl super::Nexus {
             }),
         }
     }
+
+    /// Looks up an image that is being shared with other silos, which must
+    /// be a silo image
+    async fn image_silo_image_lookup_for(
+        image_lookup: &ImageLookup<'_>,
+        action: authz::Action,
+    ) -> LookupResult<(authz::Silo, authz::SiloImage)> {
+        match image_lookup {
+            ImageLookup::SiloImage(lookup) => lookup.lookup_for(action).await,
+            ImageLookup::ProjectImage(_) => Err(Error::invalid_request(
+                "only silo images can be shared with other silos",
+            )),
+        }
+    }
+
+    /// Lists the silos with which a silo image has been shared
+    pub async fn image_silo_list(
+        &self,
+        opctx: &OpContext,
+        image_lookup: &ImageLookup<'_>,
+        pagparams: &PaginatedBy<'_>,
+    ) -> ListResultVec<db::model::Silo> {
+        let (_, authz_silo_image) = Self::image_silo_image_lookup_for(
+            image_lookup,
+            authz::Action::Read,
+        )
+        .await?;
+        self.db_datastore
+            .image_silo_list(opctx, &authz_silo_image, pagparams)
+            .await
+    }
+
+    /// Shares a silo image with another silo, whose projects can then create
+    /// disks from the image
+    pub async fn image_silo_add(
+        &self,
+        opctx: &OpContext,
+        image_lookup: &ImageLookup<'_>,
+        silo: NameOrId,
+    ) -> CreateResult<db::model::Silo> {
+        let (authz_image_silo, authz_silo_image) =
+            Self::image_silo_image_lookup_for(
+                image_lookup,
+                authz::Action::Modify,
+            )
+            .await?;
+        let (authz_silo, db_silo) =
+            self.silo_lookup(opctx, silo)?.fetch().await?;
+        if authz_silo.id() == authz_image_silo.id() {
+            return Err(Error::invalid_request(
+                "an image cannot be shared with the silo that owns it",
+            ));
+        }
+
+        self.db_datastore
+            .image_silo_add(opctx, &authz_silo_image, &authz_silo)
+            .await?;
+        Ok(db_silo)
+    }
+
+    /// Stops sharing a silo image with another silo
+    pub async fn image_silo_remove(
+        &self,
+        opctx: &OpContext,
+        image_lookup: &ImageLookup<'_>,
+        silo: NameOrId,
+    ) -> DeleteResult {
+        let (_, authz_silo_image) = Self::image_silo_image_lookup_for(
+            image_lookup,
+            authz::Action::Modify,
+        )
+        .await?;
+        let (authz_silo,) = self
+            .silo_lookup(opctx, silo)?
+            .lookup_for(authz::Action::Read)
+            .await?;
+
+        self.db_datastore
+            .image_silo_remove(opctx, &authz_silo_image, &authz_silo)
+            .await
+    }
 }
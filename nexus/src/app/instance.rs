@@ -14,12 +14,14 @@ use crate::app::sagas;
 use crate::app::sagas::retry_until_known_result;
 use crate::authn;
 use crate::authz;
+use crate::cidata::CiDataInputs;
 use crate::cidata::InstanceCiData;
 use crate::db;
 use crate::db::identity::Resource;
 use crate::db::lookup;
 use crate::db::lookup::LookupPath;
 use crate::external_api::params;
+use crate::external_api::shared;
 use cancel_safe_futures::prelude::*;
 use futures::future::Fuse;
 use futures::{FutureExt, SinkExt, StreamExt};
@@ -33,8 +35,11 @@ use omicron_common::api::external::CreateResult;
 use omicron_common::api::external::DataPageParams;
 use omicron_common::api::external::DeleteResult;
 use omicron_common::api::external::Error;
+use omicron_common::api::external::InstanceCpuCount;
 use omicron_common::api::external::InstanceState;
 use omicron_common::api::external::InternalContext;
+use omicron_common::api::external::LabelSelector;
+use omicron_common::api::external::Labels;
 use omicron_common::api::external::ListResultVec;
 use omicron_common::api::external::LookupResult;
 use omicron_common::api::external::NameOrId;
@@ -48,6 +53,7 @@ use propolis_client::support::tungstenite::Message as WebSocketMessage;
 use propolis_client::support::InstanceSerialConsoleHelper;
 use propolis_client::support::WSClientOffset;
 use propolis_client::support::WebSocketStream;
+use serde_json::json;
 use sled_agent_client::types::InstanceMigrationSourceParams;
 use sled_agent_client::types::InstancePutMigrationIdsBody;
 use sled_agent_client::types::InstancePutStateBody;
@@ -114,7 +120,7 @@ impl super::Nexus {
         project_lookup: &lookup::Project<'_>,
         params: &params::InstanceCreate,
     ) -> CreateResult<db::model::Instance> {
-        let (.., authz_project) =
+        let (authz_silo, authz_project) =
             project_lookup.lookup_for(authz::Action::CreateChild).await?;
 
         // Validate parameters
@@ -126,16 +132,16 @@ impl super::Nexus {
         }
         for disk in &params.disks {
             if let params::InstanceDiskAttachment::Create(create) = disk {
-                self.validate_disk_create_params(opctx, &authz_project, create)
-                    .await?;
+                self.validate_disk_create_params(
+                    opctx,
+                    &authz_silo,
+                    &authz_project,
+                    create,
+                )
+                .await?;
             }
         }
-        if params.ncpus.0 > MAX_VCPU_PER_INSTANCE {
-            return Err(Error::invalid_request(&format!(
-                "cannot have more than {} vCPUs per instance",
-                MAX_VCPU_PER_INSTANCE
-            )));
-        }
+        validate_instance_size(params.ncpus, params.memory)?;
         if params.external_ips.len() > MAX_EXTERNAL_IPS_PER_INSTANCE {
             return Err(Error::invalid_request(&format!(
                 "An instance may not have more than {} external IP addresses",
@@ -168,50 +174,33 @@ impl super::Nexus {
             }
         }
 
-        // Reject instances where the memory is not at least
-        // MIN_MEMORY_BYTES_PER_INSTANCE
-        if params.memory.to_bytes() < MIN_MEMORY_BYTES_PER_INSTANCE as u64 {
-            return Err(Error::InvalidValue {
-                label: String::from("size"),
-                message: format!(
-                    "memory must be at least {}",
-                    ByteCount::from(MIN_MEMORY_BYTES_PER_INSTANCE)
-                ),
-            });
-        }
-
-        // Reject instances where the memory is not divisible by
-        // MIN_MEMORY_BYTES_PER_INSTANCE
-        if (params.memory.to_bytes() % MIN_MEMORY_BYTES_PER_INSTANCE as u64)
-            != 0
-        {
-            return Err(Error::InvalidValue {
-                label: String::from("size"),
-                message: format!(
-                    "memory must be divisible by {}",
-                    ByteCount::from(MIN_MEMORY_BYTES_PER_INSTANCE)
-                ),
-            });
+        let mut affinity_groups =
+            Vec::with_capacity(params.affinity_groups.len());
+        for group in &params.affinity_groups {
+            affinity_groups.push(
+                self.affinity_group_lookup_in_project(
+                    opctx,
+                    &authz_project,
+                    group,
+                )
+                .await?,
+            );
         }
 
-        // Reject instances where the memory is greated than the limit
-        if params.memory.to_bytes() > MAX_MEMORY_BYTES_PER_INSTANCE {
-            return Err(Error::InvalidValue {
-                label: String::from("size"),
-                message: format!(
-                    "memory must be less than or equal to {}",
-                    ByteCount::try_from(MAX_MEMORY_BYTES_PER_INSTANCE).unwrap()
-                ),
-            });
-        }
+        let ssh_keys = self
+            .instance_ssh_keys_resolve(opctx, params.ssh_public_keys.as_deref())
+            .await?;
 
         let saga_params = sagas::instance_create::Params {
             serialized_authn: authn::saga::Serialized::for_opctx(opctx),
+            silo_id: authz_silo.id(),
             project_id: authz_project.id(),
             create_params: params.clone(),
             boundary_switches: self
                 .boundary_switches(&self.opctx_alloc)
                 .await?,
+            affinity_groups,
+            ssh_keys,
         };
 
         let saga_outputs = self
@@ -264,18 +253,163 @@ impl super::Nexus {
             .instance_id(instance_id)
             .fetch()
             .await?;
+
+        self.vpc_dns_activate();
         Ok(db_instance)
     }
 
+    /// Resolves the SSH public keys requested for a new instance to the IDs of
+    /// keys owned by the creating user.
+    ///
+    /// If no keys were requested, all of the user's keys are chosen.
+    async fn instance_ssh_keys_resolve(
+        &self,
+        opctx: &OpContext,
+        requested: Option<&[NameOrId]>,
+    ) -> Result<Vec<Uuid>, Error> {
+        // TODO-security: this should be replaced with a lookup based on
+        // on `SiloUser` role assignments once those are in place.
+        let actor = opctx.authn.actor_required().internal_context(
+            "loading current user's ssh keys for new Instance",
+        )?;
+        let silo_user_id = actor.actor_id();
+
+        let Some(requested) = requested else {
+            let (.., authz_user) = LookupPath::new(opctx, &self.db_datastore)
+                .silo_user_id(silo_user_id)
+                .lookup_for(authz::Action::ListChildren)
+                .await?;
+            let ssh_keys = self
+                .db_datastore
+                .ssh_keys_list(
+                    opctx,
+                    &authz_user,
+                    &PaginatedBy::Name(DataPageParams {
+                        marker: None,
+                        direction: dropshot::PaginationOrder::Ascending,
+                        limit: std::num::NonZeroU32::new(MAX_KEYS_PER_INSTANCE)
+                            .unwrap(),
+                    }),
+                )
+                .await?;
+            return Ok(ssh_keys.into_iter().map(|k| k.id()).collect());
+        };
+
+        if requested.len() > usize::try_from(MAX_KEYS_PER_INSTANCE).unwrap() {
+            return Err(Error::invalid_request(&format!(
+                "cannot inject more than {} SSH public keys into an instance",
+                MAX_KEYS_PER_INSTANCE,
+            )));
+        }
+
+        let mut ssh_key_ids = Vec::with_capacity(requested.len());
+        for ssh_key in requested {
+            let selector = params::SshKeySelector {
+                silo_user_id,
+                ssh_key: ssh_key.clone(),
+            };
+            let (.., authz_ssh_key, db_ssh_key) =
+                self.ssh_key_lookup(opctx, &selector)?.fetch().await?;
+            // Keys named by ID may belong to anyone; only the creating user's
+            // own keys may be chosen.
+            if db_ssh_key.silo_user_id != silo_user_id {
+                return Err(authz_ssh_key.not_found());
+            }
+            if !ssh_key_ids.contains(&db_ssh_key.id()) {
+                ssh_key_ids.push(db_ssh_key.id());
+            }
+        }
+        Ok(ssh_key_ids)
+    }
+
     pub async fn instance_list(
         &self,
         opctx: &OpContext,
         project_lookup: &lookup::Project<'_>,
         pagparams: &PaginatedBy<'_>,
+        label_selector: Option<&LabelSelector>,
     ) -> ListResultVec<db::model::Instance> {
         let (.., authz_project) =
             project_lookup.lookup_for(authz::Action::ListChildren).await?;
-        self.db_datastore.instance_list(opctx, &authz_project, pagparams).await
+        self.db_datastore
+            .instance_list(opctx, &authz_project, pagparams, label_selector)
+            .await
+    }
+
+    /// Lists the SSH public keys chosen for an Instance when it was created
+    pub async fn instance_ssh_public_key_list(
+        &self,
+        opctx: &OpContext,
+        instance_lookup: &lookup::Instance<'_>,
+        pagparams: &PaginatedBy<'_>,
+    ) -> ListResultVec<db::model::SshKey> {
+        let (.., authz_instance) =
+            instance_lookup.lookup_for(authz::Action::Read).await?;
+        self.db_datastore
+            .instance_ssh_keys_list(opctx, &authz_instance, pagparams)
+            .await
+    }
+
+    /// Replaces the labels on an Instance
+    pub async fn instance_labels_update(
+        &self,
+        opctx: &OpContext,
+        instance_lookup: &lookup::Instance<'_>,
+        labels: &Labels,
+    ) -> UpdateResult<db::model::Instance> {
+        let (.., authz_instance) =
+            instance_lookup.lookup_for(authz::Action::Modify).await?;
+        self.db_datastore
+            .instance_labels_update(
+                opctx,
+                &authz_instance,
+                labels.clone().into(),
+            )
+            .await
+    }
+
+    /// Replaces the user data of an Instance
+    ///
+    /// This can be done while the instance is running, but the guest only sees
+    /// the new user data the next time the instance starts.  The instance ID
+    /// given to cloud-init changes with the user data, so cloud-init redoes
+    /// its once-per-instance work with the new user data.
+    pub async fn instance_user_data_update(
+        &self,
+        opctx: &OpContext,
+        instance_lookup: &lookup::Instance<'_>,
+        params: &params::InstanceUserDataUpdate,
+    ) -> UpdateResult<db::model::Instance> {
+        let (.., authz_instance) =
+            instance_lookup.lookup_for(authz::Action::Modify).await?;
+        self.db_datastore
+            .instance_user_data_update(
+                opctx,
+                &authz_instance,
+                params.user_data.clone(),
+            )
+            .await
+    }
+
+    /// Change the number of vCPUs and the amount of memory of an instance,
+    /// which must be stopped.
+    pub async fn instance_update(
+        &self,
+        opctx: &OpContext,
+        instance_lookup: &lookup::Instance<'_>,
+        params: &params::InstanceUpdate,
+    ) -> UpdateResult<db::model::Instance> {
+        let (.., authz_instance) =
+            instance_lookup.lookup_for(authz::Action::Modify).await?;
+        validate_instance_size(params.ncpus, params.memory)?;
+        self.db_datastore
+            .instance_resize(
+                opctx,
+                &authz_instance,
+                params.ncpus,
+                params.memory,
+            )
+            .await
     }
 
     // This operation may only occur on stopped instances, which implies that
@@ -310,6 +444,8 @@ impl super::Nexus {
             saga_params,
         )
         .await?;
+
+        self.vpc_dns_activate();
         Ok(())
     }
 
@@ -487,6 +623,20 @@ impl super::Nexus {
         let (.., authz_instance, mut db_instance) =
             instance_lookup.fetch().await?;
 
+        // Instances are started on the sled on which they were placed, so
+        // make sure that sled still satisfies the instance's hard affinity
+        // policies.
+        let sled_id = db_instance.runtime().sled_id;
+        let constraints =
+            self.instance_affinity_constraints(db_instance.id()).await?.build();
+        if !constraints.permits(sled_id) {
+            return Err(Error::invalid_request(&format!(
+                "starting the instance on sled {} would violate the hard \
+                policy of one of its affinity groups",
+                sled_id
+            )));
+        }
+
         // The instance is not really being "created" (it already exists from
         // the caller's perspective), but if it does not exist on its sled, the
         // target sled agent will populate its instance manager with the
@@ -513,6 +663,219 @@ impl super::Nexus {
         self.db_datastore.instance_refetch(opctx, &authz_instance).await
     }
 
+    /// Restart a failed instance on a new Propolis server, placed on a
+    /// different sled from the one on which it failed if another sled has room
+    /// for it.
+    ///
+    /// This is used by the background task that restarts failed instances
+    /// whose auto-restart policy asks for it.  The old Propolis server is
+    /// abandoned: Nexus asks its sled to forget about it, in case the sled is
+    /// still alive, and releases its sled reservation.  If the instance can't
+    /// be started on its new Propolis server, it's returned to the Failed
+    /// state, so that the task tries again later.
+    pub(crate) async fn instance_auto_restart(
+        &self,
+        opctx: &OpContext,
+        instance_id: Uuid,
+    ) -> UpdateResult<db::model::Instance> {
+        let instance_lookup =
+            LookupPath::new(opctx, &self.db_datastore).instance_id(instance_id);
+        let (.., authz_instance, db_instance) =
+            instance_lookup.fetch_for(authz::Action::Modify).await?;
+        let runtime = db_instance.runtime();
+        if runtime.state.state() != &InstanceState::Failed {
+            return Err(Error::conflict(&format!(
+                "instance is no longer failed (it is \"{}\")",
+                runtime.state.state()
+            )));
+        }
+
+        if let Err(error) = self
+            .instance_ensure_unregistered(
+                opctx,
+                &authz_instance,
+                &db_instance,
+                WriteBackUpdatedInstance::Drop,
+            )
+            .await
+        {
+            warn!(self.log, "failed to unregister failed instance";
+                "instance_id" => %instance_id,
+                "sled_id" => %runtime.sled_id,
+                "error" => ?error);
+        }
+
+        let propolis_id = Uuid::new_v4();
+        let constraints = db::model::SledReservationConstraintBuilder::new()
+            .avoid(&[runtime.sled_id])
+            .affinity_groups(instance_id, &[])
+            .build();
+        let resources = db::model::Resources::new(
+            u32::from(runtime.ncpus.0 .0),
+            runtime.memory,
+            db::model::ByteCount::from(ByteCount::from(0)),
+        );
+        let sled_id = self
+            .reserve_on_random_sled(
+                propolis_id,
+                db::model::SledResourceKind::Instance,
+                resources,
+                constraints,
+            )
+            .await?
+            .sled_id;
+        let propolis_ip =
+            self.db_datastore.next_ipv6_address(opctx, sled_id).await?;
+
+        // Moving the instance to its new Propolis server advances the
+        // Propolis generation, so that any late updates from the old server
+        // are ignored.
+        let new_runtime = db::model::InstanceRuntimeState {
+            state: db::model::InstanceState::new(InstanceState::Stopped),
+            time_updated: chrono::Utc::now(),
+            gen: runtime.gen.next().into(),
+            sled_id,
+            propolis_id,
+            propolis_ip: Some(ipnetwork::Ipv6Network::from(propolis_ip).into()),
+            dst_propolis_id: None,
+            migration_id: None,
+            propolis_gen: runtime.propolis_gen.next().into(),
+            ..runtime.clone()
+        };
+        let updated = self
+            .db_datastore
+            .instance_update_runtime(&instance_id, &new_runtime)
+            .await?;
+        if !updated {
+            self.delete_sled_reservation(propolis_id).await?;
+            return Err(Error::conflict(
+                "instance changed while it was being restarted",
+            ));
+        }
+        self.delete_sled_reservation(runtime.propolis_id).await?;
+
+        let result = match self
+            .handle_instance_propolis_gen_change(
+                opctx,
+                &new_runtime.into(),
+                &db_instance,
+            )
+            .await
+        {
+            Ok(()) => self.instance_start(opctx, &instance_lookup).await,
+            Err(error) => Err(error),
+        };
+        if let Err(error) = &result {
+            warn!(self.log, "failed to start restarted instance";
+                "instance_id" => %instance_id,
+                "sled_id" => %sled_id,
+                "error" => ?error);
+            self.instance_auto_restart_unwind(
+                opctx,
+                &authz_instance,
+                propolis_id,
+            )
+            .await;
+        }
+        result
+    }
+
+    /// Returns an instance that `instance_auto_restart` moved to the Propolis
+    /// server `propolis_id` but couldn't start to the Failed state, and
+    /// releases the sled reservation made for that server
+    ///
+    /// Nothing is changed if the instance has moved on from that server, or
+    /// is starting or running on it after all.  Errors are logged, since the
+    /// failure to start the instance is what gets reported.
+    async fn instance_auto_restart_unwind(
+        &self,
+        opctx: &OpContext,
+        authz_instance: &authz::Instance,
+        propolis_id: Uuid,
+    ) {
+        let log = self.log.new(o!(
+            "instance_id" => authz_instance.id().to_string(),
+            "propolis_id" => propolis_id.to_string(),
+        ));
+        let db_instance = match self
+            .db_datastore
+            .instance_refetch(opctx, authz_instance)
+            .await
+        {
+            Ok(db_instance) => db_instance,
+            Err(error) => {
+                error!(log, "failed to fetch instance after failed restart";
+                    "error" => ?error);
+                return;
+            }
+        };
+        let runtime = db_instance.runtime();
+        if runtime.propolis_id != propolis_id {
+            info!(log, "instance moved on after failed restart";
+                "current_propolis_id" => %runtime.propolis_id);
+            return;
+        }
+
+        match runtime.state.state() {
+            InstanceState::Creating | InstanceState::Stopped => {
+                // The new sled may have registered the instance before
+                // starting it failed.
+                if let Err(error) = self
+                    .instance_ensure_unregistered(
+                        opctx,
+                        authz_instance,
+                        &db_instance,
+                        WriteBackUpdatedInstance::Drop,
+                    )
+                    .await
+                {
+                    warn!(log, "failed to unregister instance after failed \
+                        restart"; "error" => ?error);
+                }
+
+                let failed_runtime = db::model::InstanceRuntimeState {
+                    state: db::model::InstanceState::new(InstanceState::Failed),
+                    time_updated: chrono::Utc::now(),
+                    gen: runtime.gen.next().into(),
+                    ..runtime.clone()
+                };
+                match self
+                    .db_datastore
+                    .instance_update_runtime(
+                        &authz_instance.id(),
+                        &failed_runtime,
+                    )
+                    .await
+                {
+                    Ok(true) => {}
+                    Ok(false) => {
+                        info!(log, "instance changed after failed restart");
+                        return;
+                    }
+                    Err(error) => {
+                        error!(log, "failed to mark instance failed after \
+                            failed restart"; "error" => ?error);
+                        return;
+                    }
+                }
+            }
+
+            // Nexus already marked the instance failed, because its new sled
+            // agent returned an error.
+            InstanceState::Failed => {}
+
+            state => {
+                info!(log, "instance is {} after failed restart", state);
+                return;
+            }
+        }
+
+        if let Err(error) = self.delete_sled_reservation(propolis_id).await {
+            error!(log, "failed to release sled reservation after failed \
+                restart"; "error" => ?error);
+        }
+    }
+
     /// Make sure the given Instance is stopped.
     pub async fn instance_stop(
         &self,
@@ -702,9 +1065,8 @@ impl super::Nexus {
             .derive_guest_network_interface_info(&opctx, &authz_instance)
             .await?;
 
-        // Collect the external IPs for the instance.
-        // TODO-correctness: Handle Floating IPs, see
-        //  https://github.com/oxidecomputer/omicron/issues/1334
+        // Collect the external IPs for the instance, including any attached
+        // Floating IPs.
         let (snat_ip, external_ips): (Vec<_>, Vec<_>) = self
             .db_datastore
             .instance_lookup_external_ips(&opctx, authz_instance.id())
@@ -726,7 +1088,7 @@ impl super::Nexus {
                 .as_str(),
             ));
         }
-        let external_ips =
+        let external_ips: Vec<_> =
             external_ips.into_iter().map(|model| model.ip.ip()).collect();
         if snat_ip.len() != 1 {
             return Err(Error::internal_error(
@@ -736,13 +1098,13 @@ impl super::Nexus {
         let source_nat =
             SourceNatConfig::from(snat_ip.into_iter().next().unwrap());
 
-        // Gather the firewall rules for the VPC this instance is in.
-        // The NIC info we gathered above doesn't have VPC information
-        // because the sled agent doesn't care about that directly,
-        // so we fetch it via the first interface's VNI. (It doesn't
-        // matter which one we use because all NICs must be in the
-        // same VPC; see the check in project_create_instance.)
-        let firewall_rules = if let Some(nic) = nics.first() {
+        // Gather the firewall rules and the DNS zone for the VPC this
+        // instance is in.  The NIC info we gathered above doesn't have VPC
+        // information because the sled agent doesn't care about that
+        // directly, so we fetch it via the first interface's VNI. (It doesn't
+        // matter which one we use because all NICs must be in the same VPC;
+        // see the check in project_create_instance.)
+        let (firewall_rules, vpc_dns) = if let Some(nic) = nics.first() {
             let vni = Vni::try_from(nic.vni.0)?;
             let vpc = self
                 .db_datastore
@@ -756,28 +1118,24 @@ impl super::Nexus {
                 .db_datastore
                 .vpc_list_firewall_rules(opctx, &authz_vpc)
                 .await?;
-            self.resolve_firewall_rules_for_sled_agent(opctx, &vpc, &rules)
-                .await?
+            let firewall_rules = self
+                .resolve_firewall_rules_for_sled_agent(opctx, &vpc, &rules)
+                .await?;
+            let (vpc_dns, _) = self
+                .vpc_dns_zone_for_sled_agent(opctx, &authz_vpc, &vpc)
+                .await?;
+            (firewall_rules, Some(vpc_dns))
         } else {
-            vec![]
+            (vec![], None)
         };
 
-        // Gather the SSH public keys of the actor make the request so
-        // that they may be injected into the new image via cloud-init.
-        // TODO-security: this should be replaced with a lookup based on
-        // on `SiloUser` role assignments once those are in place.
-        let actor = opctx.authn.actor_required().internal_context(
-            "loading current user's ssh keys for new Instance",
-        )?;
-        let (.., authz_user) = LookupPath::new(opctx, &self.db_datastore)
-            .silo_user_id(actor.actor_id())
-            .lookup_for(authz::Action::ListChildren)
-            .await?;
+        // Gather the SSH public keys chosen for the instance when it was
+        // created so that they may be injected into the guest via cloud-init.
         let public_keys = self
             .db_datastore
-            .ssh_keys_list(
+            .instance_ssh_keys_list(
                 opctx,
-                &authz_user,
+                authz_instance,
                 &PaginatedBy::Name(DataPageParams {
                     marker: None,
                     direction: dropshot::PaginationOrder::Ascending,
@@ -790,6 +1148,24 @@ impl super::Nexus {
             .map(|ssh_key| ssh_key.public_key)
             .collect::<Vec<String>>();
 
+        // The project's labels are exposed to the guest alongside the
+        // instance's own.
+        let (.., db_project) = LookupPath::new(opctx, &self.db_datastore)
+            .project_id(db_instance.project_id)
+            .fetch()
+            .await?;
+
+        // The cidata volume is built from scratch here every time the instance
+        // is registered, so that it reflects the current SSH keys, user data,
+        // network interfaces, external IPs and labels.
+        let cidata = db_instance.generate_cidata(&CiDataInputs {
+            public_keys: &public_keys,
+            nics: &nics,
+            external_ips: &external_ips,
+            project_labels: &db_project.labels,
+            vpc_dns_zone: vpc_dns.as_ref().map(|zone| zone.name.as_str()),
+        })?;
+
         // Ask the sled agent to begin the state change.  Then update the
         // database to reflect the new intermediate state.  If this update is
         // not the newest one, that's fine.  That might just mean the sled agent
@@ -803,10 +1179,11 @@ impl super::Nexus {
             source_nat,
             external_ips,
             firewall_rules,
+            vpc_dns,
             disks: disk_reqs,
             cloud_init_bytes: Some(base64::Engine::encode(
                 &base64::engine::general_purpose::STANDARD,
-                db_instance.generate_cidata(&public_keys)?,
+                cidata,
             )),
         };
 
@@ -957,7 +1334,7 @@ impl super::Nexus {
         instance_lookup: &lookup::Instance<'_>,
         disk: NameOrId,
     ) -> UpdateResult<db::model::Disk> {
-        let (.., authz_project, authz_instance) =
+        let (authz_silo, authz_project, authz_instance) =
             instance_lookup.lookup_for(authz::Action::Modify).await?;
         let (.., authz_project_disk, authz_disk) = self
             .disk_lookup(
@@ -1007,6 +1384,17 @@ impl super::Nexus {
                 MAX_DISKS_PER_INSTANCE,
             )
             .await?;
+
+        self.webhook_event_publish(
+            Some(authz_silo.id()),
+            shared::WebhookEventClass::DiskAttach,
+            json!({
+                "disk_id": authz_disk.id(),
+                "instance_id": authz_instance.id(),
+                "project_id": authz_project.id(),
+            }),
+        )
+        .await;
         Ok(disk)
     }
 
@@ -1017,7 +1405,7 @@ impl super::Nexus {
         instance_lookup: &lookup::Instance<'_>,
         disk: NameOrId,
     ) -> UpdateResult<db::model::Disk> {
-        let (.., authz_project, authz_instance) =
+        let (authz_silo, authz_project, authz_instance) =
             instance_lookup.lookup_for(authz::Action::Modify).await?;
         let (.., authz_disk) = self
             .disk_lookup(
@@ -1049,6 +1437,17 @@ impl super::Nexus {
             .db_datastore
             .instance_detach_disk(&opctx, &authz_instance, &authz_disk)
             .await?;
+
+        self.webhook_event_publish(
+            Some(authz_silo.id()),
+            shared::WebhookEventClass::DiskDetach,
+            json!({
+                "disk_id": authz_disk.id(),
+                "instance_id": authz_instance.id(),
+                "project_id": authz_project.id(),
+            }),
+        )
+        .await;
         Ok(disk)
     }
 
@@ -1094,10 +1493,11 @@ impl super::Nexus {
         //   writes an update back to CRDB, and (c) sled agent won't process any
         //   new instance state changes (e.g. a change that stops an instance)
         //   until this state change is successfully committed.
-        let (.., db_instance) = LookupPath::new(&opctx, &self.db_datastore)
-            .instance_id(*id)
-            .fetch_for(authz::Action::Read)
-            .await?;
+        let (authz_silo, authz_project, _, db_instance) =
+            LookupPath::new(&opctx, &self.db_datastore)
+                .instance_id(*id)
+                .fetch_for(authz::Action::Read)
+                .await?;
 
         if new_runtime_state.propolis_gen > *db_instance.runtime().propolis_gen
         {
@@ -1120,6 +1520,21 @@ impl super::Nexus {
                     "instance_id" => %id,
                     "propolis_id" => %new_runtime_state.propolis_id,
                     "new_state" => %new_runtime_state.run_state);
+
+                let previous_state = db_instance.runtime().state.0;
+                if previous_state != new_runtime_state.run_state {
+                    self.webhook_event_publish(
+                        Some(authz_silo.id()),
+                        shared::WebhookEventClass::InstanceStateChange,
+                        json!({
+                            "instance_id": id,
+                            "project_id": authz_project.id(),
+                            "previous_state": previous_state,
+                            "state": new_runtime_state.run_state,
+                        }),
+                    )
+                    .await;
+                }
                 Ok(())
             }
 
@@ -1653,6 +2068,57 @@ impl super::Nexus {
     }
 }
 
+/// Checks that an instance with `ncpus` vCPUs and `memory` bytes of memory
+/// is within the per-instance limits.
+fn validate_instance_size(
+    ncpus: InstanceCpuCount,
+    memory: ByteCount,
+) -> Result<(), Error> {
+    if ncpus.0 > MAX_VCPU_PER_INSTANCE {
+        return Err(Error::invalid_request(&format!(
+            "cannot have more than {} vCPUs per instance",
+            MAX_VCPU_PER_INSTANCE
+        )));
+    }
+
+    // Reject instances where the memory is not at least
+    // MIN_MEMORY_BYTES_PER_INSTANCE
+    if memory.to_bytes() < MIN_MEMORY_BYTES_PER_INSTANCE as u64 {
+        return Err(Error::InvalidValue {
+            label: String::from("size"),
+            message: format!(
+                "memory must be at least {}",
+                ByteCount::from(MIN_MEMORY_BYTES_PER_INSTANCE)
+            ),
+        });
+    }
+
+    // Reject instances where the memory is not divisible by
+    // MIN_MEMORY_BYTES_PER_INSTANCE
+    if (memory.to_bytes() % MIN_MEMORY_BYTES_PER_INSTANCE as u64) != 0 {
+        return Err(Error::InvalidValue {
+            label: String::from("size"),
+            message: format!(
+                "memory must be divisible by {}",
+                ByteCount::from(MIN_MEMORY_BYTES_PER_INSTANCE)
+            ),
+        });
+    }
+
+    // Reject instances where the memory is greated than the limit
+    if memory.to_bytes() > MAX_MEMORY_BYTES_PER_INSTANCE {
+        return Err(Error::InvalidValue {
+            label: String::from("size"),
+            message: format!(
+                "memory must be less than or equal to {}",
+                ByteCount::try_from(MAX_MEMORY_BYTES_PER_INSTANCE).unwrap()
+            ),
+        });
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::super::Nexus;
@@ -18,6 +18,7 @@ use omicron_common::api::external::CreateResult;
 use omicron_common::api::external::DataPageParams;
 use omicron_common::api::external::DeleteResult;
 use omicron_common::api::external::Error;
+use omicron_common::api::external::IdentityMetadataCreateParams;
 use omicron_common::api::external::ListResultVec;
 use omicron_common::api::external::LookupResult;
 use omicron_common::api::external::NameOrId;
@@ -50,19 +51,35 @@ impl super::Nexus {
         opctx: &OpContext,
         new_pool: &params::IpPoolCreate,
     ) -> CreateResult<db::model::IpPool> {
-        self.db_datastore
-            .ip_pool_create(opctx, new_pool, /* internal= */ false)
-            .await
+        let silo_id = match &new_pool.silo {
+            Some(silo) => {
+                let (.., authz_silo) = self
+                    .silo_lookup(opctx, silo.clone())?
+                    .lookup_for(authz::Action::Read)
+                    .await?;
+                Some(authz_silo.id())
+            }
+            None => None,
+        };
+        let pool = db::model::IpPool::new(
+            &new_pool.identity,
+            silo_id,
+            new_pool.is_default,
+        );
+        self.db_datastore.ip_pool_create(opctx, pool).await
     }
 
+    /// Creates an IP pool for Oxide services
+    ///
+    /// Services pools are never linked to a Silo or made a default pool, so
+    /// only their identity is given.
     pub async fn ip_pool_services_create(
         &self,
         opctx: &OpContext,
-        new_pool: &params::IpPoolCreate,
+        identity: &IdentityMetadataCreateParams,
     ) -> CreateResult<db::model::IpPool> {
-        self.db_datastore
-            .ip_pool_create(opctx, new_pool, /* internal= */ true)
-            .await
+        let pool = db::model::IpPool::new_internal(identity);
+        self.db_datastore.ip_pool_create(opctx, pool).await
     }
 
     pub async fn ip_pools_list(
@@ -96,6 +113,88 @@ impl super::Nexus {
             .await
     }
 
+    /// Links an IP pool to a Silo, in place of any Silo it's linked to
+    pub async fn ip_pool_silo_link(
+        &self,
+        opctx: &OpContext,
+        pool_lookup: &lookup::IpPool<'_>,
+        link: &params::IpPoolSiloLink,
+    ) -> UpdateResult<db::model::IpPool> {
+        let (.., authz_pool, db_pool) =
+            pool_lookup.fetch_for(authz::Action::Modify).await?;
+        if db_pool.internal {
+            return Err(Error::not_found_by_name(
+                ResourceType::IpPool,
+                &db_pool.identity.name,
+            ));
+        }
+        let (.., authz_silo) = self
+            .silo_lookup(opctx, link.silo.clone())?
+            .lookup_for(authz::Action::Read)
+            .await?;
+        self.db_datastore
+            .ip_pool_set_link(
+                opctx,
+                &authz_pool,
+                Some(authz_silo.id()),
+                link.is_default,
+            )
+            .await
+    }
+
+    /// Unlinks an IP pool from its Silo, making it available to every Silo
+    ///
+    /// The pool stops being its Silo's default pool, rather than becoming the
+    /// fleet's.
+    pub async fn ip_pool_silo_unlink(
+        &self,
+        opctx: &OpContext,
+        pool_lookup: &lookup::IpPool<'_>,
+    ) -> UpdateResult<db::model::IpPool> {
+        let (.., authz_pool, db_pool) =
+            pool_lookup.fetch_for(authz::Action::Modify).await?;
+        if db_pool.internal {
+            return Err(Error::not_found_by_name(
+                ResourceType::IpPool,
+                &db_pool.identity.name,
+            ));
+        }
+        if db_pool.silo_id.is_none() {
+            return Err(Error::invalid_request(
+                "the IP pool is not linked to a Silo",
+            ));
+        }
+        self.db_datastore
+            .ip_pool_set_link(opctx, &authz_pool, None, false)
+            .await
+    }
+
+    /// Sets whether an IP pool is the default pool for its Silo, or for the
+    /// fleet if it isn't linked to a Silo
+    pub async fn ip_pool_set_default(
+        &self,
+        opctx: &OpContext,
+        pool_lookup: &lookup::IpPool<'_>,
+        params: &params::IpPoolSetDefault,
+    ) -> UpdateResult<db::model::IpPool> {
+        let (.., authz_pool, db_pool) =
+            pool_lookup.fetch_for(authz::Action::Modify).await?;
+        if db_pool.internal {
+            return Err(Error::not_found_by_name(
+                ResourceType::IpPool,
+                &db_pool.identity.name,
+            ));
+        }
+        self.db_datastore
+            .ip_pool_set_link(
+                opctx,
+                &authz_pool,
+                db_pool.silo_id,
+                params.is_default,
+            )
+            .await
+    }
+
     pub async fn ip_pool_list_ranges(
         &self,
         opctx: &OpContext,
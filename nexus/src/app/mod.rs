@@ -32,7 +32,10 @@ use uuid::Uuid;
 // The implementation of Nexus is large, and split into a number of submodules
 // by resource.
 mod address_lot;
+mod affinity;
+mod audit_log;
 pub mod background;
+mod bgp;
 mod certificate;
 mod device_auth;
 mod disk;
@@ -48,11 +51,13 @@ mod oximeter;
 mod project;
 mod rack;
 pub mod saga;
+mod scim;
 mod session;
 mod silo;
 mod sled;
 mod sled_instance;
 mod snapshot;
+mod snapshot_schedule;
 mod switch;
 mod switch_interface;
 mod switch_port;
@@ -60,8 +65,11 @@ pub mod test_interfaces;
 mod update;
 mod volume;
 mod vpc;
+mod vpc_dns;
+mod vpc_peering;
 mod vpc_router;
 mod vpc_subnet;
+mod webhook;
 
 // Sagas are not part of the "Nexus" implementation, but they are
 // application logic.
@@ -71,6 +79,7 @@ pub mod sagas;
 // the prefix unless it is unambiguous.
 
 pub(crate) use nexus_db_queries::db::queries::disk::MAX_DISKS_PER_INSTANCE;
+pub(crate) use scim::ScimGroupMembersUpdate;
 
 pub(crate) const MAX_NICS_PER_INSTANCE: usize = 8;
 
@@ -84,6 +93,7 @@ pub const MAX_MEMORY_BYTES_PER_INSTANCE: u64 = 64 * (1 << 30); // 64 GiB
 
 pub const MIN_DISK_SIZE_BYTES: u32 = 1 << 30; // 1 GiB
 pub const MAX_DISK_SIZE_BYTES: u64 = 1 << 40; // 1 TiB
+pub const MAX_BULK_READ_BYTES: u64 = 512 * 1024; // 512 KiB
 
 /// Manages an Oxide fleet -- the heart of the control plane
 pub struct Nexus {
@@ -147,6 +157,9 @@ pub struct Nexus {
 
     /// Background tasks
     background_tasks: background::BackgroundTasks,
+
+    /// Whether webhook receivers' endpoints may be at non-global addresses
+    webhook_allow_private_endpoints: bool,
 }
 
 impl Nexus {
@@ -268,10 +281,18 @@ impl Nexus {
             authn::Context::internal_api(),
             Arc::clone(&db_datastore),
         );
+        let (instance_restart_tx, mut instance_restart_rx) =
+            tokio::sync::mpsc::channel(1);
+        let (snapshot_schedule_tx, mut snapshot_schedule_rx) =
+            tokio::sync::mpsc::channel(1);
+        let (vpc_dns_tx, mut vpc_dns_rx) = tokio::sync::mpsc::channel(1);
         let background_tasks = background::BackgroundTasks::start(
             &background_ctx,
             Arc::clone(&db_datastore),
             &config.pkg.background_tasks,
+            instance_restart_tx,
+            snapshot_schedule_tx,
+            vpc_dns_tx,
         );
 
         let nexus = Nexus {
@@ -304,6 +325,11 @@ impl Nexus {
             resolver,
             dpd_clients,
             background_tasks,
+            webhook_allow_private_endpoints: config
+                .pkg
+                .background_tasks
+                .webhook_deliveries
+                .allow_private_endpoints,
         };
 
         // TODO-cleanup all the extra Arcs here seems wrong
@@ -330,6 +356,76 @@ impl Nexus {
 
         *nexus.recovery_task.lock().unwrap() = Some(recovery_task);
 
+        // Carry out the restarts of failed instances requested by the
+        // background task that finds them.  This holds only a weak reference
+        // to Nexus: the channel closes when Nexus (and so the background task)
+        // goes away.
+        let restart_nexus = Arc::downgrade(&nexus);
+        let restart_opctx = OpContext::for_background(
+            nexus.log.new(o!("component" => "InstanceRestarter")),
+            Arc::clone(&authz),
+            authn::Context::internal_api(),
+            Arc::clone(&nexus.db_datastore),
+        );
+        tokio::spawn(async move {
+            while let Some(request) = instance_restart_rx.recv().await {
+                let Some(nexus) = restart_nexus.upgrade() else {
+                    break;
+                };
+                let result = nexus
+                    .instance_auto_restart(&restart_opctx, request.instance_id)
+                    .await
+                    .map(|_| ());
+                let _ = request.result.send(result);
+            }
+        });
+
+        // Likewise, carry out the runs of snapshot schedules requested by the
+        // background task that finds the schedules that are due.
+        let schedule_nexus = Arc::downgrade(&nexus);
+        let schedule_opctx = OpContext::for_background(
+            nexus.log.new(o!("component" => "SnapshotScheduler")),
+            Arc::clone(&authz),
+            authn::Context::internal_api(),
+            Arc::clone(&nexus.db_datastore),
+        );
+        tokio::spawn(async move {
+            while let Some(request) = snapshot_schedule_rx.recv().await {
+                let Some(nexus) = schedule_nexus.upgrade() else {
+                    break;
+                };
+                let result = nexus
+                    .snapshot_schedule_run(
+                        &schedule_opctx,
+                        request.schedule_id,
+                        request.time_started,
+                    )
+                    .await;
+                let _ = request.result.send(result);
+            }
+        });
+
+        // Likewise, send the DNS zones of VPCs to sled agents as requested by
+        // the background task that keeps them up to date.
+        let vpc_dns_nexus = Arc::downgrade(&nexus);
+        let vpc_dns_opctx = OpContext::for_background(
+            nexus.log.new(o!("component" => "VpcDnsPropagator")),
+            Arc::clone(&authz),
+            authn::Context::internal_api(),
+            Arc::clone(&nexus.db_datastore),
+        );
+        tokio::spawn(async move {
+            while let Some(request) = vpc_dns_rx.recv().await {
+                let Some(nexus) = vpc_dns_nexus.upgrade() else {
+                    break;
+                };
+                let result = nexus
+                    .send_sled_agents_vpc_dns(&vpc_dns_opctx, request.vpc_id)
+                    .await;
+                let _ = request.result.send(result);
+            }
+        });
+
         // Kick all background tasks once the populate step finishes.  Among
         // other things, the populate step installs role assignments for
         // internal identities that are used by the background tasks.  If we
@@ -92,7 +92,8 @@ impl super::Nexus {
             params.identity.clone(),
             params.ip,
         )?;
-        self.db_datastore
+        let interface = self
+            .db_datastore
             .instance_create_network_interface(
                 opctx,
                 &authz_subnet,
@@ -120,7 +121,10 @@ impl super::Nexus {
                     // Convert other errors into an appropriate client error
                     network_interface::InsertError::into_external(e)
                 }
-            })
+            })?;
+
+        self.vpc_dns_activate();
+        Ok(interface)
     }
 
     /// Lists network interfaces attached to the instance.
@@ -146,14 +150,19 @@ impl super::Nexus {
     ) -> UpdateResult<db::model::InstanceNetworkInterface> {
         let (.., authz_instance, authz_interface) =
             network_interface_lookup.lookup_for(authz::Action::Modify).await?;
-        self.db_datastore
+        let interface = self
+            .db_datastore
             .instance_update_network_interface(
                 opctx,
                 &authz_instance,
                 &authz_interface,
                 db::model::NetworkInterfaceUpdate::from(updates),
             )
-            .await
+            .await?;
+
+        // The update may have made this the instance's primary interface.
+        self.vpc_dns_activate();
+        Ok(interface)
     }
 
     /// Delete a network interface from the provided instance.
@@ -194,6 +203,9 @@ impl super::Nexus {
                     // Convert other errors into an appropriate client error
                     network_interface::DeleteError::into_external(e)
                 }
-            })
+            })?;
+
+        self.vpc_dns_activate();
+        Ok(())
     }
 }
@@ -13,6 +13,8 @@ use crate::db::lookup::LookupPath;
 use crate::external_api::params;
 use crate::external_api::shared;
 use anyhow::Context;
+use chrono::DateTime;
+use chrono::Utc;
 use nexus_db_model::Name;
 use nexus_db_queries::context::OpContext;
 use omicron_common::api::external::http_pagination::PaginatedBy;
@@ -20,6 +22,7 @@ use omicron_common::api::external::CreateResult;
 use omicron_common::api::external::DeleteResult;
 use omicron_common::api::external::Error;
 use omicron_common::api::external::InternalContext;
+use omicron_common::api::external::LabelSelector;
 use omicron_common::api::external::ListResultVec;
 use omicron_common::api::external::LookupResult;
 use omicron_common::api::external::NameOrId;
@@ -78,32 +81,61 @@ impl super::Nexus {
         &self,
         opctx: &OpContext,
         pagparams: &PaginatedBy<'_>,
+        label_selector: Option<&LabelSelector>,
     ) -> ListResultVec<db::model::Project> {
-        self.db_datastore.projects_list(opctx, pagparams).await
+        self.db_datastore.projects_list(opctx, pagparams, label_selector).await
     }
 
+    /// Updates a project
+    ///
+    /// If `expected_time_modified` is provided, the update fails with a 412
+    /// ("Precondition Failed") unless the project's modification time is one
+    /// of those given.
     pub async fn project_update(
         &self,
         opctx: &OpContext,
         project_lookup: &lookup::Project<'_>,
         new_params: &params::ProjectUpdate,
+        expected_time_modified: Option<&[DateTime<Utc>]>,
     ) -> UpdateResult<db::model::Project> {
         let (.., authz_project) =
             project_lookup.lookup_for(authz::Action::Modify).await?;
-        self.db_datastore
-            .project_update(opctx, &authz_project, new_params.clone().into())
-            .await
+        let db_project = self
+            .db_datastore
+            .project_update(
+                opctx,
+                &authz_project,
+                new_params.clone().into(),
+                expected_time_modified,
+            )
+            .await?;
+
+        if new_params.identity.name.is_some() {
+            self.vpc_dns_activate();
+        }
+        Ok(db_project)
     }
 
+    /// Deletes a project
+    ///
+    /// If `expected_time_modified` is provided, the delete fails with a 412
+    /// ("Precondition Failed") unless the project's modification time is one
+    /// of those given.
     pub async fn project_delete(
         &self,
         opctx: &OpContext,
         project_lookup: &lookup::Project<'_>,
+        expected_time_modified: Option<&[DateTime<Utc>]>,
     ) -> DeleteResult {
         let (.., authz_project, db_project) =
             project_lookup.fetch_for(authz::Action::Delete).await?;
         self.db_datastore
-            .project_delete(opctx, &authz_project, &db_project)
+            .project_delete(
+                opctx,
+                &authz_project,
+                &db_project,
+                expected_time_modified,
+            )
             .await
     }
 
@@ -150,6 +182,34 @@ impl super::Nexus {
         Ok(shared::Policy { role_assignments })
     }
 
+    pub async fn project_quota_view(
+        &self,
+        opctx: &OpContext,
+        project_lookup: &lookup::Project<'_>,
+    ) -> LookupResult<db::model::VirtualProvisioningQuota> {
+        let (.., authz_project) =
+            project_lookup.lookup_for(authz::Action::Read).await?;
+        self.db_datastore.project_quota_view(opctx, &authz_project).await
+    }
+
+    pub async fn project_quota_update(
+        &self,
+        opctx: &OpContext,
+        project_lookup: &lookup::Project<'_>,
+        updates: &params::QuotaUpdate,
+    ) -> UpdateResult<db::model::VirtualProvisioningQuota> {
+        let (authz_silo, authz_project) =
+            project_lookup.lookup_for(authz::Action::Read).await?;
+        self.db_datastore
+            .project_quota_update(
+                opctx,
+                &authz_silo,
+                &authz_project,
+                updates.clone(),
+            )
+            .await
+    }
+
     pub async fn project_ip_pools_list(
         &self,
         opctx: &OpContext,
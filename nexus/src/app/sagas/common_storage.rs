@@ -289,33 +289,52 @@ pub async fn call_pantry_attach_for_disk(
     disk_id: Uuid,
     pantry_address: SocketAddrV6,
 ) -> Result<(), ActionError> {
-    let endpoint = format!("http://{}", pantry_address);
-
     let (.., disk) = LookupPath::new(opctx, &nexus.datastore())
         .disk_id(disk_id)
         .fetch_for(authz::Action::Modify)
         .await
         .map_err(ActionError::action_failed)?;
 
-    let disk_volume = nexus
+    call_pantry_attach_for_volume(
+        log,
+        nexus,
+        disk_id,
+        disk.volume_id,
+        pantry_address,
+    )
+    .await
+}
+
+/// Attach `volume_id` to the Pantry at `pantry_address`, where it can then be
+/// referred to as `attach_id`.
+pub async fn call_pantry_attach_for_volume(
+    log: &slog::Logger,
+    nexus: &Arc<Nexus>,
+    attach_id: Uuid,
+    volume_id: Uuid,
+    pantry_address: SocketAddrV6,
+) -> Result<(), ActionError> {
+    let endpoint = format!("http://{}", pantry_address);
+
+    let volume = nexus
         .datastore()
-        .volume_checkout(disk.volume_id)
+        .volume_checkout(volume_id)
         .await
         .map_err(ActionError::action_failed)?;
 
     info!(
         log,
-        "sending attach for disk {} volume {} to endpoint {}",
-        disk_id,
-        disk.volume_id,
+        "sending attach for {} volume {} to endpoint {}",
+        attach_id,
+        volume_id,
         endpoint,
     );
 
     let volume_construction_request: crucible_pantry_client::types::VolumeConstructionRequest =
-        serde_json::from_str(&disk_volume.data()).map_err(|e| {
+        serde_json::from_str(&volume.data()).map_err(|e| {
             ActionError::action_failed(Error::internal_error(&format!(
-                "failed to deserialize disk {} volume data: {}",
-                disk.id(),
+                "failed to deserialize volume {} data: {}",
+                volume_id,
                 e,
             )))
         })?;
@@ -327,7 +346,7 @@ pub async fn call_pantry_attach_for_disk(
     };
 
     retry_until_known_result(log, || async {
-        client.attach(&disk_id.to_string(), &attach_request).await
+        client.attach(&attach_id.to_string(), &attach_request).await
     })
     .await
     .map_err(|e| {
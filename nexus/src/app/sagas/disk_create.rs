@@ -12,12 +12,16 @@ use super::{
     ACTION_GENERATE_ID,
 };
 use crate::app::sagas::declare_saga_actions;
+use crate::app::sagas::snapshot_create;
+use crate::app::sagas::volume_delete;
 use crate::db::identity::{Asset, Resource};
 use crate::db::lookup::LookupPath;
 use crate::external_api::params;
 use crate::{authn, authz, db};
 use nexus_db_queries::db::datastore::RegionAllocationStrategy;
+use omicron_common::api::external;
 use omicron_common::api::external::Error;
+use omicron_common::api::external::LookupType;
 use rand::{rngs::StdRng, RngCore, SeedableRng};
 use serde::Deserialize;
 use serde::Serialize;
@@ -33,10 +37,25 @@ use uuid::Uuid;
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Params {
     pub serialized_authn: authn::saga::Serialized,
+    pub silo_id: Uuid,
     pub project_id: Uuid,
     pub create_params: params::DiskCreate,
 }
 
+/// Name of the node holding the parameters of the snapshot create subsaga run
+/// when copying another disk
+const CLONE_SNAPSHOT_PARAMS: &str = "params_for_clone_snapshot_subsaga";
+
+/// Returns the silo in which the disk is being created, which determines the
+/// images that may be used as its source
+fn disk_authz_silo(params: &Params) -> authz::Silo {
+    authz::Silo::new(
+        authz::FLEET,
+        params.silo_id,
+        LookupType::ById(params.silo_id),
+    )
+}
+
 // disk create saga: actions
 
 declare_saga_actions! {
@@ -71,6 +90,18 @@ declare_saga_actions! {
         + sdc_call_pantry_attach_for_disk
         - sdc_call_pantry_attach_for_disk_undo
     }
+    GET_CLONE_SNAPSHOT -> "clone_snapshot" {
+        + sdc_get_clone_snapshot
+    }
+    DELETE_CLONE_SNAPSHOT_RECORD -> "delete_clone_snapshot_record" {
+        + sdc_delete_clone_snapshot_record
+    }
+    CLONE_VOLUME_PARAMS -> "clone_volume_params" {
+        + sdc_clone_volume_params
+    }
+    CLONE_DESTINATION_VOLUME_PARAMS -> "clone_destination_volume_params" {
+        + sdc_clone_destination_volume_params
+    }
 }
 
 // disk create saga: definition
@@ -101,11 +132,105 @@ impl NexusSaga for SagaDiskCreate {
             ACTION_GENERATE_ID.as_ref(),
         ));
 
+        // Copying a disk is done by taking a snapshot of it through the
+        // Pantry, creating the new disk from that snapshot, and then deleting
+        // the snapshot again before the new disk is finalized.  The snapshot
+        // is transient, so it isn't listed in the project or charged to it.
+        let source_disk_id = match &params.create_params.disk_source {
+            params::DiskSource::Disk { disk_id } => Some(*disk_id),
+            _ => None,
+        };
+
+        if let Some(source_disk_id) = source_disk_id {
+            let snapshot_name =
+                external::Name::try_from(format!("clone-{}", Uuid::new_v4()))
+                    .map_err(SagaInitError::InvalidParameter)?;
+
+            let subsaga_params = snapshot_create::Params {
+                serialized_authn: params.serialized_authn.clone(),
+                silo_id: params.silo_id,
+                project_id: params.project_id,
+                disk_id: source_disk_id,
+                use_the_pantry: true,
+                create_params: params::SnapshotCreate {
+                    identity: external::IdentityMetadataCreateParams {
+                        name: snapshot_name,
+                        description: format!(
+                            "transient snapshot for copying disk {}",
+                            source_disk_id
+                        ),
+                    },
+                    disk: source_disk_id.into(),
+                    labels: Default::default(),
+                },
+                snapshot_schedule_id: None,
+                disk_in_maintenance: false,
+                transient: true,
+            };
+
+            let subsaga_dag = {
+                let subsaga_builder =
+                    steno::DagBuilder::new(steno::SagaName::new(
+                        snapshot_create::SagaSnapshotCreate::NAME,
+                    ));
+                snapshot_create::SagaSnapshotCreate::make_saga_dag(
+                    &subsaga_params,
+                    subsaga_builder,
+                )?
+            };
+
+            builder.append(Node::constant(
+                CLONE_SNAPSHOT_PARAMS,
+                serde_json::to_value(&subsaga_params).map_err(|e| {
+                    SagaInitError::SerializeError(
+                        CLONE_SNAPSHOT_PARAMS.to_string(),
+                        e,
+                    )
+                })?,
+            ));
+
+            builder.append(Node::subsaga(
+                "clone_snapshot_subsaga_no_result",
+                subsaga_dag,
+                CLONE_SNAPSHOT_PARAMS,
+            ));
+
+            builder.append(get_clone_snapshot_action());
+        }
+
         builder.append(create_disk_record_action());
         builder.append(regions_alloc_action());
         builder.append(space_account_action());
         builder.append(regions_ensure_action());
         builder.append(create_volume_record_action());
+
+        if source_disk_id.is_some() {
+            // The new disk's volume holds its own references to the
+            // snapshot's read-only regions, so the snapshot itself can go.
+            builder.append(delete_clone_snapshot_record_action());
+
+            let make_volume_delete_dag = || {
+                let subsaga_builder = steno::DagBuilder::new(
+                    steno::SagaName::new(volume_delete::SagaVolumeDelete::NAME),
+                );
+                volume_delete::create_dag(subsaga_builder)
+            };
+
+            builder.append(clone_volume_params_action());
+            builder.append(Node::subsaga(
+                "clone_snapshot_delete_volume_no_result",
+                make_volume_delete_dag()?,
+                "clone_volume_params",
+            ));
+
+            builder.append(clone_destination_volume_params_action());
+            builder.append(Node::subsaga(
+                "clone_snapshot_delete_destination_volume_no_result",
+                make_volume_delete_dag()?,
+                "clone_destination_volume_params",
+            ));
+        }
+
         builder.append(finalize_disk_record_action());
 
         match &params.create_params.disk_source {
@@ -163,16 +288,16 @@ async fn sdc_create_disk_record(
                 db_snapshot.block_size
             }
             params::DiskSource::Image { image_id } => {
-                let (.., image) =
-                    LookupPath::new(&opctx, &osagactx.datastore())
-                        .image_id(*image_id)
-                        .fetch()
-                        .await
-                        .map_err(|e| {
-                            ActionError::action_failed(Error::internal_error(
-                                &e.to_string(),
-                            ))
-                        })?;
+                let authz_silo = disk_authz_silo(&params);
+                let image = osagactx
+                    .nexus()
+                    .image_fetch_for_silo(&opctx, &authz_silo, *image_id)
+                    .await
+                    .map_err(|e| {
+                        ActionError::action_failed(Error::internal_error(
+                            &e.to_string(),
+                        ))
+                    })?;
 
                 image.block_size
             }
@@ -183,6 +308,12 @@ async fn sdc_create_disk_record(
                     ))
                 })?
             }
+            params::DiskSource::Disk { .. } => {
+                let db_snapshot =
+                    sagactx.lookup::<db::model::Snapshot>("clone_snapshot")?;
+
+                db_snapshot.block_size
+            }
         };
 
     let disk = db::model::Disk::new(
@@ -338,10 +469,6 @@ async fn sdc_regions_ensure(
     )
     .await?;
 
-    let block_size = datasets_and_regions[0].1.block_size;
-    let blocks_per_extent = datasets_and_regions[0].1.extent_size;
-    let extent_count = datasets_and_regions[0].1.extent_count;
-
     // If a disk source was requested, set the read-only parent of this disk.
     let osagactx = sagactx.user_data();
     let params = sagactx.saga_params::<Params>()?;
@@ -397,12 +524,12 @@ async fn sdc_regions_ensure(
             params::DiskSource::Image { image_id } => {
                 debug!(log, "grabbing image {}", image_id);
 
-                let (.., image) =
-                    LookupPath::new(&opctx, &osagactx.datastore())
-                        .image_id(*image_id)
-                        .fetch()
-                        .await
-                        .map_err(ActionError::action_failed)?;
+                let authz_silo = disk_authz_silo(&params);
+                let image = osagactx
+                    .nexus()
+                    .image_fetch_for_silo(&opctx, &authz_silo, *image_id)
+                    .await
+                    .map_err(ActionError::action_failed)?;
 
                 debug!(log, "retrieved project image {}", image.id());
 
@@ -438,6 +565,42 @@ async fn sdc_regions_ensure(
                 )?))
             }
             params::DiskSource::ImportingBlocks { block_size: _ } => None,
+            params::DiskSource::Disk { disk_id } => {
+                let db_snapshot =
+                    sagactx.lookup::<db::model::Snapshot>("clone_snapshot")?;
+
+                debug!(
+                    log,
+                    "grabbing snapshot {} of disk {} volume {}",
+                    db_snapshot.id(),
+                    disk_id,
+                    db_snapshot.volume_id,
+                );
+
+                let volume = osagactx
+                    .datastore()
+                    .volume_checkout(db_snapshot.volume_id)
+                    .await
+                    .map_err(ActionError::action_failed)?;
+
+                debug!(
+                    log,
+                    "grabbed volume {}, with data {}",
+                    volume.id(),
+                    volume.data()
+                );
+
+                Some(Box::new(serde_json::from_str(volume.data()).map_err(
+                    |e| {
+                        ActionError::action_failed(Error::internal_error(
+                            &format!(
+                                "failed to deserialize volume data: {}",
+                                e,
+                            ),
+                        ))
+                    },
+                )?))
+            }
         };
 
     // Each ID should be unique to this disk
@@ -454,55 +617,11 @@ async fn sdc_regions_ensure(
     }
 
     // Create volume construction request for this disk
-    let mut rng = StdRng::from_entropy();
-    let volume_construction_request = VolumeConstructionRequest::Volume {
-        id: disk_id,
-        block_size,
-        sub_volumes: vec![VolumeConstructionRequest::Region {
-            block_size,
-            blocks_per_extent,
-            extent_count: extent_count.try_into().unwrap(),
-            gen: 1,
-            opts: CrucibleOpts {
-                id: disk_id,
-                target: datasets_and_regions
-                    .iter()
-                    .map(|(dataset, region)| {
-                        dataset
-                            .address_with_port(region.port_number)
-                            .to_string()
-                    })
-                    .collect(),
-
-                lossy: false,
-                flush_timeout: None,
-
-                // all downstairs will expect encrypted blocks
-                key: Some(base64::Engine::encode(
-                    &base64::engine::general_purpose::STANDARD,
-                    {
-                        // TODO the current encryption key
-                        // requirement is 32 bytes, what if that
-                        // changes?
-                        let mut random_bytes: [u8; 32] = [0; 32];
-                        rng.fill_bytes(&mut random_bytes);
-                        random_bytes
-                    },
-                )),
-
-                // TODO TLS, which requires sending X509 stuff during
-                // downstairs region allocation too.
-                cert_pem: None,
-                key_pem: None,
-                root_cert_pem: None,
-
-                control: None,
-
-                read_only: false,
-            },
-        }],
+    let volume_construction_request = disk_volume_construction_request(
+        disk_id,
+        &datasets_and_regions,
         read_only_parent,
-    };
+    );
 
     let volume_data = serde_json::to_string(&volume_construction_request)
         .map_err(|e| {
@@ -693,10 +812,153 @@ async fn sdc_call_pantry_attach_for_disk_undo(
     Ok(())
 }
 
+async fn sdc_get_clone_snapshot(
+    sagactx: NexusActionContext,
+) -> Result<db::model::Snapshot, ActionError> {
+    let osagactx = sagactx.user_data();
+    let params = sagactx.saga_params::<Params>()?;
+    let opctx = crate::context::op_context_for_saga_action(
+        &sagactx,
+        &params.serialized_authn,
+    );
+    let subsaga_params =
+        sagactx.lookup::<snapshot_create::Params>(CLONE_SNAPSHOT_PARAMS)?;
+
+    let (.., db_snapshot) = LookupPath::new(&opctx, &osagactx.datastore())
+        .project_id(params.project_id)
+        .snapshot_name(&db::model::Name(
+            subsaga_params.create_params.identity.name,
+        ))
+        .fetch()
+        .await
+        .map_err(ActionError::action_failed)?;
+
+    Ok(db_snapshot)
+}
+
+async fn sdc_delete_clone_snapshot_record(
+    sagactx: NexusActionContext,
+) -> Result<(), ActionError> {
+    let osagactx = sagactx.user_data();
+    let params = sagactx.saga_params::<Params>()?;
+    let opctx = crate::context::op_context_for_saga_action(
+        &sagactx,
+        &params.serialized_authn,
+    );
+    let db_snapshot =
+        sagactx.lookup::<db::model::Snapshot>("clone_snapshot")?;
+
+    let (.., authz_project) = LookupPath::new(&opctx, &osagactx.datastore())
+        .project_id(params.project_id)
+        .lookup_for(authz::Action::CreateChild)
+        .await
+        .map_err(ActionError::action_failed)?;
+    let authz_snapshot = authz::Snapshot::new(
+        authz_project,
+        db_snapshot.id(),
+        LookupType::ById(db_snapshot.id()),
+    );
+
+    osagactx
+        .datastore()
+        .project_delete_snapshot(&opctx, &authz_snapshot, &db_snapshot)
+        .await
+        .map_err(ActionError::action_failed)?;
+    Ok(())
+}
+
+async fn sdc_clone_volume_params(
+    sagactx: NexusActionContext,
+) -> Result<volume_delete::Params, ActionError> {
+    let params = sagactx.saga_params::<Params>()?;
+    let db_snapshot =
+        sagactx.lookup::<db::model::Snapshot>("clone_snapshot")?;
+
+    Ok(volume_delete::Params {
+        serialized_authn: params.serialized_authn,
+        volume_id: db_snapshot.volume_id,
+    })
+}
+
+async fn sdc_clone_destination_volume_params(
+    sagactx: NexusActionContext,
+) -> Result<volume_delete::Params, ActionError> {
+    let params = sagactx.saga_params::<Params>()?;
+    let db_snapshot =
+        sagactx.lookup::<db::model::Snapshot>("clone_snapshot")?;
+
+    Ok(volume_delete::Params {
+        serialized_authn: params.serialized_authn,
+        volume_id: db_snapshot.destination_volume_id,
+    })
+}
+
 // helper functions
 
+/// Build the volume construction request for a disk backed by the given
+/// regions, optionally layered on top of a read-only parent
+pub(super) fn disk_volume_construction_request(
+    disk_id: Uuid,
+    datasets_and_regions: &[(db::model::Dataset, db::model::Region)],
+    read_only_parent: Option<Box<VolumeConstructionRequest>>,
+) -> VolumeConstructionRequest {
+    let block_size = datasets_and_regions[0].1.block_size;
+    let blocks_per_extent = datasets_and_regions[0].1.extent_size;
+    let extent_count = datasets_and_regions[0].1.extent_count;
+
+    let mut rng = StdRng::from_entropy();
+    VolumeConstructionRequest::Volume {
+        id: disk_id,
+        block_size,
+        sub_volumes: vec![VolumeConstructionRequest::Region {
+            block_size,
+            blocks_per_extent,
+            extent_count: extent_count.try_into().unwrap(),
+            gen: 1,
+            opts: CrucibleOpts {
+                id: disk_id,
+                target: datasets_and_regions
+                    .iter()
+                    .map(|(dataset, region)| {
+                        dataset
+                            .address_with_port(region.port_number)
+                            .to_string()
+                    })
+                    .collect(),
+
+                lossy: false,
+                flush_timeout: None,
+
+                // all downstairs will expect encrypted blocks
+                key: Some(base64::Engine::encode(
+                    &base64::engine::general_purpose::STANDARD,
+                    {
+                        // TODO the current encryption key
+                        // requirement is 32 bytes, what if that
+                        // changes?
+                        let mut random_bytes: [u8; 32] = [0; 32];
+                        rng.fill_bytes(&mut random_bytes);
+                        random_bytes
+                    },
+                )),
+
+                // TODO TLS, which requires sending X509 stuff during
+                // downstairs region allocation too.
+                cert_pem: None,
+                key_pem: None,
+                root_cert_pem: None,
+
+                control: None,
+
+                read_only: false,
+            },
+        }],
+        read_only_parent,
+    }
+}
+
 /// Generate new IDs for each layer
-fn randomize_volume_construction_request_ids(
+pub(super) fn randomize_volume_construction_request_ids(
     input: &VolumeConstructionRequest,
 ) -> anyhow::Result<VolumeConstructionRequest> {
     match input {
@@ -807,6 +1069,7 @@ pub(crate) mod test {
                 block_size: params::BlockSize(512),
             },
             size: ByteCount::from_gibibytes_u32(1),
+            labels: Default::default(),
         }
     }
 
@@ -814,6 +1077,7 @@ pub(crate) mod test {
     fn new_test_params(opctx: &OpContext, project_id: Uuid) -> Params {
         Params {
             serialized_authn: Serialized::for_opctx(opctx),
+            silo_id: opctx.authn.silo_required().unwrap().id(),
             project_id,
             create_params: new_disk_create_params(),
         }
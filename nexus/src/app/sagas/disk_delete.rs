@@ -149,7 +149,7 @@ async fn sdd_account_space_undo(
     );
     osagactx
         .datastore()
-        .virtual_provisioning_collection_insert_disk(
+        .virtual_provisioning_collection_restore_disk(
             &opctx,
             deleted_disk.id(),
             params.project_id,
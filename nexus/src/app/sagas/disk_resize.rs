@@ -0,0 +1,749 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Grow a detached disk in place
+//!
+//! The Crucible agent can only create regions of a fixed size, so the regions
+//! backing a disk can't be grown where they are.  Instead, the disk keeps its
+//! identity, but the volume backing it is replaced with a larger one whose
+//! read-only parent holds the disk's current contents:
+//!
+//! 1. Move the disk from "detached" to "maintenance", so that it cannot be
+//!    attached to an instance, or written to, while its volume is being
+//!    replaced.
+//! 2. Take a transient snapshot of the disk through the Pantry, with the
+//!    snapshot create saga, which leaves the disk in "maintenance".  The
+//!    snapshot isn't listed in the project or charged to it.
+//! 3. Allocate regions of the new size, have the Crucible agents create them,
+//!    and create a volume record whose read-only parent is the snapshot's
+//!    volume.  Each of these steps is undone if a later one fails, and none of
+//!    them touch the disk or its volume.
+//! 4. In one transaction, point the disk at the new volume, record its new
+//!    size, and charge the difference to its project.
+//! 5. Delete the snapshot, whose read-only regions the new volume holds its
+//!    own references to, and move the disk back to "detached".
+//! 6. Release the old volume with the volume delete saga.
+//!
+//! Disks attached to an instance can't be resized: a running instance's
+//! Crucible Upstairs can't be switched to a new volume, and the guest has no
+//! way to find out about the new size.
+
+use super::{
+    common_storage::{
+        delete_crucible_regions, ensure_all_datasets_and_regions,
+    },
+    disk_create::{
+        disk_volume_construction_request,
+        randomize_volume_construction_request_ids,
+    },
+    snapshot_create, volume_delete, ActionRegistry, NexusActionContext,
+    NexusSaga, SagaInitError, ACTION_GENERATE_ID,
+};
+use crate::app::sagas::declare_saga_actions;
+use crate::db::identity::{Asset, Resource};
+use crate::db::lookup::LookupPath;
+use crate::external_api::params;
+use crate::{authn, authz, db};
+use nexus_db_model::Generation;
+use nexus_db_queries::db::datastore::RegionAllocationStrategy;
+use omicron_common::api::external;
+use omicron_common::api::external::Error;
+use omicron_common::api::external::LookupType;
+use serde::Deserialize;
+use serde::Serialize;
+use sled_agent_client::types::VolumeConstructionRequest;
+use steno::ActionError;
+use steno::Node;
+use uuid::Uuid;
+
+// disk resize saga: input parameters
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Params {
+    pub serialized_authn: authn::saga::Serialized,
+    pub silo_id: Uuid,
+    pub project_id: Uuid,
+    pub disk_id: Uuid,
+    /// the volume backing the disk when the saga was created
+    pub old_volume_id: Uuid,
+    /// the size of the disk when the saga was created
+    pub old_size: external::ByteCount,
+    pub new_size: external::ByteCount,
+}
+
+/// Name of the node holding the parameters of the snapshot create subsaga
+const RESIZE_SNAPSHOT_PARAMS: &str = "params_for_resize_snapshot_subsaga";
+
+// disk resize saga: actions
+
+declare_saga_actions! {
+    disk_resize;
+    SET_DISK_MAINTENANCE -> "maintenance_gen" {
+        + sdrs_set_disk_maintenance
+        - sdrs_set_disk_maintenance_undo
+    }
+    GET_RESIZE_SNAPSHOT -> "resize_snapshot" {
+        + sdrs_get_resize_snapshot
+    }
+    REGIONS_ALLOC -> "datasets_and_regions" {
+        + sdrs_alloc_regions
+        - sdrs_alloc_regions_undo
+    }
+    REGIONS_ENSURE -> "regions_ensure" {
+        + sdrs_regions_ensure
+        - sdrs_regions_ensure_undo
+    }
+    CREATE_VOLUME_RECORD -> "created_volume" {
+        + sdrs_create_volume_record
+        - sdrs_create_volume_record_undo
+    }
+    RESIZE_DISK_RECORD -> "no_result1" {
+        + sdrs_resize_disk_record
+        - sdrs_resize_disk_record_undo
+    }
+    DELETE_RESIZE_SNAPSHOT_RECORD -> "no_result2" {
+        + sdrs_delete_resize_snapshot_record
+    }
+    SNAPSHOT_VOLUME_PARAMS -> "snapshot_volume_params" {
+        + sdrs_snapshot_volume_params
+    }
+    DESTINATION_VOLUME_PARAMS -> "destination_volume_params" {
+        + sdrs_destination_volume_params
+    }
+    SET_DISK_DETACHED -> "no_result3" {
+        + sdrs_set_disk_detached
+    }
+}
+
+// disk resize saga: definition
+
+#[derive(Debug)]
+pub struct SagaDiskResize;
+impl NexusSaga for SagaDiskResize {
+    const NAME: &'static str = "disk-resize";
+    type Params = Params;
+
+    fn register_actions(registry: &mut ActionRegistry) {
+        disk_resize_register_actions(registry);
+    }
+
+    fn make_saga_dag(
+        params: &Self::Params,
+        mut builder: steno::DagBuilder,
+    ) -> Result<steno::Dag, SagaInitError> {
+        builder.append(Node::action(
+            "new_volume_id",
+            "GenerateVolumeId",
+            ACTION_GENERATE_ID.as_ref(),
+        ));
+
+        // Keep anything from attaching to the disk before its contents are
+        // snapshotted, until it's backed by the new volume.
+        builder.append(set_disk_maintenance_action());
+
+        // Take the snapshot holding the disk's current contents.
+        let snapshot_name =
+            external::Name::try_from(format!("resize-{}", Uuid::new_v4()))
+                .map_err(SagaInitError::InvalidParameter)?;
+
+        let snapshot_params = snapshot_create::Params {
+            serialized_authn: params.serialized_authn.clone(),
+            silo_id: params.silo_id,
+            project_id: params.project_id,
+            disk_id: params.disk_id,
+            use_the_pantry: true,
+            create_params: params::SnapshotCreate {
+                identity: external::IdentityMetadataCreateParams {
+                    name: snapshot_name,
+                    description: format!(
+                        "transient snapshot for resizing disk {}",
+                        params.disk_id
+                    ),
+                },
+                disk: params.disk_id.into(),
+                labels: Default::default(),
+            },
+            snapshot_schedule_id: None,
+            disk_in_maintenance: true,
+            transient: true,
+        };
+
+        let snapshot_dag = {
+            let subsaga_builder = steno::DagBuilder::new(steno::SagaName::new(
+                snapshot_create::SagaSnapshotCreate::NAME,
+            ));
+            snapshot_create::SagaSnapshotCreate::make_saga_dag(
+                &snapshot_params,
+                subsaga_builder,
+            )?
+        };
+
+        builder.append(Node::constant(
+            RESIZE_SNAPSHOT_PARAMS,
+            serde_json::to_value(&snapshot_params).map_err(|e| {
+                SagaInitError::SerializeError(
+                    RESIZE_SNAPSHOT_PARAMS.to_string(),
+                    e,
+                )
+            })?,
+        ));
+
+        builder.append(Node::subsaga(
+            "resize_snapshot_subsaga_no_result",
+            snapshot_dag,
+            RESIZE_SNAPSHOT_PARAMS,
+        ));
+
+        builder.append(get_resize_snapshot_action());
+        builder.append(regions_alloc_action());
+        builder.append(regions_ensure_action());
+        builder.append(create_volume_record_action());
+        builder.append(resize_disk_record_action());
+
+        // The new volume holds its own references to the snapshot's read-only
+        // regions, so the snapshot itself can go.
+        builder.append(delete_resize_snapshot_record_action());
+
+        let make_volume_delete_dag = || {
+            let subsaga_builder = steno::DagBuilder::new(steno::SagaName::new(
+                volume_delete::SagaVolumeDelete::NAME,
+            ));
+            volume_delete::create_dag(subsaga_builder)
+        };
+
+        builder.append(snapshot_volume_params_action());
+        builder.append(Node::subsaga(
+            "resize_snapshot_delete_volume_no_result",
+            make_volume_delete_dag()?,
+            "snapshot_volume_params",
+        ));
+
+        builder.append(destination_volume_params_action());
+        builder.append(Node::subsaga(
+            "resize_snapshot_delete_destination_volume_no_result",
+            make_volume_delete_dag()?,
+            "destination_volume_params",
+        ));
+
+        builder.append(set_disk_detached_action());
+
+        // Once the disk no longer refers to it, release the old volume.
+        let volume_delete_params = volume_delete::Params {
+            serialized_authn: params.serialized_authn.clone(),
+            volume_id: params.old_volume_id,
+        };
+
+        builder.append(Node::constant(
+            "params_for_volume_delete_subsaga",
+            serde_json::to_value(&volume_delete_params).map_err(|e| {
+                SagaInitError::SerializeError(
+                    "params_for_volume_delete_subsaga".to_string(),
+                    e,
+                )
+            })?,
+        ));
+
+        builder.append(Node::subsaga(
+            "volume_delete_subsaga_no_result",
+            make_volume_delete_dag()?,
+            "params_for_volume_delete_subsaga",
+        ));
+
+        Ok(builder.build()?)
+    }
+}
+
+// disk resize saga: action implementations
+
+async fn sdrs_set_disk_maintenance(
+    sagactx: NexusActionContext,
+) -> Result<Generation, ActionError> {
+    let log = sagactx.user_data().log();
+    let osagactx = sagactx.user_data();
+    let params = sagactx.saga_params::<Params>()?;
+    let opctx = crate::context::op_context_for_saga_action(
+        &sagactx,
+        &params.serialized_authn,
+    );
+
+    let (.., authz_disk, db_disk) =
+        LookupPath::new(&opctx, &osagactx.datastore())
+            .disk_id(params.disk_id)
+            .fetch_for(authz::Action::Modify)
+            .await
+            .map_err(ActionError::action_failed)?;
+
+    // The disk may have been attached to an instance since the saga was
+    // created.  If it was, bail out.  If it's attached after this lookup, the
+    // update below fails because the generation number is too low.
+    match db_disk.state().into() {
+        external::DiskState::Detached => {
+            info!(log, "setting state of {} to maintenance", params.disk_id);
+
+            osagactx
+                .datastore()
+                .disk_update_runtime(
+                    &opctx,
+                    &authz_disk,
+                    &db_disk.runtime().maintenance(),
+                )
+                .await
+                .map_err(ActionError::action_failed)?;
+        }
+
+        _ => {
+            return Err(ActionError::action_failed(Error::invalid_request(
+                &format!(
+                    "disk cannot be resized in state {:?}",
+                    db_disk.state()
+                ),
+            )));
+        }
+    }
+
+    // Record the disk's new generation number so that we only move the disk
+    // out of maintenance if nothing else has changed it since.
+    let (.., db_disk) = LookupPath::new(&opctx, &osagactx.datastore())
+        .disk_id(params.disk_id)
+        .fetch_for(authz::Action::Read)
+        .await
+        .map_err(ActionError::action_failed)?;
+
+    Ok(db_disk.runtime().gen)
+}
+
+async fn sdrs_set_disk_maintenance_undo(
+    sagactx: NexusActionContext,
+) -> Result<(), anyhow::Error> {
+    let log = sagactx.user_data().log();
+    let osagactx = sagactx.user_data();
+    let params = sagactx.saga_params::<Params>()?;
+    let opctx = crate::context::op_context_for_saga_action(
+        &sagactx,
+        &params.serialized_authn,
+    );
+
+    let (.., authz_disk, db_disk) =
+        LookupPath::new(&opctx, &osagactx.datastore())
+            .disk_id(params.disk_id)
+            .fetch_for(authz::Action::Modify)
+            .await
+            .map_err(ActionError::action_failed)?;
+
+    match db_disk.state().into() {
+        external::DiskState::Maintenance => {
+            info!(
+                log,
+                "undo: setting disk {} state from maintenance to detached",
+                params.disk_id
+            );
+
+            osagactx
+                .datastore()
+                .disk_update_runtime(
+                    &opctx,
+                    &authz_disk,
+                    &db_disk.runtime().detach(),
+                )
+                .await
+                .map_err(ActionError::action_failed)?;
+        }
+
+        external::DiskState::Detached => {
+            info!(
+                log,
+                "undo: disk {} already in state detached", params.disk_id
+            );
+        }
+
+        _ => {
+            warn!(
+                log,
+                "undo: disk {} is in state {:?}",
+                params.disk_id,
+                db_disk.state()
+            );
+        }
+    }
+
+    Ok(())
+}
+
+async fn sdrs_get_resize_snapshot(
+    sagactx: NexusActionContext,
+) -> Result<db::model::Snapshot, ActionError> {
+    let osagactx = sagactx.user_data();
+    let params = sagactx.saga_params::<Params>()?;
+    let opctx = crate::context::op_context_for_saga_action(
+        &sagactx,
+        &params.serialized_authn,
+    );
+    let snapshot_params =
+        sagactx.lookup::<snapshot_create::Params>(RESIZE_SNAPSHOT_PARAMS)?;
+
+    let (.., db_snapshot) = LookupPath::new(&opctx, &osagactx.datastore())
+        .project_id(params.project_id)
+        .snapshot_name(&db::model::Name(
+            snapshot_params.create_params.identity.name,
+        ))
+        .fetch()
+        .await
+        .map_err(ActionError::action_failed)?;
+
+    Ok(db_snapshot)
+}
+
+async fn sdrs_alloc_regions(
+    sagactx: NexusActionContext,
+) -> Result<Vec<(db::model::Dataset, db::model::Region)>, ActionError> {
+    let osagactx = sagactx.user_data();
+    let params = sagactx.saga_params::<Params>()?;
+    let new_volume_id = sagactx.lookup::<Uuid>("new_volume_id")?;
+    let opctx = crate::context::op_context_for_saga_action(
+        &sagactx,
+        &params.serialized_authn,
+    );
+
+    // The new regions are the new size of the disk, and take their block size
+    // from the disk.
+    let datasets_and_regions = osagactx
+        .datastore()
+        .region_allocate(
+            &opctx,
+            new_volume_id,
+            &params::DiskSource::Disk { disk_id: params.disk_id },
+            params.new_size,
+            &RegionAllocationStrategy::Random(None),
+        )
+        .await
+        .map_err(ActionError::action_failed)?;
+    Ok(datasets_and_regions)
+}
+
+async fn sdrs_alloc_regions_undo(
+    sagactx: NexusActionContext,
+) -> Result<(), anyhow::Error> {
+    let osagactx = sagactx.user_data();
+
+    let region_ids = sagactx
+        .lookup::<Vec<(db::model::Dataset, db::model::Region)>>(
+            "datasets_and_regions",
+        )?
+        .into_iter()
+        .map(|(_, region)| region.id())
+        .collect::<Vec<Uuid>>();
+
+    osagactx.datastore().regions_hard_delete(region_ids).await?;
+    Ok(())
+}
+
+/// Have the Crucible agents create the new regions, and build the new
+/// volume's construction request, with the snapshot's volume as its read-only
+/// parent.
+async fn sdrs_regions_ensure(
+    sagactx: NexusActionContext,
+) -> Result<String, ActionError> {
+    let log = sagactx.user_data().log();
+    let osagactx = sagactx.user_data();
+    let params = sagactx.saga_params::<Params>()?;
+
+    let datasets_and_regions = ensure_all_datasets_and_regions(
+        &log,
+        sagactx.lookup::<Vec<(db::model::Dataset, db::model::Region)>>(
+            "datasets_and_regions",
+        )?,
+    )
+    .await?;
+
+    let db_snapshot =
+        sagactx.lookup::<db::model::Snapshot>("resize_snapshot")?;
+
+    debug!(
+        log,
+        "grabbing snapshot {} of disk {} volume {}",
+        db_snapshot.id(),
+        params.disk_id,
+        db_snapshot.volume_id,
+    );
+
+    let volume = osagactx
+        .datastore()
+        .volume_checkout(db_snapshot.volume_id)
+        .await
+        .map_err(ActionError::action_failed)?;
+
+    let snapshot_vcr: VolumeConstructionRequest =
+        serde_json::from_str(volume.data()).map_err(|e| {
+            ActionError::action_failed(Error::internal_error(&format!(
+                "failed to deserialize volume data: {}",
+                e,
+            )))
+        })?;
+
+    // Each ID should be unique to this disk's new volume
+    let read_only_parent = randomize_volume_construction_request_ids(
+        &snapshot_vcr,
+    )
+    .map_err(|e| {
+        ActionError::action_failed(Error::internal_error(&format!(
+            "failed to randomize ids: {}",
+            e,
+        )))
+    })?;
+
+    let volume_construction_request = disk_volume_construction_request(
+        params.disk_id,
+        &datasets_and_regions,
+        Some(Box::new(read_only_parent)),
+    );
+
+    let volume_data = serde_json::to_string(&volume_construction_request)
+        .map_err(|e| {
+            ActionError::action_failed(Error::internal_error(&e.to_string()))
+        })?;
+
+    Ok(volume_data)
+}
+
+async fn sdrs_regions_ensure_undo(
+    sagactx: NexusActionContext,
+) -> Result<(), anyhow::Error> {
+    let log = sagactx.user_data().log();
+    warn!(log, "sdrs_regions_ensure_undo: Deleting crucible regions");
+    delete_crucible_regions(
+        sagactx.lookup::<Vec<(db::model::Dataset, db::model::Region)>>(
+            "datasets_and_regions",
+        )?,
+    )
+    .await?;
+    info!(log, "sdrs_regions_ensure_undo: Deleted crucible regions");
+    Ok(())
+}
+
+async fn sdrs_create_volume_record(
+    sagactx: NexusActionContext,
+) -> Result<db::model::Volume, ActionError> {
+    let osagactx = sagactx.user_data();
+
+    let new_volume_id = sagactx.lookup::<Uuid>("new_volume_id")?;
+    let volume_data = sagactx.lookup::<String>("regions_ensure")?;
+
+    let volume = db::model::Volume::new(new_volume_id, volume_data);
+
+    let volume_created = osagactx
+        .datastore()
+        .volume_create(volume)
+        .await
+        .map_err(ActionError::action_failed)?;
+
+    Ok(volume_created)
+}
+
+async fn sdrs_create_volume_record_undo(
+    sagactx: NexusActionContext,
+) -> Result<(), anyhow::Error> {
+    let osagactx = sagactx.user_data();
+    let params = sagactx.saga_params::<Params>()?;
+
+    let opctx = crate::context::op_context_for_saga_action(
+        &sagactx,
+        &params.serialized_authn,
+    );
+    let new_volume_id = sagactx.lookup::<Uuid>("new_volume_id")?;
+    osagactx.nexus().volume_delete(&opctx, new_volume_id).await?;
+    Ok(())
+}
+
+async fn sdrs_resize_disk_record(
+    sagactx: NexusActionContext,
+) -> Result<(), ActionError> {
+    let osagactx = sagactx.user_data();
+    let params = sagactx.saga_params::<Params>()?;
+    let opctx = crate::context::op_context_for_saga_action(
+        &sagactx,
+        &params.serialized_authn,
+    );
+    let new_volume_id = sagactx.lookup::<Uuid>("new_volume_id")?;
+
+    let (.., authz_disk) = LookupPath::new(&opctx, &osagactx.datastore())
+        .disk_id(params.disk_id)
+        .lookup_for(authz::Action::Modify)
+        .await
+        .map_err(ActionError::action_failed)?;
+
+    let resized = osagactx
+        .datastore()
+        .disk_resize(
+            &opctx,
+            &authz_disk,
+            params.old_volume_id,
+            new_volume_id,
+            params.new_size,
+        )
+        .await
+        .map_err(ActionError::action_failed)?;
+
+    if !resized {
+        return Err(ActionError::action_failed(Error::conflict(
+            "disk's volume changed while it was being resized",
+        )));
+    }
+
+    Ok(())
+}
+
+async fn sdrs_resize_disk_record_undo(
+    sagactx: NexusActionContext,
+) -> Result<(), anyhow::Error> {
+    let osagactx = sagactx.user_data();
+    let params = sagactx.saga_params::<Params>()?;
+    let opctx = crate::context::op_context_for_saga_action(
+        &sagactx,
+        &params.serialized_authn,
+    );
+    let new_volume_id = sagactx.lookup::<Uuid>("new_volume_id")?;
+
+    let (.., authz_disk) = LookupPath::new(&opctx, &osagactx.datastore())
+        .disk_id(params.disk_id)
+        .lookup_for(authz::Action::Modify)
+        .await
+        .map_err(ActionError::action_failed)?;
+
+    osagactx
+        .datastore()
+        .disk_resize(
+            &opctx,
+            &authz_disk,
+            new_volume_id,
+            params.old_volume_id,
+            params.old_size,
+        )
+        .await?;
+
+    Ok(())
+}
+
+async fn sdrs_delete_resize_snapshot_record(
+    sagactx: NexusActionContext,
+) -> Result<(), ActionError> {
+    let osagactx = sagactx.user_data();
+    let params = sagactx.saga_params::<Params>()?;
+    let opctx = crate::context::op_context_for_saga_action(
+        &sagactx,
+        &params.serialized_authn,
+    );
+    let db_snapshot =
+        sagactx.lookup::<db::model::Snapshot>("resize_snapshot")?;
+
+    let (.., authz_project) = LookupPath::new(&opctx, &osagactx.datastore())
+        .project_id(params.project_id)
+        .lookup_for(authz::Action::CreateChild)
+        .await
+        .map_err(ActionError::action_failed)?;
+    let authz_snapshot = authz::Snapshot::new(
+        authz_project,
+        db_snapshot.id(),
+        LookupType::ById(db_snapshot.id()),
+    );
+
+    osagactx
+        .datastore()
+        .project_delete_snapshot(&opctx, &authz_snapshot, &db_snapshot)
+        .await
+        .map_err(ActionError::action_failed)?;
+    Ok(())
+}
+
+async fn sdrs_snapshot_volume_params(
+    sagactx: NexusActionContext,
+) -> Result<volume_delete::Params, ActionError> {
+    let params = sagactx.saga_params::<Params>()?;
+    let db_snapshot =
+        sagactx.lookup::<db::model::Snapshot>("resize_snapshot")?;
+
+    Ok(volume_delete::Params {
+        serialized_authn: params.serialized_authn,
+        volume_id: db_snapshot.volume_id,
+    })
+}
+
+async fn sdrs_destination_volume_params(
+    sagactx: NexusActionContext,
+) -> Result<volume_delete::Params, ActionError> {
+    let params = sagactx.saga_params::<Params>()?;
+    let db_snapshot =
+        sagactx.lookup::<db::model::Snapshot>("resize_snapshot")?;
+
+    Ok(volume_delete::Params {
+        serialized_authn: params.serialized_authn,
+        volume_id: db_snapshot.destination_volume_id,
+    })
+}
+
+async fn sdrs_set_disk_detached(
+    sagactx: NexusActionContext,
+) -> Result<(), ActionError> {
+    let log = sagactx.user_data().log();
+    let osagactx = sagactx.user_data();
+    let params = sagactx.saga_params::<Params>()?;
+    let opctx = crate::context::op_context_for_saga_action(
+        &sagactx,
+        &params.serialized_authn,
+    );
+    let maintenance_gen = sagactx.lookup::<Generation>("maintenance_gen")?;
+
+    let (.., authz_disk, db_disk) =
+        LookupPath::new(&opctx, &osagactx.datastore())
+            .disk_id(params.disk_id)
+            .fetch_for(authz::Action::Modify)
+            .await
+            .map_err(ActionError::action_failed)?;
+
+    // Only move the disk out of maintenance if this saga put it there.
+    match db_disk.state().into() {
+        external::DiskState::Maintenance => {
+            if db_disk.runtime().gen == maintenance_gen {
+                info!(
+                    log,
+                    "setting disk {} state from maintenance to detached",
+                    params.disk_id
+                );
+
+                osagactx
+                    .datastore()
+                    .disk_update_runtime(
+                        &opctx,
+                        &authz_disk,
+                        &db_disk.runtime().detach(),
+                    )
+                    .await
+                    .map_err(ActionError::action_failed)?;
+            } else {
+                info!(
+                    log,
+                    "disk {} has generation number {:?}, which doesn't match \
+                    the expected {:?}: skip setting to detach",
+                    params.disk_id,
+                    db_disk.runtime().gen,
+                    maintenance_gen,
+                );
+            }
+        }
+
+        external::DiskState::Detached => {
+            info!(log, "disk {} already in state detached", params.disk_id);
+        }
+
+        _ => {
+            warn!(
+                log,
+                "disk {} is in state {:?}",
+                params.disk_id,
+                db_disk.state()
+            );
+        }
+    }
+
+    Ok(())
+}
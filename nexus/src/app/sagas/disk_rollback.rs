@@ -0,0 +1,566 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Restore a disk in place from one of its project's snapshots
+//!
+//! The disk keeps its identity, but the volume backing it is replaced with a
+//! new volume whose read-only parent is the snapshot's volume:
+//!
+//! 1. Move the disk from "detached" to "maintenance", so that it cannot be
+//!    attached to an instance while its volume is being replaced.
+//! 2. Allocate and ensure regions for the new volume, and create the volume
+//!    record, the same way the disk create saga does for a disk created from a
+//!    snapshot.
+//! 3. Point the disk at the new volume and move it back to "detached".
+//! 4. Release the old volume with the volume delete saga.
+
+use super::{
+    common_storage::{
+        delete_crucible_regions, ensure_all_datasets_and_regions,
+    },
+    disk_create::{
+        disk_volume_construction_request,
+        randomize_volume_construction_request_ids,
+    },
+    ActionRegistry, NexusActionContext, NexusSaga, SagaInitError,
+    ACTION_GENERATE_ID,
+};
+use crate::app::sagas::declare_saga_actions;
+use crate::app::sagas::volume_delete;
+use crate::db::identity::{Asset, Resource};
+use crate::db::lookup::LookupPath;
+use crate::external_api::params;
+use crate::{authn, authz, db};
+use nexus_db_model::Generation;
+use nexus_db_queries::db::datastore::RegionAllocationStrategy;
+use omicron_common::api::external;
+use omicron_common::api::external::Error;
+use serde::Deserialize;
+use serde::Serialize;
+use sled_agent_client::types::VolumeConstructionRequest;
+use steno::ActionError;
+use steno::Node;
+use uuid::Uuid;
+
+// disk rollback saga: input parameters
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Params {
+    pub serialized_authn: authn::saga::Serialized,
+    pub disk_id: Uuid,
+    pub snapshot_id: Uuid,
+    /// the volume backing the disk when the saga was created
+    pub old_volume_id: Uuid,
+    pub size: external::ByteCount,
+}
+
+// disk rollback saga: actions
+
+declare_saga_actions! {
+    disk_rollback;
+    SET_DISK_MAINTENANCE -> "maintenance_gen" {
+        + sdr_set_disk_maintenance
+        - sdr_set_disk_maintenance_undo
+    }
+    REGIONS_ALLOC -> "datasets_and_regions" {
+        + sdr_alloc_regions
+        - sdr_alloc_regions_undo
+    }
+    REGIONS_ENSURE -> "regions_ensure" {
+        + sdr_regions_ensure
+        - sdr_regions_ensure_undo
+    }
+    CREATE_VOLUME_RECORD -> "created_volume" {
+        + sdr_create_volume_record
+        - sdr_create_volume_record_undo
+    }
+    SWAP_DISK_VOLUME -> "no_result1" {
+        + sdr_swap_disk_volume
+        - sdr_swap_disk_volume_undo
+    }
+    SET_DISK_DETACHED -> "no_result2" {
+        + sdr_set_disk_detached
+    }
+}
+
+// disk rollback saga: definition
+
+#[derive(Debug)]
+pub struct SagaDiskRollback;
+impl NexusSaga for SagaDiskRollback {
+    const NAME: &'static str = "disk-rollback";
+    type Params = Params;
+
+    fn register_actions(registry: &mut ActionRegistry) {
+        disk_rollback_register_actions(registry);
+    }
+
+    fn make_saga_dag(
+        params: &Self::Params,
+        mut builder: steno::DagBuilder,
+    ) -> Result<steno::Dag, SagaInitError> {
+        builder.append(Node::action(
+            "new_volume_id",
+            "GenerateVolumeId",
+            ACTION_GENERATE_ID.as_ref(),
+        ));
+
+        builder.append(set_disk_maintenance_action());
+        builder.append(regions_alloc_action());
+        builder.append(regions_ensure_action());
+        builder.append(create_volume_record_action());
+        builder.append(swap_disk_volume_action());
+        builder.append(set_disk_detached_action());
+
+        // Once the disk no longer refers to it, release the old volume.
+        let subsaga_params = volume_delete::Params {
+            serialized_authn: params.serialized_authn.clone(),
+            volume_id: params.old_volume_id,
+        };
+
+        let subsaga_dag = {
+            let subsaga_builder = steno::DagBuilder::new(steno::SagaName::new(
+                volume_delete::SagaVolumeDelete::NAME,
+            ));
+            volume_delete::SagaVolumeDelete::make_saga_dag(
+                &subsaga_params,
+                subsaga_builder,
+            )?
+        };
+
+        builder.append(Node::constant(
+            "params_for_volume_delete_subsaga",
+            serde_json::to_value(&subsaga_params).map_err(|e| {
+                SagaInitError::SerializeError(
+                    "params_for_volume_delete_subsaga".to_string(),
+                    e,
+                )
+            })?,
+        ));
+
+        builder.append(Node::subsaga(
+            "volume_delete_subsaga_no_result",
+            subsaga_dag,
+            "params_for_volume_delete_subsaga",
+        ));
+
+        Ok(builder.build()?)
+    }
+}
+
+// disk rollback saga: action implementations
+
+async fn sdr_set_disk_maintenance(
+    sagactx: NexusActionContext,
+) -> Result<Generation, ActionError> {
+    let log = sagactx.user_data().log();
+    let osagactx = sagactx.user_data();
+    let params = sagactx.saga_params::<Params>()?;
+    let opctx = crate::context::op_context_for_saga_action(
+        &sagactx,
+        &params.serialized_authn,
+    );
+
+    let (.., authz_disk, db_disk) =
+        LookupPath::new(&opctx, &osagactx.datastore())
+            .disk_id(params.disk_id)
+            .fetch_for(authz::Action::Modify)
+            .await
+            .map_err(ActionError::action_failed)?;
+
+    // The disk may have been attached to an instance since the saga was
+    // created.  If it was, bail out.  If it's attached after this lookup, the
+    // update below fails because the generation number is too low.
+    match db_disk.state().into() {
+        external::DiskState::Detached => {
+            info!(log, "setting state of {} to maintenance", params.disk_id);
+
+            osagactx
+                .datastore()
+                .disk_update_runtime(
+                    &opctx,
+                    &authz_disk,
+                    &db_disk.runtime().maintenance(),
+                )
+                .await
+                .map_err(ActionError::action_failed)?;
+        }
+
+        _ => {
+            return Err(ActionError::action_failed(Error::invalid_request(
+                &format!(
+                    "disk cannot be rolled back in state {:?}",
+                    db_disk.state()
+                ),
+            )));
+        }
+    }
+
+    // Record the disk's new generation number so that we only move the disk
+    // out of maintenance if nothing else has changed it since.
+    let (.., db_disk) = LookupPath::new(&opctx, &osagactx.datastore())
+        .disk_id(params.disk_id)
+        .fetch_for(authz::Action::Read)
+        .await
+        .map_err(ActionError::action_failed)?;
+
+    Ok(db_disk.runtime().gen)
+}
+
+async fn sdr_set_disk_maintenance_undo(
+    sagactx: NexusActionContext,
+) -> Result<(), anyhow::Error> {
+    let log = sagactx.user_data().log();
+    let osagactx = sagactx.user_data();
+    let params = sagactx.saga_params::<Params>()?;
+    let opctx = crate::context::op_context_for_saga_action(
+        &sagactx,
+        &params.serialized_authn,
+    );
+
+    let (.., authz_disk, db_disk) =
+        LookupPath::new(&opctx, &osagactx.datastore())
+            .disk_id(params.disk_id)
+            .fetch_for(authz::Action::Modify)
+            .await
+            .map_err(ActionError::action_failed)?;
+
+    match db_disk.state().into() {
+        external::DiskState::Maintenance => {
+            info!(
+                log,
+                "undo: setting disk {} state from maintenance to detached",
+                params.disk_id
+            );
+
+            osagactx
+                .datastore()
+                .disk_update_runtime(
+                    &opctx,
+                    &authz_disk,
+                    &db_disk.runtime().detach(),
+                )
+                .await
+                .map_err(ActionError::action_failed)?;
+        }
+
+        external::DiskState::Detached => {
+            info!(
+                log,
+                "undo: disk {} already in state detached", params.disk_id
+            );
+        }
+
+        _ => {
+            warn!(
+                log,
+                "undo: disk {} is in state {:?}",
+                params.disk_id,
+                db_disk.state()
+            );
+        }
+    }
+
+    Ok(())
+}
+
+async fn sdr_alloc_regions(
+    sagactx: NexusActionContext,
+) -> Result<Vec<(db::model::Dataset, db::model::Region)>, ActionError> {
+    let osagactx = sagactx.user_data();
+    let params = sagactx.saga_params::<Params>()?;
+    let new_volume_id = sagactx.lookup::<Uuid>("new_volume_id")?;
+    let opctx = crate::context::op_context_for_saga_action(
+        &sagactx,
+        &params.serialized_authn,
+    );
+
+    // The new regions are the same size as the disk, and take their block
+    // size from the snapshot (which was checked to match the disk's).
+    let datasets_and_regions = osagactx
+        .datastore()
+        .region_allocate(
+            &opctx,
+            new_volume_id,
+            &params::DiskSource::Snapshot { snapshot_id: params.snapshot_id },
+            params.size,
+            &RegionAllocationStrategy::Random(None),
+        )
+        .await
+        .map_err(ActionError::action_failed)?;
+    Ok(datasets_and_regions)
+}
+
+async fn sdr_alloc_regions_undo(
+    sagactx: NexusActionContext,
+) -> Result<(), anyhow::Error> {
+    let osagactx = sagactx.user_data();
+
+    let region_ids = sagactx
+        .lookup::<Vec<(db::model::Dataset, db::model::Region)>>(
+            "datasets_and_regions",
+        )?
+        .into_iter()
+        .map(|(_, region)| region.id())
+        .collect::<Vec<Uuid>>();
+
+    osagactx.datastore().regions_hard_delete(region_ids).await?;
+    Ok(())
+}
+
+/// Create the new regions and build the new volume's construction request,
+/// with the snapshot's volume as its read-only parent.
+async fn sdr_regions_ensure(
+    sagactx: NexusActionContext,
+) -> Result<String, ActionError> {
+    let log = sagactx.user_data().log();
+    let osagactx = sagactx.user_data();
+    let params = sagactx.saga_params::<Params>()?;
+    let opctx = crate::context::op_context_for_saga_action(
+        &sagactx,
+        &params.serialized_authn,
+    );
+
+    let datasets_and_regions = ensure_all_datasets_and_regions(
+        &log,
+        sagactx.lookup::<Vec<(db::model::Dataset, db::model::Region)>>(
+            "datasets_and_regions",
+        )?,
+    )
+    .await?;
+
+    let (.., db_snapshot) = LookupPath::new(&opctx, &osagactx.datastore())
+        .snapshot_id(params.snapshot_id)
+        .fetch()
+        .await
+        .map_err(ActionError::action_failed)?;
+
+    debug!(
+        log,
+        "grabbing snapshot {} volume {}",
+        db_snapshot.id(),
+        db_snapshot.volume_id,
+    );
+
+    let volume = osagactx
+        .datastore()
+        .volume_checkout(db_snapshot.volume_id)
+        .await
+        .map_err(ActionError::action_failed)?;
+
+    let snapshot_vcr: VolumeConstructionRequest =
+        serde_json::from_str(volume.data()).map_err(|e| {
+            ActionError::action_failed(Error::internal_error(&format!(
+                "failed to deserialize volume data: {}",
+                e,
+            )))
+        })?;
+
+    // Each ID should be unique to this disk's new volume
+    let read_only_parent = randomize_volume_construction_request_ids(
+        &snapshot_vcr,
+    )
+    .map_err(|e| {
+        ActionError::action_failed(Error::internal_error(&format!(
+            "failed to randomize ids: {}",
+            e,
+        )))
+    })?;
+
+    let volume_construction_request = disk_volume_construction_request(
+        params.disk_id,
+        &datasets_and_regions,
+        Some(Box::new(read_only_parent)),
+    );
+
+    let volume_data = serde_json::to_string(&volume_construction_request)
+        .map_err(|e| {
+            ActionError::action_failed(Error::internal_error(&e.to_string()))
+        })?;
+
+    Ok(volume_data)
+}
+
+async fn sdr_regions_ensure_undo(
+    sagactx: NexusActionContext,
+) -> Result<(), anyhow::Error> {
+    let log = sagactx.user_data().log();
+    warn!(log, "sdr_regions_ensure_undo: Deleting crucible regions");
+    delete_crucible_regions(
+        sagactx.lookup::<Vec<(db::model::Dataset, db::model::Region)>>(
+            "datasets_and_regions",
+        )?,
+    )
+    .await?;
+    info!(log, "sdr_regions_ensure_undo: Deleted crucible regions");
+    Ok(())
+}
+
+async fn sdr_create_volume_record(
+    sagactx: NexusActionContext,
+) -> Result<db::model::Volume, ActionError> {
+    let osagactx = sagactx.user_data();
+
+    let new_volume_id = sagactx.lookup::<Uuid>("new_volume_id")?;
+    let volume_data = sagactx.lookup::<String>("regions_ensure")?;
+
+    let volume = db::model::Volume::new(new_volume_id, volume_data);
+
+    let volume_created = osagactx
+        .datastore()
+        .volume_create(volume)
+        .await
+        .map_err(ActionError::action_failed)?;
+
+    Ok(volume_created)
+}
+
+async fn sdr_create_volume_record_undo(
+    sagactx: NexusActionContext,
+) -> Result<(), anyhow::Error> {
+    let osagactx = sagactx.user_data();
+    let params = sagactx.saga_params::<Params>()?;
+
+    let opctx = crate::context::op_context_for_saga_action(
+        &sagactx,
+        &params.serialized_authn,
+    );
+    let new_volume_id = sagactx.lookup::<Uuid>("new_volume_id")?;
+    osagactx.nexus().volume_delete(&opctx, new_volume_id).await?;
+    Ok(())
+}
+
+async fn sdr_swap_disk_volume(
+    sagactx: NexusActionContext,
+) -> Result<(), ActionError> {
+    let osagactx = sagactx.user_data();
+    let params = sagactx.saga_params::<Params>()?;
+    let opctx = crate::context::op_context_for_saga_action(
+        &sagactx,
+        &params.serialized_authn,
+    );
+    let new_volume_id = sagactx.lookup::<Uuid>("new_volume_id")?;
+
+    let (.., authz_disk) = LookupPath::new(&opctx, &osagactx.datastore())
+        .disk_id(params.disk_id)
+        .lookup_for(authz::Action::Modify)
+        .await
+        .map_err(ActionError::action_failed)?;
+
+    let swapped = osagactx
+        .datastore()
+        .disk_set_volume(
+            &opctx,
+            &authz_disk,
+            params.old_volume_id,
+            new_volume_id,
+        )
+        .await
+        .map_err(ActionError::action_failed)?;
+
+    if !swapped {
+        return Err(ActionError::action_failed(Error::conflict(
+            "disk's volume changed while it was being rolled back",
+        )));
+    }
+
+    Ok(())
+}
+
+async fn sdr_swap_disk_volume_undo(
+    sagactx: NexusActionContext,
+) -> Result<(), anyhow::Error> {
+    let osagactx = sagactx.user_data();
+    let params = sagactx.saga_params::<Params>()?;
+    let opctx = crate::context::op_context_for_saga_action(
+        &sagactx,
+        &params.serialized_authn,
+    );
+    let new_volume_id = sagactx.lookup::<Uuid>("new_volume_id")?;
+
+    let (.., authz_disk) = LookupPath::new(&opctx, &osagactx.datastore())
+        .disk_id(params.disk_id)
+        .lookup_for(authz::Action::Modify)
+        .await
+        .map_err(ActionError::action_failed)?;
+
+    osagactx
+        .datastore()
+        .disk_set_volume(
+            &opctx,
+            &authz_disk,
+            new_volume_id,
+            params.old_volume_id,
+        )
+        .await?;
+
+    Ok(())
+}
+
+async fn sdr_set_disk_detached(
+    sagactx: NexusActionContext,
+) -> Result<(), ActionError> {
+    let log = sagactx.user_data().log();
+    let osagactx = sagactx.user_data();
+    let params = sagactx.saga_params::<Params>()?;
+    let opctx = crate::context::op_context_for_saga_action(
+        &sagactx,
+        &params.serialized_authn,
+    );
+    let maintenance_gen = sagactx.lookup::<Generation>("maintenance_gen")?;
+
+    let (.., authz_disk, db_disk) =
+        LookupPath::new(&opctx, &osagactx.datastore())
+            .disk_id(params.disk_id)
+            .fetch_for(authz::Action::Modify)
+            .await
+            .map_err(ActionError::action_failed)?;
+
+    // Only move the disk out of maintenance if this saga put it there.
+    match db_disk.state().into() {
+        external::DiskState::Maintenance => {
+            if db_disk.runtime().gen == maintenance_gen {
+                info!(
+                    log,
+                    "setting disk {} state from maintenance to detached",
+                    params.disk_id
+                );
+
+                osagactx
+                    .datastore()
+                    .disk_update_runtime(
+                        &opctx,
+                        &authz_disk,
+                        &db_disk.runtime().detach(),
+                    )
+                    .await
+                    .map_err(ActionError::action_failed)?;
+            } else {
+                info!(
+                    log,
+                    "disk {} has generation number {:?}, which doesn't match \
+                    the expected {:?}: skip setting to detach",
+                    params.disk_id,
+                    db_disk.runtime().gen,
+                    maintenance_gen,
+                );
+            }
+        }
+
+        external::DiskState::Detached => {
+            info!(log, "disk {} already in state detached", params.disk_id);
+        }
+
+        _ => {
+            warn!(
+                log,
+                "disk {} is in state {:?}",
+                params.disk_id,
+                db_disk.state()
+            );
+        }
+    }
+
+    Ok(())
+}
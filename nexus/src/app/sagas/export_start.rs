@@ -0,0 +1,375 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Attach a detached disk or a snapshot to a Pantry, so that its blocks can be
+//! read back out with bulk reads.
+//!
+//! Disks move to state Exporting for the duration of the export.  Snapshots
+//! don't have a state that can be used for this, so recording the address of
+//! the Pantry a snapshot is attached to is what prevents two exports of the
+//! same snapshot.
+
+use super::declare_saga_actions;
+use super::ActionRegistry;
+use super::NexusActionContext;
+use super::NexusSaga;
+use super::SagaInitError;
+use crate::app::sagas::common_storage::{
+    call_pantry_attach_for_volume, call_pantry_detach_for_disk,
+    get_pantry_address,
+};
+use crate::db::lookup::LookupPath;
+use crate::{authn, authz};
+use nexus_db_model::Generation;
+use omicron_common::api::external;
+use omicron_common::api::external::Error;
+use serde::Deserialize;
+use serde::Serialize;
+use std::net::SocketAddrV6;
+use steno::ActionError;
+use uuid::Uuid;
+
+/// What is being exported
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub enum ExportSource {
+    Disk(Uuid),
+    Snapshot(Uuid),
+}
+
+impl ExportSource {
+    /// Returns the id that the Pantry knows this source by
+    pub fn id(&self) -> Uuid {
+        match self {
+            ExportSource::Disk(id) | ExportSource::Snapshot(id) => *id,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Params {
+    pub serialized_authn: authn::saga::Serialized,
+    pub source: ExportSource,
+}
+
+declare_saga_actions! {
+    export_start;
+    SET_EXPORTING_STATE -> "disk_generation_number" {
+        + ses_set_exporting_state
+        - ses_set_exporting_state_undo
+    }
+    GET_PANTRY_ADDRESS -> "pantry_address" {
+        + ses_get_pantry_address
+    }
+    SET_PANTRY_ADDRESS -> "set_pantry_address" {
+        + ses_set_pantry_address
+        - ses_set_pantry_address_undo
+    }
+    CALL_PANTRY_ATTACH -> "call_pantry_attach" {
+        + ses_call_pantry_attach
+        - ses_call_pantry_attach_undo
+    }
+}
+
+#[derive(Debug)]
+pub struct SagaExportStart;
+impl NexusSaga for SagaExportStart {
+    const NAME: &'static str = "export-start";
+    type Params = Params;
+
+    fn register_actions(registry: &mut ActionRegistry) {
+        export_start_register_actions(registry);
+    }
+
+    fn make_saga_dag(
+        params: &Self::Params,
+        mut builder: steno::DagBuilder,
+    ) -> Result<steno::Dag, SagaInitError> {
+        if let ExportSource::Disk(_) = params.source {
+            builder.append(set_exporting_state_action());
+        }
+
+        builder.append(get_pantry_address_action());
+
+        builder.append(set_pantry_address_action());
+
+        builder.append(call_pantry_attach_action());
+
+        Ok(builder.build()?)
+    }
+}
+
+async fn ses_set_exporting_state(
+    sagactx: NexusActionContext,
+) -> Result<Generation, ActionError> {
+    let log = sagactx.user_data().log();
+    let osagactx = sagactx.user_data();
+    let params = sagactx.saga_params::<Params>()?;
+    let opctx = crate::context::op_context_for_saga_action(
+        &sagactx,
+        &params.serialized_authn,
+    );
+    let disk_id = params.source.id();
+
+    let (.., authz_disk, db_disk) =
+        LookupPath::new(&opctx, &osagactx.datastore())
+            .disk_id(disk_id)
+            .fetch_for(authz::Action::Modify)
+            .await
+            .map_err(ActionError::action_failed)?;
+
+    match db_disk.state().into() {
+        external::DiskState::Detached => {
+            info!(log, "setting disk {} to state exporting", disk_id);
+
+            osagactx
+                .datastore()
+                .disk_update_runtime(
+                    &opctx,
+                    &authz_disk,
+                    &db_disk.runtime().exporting(),
+                )
+                .await
+                .map_err(ActionError::action_failed)?;
+
+            // Record the disk's new generation number as this saga node's
+            // output, so that the undo only moves the disk out of exporting
+            // if nothing else has changed it since.
+            let (.., db_disk) = LookupPath::new(&opctx, &osagactx.datastore())
+                .disk_id(disk_id)
+                .fetch_for(authz::Action::Read)
+                .await
+                .map_err(ActionError::action_failed)?;
+
+            Ok(db_disk.runtime().gen)
+        }
+
+        _ => Err(ActionError::action_failed(Error::invalid_request(&format!(
+            "cannot export disk in state {:?}",
+            db_disk.state()
+        )))),
+    }
+}
+
+async fn ses_set_exporting_state_undo(
+    sagactx: NexusActionContext,
+) -> Result<(), anyhow::Error> {
+    let log = sagactx.user_data().log();
+    let osagactx = sagactx.user_data();
+    let params = sagactx.saga_params::<Params>()?;
+    let opctx = crate::context::op_context_for_saga_action(
+        &sagactx,
+        &params.serialized_authn,
+    );
+    let disk_id = params.source.id();
+
+    let (.., authz_disk, db_disk) =
+        LookupPath::new(&opctx, &osagactx.datastore())
+            .disk_id(disk_id)
+            .fetch_for(authz::Action::Modify)
+            .await
+            .map_err(ActionError::action_failed)?;
+
+    let expected_disk_generation_number =
+        sagactx.lookup::<Generation>("disk_generation_number")?;
+
+    match db_disk.state().into() {
+        external::DiskState::Exporting => {
+            if expected_disk_generation_number == db_disk.runtime().gen {
+                info!(
+                    log,
+                    "undo: setting disk {} state from exporting to detached",
+                    disk_id
+                );
+
+                osagactx
+                    .datastore()
+                    .disk_update_runtime(
+                        &opctx,
+                        &authz_disk,
+                        &db_disk.runtime().detach(),
+                    )
+                    .await
+                    .map_err(ActionError::action_failed)?;
+            } else {
+                info!(
+                    log,
+                    "disk {} has generation number {:?}, which doesn't match the expected {:?}: skip setting to detached",
+                    disk_id,
+                    db_disk.runtime().gen,
+                    expected_disk_generation_number,
+                );
+            }
+        }
+
+        external::DiskState::Detached => {
+            info!(log, "disk {} already detached", disk_id);
+        }
+
+        _ => {
+            warn!(log, "disk is in state {:?}", db_disk.state());
+        }
+    }
+
+    Ok(())
+}
+
+async fn ses_get_pantry_address(
+    sagactx: NexusActionContext,
+) -> Result<SocketAddrV6, ActionError> {
+    let log = sagactx.user_data().log();
+    let osagactx = sagactx.user_data();
+    let params = sagactx.saga_params::<Params>()?;
+
+    let pantry_address = get_pantry_address(osagactx.nexus()).await?;
+
+    info!(
+        log,
+        "using pantry at {} for exporting {:?}", pantry_address, params.source
+    );
+
+    Ok(pantry_address)
+}
+
+async fn ses_set_pantry_address(
+    sagactx: NexusActionContext,
+) -> Result<(), ActionError> {
+    let osagactx = sagactx.user_data();
+    let datastore = osagactx.datastore();
+    let params = sagactx.saga_params::<Params>()?;
+    let opctx = crate::context::op_context_for_saga_action(
+        &sagactx,
+        &params.serialized_authn,
+    );
+    let pantry_address = sagactx.lookup::<SocketAddrV6>("pantry_address")?;
+
+    match params.source {
+        ExportSource::Disk(disk_id) => {
+            let (.., authz_disk) = LookupPath::new(&opctx, &datastore)
+                .disk_id(disk_id)
+                .lookup_for(authz::Action::Modify)
+                .await
+                .map_err(ActionError::action_failed)?;
+
+            datastore
+                .disk_set_pantry(&opctx, &authz_disk, pantry_address)
+                .await
+                .map_err(ActionError::action_failed)?;
+        }
+
+        ExportSource::Snapshot(snapshot_id) => {
+            let (.., authz_snapshot) = LookupPath::new(&opctx, &datastore)
+                .snapshot_id(snapshot_id)
+                .lookup_for(authz::Action::Modify)
+                .await
+                .map_err(ActionError::action_failed)?;
+
+            let updated = datastore
+                .snapshot_set_pantry(&opctx, &authz_snapshot, pantry_address)
+                .await
+                .map_err(ActionError::action_failed)?;
+
+            if !updated {
+                return Err(ActionError::action_failed(
+                    Error::invalid_request(
+                        "snapshot must be ready and not already being exported",
+                    ),
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn ses_set_pantry_address_undo(
+    sagactx: NexusActionContext,
+) -> Result<(), anyhow::Error> {
+    let osagactx = sagactx.user_data();
+    let datastore = osagactx.datastore();
+    let params = sagactx.saga_params::<Params>()?;
+    let opctx = crate::context::op_context_for_saga_action(
+        &sagactx,
+        &params.serialized_authn,
+    );
+
+    match params.source {
+        ExportSource::Disk(disk_id) => {
+            let (.., authz_disk) = LookupPath::new(&opctx, &datastore)
+                .disk_id(disk_id)
+                .lookup_for(authz::Action::Modify)
+                .await?;
+
+            datastore.disk_clear_pantry(&opctx, &authz_disk).await?;
+        }
+
+        ExportSource::Snapshot(snapshot_id) => {
+            let (.., authz_snapshot) = LookupPath::new(&opctx, &datastore)
+                .snapshot_id(snapshot_id)
+                .lookup_for(authz::Action::Modify)
+                .await?;
+
+            datastore.snapshot_clear_pantry(&opctx, &authz_snapshot).await?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn ses_call_pantry_attach(
+    sagactx: NexusActionContext,
+) -> Result<(), ActionError> {
+    let log = sagactx.user_data().log();
+    let osagactx = sagactx.user_data();
+    let datastore = osagactx.datastore();
+    let params = sagactx.saga_params::<Params>()?;
+    let opctx = crate::context::op_context_for_saga_action(
+        &sagactx,
+        &params.serialized_authn,
+    );
+    let pantry_address = sagactx.lookup::<SocketAddrV6>("pantry_address")?;
+
+    let volume_id = match params.source {
+        ExportSource::Disk(disk_id) => {
+            let (.., db_disk) = LookupPath::new(&opctx, &datastore)
+                .disk_id(disk_id)
+                .fetch_for(authz::Action::Modify)
+                .await
+                .map_err(ActionError::action_failed)?;
+
+            db_disk.volume_id
+        }
+
+        ExportSource::Snapshot(snapshot_id) => {
+            let (.., db_snapshot) = LookupPath::new(&opctx, &datastore)
+                .snapshot_id(snapshot_id)
+                .fetch_for(authz::Action::Modify)
+                .await
+                .map_err(ActionError::action_failed)?;
+
+            db_snapshot.volume_id
+        }
+    };
+
+    call_pantry_attach_for_volume(
+        &log,
+        osagactx.nexus(),
+        params.source.id(),
+        volume_id,
+        pantry_address,
+    )
+    .await
+}
+
+async fn ses_call_pantry_attach_undo(
+    sagactx: NexusActionContext,
+) -> Result<(), anyhow::Error> {
+    let log = sagactx.user_data().log();
+    let params = sagactx.saga_params::<Params>()?;
+    let pantry_address = sagactx.lookup::<SocketAddrV6>("pantry_address")?;
+
+    call_pantry_detach_for_disk(&log, params.source.id(), pantry_address)
+        .await?;
+
+    Ok(())
+}
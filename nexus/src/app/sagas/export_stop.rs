@@ -0,0 +1,230 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Detach a disk or a snapshot that is being exported from its Pantry.  Disks
+//! go back to state Detached.
+
+use super::declare_saga_actions;
+use super::export_start::ExportSource;
+use super::ActionRegistry;
+use super::NexusActionContext;
+use super::NexusSaga;
+use super::SagaInitError;
+use crate::app::sagas::common_storage::call_pantry_detach_for_disk;
+use crate::db::lookup::LookupPath;
+use crate::{authn, authz};
+use omicron_common::api::external;
+use omicron_common::api::external::Error;
+use serde::Deserialize;
+use serde::Serialize;
+use std::net::SocketAddrV6;
+use steno::ActionError;
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Params {
+    pub serialized_authn: authn::saga::Serialized,
+    pub source: ExportSource,
+}
+
+declare_saga_actions! {
+    export_stop;
+    GET_PANTRY_ADDRESS -> "pantry_address" {
+        + sep_get_pantry_address
+    }
+    CALL_PANTRY_DETACH -> "call_pantry_detach" {
+        + sep_call_pantry_detach
+    }
+    CLEAR_PANTRY_ADDRESS -> "clear_pantry_address" {
+        + sep_clear_pantry_address
+    }
+    SET_DETACHED_STATE -> "set_detached_state" {
+        + sep_set_detached_state
+    }
+}
+
+#[derive(Debug)]
+pub struct SagaExportStop;
+impl NexusSaga for SagaExportStop {
+    const NAME: &'static str = "export-stop";
+    type Params = Params;
+
+    fn register_actions(registry: &mut ActionRegistry) {
+        export_stop_register_actions(registry);
+    }
+
+    fn make_saga_dag(
+        params: &Self::Params,
+        mut builder: steno::DagBuilder,
+    ) -> Result<steno::Dag, SagaInitError> {
+        builder.append(get_pantry_address_action());
+
+        builder.append(call_pantry_detach_action());
+
+        builder.append(clear_pantry_address_action());
+
+        if let ExportSource::Disk(_) = params.source {
+            builder.append(set_detached_state_action());
+        }
+
+        Ok(builder.build()?)
+    }
+}
+
+async fn sep_get_pantry_address(
+    sagactx: NexusActionContext,
+) -> Result<SocketAddrV6, ActionError> {
+    let log = sagactx.user_data().log();
+    let osagactx = sagactx.user_data();
+    let datastore = osagactx.datastore();
+    let params = sagactx.saga_params::<Params>()?;
+    let opctx = crate::context::op_context_for_saga_action(
+        &sagactx,
+        &params.serialized_authn,
+    );
+
+    let pantry_address = match params.source {
+        ExportSource::Disk(disk_id) => {
+            let (.., db_disk) = LookupPath::new(&opctx, &datastore)
+                .disk_id(disk_id)
+                .fetch_for(authz::Action::Modify)
+                .await
+                .map_err(ActionError::action_failed)?;
+
+            match db_disk.state().into() {
+                external::DiskState::Exporting => {}
+                _ => {
+                    return Err(ActionError::action_failed(
+                        Error::invalid_request(&format!(
+                            "cannot stop exporting disk in state {:?}",
+                            db_disk.state()
+                        )),
+                    ));
+                }
+            }
+
+            db_disk.pantry_address()
+        }
+
+        ExportSource::Snapshot(snapshot_id) => {
+            let (.., db_snapshot) = LookupPath::new(&opctx, &datastore)
+                .snapshot_id(snapshot_id)
+                .fetch_for(authz::Action::Modify)
+                .await
+                .map_err(ActionError::action_failed)?;
+
+            db_snapshot.pantry_address()
+        }
+    };
+
+    let pantry_address = pantry_address.ok_or_else(|| {
+        ActionError::action_failed(Error::invalid_request(
+            "not attached to a pantry for export",
+        ))
+    })?;
+
+    info!(log, "{:?} is using pantry at {}", params.source, pantry_address);
+
+    Ok(pantry_address)
+}
+
+async fn sep_call_pantry_detach(
+    sagactx: NexusActionContext,
+) -> Result<(), ActionError> {
+    let log = sagactx.user_data().log();
+    let params = sagactx.saga_params::<Params>()?;
+    let pantry_address = sagactx.lookup::<SocketAddrV6>("pantry_address")?;
+
+    call_pantry_detach_for_disk(&log, params.source.id(), pantry_address).await
+}
+
+async fn sep_clear_pantry_address(
+    sagactx: NexusActionContext,
+) -> Result<(), ActionError> {
+    let osagactx = sagactx.user_data();
+    let datastore = osagactx.datastore();
+    let params = sagactx.saga_params::<Params>()?;
+    let opctx = crate::context::op_context_for_saga_action(
+        &sagactx,
+        &params.serialized_authn,
+    );
+
+    match params.source {
+        ExportSource::Disk(disk_id) => {
+            let (.., authz_disk) = LookupPath::new(&opctx, &datastore)
+                .disk_id(disk_id)
+                .lookup_for(authz::Action::Modify)
+                .await
+                .map_err(ActionError::action_failed)?;
+
+            datastore
+                .disk_clear_pantry(&opctx, &authz_disk)
+                .await
+                .map_err(ActionError::action_failed)?;
+        }
+
+        ExportSource::Snapshot(snapshot_id) => {
+            let (.., authz_snapshot) = LookupPath::new(&opctx, &datastore)
+                .snapshot_id(snapshot_id)
+                .lookup_for(authz::Action::Modify)
+                .await
+                .map_err(ActionError::action_failed)?;
+
+            datastore
+                .snapshot_clear_pantry(&opctx, &authz_snapshot)
+                .await
+                .map_err(ActionError::action_failed)?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn sep_set_detached_state(
+    sagactx: NexusActionContext,
+) -> Result<(), ActionError> {
+    let log = sagactx.user_data().log();
+    let osagactx = sagactx.user_data();
+    let params = sagactx.saga_params::<Params>()?;
+    let opctx = crate::context::op_context_for_saga_action(
+        &sagactx,
+        &params.serialized_authn,
+    );
+    let disk_id = params.source.id();
+
+    let (.., authz_disk, db_disk) =
+        LookupPath::new(&opctx, &osagactx.datastore())
+            .disk_id(disk_id)
+            .fetch_for(authz::Action::Modify)
+            .await
+            .map_err(ActionError::action_failed)?;
+
+    match db_disk.state().into() {
+        external::DiskState::Exporting => {
+            info!(
+                log,
+                "setting disk {} state from exporting to detached", disk_id
+            );
+
+            osagactx
+                .datastore()
+                .disk_update_runtime(
+                    &opctx,
+                    &authz_disk,
+                    &db_disk.runtime().detach(),
+                )
+                .await
+                .map_err(ActionError::action_failed)?;
+        }
+
+        external::DiskState::Detached => {
+            info!(log, "disk {} already detached", disk_id);
+        }
+
+        _ => {
+            warn!(log, "disk is in state {:?}", db_disk.state());
+        }
+    }
+
+    Ok(())
+}
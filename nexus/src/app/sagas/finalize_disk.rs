@@ -89,7 +89,11 @@ impl NexusSaga for SagaFinalizeDisk {
                         ),
                     },
                     disk: params.disk_id.into(),
+                    labels: Default::default(),
                 },
+                snapshot_schedule_id: None,
+                disk_in_maintenance: false,
+                transient: false,
             };
 
             let subsaga_dag = {
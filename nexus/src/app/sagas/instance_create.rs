@@ -47,9 +47,15 @@ use uuid::Uuid;
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Params {
     pub serialized_authn: authn::saga::Serialized,
+    pub silo_id: Uuid,
     pub project_id: Uuid,
     pub create_params: params::InstanceCreate,
     pub boundary_switches: HashSet<SwitchLocation>,
+    /// The affinity groups named in `create_params`, which the instance joins
+    /// once it has been placed
+    pub affinity_groups: Vec<authz::AffinityGroup>,
+    /// The IDs of the creating user's SSH public keys chosen for the instance
+    pub ssh_keys: Vec<Uuid>,
 }
 
 // Several nodes in this saga are wrapped in their own subsaga so that they can
@@ -98,6 +104,14 @@ declare_saga_actions! {
         + sic_create_instance_record
         - sic_delete_instance_record
     }
+    JOIN_AFFINITY_GROUPS -> "join_affinity_groups" {
+        + sic_join_affinity_groups
+        - sic_join_affinity_groups_undo
+    }
+    ASSOCIATE_SSH_KEYS -> "associate_ssh_keys" {
+        + sic_associate_ssh_keys
+        - sic_associate_ssh_keys_undo
+    }
     CREATE_NETWORK_INTERFACE -> "output" {
         + sic_create_network_interface
         - sic_create_network_interface_undo
@@ -171,6 +185,8 @@ impl NexusSaga for SagaInstanceCreate {
         builder.append(virtual_resources_account_action());
         builder.append(alloc_propolis_ip_action());
         builder.append(create_instance_record_action());
+        builder.append(join_affinity_groups_action());
+        builder.append(associate_ssh_keys_action());
 
         // Helper function for appending subsagas to our parent saga.
         fn subsaga_append<S: Serialize>(
@@ -288,6 +304,7 @@ impl NexusSaga for SagaInstanceCreate {
                 let subsaga_builder = DagBuilder::new(subsaga_name);
                 let params = disk_create::Params {
                     serialized_authn: params.serialized_authn.clone(),
+                    silo_id: params.silo_id,
                     project_id: params.project_id,
                     create_params: create_disk.clone(),
                 };
@@ -507,9 +524,9 @@ async fn sic_alloc_server(
     //   multi-rack, this is going to fling the sled to an arbitrary system.
     //   Maybe that's okay, but worth knowing about explicitly.
     //
-    // - This doesn't take into account anti-affinity - users will want to
-    //   schedule instances that belong to a cluster on different failure
-    //   domains. See https://github.com/oxidecomputer/omicron/issues/1705.
+    // - Affinity groups only constrain placement relative to other instances
+    //   on individual sleds, not to larger failure domains like racks. See
+    //   https://github.com/oxidecomputer/omicron/issues/1705.
 
     // TODO: Fix these values. They're wrong now, but they let us move
     // forward with plumbing.
@@ -528,13 +545,22 @@ async fn sic_alloc_server(
         reservoir_ram.into(),
     );
 
+    // Place the instance according to the other members of the affinity
+    // groups it is joining.
+    let instance_id = sagactx.lookup::<Uuid>("instance_id")?;
+    let group_ids =
+        params.affinity_groups.iter().map(|g| g.id()).collect::<Vec<_>>();
+    let constraints = db::model::SledReservationConstraintBuilder::new()
+        .affinity_groups(instance_id, &group_ids)
+        .build();
+
     let resource = osagactx
         .nexus()
         .reserve_on_random_sled(
             propolis_id,
             db::model::SledResourceKind::Instance,
             resources,
-            db::model::SledReservationConstraints::none(),
+            constraints,
         )
         .await
         .map_err(ActionError::action_failed)?;
@@ -551,6 +577,96 @@ async fn sic_alloc_server_undo(
     Ok(())
 }
 
+async fn sic_join_affinity_groups(
+    sagactx: NexusActionContext,
+) -> Result<(), ActionError> {
+    let osagactx = sagactx.user_data();
+    let params = sagactx.saga_params::<Params>()?;
+    let datastore = osagactx.datastore();
+    let opctx = crate::context::op_context_for_saga_action(
+        &sagactx,
+        &params.serialized_authn,
+    );
+    let instance_id = sagactx.lookup::<Uuid>("instance_id")?;
+    if params.affinity_groups.is_empty() {
+        return Ok(());
+    }
+
+    let (.., authz_instance) = LookupPath::new(&opctx, &datastore)
+        .instance_id(instance_id)
+        .lookup_for(authz::Action::Modify)
+        .await
+        .map_err(ActionError::action_failed)?;
+    for authz_group in &params.affinity_groups {
+        datastore
+            .affinity_group_member_add(&opctx, authz_group, &authz_instance)
+            .await
+            .map_err(ActionError::action_failed)?;
+    }
+    Ok(())
+}
+
+async fn sic_join_affinity_groups_undo(
+    sagactx: NexusActionContext,
+) -> Result<(), anyhow::Error> {
+    let osagactx = sagactx.user_data();
+    let params = sagactx.saga_params::<Params>()?;
+    let opctx = crate::context::op_context_for_saga_action(
+        &sagactx,
+        &params.serialized_authn,
+    );
+    let instance_id = sagactx.lookup::<Uuid>("instance_id")?;
+    osagactx
+        .datastore()
+        .affinity_group_memberships_delete_by_instance_id(&opctx, instance_id)
+        .await?;
+    Ok(())
+}
+
+async fn sic_associate_ssh_keys(
+    sagactx: NexusActionContext,
+) -> Result<(), ActionError> {
+    let osagactx = sagactx.user_data();
+    let params = sagactx.saga_params::<Params>()?;
+    let datastore = osagactx.datastore();
+    let opctx = crate::context::op_context_for_saga_action(
+        &sagactx,
+        &params.serialized_authn,
+    );
+    let instance_id = sagactx.lookup::<Uuid>("instance_id")?;
+    if params.ssh_keys.is_empty() {
+        return Ok(());
+    }
+
+    let (.., authz_instance) = LookupPath::new(&opctx, &datastore)
+        .instance_id(instance_id)
+        .lookup_for(authz::Action::Modify)
+        .await
+        .map_err(ActionError::action_failed)?;
+    datastore
+        .instance_ssh_keys_add(&opctx, &authz_instance, &params.ssh_keys)
+        .await
+        .map_err(ActionError::action_failed)?;
+    Ok(())
+}
+
+async fn sic_associate_ssh_keys_undo(
+    sagactx: NexusActionContext,
+) -> Result<(), anyhow::Error> {
+    let osagactx = sagactx.user_data();
+    let params = sagactx.saga_params::<Params>()?;
+    let opctx = crate::context::op_context_for_saga_action(
+        &sagactx,
+        &params.serialized_authn,
+    );
+    let instance_id = sagactx.lookup::<Uuid>("instance_id")?;
+    osagactx
+        .datastore()
+        .instance_ssh_keys_delete_by_instance_id(&opctx, instance_id)
+        .await?;
+    Ok(())
+}
+
 /// Create a network interface for an instance, using the parameters at index
 /// `nic_index`, returning the UUID for the NIC (or None).
 async fn sic_create_network_interface(
@@ -1411,6 +1527,7 @@ pub mod test {
     fn new_test_params(opctx: &OpContext, project_id: Uuid) -> Params {
         Params {
             serialized_authn: Serialized::for_opctx(opctx),
+            silo_id: opctx.authn.silo_required().unwrap().id(),
             project_id,
             create_params: params::InstanceCreate {
                 identity: IdentityMetadataCreateParams {
@@ -1431,9 +1548,15 @@ pub mod test {
                         name: DISK_NAME.parse().unwrap(),
                     },
                 )],
+                affinity_groups: vec![],
+                ssh_public_keys: None,
                 start: false,
+                labels: Default::default(),
+                auto_restart_policy: Default::default(),
             },
             boundary_switches: HashSet::from([SwitchLocation::Switch0]),
+            affinity_groups: vec![],
+            ssh_keys: vec![],
         }
     }
 
@@ -52,10 +52,19 @@ declare_saga_actions! {
     DEALLOCATE_EXTERNAL_IP -> "no_result3" {
         + sid_deallocate_external_ip
     }
-    VIRTUAL_RESOURCES_ACCOUNT -> "no_result4" {
+    DETACH_FLOATING_IPS -> "no_result4" {
+        + sid_detach_floating_ips
+    }
+    LEAVE_AFFINITY_GROUPS -> "no_result5" {
+        + sid_leave_affinity_groups
+    }
+    REMOVE_SSH_KEYS -> "no_result6" {
+        + sid_remove_ssh_keys
+    }
+    VIRTUAL_RESOURCES_ACCOUNT -> "no_result7" {
         + sid_account_virtual_resources
     }
-    SLED_RESOURCES_ACCOUNT -> "no_result5" {
+    SLED_RESOURCES_ACCOUNT -> "no_result8" {
         + sid_account_sled_resources
     }
 }
@@ -82,6 +91,9 @@ impl NexusSaga for SagaInstanceDelete {
         builder.append(instance_delete_record_action());
         builder.append(delete_network_interfaces_action());
         builder.append(deallocate_external_ip_action());
+        builder.append(detach_floating_ips_action());
+        builder.append(leave_affinity_groups_action());
+        builder.append(remove_ssh_keys_action());
         builder.append(virtual_resources_account_action());
         builder.append(sled_resources_account_action());
         Ok(builder.build()?)
@@ -281,6 +293,64 @@ async fn sid_deallocate_external_ip(
     Ok(())
 }
 
+/// Floating IPs outlive the instance: return them to the project.
+async fn sid_detach_floating_ips(
+    sagactx: NexusActionContext,
+) -> Result<(), ActionError> {
+    let osagactx = sagactx.user_data();
+    let params = sagactx.saga_params::<Params>()?;
+    let opctx = crate::context::op_context_for_saga_action(
+        &sagactx,
+        &params.serialized_authn,
+    );
+    osagactx
+        .datastore()
+        .detach_floating_ips_by_instance_id(&opctx, params.authz_instance.id())
+        .await
+        .map_err(ActionError::action_failed)?;
+    Ok(())
+}
+
+async fn sid_leave_affinity_groups(
+    sagactx: NexusActionContext,
+) -> Result<(), ActionError> {
+    let osagactx = sagactx.user_data();
+    let params = sagactx.saga_params::<Params>()?;
+    let opctx = crate::context::op_context_for_saga_action(
+        &sagactx,
+        &params.serialized_authn,
+    );
+    osagactx
+        .datastore()
+        .affinity_group_memberships_delete_by_instance_id(
+            &opctx,
+            params.authz_instance.id(),
+        )
+        .await
+        .map_err(ActionError::action_failed)?;
+    Ok(())
+}
+
+async fn sid_remove_ssh_keys(
+    sagactx: NexusActionContext,
+) -> Result<(), ActionError> {
+    let osagactx = sagactx.user_data();
+    let params = sagactx.saga_params::<Params>()?;
+    let opctx = crate::context::op_context_for_saga_action(
+        &sagactx,
+        &params.serialized_authn,
+    );
+    osagactx
+        .datastore()
+        .instance_ssh_keys_delete_by_instance_id(
+            &opctx,
+            params.authz_instance.id(),
+        )
+        .await
+        .map_err(ActionError::action_failed)?;
+    Ok(())
+}
+
 async fn sid_account_virtual_resources(
     sagactx: NexusActionContext,
 ) -> Result<(), ActionError> {
@@ -421,7 +491,11 @@ mod test {
             disks: vec![params::InstanceDiskAttachment::Attach(
                 params::InstanceDiskAttach { name: DISK_NAME.parse().unwrap() },
             )],
+            affinity_groups: vec![],
+            ssh_public_keys: None,
             start: false,
+            labels: Default::default(),
+            auto_restart_policy: Default::default(),
         }
     }
 
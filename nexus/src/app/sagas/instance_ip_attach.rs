@@ -0,0 +1,140 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use super::ActionRegistry;
+use super::NexusActionContext;
+use super::NexusSaga;
+use crate::app::sagas::declare_saga_actions;
+use crate::app::MAX_EXTERNAL_IPS_PER_INSTANCE;
+use crate::db::lookup::LookupPath;
+use crate::{authn, authz};
+use omicron_common::api::external::Error;
+use omicron_common::api::external::InstanceState;
+use serde::Deserialize;
+use serde::Serialize;
+use steno::ActionError;
+
+// instance ip attach saga: input parameters
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Params {
+    pub serialized_authn: authn::saga::Serialized,
+    pub authz_fip: authz::FloatingIp,
+    pub authz_instance: authz::Instance,
+}
+
+// instance ip attach saga: actions
+
+declare_saga_actions! {
+    instance_ip_attach;
+    ATTACH_FLOATING_IP -> "no_result1" {
+        + siia_attach_ip
+        - siia_attach_ip_undo
+    }
+    ENSURE_INSTANCE_STOPPED -> "no_result2" {
+        + siia_ensure_instance_stopped
+    }
+}
+
+// instance ip attach saga: definition
+
+#[derive(Debug)]
+pub struct SagaInstanceIpAttach;
+impl NexusSaga for SagaInstanceIpAttach {
+    const NAME: &'static str = "instance-ip-attach";
+    type Params = Params;
+
+    fn register_actions(registry: &mut ActionRegistry) {
+        instance_ip_attach_register_actions(registry);
+    }
+
+    fn make_saga_dag(
+        _params: &Self::Params,
+        mut builder: steno::DagBuilder,
+    ) -> Result<steno::Dag, super::SagaInitError> {
+        builder.append(attach_floating_ip_action());
+        builder.append(ensure_instance_stopped_action());
+        Ok(builder.build()?)
+    }
+}
+
+// instance ip attach saga: action implementations
+
+async fn siia_attach_ip(
+    sagactx: NexusActionContext,
+) -> Result<(), ActionError> {
+    let osagactx = sagactx.user_data();
+    let params = sagactx.saga_params::<Params>()?;
+    let opctx = crate::context::op_context_for_saga_action(
+        &sagactx,
+        &params.serialized_authn,
+    );
+
+    osagactx
+        .datastore()
+        .floating_ip_attach(
+            &opctx,
+            &params.authz_fip,
+            &params.authz_instance,
+            MAX_EXTERNAL_IPS_PER_INSTANCE,
+        )
+        .await
+        .map_err(ActionError::action_failed)?;
+    Ok(())
+}
+
+async fn siia_attach_ip_undo(
+    sagactx: NexusActionContext,
+) -> Result<(), anyhow::Error> {
+    let osagactx = sagactx.user_data();
+    let params = sagactx.saga_params::<Params>()?;
+    let opctx = crate::context::op_context_for_saga_action(
+        &sagactx,
+        &params.serialized_authn,
+    );
+
+    osagactx
+        .datastore()
+        .floating_ip_set_instance_no_check(
+            &opctx,
+            &params.authz_fip,
+            Some(params.authz_instance.id()),
+            None,
+        )
+        .await?;
+    Ok(())
+}
+
+/// The external IPs of an instance are sent to its sled when the instance
+/// starts. If the instance began starting while the Floating IP was being
+/// attached, the new address may have been missed, so the attachment must be
+/// unwound.
+async fn siia_ensure_instance_stopped(
+    sagactx: NexusActionContext,
+) -> Result<(), ActionError> {
+    let osagactx = sagactx.user_data();
+    let params = sagactx.saga_params::<Params>()?;
+    let opctx = crate::context::op_context_for_saga_action(
+        &sagactx,
+        &params.serialized_authn,
+    );
+
+    let (.., db_instance) = LookupPath::new(&opctx, &osagactx.datastore())
+        .instance_id(params.authz_instance.id())
+        .fetch_for(authz::Action::Read)
+        .await
+        .map_err(ActionError::action_failed)?;
+
+    match db_instance.runtime().state.state() {
+        InstanceState::Creating
+        | InstanceState::Stopped
+        | InstanceState::Failed => Ok(()),
+        state => {
+            Err(ActionError::action_failed(Error::invalid_request(&format!(
+                "instance changed state to {} while attaching Floating IP",
+                state
+            ))))
+        }
+    }
+}
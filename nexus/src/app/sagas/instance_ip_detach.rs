@@ -0,0 +1,134 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use super::ActionRegistry;
+use super::NexusActionContext;
+use super::NexusSaga;
+use crate::app::sagas::declare_saga_actions;
+use crate::db::lookup::LookupPath;
+use crate::{authn, authz};
+use omicron_common::api::external::Error;
+use omicron_common::api::external::InstanceState;
+use serde::Deserialize;
+use serde::Serialize;
+use steno::ActionError;
+
+// instance ip detach saga: input parameters
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Params {
+    pub serialized_authn: authn::saga::Serialized,
+    pub authz_fip: authz::FloatingIp,
+    pub authz_instance: authz::Instance,
+}
+
+// instance ip detach saga: actions
+
+declare_saga_actions! {
+    instance_ip_detach;
+    DETACH_FLOATING_IP -> "no_result1" {
+        + siid_detach_ip
+        - siid_detach_ip_undo
+    }
+    ENSURE_INSTANCE_STOPPED -> "no_result2" {
+        + siid_ensure_instance_stopped
+    }
+}
+
+// instance ip detach saga: definition
+
+#[derive(Debug)]
+pub struct SagaInstanceIpDetach;
+impl NexusSaga for SagaInstanceIpDetach {
+    const NAME: &'static str = "instance-ip-detach";
+    type Params = Params;
+
+    fn register_actions(registry: &mut ActionRegistry) {
+        instance_ip_detach_register_actions(registry);
+    }
+
+    fn make_saga_dag(
+        _params: &Self::Params,
+        mut builder: steno::DagBuilder,
+    ) -> Result<steno::Dag, super::SagaInitError> {
+        builder.append(detach_floating_ip_action());
+        builder.append(ensure_instance_stopped_action());
+        Ok(builder.build()?)
+    }
+}
+
+// instance ip detach saga: action implementations
+
+async fn siid_detach_ip(
+    sagactx: NexusActionContext,
+) -> Result<(), ActionError> {
+    let osagactx = sagactx.user_data();
+    let params = sagactx.saga_params::<Params>()?;
+    let opctx = crate::context::op_context_for_saga_action(
+        &sagactx,
+        &params.serialized_authn,
+    );
+
+    osagactx
+        .datastore()
+        .floating_ip_detach(&opctx, &params.authz_fip, &params.authz_instance)
+        .await
+        .map_err(ActionError::action_failed)?;
+    Ok(())
+}
+
+async fn siid_detach_ip_undo(
+    sagactx: NexusActionContext,
+) -> Result<(), anyhow::Error> {
+    let osagactx = sagactx.user_data();
+    let params = sagactx.saga_params::<Params>()?;
+    let opctx = crate::context::op_context_for_saga_action(
+        &sagactx,
+        &params.serialized_authn,
+    );
+
+    osagactx
+        .datastore()
+        .floating_ip_set_instance_no_check(
+            &opctx,
+            &params.authz_fip,
+            None,
+            Some(params.authz_instance.id()),
+        )
+        .await?;
+    Ok(())
+}
+
+/// The external IPs of an instance are sent to its sled when the instance
+/// starts. If the instance began starting while the Floating IP was being
+/// detached, the old address may still be in use, so the detachment must be
+/// unwound.
+async fn siid_ensure_instance_stopped(
+    sagactx: NexusActionContext,
+) -> Result<(), ActionError> {
+    let osagactx = sagactx.user_data();
+    let params = sagactx.saga_params::<Params>()?;
+    let opctx = crate::context::op_context_for_saga_action(
+        &sagactx,
+        &params.serialized_authn,
+    );
+
+    let (.., db_instance) = LookupPath::new(&opctx, &osagactx.datastore())
+        .instance_id(params.authz_instance.id())
+        .fetch_for(authz::Action::Read)
+        .await
+        .map_err(ActionError::action_failed)?;
+
+    match db_instance.runtime().state.state() {
+        InstanceState::Creating
+        | InstanceState::Stopped
+        | InstanceState::Failed => Ok(()),
+        state => {
+            Err(ActionError::action_failed(Error::invalid_request(&format!(
+                "instance changed state to {} while detaching Floating IP",
+                state
+            ))))
+        }
+    }
+}
@@ -9,6 +9,7 @@ use crate::app::sagas::declare_saga_actions;
 use crate::db::{identity::Resource, lookup::LookupPath};
 use crate::external_api::params;
 use crate::{authn, authz, db};
+use omicron_common::api::external::Error;
 use omicron_common::api::external::InstanceState;
 use omicron_common::api::internal::nexus::InstanceRuntimeState;
 use serde::Deserialize;
@@ -223,10 +224,31 @@ async fn sim_reserve_sled_resources(
         omicron_common::api::external::ByteCount::from(0).into(),
     );
 
+    // The destination must satisfy the hard policies of the instance's
+    // affinity groups.
+    let dst_sled_id = params.migrate_params.dst_sled_id;
+    let affinity_constraints = osagactx
+        .nexus()
+        .instance_affinity_constraints(params.instance.id())
+        .await
+        .map_err(ActionError::action_failed)?
+        .build();
+    if !affinity_constraints.permits(dst_sled_id) {
+        return Err(ActionError::action_failed(Error::invalid_request(
+            &format!(
+                "migrating the instance to sled {} would violate the hard \
+                policy of one of its affinity groups",
+                dst_sled_id
+            ),
+        )));
+    }
+
     // Add a constraint that the only allowed sled is the one specified in the
-    // parameters.
+    // parameters, and check it against the instance's affinity groups again
+    // in the reservation's transaction.
     let constraints = db::model::SledReservationConstraintBuilder::new()
-        .must_select_from(&[params.migrate_params.dst_sled_id])
+        .must_select_from(&[dst_sled_id])
+        .affinity_groups(params.instance.id(), &[])
         .build();
 
     let propolis_id = sagactx.lookup::<Uuid>("dst_propolis_id")?;
@@ -555,7 +577,11 @@ mod tests {
                     params::InstanceNetworkInterfaceAttachment::None,
                 external_ips: vec![],
                 disks: vec![],
+                affinity_groups: vec![],
+                ssh_public_keys: None,
                 start: true,
+                labels: Default::default(),
+                auto_restart_policy: Default::default(),
             },
         )
         .await
@@ -21,10 +21,16 @@ use uuid::Uuid;
 
 pub mod disk_create;
 pub mod disk_delete;
+pub mod disk_resize;
+pub mod disk_rollback;
+pub mod export_start;
+pub mod export_stop;
 pub mod finalize_disk;
 pub mod import_blocks_from_url;
 pub mod instance_create;
 pub mod instance_delete;
+pub mod instance_ip_attach;
+pub mod instance_ip_detach;
 pub mod instance_migrate;
 pub mod loopback_address_create;
 pub mod loopback_address_delete;
@@ -116,6 +122,16 @@ fn make_action_registry() -> ActionRegistry {
 
     <disk_create::SagaDiskCreate as NexusSaga>::register_actions(&mut registry);
     <disk_delete::SagaDiskDelete as NexusSaga>::register_actions(&mut registry);
+    <disk_resize::SagaDiskResize as NexusSaga>::register_actions(
+        &mut registry,
+    );
+    <disk_rollback::SagaDiskRollback as NexusSaga>::register_actions(
+        &mut registry,
+    );
+    <export_start::SagaExportStart as NexusSaga>::register_actions(
+        &mut registry,
+    );
+    <export_stop::SagaExportStop as NexusSaga>::register_actions(&mut registry);
     <finalize_disk::SagaFinalizeDisk as NexusSaga>::register_actions(
         &mut registry,
     );
@@ -126,6 +142,12 @@ fn make_action_registry() -> ActionRegistry {
     <instance_delete::SagaInstanceDelete as NexusSaga>::register_actions(
         &mut registry,
     );
+    <instance_ip_attach::SagaInstanceIpAttach as NexusSaga>::register_actions(
+        &mut registry,
+    );
+    <instance_ip_detach::SagaInstanceIpDetach as NexusSaga>::register_actions(
+        &mut registry,
+    );
     <instance_migrate::SagaInstanceMigrate as NexusSaga>::register_actions(
         &mut registry,
     );
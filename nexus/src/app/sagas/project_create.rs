@@ -111,7 +111,7 @@ async fn spc_create_record_undo(
 
     osagactx
         .datastore()
-        .project_delete(&opctx, &authz_project, &project)
+        .project_delete(&opctx, &authz_project, &project, None)
         .await?;
     Ok(())
 }
@@ -142,6 +142,7 @@ async fn spc_create_vpc_params(
         // handle the logic around name and dns_name by making
         // dns_name optional
         dns_name: "default".parse().unwrap(),
+        labels: Default::default(),
     };
     let saga_params = sagas::vpc_create::Params {
         serialized_authn: authn::saga::Serialized::for_opctx(&opctx),
@@ -179,6 +180,7 @@ mod test {
                     name: "my-project".parse().unwrap(),
                     description: "My Project".to_string(),
                 },
+                labels: Default::default(),
             },
             authz_silo,
         }
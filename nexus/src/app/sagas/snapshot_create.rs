@@ -132,6 +132,15 @@ pub struct Params {
     pub disk_id: Uuid,
     pub use_the_pantry: bool,
     pub create_params: params::SnapshotCreate,
+    /// the snapshot schedule taking this snapshot, if any
+    pub snapshot_schedule_id: Option<Uuid>,
+    /// whether the disk has already been moved to "maintenance" by the saga
+    /// that this one is a sub-saga of, which also moves it out again
+    pub disk_in_maintenance: bool,
+    /// whether the snapshot is only taken for the use of the saga that this
+    /// one is a sub-saga of, which deletes it again: such a snapshot isn't
+    /// listed in its project or charged to it
+    pub transient: bool,
 }
 
 // snapshot create saga: actions
@@ -245,7 +254,9 @@ impl NexusSaga for SagaSnapshotCreate {
         // original disk ID and the destination volume
         builder.append(create_snapshot_record_action());
         // (DB) Tracks virtual resource provisioning.
-        builder.append(space_account_action());
+        if !params.transient {
+            builder.append(space_account_action());
+        }
 
         if !params.use_the_pantry {
             // (Sleds) If the disk is attached to an instance, send a
@@ -546,6 +557,10 @@ async fn ssc_create_snapshot_record(
         state: db::model::SnapshotState::Creating,
         block_size: disk.block_size,
         size: disk.size,
+        labels: params.create_params.labels.clone().into(),
+        pantry_address: None,
+        snapshot_schedule_id: params.snapshot_schedule_id,
+        transient: params.transient,
     };
 
     let (.., authz_project) = LookupPath::new(&opctx, &osagactx.datastore())
@@ -841,6 +856,13 @@ async fn ssc_attach_disk_to_pantry(
             info!(log, "disk {} in state finalizing", params.disk_id);
         }
 
+        external::DiskState::Maintenance if params.disk_in_maintenance => {
+            // This saga is a sub-saga of one that put the disk in maintenance
+            // before starting it, such as the disk resize saga. No state
+            // change is required.
+            info!(log, "disk {} in state maintenance", params.disk_id);
+        }
+
         _ => {
             // Return a 503 indicating that the user should retry
             return Err(ActionError::action_failed(
@@ -885,6 +907,13 @@ async fn ssc_attach_disk_to_pantry_undo(
             .map_err(ActionError::action_failed)?;
 
     match db_disk.state().into() {
+        external::DiskState::Maintenance if params.disk_in_maintenance => {
+            info!(
+                log,
+                "undo: leaving disk {} in state maintenance", params.disk_id
+            );
+        }
+
         external::DiskState::Maintenance => {
             info!(
                 log,
@@ -1117,6 +1146,10 @@ async fn ssc_detach_disk_from_pantry(
             .map_err(ActionError::action_failed)?;
 
     match db_disk.state().into() {
+        external::DiskState::Maintenance if params.disk_in_maintenance => {
+            info!(log, "leaving disk {} in state maintenance", params.disk_id);
+        }
+
         external::DiskState::Maintenance => {
             // A previous execution of this node in *this* saga may have already
             // transitioned this disk from maintenance to detached. Another saga
@@ -1822,7 +1855,11 @@ mod test {
                     description: "My snapshot".to_string(),
                 },
                 disk,
+                labels: Default::default(),
             },
+            snapshot_schedule_id: None,
+            disk_in_maintenance: false,
+            transient: false,
         }
     }
 
@@ -2256,7 +2293,11 @@ mod test {
                     params::InstanceDiskAttach { name: Name::from_str(DISK_NAME).unwrap() },
                 )],
                 external_ips: vec![],
+                affinity_groups: vec![],
+                ssh_public_keys: None,
                 start: true,
+                labels: Default::default(),
+                auto_restart_policy: Default::default(),
             },
         )
         .await;
@@ -8,6 +8,7 @@ use crate::app::sagas::{
     declare_saga_actions, ActionRegistry, NexusSaga, SagaInitError,
 };
 use crate::db::datastore::UpdatePrecondition;
+use crate::external_api::params::BgpAnnounceSetSelector;
 use crate::{authn, db};
 use anyhow::Error;
 use db::datastore::SwitchPortSettingsCombinedResult;
@@ -20,6 +21,7 @@ use ipnetwork::IpNetwork;
 use omicron_common::api::external::{self, NameOrId};
 use omicron_common::api::internal::shared::SwitchLocation;
 use serde::{Deserialize, Serialize};
+use slog::warn;
 use std::collections::HashMap;
 use std::net::IpAddr;
 use std::str::FromStr;
@@ -48,6 +50,9 @@ declare_saga_actions! {
     GET_SWITCH_PORT_SETTINGS -> "switch_port_settings" {
         + spa_get_switch_port_settings
     }
+    GET_SWITCH_PORT_BGP_SETTINGS -> "switch_port_bgp_settings" {
+        + spa_get_switch_port_bgp_settings
+    }
     ENSURE_SWITCH_PORT_SETTINGS -> "ensure_switch_port_settings" {
         + spa_ensure_switch_port_settings
         - spa_undo_ensure_switch_port_settings
@@ -73,6 +78,7 @@ impl NexusSaga for SagaSwitchPortSettingsApply {
     ) -> Result<steno::Dag, SagaInitError> {
         builder.append(associate_switch_port_action());
         builder.append(get_switch_port_settings_action());
+        builder.append(get_switch_port_bgp_settings_action());
         builder.append(ensure_switch_port_settings_action());
         Ok(builder.build()?)
     }
@@ -132,6 +138,85 @@ async fn spa_get_switch_port_settings(
     Ok(port_settings)
 }
 
+/// A BGP peer of a switch port, resolved against the global BGP configuration
+/// and the announce set its peer configuration references.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub(crate) struct BgpPeerSettings {
+    pub interface_name: String,
+    pub addr: IpAddr,
+    pub asn: u32,
+    pub vrf: Option<String>,
+    pub announcements: Vec<IpNetwork>,
+}
+
+async fn spa_get_switch_port_bgp_settings(
+    sagactx: NexusActionContext,
+) -> Result<Vec<BgpPeerSettings>, ActionError> {
+    let osagactx = sagactx.user_data();
+    let params = sagactx.saga_params::<Params>()?;
+    let nexus = osagactx.nexus();
+    let opctx = crate::context::op_context_for_saga_action(
+        &sagactx,
+        &params.serialized_authn,
+    );
+
+    let settings = sagactx
+        .lookup::<SwitchPortSettingsCombinedResult>("switch_port_settings")?;
+
+    let mut peers = Vec::new();
+    for p in &settings.bgp_peers {
+        let config = nexus
+            .bgp_config_get(&opctx, &NameOrId::Id(p.bgp_config_id))
+            .await
+            .map_err(ActionError::action_failed)?;
+
+        let announcements = nexus
+            .bgp_announcement_list(
+                &opctx,
+                &BgpAnnounceSetSelector {
+                    name_or_id: NameOrId::Id(p.bgp_announce_set_id),
+                },
+            )
+            .await
+            .map_err(ActionError::action_failed)?;
+
+        peers.push(BgpPeerSettings {
+            interface_name: p.interface_name.clone(),
+            addr: p.addr.ip(),
+            asn: config.asn.into(),
+            vrf: config.vrf,
+            announcements: announcements.iter().map(|a| a.network).collect(),
+        });
+    }
+
+    validate_bgp_peer_settings(&settings, &peers)
+        .map_err(ActionError::action_failed)?;
+
+    Ok(peers)
+}
+
+/// Check that each BGP peer can be reached from the port: a peer session is
+/// established over an interface address of the same family as the peer.
+fn validate_bgp_peer_settings(
+    settings: &SwitchPortSettingsCombinedResult,
+    peers: &[BgpPeerSettings],
+) -> Result<(), String> {
+    for peer in peers {
+        let reachable = settings.addresses.iter().any(|a| {
+            a.interface_name == peer.interface_name
+                && a.address.is_ipv4() == peer.addr.is_ipv4()
+        });
+        if !reachable {
+            return Err(format!(
+                "BGP peer {} has no address of the same family on \
+                interface {}",
+                peer.addr, peer.interface_name,
+            ));
+        }
+    }
+    Ok(())
+}
+
 pub(crate) fn api_to_dpd_port_settings(
     settings: &SwitchPortSettingsCombinedResult,
 ) -> Result<PortSettings, String> {
@@ -229,6 +314,24 @@ async fn spa_ensure_switch_port_settings(
     .await
     .map_err(|e| ActionError::action_failed(e.to_string()))?;
 
+    // TODO Hand BGP peers to the routing daemon on the switch once Nexus has
+    // a client for it. Until then they are only validated, and the port
+    // settings report them as not applied.
+    let bgp_peers =
+        sagactx.lookup::<Vec<BgpPeerSettings>>("switch_port_bgp_settings")?;
+    for peer in &bgp_peers {
+        warn!(
+            log,
+            "switch port bgp peer not applied";
+            "port" => &params.switch_port_name,
+            "interface" => &peer.interface_name,
+            "addr" => %peer.addr,
+            "asn" => peer.asn,
+            "vrf" => ?peer.vrf,
+            "announcements" => ?peer.announcements,
+        );
+    }
+
     Ok(())
 }
 
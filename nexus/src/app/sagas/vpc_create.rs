@@ -158,7 +158,7 @@ async fn svc_create_vpc_undo(
         sagactx.lookup::<(authz::Vpc, db::model::Vpc)>("vpc")?;
     osagactx
         .datastore()
-        .project_delete_vpc(&opctx, &db_vpc, &authz_vpc)
+        .project_delete_vpc(&opctx, &db_vpc, &authz_vpc, None)
         .await?;
     Ok(())
 }
@@ -365,7 +365,7 @@ async fn svc_create_subnet_undo(
 
     osagactx
         .datastore()
-        .vpc_delete_subnet(&opctx, &db_subnet, &authz_subnet)
+        .vpc_delete_subnet(&opctx, &db_subnet, &authz_subnet, None)
         .await?;
     Ok(())
 }
@@ -484,6 +484,7 @@ pub(crate) mod test {
                 },
                 ipv6_prefix: None,
                 dns_name: "abc".parse().unwrap(),
+                labels: Default::default(),
             },
             authz_project,
         }
@@ -532,7 +533,7 @@ pub(crate) mod test {
             .await
             .expect("Failed to fetch default Subnet");
         datastore
-            .vpc_delete_subnet(&opctx, &subnet, &authz_subnet)
+            .vpc_delete_subnet(&opctx, &subnet, &authz_subnet, None)
             .await
             .expect("Failed to delete default Subnet");
 
@@ -575,7 +576,7 @@ pub(crate) mod test {
             .await
             .expect("Failed to delete all firewall rules for VPC");
         datastore
-            .project_delete_vpc(&opctx, &vpc, &authz_vpc)
+            .project_delete_vpc(&opctx, &vpc, &authz_vpc, None)
             .await
             .expect("Failed to delete VPC");
     }
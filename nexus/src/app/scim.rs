@@ -0,0 +1,529 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! SCIM 2.0 provisioning of Silo users and groups
+//!
+//! In Silos whose identity mode is `saml_scim`, an external identity provider
+//! acting as a SCIM client (see RFC 7644) creates, updates and deactivates the
+//! Silo's users and groups ahead of time.  SAML login then only finds users;
+//! it never creates them or changes their group memberships.
+//!
+//! SCIM clients authenticate with bearer tokens created through the system
+//! API (`/v1/system/scim/tokens`), which requires permission to modify the
+//! Silo.  Each token belongs to exactly one Silo, which is how the protocol
+//! endpoints know which Silo a request is for.  Having identified the Silo,
+//! those endpoints act using the Nexus "external authentication" context, the
+//! same one used to provision users during JIT login.  Requests that change
+//! users or groups are recorded in the audit log as made by the token, in its
+//! Silo.
+
+use crate::authz;
+use crate::authz::ApiResource;
+use crate::db;
+use crate::db::identity::Asset;
+use crate::db::lookup;
+use crate::db::lookup::LookupPath;
+use crate::db::model::ScimClientBearerToken;
+use crate::db::model::UserProvisionType;
+use crate::external_api::params;
+use chrono::Utc;
+use nexus_db_queries::context::OpContext;
+use omicron_common::api::external::CreateResult;
+use omicron_common::api::external::DataPageParams;
+use omicron_common::api::external::DeleteResult;
+use omicron_common::api::external::Error;
+use omicron_common::api::external::ListResultVec;
+use omicron_common::api::external::LookupResult;
+use omicron_common::api::external::LookupType;
+use omicron_common::api::external::UpdateResult;
+use uuid::Uuid;
+
+/// Changes that a SCIM client asked to make to a group's membership
+pub enum ScimGroupMembersUpdate {
+    /// Make the group's members exactly these users
+    Replace(Vec<Uuid>),
+    /// Add these users to the group and remove those from it
+    Modify { add: Vec<Uuid>, remove: Vec<Uuid> },
+}
+
+impl super::Nexus {
+    // Bearer tokens
+
+    /// Fetch a Silo for the purpose of managing its SCIM client tokens
+    async fn scim_silo_fetch(
+        &self,
+        silo_lookup: &lookup::Silo<'_>,
+    ) -> LookupResult<authz::Silo> {
+        let (authz_silo, db_silo) =
+            silo_lookup.fetch_for(authz::Action::Modify).await?;
+        if db_silo.user_provision_type != UserProvisionType::Scim {
+            return Err(Error::invalid_request(
+                "SCIM client tokens can only be created in Silos whose users \
+                are provisioned with SCIM",
+            ));
+        }
+        Ok(authz_silo)
+    }
+
+    pub async fn scim_client_bearer_token_create(
+        &self,
+        opctx: &OpContext,
+        silo_lookup: &lookup::Silo<'_>,
+        params: params::ScimClientBearerTokenCreate,
+    ) -> CreateResult<ScimClientBearerToken> {
+        let authz_silo = self.scim_silo_fetch(silo_lookup).await?;
+        if let Some(time_expires) = params.time_expires {
+            if time_expires <= Utc::now() {
+                return Err(Error::invalid_request(
+                    "token expiration time must be in the future",
+                ));
+            }
+        }
+        let token =
+            ScimClientBearerToken::new(authz_silo.id(), params.time_expires);
+        self.db_datastore
+            .scim_client_bearer_token_create(opctx, &authz_silo, token)
+            .await
+    }
+
+    pub async fn scim_client_bearer_tokens_list(
+        &self,
+        opctx: &OpContext,
+        silo_lookup: &lookup::Silo<'_>,
+        pagparams: &DataPageParams<'_, Uuid>,
+    ) -> ListResultVec<ScimClientBearerToken> {
+        let (authz_silo,) =
+            silo_lookup.lookup_for(authz::Action::Modify).await?;
+        self.db_datastore
+            .scim_client_bearer_tokens_list(opctx, &authz_silo, pagparams)
+            .await
+    }
+
+    pub async fn scim_client_bearer_token_delete(
+        &self,
+        opctx: &OpContext,
+        silo_lookup: &lookup::Silo<'_>,
+        token_id: Uuid,
+    ) -> DeleteResult {
+        let (authz_silo,) =
+            silo_lookup.lookup_for(authz::Action::Modify).await?;
+        self.db_datastore
+            .scim_client_bearer_token_delete(opctx, &authz_silo, token_id)
+            .await
+    }
+
+    /// Determine which Silo a SCIM client's bearer token belongs to, returning
+    /// the Silo along with the token
+    ///
+    /// Fails with `Error::Unauthenticated` if the token is unknown or has
+    /// expired, or if the Silo no longer provisions users with SCIM.
+    pub async fn scim_authenticate(
+        &self,
+        opctx: &OpContext,
+        bearer_token: &str,
+    ) -> LookupResult<(authz::Silo, ScimClientBearerToken)> {
+        let unauthenticated = |reason: &str| Error::Unauthenticated {
+            internal_message: format!("SCIM client token {}", reason),
+        };
+
+        let (authz_silo, token) = self
+            .db_datastore
+            .scim_client_bearer_token_lookup(opctx, bearer_token)
+            .await?
+            .ok_or_else(|| unauthenticated("not found"))?;
+        if token.is_expired(Utc::now()) {
+            return Err(unauthenticated("has expired"));
+        }
+
+        let (.., db_silo) = LookupPath::new(opctx, &self.db_datastore)
+            .silo_id(authz_silo.id())
+            .fetch()
+            .await
+            .map_err(|e| match e {
+                Error::ObjectNotFound { .. } => {
+                    unauthenticated("belongs to a Silo that does not exist")
+                }
+                e => e,
+            })?;
+        if db_silo.user_provision_type != UserProvisionType::Scim {
+            return Err(unauthenticated(
+                "belongs to a Silo that does not use SCIM",
+            ));
+        }
+
+        Ok((authz_silo, token))
+    }
+
+    // Users
+
+    /// List a Silo's users, optionally only the one with the given user name
+    ///
+    /// Returns the total number of matching users along with the requested
+    /// page of them.  `offset` is zero-based.
+    pub async fn scim_users_list(
+        &self,
+        opctx: &OpContext,
+        authz_silo: &authz::Silo,
+        user_name: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> LookupResult<(i64, Vec<db::model::SiloUser>)> {
+        if let Some(user_name) = user_name {
+            let user = self
+                .db_datastore
+                .silo_user_fetch_by_external_id(opctx, authz_silo, user_name)
+                .await?
+                .map(|(_, db_silo_user)| db_silo_user);
+            let total = i64::from(user.is_some());
+            let users = user.into_iter().skip(offset as usize).collect();
+            return Ok((total, users));
+        }
+
+        let authz_silo_user_list = authz::SiloUserList::new(authz_silo.clone());
+        let total = self
+            .db_datastore
+            .silo_users_count(opctx, &authz_silo_user_list)
+            .await?;
+        let users = self
+            .db_datastore
+            .silo_users_list_by_offset(
+                opctx,
+                &authz_silo_user_list,
+                offset,
+                limit,
+            )
+            .await?;
+        Ok((total, users))
+    }
+
+    pub async fn scim_user_fetch(
+        &self,
+        opctx: &OpContext,
+        authz_silo: &authz::Silo,
+        silo_user_id: Uuid,
+    ) -> LookupResult<db::model::SiloUser> {
+        let (_, db_silo_user) = self
+            .silo_user_lookup_by_id(
+                opctx,
+                authz_silo,
+                silo_user_id,
+                authz::Action::Read,
+            )
+            .await?;
+        Ok(db_silo_user)
+    }
+
+    pub async fn scim_user_create(
+        &self,
+        opctx: &OpContext,
+        authz_silo: &authz::Silo,
+        user_name: String,
+        active: bool,
+    ) -> CreateResult<db::model::SiloUser> {
+        // As with JIT provisioning, this acts as the external authenticator,
+        // which may create children of the Silo but not of its SiloUserList.
+        // TODO-cleanup This authz check belongs in silo_user_create().
+        opctx.authorize(authz::Action::CreateChild, authz_silo).await?;
+        let mut silo_user = db::model::SiloUser::new(
+            authz_silo.id(),
+            Uuid::new_v4(),
+            user_name,
+        );
+        silo_user.active = active;
+        let (_, db_silo_user) =
+            self.db_datastore.silo_user_create(authz_silo, silo_user).await?;
+        Ok(db_silo_user)
+    }
+
+    /// Change a user's user name or whether they're active
+    ///
+    /// Deactivated users cannot log in, and any sessions or tokens they have
+    /// are deleted.
+    pub async fn scim_user_update(
+        &self,
+        opctx: &OpContext,
+        authz_silo: &authz::Silo,
+        silo_user_id: Uuid,
+        user_name: Option<String>,
+        active: Option<bool>,
+    ) -> UpdateResult<db::model::SiloUser> {
+        let (authz_silo_user, _) = self
+            .silo_user_lookup_by_id(
+                opctx,
+                authz_silo,
+                silo_user_id,
+                authz::Action::Modify,
+            )
+            .await?;
+        self.db_datastore
+            .silo_user_update(
+                opctx,
+                &authz_silo_user,
+                db::model::SiloUserUpdate {
+                    external_id: user_name,
+                    active,
+                    time_modified: Utc::now(),
+                },
+            )
+            .await
+    }
+
+    pub async fn scim_user_delete(
+        &self,
+        opctx: &OpContext,
+        authz_silo: &authz::Silo,
+        silo_user_id: Uuid,
+    ) -> DeleteResult {
+        let (authz_silo_user, _) = self
+            .silo_user_lookup_by_id(
+                opctx,
+                authz_silo,
+                silo_user_id,
+                authz::Action::Delete,
+            )
+            .await?;
+        self.db_datastore.silo_user_delete(opctx, &authz_silo_user).await
+    }
+
+    // Groups
+
+    /// Look up a group, validating that it's in the expected Silo
+    async fn scim_group_lookup_by_id(
+        &self,
+        opctx: &OpContext,
+        authz_silo: &authz::Silo,
+        silo_group_id: Uuid,
+        action: authz::Action,
+    ) -> LookupResult<(authz::SiloGroup, db::model::SiloGroup)> {
+        let (_, authz_silo_group, db_silo_group) =
+            LookupPath::new(opctx, &self.db_datastore)
+                .silo_group_id(silo_group_id)
+                .fetch_for(action)
+                .await?;
+        if db_silo_group.silo_id != authz_silo.id() {
+            return Err(authz_silo_group.not_found());
+        }
+        Ok((authz_silo_group, db_silo_group))
+    }
+
+    /// List the ids of a group's members
+    pub async fn scim_group_members(
+        &self,
+        opctx: &OpContext,
+        authz_silo: &authz::Silo,
+        db_silo_group: &db::model::SiloGroup,
+    ) -> ListResultVec<Uuid> {
+        assert_eq!(authz_silo.id(), db_silo_group.silo_id);
+        let authz_silo_group = authz::SiloGroup::new(
+            authz_silo.clone(),
+            db_silo_group.id(),
+            LookupType::ById(db_silo_group.id()),
+        );
+        Ok(self
+            .db_datastore
+            .silo_group_membership_for_group(opctx, &authz_silo_group)
+            .await?
+            .into_iter()
+            .map(|membership| membership.silo_user_id)
+            .collect())
+    }
+
+    /// List a Silo's groups, optionally only the one with the given display
+    /// name
+    ///
+    /// Returns the total number of matching groups along with the requested
+    /// page of them.  `offset` is zero-based.
+    pub async fn scim_groups_list(
+        &self,
+        opctx: &OpContext,
+        authz_silo: &authz::Silo,
+        display_name: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> LookupResult<(i64, Vec<db::model::SiloGroup>)> {
+        if let Some(display_name) = display_name {
+            let group = self
+                .db_datastore
+                .silo_group_optional_lookup(
+                    opctx,
+                    authz_silo,
+                    display_name.to_string(),
+                )
+                .await?;
+            let total = i64::from(group.is_some());
+            let groups = group.into_iter().skip(offset as usize).collect();
+            return Ok((total, groups));
+        }
+
+        let total =
+            self.db_datastore.silo_groups_count(opctx, authz_silo).await?;
+        let groups = self
+            .db_datastore
+            .silo_groups_list_by_offset(opctx, authz_silo, offset, limit)
+            .await?;
+        Ok((total, groups))
+    }
+
+    pub async fn scim_group_fetch(
+        &self,
+        opctx: &OpContext,
+        authz_silo: &authz::Silo,
+        silo_group_id: Uuid,
+    ) -> LookupResult<db::model::SiloGroup> {
+        let (_, db_silo_group) = self
+            .scim_group_lookup_by_id(
+                opctx,
+                authz_silo,
+                silo_group_id,
+                authz::Action::Read,
+            )
+            .await?;
+        Ok(db_silo_group)
+    }
+
+    pub async fn scim_group_create(
+        &self,
+        opctx: &OpContext,
+        authz_silo: &authz::Silo,
+        display_name: String,
+        members: Vec<Uuid>,
+    ) -> CreateResult<db::model::SiloGroup> {
+        self.scim_validate_members(opctx, authz_silo, &members).await?;
+        let db_silo_group = self
+            .db_datastore
+            .silo_group_create(
+                opctx,
+                authz_silo,
+                db::model::SiloGroup::new(
+                    Uuid::new_v4(),
+                    authz_silo.id(),
+                    display_name,
+                ),
+            )
+            .await?;
+        // TODO These two steps should happen in a transaction.
+        if !members.is_empty() {
+            self.scim_group_update(
+                opctx,
+                authz_silo,
+                db_silo_group.id(),
+                None,
+                Some(ScimGroupMembersUpdate::Replace(members)),
+            )
+            .await?;
+        }
+        Ok(db_silo_group)
+    }
+
+    /// Change a group's display name or membership
+    pub async fn scim_group_update(
+        &self,
+        opctx: &OpContext,
+        authz_silo: &authz::Silo,
+        silo_group_id: Uuid,
+        display_name: Option<String>,
+        members: Option<ScimGroupMembersUpdate>,
+    ) -> UpdateResult<db::model::SiloGroup> {
+        let (authz_silo_group, mut db_silo_group) = self
+            .scim_group_lookup_by_id(
+                opctx,
+                authz_silo,
+                silo_group_id,
+                authz::Action::Modify,
+            )
+            .await?;
+
+        if let Some(members) = members {
+            let (replace, add, remove) = match members {
+                ScimGroupMembersUpdate::Replace(members) => {
+                    (true, members, vec![])
+                }
+                ScimGroupMembersUpdate::Modify { add, remove } => {
+                    (false, add, remove)
+                }
+            };
+            self.scim_validate_members(opctx, authz_silo, &add).await?;
+            self.db_datastore
+                .silo_group_membership_update_for_group(
+                    opctx,
+                    &authz_silo_group,
+                    replace,
+                    add,
+                    remove,
+                )
+                .await?;
+        }
+
+        if display_name.is_some() {
+            db_silo_group = self
+                .db_datastore
+                .silo_group_update(
+                    opctx,
+                    &authz_silo_group,
+                    db::model::SiloGroupUpdate {
+                        external_id: display_name,
+                        time_modified: Utc::now(),
+                    },
+                )
+                .await?;
+        }
+
+        Ok(db_silo_group)
+    }
+
+    /// Delete a group, first removing all of its members
+    pub async fn scim_group_delete(
+        &self,
+        opctx: &OpContext,
+        authz_silo: &authz::Silo,
+        silo_group_id: Uuid,
+    ) -> DeleteResult {
+        let (authz_silo_group, _) = self
+            .scim_group_lookup_by_id(
+                opctx,
+                authz_silo,
+                silo_group_id,
+                authz::Action::Delete,
+            )
+            .await?;
+        // TODO These two steps should happen in a transaction.
+        self.db_datastore
+            .silo_group_membership_update_for_group(
+                opctx,
+                &authz_silo_group,
+                true,
+                vec![],
+                vec![],
+            )
+            .await?;
+        self.db_datastore.silo_group_delete(opctx, &authz_silo_group).await
+    }
+
+    /// Check that the users a SCIM client wants to add to a group exist in
+    /// the group's Silo
+    async fn scim_validate_members(
+        &self,
+        opctx: &OpContext,
+        authz_silo: &authz::Silo,
+        members: &[Uuid],
+    ) -> Result<(), Error> {
+        // TODO-scalability This should be one query.
+        for silo_user_id in members {
+            self.silo_user_lookup_by_id(
+                opctx,
+                authz_silo,
+                *silo_user_id,
+                authz::Action::Read,
+            )
+            .await
+            .map_err(|e| match e {
+                Error::ObjectNotFound { .. } => Error::invalid_request(
+                    &format!("group member {} does not exist", silo_user_id),
+                ),
+                e => e,
+            })?;
+        }
+        Ok(())
+    }
+}
@@ -156,7 +156,8 @@ impl super::Nexus {
                 | Error::ServiceUnavailable { .. }
                 | Error::MethodNotAllowed { .. }
                 | Error::TypeVersionMismatch { .. }
-                | Error::Conflict { .. } => {
+                | Error::Conflict { .. }
+                | Error::PreconditionFailed { .. } => {
                     Reason::UnknownError { source: error }
                 }
             })?;
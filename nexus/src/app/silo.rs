@@ -162,21 +162,45 @@ impl super::Nexus {
         Ok(shared::Policy { role_assignments })
     }
 
-    pub async fn silo_update_policy(
+    /// Replaces a silo's IAM policy
+    ///
+    /// `precondition` is invoked with the current policy in the same
+    /// transaction that replaces it.  If it returns an error, the policy is
+    /// left unchanged and that error is returned.
+    pub async fn silo_update_policy<P>(
         &self,
         opctx: &OpContext,
         silo_lookup: &lookup::Silo<'_>,
         policy: &shared::Policy<shared::SiloRole>,
-    ) -> UpdateResult<shared::Policy<shared::SiloRole>> {
+        precondition: P,
+    ) -> UpdateResult<shared::Policy<shared::SiloRole>>
+    where
+        P: FnOnce(&shared::Policy<shared::SiloRole>) -> Result<(), Error>
+            + Send,
+    {
         let (.., authz_silo) =
             silo_lookup.lookup_for(authz::Action::ModifyPolicy).await?;
 
         let role_assignments = self
             .db_datastore
-            .role_assignment_replace_visible(
+            .role_assignment_replace_visible_conditional(
                 opctx,
                 &authz_silo,
                 &policy.role_assignments,
+                |current| {
+                    let role_assignments = current
+                        .iter()
+                        .cloned()
+                        .map(|r| {
+                            r.try_into()
+                                .context("parsing database role assignment")
+                        })
+                        .collect::<Result<Vec<_>, _>>()
+                        .map_err(|error| {
+                            Error::internal_error(&format!("{:#}", error))
+                        })?;
+                    precondition(&shared::Policy { role_assignments })
+                },
             )
             .await?
             .into_iter()
@@ -186,13 +210,38 @@ impl super::Nexus {
         Ok(shared::Policy { role_assignments })
     }
 
+    // Quotas
+
+    pub async fn silo_quota_view(
+        &self,
+        opctx: &OpContext,
+        silo_lookup: &lookup::Silo<'_>,
+    ) -> LookupResult<db::model::VirtualProvisioningQuota> {
+        let (.., authz_silo) =
+            silo_lookup.lookup_for(authz::Action::Read).await?;
+        self.db_datastore.silo_quota_view(opctx, &authz_silo).await
+    }
+
+    pub async fn silo_quota_update(
+        &self,
+        opctx: &OpContext,
+        silo_lookup: &lookup::Silo<'_>,
+        updates: &params::QuotaUpdate,
+    ) -> UpdateResult<db::model::VirtualProvisioningQuota> {
+        let (.., authz_silo) =
+            silo_lookup.lookup_for(authz::Action::Read).await?;
+        self.db_datastore
+            .silo_quota_update(opctx, &authz_silo, updates.clone())
+            .await
+    }
+
     // Users
 
     /// Helper function for looking up a user in a Silo
     ///
     /// `LookupPath` lets you look up users directly, regardless of what Silo
     /// they're in.  This helper validates that they're in the expected Silo.
-    async fn silo_user_lookup_by_id(
+    pub(super) async fn silo_user_lookup_by_id(
         &self,
         opctx: &OpContext,
         authz_silo: &authz::Silo,
@@ -347,15 +396,20 @@ impl super::Nexus {
 
         let (authz_silo_user, db_silo_user) =
             if let Some(existing_silo_user) = fetch_result {
+                // Users deactivated by a SCIM client cannot log in.
+                if !existing_silo_user.1.active {
+                    return Ok(None);
+                }
                 existing_silo_user
             } else {
                 // In this branch, no user exists for the authenticated subject
                 // external id. The next action depends on the silo's user
                 // provision type.
                 match db_silo.user_provision_type {
-                    // If the user provision type is ApiOnly, do not create a
-                    // new user if one does not exist.
-                    db::model::UserProvisionType::ApiOnly => {
+                    // If the user provision type is ApiOnly or Scim, do not
+                    // create a new user if one does not exist.
+                    db::model::UserProvisionType::ApiOnly
+                    | db::model::UserProvisionType::Scim => {
                         return Ok(None);
                     }
 
@@ -375,6 +429,12 @@ impl super::Nexus {
                 }
             };
 
+        // In Scim silos, group memberships are managed by the SCIM client, not
+        // by whatever groups the IdP sends at login.
+        if db_silo.user_provision_type == db::model::UserProvisionType::Scim {
+            return Ok(Some(db_silo_user));
+        }
+
         // Gather a list of groups that the user is part of based on what the
         // IdP sent us. Also, if the silo user provision type is Jit, create
         // silo groups if new groups from the IdP are seen.
@@ -384,7 +444,8 @@ impl super::Nexus {
 
         for group in &authenticated_subject.groups {
             let silo_group = match db_silo.user_provision_type {
-                db::model::UserProvisionType::ApiOnly => {
+                db::model::UserProvisionType::ApiOnly
+                | db::model::UserProvisionType::Scim => {
                     self.db_datastore
                         .silo_group_optional_lookup(
                             opctx,
@@ -718,6 +779,41 @@ impl super::Nexus {
         }
     }
 
+    pub fn oidc_identity_provider_lookup<'a>(
+        &'a self,
+        opctx: &'a OpContext,
+        oidc_identity_provider_selector: params::OidcIdentityProviderSelector,
+    ) -> LookupResult<lookup::OidcIdentityProvider<'a>> {
+        match oidc_identity_provider_selector {
+            params::OidcIdentityProviderSelector {
+                oidc_identity_provider: NameOrId::Id(id),
+                silo: None,
+            } => {
+                let oidc_provider = LookupPath::new(opctx, &self.db_datastore)
+                    .oidc_identity_provider_id(id);
+                Ok(oidc_provider)
+            }
+            params::OidcIdentityProviderSelector {
+                oidc_identity_provider: NameOrId::Name(name),
+                silo: Some(silo),
+            } => {
+                let oidc_provider = self
+                    .silo_lookup(opctx, silo)?
+                    .oidc_identity_provider_name_owned(name.into());
+                Ok(oidc_provider)
+            }
+            params::OidcIdentityProviderSelector {
+                oidc_identity_provider: NameOrId::Id(_),
+                silo: _,
+            } => Err(Error::invalid_request(
+                "when providing provider as an ID, silo should not be specified",
+            )),
+            _ => Err(Error::invalid_request(
+                "provider should either be a UUID or silo should be specified",
+            )),
+        }
+    }
+
     pub async fn identity_provider_list(
         &self,
         opctx: &OpContext,
@@ -744,7 +840,9 @@ impl super::Nexus {
         let (authz_silo, db_silo) = silo_lookup.fetch().await?;
         let authz_idp_list = authz::SiloIdentityProviderList::new(authz_silo);
 
-        if db_silo.user_provision_type != UserProvisionType::Jit {
+        // SAML identity providers authenticate users in both JIT and SCIM
+        // Silos.
+        if db_silo.user_provision_type == UserProvisionType::ApiOnly {
             return Err(Error::invalid_request(
                 "cannot create identity providers in this kind of Silo",
             ));
@@ -919,6 +1017,132 @@ impl super::Nexus {
             .await
     }
 
+    pub async fn oidc_identity_provider_create(
+        &self,
+        opctx: &OpContext,
+        silo_lookup: &lookup::Silo<'_>,
+        params: params::OidcIdentityProviderCreate,
+    ) -> CreateResult<db::model::OidcIdentityProvider> {
+        // TODO-security: This should likely be fetch_for CreateChild on the silo
+        let (authz_silo, db_silo) = silo_lookup.fetch().await?;
+        let authz_idp_list = authz::SiloIdentityProviderList::new(authz_silo);
+
+        if db_silo.user_provision_type != UserProvisionType::Jit {
+            return Err(Error::invalid_request(
+                "cannot create identity providers in this kind of Silo",
+            ));
+        }
+
+        // As with SAML, check this now to protect the code that fetches the
+        // discovery document and keys from an external source.
+        opctx.authorize(authz::Action::CreateChild, &authz_idp_list).await?;
+
+        // The authentication mode is immutable so it's safe to check this here
+        // and bail out.
+        if db_silo.authentication_mode
+            != nexus_db_model::AuthenticationMode::Oidc
+        {
+            return Err(Error::invalid_request(&format!(
+                "cannot create OpenID Connect identity provider for this Silo \
+                type (expected authentication mode {:?}, found {:?})",
+                nexus_db_model::AuthenticationMode::Oidc,
+                &db_silo.authentication_mode,
+            )));
+        }
+
+        // Like the SAML IdP descriptor, the discovery document and the keys
+        // that it points to are fetched only once and stored.
+        let discovery_document_string = match &params.discovery_source {
+            params::OidcDiscoverySource::Url { url } => {
+                fetch_identity_provider_document(url).await?
+            }
+            params::OidcDiscoverySource::Base64EncodedJson { data } => {
+                let bytes = base64::Engine::decode(
+                    &base64::engine::general_purpose::STANDARD,
+                    data,
+                )
+                .map_err(|e| Error::InvalidValue {
+                    label: String::from("data"),
+                    message: format!(
+                        "error getting decoding base64 data: {}",
+                        e
+                    ),
+                })?;
+                String::from_utf8_lossy(&bytes).into_owned()
+            }
+        };
+
+        let discovery: authn::oidc::DiscoveryDocument =
+            serde_json::from_str(&discovery_document_string).map_err(|e| {
+                Error::invalid_request(&format!(
+                    "discovery document could not be parsed: {}",
+                    e
+                ))
+            })?;
+
+        // The issuer must be the URL that the discovery document was fetched
+        // from, less the well-known suffix (see OpenID Connect Discovery 1.0,
+        // Section 4.3).
+        if let params::OidcDiscoverySource::Url { url } =
+            &params.discovery_source
+        {
+            let expected = format!(
+                "{}/.well-known/openid-configuration",
+                discovery.issuer.trim_end_matches('/')
+            );
+            if *url != expected {
+                return Err(Error::invalid_request(&format!(
+                    "discovery document issuer {:?} does not match url",
+                    discovery.issuer
+                )));
+            }
+        }
+
+        let jwks_string =
+            fetch_identity_provider_document(&discovery.jwks_uri).await?;
+        let jwks: authn::oidc::JsonWebKeySet =
+            serde_json::from_str(&jwks_string).map_err(|e| {
+                Error::invalid_request(&format!(
+                    "JSON Web Key Set could not be parsed: {}",
+                    e
+                ))
+            })?;
+
+        let provider = db::model::OidcIdentityProvider {
+            identity: db::model::OidcIdentityProviderIdentity::new(
+                Uuid::new_v4(),
+                params.identity,
+            ),
+            silo_id: db_silo.id(),
+
+            issuer: discovery.issuer,
+            authorization_endpoint: discovery.authorization_endpoint,
+            token_endpoint: discovery.token_endpoint,
+            // Only the public key parameters that we parsed are stored.
+            jwks: serde_json::to_string(&jwks).map_err(|e| {
+                Error::internal_error(&format!("serializing jwks: {}", e))
+            })?,
+
+            client_id: params.client_id,
+            client_secret: params.client_secret,
+            redirect_uri: params.redirect_uri,
+            scopes: params.scopes.join(" "),
+
+            group_claim_name: params.group_claim_name,
+        };
+
+        let _authn_provider: authn::oidc::OidcIdentityProvider =
+            provider.clone().try_into().map_err(|e: anyhow::Error|
+                // If an error is encountered converting from the model to the
+                // authn type here, this is a request error: something about the
+                // parameters of this request doesn't work.
+                Error::invalid_request(&format!("{:#}", e)))?;
+
+        self.db_datastore
+            .oidc_identity_provider_create(opctx, &authz_idp_list, provider)
+            .await
+    }
+
     pub fn silo_group_lookup<'a>(
         &'a self,
         opctx: &'a OpContext,
@@ -928,6 +1152,44 @@ impl super::Nexus {
     }
 }
 
+/// Downloads a document (e.g., a discovery document or key set) that describes
+/// an external identity provider
+async fn fetch_identity_provider_document(url: &str) -> Result<String, Error> {
+    let dur = std::time::Duration::from_secs(5);
+    let client = reqwest::ClientBuilder::new()
+        .connect_timeout(dur)
+        .timeout(dur)
+        .build()
+        .map_err(|e| {
+            Error::internal_error(&format!(
+                "failed to build reqwest client: {}",
+                e
+            ))
+        })?;
+
+    let response =
+        client.get(url).send().await.map_err(|e| Error::InvalidValue {
+            label: String::from("url"),
+            message: format!("error querying url {}: {}", url, e),
+        })?;
+
+    if !response.status().is_success() {
+        return Err(Error::InvalidValue {
+            label: String::from("url"),
+            message: format!(
+                "querying url {} returned: {}",
+                url,
+                response.status()
+            ),
+        });
+    }
+
+    response.text().await.map_err(|e| Error::InvalidValue {
+        label: String::from("url"),
+        message: format!("error getting text from url {}: {}", url, e),
+    })
+}
+
 /// Returns the (relative) DNS name for this Silo's API and console endpoints
 /// _within_ the external DNS zone (i.e., without that zone's suffix)
 ///
@@ -3,19 +3,27 @@ use std::sync::Arc;
 use crate::authn;
 use crate::authz;
 use crate::db;
+use crate::db::identity::Resource;
 use crate::db::lookup;
 use crate::db::lookup::LookupPath;
 use nexus_db_queries::context::OpContext;
 use nexus_types::external_api::params;
 use nexus_types::external_api::params::DiskSelector;
+use nexus_types::external_api::shared;
+use nexus_types::external_api::views;
 use omicron_common::api::external::http_pagination::PaginatedBy;
 use omicron_common::api::external::CreateResult;
 use omicron_common::api::external::DeleteResult;
 use omicron_common::api::external::Error;
 use omicron_common::api::external::InstanceState;
+use omicron_common::api::external::LabelSelector;
+use omicron_common::api::external::Labels;
 use omicron_common::api::external::ListResultVec;
 use omicron_common::api::external::LookupResult;
 use omicron_common::api::external::NameOrId;
+use omicron_common::api::external::UpdateResult;
+use serde_json::json;
+use uuid::Uuid;
 
 use super::sagas;
 
@@ -63,6 +71,19 @@ impl super::Nexus {
         // Is passed by value due to `disk_name` taking ownership of `self` below
         project_lookup: lookup::Project<'_>,
         params: &params::SnapshotCreate,
+    ) -> CreateResult<db::model::Snapshot> {
+        self.snapshot_create_for_schedule(opctx, project_lookup, params, None)
+            .await
+    }
+
+    /// Creates a snapshot, recording that it was taken by the snapshot
+    /// schedule `snapshot_schedule_id`, if any
+    pub(crate) async fn snapshot_create_for_schedule(
+        self: &Arc<Self>,
+        opctx: &OpContext,
+        project_lookup: lookup::Project<'_>,
+        params: &params::SnapshotCreate,
+        snapshot_schedule_id: Option<Uuid>,
     ) -> CreateResult<db::model::Snapshot> {
         let authz_silo: authz::Silo;
         let authz_disk_project: authz::Project;
@@ -137,6 +158,9 @@ impl super::Nexus {
             disk_id: authz_disk.id(),
             use_the_pantry,
             create_params: params.clone(),
+            snapshot_schedule_id,
+            disk_in_maintenance: false,
+            transient: false,
         };
 
         let saga_outputs = self
@@ -151,6 +175,18 @@ impl super::Nexus {
                 internal_message: e.to_string(),
             })?;
 
+        self.webhook_event_publish(
+            Some(authz_silo.id()),
+            shared::WebhookEventClass::SnapshotComplete,
+            json!({
+                "snapshot_id": snapshot_created.id(),
+                "disk_id": authz_disk.id(),
+                "project_id": authz_project.id(),
+                "snapshot_schedule_id": snapshot_schedule_id,
+            }),
+        )
+        .await;
+
         Ok(snapshot_created)
     }
 
@@ -159,11 +195,32 @@ impl super::Nexus {
         opctx: &OpContext,
         project_lookup: &lookup::Project<'_>,
         pagparams: &PaginatedBy<'_>,
+        label_selector: Option<&LabelSelector>,
     ) -> ListResultVec<db::model::Snapshot> {
         let (.., authz_project) =
             project_lookup.lookup_for(authz::Action::ListChildren).await?;
 
-        self.db_datastore.snapshot_list(opctx, &authz_project, pagparams).await
+        self.db_datastore
+            .snapshot_list(opctx, &authz_project, pagparams, label_selector)
+            .await
+    }
+
+    /// Replaces the labels on a Snapshot
+    pub async fn snapshot_labels_update(
+        &self,
+        opctx: &OpContext,
+        snapshot_lookup: &lookup::Snapshot<'_>,
+        labels: &Labels,
+    ) -> UpdateResult<db::model::Snapshot> {
+        let (.., authz_snapshot) =
+            snapshot_lookup.lookup_for(authz::Action::Modify).await?;
+        self.db_datastore
+            .snapshot_labels_update(
+                opctx,
+                &authz_snapshot,
+                labels.clone().into(),
+            )
+            .await
     }
 
     pub async fn snapshot_delete(
@@ -181,6 +238,12 @@ impl super::Nexus {
         let (.., authz_snapshot, db_snapshot) =
             snapshot_lookup.fetch_for(authz::Action::Delete).await?;
 
+        if db_snapshot.pantry_address.is_some() {
+            return Err(Error::invalid_request(
+                "cannot delete a snapshot that is being exported",
+            ));
+        }
+
         let saga_params = sagas::snapshot_delete::Params {
             serialized_authn: authn::saga::Serialized::for_opctx(opctx),
             authz_snapshot,
@@ -192,4 +255,83 @@ impl super::Nexus {
         .await?;
         Ok(())
     }
+
+    /// Attach a snapshot to a Pantry so that its blocks can be read out with
+    /// bulk reads.
+    pub async fn snapshot_export_start(
+        self: &Arc<Self>,
+        opctx: &OpContext,
+        snapshot_lookup: &lookup::Snapshot<'_>,
+    ) -> UpdateResult<()> {
+        let (.., authz_snapshot, db_snapshot) =
+            snapshot_lookup.fetch_for(authz::Action::Modify).await?;
+
+        if db_snapshot.state != db::model::SnapshotState::Ready {
+            return Err(Error::invalid_request(&format!(
+                "snapshot must be ready to be exported, but it is {:?}",
+                db_snapshot.state,
+            )));
+        }
+
+        if db_snapshot.pantry_address.is_some() {
+            return Err(Error::invalid_request(
+                "snapshot is already being exported",
+            ));
+        }
+
+        let saga_params = sagas::export_start::Params {
+            serialized_authn: authn::saga::Serialized::for_opctx(opctx),
+            source: sagas::export_start::ExportSource::Snapshot(
+                authz_snapshot.id(),
+            ),
+        };
+
+        self.execute_saga::<sagas::export_start::SagaExportStart>(saga_params)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Bulk read some bytes from a snapshot that is being exported
+    pub async fn snapshot_bulk_read(
+        self: &Arc<Self>,
+        snapshot_lookup: &lookup::Snapshot<'_>,
+        param: params::ExportBlocksBulkRead,
+    ) -> LookupResult<views::ExportedBlocks> {
+        let (.., db_snapshot) =
+            snapshot_lookup.fetch_for(authz::Action::Read).await?;
+
+        match db_snapshot.pantry_address() {
+            Some(endpoint) => {
+                self.pantry_bulk_read(db_snapshot.id(), endpoint, param).await
+            }
+
+            None => Err(Error::invalid_request(
+                "cannot export blocks with a bulk read for a snapshot that is \
+                not being exported",
+            )),
+        }
+    }
+
+    /// Detach a snapshot that is being exported from its Pantry
+    pub async fn snapshot_export_stop(
+        self: &Arc<Self>,
+        opctx: &OpContext,
+        snapshot_lookup: &lookup::Snapshot<'_>,
+    ) -> UpdateResult<()> {
+        let (.., authz_snapshot) =
+            snapshot_lookup.lookup_for(authz::Action::Modify).await?;
+
+        let saga_params = sagas::export_stop::Params {
+            serialized_authn: authn::saga::Serialized::for_opctx(opctx),
+            source: sagas::export_start::ExportSource::Snapshot(
+                authz_snapshot.id(),
+            ),
+        };
+
+        self.execute_saga::<sagas::export_stop::SagaExportStop>(saga_params)
+            .await?;
+
+        Ok(())
+    }
 }
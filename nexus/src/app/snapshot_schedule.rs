@@ -0,0 +1,353 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Snapshot schedules, which periodically snapshot a set of disks
+
+use super::background::SnapshotScheduleRunResult;
+use crate::authz;
+use crate::db;
+use crate::db::identity::Resource;
+use crate::db::lookup;
+use crate::db::lookup::LookupPath;
+use chrono::DateTime;
+use chrono::Utc;
+use nexus_db_queries::context::OpContext;
+use nexus_types::external_api::params;
+use nexus_types::external_api::views;
+use omicron_common::api::external::http_pagination::PaginatedBy;
+use omicron_common::api::external::CreateResult;
+use omicron_common::api::external::DeleteResult;
+use omicron_common::api::external::Error;
+use omicron_common::api::external::IdentityMetadataCreateParams;
+use omicron_common::api::external::ListResultVec;
+use omicron_common::api::external::LookupResult;
+use omicron_common::api::external::Name;
+use omicron_common::api::external::NameOrId;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Returns the name of the snapshot of a disk taken by a run of a snapshot
+/// schedule that started at `time_started`
+///
+/// The name is made from the schedule's name, the disk's name, the first eight
+/// digits of the disk's ID, and the time, with the names shortened as needed
+/// to fit.  The ID fragment tells apart disks whose names only differ after
+/// the part that's kept, and runs of a schedule are at least a second apart,
+/// so the names of the snapshots taken by one schedule are unique.
+fn scheduled_snapshot_name(
+    schedule_name: &str,
+    disk_name: &str,
+    disk_id: Uuid,
+    time_started: DateTime<Utc>,
+) -> Result<Name, Error> {
+    fn shorten(name: &str, len: usize) -> &str {
+        name.get(..len).unwrap_or(name).trim_end_matches('-')
+    }
+    let disk_id = disk_id.simple().to_string();
+    format!(
+        "{}-{}-{}-{}",
+        shorten(schedule_name, 16),
+        shorten(disk_name, 22),
+        &disk_id[..8],
+        time_started.format("%Y%m%d%H%M%S")
+    )
+    .parse()
+    .map_err(|e: String| {
+        Error::internal_error(&format!("invalid snapshot name: {}", e))
+    })
+}
+
+impl super::Nexus {
+    pub fn snapshot_schedule_lookup<'a>(
+        &'a self,
+        opctx: &'a OpContext,
+        schedule_selector: params::SnapshotScheduleSelector,
+    ) -> LookupResult<lookup::SnapshotSchedule<'a>> {
+        match schedule_selector {
+            params::SnapshotScheduleSelector {
+                schedule: NameOrId::Id(id),
+                project: None,
+            } => {
+                let schedule = LookupPath::new(opctx, &self.db_datastore)
+                    .snapshot_schedule_id(id);
+                Ok(schedule)
+            }
+            params::SnapshotScheduleSelector {
+                schedule: NameOrId::Name(name),
+                project: Some(project),
+            } => {
+                let schedule = self
+                    .project_lookup(opctx, params::ProjectSelector { project })?
+                    .snapshot_schedule_name_owned(name.into());
+                Ok(schedule)
+            }
+            params::SnapshotScheduleSelector {
+                schedule: NameOrId::Id(_),
+                ..
+            } => Err(Error::invalid_request(
+                "when providing snapshot schedule as an ID project should not be specified",
+            )),
+            _ => Err(Error::invalid_request(
+                "snapshot schedule should either be UUID or project should be specified",
+            )),
+        }
+    }
+
+    pub async fn snapshot_schedule_create(
+        &self,
+        opctx: &OpContext,
+        project_lookup: &lookup::Project<'_>,
+        params: params::SnapshotScheduleCreate,
+    ) -> CreateResult<db::model::SnapshotSchedule> {
+        let (.., authz_project) =
+            project_lookup.lookup_for(authz::Action::CreateChild).await?;
+
+        if params.interval_secs == 0 {
+            return Err(Error::invalid_request(
+                "snapshot schedule interval must be at least one second",
+            ));
+        }
+        if params.retain == 0 {
+            return Err(Error::invalid_request(
+                "snapshot schedule must retain at least one snapshot",
+            ));
+        }
+
+        let target = match &params.target {
+            params::SnapshotScheduleTargetCreate::Disk { disk } => {
+                let disk_lookup = match disk {
+                    NameOrId::Id(id) => {
+                        LookupPath::new(opctx, &self.db_datastore).disk_id(*id)
+                    }
+                    NameOrId::Name(name) => {
+                        LookupPath::new(opctx, &self.db_datastore)
+                            .project_id(authz_project.id())
+                            .disk_name_owned(name.clone().into())
+                    }
+                };
+                let (.., authz_disk_project, authz_disk) =
+                    disk_lookup.lookup_for(authz::Action::Read).await?;
+                if authz_disk_project.id() != authz_project.id() {
+                    return Err(Error::invalid_request(
+                        "disk must be in the same project as the snapshot \
+                         schedule",
+                    ));
+                }
+                views::SnapshotScheduleTarget::Disk { disk_id: authz_disk.id() }
+            }
+            params::SnapshotScheduleTargetCreate::Labels { labels } => {
+                if labels.is_empty() {
+                    return Err(Error::invalid_request(
+                        "snapshot schedule must target disks by at least \
+                         one label",
+                    ));
+                }
+                views::SnapshotScheduleTarget::Labels { labels: labels.clone() }
+            }
+        };
+
+        let schedule = db::model::SnapshotSchedule::new(
+            authz_project.id(),
+            params,
+            target,
+        );
+        self.db_datastore
+            .snapshot_schedule_create(opctx, &authz_project, schedule)
+            .await
+    }
+
+    pub async fn snapshot_schedule_list(
+        &self,
+        opctx: &OpContext,
+        project_lookup: &lookup::Project<'_>,
+        pagparams: &PaginatedBy<'_>,
+    ) -> ListResultVec<db::model::SnapshotSchedule> {
+        let (.., authz_project) =
+            project_lookup.lookup_for(authz::Action::ListChildren).await?;
+
+        self.db_datastore
+            .snapshot_schedule_list(opctx, &authz_project, pagparams)
+            .await
+    }
+
+    pub async fn snapshot_schedule_delete(
+        &self,
+        opctx: &OpContext,
+        schedule_lookup: &lookup::SnapshotSchedule<'_>,
+    ) -> DeleteResult {
+        let (.., authz_schedule) =
+            schedule_lookup.lookup_for(authz::Action::Delete).await?;
+
+        self.db_datastore.snapshot_schedule_delete(opctx, &authz_schedule).await
+    }
+
+    /// Runs a snapshot schedule: snapshots each of the disks it targets, then
+    /// deletes the oldest of the snapshots of each disk taken by the schedule,
+    /// beyond the number it retains
+    ///
+    /// A failure to snapshot one disk doesn't stop the others from being
+    /// snapshotted, but old snapshots of a disk are only deleted once a new
+    /// one has been taken.  Everything that went wrong is reported in the
+    /// result.
+    pub(crate) async fn snapshot_schedule_run(
+        self: &Arc<Self>,
+        opctx: &OpContext,
+        schedule_id: Uuid,
+        time_started: DateTime<Utc>,
+    ) -> SnapshotScheduleRunResult {
+        let mut result = SnapshotScheduleRunResult::default();
+
+        let lookup = LookupPath::new(opctx, &self.db_datastore)
+            .snapshot_schedule_id(schedule_id);
+        let (_, authz_project, _, db_schedule) = match lookup.fetch().await {
+            Ok(found) => found,
+            Err(error) => {
+                result.errors.push(format!("{:#}", error));
+                return result;
+            }
+        };
+        let disks = match self
+            .db_datastore
+            .snapshot_schedule_disk_list(opctx, &authz_project, &db_schedule)
+            .await
+        {
+            Ok(disks) => disks,
+            Err(error) => {
+                result.errors.push(format!("{:#}", error));
+                return result;
+            }
+        };
+
+        for disk in disks {
+            if let Err(error) = self
+                .snapshot_schedule_run_disk(
+                    opctx,
+                    &authz_project,
+                    &db_schedule,
+                    &disk,
+                    time_started,
+                    &mut result,
+                )
+                .await
+            {
+                result.errors.push(format!(
+                    "disk {:?}: {:#}",
+                    disk.name().as_str(),
+                    error
+                ));
+            }
+        }
+
+        result
+    }
+
+    /// Snapshots one disk for a run of a snapshot schedule, then deletes the
+    /// disk's old snapshots
+    async fn snapshot_schedule_run_disk(
+        self: &Arc<Self>,
+        opctx: &OpContext,
+        authz_project: &authz::Project,
+        db_schedule: &db::model::SnapshotSchedule,
+        disk: &db::model::Disk,
+        time_started: DateTime<Utc>,
+        result: &mut SnapshotScheduleRunResult,
+    ) -> Result<(), Error> {
+        let snapshot_params = params::SnapshotCreate {
+            identity: IdentityMetadataCreateParams {
+                name: scheduled_snapshot_name(
+                    db_schedule.name().as_str(),
+                    disk.name().as_str(),
+                    disk.id(),
+                    time_started,
+                )?,
+                description: format!(
+                    "taken by snapshot schedule {:?}",
+                    db_schedule.name().as_str()
+                ),
+            },
+            disk: NameOrId::Id(disk.id()),
+            labels: Default::default(),
+        };
+        let project_lookup = LookupPath::new(opctx, &self.db_datastore)
+            .project_id(authz_project.id());
+        self.snapshot_create_for_schedule(
+            opctx,
+            project_lookup,
+            &snapshot_params,
+            Some(db_schedule.id()),
+        )
+        .await?;
+        result.snapshots_created += 1;
+
+        let snapshots = self
+            .db_datastore
+            .snapshot_schedule_snapshot_list(
+                opctx,
+                authz_project,
+                db_schedule.id(),
+                disk.id(),
+            )
+            .await?;
+        let retain = usize::try_from(*db_schedule.retain).unwrap();
+        for snapshot in snapshots.into_iter().skip(retain) {
+            let snapshot_lookup = LookupPath::new(opctx, &self.db_datastore)
+                .snapshot_id(snapshot.id());
+            self.snapshot_delete(opctx, &snapshot_lookup).await?;
+            result.snapshots_deleted += 1;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::scheduled_snapshot_name;
+    use chrono::TimeZone;
+    use chrono::Utc;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_scheduled_snapshot_name() {
+        let time = Utc.with_ymd_and_hms(2023, 10, 17, 3, 4, 5).unwrap();
+        let disk_id: Uuid =
+            "0f1e2d3c-4b5a-6978-8796-a5b4c3d2e1f0".parse().unwrap();
+        assert_eq!(
+            scheduled_snapshot_name("nightly", "db-disk", disk_id, time)
+                .unwrap()
+                .as_str(),
+            "nightly-db-disk-0f1e2d3c-20231017030405"
+        );
+
+        // Long names are shortened to fit, without leaving a dangling "-".
+        let name = scheduled_snapshot_name(
+            "a-very-long-sch-edule-name",
+            "b-really-long-disk-nam-e-that-goes-on",
+            disk_id,
+            time,
+        )
+        .unwrap();
+        assert_eq!(
+            name.as_str(),
+            "a-very-long-sch-b-really-long-disk-nam-0f1e2d3c-20231017030405"
+        );
+        assert!(name.as_str().len() <= 63);
+
+        // Disks whose names only differ after the part that's kept get
+        // different snapshot names.
+        let other_disk_id: Uuid =
+            "1a2b3c4d-0000-0000-0000-000000000000".parse().unwrap();
+        let other_name = scheduled_snapshot_name(
+            "a-very-long-sch-edule-name",
+            "b-really-long-disk-nam-e-that-goes-elsewhere",
+            other_disk_id,
+            time,
+        )
+        .unwrap();
+        assert_eq!(
+            other_name.as_str(),
+            "a-very-long-sch-b-really-long-disk-nam-1a2b3c4d-20231017030405"
+        );
+        assert_ne!(name, other_name);
+    }
+}
@@ -2,10 +2,12 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
+use crate::db;
 use crate::db::lookup::LookupPath;
 use async_trait::async_trait;
 use nexus_db_queries::context::OpContext;
 use omicron_common::api::external::Error;
+use omicron_common::api::external::InstanceState;
 use sled_agent_client::Client as SledAgentClient;
 use std::sync::Arc;
 use uuid::Uuid;
@@ -36,6 +38,22 @@ pub trait TestInterfaces {
 
     async fn set_disk_as_faulted(&self, disk_id: &Uuid) -> Result<bool, Error>;
 
+    /// Marks an instance as failed, as though its sled or its Propolis server
+    /// had failed.
+    async fn set_instance_as_failed(
+        &self,
+        instance_id: &Uuid,
+    ) -> Result<bool, Error>;
+
+    /// Activates the background task that restarts failed instances.
+    fn activate_instance_auto_restart(&self);
+
+    /// Activates the background task that runs snapshot schedules.
+    fn activate_snapshot_schedules(&self);
+
+    /// Activates the background task that delivers webhook events.
+    fn activate_webhook_deliveries(&self);
+
     fn set_samael_max_issue_delay(&self, max_issue_delay: chrono::Duration);
 }
 
@@ -110,6 +128,45 @@ impl TestInterfaces for super::Nexus {
             .await
     }
 
+    async fn set_instance_as_failed(
+        &self,
+        instance_id: &Uuid,
+    ) -> Result<bool, Error> {
+        let opctx = OpContext::for_tests(
+            self.log.new(o!()),
+            Arc::clone(&self.db_datastore),
+        );
+        let (.., db_instance) = LookupPath::new(&opctx, &self.db_datastore)
+            .instance_id(*instance_id)
+            .fetch()
+            .await?;
+
+        let new_runtime = db::model::InstanceRuntimeState {
+            state: db::model::InstanceState::new(InstanceState::Failed),
+            time_updated: chrono::Utc::now(),
+            gen: db_instance.runtime_state.gen.next().into(),
+            ..db_instance.runtime_state
+        };
+        self.db_datastore
+            .instance_update_runtime(instance_id, &new_runtime)
+            .await
+    }
+
+    fn activate_instance_auto_restart(&self) {
+        self.background_tasks
+            .activate(&self.background_tasks.task_instance_auto_restart);
+    }
+
+    fn activate_snapshot_schedules(&self) {
+        self.background_tasks
+            .activate(&self.background_tasks.task_snapshot_schedules);
+    }
+
+    fn activate_webhook_deliveries(&self) {
+        self.background_tasks
+            .activate(&self.background_tasks.task_webhook_deliveries);
+    }
+
     fn set_samael_max_issue_delay(&self, max_issue_delay: chrono::Duration) {
         let mut mid = self.samael_max_issue_delay.lock().unwrap();
         *mid = Some(max_issue_delay);
@@ -12,7 +12,7 @@ use crate::db::model::KnownArtifactKind;
 use chrono::Utc;
 use hex;
 use nexus_db_queries::context::OpContext;
-use nexus_types::external_api::{params, shared};
+use nexus_types::external_api::{params, shared, views};
 use omicron_common::api::external::{
     self, CreateResult, DataPageParams, Error, ListResultVec, LookupResult,
     PaginationOrder, UpdateResult,
@@ -399,7 +399,12 @@ impl super::Nexus {
             version: db::model::SemverVersion(start.version),
             status: db::model::UpdateStatus::Updating,
         };
-        self.db_datastore.create_update_deployment(opctx, deployment).await
+        let deployment = self
+            .db_datastore
+            .create_update_deployment(opctx, deployment)
+            .await?;
+        self.update_deployment_event_publish(&deployment).await;
+        Ok(deployment)
     }
 
     /// If there's a running update, change it to steady. Otherwise do nothing.
@@ -414,7 +419,28 @@ impl super::Nexus {
             return Ok(latest);
         }
 
-        self.db_datastore.steady_update_deployment(opctx, latest.id()).await
+        let deployment = self
+            .db_datastore
+            .steady_update_deployment(opctx, latest.id())
+            .await?;
+        self.update_deployment_event_publish(&deployment).await;
+        Ok(deployment)
+    }
+
+    /// Tells webhook receivers in every silo how an update deployment is
+    /// going
+    async fn update_deployment_event_publish(
+        &self,
+        deployment: &db::model::UpdateDeployment,
+    ) {
+        self.webhook_event_publish(
+            None,
+            shared::WebhookEventClass::UpdateDeploymentProgress,
+            serde_json::json!(views::UpdateDeployment::from(
+                deployment.clone()
+            )),
+        )
+        .await;
     }
 
     pub async fn update_deployments_list_by_id(
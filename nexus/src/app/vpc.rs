@@ -14,6 +14,8 @@ use crate::db::lookup;
 use crate::db::lookup::LookupPath;
 use crate::db::model::Name;
 use crate::external_api::params;
+use chrono::DateTime;
+use chrono::Utc;
 use nexus_db_queries::context::OpContext;
 use nexus_defaults as defaults;
 use omicron_common::api::external;
@@ -23,6 +25,7 @@ use omicron_common::api::external::DeleteResult;
 use omicron_common::api::external::Error;
 use omicron_common::api::external::InternalContext;
 use omicron_common::api::external::IpNet;
+use omicron_common::api::external::LabelSelector;
 use omicron_common::api::external::ListResultVec;
 use omicron_common::api::external::LookupResult;
 use omicron_common::api::external::LookupType;
@@ -108,29 +111,55 @@ impl super::Nexus {
         opctx: &OpContext,
         project_lookup: &lookup::Project<'_>,
         pagparams: &PaginatedBy<'_>,
+        label_selector: Option<&LabelSelector>,
     ) -> ListResultVec<db::model::Vpc> {
         let (.., authz_project) =
             project_lookup.lookup_for(authz::Action::ListChildren).await?;
-        self.db_datastore.vpc_list(&opctx, &authz_project, pagparams).await
+        self.db_datastore
+            .vpc_list(&opctx, &authz_project, pagparams, label_selector)
+            .await
     }
 
+    /// Updates a VPC
+    ///
+    /// If `expected_time_modified` is provided, the update fails with a 412
+    /// ("Precondition Failed") unless the VPC's modification time is one of
+    /// those given.
     pub async fn project_update_vpc(
         &self,
         opctx: &OpContext,
         vpc_lookup: &lookup::Vpc<'_>,
         params: &params::VpcUpdate,
+        expected_time_modified: Option<&[DateTime<Utc>]>,
     ) -> UpdateResult<db::model::Vpc> {
         let (.., authz_vpc) =
             vpc_lookup.lookup_for(authz::Action::Modify).await?;
-        self.db_datastore
-            .project_update_vpc(opctx, &authz_vpc, params.clone().into())
-            .await
+        let db_vpc = self
+            .db_datastore
+            .project_update_vpc(
+                opctx,
+                &authz_vpc,
+                params.clone().into(),
+                expected_time_modified,
+            )
+            .await?;
+
+        if params.identity.name.is_some() {
+            self.vpc_dns_activate();
+        }
+        Ok(db_vpc)
     }
 
+    /// Deletes a VPC
+    ///
+    /// If `expected_time_modified` is provided, the delete fails with a 412
+    /// ("Precondition Failed") unless the VPC's modification time is one of
+    /// those given.
     pub async fn project_delete_vpc(
         &self,
         opctx: &OpContext,
         vpc_lookup: &lookup::Vpc<'_>,
+        expected_time_modified: Option<&[DateTime<Utc>]>,
     ) -> DeleteResult {
         let (.., authz_vpc, db_vpc) = vpc_lookup.fetch().await?;
 
@@ -153,7 +182,12 @@ impl super::Nexus {
         // TODO: This should eventually use a saga to call the
         // networking subsystem to have it clean up the networking resources
         self.db_datastore
-            .project_delete_vpc(opctx, &db_vpc, &authz_vpc)
+            .project_delete_vpc(
+                opctx,
+                &db_vpc,
+                &authz_vpc,
+                expected_time_modified,
+            )
             .await?;
         self.db_datastore.vpc_delete_router(&opctx, &authz_vpc_router).await?;
 
@@ -180,12 +214,21 @@ impl super::Nexus {
         Ok(rules)
     }
 
-    pub async fn vpc_update_firewall_rules(
+    /// Replaces a VPC's firewall rules
+    ///
+    /// `precondition` is invoked with the current rules in the same
+    /// transaction that replaces them.  If it returns an error, the rules are
+    /// left unchanged and that error is returned.
+    pub async fn vpc_update_firewall_rules<P>(
         &self,
         opctx: &OpContext,
         vpc_lookup: &lookup::Vpc<'_>,
         params: &VpcFirewallRuleUpdateParams,
-    ) -> UpdateResult<Vec<db::model::VpcFirewallRule>> {
+        precondition: P,
+    ) -> UpdateResult<Vec<db::model::VpcFirewallRule>>
+    where
+        P: FnOnce(&[db::model::VpcFirewallRule]) -> Result<(), Error> + Send,
+    {
         let (.., authz_vpc, db_vpc) =
             vpc_lookup.fetch_for(authz::Action::Modify).await?;
         let rules = db::model::VpcFirewallRule::vec_from_params(
@@ -194,7 +237,12 @@ impl super::Nexus {
         );
         let rules = self
             .db_datastore
-            .vpc_update_firewall_rules(opctx, &authz_vpc, rules)
+            .vpc_update_firewall_rules_conditional(
+                opctx,
+                &authz_vpc,
+                rules,
+                precondition,
+            )
             .await?;
         self.send_sled_agents_firewall_rules(opctx, &db_vpc, &rules, &[])
             .await?;
@@ -318,6 +366,15 @@ impl super::Nexus {
         let mut instances: HashSet<Name> = HashSet::new();
         let mut subnets: HashSet<Name> = HashSet::new();
         let mut vpcs: HashSet<Name> = HashSet::new();
+
+        // Host filters may also name VPCs this VPC is peered with, or subnets
+        // in those VPCs.
+        let peer_vpcs = self.db_datastore.resolve_vpc_to_peer_vpcs(vpc).await?;
+        let peer_vnis: HashMap<external::Name, Vni> = peer_vpcs
+            .iter()
+            .map(|peer| (peer.name().clone(), peer.vni.0))
+            .collect();
+
         for rule in rules {
             for target in &rule.targets {
                 match &target.0 {
@@ -351,12 +408,11 @@ impl super::Nexus {
                         subnets.insert(name.clone().into());
                     }
                     external::VpcFirewallRuleHostFilter::Vpc(name) => {
-                        if name != vpc.name() {
-                            return Err(Error::invalid_request(
-                                "cross-VPC firewall host filter unsupported",
-                            ));
+                        // Other VPCs are resolved by VNI below, if they're
+                        // peered with this one.
+                        if name == vpc.name() {
+                            vpcs.insert(name.clone().into());
                         }
-                        vpcs.insert(name.clone().into());
                     }
                     // We don't need to resolve anything for Ip(Net)s.
                     external::VpcFirewallRuleHostFilter::Ip(_) => (),
@@ -441,14 +497,35 @@ impl super::Nexus {
             }
         }
 
-        let subnet_networks: NetMap = self
+        let mut subnet_networks: NetMap = self
             .db_datastore
-            .resolve_vpc_subnets_to_ip_networks(vpc, subnets)
+            .resolve_vpc_subnets_to_ip_networks(vpc, subnets.clone())
             .await?
             .into_iter()
             .map(|(name, v)| (name.0, v))
             .collect();
 
+        // Subnet names not found in this VPC may refer to subnets in a peered
+        // VPC. These are only usable as host filters; targets are always
+        // resolved within this VPC.
+        for peer in &peer_vpcs {
+            let unresolved = subnets
+                .iter()
+                .filter(|name| !subnet_networks.contains_key(&name.0))
+                .cloned()
+                .collect::<Vec<_>>();
+            if unresolved.is_empty() {
+                break;
+            }
+            for (name, v) in self
+                .db_datastore
+                .resolve_vpc_subnets_to_ip_networks(peer, unresolved)
+                .await?
+            {
+                subnet_networks.entry(name.0).or_insert(v);
+            }
+        }
+
         debug!(
             self.log,
             "resolved names for firewall rules";
@@ -583,6 +660,14 @@ impl super::Nexus {
                             external::VpcFirewallRuleHostFilter::IpNet(net) => {
                                 host_addrs.push(HostIdentifier::Ip(*net).into())
                             }
+                            external::VpcFirewallRuleHostFilter::Vpc(name)
+                                if name != vpc.name() =>
+                            {
+                                if let Some(vni) = peer_vnis.get(name) {
+                                    host_addrs
+                                        .push(HostIdentifier::Vpc(*vni).into())
+                                }
+                            }
                             external::VpcFirewallRuleHostFilter::Vpc(name) => {
                                 for interface in vpc_interfaces
                                     .get(&name)
@@ -0,0 +1,146 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! DNS names for instances within a VPC
+//!
+//! Each VPC with instances has its own DNS zone, named
+//! `<vpc>.<project>.<silo>.internal`, in which each instance whose primary
+//! network interface is in the VPC has a name with that interface's address.
+//! These zones aren't published through the rack's DNS servers.  Instead, the
+//! sled agents serve each zone only to the instances in its VPC, at their
+//! subnets' gateway addresses, which the instances are told about in their
+//! `network-config`.
+//!
+//! A sled agent is sent the zone with each instance that starts on it.  A
+//! background task (see `background::vpc_dns`) sends the zones to the sleds
+//! running each VPC's instances whenever something that affects them changes,
+//! and periodically, to pick up any changes that were missed.
+
+use super::background::VpcDnsSent;
+use crate::authz;
+use crate::db;
+use crate::db::identity::Asset;
+use crate::db::identity::Resource;
+use crate::db::lookup::LookupPath;
+use futures::future::join_all;
+use nexus_db_queries::context::OpContext;
+use omicron_common::api::external::Error;
+use sled_agent_client::types::VpcDnsEnsureBody;
+use sled_agent_client::types::VpcDnsZone;
+use uuid::Uuid;
+
+/// The maximum length of a DNS name, not counting the final dot
+const MAX_DNS_NAME_LEN: usize = 253;
+
+impl super::Nexus {
+    /// Ask the background task that sends the DNS zones of VPCs to sled agents
+    /// to bring them up to date
+    ///
+    /// This should be called whenever an instance is created or deleted, its
+    /// network interfaces change, or a VPC or project is renamed.  The task
+    /// also runs periodically, so a change that's missed here is still picked
+    /// up eventually.
+    pub(crate) fn vpc_dns_activate(&self) {
+        self.background_tasks.activate(&self.background_tasks.task_vpc_dns);
+    }
+
+    /// Returns the DNS zone of a VPC, in the form the sled agent serves it
+    ///
+    /// Instances whose fully-qualified name would be longer than a DNS name
+    /// can be are left out of the zone.  Their names are returned alongside
+    /// it.
+    pub(crate) async fn vpc_dns_zone_for_sled_agent(
+        &self,
+        opctx: &OpContext,
+        authz_vpc: &authz::Vpc,
+        vpc: &db::model::Vpc,
+    ) -> Result<(VpcDnsZone, Vec<String>), Error> {
+        let (authz_silo, _, db_project) =
+            LookupPath::new(opctx, &self.db_datastore)
+                .project_id(vpc.project_id)
+                .fetch()
+                .await?;
+        let (_, db_silo) = LookupPath::new(opctx, &self.db_datastore)
+            .silo_id(authz_silo.id())
+            .fetch()
+            .await?;
+        let name = vpc_dns_zone_name(
+            vpc.name().as_str(),
+            db_project.name().as_str(),
+            db_silo.name().as_str(),
+        );
+
+        let (records, names_too_long): (Vec<_>, Vec<_>) = self
+            .db_datastore
+            .derive_vpc_dns_records(opctx, authz_vpc)
+            .await?
+            .into_iter()
+            .partition(|record| {
+                record.name.len() + 1 + name.len() <= MAX_DNS_NAME_LEN
+            });
+        let names_too_long = names_too_long
+            .into_iter()
+            .map(|record| format!("{}.{}", record.name, name))
+            .collect();
+        Ok((VpcDnsZone { name, records }, names_too_long))
+    }
+
+    /// Sends the DNS zone of a VPC to the sleds running its instances
+    pub(crate) async fn send_sled_agents_vpc_dns(
+        &self,
+        opctx: &OpContext,
+        vpc_id: Uuid,
+    ) -> Result<VpcDnsSent, Error> {
+        let (.., authz_vpc, db_vpc) =
+            LookupPath::new(opctx, &self.db_datastore)
+                .vpc_id(vpc_id)
+                .fetch()
+                .await?;
+        let (zone, names_too_long) = self
+            .vpc_dns_zone_for_sled_agent(opctx, &authz_vpc, &db_vpc)
+            .await?;
+        let sled_dns_request =
+            VpcDnsEnsureBody { vni: db_vpc.vni.0.into(), zone };
+
+        let vpc_to_sleds =
+            self.db_datastore.vpc_resolve_to_sleds(vpc_id, &[]).await?;
+        let mut sled_requests = Vec::with_capacity(vpc_to_sleds.len());
+        for sled in &vpc_to_sleds {
+            let sled_id = sled.id();
+            let sled_dns_request = &sled_dns_request;
+            sled_requests.push(async move {
+                self.sled_client(&sled_id)
+                    .await?
+                    .vpc_dns_put(&vpc_id, sled_dns_request)
+                    .await
+                    .map_err(|e| Error::internal_error(&e.to_string()))
+            });
+        }
+
+        let results = join_all(sled_requests).await;
+        for (sled, result) in vpc_to_sleds.iter().zip(results) {
+            if let Err(e) = result {
+                warn!(self.log, "failed to update VPC DNS zone on sled agent";
+                      "sled_id" => %sled.id(),
+                      "vpc_id" => %vpc_id,
+                      "error" => %e);
+                return Err(e);
+            }
+        }
+
+        Ok(VpcDnsSent { nsleds: vpc_to_sleds.len(), names_too_long })
+    }
+}
+
+/// Returns the name of the DNS zone for the given VPC
+///
+/// Project names are only unique within a Silo, so the Silo's name is part of
+/// the zone name, too.
+fn vpc_dns_zone_name(
+    vpc_name: &str,
+    project_name: &str,
+    silo_name: &str,
+) -> String {
+    format!("{}.{}.{}.internal", vpc_name, project_name, silo_name)
+}
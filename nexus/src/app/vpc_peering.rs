@@ -0,0 +1,246 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! VPC peerings
+
+use crate::authz;
+use crate::db;
+use crate::db::identity::Resource;
+use crate::db::lookup;
+use crate::db::lookup::LookupPath;
+use crate::db::model::VpcPeering;
+use crate::db::model::VpcPeeringState;
+use crate::external_api::params;
+use nexus_db_queries::context::OpContext;
+use omicron_common::api::external::http_pagination::PaginatedBy;
+use omicron_common::api::external::CreateResult;
+use omicron_common::api::external::DeleteResult;
+use omicron_common::api::external::Error;
+use omicron_common::api::external::ListResultVec;
+use omicron_common::api::external::LookupResult;
+use omicron_common::api::external::NameOrId;
+use omicron_common::api::external::ResourceType;
+
+impl super::Nexus {
+    pub fn vpc_peering_lookup<'a>(
+        &'a self,
+        opctx: &'a OpContext,
+        peering_selector: params::VpcPeeringSelector,
+    ) -> LookupResult<lookup::VpcPeering<'a>> {
+        match peering_selector {
+            params::VpcPeeringSelector {
+                peering: NameOrId::Id(id),
+                vpc: None,
+                project: None,
+            } => {
+                let peering = LookupPath::new(opctx, &self.db_datastore)
+                    .vpc_peering_id(id);
+                Ok(peering)
+            }
+            params::VpcPeeringSelector {
+                peering: NameOrId::Name(name),
+                vpc: Some(vpc),
+                project,
+            } => {
+                let peering = self
+                    .vpc_lookup(opctx, params::VpcSelector { project, vpc })?
+                    .vpc_peering_name_owned(name.into());
+                Ok(peering)
+            }
+            params::VpcPeeringSelector {
+                peering: NameOrId::Id(_),
+                ..
+            } => Err(Error::invalid_request(
+                "when providing peering as an ID, vpc and project should not be specified",
+            )),
+            _ => Err(Error::invalid_request(
+                "peering should either be an ID or vpc should be specified",
+            )),
+        }
+    }
+
+    pub async fn vpc_peering_list(
+        &self,
+        opctx: &OpContext,
+        vpc_lookup: &lookup::Vpc<'_>,
+        pagparams: &PaginatedBy<'_>,
+    ) -> ListResultVec<VpcPeering> {
+        let (.., authz_vpc) =
+            vpc_lookup.lookup_for(authz::Action::ListChildren).await?;
+        self.db_datastore.vpc_peering_list(opctx, &authz_vpc, pagparams).await
+    }
+
+    /// Create this VPC's side of a peering with another VPC in the same silo.
+    ///
+    /// The peering becomes active once the peer VPC has also created a
+    /// peering with this one, at which point both VPCs' firewall rules are
+    /// re-sent to sled agents and V2P mappings for their running instances
+    /// are ensured.
+    ///
+    /// TODO The routes that an active peering adds to the VPCs' system routers
+    /// are only recorded in the database.  Nothing sends VPC router routes to
+    /// sled agents yet, so they'll only take effect once something does.
+    pub async fn vpc_peering_create(
+        &self,
+        opctx: &OpContext,
+        vpc_lookup: &lookup::Vpc<'_>,
+        params: &params::VpcPeeringCreate,
+    ) -> CreateResult<VpcPeering> {
+        let (authz_silo, authz_project, authz_vpc, db_vpc) =
+            vpc_lookup.fetch_for(authz::Action::CreateChild).await?;
+
+        // The peer VPC may be in a project the caller can't see. Whether the
+        // peering is ever established is up to whoever administers that VPC,
+        // so we look it up with Nexus's own privileges, but never outside of
+        // the caller's silo.
+        let internal_opctx = self.opctx_for_internal_api();
+        let silo_lookup = LookupPath::new(&internal_opctx, &self.db_datastore)
+            .silo_id(authz_silo.id());
+        let peer_vpc_lookup = match (&params.peer_vpc, &params.peer_project) {
+            (NameOrId::Id(id), None) => {
+                LookupPath::new(&internal_opctx, &self.db_datastore).vpc_id(*id)
+            }
+            (NameOrId::Name(name), None) => silo_lookup
+                .project_id(authz_project.id())
+                .vpc_name_owned(name.clone().into()),
+            (NameOrId::Name(name), Some(NameOrId::Id(project_id))) => {
+                silo_lookup
+                    .project_id(*project_id)
+                    .vpc_name_owned(name.clone().into())
+            }
+            (NameOrId::Name(name), Some(NameOrId::Name(project))) => {
+                silo_lookup
+                    .project_name_owned(project.clone().into())
+                    .vpc_name_owned(name.clone().into())
+            }
+            (NameOrId::Id(_), Some(_)) => {
+                return Err(Error::invalid_request(
+                    "when providing peer_vpc as an ID, peer_project should \
+                    not be specified",
+                ));
+            }
+        };
+        let (peer_authz_silo, .., db_peer_vpc) =
+            peer_vpc_lookup.fetch().await?;
+        if peer_authz_silo.id() != authz_silo.id() {
+            return Err(match &params.peer_vpc {
+                NameOrId::Id(id) => {
+                    Error::not_found_by_id(ResourceType::Vpc, id)
+                }
+                NameOrId::Name(name) => {
+                    Error::not_found_by_name(ResourceType::Vpc, name)
+                }
+            });
+        }
+        if db_peer_vpc.id() == db_vpc.id() {
+            return Err(Error::invalid_request(
+                "a VPC cannot be peered with itself",
+            ));
+        }
+
+        let peering =
+            VpcPeering::new(db_vpc.id(), db_peer_vpc.id(), params.clone());
+        let peering = self
+            .db_datastore
+            .vpc_peering_create(
+                opctx,
+                &authz_vpc,
+                &db_vpc,
+                &db_peer_vpc,
+                peering,
+            )
+            .await?;
+
+        if peering.state == VpcPeeringState::Active {
+            self.vpc_peering_ensure_connectivity(
+                &internal_opctx,
+                &[&db_vpc, &db_peer_vpc],
+                true,
+            )
+            .await?;
+        }
+        Ok(peering)
+    }
+
+    /// Delete this VPC's side of a peering.
+    ///
+    /// If the peering was active, the peer VPC's side reverts to pending, and
+    /// both VPCs' firewall rules are re-sent to sled agents so that rules
+    /// naming the other VPC or its subnets no longer apply.
+    pub async fn vpc_peering_delete(
+        &self,
+        opctx: &OpContext,
+        vpc_peering_lookup: &lookup::VpcPeering<'_>,
+    ) -> DeleteResult {
+        let (.., authz_peering, db_peering) =
+            vpc_peering_lookup.fetch_for(authz::Action::Delete).await?;
+        self.db_datastore
+            .vpc_peering_delete(opctx, &authz_peering, &db_peering)
+            .await?;
+
+        if db_peering.state == VpcPeeringState::Active {
+            let internal_opctx = self.opctx_for_internal_api();
+            let mut vpcs = Vec::with_capacity(2);
+            for vpc_id in [db_peering.vpc_id, db_peering.peer_vpc_id] {
+                let (.., db_vpc) =
+                    LookupPath::new(&internal_opctx, &self.db_datastore)
+                        .vpc_id(vpc_id)
+                        .fetch()
+                        .await?;
+                vpcs.push(db_vpc);
+            }
+            self.vpc_peering_ensure_connectivity(
+                &internal_opctx,
+                &vpcs.iter().collect::<Vec<_>>(),
+                false,
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Propagate a change in peering state to sled agents.
+    ///
+    /// Each VPC's firewall rules are re-resolved, since host filters may name
+    /// peered VPCs and their subnets. When a peering is established, V2P
+    /// mappings are also ensured for every running instance in the VPCs, so
+    /// that each sled can reach instances in the peer VPC. Mappings are not
+    /// removed when a peering is torn down: they're broadcast to every sled
+    /// regardless of VPC, and the firewall is what isolates the VPCs.
+    async fn vpc_peering_ensure_connectivity(
+        &self,
+        opctx: &OpContext,
+        vpcs: &[&db::model::Vpc],
+        established: bool,
+    ) -> Result<(), Error> {
+        for vpc in vpcs {
+            let (.., authz_vpc) = LookupPath::new(opctx, &self.db_datastore)
+                .vpc_id(vpc.id())
+                .lookup_for(authz::Action::Read)
+                .await?;
+            let rules = self
+                .db_datastore
+                .vpc_list_firewall_rules(opctx, &authz_vpc)
+                .await?;
+            self.send_sled_agents_firewall_rules(opctx, vpc, &rules, &[])
+                .await?;
+
+            if established {
+                for (instance_id, sled_id) in self
+                    .db_datastore
+                    .vpc_resolve_to_running_instances(vpc.id())
+                    .await?
+                {
+                    self.create_instance_v2p_mappings(
+                        opctx,
+                        instance_id,
+                        sled_id,
+                    )
+                    .await?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -12,6 +12,8 @@ use crate::db::lookup::LookupPath;
 use crate::db::model::VpcSubnet;
 use crate::db::queries::vpc_subnet::SubnetError;
 use crate::external_api::params;
+use chrono::DateTime;
+use chrono::Utc;
 use nexus_db_queries::context::OpContext;
 use omicron_common::api::external;
 use omicron_common::api::external::http_pagination::PaginatedBy;
@@ -226,30 +228,52 @@ impl super::Nexus {
         self.db_datastore.vpc_subnet_list(opctx, &authz_vpc, pagparams).await
     }
 
+    /// Updates a VPC Subnet
+    ///
+    /// If `expected_time_modified` is provided, the update fails with a 412
+    /// ("Precondition Failed") unless the subnet's modification time is one of
+    /// those given.
     pub async fn vpc_update_subnet(
         &self,
         opctx: &OpContext,
         vpc_subnet_lookup: &lookup::VpcSubnet<'_>,
         params: &params::VpcSubnetUpdate,
+        expected_time_modified: Option<&[DateTime<Utc>]>,
     ) -> UpdateResult<VpcSubnet> {
         let (.., authz_subnet) =
             vpc_subnet_lookup.lookup_for(authz::Action::Modify).await?;
         self.db_datastore
-            .vpc_update_subnet(&opctx, &authz_subnet, params.clone().into())
+            .vpc_update_subnet(
+                &opctx,
+                &authz_subnet,
+                params.clone().into(),
+                expected_time_modified,
+            )
             .await
     }
 
+    /// Deletes a VPC Subnet
+    ///
+    /// If `expected_time_modified` is provided, the delete fails with a 412
+    /// ("Precondition Failed") unless the subnet's modification time is one of
+    /// those given.
     // TODO: When a subnet is deleted it should remove its entry from the VPC's
     // system router.
     pub async fn vpc_delete_subnet(
         &self,
         opctx: &OpContext,
         vpc_subnet_lookup: &lookup::VpcSubnet<'_>,
+        expected_time_modified: Option<&[DateTime<Utc>]>,
     ) -> DeleteResult {
         let (.., authz_subnet, db_subnet) =
             vpc_subnet_lookup.fetch_for(authz::Action::Delete).await?;
         self.db_datastore
-            .vpc_delete_subnet(opctx, &db_subnet, &authz_subnet)
+            .vpc_delete_subnet(
+                opctx,
+                &db_subnet,
+                &authz_subnet,
+                expected_time_modified,
+            )
             .await
     }
 
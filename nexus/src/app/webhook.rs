@@ -0,0 +1,357 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Webhook receivers, to which events concerning the resources in a Silo are
+//! delivered
+
+use crate::authz;
+use crate::db;
+use crate::db::lookup;
+use crate::db::lookup::LookupPath;
+use nexus_db_queries::context::OpContext;
+use nexus_types::external_api::params;
+use nexus_types::external_api::shared;
+use omicron_common::api::external::http_pagination::PaginatedBy;
+use omicron_common::api::external::CreateResult;
+use omicron_common::api::external::DataPageParams;
+use omicron_common::api::external::DeleteResult;
+use omicron_common::api::external::Error;
+use omicron_common::api::external::ListResultVec;
+use omicron_common::api::external::LookupResult;
+use omicron_common::api::external::NameOrId;
+use std::net::IpAddr;
+use std::net::Ipv4Addr;
+use std::net::SocketAddr;
+use uuid::Uuid;
+
+impl super::Nexus {
+    pub fn webhook_receiver_lookup<'a>(
+        &'a self,
+        opctx: &'a OpContext,
+        receiver: NameOrId,
+    ) -> LookupResult<lookup::WebhookReceiver<'a>> {
+        match receiver {
+            NameOrId::Id(id) => Ok(LookupPath::new(opctx, &self.db_datastore)
+                .webhook_receiver_id(id)),
+            NameOrId::Name(name) => Ok(self
+                .current_silo_lookup(opctx)?
+                .webhook_receiver_name_owned(name.into())),
+        }
+    }
+
+    pub async fn webhook_receiver_create(
+        &self,
+        opctx: &OpContext,
+        params: params::WebhookReceiverCreate,
+    ) -> CreateResult<db::model::WebhookReceiver> {
+        let (.., authz_silo) = self
+            .current_silo_lookup(opctx)?
+            .lookup_for(authz::Action::CreateChild)
+            .await?;
+
+        let endpoint = match reqwest::Url::parse(&params.endpoint) {
+            Ok(url) if url.scheme() == "http" || url.scheme() == "https" => url,
+            Ok(_) => {
+                return Err(Error::invalid_request(
+                    "webhook receiver endpoint must be an HTTP or HTTPS URL",
+                ))
+            }
+            Err(e) => {
+                return Err(Error::invalid_request(&format!(
+                    "invalid webhook receiver endpoint: {}",
+                    e
+                )))
+            }
+        };
+        // A host that doesn't resolve (yet) is accepted: the addresses are
+        // checked again before every delivery, since they may change anyway.
+        match webhook_endpoint_resolve(
+            &endpoint,
+            self.webhook_allow_private_endpoints,
+        )
+        .await
+        {
+            Ok(_) | Err(WebhookEndpointError::Unresolvable) => (),
+            Err(e) => {
+                return Err(Error::invalid_request(&format!(
+                    "invalid webhook receiver endpoint: {}",
+                    e
+                )))
+            }
+        }
+        if params.secret.is_empty() {
+            return Err(Error::invalid_request(
+                "webhook receiver secret must not be empty",
+            ));
+        }
+        if params.event_classes.is_empty() {
+            return Err(Error::invalid_request(
+                "webhook receiver must subscribe to at least one event class",
+            ));
+        }
+
+        let receiver = db::model::WebhookReceiver::new(authz_silo.id(), params);
+        self.db_datastore
+            .webhook_receiver_create(opctx, &authz_silo, receiver)
+            .await
+    }
+
+    pub async fn webhook_receiver_list(
+        &self,
+        opctx: &OpContext,
+        pagparams: &PaginatedBy<'_>,
+    ) -> ListResultVec<db::model::WebhookReceiver> {
+        let (.., authz_silo) = self
+            .current_silo_lookup(opctx)?
+            .lookup_for(authz::Action::ListChildren)
+            .await?;
+        self.db_datastore
+            .webhook_receiver_list(opctx, &authz_silo, pagparams)
+            .await
+    }
+
+    pub async fn webhook_receiver_delete(
+        &self,
+        opctx: &OpContext,
+        receiver_lookup: &lookup::WebhookReceiver<'_>,
+    ) -> DeleteResult {
+        let (.., authz_receiver) =
+            receiver_lookup.lookup_for(authz::Action::Delete).await?;
+        self.db_datastore.webhook_receiver_delete(opctx, &authz_receiver).await
+    }
+
+    pub async fn webhook_delivery_list(
+        &self,
+        opctx: &OpContext,
+        receiver_lookup: &lookup::WebhookReceiver<'_>,
+        pagparams: &DataPageParams<'_, Uuid>,
+    ) -> ListResultVec<(db::model::WebhookDelivery, db::model::WebhookEvent)>
+    {
+        let (.., authz_receiver) =
+            receiver_lookup.lookup_for(authz::Action::ListChildren).await?;
+        self.db_datastore
+            .webhook_delivery_list(opctx, &authz_receiver, pagparams)
+            .await
+    }
+
+    /// Publishes an event to the webhook receivers subscribed to it, and
+    /// kicks off its delivery
+    ///
+    /// An event concerns the resources of the Silo `silo_id`, or the whole
+    /// system if there's no Silo.  Publishing an event is best-effort: a
+    /// failure is logged, but doesn't fail the operation the event describes.
+    pub(crate) async fn webhook_event_publish(
+        &self,
+        silo_id: Option<Uuid>,
+        event_class: shared::WebhookEventClass,
+        data: serde_json::Value,
+    ) {
+        let opctx = self.opctx_for_internal_api();
+        let event =
+            db::model::WebhookEvent::new(silo_id, event_class.into(), data);
+        let event_id = event.id;
+        match self.db_datastore.webhook_event_publish(&opctx, event).await {
+            Ok(0) => (),
+            Ok(ndeliveries) => {
+                debug!(
+                    opctx.log,
+                    "published webhook event";
+                    "event_id" => %event_id,
+                    "event_class" => ?event_class,
+                    "deliveries" => ndeliveries,
+                );
+                self.background_tasks
+                    .activate(&self.background_tasks.task_webhook_deliveries);
+            }
+            Err(error) => {
+                warn!(
+                    opctx.log,
+                    "failed to publish webhook event";
+                    "event_class" => ?event_class,
+                    "error" => format!("{:#}", error),
+                );
+            }
+        }
+    }
+}
+
+/// Reasons that deliveries can't be made to a webhook receiver's endpoint
+///
+/// These are recorded with the deliveries, so they mustn't say anything about
+/// the network beyond the endpoint that the receiver's owner gave us.
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum WebhookEndpointError {
+    #[error("endpoint has no host")]
+    NoHost,
+    #[error("could not resolve endpoint host")]
+    Unresolvable,
+    #[error(
+        "endpoint host is, or resolves to, a loopback, private, link-local, \
+         or otherwise non-global address"
+    )]
+    AddressNotAllowed,
+}
+
+/// Resolves the host of a webhook receiver's endpoint to the addresses that
+/// deliveries may be made to
+///
+/// Unless `allow_private` is set, this fails if any of the addresses is not
+/// a global one, so that receivers can't be used to send requests to the
+/// control plane, to the rack's underlay network, or to anything else that's
+/// only reachable from inside the rack.
+pub(crate) async fn webhook_endpoint_resolve(
+    endpoint: &reqwest::Url,
+    allow_private: bool,
+) -> Result<Vec<SocketAddr>, WebhookEndpointError> {
+    let port =
+        endpoint.port_or_known_default().ok_or(WebhookEndpointError::NoHost)?;
+    let addrs = match endpoint.domain() {
+        Some(domain) => tokio::net::lookup_host((domain, port))
+            .await
+            .map_err(|_| WebhookEndpointError::Unresolvable)?
+            .collect::<Vec<_>>(),
+        None => {
+            // The host is an IP address, which is bracketed if it's IPv6.
+            let ip = endpoint
+                .host_str()
+                .ok_or(WebhookEndpointError::NoHost)?
+                .trim_start_matches('[')
+                .trim_end_matches(']')
+                .parse::<IpAddr>()
+                .map_err(|_| WebhookEndpointError::NoHost)?;
+            vec![SocketAddr::new(ip, port)]
+        }
+    };
+    if addrs.is_empty() {
+        return Err(WebhookEndpointError::Unresolvable);
+    }
+    if !allow_private
+        && !addrs.iter().all(|addr| webhook_address_allowed(addr.ip()))
+    {
+        return Err(WebhookEndpointError::AddressNotAllowed);
+    }
+    Ok(addrs)
+}
+
+/// Returns whether deliveries may be made to `ip`, which must be a global
+/// unicast address
+///
+/// The rack's underlay network uses IPv6 unique local addresses, so it's
+/// excluded along with the other non-global ranges.
+fn webhook_address_allowed(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_unspecified()
+                || ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || ip.is_documentation()
+                // "this network" (RFC 791) and reserved (RFC 1112)
+                || a == 0
+                || a >= 240
+                // shared address space (RFC 6598)
+                || (a == 100 && (b & 0xc0) == 64)
+                // IETF protocol assignments (RFC 6890)
+                || (a == 192 && b == 0 && ip.octets()[2] == 0))
+        }
+        IpAddr::V6(ip) => {
+            let segments = ip.segments();
+            if let Some(ipv4) = ip.to_ipv4_mapped() {
+                return webhook_address_allowed(ipv4.into());
+            }
+            // NAT64 (RFC 6052) addresses reach the embedded IPv4 address.
+            if segments[..6] == [0x64, 0xff9b, 0, 0, 0, 0] {
+                let [.., c, d, e, f] = ip.octets();
+                return webhook_address_allowed(
+                    Ipv4Addr::new(c, d, e, f).into(),
+                );
+            }
+            !(ip.is_unspecified()
+                || ip.is_loopback()
+                || ip.is_multicast()
+                // unique local (fc00::/7), including the underlay
+                || (segments[0] & 0xfe00) == 0xfc00
+                // link-local (fe80::/10)
+                || (segments[0] & 0xffc0) == 0xfe80
+                // site-local (fec0::/10), deprecated but still non-global
+                || (segments[0] & 0xffc0) == 0xfec0
+                // documentation (2001:db8::/32)
+                || (segments[0] == 0x2001 && segments[1] == 0xdb8))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::webhook_address_allowed;
+    use super::webhook_endpoint_resolve;
+    use super::WebhookEndpointError;
+    use std::net::IpAddr;
+
+    #[test]
+    fn test_webhook_address_allowed() {
+        for allowed in [
+            "1.1.1.1",
+            "100.128.0.1",
+            "172.32.0.1",
+            "2600:1f18::1",
+            "::ffff:8.8.8.8",
+            "64:ff9b::808:808",
+        ] {
+            let ip: IpAddr = allowed.parse().unwrap();
+            assert!(webhook_address_allowed(ip), "{} should be allowed", ip);
+        }
+        for disallowed in [
+            "0.0.0.0",
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "224.0.0.1",
+            "255.255.255.255",
+            "192.0.2.1",
+            "::",
+            "::1",
+            "fd00:1122:3344:101::1",
+            "fe80::1",
+            "ff02::1",
+            "::ffff:127.0.0.1",
+            "64:ff9b::a00:1",
+            "2001:db8::1",
+        ] {
+            let ip: IpAddr = disallowed.parse().unwrap();
+            assert!(!webhook_address_allowed(ip), "{} should be rejected", ip);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_webhook_endpoint_resolve() {
+        let endpoint = "http://127.0.0.1:8080/events".parse().unwrap();
+        assert!(matches!(
+            webhook_endpoint_resolve(&endpoint, false).await,
+            Err(WebhookEndpointError::AddressNotAllowed)
+        ));
+        assert_eq!(
+            webhook_endpoint_resolve(&endpoint, true).await.unwrap(),
+            vec!["127.0.0.1:8080".parse().unwrap()]
+        );
+
+        let endpoint = "https://[fd00:1122:3344:101::1]/".parse().unwrap();
+        assert!(matches!(
+            webhook_endpoint_resolve(&endpoint, false).await,
+            Err(WebhookEndpointError::AddressNotAllowed)
+        ));
+
+        let endpoint = "http://localhost/events".parse().unwrap();
+        assert!(matches!(
+            webhook_endpoint_resolve(&endpoint, false).await,
+            Err(WebhookEndpointError::AddressNotAllowed)
+        ));
+    }
+}
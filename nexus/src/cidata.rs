@@ -2,36 +2,81 @@ use crate::db::{identity::Resource, model::Instance};
 use fatfs::{FatType, FileSystem, FormatVolumeOptions, FsOptions};
 use num_integer::Integer;
 use omicron_common::api::external::Error;
+use omicron_common::api::external::Labels;
 use serde::Serialize;
+use sled_agent_client::types::{IpNet, NetworkInterface};
+use std::collections::BTreeMap;
 use std::io::{self, Cursor, Write};
+use std::net::IpAddr;
 use uuid::Uuid;
 
 pub use nexus_types::external_api::params::MAX_USER_DATA_BYTES;
 
+/// Everything besides the instance record itself that goes into an instance's
+/// cidata volume
+///
+/// The volume is generated from scratch every time the instance is registered
+/// with a sled, so changes to any of these (or to the instance's user data)
+/// are picked up the next time the instance starts.  cloud-init only applies
+/// its once-per-instance configuration (including the network configuration)
+/// when the instance ID in the meta-data changes, so that ID is the instance's
+/// UUID followed by a digest of everything else in the volume.
+pub struct CiDataInputs<'a> {
+    pub public_keys: &'a [String],
+    pub nics: &'a [NetworkInterface],
+    pub external_ips: &'a [IpAddr],
+    pub project_labels: &'a Labels,
+    /// The name of the DNS zone of the instance's VPC, which the instance can
+    /// query at the gateway of its primary NIC's subnet
+    pub vpc_dns_zone: Option<&'a str>,
+}
+
 pub trait InstanceCiData {
-    fn generate_cidata(&self, public_keys: &[String])
-        -> Result<Vec<u8>, Error>;
+    fn generate_cidata(
+        &self,
+        inputs: &CiDataInputs<'_>,
+    ) -> Result<Vec<u8>, Error>;
 }
 
 impl InstanceCiData for Instance {
     fn generate_cidata(
         &self,
-        public_keys: &[String],
+        inputs: &CiDataInputs<'_>,
     ) -> Result<Vec<u8>, Error> {
+        let config = MetaDataConfig {
+            local_hostname: &self.runtime().hostname,
+            public_keys: inputs.public_keys,
+            external_ips: inputs.external_ips,
+            project_labels: inputs.project_labels,
+            instance_labels: &self.labels,
+        };
+        let network_config = serde_json::to_vec(&NetworkConfig::from_nics(
+            inputs.nics,
+            inputs.vpc_dns_zone,
+        )?)
+        .map_err(|_| {
+            Error::internal_error("failed to serialize network-config")
+        })?;
+
         // cloud-init meta-data is YAML, but YAML is a strict superset of JSON.
+        let config_bytes = serde_json::to_vec(&config).map_err(|_| {
+            Error::internal_error("failed to serialize meta-data")
+        })?;
         let meta_data = serde_json::to_vec(&MetaData {
-            instance_id: self.id(),
-            local_hostname: &self.runtime().hostname,
-            public_keys,
+            instance_id: cidata_instance_id(
+                self.id(),
+                &[&config_bytes, &self.user_data, &network_config],
+            ),
+            config,
         })
         .map_err(|_| Error::internal_error("failed to serialize meta-data"))?;
-        let cidata =
-            build_vfat(&meta_data, &self.user_data).map_err(|err| {
-                Error::internal_error(&format!(
-                    "failed to create cidata volume: {}",
-                    err
-                ))
-            })?;
+        let cidata = build_vfat(&meta_data, &self.user_data, &network_config)
+            .map_err(|err| {
+            Error::internal_error(&format!(
+                "failed to create cidata volume: {}",
+                err
+            ))
+        })?;
         Ok(cidata)
     }
 }
@@ -39,14 +84,154 @@ impl InstanceCiData for Instance {
 #[derive(Serialize)]
 #[serde(rename_all = "kebab-case")]
 struct MetaData<'a> {
-    instance_id: Uuid,
+    instance_id: String,
+    #[serde(flatten)]
+    config: MetaDataConfig<'a>,
+}
+
+/// The parts of the meta-data besides the instance ID
+#[derive(Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct MetaDataConfig<'a> {
     local_hostname: &'a str,
     public_keys: &'a [String],
+    external_ips: &'a [IpAddr],
+    project_labels: &'a Labels,
+    instance_labels: &'a Labels,
+}
+
+/// Returns the instance ID given to cloud-init: the instance's UUID, followed
+/// by a digest of the other files in the cidata volume
+///
+/// cloud-init treats an instance whose ID has changed as a new instance, and
+/// reruns its once-per-instance configuration, so this changes whenever the
+/// configuration does.
+fn cidata_instance_id(id: Uuid, files: &[&[u8]]) -> String {
+    let mut context = ring::digest::Context::new(&ring::digest::SHA256);
+    for file in files {
+        context.update(&u64::try_from(file.len()).unwrap().to_be_bytes());
+        context.update(file);
+    }
+    let digest = hex::encode(context.finish().as_ref());
+    format!("{}-{}", id, &digest[..16])
+}
+
+/// cloud-init network configuration, version 2
+///
+/// See <https://cloudinit.readthedocs.io/en/latest/reference/network-config-format-v2.html>.
+#[derive(Serialize)]
+struct NetworkConfig {
+    version: u8,
+    ethernets: BTreeMap<String, Ethernet>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct Ethernet {
+    #[serde(rename = "match")]
+    match_: EthernetMatch,
+    addresses: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    routes: Vec<Route>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    nameservers: Option<Nameservers>,
+}
+
+#[derive(Serialize)]
+struct EthernetMatch {
+    macaddress: String,
 }
 
-fn build_vfat(meta_data: &[u8], user_data: &[u8]) -> io::Result<Vec<u8>> {
+#[derive(Serialize)]
+struct Route {
+    to: &'static str,
+    via: IpAddr,
+}
+
+#[derive(Serialize)]
+struct Nameservers {
+    addresses: Vec<IpAddr>,
+    search: Vec<String>,
+}
+
+impl NetworkConfig {
+    /// Builds a static configuration for each of an instance's NICs, matching
+    /// them by MAC address.  Only the primary NIC gets a default route, via
+    /// the VPC subnet's gateway.  If the VPC has a DNS zone, the primary NIC
+    /// also gets the gateway as its nameserver, and the zone as its search
+    /// domain.
+    ///
+    /// The NICs' names are only used as the configuration's IDs for them.
+    /// They aren't used to rename the guest's interfaces, since they can be
+    /// longer than the guest allows (15 characters on Linux).
+    fn from_nics(
+        nics: &[NetworkInterface],
+        vpc_dns_zone: Option<&str>,
+    ) -> Result<Self, Error> {
+        let mut ethernets = BTreeMap::new();
+        for nic in nics {
+            // The gateway is always the first host address of the subnet.
+            let (prefix, gateway, default_route) = match &nic.subnet {
+                IpNet::V4(net) => {
+                    let net: ipnetwork::Ipv4Network =
+                        net.parse().map_err(|_| {
+                            Error::internal_error(&format!(
+                                "bad subnet for nic {}",
+                                nic.id
+                            ))
+                        })?;
+                    let gateway = net.iter().nth(1).map(IpAddr::V4);
+                    (net.prefix(), gateway, "0.0.0.0/0")
+                }
+                IpNet::V6(net) => {
+                    let net: ipnetwork::Ipv6Network =
+                        net.parse().map_err(|_| {
+                            Error::internal_error(&format!(
+                                "bad subnet for nic {}",
+                                nic.id
+                            ))
+                        })?;
+                    let gateway = net.iter().nth(1).map(IpAddr::V6);
+                    (net.prefix(), gateway, "::/0")
+                }
+            };
+
+            let (routes, nameservers) = match gateway {
+                Some(via) if nic.primary => (
+                    vec![Route { to: default_route, via }],
+                    vpc_dns_zone.map(|zone| Nameservers {
+                        addresses: vec![via],
+                        search: vec![zone.to_string()],
+                    }),
+                ),
+                _ => (vec![], None),
+            };
+
+            ethernets.insert(
+                nic.name.to_string(),
+                Ethernet {
+                    match_: EthernetMatch {
+                        macaddress: nic.mac.to_string().to_lowercase(),
+                    },
+                    addresses: vec![format!("{}/{}", nic.ip, prefix)],
+                    routes,
+                    nameservers,
+                },
+            );
+        }
+
+        Ok(NetworkConfig { version: 2, ethernets })
+    }
+}
+
+fn build_vfat(
+    meta_data: &[u8],
+    user_data: &[u8],
+    network_config: &[u8],
+) -> io::Result<Vec<u8>> {
     let file_sectors = Integer::div_ceil(&meta_data.len(), &512)
-        + Integer::div_ceil(&user_data.len(), &512);
+        + Integer::div_ceil(&user_data.len(), &512)
+        + Integer::div_ceil(&network_config.len(), &512);
     // vfat can hold more data than this, but we don't expect to ever need that for cloud-init
     // purposes.
     if file_sectors > 512 {
@@ -79,8 +264,11 @@ fn build_vfat(meta_data: &[u8], user_data: &[u8]) -> io::Result<Vec<u8>> {
     {
         let fs = FileSystem::new(&mut disk, FsOptions::new())?;
         let root_dir = fs.root_dir();
-        for (file, data) in [("meta-data", meta_data), ("user-data", user_data)]
-        {
+        for (file, data) in [
+            ("meta-data", meta_data),
+            ("user-data", user_data),
+            ("network-config", network_config),
+        ] {
             // Cloud-init requires the files `meta-data` and `user-data`
             // to be present, even if empty.
             let mut file = root_dir.create_file(file)?;
@@ -110,10 +298,88 @@ mod tests {
             for ud_size in (0..upper).step_by(269) {
                 assert!(super::build_vfat(
                     &vec![0x5a; md_size],
-                    &vec![0xa5; ud_size]
+                    &vec![0xa5; ud_size],
+                    &vec![0x3c; 1021],
                 )
                 .is_ok());
             }
         }
     }
+
+    /// The instance ID changes when, and only when, the configuration does.
+    #[test]
+    fn cidata_instance_id_tracks_configuration() {
+        let id = uuid::Uuid::new_v4();
+        let instance_id = |files: &[&str]| {
+            let files: Vec<&[u8]> =
+                files.iter().map(|f| f.as_bytes()).collect();
+            super::cidata_instance_id(id, &files)
+        };
+        let original = instance_id(&["a", "bc"]);
+        assert!(original.starts_with(&format!("{}-", id)));
+        assert_eq!(original, instance_id(&["a", "bc"]));
+        assert_ne!(original, instance_id(&["a", "bd"]));
+        assert_ne!(original, instance_id(&["ab", "c"]));
+    }
+
+    /// Only the primary NIC should get a default route and nameserver, and
+    /// NICs should be matched by MAC address with the addresses Nexus assigned
+    /// them.
+    #[test]
+    fn network_config_from_nics() {
+        use omicron_common::api::external;
+        use sled_agent_client::types;
+
+        let nic = |name: &str, ip: &str, subnet: &str, primary, slot| {
+            let id = uuid::Uuid::new_v4();
+            let name: external::Name = name.parse().unwrap();
+            let subnet: ipnetwork::IpNetwork = subnet.parse().unwrap();
+            types::NetworkInterface {
+                id,
+                kind: types::NetworkInterfaceKind::Instance(id),
+                name: types::Name::from(&name),
+                ip: ip.parse().unwrap(),
+                mac: types::MacAddr::from(
+                    format!("a8:40:25:f0:00:0{}", slot)
+                        .parse::<external::MacAddr>()
+                        .unwrap(),
+                ),
+                subnet: types::IpNet::from(subnet),
+                vni: types::Vni::from(external::Vni::try_from(10).unwrap()),
+                primary,
+                slot,
+            }
+        };
+
+        let config = super::NetworkConfig::from_nics(
+            &[
+                nic("net0", "172.30.0.5", "172.30.0.0/22", true, 0),
+                nic("net1", "172.30.4.5", "172.30.4.0/22", false, 1),
+            ],
+            Some("default.myproj.mysilo.internal"),
+        )
+        .unwrap();
+
+        assert_eq!(
+            serde_json::to_value(&config).unwrap(),
+            serde_json::json!({
+                "version": 2,
+                "ethernets": {
+                    "net0": {
+                        "match": { "macaddress": "a8:40:25:f0:00:00" },
+                        "addresses": ["172.30.0.5/22"],
+                        "routes": [{ "to": "0.0.0.0/0", "via": "172.30.0.1" }],
+                        "nameservers": {
+                            "addresses": ["172.30.0.1"],
+                            "search": ["default.myproj.mysilo.internal"],
+                        },
+                    },
+                    "net1": {
+                        "match": { "macaddress": "a8:40:25:f0:00:01" },
+                        "addresses": ["172.30.4.5/22"],
+                    },
+                },
+            })
+        );
+    }
 }
@@ -16,6 +16,13 @@ use authn::external::spoof::HttpAuthnSpoof;
 use authn::external::token::HttpAuthnToken;
 use authn::external::HttpAuthnScheme;
 use chrono::Duration;
+use chrono::Utc;
+use dropshot::HttpError;
+use dropshot::HttpResponse;
+use dropshot::RequestContext;
+use futures::Future;
+use http::Response;
+use hyper::Body;
 use internal_dns::ServiceName;
 use nexus_db_queries::context::{OpContext, OpKind};
 use nexus_db_queries::db::lookup::LookupPath;
@@ -25,6 +32,7 @@ use omicron_common::postgres_config::PostgresConfigWithUrl;
 use oximeter::types::ProducerRegistry;
 use oximeter_instruments::http::{HttpService, LatencyTracker};
 use slog::Logger;
+use std::cell::RefCell;
 use std::env;
 use std::path::PathBuf;
 use std::str::FromStr;
@@ -227,13 +235,144 @@ impl ServerContext {
     }
 }
 
+/// Whoever made an audited external API request
+#[derive(Clone, Copy)]
+struct AuditedActor {
+    actor_id: Uuid,
+    silo_id: Option<Uuid>,
+}
+
+tokio::task_local! {
+    /// Whoever made the audited external API request being handled by the
+    /// current task
+    ///
+    /// This is filled in by [`op_context_for_external_api()`] (or, for SCIM
+    /// requests, [`audit_scim_client()`]) so that
+    /// [`ServerContext::instrument_audited_handler()`] can attribute the
+    /// request to whoever made it.
+    static AUDITED_ACTOR: RefCell<Option<AuditedActor>>;
+}
+
+impl ServerContext {
+    /// Runs the handler for an external API request that may modify the system
+    ///
+    /// Like `external_latencies.instrument_dropshot_handler()`, this records
+    /// the request's latency.  Additionally, if the request was authenticated,
+    /// this records it in the audit log under the given operation id.
+    pub async fn instrument_audited_handler<R, H>(
+        &self,
+        rqctx: &RequestContext<Arc<ServerContext>>,
+        operation_id: &str,
+        handler: H,
+    ) -> Result<R, HttpError>
+    where
+        R: HttpResponse,
+        H: Future<Output = Result<R, HttpError>>,
+    {
+        self.instrument_audited(rqctx, operation_id, handler, |_| {
+            R::response_metadata().success.unwrap_or(http::StatusCode::OK)
+        })
+        .await
+    }
+
+    /// Like [`ServerContext::instrument_audited_handler()`], for handlers that
+    /// build their own `Response`, whose status is recorded as is
+    pub async fn instrument_audited_raw_handler<H>(
+        &self,
+        rqctx: &RequestContext<Arc<ServerContext>>,
+        operation_id: &str,
+        handler: H,
+    ) -> Result<Response<Body>, HttpError>
+    where
+        H: Future<Output = Result<Response<Body>, HttpError>>,
+    {
+        self.instrument_audited(rqctx, operation_id, handler, |response| {
+            response.status()
+        })
+        .await
+    }
+
+    async fn instrument_audited<R, H, S>(
+        &self,
+        rqctx: &RequestContext<Arc<ServerContext>>,
+        operation_id: &str,
+        handler: H,
+        success_status: S,
+    ) -> Result<R, HttpError>
+    where
+        R: HttpResponse,
+        H: Future<Output = Result<R, HttpError>>,
+        S: FnOnce(&R) -> http::StatusCode,
+    {
+        let time_started = Utc::now();
+        let (result, actor) = AUDITED_ACTOR
+            .scope(RefCell::new(None), async {
+                let result = self
+                    .external_latencies
+                    .instrument_dropshot_handler(rqctx, handler)
+                    .await;
+                (result, AUDITED_ACTOR.with(|actor| actor.take()))
+            })
+            .await;
+        let time_completed = Utc::now();
+
+        // Unauthenticated requests (e.g., logging in) are not audited.
+        let Some(actor) = actor else {
+            return result;
+        };
+        let status_code = match &result {
+            Ok(response) => success_status(response),
+            Err(error) => error.status_code,
+        };
+        let entry = db::model::AuditLogEntry {
+            id: Uuid::new_v4(),
+            time_started,
+            time_completed,
+            request_id: rqctx.request_id.clone(),
+            actor_id: actor.actor_id,
+            actor_silo_id: actor.silo_id,
+            operation_id: operation_id.to_string(),
+            http_method: rqctx.request.method().to_string(),
+            resource_path: rqctx.request.uri().to_string(),
+            result_status: status_code.as_u16().into(),
+        };
+
+        // The request has already been carried out by this point, so there's
+        // nothing to be gained by failing it.
+        if let Err(error) = self.nexus.audit_log_record(entry).await {
+            error!(
+                rqctx.log,
+                "failed to record audit log entry";
+                "operation_id" => operation_id,
+                "error" => #%error,
+            );
+        }
+
+        result
+    }
+}
+
+/// Attributes the audited request being handled by the current task to a SCIM
+/// client
+///
+/// SCIM clients aren't users, so the request is recorded as made by the bearer
+/// token that the client authenticated with, in that token's Silo.
+pub fn audit_scim_client(token_id: Uuid, silo_id: Uuid) {
+    let _ = AUDITED_ACTOR.try_with(|actor| {
+        actor.replace(Some(AuditedActor {
+            actor_id: token_id,
+            silo_id: Some(silo_id),
+        }));
+    });
+}
+
 /// Authenticates an incoming request to the external API and produces a new
 /// operation context for it
 pub async fn op_context_for_external_api(
     rqctx: &dropshot::RequestContext<Arc<ServerContext>>,
 ) -> Result<OpContext, dropshot::HttpError> {
     let apictx = rqctx.context();
-    OpContext::new_async(
+    let opctx = OpContext::new_async(
         &rqctx.log,
         async {
             let authn =
@@ -249,7 +388,19 @@ pub async fn op_context_for_external_api(
         |metadata| OpContext::load_request_metadata(rqctx, metadata),
         OpKind::ExternalApiRequest,
     )
-    .await
+    .await?;
+
+    // If this request is being audited, remember who made it.
+    if let Some(actor) = opctx.authn.actor() {
+        let _ = AUDITED_ACTOR.try_with(|audited| {
+            audited.replace(Some(AuditedActor {
+                actor_id: actor.actor_id(),
+                silo_id: actor.silo_id(),
+            }));
+        });
+    }
+
+    Ok(opctx)
 }
 
 pub async fn op_context_for_internal_api(
@@ -37,6 +37,7 @@ use nexus_types::identity::Resource;
 use omicron_common::api::external::http_pagination::PaginatedBy;
 use omicron_common::api::external::{DataPageParams, Error, NameOrId};
 use parse_display::Display;
+use rand::{rngs::StdRng, RngCore, SeedableRng};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_urlencoded;
@@ -54,14 +55,15 @@ use std::{collections::HashSet, ffi::OsString, path::PathBuf, sync::Arc};
 // 1. Navigate an auth-gated console page (e.g., /projects) directly while
 //    logged out. This goes through `console_index_or_login_redirect` and
 //    therefore results in a redirect straight to either
-//    /login/{silo}/local?redirect_uri={} or
-//    /login/{silo}/saml/{provider}?redirect_uri={} depending on the silo's
+//    /login/{silo}/local?redirect_uri={},
+//    /login/{silo}/saml/{provider}?redirect_uri={}, or
+//    /login/{silo}/oidc/{provider}?redirect_uri={} depending on the silo's
 //    authn mode. Nexus takes the path the user was trying to hit and sticks it
 //    in a `redirect_uri` query param.
 // 2. Hit a 401 on a background API call while already in the console (for
 //    example, if session expires while in use). In that case, the console will
 //    navigate to `/login?redirect_uri={current_path}`, which will respond with
-//    a redirect to local or SAML login as above.
+//    a redirect to local, SAML, or OpenID Connect login as above.
 //
 // Local login is very simple. We show a login form, the username and password
 // are POSTed to the API, and on success, the console pulls `redirect_uri` out
@@ -73,6 +75,11 @@ use std::{collections::HashSet, ffi::OsString, path::PathBuf, sync::Arc};
 // in a RelayState query param). On successful login in the IdP, the IdP will
 // POST /login/{silo}/saml/{provider} with a body including that redirect_uri,
 // so that on success, we can redirect to the original target page.
+//
+// OpenID Connect is similar, except that there's no console page:
+// /login/{silo}/oidc/{provider}?redirect_uri={} redirects straight to the IdP
+// with `redirect_uri` encoded in the `state` query param, and the IdP sends the
+// user back to GET /login/{silo}/oidc/{provider}/callback.
 
 // -------------------------------
 // Detailed overview of SAML login
@@ -180,6 +187,32 @@ use std::{collections::HashSet, ffi::OsString, path::PathBuf, sync::Arc};
 // For IDP inititated, the IDP can spontaneously POST a LogoutRequest to
 //
 //   /logout/{silo_name}/{provider_name}
+//
+// OpenID Connect login flow
+// -------------------------
+//
+// Nexus in this case is the relying party, and uses the authorization code
+// flow.  The user's browser is first sent to
+//
+//   GET /login/{silo_name}/oidc/{provider_name}
+//
+// which redirects to the IdP's authorization endpoint:
+//
+//   https://some.idp.test/authorize?response_type=code&client_id=...&state=...&nonce=...
+//
+// At the same time, Nexus sets a short-lived cookie holding a random nonce.
+// The nonce is also included in the `state` param (along with the
+// redirect_uri) and will be embedded by the IdP in the ID token it issues, so
+// that the login can only be completed by the browser that started it.
+//
+// The user authenticates with the IdP, which redirects them back to
+//
+//   GET /login/{silo_name}/oidc/{provider_name}/callback?code=...&state=...
+//
+// Nexus checks `state` against the cookie, exchanges the code at the IdP's
+// token endpoint for an ID token, and verifies the ID token's signature and
+// claims.  From there, the silo user is created or retrieved just as it is
+// for SAML, and the user is redirected to the `redirect_uri` from `state`.
 
 #[derive(Deserialize, JsonSchema)]
 pub struct LoginToProviderPathParam {
@@ -284,6 +317,9 @@ pub async fn login_saml_redirect(
 
                 http_response_found(sign_in_url)
             }
+            IdentityProviderType::Oidc(_) => {
+                Err(not_a_provider_of_type("SAML"))
+            }
         }
     };
 
@@ -329,6 +365,9 @@ pub async fn login_saml(
                         nexus.samael_max_issue_delay(),
                     )?
                 }
+                IdentityProviderType::Oidc(_) => {
+                    return Err(not_a_provider_of_type("SAML"));
+                }
             };
 
         let relay_state =
@@ -369,9 +408,240 @@ pub async fn login_saml(
         }
         Ok(response)
     };
+    apictx.instrument_audited_handler(&rqctx, "login_saml", handler).await
+}
+
+fn not_a_provider_of_type(kind: &str) -> HttpError {
+    HttpError::for_bad_request(
+        None,
+        format!("identity provider is not a {} identity provider", kind),
+    )
+}
+
+/// Name of the cookie holding the nonce for an OpenID Connect login that's in
+/// progress
+const OIDC_NONCE_COOKIE_NAME: &str = "oidc-nonce";
+
+/// How long a user has to complete an OpenID Connect login at the IdP
+const OIDC_LOGIN_TIMEOUT_SECS: i64 = 600;
+
+/// Sent to an OpenID Connect IdP as the `state` param, and sent back to us
+/// along with the authorization code
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct OidcState {
+    pub nonce: String,
+    pub redirect_uri: Option<RelativeUri>,
+}
+
+impl OidcState {
+    pub fn to_encoded(&self) -> Result<String, anyhow::Error> {
+        Ok(base64::Engine::encode(
+            &base64::engine::general_purpose::URL_SAFE_NO_PAD,
+            serde_json::to_string(&self).context("encoding oidc state")?,
+        ))
+    }
+
+    pub fn from_encoded(encoded: &str) -> Result<Self, anyhow::Error> {
+        serde_json::from_slice(
+            &base64::Engine::decode(
+                &base64::engine::general_purpose::URL_SAFE_NO_PAD,
+                encoded,
+            )
+            .context("base64 decoding oidc state")?,
+        )
+        .context("json from oidc state")
+    }
+}
+
+fn oidc_nonce_cookie_header_value(
+    nonce: &str,
+    max_age_secs: i64,
+) -> Result<http::HeaderValue, HttpError> {
+    // SameSite=Lax (rather than Strict) is required here so that the cookie is
+    // sent along when the IdP redirects the user back to us.
+    let value = format!(
+        "{}={}; Path=/login; HttpOnly; SameSite=Lax; Max-Age={}",
+        OIDC_NONCE_COOKIE_NAME, nonce, max_age_secs,
+    );
+    http::HeaderValue::from_str(&value).map_err(|_e| {
+        HttpError::for_internal_error(format!(
+            "unsupported cookie value: {:#}",
+            value
+        ))
+    })
+}
+
+fn generate_oidc_nonce() -> String {
+    let mut rng = StdRng::from_entropy();
+    let mut random_bytes: [u8; 20] = [0; 20];
+    rng.fill_bytes(&mut random_bytes);
+    hex::encode(random_bytes)
+}
+
+/// Get a redirect straight to an OpenID Connect IdP
+///
+/// Unlike SAML, there's no console page for this.  The login URL for the Silo
+/// points straight here.
+#[endpoint {
+   method = GET,
+   path = "/login/{silo_name}/oidc/{provider_name}",
+   tags = ["login"],
+   unpublished = true,
+}]
+pub async fn login_oidc_begin(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    path_params: Path<LoginToProviderPathParam>,
+    query_params: Query<LoginUrlQuery>,
+) -> Result<HttpResponseFound, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let nexus = &apictx.nexus;
+        let path_params = path_params.into_inner();
+
+        // Use opctx_external_authn because this request will be
+        // unauthenticated.
+        let opctx = nexus.opctx_external_authn();
+
+        let (.., identity_provider) = IdentityProviderType::lookup(
+            &nexus.datastore(),
+            &opctx,
+            &path_params.silo_name,
+            &path_params.provider_name,
+        )
+        .await?;
+
+        let IdentityProviderType::Oidc(oidc_identity_provider) =
+            identity_provider
+        else {
+            return Err(not_a_provider_of_type("OpenID Connect"));
+        };
+
+        let nonce = generate_oidc_nonce();
+        let redirect_uri = query_params.into_inner().redirect_uri;
+        let state = OidcState { nonce: nonce.clone(), redirect_uri }
+            .to_encoded()
+            .map_err(|e| {
+                HttpError::for_internal_error(format!(
+                    "encoding oidc state failed: {}",
+                    e
+                ))
+            })?;
+
+        let sign_in_url = oidc_identity_provider
+            .sign_in_url(&state, &nonce)
+            .map_err(|e| HttpError::for_internal_error(e.to_string()))?;
+
+        let mut response = http_response_found(sign_in_url)?;
+        response.headers_mut().append(
+            header::SET_COOKIE,
+            oidc_nonce_cookie_header_value(&nonce, OIDC_LOGIN_TIMEOUT_SECS)?,
+        );
+        Ok(response)
+    };
+
     apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
 }
 
+#[derive(Deserialize, JsonSchema)]
+pub struct OidcCallbackQuery {
+    /// authorization code issued by the IdP
+    pub code: String,
+    /// state sent to the IdP when the login began
+    pub state: String,
+}
+
+/// Authenticate a user via OpenID Connect
+#[endpoint {
+   method = GET,
+   path = "/login/{silo_name}/oidc/{provider_name}/callback",
+   tags = ["login"],
+}]
+pub async fn login_oidc(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    path_params: Path<LoginToProviderPathParam>,
+    query_params: Query<OidcCallbackQuery>,
+    cookies: Cookies,
+) -> Result<HttpResponseSeeOther, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let nexus = &apictx.nexus;
+        let path_params = path_params.into_inner();
+        let query = query_params.into_inner();
+
+        // Make sure that this is the browser that began the login.
+        let state = OidcState::from_encoded(&query.state).map_err(|e| {
+            HttpError::for_bad_request(None, format!("invalid state: {:#}", e))
+        })?;
+        let nonce = cookies
+            .get(OIDC_NONCE_COOKIE_NAME)
+            .map(|cookie| cookie.value())
+            .filter(|nonce| !nonce.is_empty() && *nonce == state.nonce)
+            .ok_or_else(|| {
+                HttpError::for_bad_request(
+                    None,
+                    String::from("state does not match login in progress"),
+                )
+            })?;
+
+        // By definition, this request is not authenticated.  These operations
+        // happen using the Nexus "external authentication" context, which we
+        // keep specifically for this purpose.
+        let opctx = nexus.opctx_external_authn();
+
+        let (authz_silo, db_silo, identity_provider) =
+            IdentityProviderType::lookup(
+                &nexus.datastore(),
+                &opctx,
+                &path_params.silo_name,
+                &path_params.provider_name,
+            )
+            .await?;
+
+        let IdentityProviderType::Oidc(oidc_identity_provider) =
+            identity_provider
+        else {
+            return Err(not_a_provider_of_type("OpenID Connect"));
+        };
+        let authenticated_subject = oidc_identity_provider
+            .authenticated_subject(&query.code, nonce)
+            .await?;
+
+        let user = nexus
+            .silo_user_from_authenticated_subject(
+                &opctx,
+                &authz_silo,
+                &db_silo,
+                &authenticated_subject,
+            )
+            .await?;
+
+        let session = create_session(opctx, apictx, user).await?;
+        let next_url = state
+            .redirect_uri
+            .map(|u| u.to_string())
+            .unwrap_or_else(|| "/".to_string());
+        let mut response = http_response_see_other(next_url)?;
+
+        {
+            let headers = response.headers_mut();
+            let cookie = session_cookie_header_value(
+                &session.token,
+                // use absolute timeout even though session might idle out first.
+                // browser expiration is mostly for convenience, as the API will
+                // reject requests with an expired session regardless
+                apictx.session_absolute_timeout(),
+            )?;
+            headers.append(header::SET_COOKIE, cookie);
+            headers.append(
+                header::SET_COOKIE,
+                oidc_nonce_cookie_header_value("", 0)?,
+            );
+        }
+        Ok(response)
+    };
+    apictx.instrument_audited_handler(&rqctx, "login_oidc", handler).await
+}
+
 #[derive(Deserialize, JsonSchema)]
 pub struct LoginPathParam {
     pub silo_name: crate::db::model::Name,
@@ -437,7 +707,7 @@ pub async fn login_local(
         }
         Ok(response)
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_audited_handler(&rqctx, "login_local", handler).await
 }
 
 async fn create_session(
@@ -502,7 +772,7 @@ pub async fn logout(
         Ok(response)
     };
 
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_audited_handler(&rqctx, "logout", handler).await
 }
 
 #[derive(Deserialize, JsonSchema)]
@@ -599,11 +869,12 @@ async fn get_login_url(
             );
         }
 
-        format!(
-            "/login/{}/saml/{}",
-            silo.name(),
-            idps.into_iter().next().unwrap().name()
-        )
+        let idp = idps.into_iter().next().unwrap();
+        let kind = match idp.provider_type {
+            nexus_db_model::IdentityProviderType::Saml => "saml",
+            nexus_db_model::IdentityProviderType::Oidc => "oidc",
+        };
+        format!("/login/{}/{}/{}", silo.name(), kind, idp.name())
     };
 
     // Stick redirect_url into the state param and URL encode it so it can be
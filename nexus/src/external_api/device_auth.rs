@@ -165,7 +165,9 @@ pub async fn device_auth_confirm(
             .await?;
         Ok(HttpResponseUpdatedNoContent())
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx
+        .instrument_audited_handler(&rqctx, "device_auth_confirm", handler)
+        .await
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
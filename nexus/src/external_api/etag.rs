@@ -0,0 +1,270 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Entity tags and conditional requests (see RFC 9110, Section 8.8.3 and
+//! Section 13)
+//!
+//! Endpoints for resources that can be modified return an "ETag" header
+//! identifying the version of the resource that they returned.  Clients can
+//! send that value back in an "If-Match" header on a subsequent PUT or DELETE
+//! so that the request fails with a 412 ("Precondition Failed") if somebody
+//! else modified the resource in the meantime.
+//!
+//! The precondition is evaluated by the database as part of the update or
+//! delete itself: resources tagged by their `time_modified` are only written
+//! if their modification time is one of those identified by
+//! [`IfMatch::time_modified_candidates()`], and resources tagged by their
+//! content are checked (with [`IfMatch::check()`]) inside the transaction that
+//! replaces them.
+//!
+//! TODO "If-None-Match" is not yet supported.
+
+use chrono::DateTime;
+use chrono::TimeZone;
+use chrono::Utc;
+use dropshot::HttpResponseHeaders;
+use dropshot::HttpResponseOk;
+use dropshot::RequestInfo;
+use omicron_common::api::external::Error;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+/// Identifies one version of the representation of a resource
+///
+/// All of our entity tags are strong validators: two representations with the
+/// same tag are byte-for-byte identical.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ETag(String);
+
+impl ETag {
+    /// Returns the tag for a resource that's updated only by operations that
+    /// also bump its `time_modified`
+    pub fn from_time_modified(time_modified: DateTime<Utc>) -> ETag {
+        // The database stores timestamps with microsecond precision, so that's
+        // all we can use here.  Otherwise the tag for an object returned from
+        // an update would not match the tag for the same object once it's been
+        // read back from the database.
+        ETag(format!("{:x}", time_modified.timestamp_micros()))
+    }
+
+    /// Returns the tag for a resource that has no modification time or
+    /// generation number of its own (e.g., a collection of things that's always
+    /// read and written as a whole) by hashing its representation
+    pub fn from_content<T: Serialize>(content: &T) -> Result<ETag, Error> {
+        let bytes = serde_json::to_vec(content).map_err(|error| {
+            Error::internal_error(&format!(
+                "serializing content for entity tag: {:#}",
+                error
+            ))
+        })?;
+        let digest = ring::digest::digest(&ring::digest::SHA256, &bytes);
+        Ok(ETag(hex::encode(digest.as_ref())))
+    }
+
+    /// Returns the value of this tag as it appears in an HTTP header,
+    /// including the surrounding double quotes
+    pub fn header_value(&self) -> String {
+        format!("\"{}\"", self.0)
+    }
+}
+
+/// Response headers for endpoints that return an entity tag
+#[derive(JsonSchema, Serialize)]
+pub struct ETagHeader {
+    /// HTTP "ETag" header
+    etag: String,
+}
+
+/// Returns a 200 ("OK") response for `body` with an "ETag" header for `etag`
+pub fn http_response_ok<T>(
+    body: T,
+    etag: &ETag,
+) -> HttpResponseHeaders<HttpResponseOk<T>, ETagHeader>
+where
+    T: JsonSchema + Serialize + Send + Sync + 'static,
+{
+    HttpResponseHeaders::new(
+        HttpResponseOk(body),
+        ETagHeader { etag: etag.header_value() },
+    )
+}
+
+/// Describes the contents of an "If-Match" request header
+#[derive(Debug, Eq, PartialEq)]
+pub enum IfMatch {
+    /// "If-Match: *" (matches any current version of the resource)
+    Any,
+    /// The list of entity tags provided by the client
+    ///
+    /// "If-Match" always uses the strong comparison function, which means weak
+    /// tags never match anything.  They're still parsed, but dropped here.
+    Tags(Vec<ETag>),
+}
+
+impl IfMatch {
+    /// Parses the "If-Match" header(s) from an incoming request
+    ///
+    /// Returns `None` if the request has no "If-Match" header, in which case
+    /// the request is unconditional.
+    pub fn from_request(
+        request: &RequestInfo,
+    ) -> Result<Option<IfMatch>, Error> {
+        let mut values =
+            request.headers().get_all(http::header::IF_MATCH).iter();
+        let Some(first) = values.next() else {
+            return Ok(None);
+        };
+
+        let mut tags = Vec::new();
+        for value in std::iter::once(first).chain(values) {
+            let value = value.to_str().map_err(|_| {
+                Error::invalid_request("If-Match header is not valid ASCII")
+            })?;
+            if value.trim() == "*" {
+                return Ok(Some(IfMatch::Any));
+            }
+            parse_entity_tags(value, &mut tags)?;
+        }
+
+        Ok(Some(IfMatch::Tags(tags)))
+    }
+
+    /// Returns an error if the resource whose current version is `current`
+    /// does not satisfy this precondition
+    pub fn check(&self, current: &ETag) -> Result<(), Error> {
+        match self {
+            IfMatch::Any => Ok(()),
+            IfMatch::Tags(tags) if tags.contains(current) => Ok(()),
+            IfMatch::Tags(_) => Err(Error::precondition_failed(
+                "resource has been modified since the version identified by \
+                 the If-Match header",
+            )),
+        }
+    }
+
+    /// Returns the modification times of the versions of a resource (tagged
+    /// with [`ETag::from_time_modified()`]) that satisfy this precondition
+    ///
+    /// Returns `None` if any version does.  Tags that could not have come from
+    /// a modification time are ignored, so this may return an empty list, in
+    /// which case no version does.
+    pub fn time_modified_candidates(&self) -> Option<Vec<DateTime<Utc>>> {
+        match self {
+            IfMatch::Any => None,
+            IfMatch::Tags(tags) => Some(
+                tags.iter()
+                    .filter_map(|tag| {
+                        // `from_time_modified()` formats the (signed) number
+                        // of microseconds as hex, which is its two's
+                        // complement for times before the epoch.
+                        let micros =
+                            u64::from_str_radix(&tag.0, 16).ok()? as i64;
+                        let nanos = micros.rem_euclid(1_000_000) * 1_000;
+                        Utc.timestamp_opt(
+                            micros.div_euclid(1_000_000),
+                            u32::try_from(nanos).ok()?,
+                        )
+                        .single()
+                    })
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// Parses a comma-separated list of entity tags (as found in an "If-Match"
+/// header), appending the strong ones to `tags`
+fn parse_entity_tags(value: &str, tags: &mut Vec<ETag>) -> Result<(), Error> {
+    let bad_header = || Error::invalid_request("malformed If-Match header");
+    let mut rest = value;
+    loop {
+        rest = rest.trim_start_matches(|c| c == ' ' || c == '\t' || c == ',');
+        if rest.is_empty() {
+            return Ok(());
+        }
+
+        let (weak, quoted) = match rest.strip_prefix("W/") {
+            Some(quoted) => (true, quoted),
+            None => (false, rest),
+        };
+        let quoted = quoted.strip_prefix('"').ok_or_else(bad_header)?;
+        let end = quoted.find('"').ok_or_else(bad_header)?;
+        if !weak {
+            tags.push(ETag(quoted[..end].to_string()));
+        }
+
+        rest = quoted[end + 1..].trim_start_matches(|c| c == ' ' || c == '\t');
+        if !rest.is_empty() && !rest.starts_with(',') {
+            return Err(bad_header());
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::parse_entity_tags;
+    use super::ETag;
+    use super::IfMatch;
+    use chrono::TimeZone;
+    use chrono::Utc;
+
+    fn parse(value: &str) -> Result<Vec<String>, ()> {
+        let mut tags = Vec::new();
+        parse_entity_tags(value, &mut tags).map_err(|_| ())?;
+        Ok(tags.into_iter().map(|t| t.0).collect())
+    }
+
+    #[test]
+    fn test_parse_entity_tags() {
+        assert_eq!(parse("\"abc\""), Ok(vec![String::from("abc")]));
+        assert_eq!(
+            parse(" \"abc\" ,\"d,e\",, W/\"weak\", \"\""),
+            Ok(vec![String::from("abc"), String::from("d,e"), String::new()])
+        );
+        assert_eq!(parse(""), Ok(vec![]));
+        assert_eq!(parse("abc"), Err(()));
+        assert_eq!(parse("\"abc"), Err(()));
+        assert_eq!(parse("\"abc\" \"def\""), Err(()));
+        assert_eq!(parse("w/\"abc\""), Err(()));
+    }
+
+    #[test]
+    fn test_etag_values() {
+        let time = Utc.timestamp_nanos(1_700_000_000_123_456_789);
+        let etag = ETag::from_time_modified(time);
+        assert_eq!(etag.header_value(), "\"60a2418202240\"");
+        assert_eq!(
+            etag,
+            ETag::from_time_modified(
+                Utc.timestamp_nanos(1_700_000_000_123_456_000)
+            )
+        );
+
+        let content = ETag::from_content(&vec!["a", "b"]).unwrap();
+        assert_eq!(content, ETag::from_content(&vec!["a", "b"]).unwrap());
+        assert_ne!(content, ETag::from_content(&vec!["b", "a"]).unwrap());
+        assert_eq!(content.0.len(), 64);
+    }
+
+    #[test]
+    fn test_time_modified_candidates() {
+        let time = Utc.timestamp_nanos(1_700_000_000_123_456_000);
+        let before_epoch = Utc.timestamp_nanos(-1_000);
+        let if_match = IfMatch::Tags(vec![
+            ETag::from_time_modified(time),
+            ETag::from_content(&vec!["a", "b"]).unwrap(),
+            ETag(String::from("not-a-time")),
+            ETag::from_time_modified(before_epoch),
+        ]);
+        assert_eq!(
+            if_match.time_modified_candidates(),
+            Some(vec![time, before_epoch])
+        );
+        assert_eq!(
+            IfMatch::Tags(vec![]).time_modified_candidates(),
+            Some(vec![])
+        );
+        assert_eq!(IfMatch::Any.time_modified_candidates(), None);
+    }
+}
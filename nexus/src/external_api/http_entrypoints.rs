@@ -5,17 +5,22 @@
 //! Handler functions (entrypoints) for external HTTP APIs
 
 use super::{
-    console_api, device_auth, params,
+    console_api, device_auth, params, scim,
     views::{
-        self, Certificate, Group, IdentityProvider, Image, IpPool, IpPoolRange,
-        PhysicalDisk, Project, Rack, Role, Silo, Sled, Snapshot, SshKey, User,
-        UserBuiltin, Vpc, VpcRouter, VpcSubnet,
+        self, AffinityGroup, Certificate, FloatingIp, Group, IdentityProvider,
+        Image, IpPool, IpPoolRange, PhysicalDisk, Project, Rack, Role, Silo,
+        Sled, Snapshot, SnapshotSchedule, SshKey, User, UserBuiltin, Vpc,
+        VpcPeering, VpcRouter, VpcSubnet, WebhookDelivery, WebhookReceiver,
     },
 };
 use crate::authz;
 use crate::db;
 use crate::db::identity::Resource;
 use crate::db::model::Name;
+use crate::external_api::etag;
+use crate::external_api::etag::ETag;
+use crate::external_api::etag::ETagHeader;
+use crate::external_api::etag::IfMatch;
 use crate::external_api::shared;
 use crate::ServerContext;
 use chrono::Utc;
@@ -25,6 +30,7 @@ use dropshot::HttpError;
 use dropshot::HttpResponseAccepted;
 use dropshot::HttpResponseCreated;
 use dropshot::HttpResponseDeleted;
+use dropshot::HttpResponseHeaders;
 use dropshot::HttpResponseOk;
 use dropshot::HttpResponseUpdatedNoContent;
 use dropshot::PaginationOrder;
@@ -40,7 +46,6 @@ use dropshot::{
 };
 use ipnetwork::IpNetwork;
 use nexus_db_queries::authz::ApiResource;
-use nexus_db_queries::db::lookup::ImageLookup;
 use nexus_db_queries::db::lookup::ImageParentLookup;
 use nexus_types::external_api::params::ProjectSelector;
 use nexus_types::{
@@ -61,12 +66,16 @@ use omicron_common::api::external::http_pagination::ScanParams;
 use omicron_common::api::external::AddressLot;
 use omicron_common::api::external::AddressLotBlock;
 use omicron_common::api::external::AddressLotCreateResponse;
+use omicron_common::api::external::BgpAnnounceSet;
+use omicron_common::api::external::BgpAnnouncement;
+use omicron_common::api::external::BgpConfig;
 use omicron_common::api::external::DataPageParams;
 use omicron_common::api::external::Disk;
 use omicron_common::api::external::Error;
 use omicron_common::api::external::Instance;
 use omicron_common::api::external::InstanceNetworkInterface;
 use omicron_common::api::external::InternalContext;
+use omicron_common::api::external::Labels;
 use omicron_common::api::external::LoopbackAddress;
 use omicron_common::api::external::NameOrId;
 use omicron_common::api::external::RouterRoute;
@@ -109,6 +118,8 @@ pub fn external_api() -> NexusApiDescription {
         api.register(project_update)?;
         api.register(project_policy_view)?;
         api.register(project_policy_update)?;
+        api.register(project_quota_view)?;
+        api.register(project_quota_update)?;
         api.register(project_ip_pool_list)?;
         api.register(project_ip_pool_view)?;
 
@@ -118,6 +129,9 @@ pub fn external_api() -> NexusApiDescription {
         api.register(ip_pool_view)?;
         api.register(ip_pool_delete)?;
         api.register(ip_pool_update)?;
+        api.register(ip_pool_silo_link)?;
+        api.register(ip_pool_silo_unlink)?;
+        api.register(ip_pool_set_default)?;
         // Variants for internal services
         api.register(ip_pool_service_view)?;
 
@@ -141,9 +155,18 @@ pub fn external_api() -> NexusApiDescription {
         api.register(disk_bulk_write_import_stop)?;
         api.register(disk_import_blocks_from_url)?;
         api.register(disk_finalize_import)?;
+        api.register(disk_export_start)?;
+        api.register(disk_bulk_read_export)?;
+        api.register(disk_export_stop)?;
+        api.register(disk_resize)?;
+        api.register(disk_rollback)?;
+        api.register(disk_labels_update)?;
 
         api.register(instance_list)?;
         api.register(instance_view)?;
+        api.register(instance_update)?;
+        api.register(instance_labels_update)?;
+        api.register(instance_user_data_update)?;
         api.register(instance_create)?;
         api.register(instance_delete)?;
         api.register(instance_migrate)?;
@@ -151,6 +174,7 @@ pub fn external_api() -> NexusApiDescription {
         api.register(instance_start)?;
         api.register(instance_stop)?;
         api.register(instance_disk_list)?;
+        api.register(instance_ssh_public_key_list)?;
         api.register(instance_disk_attach)?;
         api.register(instance_disk_detach)?;
         api.register(instance_serial_console)?;
@@ -162,11 +186,30 @@ pub fn external_api() -> NexusApiDescription {
         api.register(image_delete)?;
         api.register(image_promote)?;
         api.register(image_demote)?;
+        api.register(image_labels_update)?;
+        api.register(image_silo_list)?;
+        api.register(image_silo_add)?;
+        api.register(image_silo_remove)?;
 
         api.register(snapshot_list)?;
         api.register(snapshot_create)?;
         api.register(snapshot_view)?;
         api.register(snapshot_delete)?;
+        api.register(snapshot_labels_update)?;
+        api.register(snapshot_export_start)?;
+        api.register(snapshot_bulk_read_export)?;
+        api.register(snapshot_export_stop)?;
+
+        api.register(snapshot_schedule_list)?;
+        api.register(snapshot_schedule_create)?;
+        api.register(snapshot_schedule_view)?;
+        api.register(snapshot_schedule_delete)?;
+
+        api.register(webhook_receiver_list)?;
+        api.register(webhook_receiver_create)?;
+        api.register(webhook_receiver_view)?;
+        api.register(webhook_receiver_delete)?;
+        api.register(webhook_delivery_list)?;
 
         api.register(vpc_list)?;
         api.register(vpc_create)?;
@@ -181,6 +224,11 @@ pub fn external_api() -> NexusApiDescription {
         api.register(vpc_subnet_update)?;
         api.register(vpc_subnet_list_network_interfaces)?;
 
+        api.register(vpc_peering_list)?;
+        api.register(vpc_peering_create)?;
+        api.register(vpc_peering_view)?;
+        api.register(vpc_peering_delete)?;
+
         api.register(instance_network_interface_create)?;
         api.register(instance_network_interface_list)?;
         api.register(instance_network_interface_view)?;
@@ -189,6 +237,21 @@ pub fn external_api() -> NexusApiDescription {
 
         api.register(instance_external_ip_list)?;
 
+        api.register(floating_ip_list)?;
+        api.register(floating_ip_create)?;
+        api.register(floating_ip_view)?;
+        api.register(floating_ip_delete)?;
+        api.register(floating_ip_attach)?;
+        api.register(floating_ip_detach)?;
+
+        api.register(affinity_group_list)?;
+        api.register(affinity_group_create)?;
+        api.register(affinity_group_view)?;
+        api.register(affinity_group_delete)?;
+        api.register(affinity_group_member_list)?;
+        api.register(affinity_group_member_add)?;
+        api.register(affinity_group_member_remove)?;
+
         api.register(vpc_router_list)?;
         api.register(vpc_router_view)?;
         api.register(vpc_router_create)?;
@@ -227,6 +290,10 @@ pub fn external_api() -> NexusApiDescription {
         api.register(current_user_ssh_key_create)?;
         api.register(current_user_ssh_key_delete)?;
 
+        api.register(current_user_token_list)?;
+        api.register(current_user_token_create)?;
+        api.register(current_user_token_delete)?;
+
         // Customer network integration
         api.register(networking_address_lot_list)?;
         api.register(networking_address_lot_create)?;
@@ -246,6 +313,14 @@ pub fn external_api() -> NexusApiDescription {
         api.register(networking_switch_port_apply_settings)?;
         api.register(networking_switch_port_clear_settings)?;
 
+        api.register(networking_bgp_config_list)?;
+        api.register(networking_bgp_config_create)?;
+        api.register(networking_bgp_config_delete)?;
+        api.register(networking_bgp_announce_set_list)?;
+        api.register(networking_bgp_announce_set_create)?;
+        api.register(networking_bgp_announce_set_delete)?;
+        api.register(networking_bgp_announcement_list)?;
+
         // Fleet-wide API operations
         api.register(silo_list)?;
         api.register(silo_create)?;
@@ -253,16 +328,24 @@ pub fn external_api() -> NexusApiDescription {
         api.register(silo_delete)?;
         api.register(silo_policy_view)?;
         api.register(silo_policy_update)?;
+        api.register(silo_quota_view)?;
+        api.register(silo_quota_update)?;
 
         api.register(silo_identity_provider_list)?;
 
         api.register(saml_identity_provider_create)?;
         api.register(saml_identity_provider_view)?;
+        api.register(oidc_identity_provider_create)?;
+        api.register(oidc_identity_provider_view)?;
 
         api.register(local_idp_user_create)?;
         api.register(local_idp_user_delete)?;
         api.register(local_idp_user_set_password)?;
 
+        api.register(scim_token_list)?;
+        api.register(scim_token_create)?;
+        api.register(scim_token_delete)?;
+
         api.register(certificate_list)?;
         api.register(certificate_create)?;
         api.register(certificate_view)?;
@@ -271,6 +354,8 @@ pub fn external_api() -> NexusApiDescription {
         api.register(system_metric)?;
         api.register(silo_metric)?;
 
+        api.register(audit_log_list)?;
+
         api.register(system_update_refresh)?;
         api.register(system_version)?;
         api.register(system_component_version_list)?;
@@ -295,6 +380,8 @@ pub fn external_api() -> NexusApiDescription {
         api.register(console_api::login_saml_begin)?;
         api.register(console_api::login_saml_redirect)?;
         api.register(console_api::login_saml)?;
+        api.register(console_api::login_oidc_begin)?;
+        api.register(console_api::login_oidc)?;
         api.register(console_api::logout)?;
 
         api.register(console_api::console_projects)?;
@@ -313,6 +400,20 @@ pub fn external_api() -> NexusApiDescription {
         api.register(device_auth::device_auth_confirm)?;
         api.register(device_auth::device_access_token)?;
 
+        api.register(scim::scim_service_provider_config)?;
+        api.register(scim::scim_user_list)?;
+        api.register(scim::scim_user_view)?;
+        api.register(scim::scim_user_create)?;
+        api.register(scim::scim_user_replace)?;
+        api.register(scim::scim_user_patch)?;
+        api.register(scim::scim_user_delete)?;
+        api.register(scim::scim_group_list)?;
+        api.register(scim::scim_group_view)?;
+        api.register(scim::scim_group_create)?;
+        api.register(scim::scim_group_replace)?;
+        api.register(scim::scim_group_patch)?;
+        api.register(scim::scim_group_delete)?;
+
         Ok(())
     }
 
@@ -408,7 +509,9 @@ async fn system_policy_update(
         let policy = nexus.fleet_update_policy(&opctx, &new_policy).await?;
         Ok(HttpResponseOk(policy))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx
+        .instrument_audited_handler(&rqctx, "system_policy_update", handler)
+        .await
 }
 
 /// Fetch the current silo's IAM policy
@@ -419,7 +522,13 @@ async fn system_policy_update(
  }]
 pub async fn policy_view(
     rqctx: RequestContext<Arc<ServerContext>>,
-) -> Result<HttpResponseOk<shared::Policy<shared::SiloRole>>, HttpError> {
+) -> Result<
+    HttpResponseHeaders<
+        HttpResponseOk<shared::Policy<shared::SiloRole>>,
+        ETagHeader,
+    >,
+    HttpError,
+> {
     let apictx = rqctx.context();
     let handler = async {
         let nexus = &apictx.nexus;
@@ -433,7 +542,8 @@ pub async fn policy_view(
 
         let silo_lookup = nexus.silo_lookup(&opctx, silo)?;
         let policy = nexus.silo_fetch_policy(&opctx, &silo_lookup).await?;
-        Ok(HttpResponseOk(policy))
+        let etag = ETag::from_content(&policy)?;
+        Ok(etag::http_response_ok(policy, &etag))
     };
     apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
 }
@@ -447,7 +557,13 @@ pub async fn policy_view(
 async fn policy_update(
     rqctx: RequestContext<Arc<ServerContext>>,
     new_policy: TypedBody<shared::Policy<shared::SiloRole>>,
-) -> Result<HttpResponseOk<shared::Policy<shared::SiloRole>>, HttpError> {
+) -> Result<
+    HttpResponseHeaders<
+        HttpResponseOk<shared::Policy<shared::SiloRole>>,
+        ETagHeader,
+    >,
+    HttpError,
+> {
     let apictx = rqctx.context();
     let handler = async {
         let nexus = &apictx.nexus;
@@ -463,11 +579,21 @@ async fn policy_update(
             .id()
             .into();
         let silo_lookup = nexus.silo_lookup(&opctx, silo)?;
-        let policy =
-            nexus.silo_update_policy(&opctx, &silo_lookup, &new_policy).await?;
-        Ok(HttpResponseOk(policy))
+        let if_match = IfMatch::from_request(&rqctx.request)?;
+        let policy = nexus
+            .silo_update_policy(&opctx, &silo_lookup, &new_policy, |current| {
+                match &if_match {
+                    Some(if_match) => {
+                        if_match.check(&ETag::from_content(current)?)
+                    }
+                    None => Ok(()),
+                }
+            })
+            .await?;
+        let etag = ETag::from_content(&policy)?;
+        Ok(etag::http_response_ok(policy, &etag))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_audited_handler(&rqctx, "policy_update", handler).await
 }
 
 /// List silos
@@ -523,7 +649,7 @@ async fn silo_create(
             nexus.silo_create(&opctx, new_silo_params.into_inner()).await?;
         Ok(HttpResponseCreated(silo.try_into()?))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_audited_handler(&rqctx, "silo_create", handler).await
 }
 
 /// Fetch a silo
@@ -552,7 +678,8 @@ async fn silo_view(
 
 /// Delete a silo
 ///
-/// Delete a silo by name.
+/// Delete a silo by name.  A silo can't be deleted while it contains projects
+/// or has IP pools linked to it.
 #[endpoint {
     method = DELETE,
     path = "/v1/system/silos/{silo}",
@@ -571,7 +698,7 @@ async fn silo_delete(
         nexus.silo_delete(&opctx, &silo_lookup).await?;
         Ok(HttpResponseDeleted())
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_audited_handler(&rqctx, "silo_delete", handler).await
 }
 
 /// Fetch a silo's IAM policy
@@ -583,7 +710,13 @@ async fn silo_delete(
 async fn silo_policy_view(
     rqctx: RequestContext<Arc<ServerContext>>,
     path_params: Path<params::SiloPath>,
-) -> Result<HttpResponseOk<shared::Policy<shared::SiloRole>>, HttpError> {
+) -> Result<
+    HttpResponseHeaders<
+        HttpResponseOk<shared::Policy<shared::SiloRole>>,
+        ETagHeader,
+    >,
+    HttpError,
+> {
     let apictx = rqctx.context();
     let handler = async {
         let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
@@ -591,7 +724,8 @@ async fn silo_policy_view(
         let path = path_params.into_inner();
         let silo_lookup = nexus.silo_lookup(&opctx, path.silo)?;
         let policy = nexus.silo_fetch_policy(&opctx, &silo_lookup).await?;
-        Ok(HttpResponseOk(policy))
+        let etag = ETag::from_content(&policy)?;
+        Ok(etag::http_response_ok(policy, &etag))
     };
     apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
 }
@@ -606,7 +740,13 @@ async fn silo_policy_update(
     rqctx: RequestContext<Arc<ServerContext>>,
     path_params: Path<params::SiloPath>,
     new_policy: TypedBody<shared::Policy<shared::SiloRole>>,
-) -> Result<HttpResponseOk<shared::Policy<shared::SiloRole>>, HttpError> {
+) -> Result<
+    HttpResponseHeaders<
+        HttpResponseOk<shared::Policy<shared::SiloRole>>,
+        ETagHeader,
+    >,
+    HttpError,
+> {
     let apictx = rqctx.context();
     let handler = async {
         let new_policy = new_policy.into_inner();
@@ -617,13 +757,78 @@ async fn silo_policy_update(
         let nexus = &apictx.nexus;
         let path = path_params.into_inner();
         let silo_lookup = nexus.silo_lookup(&opctx, path.silo)?;
-        let policy =
-            nexus.silo_update_policy(&opctx, &silo_lookup, &new_policy).await?;
-        Ok(HttpResponseOk(policy))
+        let if_match = IfMatch::from_request(&rqctx.request)?;
+        let policy = nexus
+            .silo_update_policy(&opctx, &silo_lookup, &new_policy, |current| {
+                match &if_match {
+                    Some(if_match) => {
+                        if_match.check(&ETag::from_content(current)?)
+                    }
+                    None => Ok(()),
+                }
+            })
+            .await?;
+        let etag = ETag::from_content(&policy)?;
+        Ok(etag::http_response_ok(policy, &etag))
+    };
+    apictx
+        .instrument_audited_handler(&rqctx, "silo_policy_update", handler)
+        .await
+}
+
+/// Fetch a silo's quota
+#[endpoint {
+    method = GET,
+    path = "/v1/system/silos/{silo}/quotas",
+    tags = ["system/silos"],
+}]
+async fn silo_quota_view(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    path_params: Path<params::SiloPath>,
+) -> Result<HttpResponseOk<views::Quota>, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.nexus;
+        let path = path_params.into_inner();
+        let silo_lookup = nexus.silo_lookup(&opctx, path.silo)?;
+        let quota = nexus.silo_quota_view(&opctx, &silo_lookup).await?;
+        Ok(HttpResponseOk(quota.into()))
     };
     apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
 }
 
+/// Update a silo's quota
+///
+/// Limits which are omitted are removed, allowing the silo to provision an
+/// unlimited amount of the corresponding resource.
+#[endpoint {
+    method = PUT,
+    path = "/v1/system/silos/{silo}/quotas",
+    tags = ["system/silos"],
+}]
+async fn silo_quota_update(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    path_params: Path<params::SiloPath>,
+    updated_quota: TypedBody<params::QuotaUpdate>,
+) -> Result<HttpResponseOk<views::Quota>, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.nexus;
+        let path = path_params.into_inner();
+        let updated_quota = updated_quota.into_inner();
+        let silo_lookup = nexus.silo_lookup(&opctx, path.silo)?;
+        let quota = nexus
+            .silo_quota_update(&opctx, &silo_lookup, &updated_quota)
+            .await?;
+        Ok(HttpResponseOk(quota.into()))
+    };
+    apictx
+        .instrument_audited_handler(&rqctx, "silo_quota_update", handler)
+        .await
+}
+
 // Silo-specific user endpoints
 
 /// List built-in (system) users in a silo
@@ -757,7 +962,13 @@ async fn saml_identity_provider_create(
             .await?;
         Ok(HttpResponseCreated(provider.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx
+        .instrument_audited_handler(
+            &rqctx,
+            "saml_identity_provider_create",
+            handler,
+        )
+        .await
 }
 
 /// Fetch a SAML IdP
@@ -794,6 +1005,80 @@ async fn saml_identity_provider_view(
     apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
 }
 
+// Silo OpenID Connect identity providers
+
+/// Create an OpenID Connect IdP
+///
+/// The IdP's discovery document and signing keys are fetched once, when the
+/// IdP is created.
+#[endpoint {
+    method = POST,
+    path = "/v1/system/identity-providers/oidc",
+    tags = ["system/silos"],
+}]
+async fn oidc_identity_provider_create(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    query_params: Query<params::SiloSelector>,
+    new_provider: TypedBody<params::OidcIdentityProviderCreate>,
+) -> Result<HttpResponseCreated<views::OidcIdentityProvider>, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.nexus;
+        let query = query_params.into_inner();
+        let silo_lookup = nexus.silo_lookup(&opctx, query.silo)?;
+        let provider = nexus
+            .oidc_identity_provider_create(
+                &opctx,
+                &silo_lookup,
+                new_provider.into_inner(),
+            )
+            .await?;
+        Ok(HttpResponseCreated(provider.into()))
+    };
+    apictx
+        .instrument_audited_handler(
+            &rqctx,
+            "oidc_identity_provider_create",
+            handler,
+        )
+        .await
+}
+
+/// Fetch an OpenID Connect IdP
+#[endpoint {
+    method = GET,
+    path = "/v1/system/identity-providers/oidc/{provider}",
+    tags = ["system/silos"],
+}]
+async fn oidc_identity_provider_view(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    path_params: Path<params::ProviderPath>,
+    query_params: Query<params::SiloSelector>,
+) -> Result<HttpResponseOk<views::OidcIdentityProvider>, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.nexus;
+        let path = path_params.into_inner();
+        let query = query_params.into_inner();
+        let oidc_identity_provider_selector =
+            params::OidcIdentityProviderSelector {
+                silo: Some(query.silo),
+                oidc_identity_provider: path.provider,
+            };
+        let (.., provider) = nexus
+            .oidc_identity_provider_lookup(
+                &opctx,
+                oidc_identity_provider_selector,
+            )?
+            .fetch()
+            .await?;
+        Ok(HttpResponseOk(provider.into()))
+    };
+    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+}
+
 // TODO: no DELETE for identity providers?
 
 // "Local" Identity Provider
@@ -828,7 +1113,9 @@ async fn local_idp_user_create(
             .await?;
         Ok(HttpResponseCreated(user.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx
+        .instrument_audited_handler(&rqctx, "local_idp_user_create", handler)
+        .await
 }
 
 /// Delete a user
@@ -852,7 +1139,9 @@ async fn local_idp_user_delete(
         nexus.local_idp_delete_user(&opctx, &silo_lookup, path.user_id).await?;
         Ok(HttpResponseDeleted())
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx
+        .instrument_audited_handler(&rqctx, "local_idp_user_delete", handler)
+        .await
 }
 
 /// Set or invalidate a user's password
@@ -887,9 +1176,124 @@ async fn local_idp_user_set_password(
             .await?;
         Ok(HttpResponseUpdatedNoContent())
     };
+    apictx
+        .instrument_audited_handler(
+            &rqctx,
+            "local_idp_user_set_password",
+            handler,
+        )
+        .await
+}
+
+// SCIM client tokens
+
+/// List SCIM client tokens
+///
+/// Lists the bearer tokens that SCIM clients can use to provision users and
+/// groups in a Silo.  The tokens themselves are not included.
+#[endpoint {
+    method = GET,
+    path = "/v1/system/scim/tokens",
+    tags = ["system/silos"],
+}]
+async fn scim_token_list(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    query_params: Query<PaginatedById<params::SiloSelector>>,
+) -> Result<HttpResponseOk<ResultsPage<views::ScimClientBearerToken>>, HttpError>
+{
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.nexus;
+        let query = query_params.into_inner();
+        let pag_params = data_page_params_for(&rqctx, &query)?;
+        let scan_params = ScanById::from_query(&query)?;
+        let silo_lookup =
+            nexus.silo_lookup(&opctx, scan_params.selector.silo.clone())?;
+        let tokens = nexus
+            .scim_client_bearer_tokens_list(&opctx, &silo_lookup, &pag_params)
+            .await?
+            .into_iter()
+            .map(views::ScimClientBearerToken::from)
+            .collect();
+        Ok(HttpResponseOk(ScanById::results_page(
+            &query,
+            tokens,
+            &|_, token: &views::ScimClientBearerToken| token.id,
+        )?))
+    };
     apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
 }
 
+/// Create a SCIM client token
+///
+/// Tokens can only be created in Silos with identity mode `SamlScim`.  The
+/// token is only included in this response and cannot be retrieved later.
+#[endpoint {
+    method = POST,
+    path = "/v1/system/scim/tokens",
+    tags = ["system/silos"],
+}]
+async fn scim_token_create(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    query_params: Query<params::SiloSelector>,
+    new_token: TypedBody<params::ScimClientBearerTokenCreate>,
+) -> Result<HttpResponseCreated<views::ScimClientBearerTokenCreated>, HttpError>
+{
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.nexus;
+        let query = query_params.into_inner();
+        let silo_lookup = nexus.silo_lookup(&opctx, query.silo)?;
+        let token = nexus
+            .scim_client_bearer_token_create(
+                &opctx,
+                &silo_lookup,
+                new_token.into_inner(),
+            )
+            .await?;
+        Ok(HttpResponseCreated(token.into()))
+    };
+    apictx
+        .instrument_audited_handler(&rqctx, "scim_token_create", handler)
+        .await
+}
+
+/// Delete a SCIM client token
+///
+/// Requests using the token will fail from then on.
+#[endpoint {
+    method = DELETE,
+    path = "/v1/system/scim/tokens/{token_id}",
+    tags = ["system/silos"],
+}]
+async fn scim_token_delete(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    path_params: Path<params::ScimClientBearerTokenPath>,
+    query_params: Query<params::SiloSelector>,
+) -> Result<HttpResponseDeleted, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.nexus;
+        let path = path_params.into_inner();
+        let query = query_params.into_inner();
+        let silo_lookup = nexus.silo_lookup(&opctx, query.silo)?;
+        nexus
+            .scim_client_bearer_token_delete(
+                &opctx,
+                &silo_lookup,
+                path.token_id,
+            )
+            .await?;
+        Ok(HttpResponseDeleted())
+    };
+    apictx
+        .instrument_audited_handler(&rqctx, "scim_token_delete", handler)
+        .await
+}
+
 /// List projects
 #[endpoint {
     method = GET,
@@ -898,7 +1302,7 @@ async fn local_idp_user_set_password(
 }]
 async fn project_list(
     rqctx: RequestContext<Arc<ServerContext>>,
-    query_params: Query<PaginatedByNameOrId>,
+    query_params: Query<PaginatedByNameOrId<params::OptionalLabelSelector>>,
 ) -> Result<HttpResponseOk<ResultsPage<Project>>, HttpError> {
     let apictx = rqctx.context();
     let handler = async {
@@ -909,7 +1313,11 @@ async fn project_list(
         let paginated_by = name_or_id_pagination(&pag_params, scan_params)?;
         let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
         let projects = nexus
-            .project_list(&opctx, &paginated_by)
+            .project_list(
+                &opctx,
+                &paginated_by,
+                scan_params.selector.label_selector.as_ref(),
+            )
             .await?
             .into_iter()
             .map(|p| p.into())
@@ -941,7 +1349,7 @@ async fn project_create(
             nexus.project_create(&opctx, &new_project.into_inner()).await?;
         Ok(HttpResponseCreated(project.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_audited_handler(&rqctx, "project_create", handler).await
 }
 
 /// Fetch a project
@@ -953,7 +1361,8 @@ async fn project_create(
 async fn project_view(
     rqctx: RequestContext<Arc<ServerContext>>,
     path_params: Path<params::ProjectPath>,
-) -> Result<HttpResponseOk<Project>, HttpError> {
+) -> Result<HttpResponseHeaders<HttpResponseOk<Project>, ETagHeader>, HttpError>
+{
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
@@ -963,7 +1372,8 @@ async fn project_view(
             params::ProjectSelector { project: path.project };
         let (.., project) =
             nexus.project_lookup(&opctx, project_selector)?.fetch().await?;
-        Ok(HttpResponseOk(project.into()))
+        let etag = ETag::from_time_modified(project.time_modified());
+        Ok(etag::http_response_ok(project.into(), &etag))
     };
     apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
 }
@@ -986,10 +1396,18 @@ async fn project_delete(
         let project_selector =
             params::ProjectSelector { project: path.project };
         let project_lookup = nexus.project_lookup(&opctx, project_selector)?;
-        nexus.project_delete(&opctx, &project_lookup).await?;
+        let expected_time_modified = IfMatch::from_request(&rqctx.request)?
+            .and_then(|if_match| if_match.time_modified_candidates());
+        nexus
+            .project_delete(
+                &opctx,
+                &project_lookup,
+                expected_time_modified.as_deref(),
+            )
+            .await?;
         Ok(HttpResponseDeleted())
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_audited_handler(&rqctx, "project_delete", handler).await
 }
 
 // TODO-correctness: Is it valid for PUT to accept application/json that's a
@@ -1007,7 +1425,8 @@ async fn project_update(
     rqctx: RequestContext<Arc<ServerContext>>,
     path_params: Path<params::ProjectPath>,
     updated_project: TypedBody<params::ProjectUpdate>,
-) -> Result<HttpResponseOk<Project>, HttpError> {
+) -> Result<HttpResponseHeaders<HttpResponseOk<Project>, ETagHeader>, HttpError>
+{
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
@@ -1017,12 +1436,20 @@ async fn project_update(
         let project_selector =
             params::ProjectSelector { project: path.project };
         let project_lookup = nexus.project_lookup(&opctx, project_selector)?;
+        let expected_time_modified = IfMatch::from_request(&rqctx.request)?
+            .and_then(|if_match| if_match.time_modified_candidates());
         let project = nexus
-            .project_update(&opctx, &project_lookup, &updated_project)
+            .project_update(
+                &opctx,
+                &project_lookup,
+                &updated_project,
+                expected_time_modified.as_deref(),
+            )
             .await?;
-        Ok(HttpResponseOk(project.into()))
+        let etag = ETag::from_time_modified(project.time_modified());
+        Ok(etag::http_response_ok(project.into(), &etag))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_audited_handler(&rqctx, "project_update", handler).await
 }
 
 /// Fetch a project's IAM policy
@@ -1075,9 +1502,68 @@ async fn project_policy_update(
             .await?;
         Ok(HttpResponseOk(new_policy))
     };
+    apictx
+        .instrument_audited_handler(&rqctx, "project_policy_update", handler)
+        .await
+}
+
+/// Fetch a project's quota
+#[endpoint {
+    method = GET,
+    path = "/v1/projects/{project}/quotas",
+    tags = ["projects"],
+}]
+async fn project_quota_view(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    path_params: Path<params::ProjectPath>,
+) -> Result<HttpResponseOk<views::Quota>, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.nexus;
+        let path = path_params.into_inner();
+        let project_selector =
+            params::ProjectSelector { project: path.project };
+        let project_lookup = nexus.project_lookup(&opctx, project_selector)?;
+        let quota = nexus.project_quota_view(&opctx, &project_lookup).await?;
+        Ok(HttpResponseOk(quota.into()))
+    };
     apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
 }
 
+/// Update a project's quota
+///
+/// Project quotas may only be changed by administrators of the enclosing
+/// silo. Limits which are omitted are removed.
+#[endpoint {
+    method = PUT,
+    path = "/v1/projects/{project}/quotas",
+    tags = ["projects"],
+}]
+async fn project_quota_update(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    path_params: Path<params::ProjectPath>,
+    updated_quota: TypedBody<params::QuotaUpdate>,
+) -> Result<HttpResponseOk<views::Quota>, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.nexus;
+        let path = path_params.into_inner();
+        let updated_quota = updated_quota.into_inner();
+        let project_selector =
+            params::ProjectSelector { project: path.project };
+        let project_lookup = nexus.project_lookup(&opctx, project_selector)?;
+        let quota = nexus
+            .project_quota_update(&opctx, &project_lookup, &updated_quota)
+            .await?;
+        Ok(HttpResponseOk(quota.into()))
+    };
+    apictx
+        .instrument_audited_handler(&rqctx, "project_quota_update", handler)
+        .await
+}
+
 // IP Pools
 
 /// List all IP Pools that can be used by a given project.
@@ -1091,11 +1577,9 @@ async fn project_ip_pool_list(
     query_params: Query<PaginatedByNameOrId<params::ProjectSelector>>,
 ) -> Result<HttpResponseOk<ResultsPage<IpPool>>, HttpError> {
     // Per https://github.com/oxidecomputer/omicron/issues/2148
-    // This is currently the same list as /v1/system/ip-pools, that is to say,
-    // IP pools that are *available to* a given project, those being ones that
-    // are not the internal pools for Oxide service usage. This may change
-    // in the future as the scoping of pools is further developed, but for now,
-    // this is literally a near-duplicate of `ip_pool_list`:
+    // These are the IP pools that are *available to* a given project, those
+    // being the ones linked to its Silo and the ones linked to no Silo at all,
+    // but not the internal pools for Oxide service usage.
     let apictx = rqctx.context();
     let handler = async {
         let nexus = &apictx.nexus;
@@ -1148,8 +1632,10 @@ async fn project_ip_pool_view(
             .fetch()
             .await?;
         // TODO(2148): once we've actualy implemented filtering to pools belonging to
-        // the specified project, we can remove this internal check.
-        if pool.internal {
+        // the specified project, we can remove these checks.  Pools linked to
+        // another Silo are not visible from this one.
+        let silo_id = opctx.authn.silo_required()?.id();
+        if pool.internal || pool.silo_id.map_or(false, |id| id != silo_id) {
             return Err(authz_pool.not_found().into());
         }
         Ok(HttpResponseOk(IpPool::from(pool)))
@@ -1213,7 +1699,7 @@ async fn ip_pool_create(
         let pool = nexus.ip_pool_create(&opctx, &pool_params).await?;
         Ok(HttpResponseCreated(IpPool::from(pool)))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_audited_handler(&rqctx, "ip_pool_create", handler).await
 }
 
 /// Fetch an IP pool
@@ -1257,7 +1743,7 @@ async fn ip_pool_delete(
         nexus.ip_pool_delete(&opctx, &pool_lookup).await?;
         Ok(HttpResponseDeleted())
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_audited_handler(&rqctx, "ip_pool_delete", handler).await
 }
 
 /// Update an IP Pool
@@ -1281,33 +1767,119 @@ async fn ip_pool_update(
         let pool = nexus.ip_pool_update(&opctx, &pool_lookup, &updates).await?;
         Ok(HttpResponseOk(pool.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_audited_handler(&rqctx, "ip_pool_update", handler).await
 }
 
-/// Fetch the IP pool used for Oxide services
+/// Link an IP pool to a Silo
+///
+/// Only instances in the Silo may draw addresses from a linked pool.  A pool
+/// can be linked to only one Silo, so this replaces any Silo it's linked to.
 #[endpoint {
-    method = GET,
-    path = "/v1/system/ip-pools-service",
+    method = POST,
+    path = "/v1/system/ip-pools/{pool}/link",
     tags = ["system/networking"],
 }]
-async fn ip_pool_service_view(
+async fn ip_pool_silo_link(
     rqctx: RequestContext<Arc<ServerContext>>,
+    path_params: Path<params::IpPoolPath>,
+    link_params: TypedBody<params::IpPoolSiloLink>,
 ) -> Result<HttpResponseOk<views::IpPool>, HttpError> {
     let apictx = rqctx.context();
-    let nexus = &apictx.nexus;
     let handler = async {
         let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
-        let pool = nexus.ip_pool_service_fetch(&opctx).await?;
-        Ok(HttpResponseOk(IpPool::from(pool)))
+        let nexus = &apictx.nexus;
+        let path = path_params.into_inner();
+        let link = link_params.into_inner();
+        let pool_lookup = nexus.ip_pool_lookup(&opctx, &path.pool)?;
+        let pool = nexus.ip_pool_silo_link(&opctx, &pool_lookup, &link).await?;
+        Ok(HttpResponseOk(pool.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx
+        .instrument_audited_handler(&rqctx, "ip_pool_silo_link", handler)
+        .await
 }
 
-type IpPoolRangePaginationParams = PaginationParams<EmptyScanParams, IpNetwork>;
-
-/// List ranges for an IP pool
+/// Unlink an IP pool from its Silo
 ///
-/// List ranges for an IP pool. Ranges are ordered by their first address.
+/// The pool becomes available to every Silo.  If it was its Silo's default
+/// pool, it stops being a default pool.
+#[endpoint {
+    method = POST,
+    path = "/v1/system/ip-pools/{pool}/unlink",
+    tags = ["system/networking"],
+}]
+async fn ip_pool_silo_unlink(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    path_params: Path<params::IpPoolPath>,
+) -> Result<HttpResponseOk<views::IpPool>, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.nexus;
+        let path = path_params.into_inner();
+        let pool_lookup = nexus.ip_pool_lookup(&opctx, &path.pool)?;
+        let pool = nexus.ip_pool_silo_unlink(&opctx, &pool_lookup).await?;
+        Ok(HttpResponseOk(pool.into()))
+    };
+    apictx
+        .instrument_audited_handler(&rqctx, "ip_pool_silo_unlink", handler)
+        .await
+}
+
+/// Make an IP pool the default pool, or not
+///
+/// A pool linked to a Silo is made the default pool for that Silo.  Otherwise,
+/// it's made the default pool for the whole fleet.
+#[endpoint {
+    method = POST,
+    path = "/v1/system/ip-pools/{pool}/set-default",
+    tags = ["system/networking"],
+}]
+async fn ip_pool_set_default(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    path_params: Path<params::IpPoolPath>,
+    default_params: TypedBody<params::IpPoolSetDefault>,
+) -> Result<HttpResponseOk<views::IpPool>, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.nexus;
+        let path = path_params.into_inner();
+        let params = default_params.into_inner();
+        let pool_lookup = nexus.ip_pool_lookup(&opctx, &path.pool)?;
+        let pool =
+            nexus.ip_pool_set_default(&opctx, &pool_lookup, &params).await?;
+        Ok(HttpResponseOk(pool.into()))
+    };
+    apictx
+        .instrument_audited_handler(&rqctx, "ip_pool_set_default", handler)
+        .await
+}
+
+/// Fetch the IP pool used for Oxide services
+#[endpoint {
+    method = GET,
+    path = "/v1/system/ip-pools-service",
+    tags = ["system/networking"],
+}]
+async fn ip_pool_service_view(
+    rqctx: RequestContext<Arc<ServerContext>>,
+) -> Result<HttpResponseOk<views::IpPool>, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let pool = nexus.ip_pool_service_fetch(&opctx).await?;
+        Ok(HttpResponseOk(IpPool::from(pool)))
+    };
+    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+}
+
+type IpPoolRangePaginationParams = PaginationParams<EmptyScanParams, IpNetwork>;
+
+/// List ranges for an IP pool
+///
+/// List ranges for an IP pool. Ranges are ordered by their first address.
 #[endpoint {
     method = GET,
     path = "/v1/system/ip-pools/{pool}/ranges",
@@ -1372,7 +1944,9 @@ async fn ip_pool_range_add(
         let out = nexus.ip_pool_add_range(&opctx, &pool_lookup, &range).await?;
         Ok(HttpResponseCreated(out.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx
+        .instrument_audited_handler(&rqctx, "ip_pool_range_add", handler)
+        .await
 }
 
 /// Remove a range from an IP pool
@@ -1396,7 +1970,9 @@ async fn ip_pool_range_remove(
         nexus.ip_pool_delete_range(&opctx, &pool_lookup, &range).await?;
         Ok(HttpResponseUpdatedNoContent())
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx
+        .instrument_audited_handler(&rqctx, "ip_pool_range_remove", handler)
+        .await
 }
 
 /// List ranges for the IP pool used for Oxide services
@@ -1461,7 +2037,13 @@ async fn ip_pool_service_range_add(
         let out = nexus.ip_pool_service_add_range(&opctx, &range).await?;
         Ok(HttpResponseCreated(out.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx
+        .instrument_audited_handler(
+            &rqctx,
+            "ip_pool_service_range_add",
+            handler,
+        )
+        .await
 }
 
 /// Remove a range from an IP pool used for Oxide services
@@ -1482,7 +2064,13 @@ async fn ip_pool_service_range_remove(
         nexus.ip_pool_service_delete_range(&opctx, &range).await?;
         Ok(HttpResponseUpdatedNoContent())
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx
+        .instrument_audited_handler(
+            &rqctx,
+            "ip_pool_service_range_remove",
+            handler,
+        )
+        .await
 }
 
 // Disks
@@ -1495,7 +2083,7 @@ async fn ip_pool_service_range_remove(
 }]
 async fn disk_list(
     rqctx: RequestContext<Arc<ServerContext>>,
-    query_params: Query<PaginatedByNameOrId<params::ProjectSelector>>,
+    query_params: Query<PaginatedByNameOrId<params::ProjectLabelSelector>>,
 ) -> Result<HttpResponseOk<ResultsPage<Disk>>, HttpError> {
     let apictx = rqctx.context();
     let handler = async {
@@ -1505,10 +2093,18 @@ async fn disk_list(
         let pag_params = data_page_params_for(&rqctx, &query)?;
         let scan_params = ScanByNameOrId::from_query(&query)?;
         let paginated_by = name_or_id_pagination(&pag_params, scan_params)?;
-        let project_lookup =
-            nexus.project_lookup(&opctx, scan_params.selector.clone())?;
+        let selector = &scan_params.selector;
+        let project_lookup = nexus.project_lookup(
+            &opctx,
+            params::ProjectSelector { project: selector.project.clone() },
+        )?;
         let disks = nexus
-            .disk_list(&opctx, &project_lookup, &paginated_by)
+            .disk_list(
+                &opctx,
+                &project_lookup,
+                &paginated_by,
+                selector.label_selector.as_ref(),
+            )
             .await?
             .into_iter()
             .map(|disk| disk.into())
@@ -1545,7 +2141,7 @@ async fn disk_create(
             nexus.project_create_disk(&opctx, &project_lookup, &params).await?;
         Ok(HttpResponseCreated(disk.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_audited_handler(&rqctx, "disk_create", handler).await
 }
 
 /// Fetch a disk
@@ -1597,7 +2193,7 @@ async fn disk_delete(
         nexus.project_delete_disk(&opctx, &disk_lookup).await?;
         Ok(HttpResponseDeleted())
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_audited_handler(&rqctx, "disk_delete", handler).await
 }
 
 #[derive(Display, Serialize, Deserialize, JsonSchema)]
@@ -1690,7 +2286,13 @@ async fn disk_bulk_write_import_start(
 
         Ok(HttpResponseUpdatedNoContent())
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx
+        .instrument_audited_handler(
+            &rqctx,
+            "disk_bulk_write_import_start",
+            handler,
+        )
+        .await
 }
 
 /// Import blocks into a disk
@@ -1721,7 +2323,9 @@ async fn disk_bulk_write_import(
 
         Ok(HttpResponseUpdatedNoContent())
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx
+        .instrument_audited_handler(&rqctx, "disk_bulk_write_import", handler)
+        .await
 }
 
 /// Stop importing blocks into a disk
@@ -1752,7 +2356,13 @@ async fn disk_bulk_write_import_stop(
 
         Ok(HttpResponseUpdatedNoContent())
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx
+        .instrument_audited_handler(
+            &rqctx,
+            "disk_bulk_write_import_stop",
+            handler,
+        )
+        .await
 }
 
 /// Request to import blocks from URL
@@ -1785,7 +2395,13 @@ async fn disk_import_blocks_from_url(
 
         Ok(HttpResponseUpdatedNoContent())
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx
+        .instrument_audited_handler(
+            &rqctx,
+            "disk_import_blocks_from_url",
+            handler,
+        )
+        .await
 }
 
 /// Confirm disk block import completion
@@ -1815,9 +2431,209 @@ async fn disk_finalize_import(
 
         Ok(HttpResponseUpdatedNoContent())
     };
+    apictx
+        .instrument_audited_handler(&rqctx, "disk_finalize_import", handler)
+        .await
+}
+
+/// Start exporting blocks from a disk
+///
+/// Attach a detached disk to a Pantry so that its blocks can be read out with
+/// bulk reads. The disk stays in state `exporting` until the export is
+/// stopped.
+#[endpoint {
+    method = POST,
+    path = "/v1/disks/{disk}/export-start",
+    tags = ["disks"],
+}]
+async fn disk_export_start(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    path_params: Path<params::DiskPath>,
+    query_params: Query<params::OptionalProjectSelector>,
+) -> Result<HttpResponseUpdatedNoContent, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.nexus;
+        let path = path_params.into_inner();
+        let query = query_params.into_inner();
+
+        let disk_selector =
+            params::DiskSelector { disk: path.disk, project: query.project };
+        let disk_lookup = nexus.disk_lookup(&opctx, disk_selector)?;
+
+        nexus.disk_export_start(&opctx, &disk_lookup).await?;
+
+        Ok(HttpResponseUpdatedNoContent())
+    };
+    apictx
+        .instrument_audited_handler(&rqctx, "disk_export_start", handler)
+        .await
+}
+
+/// Export blocks from a disk
+#[endpoint {
+    method = POST,
+    path = "/v1/disks/{disk}/bulk-read",
+    tags = ["disks"],
+}]
+async fn disk_bulk_read_export(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    path_params: Path<params::DiskPath>,
+    query_params: Query<params::OptionalProjectSelector>,
+    export_params: TypedBody<params::ExportBlocksBulkRead>,
+) -> Result<HttpResponseOk<views::ExportedBlocks>, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.nexus;
+        let path = path_params.into_inner();
+        let query = query_params.into_inner();
+        let params = export_params.into_inner();
+
+        let disk_selector =
+            params::DiskSelector { disk: path.disk, project: query.project };
+        let disk_lookup = nexus.disk_lookup(&opctx, disk_selector)?;
+
+        let blocks = nexus.disk_bulk_read(&disk_lookup, params).await?;
+
+        Ok(HttpResponseOk(blocks))
+    };
     apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
 }
 
+/// Stop exporting blocks from a disk
+///
+/// Detach the disk from its Pantry and return it to state `detached`.
+#[endpoint {
+    method = POST,
+    path = "/v1/disks/{disk}/export-stop",
+    tags = ["disks"],
+}]
+async fn disk_export_stop(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    path_params: Path<params::DiskPath>,
+    query_params: Query<params::OptionalProjectSelector>,
+) -> Result<HttpResponseUpdatedNoContent, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.nexus;
+        let path = path_params.into_inner();
+        let query = query_params.into_inner();
+
+        let disk_selector =
+            params::DiskSelector { disk: path.disk, project: query.project };
+        let disk_lookup = nexus.disk_lookup(&opctx, disk_selector)?;
+
+        nexus.disk_export_stop(&opctx, &disk_lookup).await?;
+
+        Ok(HttpResponseUpdatedNoContent())
+    };
+    apictx.instrument_audited_handler(&rqctx, "disk_export_stop", handler).await
+}
+
+/// Grow a disk
+///
+/// The disk must be detached: growing a disk that's attached to an instance
+/// is not supported yet.  It keeps its id and contents, and the extra space is
+/// charged to its project.
+#[endpoint {
+    method = POST,
+    path = "/v1/disks/{disk}/resize",
+    tags = ["disks"],
+}]
+async fn disk_resize(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    path_params: Path<params::DiskPath>,
+    query_params: Query<params::OptionalProjectSelector>,
+    resize_params: TypedBody<params::DiskResize>,
+) -> Result<HttpResponseOk<Disk>, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.nexus;
+        let path = path_params.into_inner();
+        let query = query_params.into_inner();
+        let params = resize_params.into_inner();
+        let disk_selector =
+            params::DiskSelector { disk: path.disk, project: query.project };
+        let disk_lookup = nexus.disk_lookup(&opctx, disk_selector)?;
+
+        let disk = nexus.disk_resize(&opctx, &disk_lookup, &params).await?;
+
+        Ok(HttpResponseOk(disk.into()))
+    };
+    apictx.instrument_audited_handler(&rqctx, "disk_resize", handler).await
+}
+
+/// Restore a disk from a snapshot
+///
+/// Replaces the contents of a detached disk with those of one of its project's
+/// snapshots, keeping the disk's id.  Anything written to the disk after the
+/// snapshot was taken is lost.
+#[endpoint {
+    method = POST,
+    path = "/v1/disks/{disk}/rollback",
+    tags = ["disks"],
+}]
+async fn disk_rollback(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    path_params: Path<params::DiskPath>,
+    query_params: Query<params::OptionalProjectSelector>,
+    rollback_params: TypedBody<params::DiskRollback>,
+) -> Result<HttpResponseOk<Disk>, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.nexus;
+        let path = path_params.into_inner();
+        let query = query_params.into_inner();
+        let params = rollback_params.into_inner();
+        let disk_selector =
+            params::DiskSelector { disk: path.disk, project: query.project };
+        let disk_lookup = nexus.disk_lookup(&opctx, disk_selector)?;
+
+        let disk = nexus.disk_rollback(&opctx, &disk_lookup, &params).await?;
+
+        Ok(HttpResponseOk(disk.into()))
+    };
+    apictx.instrument_audited_handler(&rqctx, "disk_rollback", handler).await
+}
+
+/// Update a disk's labels
+///
+/// Replaces all of the disk's labels with the given set.
+#[endpoint {
+    method = PUT,
+    path = "/v1/disks/{disk}/labels",
+    tags = ["disks"],
+}]
+async fn disk_labels_update(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    path_params: Path<params::DiskPath>,
+    query_params: Query<params::OptionalProjectSelector>,
+    labels: TypedBody<Labels>,
+) -> Result<HttpResponseOk<Disk>, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.nexus;
+        let path = path_params.into_inner();
+        let query = query_params.into_inner();
+        let labels = labels.into_inner();
+        let disk_selector =
+            params::DiskSelector { disk: path.disk, project: query.project };
+        let disk_lookup = nexus.disk_lookup(&opctx, disk_selector)?;
+        let disk =
+            nexus.disk_labels_update(&opctx, &disk_lookup, &labels).await?;
+        Ok(HttpResponseOk(disk.into()))
+    };
+    apictx
+        .instrument_audited_handler(&rqctx, "disk_labels_update", handler)
+        .await
+}
+
 // Instances
 
 /// List instances
@@ -1828,7 +2644,7 @@ async fn disk_finalize_import(
 }]
 async fn instance_list(
     rqctx: RequestContext<Arc<ServerContext>>,
-    query_params: Query<PaginatedByNameOrId<params::ProjectSelector>>,
+    query_params: Query<PaginatedByNameOrId<params::ProjectLabelSelector>>,
 ) -> Result<HttpResponseOk<ResultsPage<Instance>>, HttpError> {
     let apictx = rqctx.context();
     let handler = async {
@@ -1838,10 +2654,18 @@ async fn instance_list(
         let scan_params = ScanByNameOrId::from_query(&query)?;
         let paginated_by = name_or_id_pagination(&pag_params, scan_params)?;
         let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
-        let project_lookup =
-            nexus.project_lookup(&opctx, scan_params.selector.clone())?;
+        let selector = &scan_params.selector;
+        let project_lookup = nexus.project_lookup(
+            &opctx,
+            params::ProjectSelector { project: selector.project.clone() },
+        )?;
         let instances = nexus
-            .instance_list(&opctx, &project_lookup, &paginated_by)
+            .instance_list(
+                &opctx,
+                &project_lookup,
+                &paginated_by,
+                selector.label_selector.as_ref(),
+            )
             .await?
             .into_iter()
             .map(|i| i.into())
@@ -1882,7 +2706,7 @@ async fn instance_create(
             .await?;
         Ok(HttpResponseCreated(instance.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_audited_handler(&rqctx, "instance_create", handler).await
 }
 
 /// Fetch an instance
@@ -1914,47 +2738,167 @@ async fn instance_view(
     apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
 }
 
-/// Delete an instance
+/// Update an instance
+///
+/// Changes the number of vCPUs and the amount of memory of a stopped
+/// instance.
 #[endpoint {
-    method = DELETE,
+    method = PUT,
     path = "/v1/instances/{instance}",
     tags = ["instances"],
 }]
-async fn instance_delete(
+async fn instance_update(
     rqctx: RequestContext<Arc<ServerContext>>,
     query_params: Query<params::OptionalProjectSelector>,
     path_params: Path<params::InstancePath>,
-) -> Result<HttpResponseDeleted, HttpError> {
+    updated_instance: TypedBody<params::InstanceUpdate>,
+) -> Result<HttpResponseOk<Instance>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
     let path = path_params.into_inner();
     let query = query_params.into_inner();
-    let instance_selector = params::InstanceSelector {
-        project: query.project,
-        instance: path.instance,
-    };
+    let updated_instance_params = updated_instance.into_inner();
     let handler = async {
         let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let instance_selector = params::InstanceSelector {
+            project: query.project,
+            instance: path.instance,
+        };
         let instance_lookup =
             nexus.instance_lookup(&opctx, instance_selector)?;
-        nexus.project_destroy_instance(&opctx, &instance_lookup).await?;
-        Ok(HttpResponseDeleted())
+        let instance = nexus
+            .instance_update(&opctx, &instance_lookup, &updated_instance_params)
+            .await?;
+        Ok(HttpResponseOk(instance.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_audited_handler(&rqctx, "instance_update", handler).await
 }
 
-// TODO should this be in the public API?
-/// Migrate an instance
+/// Update an instance's labels
+///
+/// Replaces all of the instance's labels with the given set.  Unlike other
+/// updates to an instance, this can be done while the instance is running.
 #[endpoint {
-    method = POST,
-    path = "/v1/instances/{instance}/migrate",
+    method = PUT,
+    path = "/v1/instances/{instance}/labels",
     tags = ["instances"],
 }]
-async fn instance_migrate(
+async fn instance_labels_update(
     rqctx: RequestContext<Arc<ServerContext>>,
     query_params: Query<params::OptionalProjectSelector>,
     path_params: Path<params::InstancePath>,
-    migrate_params: TypedBody<params::InstanceMigrate>,
+    labels: TypedBody<Labels>,
+) -> Result<HttpResponseOk<Instance>, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let path = path_params.into_inner();
+    let query = query_params.into_inner();
+    let labels = labels.into_inner();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let instance_selector = params::InstanceSelector {
+            project: query.project,
+            instance: path.instance,
+        };
+        let instance_lookup =
+            nexus.instance_lookup(&opctx, instance_selector)?;
+        let instance = nexus
+            .instance_labels_update(&opctx, &instance_lookup, &labels)
+            .await?;
+        Ok(HttpResponseOk(instance.into()))
+    };
+    apictx
+        .instrument_audited_handler(&rqctx, "instance_labels_update", handler)
+        .await
+}
+
+/// Update an instance's user data
+///
+/// Replaces the user data passed to the instance's initialization system
+/// (such as cloud-init).  This can be done while the instance is running, but
+/// the new user data only reaches the guest the next time the instance starts.
+/// The instance ID given to cloud-init changes with the user data, so
+/// cloud-init treats the instance as new and reruns the parts of the user data
+/// that it only applies once per instance.
+#[endpoint {
+    method = PUT,
+    path = "/v1/instances/{instance}/user-data",
+    tags = ["instances"],
+}]
+async fn instance_user_data_update(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    query_params: Query<params::OptionalProjectSelector>,
+    path_params: Path<params::InstancePath>,
+    user_data: TypedBody<params::InstanceUserDataUpdate>,
+) -> Result<HttpResponseOk<Instance>, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let path = path_params.into_inner();
+    let query = query_params.into_inner();
+    let user_data = user_data.into_inner();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let instance_selector = params::InstanceSelector {
+            project: query.project,
+            instance: path.instance,
+        };
+        let instance_lookup =
+            nexus.instance_lookup(&opctx, instance_selector)?;
+        let instance = nexus
+            .instance_user_data_update(&opctx, &instance_lookup, &user_data)
+            .await?;
+        Ok(HttpResponseOk(instance.into()))
+    };
+    apictx
+        .instrument_audited_handler(
+            &rqctx,
+            "instance_user_data_update",
+            handler,
+        )
+        .await
+}
+
+/// Delete an instance
+#[endpoint {
+    method = DELETE,
+    path = "/v1/instances/{instance}",
+    tags = ["instances"],
+}]
+async fn instance_delete(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    query_params: Query<params::OptionalProjectSelector>,
+    path_params: Path<params::InstancePath>,
+) -> Result<HttpResponseDeleted, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let path = path_params.into_inner();
+    let query = query_params.into_inner();
+    let instance_selector = params::InstanceSelector {
+        project: query.project,
+        instance: path.instance,
+    };
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let instance_lookup =
+            nexus.instance_lookup(&opctx, instance_selector)?;
+        nexus.project_destroy_instance(&opctx, &instance_lookup).await?;
+        Ok(HttpResponseDeleted())
+    };
+    apictx.instrument_audited_handler(&rqctx, "instance_delete", handler).await
+}
+
+// TODO should this be in the public API?
+/// Migrate an instance
+#[endpoint {
+    method = POST,
+    path = "/v1/instances/{instance}/migrate",
+    tags = ["instances"],
+}]
+async fn instance_migrate(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    query_params: Query<params::OptionalProjectSelector>,
+    path_params: Path<params::InstancePath>,
+    migrate_params: TypedBody<params::InstanceMigrate>,
 ) -> Result<HttpResponseOk<Instance>, HttpError> {
     let apictx = rqctx.context();
     let nexus = &apictx.nexus;
@@ -1978,7 +2922,7 @@ async fn instance_migrate(
             .await?;
         Ok(HttpResponseOk(instance.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_audited_handler(&rqctx, "instance_migrate", handler).await
 }
 
 /// Reboot an instance
@@ -2007,7 +2951,7 @@ async fn instance_reboot(
         let instance = nexus.instance_reboot(&opctx, &instance_lookup).await?;
         Ok(HttpResponseAccepted(instance.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_audited_handler(&rqctx, "instance_reboot", handler).await
 }
 
 /// Boot an instance
@@ -2036,7 +2980,7 @@ async fn instance_start(
         let instance = nexus.instance_start(&opctx, &instance_lookup).await?;
         Ok(HttpResponseAccepted(instance.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_audited_handler(&rqctx, "instance_start", handler).await
 }
 
 /// Stop an instance
@@ -2065,7 +3009,7 @@ async fn instance_stop(
         let instance = nexus.instance_stop(&opctx, &instance_lookup).await?;
         Ok(HttpResponseAccepted(instance.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_audited_handler(&rqctx, "instance_stop", handler).await
 }
 
 /// Fetch an instance's serial console
@@ -2191,6 +3135,54 @@ async fn instance_disk_list(
     apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
 }
 
+/// List the SSH public keys added to an instance
+///
+/// These are the keys chosen when the instance was created. Keys which have
+/// since been deleted by their owner are not listed.
+#[endpoint {
+    method = GET,
+    path = "/v1/instances/{instance}/ssh-public-keys",
+    tags = ["instances"],
+}]
+async fn instance_ssh_public_key_list(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    query_params: Query<PaginatedByNameOrId<params::OptionalProjectSelector>>,
+    path_params: Path<params::InstancePath>,
+) -> Result<HttpResponseOk<ResultsPage<SshKey>>, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let nexus = &apictx.nexus;
+        let path = path_params.into_inner();
+        let query = query_params.into_inner();
+        let pag_params = data_page_params_for(&rqctx, &query)?;
+        let scan_params = ScanByNameOrId::from_query(&query)?;
+        let paginated_by = name_or_id_pagination(&pag_params, scan_params)?;
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let instance_selector = params::InstanceSelector {
+            project: scan_params.selector.project.clone(),
+            instance: path.instance,
+        };
+        let instance_lookup =
+            nexus.instance_lookup(&opctx, instance_selector)?;
+        let ssh_keys = nexus
+            .instance_ssh_public_key_list(
+                &opctx,
+                &instance_lookup,
+                &paginated_by,
+            )
+            .await?
+            .into_iter()
+            .map(SshKey::from)
+            .collect();
+        Ok(HttpResponseOk(ScanByNameOrId::results_page(
+            &query,
+            ssh_keys,
+            &marker_for_name_or_id,
+        )?))
+    };
+    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+}
+
 /// Attach a disk to an instance
 #[endpoint {
     method = POST,
@@ -2220,7 +3212,9 @@ async fn instance_disk_attach(
             nexus.instance_attach_disk(&opctx, &instance_lookup, disk).await?;
         Ok(HttpResponseAccepted(disk.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx
+        .instrument_audited_handler(&rqctx, "instance_disk_attach", handler)
+        .await
 }
 
 /// Detach a disk from an instance
@@ -2252,7 +3246,9 @@ async fn instance_disk_detach(
             nexus.instance_detach_disk(&opctx, &instance_lookup, disk).await?;
         Ok(HttpResponseAccepted(disk.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx
+        .instrument_audited_handler(&rqctx, "instance_disk_detach", handler)
+        .await
 }
 
 // Certificates
@@ -2315,7 +3311,9 @@ async fn certificate_create(
         let cert = nexus.certificate_create(&opctx, new_cert_params).await?;
         Ok(HttpResponseCreated(cert.try_into()?))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx
+        .instrument_audited_handler(&rqctx, "certificate_create", handler)
+        .await
 }
 
 /// Path parameters for Certificate requests
@@ -2373,7 +3371,9 @@ async fn certificate_delete(
             .await?;
         Ok(HttpResponseDeleted())
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx
+        .instrument_audited_handler(&rqctx, "certificate_delete", handler)
+        .await
 }
 
 /// Create an address lot
@@ -2399,7 +3399,13 @@ async fn networking_address_lot_create(
 
         Ok(HttpResponseCreated(AddressLotCreateResponse { lot, blocks }))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx
+        .instrument_audited_handler(
+            &rqctx,
+            "networking_address_lot_create",
+            handler,
+        )
+        .await
 }
 
 /// Delete an address lot
@@ -2422,7 +3428,13 @@ async fn networking_address_lot_delete(
         nexus.address_lot_delete(&opctx, &address_lot_lookup).await?;
         Ok(HttpResponseDeleted())
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx
+        .instrument_audited_handler(
+            &rqctx,
+            "networking_address_lot_delete",
+            handler,
+        )
+        .await
 }
 
 /// List address lots
@@ -2516,7 +3528,13 @@ async fn networking_loopback_address_create(
 
         Ok(HttpResponseCreated(addr))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx
+        .instrument_audited_handler(
+            &rqctx,
+            "networking_loopback_address_create",
+            handler,
+        )
+        .await
 }
 
 #[derive(Serialize, Deserialize, JsonSchema)]
@@ -2568,7 +3586,13 @@ async fn networking_loopback_address_delete(
             .await?;
         Ok(HttpResponseDeleted())
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx
+        .instrument_audited_handler(
+            &rqctx,
+            "networking_loopback_address_delete",
+            handler,
+        )
+        .await
 }
 
 /// Get loopback addresses, optionally filtering by id
@@ -2623,7 +3647,13 @@ async fn networking_switch_port_settings_create(
         let settings: SwitchPortSettingsView = result.into();
         Ok(HttpResponseCreated(settings))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx
+        .instrument_audited_handler(
+            &rqctx,
+            "networking_switch_port_settings_create",
+            handler,
+        )
+        .await
 }
 
 /// Delete switch port settings
@@ -2644,7 +3674,13 @@ async fn networking_switch_port_settings_delete(
         nexus.switch_port_settings_delete(&opctx, &selector).await?;
         Ok(HttpResponseDeleted())
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx
+        .instrument_audited_handler(
+            &rqctx,
+            "networking_switch_port_settings_delete",
+            handler,
+        )
+        .await
 }
 
 /// List switch port settings
@@ -2760,7 +3796,13 @@ async fn networking_switch_port_apply_settings(
             .await?;
         Ok(HttpResponseUpdatedNoContent {})
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx
+        .instrument_audited_handler(
+            &rqctx,
+            "networking_switch_port_apply_settings",
+            handler,
+        )
+        .await
 }
 
 /// Clear switch port settings
@@ -2783,146 +3825,351 @@ async fn networking_switch_port_clear_settings(
         nexus.switch_port_clear_settings(&opctx, &port, &query).await?;
         Ok(HttpResponseUpdatedNoContent {})
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx
+        .instrument_audited_handler(
+            &rqctx,
+            "networking_switch_port_clear_settings",
+            handler,
+        )
+        .await
 }
 
-// Images
+/// Create a BGP configuration
+#[endpoint {
+    method = POST,
+    path = "/v1/system/networking/bgp",
+    tags = ["system/networking"],
+}]
+async fn networking_bgp_config_create(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    config: TypedBody<params::CreateBgpConfig>,
+) -> Result<HttpResponseCreated<BgpConfig>, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let nexus = &apictx.nexus;
+        let config = config.into_inner();
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let result = nexus.bgp_config_create(&opctx, &config).await?;
+        Ok(HttpResponseCreated::<BgpConfig>(result.into()))
+    };
+    apictx
+        .instrument_audited_handler(
+            &rqctx,
+            "networking_bgp_config_create",
+            handler,
+        )
+        .await
+}
 
-/// List images
-///
-/// List images which are global or scoped to the specified project. The images
-/// are returned sorted by creation date, with the most recent images appearing first.
+/// List BGP configurations
 #[endpoint {
     method = GET,
-    path = "/v1/images",
-    tags = ["images"],
+    path = "/v1/system/networking/bgp",
+    tags = ["system/networking"],
 }]
-async fn image_list(
+async fn networking_bgp_config_list(
     rqctx: RequestContext<Arc<ServerContext>>,
-    query_params: Query<PaginatedByNameOrId<params::OptionalProjectSelector>>,
-) -> Result<HttpResponseOk<ResultsPage<Image>>, HttpError> {
+    query_params: Query<PaginatedByNameOrId>,
+) -> Result<HttpResponseOk<ResultsPage<BgpConfig>>, HttpError> {
     let apictx = rqctx.context();
     let handler = async {
-        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
         let nexus = &apictx.nexus;
         let query = query_params.into_inner();
         let pag_params = data_page_params_for(&rqctx, &query)?;
         let scan_params = ScanByNameOrId::from_query(&query)?;
         let paginated_by = name_or_id_pagination(&pag_params, scan_params)?;
-        let parent_lookup = match scan_params.selector.project.clone() {
-            Some(project) => {
-                let project_lookup = nexus.project_lookup(
-                    &opctx,
-                    params::ProjectSelector { project },
-                )?;
-                ImageParentLookup::Project(project_lookup)
-            }
-            None => {
-                let silo_lookup = nexus.current_silo_lookup(&opctx)?;
-                ImageParentLookup::Silo(silo_lookup)
-            }
-        };
-        let images = nexus
-            .image_list(&opctx, &parent_lookup, &paginated_by)
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let configs = nexus
+            .bgp_config_list(&opctx, &paginated_by)
             .await?
             .into_iter()
-            .map(|d| d.into())
+            .map(|p| p.into())
             .collect();
+
         Ok(HttpResponseOk(ScanByNameOrId::results_page(
             &query,
-            images,
+            configs,
             &marker_for_name_or_id,
         )?))
     };
     apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
 }
 
-/// Create an image
-///
-/// Create a new image in a project.
+/// Delete a BGP configuration
 #[endpoint {
-    method = POST,
-    path = "/v1/images",
-    tags = ["images"]
+    method = DELETE,
+    path = "/v1/system/networking/bgp",
+    tags = ["system/networking"],
 }]
-async fn image_create(
+async fn networking_bgp_config_delete(
     rqctx: RequestContext<Arc<ServerContext>>,
-    query_params: Query<params::OptionalProjectSelector>,
-    new_image: TypedBody<params::ImageCreate>,
-) -> Result<HttpResponseCreated<Image>, HttpError> {
+    sel: Query<params::BgpConfigSelector>,
+) -> Result<HttpResponseDeleted, HttpError> {
     let apictx = rqctx.context();
     let handler = async {
-        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
         let nexus = &apictx.nexus;
-        let query = query_params.into_inner();
-        let params = &new_image.into_inner();
-        let parent_lookup = match query.project.clone() {
-            Some(project) => {
-                let project_lookup = nexus.project_lookup(
-                    &opctx,
-                    params::ProjectSelector { project },
-                )?;
-                ImageParentLookup::Project(project_lookup)
-            }
-            None => {
-                let silo_lookup = nexus.current_silo_lookup(&opctx)?;
-                ImageParentLookup::Silo(silo_lookup)
-            }
-        };
-        let image = nexus.image_create(&opctx, &parent_lookup, &params).await?;
-        Ok(HttpResponseCreated(image.into()))
+        let sel = sel.into_inner();
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        nexus.bgp_config_delete(&opctx, &sel).await?;
+        Ok(HttpResponseDeleted())
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx
+        .instrument_audited_handler(
+            &rqctx,
+            "networking_bgp_config_delete",
+            handler,
+        )
+        .await
 }
 
-/// Fetch an image
-///
-/// Fetch the details for a specific image in a project.
+/// Create a BGP announce set
 #[endpoint {
-    method = GET,
-    path = "/v1/images/{image}",
-    tags = ["images"],
+    method = POST,
+    path = "/v1/system/networking/bgp-announce",
+    tags = ["system/networking"],
 }]
-async fn image_view(
+async fn networking_bgp_announce_set_create(
     rqctx: RequestContext<Arc<ServerContext>>,
-    path_params: Path<params::ImagePath>,
-    query_params: Query<params::OptionalProjectSelector>,
-) -> Result<HttpResponseOk<Image>, HttpError> {
+    config: TypedBody<params::CreateBgpAnnounceSet>,
+) -> Result<HttpResponseCreated<BgpAnnounceSet>, HttpError> {
     let apictx = rqctx.context();
     let handler = async {
-        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
         let nexus = &apictx.nexus;
-        let path = path_params.into_inner();
-        let query = query_params.into_inner();
-        let image: nexus_db_model::Image = match nexus
-            .image_lookup(
-                &opctx,
-                params::ImageSelector {
-                    image: path.image,
-                    project: query.project,
-                },
-            )
-            .await?
-        {
-            ImageLookup::ProjectImage(image) => {
-                let (.., db_image) = image.fetch().await?;
-                db_image.into()
-            }
-            ImageLookup::SiloImage(image) => {
-                let (.., db_image) = image.fetch().await?;
-                db_image.into()
-            }
-        };
-        Ok(HttpResponseOk(image.into()))
+        let config = config.into_inner();
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let result = nexus.bgp_announce_set_create(&opctx, &config).await?;
+        Ok(HttpResponseCreated::<BgpAnnounceSet>(result.announce_set.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx
+        .instrument_audited_handler(
+            &rqctx,
+            "networking_bgp_announce_set_create",
+            handler,
+        )
+        .await
 }
 
-/// Delete an image
-///
-/// Permanently delete an image from a project. This operation cannot be undone.
-/// Any instances in the project using the image will continue to run, however
-/// new instances can not be created with this image.
+/// List BGP announce sets
+#[endpoint {
+    method = GET,
+    path = "/v1/system/networking/bgp-announce",
+    tags = ["system/networking"],
+}]
+async fn networking_bgp_announce_set_list(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    query_params: Query<PaginatedByNameOrId>,
+) -> Result<HttpResponseOk<ResultsPage<BgpAnnounceSet>>, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let nexus = &apictx.nexus;
+        let query = query_params.into_inner();
+        let pag_params = data_page_params_for(&rqctx, &query)?;
+        let scan_params = ScanByNameOrId::from_query(&query)?;
+        let paginated_by = name_or_id_pagination(&pag_params, scan_params)?;
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let sets = nexus
+            .bgp_announce_set_list(&opctx, &paginated_by)
+            .await?
+            .into_iter()
+            .map(|p| p.into())
+            .collect();
+
+        Ok(HttpResponseOk(ScanByNameOrId::results_page(
+            &query,
+            sets,
+            &marker_for_name_or_id,
+        )?))
+    };
+    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+}
+
+/// Get the announcements in a BGP announce set
+#[endpoint {
+    method = GET,
+    path = "/v1/system/networking/bgp-announce/{name_or_id}/announcement",
+    tags = ["system/networking"],
+}]
+async fn networking_bgp_announcement_list(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    path_params: Path<params::BgpAnnounceSetSelector>,
+) -> Result<HttpResponseOk<Vec<BgpAnnouncement>>, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let nexus = &apictx.nexus;
+        let sel = path_params.into_inner();
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let announcements = nexus
+            .bgp_announcement_list(&opctx, &sel)
+            .await?
+            .into_iter()
+            .map(|p| p.into())
+            .collect();
+        Ok(HttpResponseOk(announcements))
+    };
+    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+}
+
+/// Delete a BGP announce set
+#[endpoint {
+    method = DELETE,
+    path = "/v1/system/networking/bgp-announce",
+    tags = ["system/networking"],
+}]
+async fn networking_bgp_announce_set_delete(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    sel: Query<params::BgpAnnounceSetSelector>,
+) -> Result<HttpResponseDeleted, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let nexus = &apictx.nexus;
+        let sel = sel.into_inner();
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        nexus.bgp_announce_set_delete(&opctx, &sel).await?;
+        Ok(HttpResponseDeleted())
+    };
+    apictx
+        .instrument_audited_handler(
+            &rqctx,
+            "networking_bgp_announce_set_delete",
+            handler,
+        )
+        .await
+}
+
+// Images
+
+/// List images
+///
+/// List images which are global or scoped to the specified project. The images
+/// are returned sorted by creation date, with the most recent images appearing first.
+#[endpoint {
+    method = GET,
+    path = "/v1/images",
+    tags = ["images"],
+}]
+async fn image_list(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    query_params: Query<
+        PaginatedByNameOrId<params::OptionalProjectLabelSelector>,
+    >,
+) -> Result<HttpResponseOk<ResultsPage<Image>>, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.nexus;
+        let query = query_params.into_inner();
+        let pag_params = data_page_params_for(&rqctx, &query)?;
+        let scan_params = ScanByNameOrId::from_query(&query)?;
+        let paginated_by = name_or_id_pagination(&pag_params, scan_params)?;
+        let parent_lookup = match scan_params.selector.project.clone() {
+            Some(project) => {
+                let project_lookup = nexus.project_lookup(
+                    &opctx,
+                    params::ProjectSelector { project },
+                )?;
+                ImageParentLookup::Project(project_lookup)
+            }
+            None => {
+                let silo_lookup = nexus.current_silo_lookup(&opctx)?;
+                ImageParentLookup::Silo(silo_lookup)
+            }
+        };
+        let images = nexus
+            .image_list(
+                &opctx,
+                &parent_lookup,
+                &paginated_by,
+                scan_params.selector.label_selector.as_ref(),
+            )
+            .await?
+            .into_iter()
+            .map(|d| d.into())
+            .collect();
+        Ok(HttpResponseOk(ScanByNameOrId::results_page(
+            &query,
+            images,
+            &marker_for_name_or_id,
+        )?))
+    };
+    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+}
+
+/// Create an image
+///
+/// Create a new image in a project.
+#[endpoint {
+    method = POST,
+    path = "/v1/images",
+    tags = ["images"]
+}]
+async fn image_create(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    query_params: Query<params::OptionalProjectSelector>,
+    new_image: TypedBody<params::ImageCreate>,
+) -> Result<HttpResponseCreated<Image>, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.nexus;
+        let query = query_params.into_inner();
+        let params = &new_image.into_inner();
+        let parent_lookup = match query.project.clone() {
+            Some(project) => {
+                let project_lookup = nexus.project_lookup(
+                    &opctx,
+                    params::ProjectSelector { project },
+                )?;
+                ImageParentLookup::Project(project_lookup)
+            }
+            None => {
+                let silo_lookup = nexus.current_silo_lookup(&opctx)?;
+                ImageParentLookup::Silo(silo_lookup)
+            }
+        };
+        let image = nexus.image_create(&opctx, &parent_lookup, &params).await?;
+        Ok(HttpResponseCreated(image.into()))
+    };
+    apictx.instrument_audited_handler(&rqctx, "image_create", handler).await
+}
+
+/// Fetch an image
+///
+/// Fetch the details for a specific image in a project.
+#[endpoint {
+    method = GET,
+    path = "/v1/images/{image}",
+    tags = ["images"],
+}]
+async fn image_view(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    path_params: Path<params::ImagePath>,
+    query_params: Query<params::OptionalProjectSelector>,
+) -> Result<HttpResponseOk<Image>, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.nexus;
+        let path = path_params.into_inner();
+        let query = query_params.into_inner();
+        let image = nexus
+            .image_fetch(
+                &opctx,
+                params::ImageSelector {
+                    image: path.image,
+                    project: query.project,
+                },
+            )
+            .await?;
+        Ok(HttpResponseOk(image.into()))
+    };
+    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+}
+
+/// Delete an image
+///
+/// Permanently delete an image from a project. This operation cannot be undone.
+/// Any instances in the project using the image will continue to run, however
+/// new instances can not be created with this image.
 #[endpoint {
     method = DELETE,
     path = "/v1/images/{image}",
@@ -2951,7 +4198,7 @@ async fn image_delete(
         nexus.image_delete(&opctx, &image_lookup).await?;
         Ok(HttpResponseDeleted())
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_audited_handler(&rqctx, "image_delete", handler).await
 }
 
 /// Promote a project image
@@ -2985,7 +4232,7 @@ async fn image_promote(
         let image = nexus.image_promote(&opctx, &image_lookup).await?;
         Ok(HttpResponseAccepted(image.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_audited_handler(&rqctx, "image_promote", handler).await
 }
 
 /// Demote a silo image
@@ -2993,46 +4240,1127 @@ async fn image_promote(
 /// Demote a silo image to be visible only to a specified project
 #[endpoint {
     method = POST,
-    path = "/v1/images/{image}/demote",
-    tags = ["images"]
+    path = "/v1/images/{image}/demote",
+    tags = ["images"]
+}]
+async fn image_demote(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    path_params: Path<params::ImagePath>,
+    query_params: Query<params::ProjectSelector>,
+) -> Result<HttpResponseAccepted<Image>, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.nexus;
+        let path = path_params.into_inner();
+        let query = query_params.into_inner();
+        let image_lookup = nexus
+            .image_lookup(
+                &opctx,
+                params::ImageSelector { image: path.image, project: None },
+            )
+            .await?;
+
+        let project_lookup = nexus.project_lookup(&opctx, query)?;
+
+        let image =
+            nexus.image_demote(&opctx, &image_lookup, &project_lookup).await?;
+        Ok(HttpResponseAccepted(image.into()))
+    };
+    apictx.instrument_audited_handler(&rqctx, "image_demote", handler).await
+}
+
+/// Update an image's labels
+///
+/// Replaces all of the image's labels with the given set.
+#[endpoint {
+    method = PUT,
+    path = "/v1/images/{image}/labels",
+    tags = ["images"],
+}]
+async fn image_labels_update(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    path_params: Path<params::ImagePath>,
+    query_params: Query<params::OptionalProjectSelector>,
+    labels: TypedBody<Labels>,
+) -> Result<HttpResponseOk<Image>, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.nexus;
+        let path = path_params.into_inner();
+        let query = query_params.into_inner();
+        let labels = labels.into_inner();
+        let image_lookup = nexus
+            .image_lookup(
+                &opctx,
+                params::ImageSelector {
+                    image: path.image,
+                    project: query.project,
+                },
+            )
+            .await?;
+        let image =
+            nexus.image_labels_update(&opctx, &image_lookup, &labels).await?;
+        Ok(HttpResponseOk(image.into()))
+    };
+    apictx
+        .instrument_audited_handler(&rqctx, "image_labels_update", handler)
+        .await
+}
+
+/// List the silos an image is shared with
+#[endpoint {
+    method = GET,
+    path = "/v1/images/{image}/silos",
+    tags = ["images"],
+}]
+async fn image_silo_list(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    query_params: Query<PaginatedByNameOrId>,
+    path_params: Path<params::ImagePath>,
+) -> Result<HttpResponseOk<ResultsPage<Silo>>, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.nexus;
+        let path = path_params.into_inner();
+        let query = query_params.into_inner();
+        let pag_params = data_page_params_for(&rqctx, &query)?;
+        let scan_params = ScanByNameOrId::from_query(&query)?;
+        let paginated_by = name_or_id_pagination(&pag_params, scan_params)?;
+        let image_lookup = nexus
+            .image_lookup(
+                &opctx,
+                params::ImageSelector { image: path.image, project: None },
+            )
+            .await?;
+        let silos = nexus
+            .image_silo_list(&opctx, &image_lookup, &paginated_by)
+            .await?
+            .into_iter()
+            .map(|s| s.try_into())
+            .collect::<Result<Vec<_>, Error>>()?;
+        Ok(HttpResponseOk(ScanByNameOrId::results_page(
+            &query,
+            silos,
+            &marker_for_name_or_id,
+        )?))
+    };
+    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+}
+
+/// Share an image with a silo
+///
+/// Projects in the silo can then create disks from the image, without its
+/// contents being copied. Only silo images can be shared.
+#[endpoint {
+    method = POST,
+    path = "/v1/images/{image}/silos/{silo}",
+    tags = ["images"],
+}]
+async fn image_silo_add(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    path_params: Path<params::ImageSiloPath>,
+) -> Result<HttpResponseCreated<Silo>, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.nexus;
+        let path = path_params.into_inner();
+        let image_lookup = nexus
+            .image_lookup(
+                &opctx,
+                params::ImageSelector { image: path.image, project: None },
+            )
+            .await?;
+        let silo =
+            nexus.image_silo_add(&opctx, &image_lookup, path.silo).await?;
+        Ok(HttpResponseCreated(silo.try_into()?))
+    };
+    apictx.instrument_audited_handler(&rqctx, "image_silo_add", handler).await
+}
+
+/// Stop sharing an image with a silo
+///
+/// Disks already created from the image in that silo are not affected.
+#[endpoint {
+    method = DELETE,
+    path = "/v1/images/{image}/silos/{silo}",
+    tags = ["images"],
+}]
+async fn image_silo_remove(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    path_params: Path<params::ImageSiloPath>,
+) -> Result<HttpResponseDeleted, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.nexus;
+        let path = path_params.into_inner();
+        let image_lookup = nexus
+            .image_lookup(
+                &opctx,
+                params::ImageSelector { image: path.image, project: None },
+            )
+            .await?;
+        nexus.image_silo_remove(&opctx, &image_lookup, path.silo).await?;
+        Ok(HttpResponseDeleted())
+    };
+    apictx
+        .instrument_audited_handler(&rqctx, "image_silo_remove", handler)
+        .await
+}
+
+/// List network interfaces
+#[endpoint {
+    method = GET,
+    path = "/v1/network-interfaces",
+    tags = ["instances"],
+}]
+async fn instance_network_interface_list(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    query_params: Query<PaginatedByNameOrId<params::InstanceSelector>>,
+) -> Result<HttpResponseOk<ResultsPage<InstanceNetworkInterface>>, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.nexus;
+        let query = query_params.into_inner();
+        let pag_params = data_page_params_for(&rqctx, &query)?;
+        let scan_params = ScanByNameOrId::from_query(&query)?;
+        let paginated_by = name_or_id_pagination(&pag_params, scan_params)?;
+        let instance_lookup =
+            nexus.instance_lookup(&opctx, scan_params.selector.clone())?;
+        let interfaces = nexus
+            .instance_network_interface_list(
+                &opctx,
+                &instance_lookup,
+                &paginated_by,
+            )
+            .await?
+            .into_iter()
+            .map(|d| d.into())
+            .collect();
+        Ok(HttpResponseOk(ScanByNameOrId::results_page(
+            &query,
+            interfaces,
+            &marker_for_name_or_id,
+        )?))
+    };
+    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+}
+
+/// Create a network interface
+#[endpoint {
+    method = POST,
+    path = "/v1/network-interfaces",
+    tags = ["instances"],
+}]
+async fn instance_network_interface_create(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    query_params: Query<params::InstanceSelector>,
+    interface_params: TypedBody<params::InstanceNetworkInterfaceCreate>,
+) -> Result<HttpResponseCreated<InstanceNetworkInterface>, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.nexus;
+        let query = query_params.into_inner();
+        let instance_lookup = nexus.instance_lookup(&opctx, query)?;
+        let iface = nexus
+            .network_interface_create(
+                &opctx,
+                &instance_lookup,
+                &interface_params.into_inner(),
+            )
+            .await?;
+        Ok(HttpResponseCreated(iface.into()))
+    };
+    apictx
+        .instrument_audited_handler(
+            &rqctx,
+            "instance_network_interface_create",
+            handler,
+        )
+        .await
+}
+
+/// Delete a network interface
+///
+/// Note that the primary interface for an instance cannot be deleted if there
+/// are any secondary interfaces. A new primary interface must be designated
+/// first. The primary interface can be deleted if there are no secondary
+/// interfaces.
+#[endpoint {
+    method = DELETE,
+    path = "/v1/network-interfaces/{interface}",
+    tags = ["instances"],
+}]
+async fn instance_network_interface_delete(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    path_params: Path<params::NetworkInterfacePath>,
+    query_params: Query<params::OptionalInstanceSelector>,
+) -> Result<HttpResponseDeleted, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.nexus;
+        let path = path_params.into_inner();
+        let query = query_params.into_inner();
+        let interface_selector = params::InstanceNetworkInterfaceSelector {
+            project: query.project,
+            instance: query.instance,
+            network_interface: path.interface,
+        };
+        let interface_lookup = nexus
+            .instance_network_interface_lookup(&opctx, interface_selector)?;
+        nexus
+            .instance_network_interface_delete(&opctx, &interface_lookup)
+            .await?;
+        Ok(HttpResponseDeleted())
+    };
+    apictx
+        .instrument_audited_handler(
+            &rqctx,
+            "instance_network_interface_delete",
+            handler,
+        )
+        .await
+}
+
+/// Fetch a network interface
+#[endpoint {
+    method = GET,
+    path = "/v1/network-interfaces/{interface}",
+    tags = ["instances"],
+}]
+async fn instance_network_interface_view(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    path_params: Path<params::NetworkInterfacePath>,
+    query_params: Query<params::OptionalInstanceSelector>,
+) -> Result<HttpResponseOk<InstanceNetworkInterface>, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.nexus;
+        let path = path_params.into_inner();
+        let query = query_params.into_inner();
+        let interface_selector = params::InstanceNetworkInterfaceSelector {
+            project: query.project,
+            instance: query.instance,
+            network_interface: path.interface,
+        };
+        let (.., interface) = nexus
+            .instance_network_interface_lookup(&opctx, interface_selector)?
+            .fetch()
+            .await?;
+        Ok(HttpResponseOk(interface.into()))
+    };
+    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+}
+
+/// Update a network interface
+#[endpoint {
+    method = PUT,
+    path = "/v1/network-interfaces/{interface}",
+    tags = ["instances"],
+}]
+async fn instance_network_interface_update(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    path_params: Path<params::NetworkInterfacePath>,
+    query_params: Query<params::OptionalInstanceSelector>,
+    updated_iface: TypedBody<params::InstanceNetworkInterfaceUpdate>,
+) -> Result<HttpResponseOk<InstanceNetworkInterface>, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.nexus;
+        let path = path_params.into_inner();
+        let query = query_params.into_inner();
+        let updated_iface = updated_iface.into_inner();
+        let network_interface_selector =
+            params::InstanceNetworkInterfaceSelector {
+                project: query.project,
+                instance: query.instance,
+                network_interface: path.interface,
+            };
+        let network_interface_lookup = nexus
+            .instance_network_interface_lookup(
+                &opctx,
+                network_interface_selector,
+            )?;
+        let interface = nexus
+            .instance_network_interface_update(
+                &opctx,
+                &network_interface_lookup,
+                updated_iface,
+            )
+            .await?;
+        Ok(HttpResponseOk(InstanceNetworkInterface::from(interface)))
+    };
+    apictx
+        .instrument_audited_handler(
+            &rqctx,
+            "instance_network_interface_update",
+            handler,
+        )
+        .await
+}
+
+// External IP addresses for instances
+
+/// List external IP addresses
+#[endpoint {
+    method = GET,
+    path = "/v1/instances/{instance}/external-ips",
+    tags = ["instances"],
+}]
+async fn instance_external_ip_list(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    query_params: Query<params::OptionalProjectSelector>,
+    path_params: Path<params::InstancePath>,
+) -> Result<HttpResponseOk<ResultsPage<views::ExternalIp>>, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let nexus = &apictx.nexus;
+        let path = path_params.into_inner();
+        let query = query_params.into_inner();
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let instance_selector = params::InstanceSelector {
+            project: query.project,
+            instance: path.instance,
+        };
+        let instance_lookup =
+            nexus.instance_lookup(&opctx, instance_selector)?;
+        let ips =
+            nexus.instance_list_external_ips(&opctx, &instance_lookup).await?;
+        Ok(HttpResponseOk(ResultsPage { items: ips, next_page: None }))
+    };
+    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+}
+
+// Floating IP Addresses
+
+/// List all Floating IPs
+#[endpoint {
+    method = GET,
+    path = "/v1/floating-ips",
+    tags = ["floating-ips"],
+}]
+async fn floating_ip_list(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    query_params: Query<PaginatedByNameOrId<params::ProjectSelector>>,
+) -> Result<HttpResponseOk<ResultsPage<FloatingIp>>, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.nexus;
+        let query = query_params.into_inner();
+        let pag_params = data_page_params_for(&rqctx, &query)?;
+        let scan_params = ScanByNameOrId::from_query(&query)?;
+        let paginated_by = name_or_id_pagination(&pag_params, scan_params)?;
+        let project_lookup =
+            nexus.project_lookup(&opctx, scan_params.selector.clone())?;
+        let ips = nexus
+            .floating_ips_list(&opctx, &project_lookup, &paginated_by)
+            .await?
+            .into_iter()
+            .map(|ip| ip.into())
+            .collect();
+        Ok(HttpResponseOk(ScanByNameOrId::results_page(
+            &query,
+            ips,
+            &marker_for_name_or_id,
+        )?))
+    };
+    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+}
+
+/// Create a Floating IP
+#[endpoint {
+    method = POST,
+    path = "/v1/floating-ips",
+    tags = ["floating-ips"],
+}]
+async fn floating_ip_create(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    query_params: Query<params::ProjectSelector>,
+    floating_params: TypedBody<params::FloatingIpCreate>,
+) -> Result<HttpResponseCreated<FloatingIp>, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.nexus;
+        let floating_params = floating_params.into_inner();
+        let project_lookup =
+            nexus.project_lookup(&opctx, query_params.into_inner())?;
+        let ip = nexus
+            .floating_ip_create(&opctx, &project_lookup, floating_params)
+            .await?;
+        Ok(HttpResponseCreated(ip.into()))
+    };
+    apictx
+        .instrument_audited_handler(&rqctx, "floating_ip_create", handler)
+        .await
+}
+
+/// Fetch a floating IP
+#[endpoint {
+    method = GET,
+    path = "/v1/floating-ips/{floating_ip}",
+    tags = ["floating-ips"],
+}]
+async fn floating_ip_view(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    path_params: Path<params::FloatingIpPath>,
+    query_params: Query<params::OptionalProjectSelector>,
+) -> Result<HttpResponseOk<FloatingIp>, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.nexus;
+        let path = path_params.into_inner();
+        let query = query_params.into_inner();
+        let floating_ip_selector = params::FloatingIpSelector {
+            floating_ip: path.floating_ip,
+            project: query.project,
+        };
+        let (.., fip) = nexus
+            .floating_ip_lookup(&opctx, floating_ip_selector)?
+            .fetch()
+            .await?;
+        Ok(HttpResponseOk(fip.into()))
+    };
+    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+}
+
+/// Delete a Floating IP
+///
+/// The Floating IP must not be attached to an instance.
+#[endpoint {
+    method = DELETE,
+    path = "/v1/floating-ips/{floating_ip}",
+    tags = ["floating-ips"],
+}]
+async fn floating_ip_delete(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    path_params: Path<params::FloatingIpPath>,
+    query_params: Query<params::OptionalProjectSelector>,
+) -> Result<HttpResponseDeleted, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.nexus;
+        let path = path_params.into_inner();
+        let query = query_params.into_inner();
+        let floating_ip_selector = params::FloatingIpSelector {
+            floating_ip: path.floating_ip,
+            project: query.project,
+        };
+        let fip_lookup =
+            nexus.floating_ip_lookup(&opctx, floating_ip_selector)?;
+        nexus.floating_ip_delete(&opctx, &fip_lookup).await?;
+        Ok(HttpResponseDeleted())
+    };
+    apictx
+        .instrument_audited_handler(&rqctx, "floating_ip_delete", handler)
+        .await
+}
+
+/// Attach a Floating IP to an instance
+///
+/// The instance must be stopped, and must belong to the same project as the
+/// Floating IP.
+#[endpoint {
+    method = POST,
+    path = "/v1/floating-ips/{floating_ip}/attach",
+    tags = ["floating-ips"],
+}]
+async fn floating_ip_attach(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    path_params: Path<params::FloatingIpPath>,
+    query_params: Query<params::OptionalProjectSelector>,
+    target: TypedBody<params::FloatingIpAttach>,
+) -> Result<HttpResponseAccepted<FloatingIp>, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.nexus;
+        let path = path_params.into_inner();
+        let query = query_params.into_inner();
+        let floating_ip_selector = params::FloatingIpSelector {
+            floating_ip: path.floating_ip,
+            project: query.project,
+        };
+        let fip_lookup =
+            nexus.floating_ip_lookup(&opctx, floating_ip_selector)?;
+        let ip = nexus
+            .floating_ip_attach(&opctx, &fip_lookup, target.into_inner())
+            .await?;
+        Ok(HttpResponseAccepted(ip.into()))
+    };
+    apictx
+        .instrument_audited_handler(&rqctx, "floating_ip_attach", handler)
+        .await
+}
+
+/// Detach a Floating IP from an instance
+///
+/// The instance must be stopped.
+#[endpoint {
+    method = POST,
+    path = "/v1/floating-ips/{floating_ip}/detach",
+    tags = ["floating-ips"],
+}]
+async fn floating_ip_detach(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    path_params: Path<params::FloatingIpPath>,
+    query_params: Query<params::OptionalProjectSelector>,
+) -> Result<HttpResponseAccepted<FloatingIp>, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.nexus;
+        let path = path_params.into_inner();
+        let query = query_params.into_inner();
+        let floating_ip_selector = params::FloatingIpSelector {
+            floating_ip: path.floating_ip,
+            project: query.project,
+        };
+        let fip_lookup =
+            nexus.floating_ip_lookup(&opctx, floating_ip_selector)?;
+        let ip = nexus.floating_ip_detach(&opctx, &fip_lookup).await?;
+        Ok(HttpResponseAccepted(ip.into()))
+    };
+    apictx
+        .instrument_audited_handler(&rqctx, "floating_ip_detach", handler)
+        .await
+}
+
+// Affinity Groups
+
+/// List affinity groups
+#[endpoint {
+    method = GET,
+    path = "/v1/affinity-groups",
+    tags = ["affinity"],
+}]
+async fn affinity_group_list(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    query_params: Query<PaginatedByNameOrId<params::ProjectSelector>>,
+) -> Result<HttpResponseOk<ResultsPage<AffinityGroup>>, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.nexus;
+        let query = query_params.into_inner();
+        let pag_params = data_page_params_for(&rqctx, &query)?;
+        let scan_params = ScanByNameOrId::from_query(&query)?;
+        let paginated_by = name_or_id_pagination(&pag_params, scan_params)?;
+        let project_lookup =
+            nexus.project_lookup(&opctx, scan_params.selector.clone())?;
+        let groups = nexus
+            .affinity_group_list(&opctx, &project_lookup, &paginated_by)
+            .await?
+            .into_iter()
+            .map(|g| g.into())
+            .collect();
+        Ok(HttpResponseOk(ScanByNameOrId::results_page(
+            &query,
+            groups,
+            &marker_for_name_or_id,
+        )?))
+    };
+    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+}
+
+/// Create an affinity group
+#[endpoint {
+    method = POST,
+    path = "/v1/affinity-groups",
+    tags = ["affinity"],
+}]
+async fn affinity_group_create(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    query_params: Query<params::ProjectSelector>,
+    new_group: TypedBody<params::AffinityGroupCreate>,
+) -> Result<HttpResponseCreated<AffinityGroup>, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.nexus;
+        let new_group = new_group.into_inner();
+        let project_lookup =
+            nexus.project_lookup(&opctx, query_params.into_inner())?;
+        let group = nexus
+            .affinity_group_create(&opctx, &project_lookup, new_group)
+            .await?;
+        Ok(HttpResponseCreated(group.into()))
+    };
+    apictx
+        .instrument_audited_handler(&rqctx, "affinity_group_create", handler)
+        .await
+}
+
+/// Fetch an affinity group
+#[endpoint {
+    method = GET,
+    path = "/v1/affinity-groups/{affinity_group}",
+    tags = ["affinity"],
+}]
+async fn affinity_group_view(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    path_params: Path<params::AffinityGroupPath>,
+    query_params: Query<params::OptionalProjectSelector>,
+) -> Result<HttpResponseOk<AffinityGroup>, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.nexus;
+        let path = path_params.into_inner();
+        let query = query_params.into_inner();
+        let group_selector = params::AffinityGroupSelector {
+            affinity_group: path.affinity_group,
+            project: query.project,
+        };
+        let (.., group) = nexus
+            .affinity_group_lookup(&opctx, group_selector)?
+            .fetch()
+            .await?;
+        Ok(HttpResponseOk(group.into()))
+    };
+    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+}
+
+/// Delete an affinity group
+///
+/// Members of the group are not affected, other than no longer being
+/// constrained by the group.
+#[endpoint {
+    method = DELETE,
+    path = "/v1/affinity-groups/{affinity_group}",
+    tags = ["affinity"],
+}]
+async fn affinity_group_delete(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    path_params: Path<params::AffinityGroupPath>,
+    query_params: Query<params::OptionalProjectSelector>,
+) -> Result<HttpResponseDeleted, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.nexus;
+        let path = path_params.into_inner();
+        let query = query_params.into_inner();
+        let group_selector = params::AffinityGroupSelector {
+            affinity_group: path.affinity_group,
+            project: query.project,
+        };
+        let group_lookup =
+            nexus.affinity_group_lookup(&opctx, group_selector)?;
+        nexus.affinity_group_delete(&opctx, &group_lookup).await?;
+        Ok(HttpResponseDeleted())
+    };
+    apictx
+        .instrument_audited_handler(&rqctx, "affinity_group_delete", handler)
+        .await
+}
+
+/// List the instances in an affinity group
+#[endpoint {
+    method = GET,
+    path = "/v1/affinity-groups/{affinity_group}/members",
+    tags = ["affinity"],
+}]
+async fn affinity_group_member_list(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    query_params: Query<PaginatedByNameOrId<params::OptionalProjectSelector>>,
+    path_params: Path<params::AffinityGroupPath>,
+) -> Result<HttpResponseOk<ResultsPage<Instance>>, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.nexus;
+        let path = path_params.into_inner();
+        let query = query_params.into_inner();
+        let pag_params = data_page_params_for(&rqctx, &query)?;
+        let scan_params = ScanByNameOrId::from_query(&query)?;
+        let paginated_by = name_or_id_pagination(&pag_params, scan_params)?;
+        let group_selector = params::AffinityGroupSelector {
+            affinity_group: path.affinity_group,
+            project: scan_params.selector.project.clone(),
+        };
+        let group_lookup =
+            nexus.affinity_group_lookup(&opctx, group_selector)?;
+        let instances = nexus
+            .affinity_group_member_list(&opctx, &group_lookup, &paginated_by)
+            .await?
+            .into_iter()
+            .map(|i| i.into())
+            .collect();
+        Ok(HttpResponseOk(ScanByNameOrId::results_page(
+            &query,
+            instances,
+            &marker_for_name_or_id,
+        )?))
+    };
+    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+}
+
+/// Add an instance to an affinity group
+///
+/// The instance must belong to the same project as the affinity group. If the
+/// group has a hard policy, the instance's current placement must not violate
+/// it.
+#[endpoint {
+    method = POST,
+    path = "/v1/affinity-groups/{affinity_group}/members/{instance}",
+    tags = ["affinity"],
+}]
+async fn affinity_group_member_add(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    path_params: Path<params::AffinityGroupMemberPath>,
+    query_params: Query<params::OptionalProjectSelector>,
+) -> Result<HttpResponseCreated<Instance>, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.nexus;
+        let path = path_params.into_inner();
+        let query = query_params.into_inner();
+        let group_selector = params::AffinityGroupSelector {
+            affinity_group: path.affinity_group,
+            project: query.project,
+        };
+        let group_lookup =
+            nexus.affinity_group_lookup(&opctx, group_selector)?;
+        let instance = nexus
+            .affinity_group_member_add(&opctx, &group_lookup, path.instance)
+            .await?;
+        Ok(HttpResponseCreated(instance.into()))
+    };
+    apictx
+        .instrument_audited_handler(
+            &rqctx,
+            "affinity_group_member_add",
+            handler,
+        )
+        .await
+}
+
+/// Remove an instance from an affinity group
+#[endpoint {
+    method = DELETE,
+    path = "/v1/affinity-groups/{affinity_group}/members/{instance}",
+    tags = ["affinity"],
+}]
+async fn affinity_group_member_remove(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    path_params: Path<params::AffinityGroupMemberPath>,
+    query_params: Query<params::OptionalProjectSelector>,
+) -> Result<HttpResponseDeleted, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.nexus;
+        let path = path_params.into_inner();
+        let query = query_params.into_inner();
+        let group_selector = params::AffinityGroupSelector {
+            affinity_group: path.affinity_group,
+            project: query.project,
+        };
+        let group_lookup =
+            nexus.affinity_group_lookup(&opctx, group_selector)?;
+        nexus
+            .affinity_group_member_remove(&opctx, &group_lookup, path.instance)
+            .await?;
+        Ok(HttpResponseDeleted())
+    };
+    apictx
+        .instrument_audited_handler(
+            &rqctx,
+            "affinity_group_member_remove",
+            handler,
+        )
+        .await
+}
+
+// Snapshots
+
+/// List snapshots
+#[endpoint {
+    method = GET,
+    path = "/v1/snapshots",
+    tags = ["snapshots"],
+}]
+async fn snapshot_list(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    query_params: Query<PaginatedByNameOrId<params::ProjectLabelSelector>>,
+) -> Result<HttpResponseOk<ResultsPage<Snapshot>>, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.nexus;
+        let query = query_params.into_inner();
+        let pag_params = data_page_params_for(&rqctx, &query)?;
+        let scan_params = ScanByNameOrId::from_query(&query)?;
+        let paginated_by = name_or_id_pagination(&pag_params, scan_params)?;
+        let selector = &scan_params.selector;
+        let project_lookup = nexus.project_lookup(
+            &opctx,
+            params::ProjectSelector { project: selector.project.clone() },
+        )?;
+        let snapshots = nexus
+            .snapshot_list(
+                &opctx,
+                &project_lookup,
+                &paginated_by,
+                selector.label_selector.as_ref(),
+            )
+            .await?
+            .into_iter()
+            .map(|d| d.into())
+            .collect();
+        Ok(HttpResponseOk(ScanByNameOrId::results_page(
+            &query,
+            snapshots,
+            &marker_for_name_or_id,
+        )?))
+    };
+    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+}
+
+/// Create a snapshot
+///
+/// Creates a point-in-time snapshot from a disk.
+#[endpoint {
+    method = POST,
+    path = "/v1/snapshots",
+    tags = ["snapshots"],
+}]
+async fn snapshot_create(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    query_params: Query<params::ProjectSelector>,
+    new_snapshot: TypedBody<params::SnapshotCreate>,
+) -> Result<HttpResponseCreated<Snapshot>, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.nexus;
+        let query = query_params.into_inner();
+        let new_snapshot_params = &new_snapshot.into_inner();
+        let project_lookup = nexus.project_lookup(&opctx, query)?;
+        let snapshot = nexus
+            .snapshot_create(&opctx, project_lookup, &new_snapshot_params)
+            .await?;
+        Ok(HttpResponseCreated(snapshot.into()))
+    };
+    apictx.instrument_audited_handler(&rqctx, "snapshot_create", handler).await
+}
+
+/// Fetch a snapshot
+#[endpoint {
+    method = GET,
+    path = "/v1/snapshots/{snapshot}",
+    tags = ["snapshots"],
+}]
+async fn snapshot_view(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    path_params: Path<params::SnapshotPath>,
+    query_params: Query<params::OptionalProjectSelector>,
+) -> Result<HttpResponseOk<Snapshot>, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.nexus;
+        let path = path_params.into_inner();
+        let query = query_params.into_inner();
+        let snapshot_selector = params::SnapshotSelector {
+            project: query.project,
+            snapshot: path.snapshot,
+        };
+        let (.., snapshot) =
+            nexus.snapshot_lookup(&opctx, snapshot_selector)?.fetch().await?;
+        Ok(HttpResponseOk(snapshot.into()))
+    };
+    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+}
+
+/// Delete a snapshot
+#[endpoint {
+    method = DELETE,
+    path = "/v1/snapshots/{snapshot}",
+    tags = ["snapshots"],
+}]
+async fn snapshot_delete(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    path_params: Path<params::SnapshotPath>,
+    query_params: Query<params::OptionalProjectSelector>,
+) -> Result<HttpResponseDeleted, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.nexus;
+        let path = path_params.into_inner();
+        let query = query_params.into_inner();
+        let snapshot_selector = params::SnapshotSelector {
+            project: query.project,
+            snapshot: path.snapshot,
+        };
+        let snapshot_lookup =
+            nexus.snapshot_lookup(&opctx, snapshot_selector)?;
+        nexus.snapshot_delete(&opctx, &snapshot_lookup).await?;
+        Ok(HttpResponseDeleted())
+    };
+    apictx.instrument_audited_handler(&rqctx, "snapshot_delete", handler).await
+}
+
+/// Update a snapshot's labels
+///
+/// Replaces all of the snapshot's labels with the given set.
+#[endpoint {
+    method = PUT,
+    path = "/v1/snapshots/{snapshot}/labels",
+    tags = ["snapshots"],
+}]
+async fn snapshot_labels_update(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    path_params: Path<params::SnapshotPath>,
+    query_params: Query<params::OptionalProjectSelector>,
+    labels: TypedBody<Labels>,
+) -> Result<HttpResponseOk<Snapshot>, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.nexus;
+        let path = path_params.into_inner();
+        let query = query_params.into_inner();
+        let labels = labels.into_inner();
+        let snapshot_selector = params::SnapshotSelector {
+            project: query.project,
+            snapshot: path.snapshot,
+        };
+        let snapshot_lookup =
+            nexus.snapshot_lookup(&opctx, snapshot_selector)?;
+        let snapshot = nexus
+            .snapshot_labels_update(&opctx, &snapshot_lookup, &labels)
+            .await?;
+        Ok(HttpResponseOk(snapshot.into()))
+    };
+    apictx
+        .instrument_audited_handler(&rqctx, "snapshot_labels_update", handler)
+        .await
+}
+
+/// Start exporting blocks from a snapshot
+///
+/// Attach a snapshot to a Pantry so that its blocks can be read out with bulk
+/// reads. The snapshot can't be deleted until the export is stopped.
+#[endpoint {
+    method = POST,
+    path = "/v1/snapshots/{snapshot}/export-start",
+    tags = ["snapshots"],
+}]
+async fn snapshot_export_start(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    path_params: Path<params::SnapshotPath>,
+    query_params: Query<params::OptionalProjectSelector>,
+) -> Result<HttpResponseUpdatedNoContent, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.nexus;
+        let path = path_params.into_inner();
+        let query = query_params.into_inner();
+        let snapshot_selector = params::SnapshotSelector {
+            project: query.project,
+            snapshot: path.snapshot,
+        };
+        let snapshot_lookup =
+            nexus.snapshot_lookup(&opctx, snapshot_selector)?;
+        nexus.snapshot_export_start(&opctx, &snapshot_lookup).await?;
+        Ok(HttpResponseUpdatedNoContent())
+    };
+    apictx
+        .instrument_audited_handler(&rqctx, "snapshot_export_start", handler)
+        .await
+}
+
+/// Export blocks from a snapshot
+#[endpoint {
+    method = POST,
+    path = "/v1/snapshots/{snapshot}/bulk-read",
+    tags = ["snapshots"],
+}]
+async fn snapshot_bulk_read_export(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    path_params: Path<params::SnapshotPath>,
+    query_params: Query<params::OptionalProjectSelector>,
+    export_params: TypedBody<params::ExportBlocksBulkRead>,
+) -> Result<HttpResponseOk<views::ExportedBlocks>, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.nexus;
+        let path = path_params.into_inner();
+        let query = query_params.into_inner();
+        let params = export_params.into_inner();
+        let snapshot_selector = params::SnapshotSelector {
+            project: query.project,
+            snapshot: path.snapshot,
+        };
+        let snapshot_lookup =
+            nexus.snapshot_lookup(&opctx, snapshot_selector)?;
+        let blocks = nexus.snapshot_bulk_read(&snapshot_lookup, params).await?;
+        Ok(HttpResponseOk(blocks))
+    };
+    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+}
+
+/// Stop exporting blocks from a snapshot
+#[endpoint {
+    method = POST,
+    path = "/v1/snapshots/{snapshot}/export-stop",
+    tags = ["snapshots"],
 }]
-async fn image_demote(
+async fn snapshot_export_stop(
     rqctx: RequestContext<Arc<ServerContext>>,
-    path_params: Path<params::ImagePath>,
-    query_params: Query<params::ProjectSelector>,
-) -> Result<HttpResponseAccepted<Image>, HttpError> {
+    path_params: Path<params::SnapshotPath>,
+    query_params: Query<params::OptionalProjectSelector>,
+) -> Result<HttpResponseUpdatedNoContent, HttpError> {
     let apictx = rqctx.context();
     let handler = async {
         let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
         let nexus = &apictx.nexus;
         let path = path_params.into_inner();
         let query = query_params.into_inner();
-        let image_lookup = nexus
-            .image_lookup(
-                &opctx,
-                params::ImageSelector { image: path.image, project: None },
-            )
-            .await?;
-
-        let project_lookup = nexus.project_lookup(&opctx, query)?;
-
-        let image =
-            nexus.image_demote(&opctx, &image_lookup, &project_lookup).await?;
-        Ok(HttpResponseAccepted(image.into()))
+        let snapshot_selector = params::SnapshotSelector {
+            project: query.project,
+            snapshot: path.snapshot,
+        };
+        let snapshot_lookup =
+            nexus.snapshot_lookup(&opctx, snapshot_selector)?;
+        nexus.snapshot_export_stop(&opctx, &snapshot_lookup).await?;
+        Ok(HttpResponseUpdatedNoContent())
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx
+        .instrument_audited_handler(&rqctx, "snapshot_export_stop", handler)
+        .await
 }
 
-/// List network interfaces
+// Snapshot schedules
+
+/// List snapshot schedules
 #[endpoint {
     method = GET,
-    path = "/v1/network-interfaces",
-    tags = ["instances"],
+    path = "/v1/snapshot-schedules",
+    tags = ["snapshots"],
 }]
-async fn instance_network_interface_list(
+async fn snapshot_schedule_list(
     rqctx: RequestContext<Arc<ServerContext>>,
-    query_params: Query<PaginatedByNameOrId<params::InstanceSelector>>,
-) -> Result<HttpResponseOk<ResultsPage<InstanceNetworkInterface>>, HttpError> {
+    query_params: Query<PaginatedByNameOrId<params::ProjectSelector>>,
+) -> Result<HttpResponseOk<ResultsPage<SnapshotSchedule>>, HttpError> {
     let apictx = rqctx.context();
     let handler = async {
         let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
@@ -3041,210 +5369,130 @@ async fn instance_network_interface_list(
         let pag_params = data_page_params_for(&rqctx, &query)?;
         let scan_params = ScanByNameOrId::from_query(&query)?;
         let paginated_by = name_or_id_pagination(&pag_params, scan_params)?;
-        let instance_lookup =
-            nexus.instance_lookup(&opctx, scan_params.selector.clone())?;
-        let interfaces = nexus
-            .instance_network_interface_list(
-                &opctx,
-                &instance_lookup,
-                &paginated_by,
-            )
+        let project_lookup =
+            nexus.project_lookup(&opctx, scan_params.selector.clone())?;
+        let schedules = nexus
+            .snapshot_schedule_list(&opctx, &project_lookup, &paginated_by)
             .await?
             .into_iter()
-            .map(|d| d.into())
+            .map(|s| s.into())
             .collect();
         Ok(HttpResponseOk(ScanByNameOrId::results_page(
             &query,
-            interfaces,
+            schedules,
             &marker_for_name_or_id,
         )?))
     };
     apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
 }
 
-/// Create a network interface
-#[endpoint {
-    method = POST,
-    path = "/v1/network-interfaces",
-    tags = ["instances"],
-}]
-async fn instance_network_interface_create(
-    rqctx: RequestContext<Arc<ServerContext>>,
-    query_params: Query<params::InstanceSelector>,
-    interface_params: TypedBody<params::InstanceNetworkInterfaceCreate>,
-) -> Result<HttpResponseCreated<InstanceNetworkInterface>, HttpError> {
-    let apictx = rqctx.context();
-    let handler = async {
-        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
-        let nexus = &apictx.nexus;
-        let query = query_params.into_inner();
-        let instance_lookup = nexus.instance_lookup(&opctx, query)?;
-        let iface = nexus
-            .network_interface_create(
-                &opctx,
-                &instance_lookup,
-                &interface_params.into_inner(),
-            )
-            .await?;
-        Ok(HttpResponseCreated(iface.into()))
-    };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
-}
-
-/// Delete a network interface
+/// Create a snapshot schedule
 ///
-/// Note that the primary interface for an instance cannot be deleted if there
-/// are any secondary interfaces. A new primary interface must be designated
-/// first. The primary interface can be deleted if there are no secondary
-/// interfaces.
+/// The schedule snapshots either a single disk, or every disk in the project
+/// that has all of the given labels at the time it runs.  Only the most
+/// recent snapshots of each disk taken by the schedule are kept.
 #[endpoint {
-    method = DELETE,
-    path = "/v1/network-interfaces/{interface}",
-    tags = ["instances"],
+    method = POST,
+    path = "/v1/snapshot-schedules",
+    tags = ["snapshots"],
 }]
-async fn instance_network_interface_delete(
+async fn snapshot_schedule_create(
     rqctx: RequestContext<Arc<ServerContext>>,
-    path_params: Path<params::NetworkInterfacePath>,
-    query_params: Query<params::OptionalInstanceSelector>,
-) -> Result<HttpResponseDeleted, HttpError> {
+    query_params: Query<params::ProjectSelector>,
+    new_schedule: TypedBody<params::SnapshotScheduleCreate>,
+) -> Result<HttpResponseCreated<SnapshotSchedule>, HttpError> {
     let apictx = rqctx.context();
     let handler = async {
         let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
         let nexus = &apictx.nexus;
-        let path = path_params.into_inner();
-        let query = query_params.into_inner();
-        let interface_selector = params::InstanceNetworkInterfaceSelector {
-            project: query.project,
-            instance: query.instance,
-            network_interface: path.interface,
-        };
-        let interface_lookup = nexus
-            .instance_network_interface_lookup(&opctx, interface_selector)?;
-        nexus
-            .instance_network_interface_delete(&opctx, &interface_lookup)
+        let new_schedule = new_schedule.into_inner();
+        let project_lookup =
+            nexus.project_lookup(&opctx, query_params.into_inner())?;
+        let schedule = nexus
+            .snapshot_schedule_create(&opctx, &project_lookup, new_schedule)
             .await?;
-        Ok(HttpResponseDeleted())
+        Ok(HttpResponseCreated(schedule.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx
+        .instrument_audited_handler(&rqctx, "snapshot_schedule_create", handler)
+        .await
 }
 
-/// Fetch a network interface
+/// Fetch a snapshot schedule
 #[endpoint {
     method = GET,
-    path = "/v1/network-interfaces/{interface}",
-    tags = ["instances"],
+    path = "/v1/snapshot-schedules/{schedule}",
+    tags = ["snapshots"],
 }]
-async fn instance_network_interface_view(
+async fn snapshot_schedule_view(
     rqctx: RequestContext<Arc<ServerContext>>,
-    path_params: Path<params::NetworkInterfacePath>,
-    query_params: Query<params::OptionalInstanceSelector>,
-) -> Result<HttpResponseOk<InstanceNetworkInterface>, HttpError> {
+    path_params: Path<params::SnapshotSchedulePath>,
+    query_params: Query<params::OptionalProjectSelector>,
+) -> Result<HttpResponseOk<SnapshotSchedule>, HttpError> {
     let apictx = rqctx.context();
     let handler = async {
         let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
         let nexus = &apictx.nexus;
         let path = path_params.into_inner();
         let query = query_params.into_inner();
-        let interface_selector = params::InstanceNetworkInterfaceSelector {
+        let schedule_selector = params::SnapshotScheduleSelector {
             project: query.project,
-            instance: query.instance,
-            network_interface: path.interface,
+            schedule: path.schedule,
         };
-        let (.., interface) = nexus
-            .instance_network_interface_lookup(&opctx, interface_selector)?
+        let (.., schedule) = nexus
+            .snapshot_schedule_lookup(&opctx, schedule_selector)?
             .fetch()
             .await?;
-        Ok(HttpResponseOk(interface.into()))
-    };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
-}
-
-/// Update a network interface
-#[endpoint {
-    method = PUT,
-    path = "/v1/network-interfaces/{interface}",
-    tags = ["instances"],
-}]
-async fn instance_network_interface_update(
-    rqctx: RequestContext<Arc<ServerContext>>,
-    path_params: Path<params::NetworkInterfacePath>,
-    query_params: Query<params::OptionalInstanceSelector>,
-    updated_iface: TypedBody<params::InstanceNetworkInterfaceUpdate>,
-) -> Result<HttpResponseOk<InstanceNetworkInterface>, HttpError> {
-    let apictx = rqctx.context();
-    let handler = async {
-        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
-        let nexus = &apictx.nexus;
-        let path = path_params.into_inner();
-        let query = query_params.into_inner();
-        let updated_iface = updated_iface.into_inner();
-        let network_interface_selector =
-            params::InstanceNetworkInterfaceSelector {
-                project: query.project,
-                instance: query.instance,
-                network_interface: path.interface,
-            };
-        let network_interface_lookup = nexus
-            .instance_network_interface_lookup(
-                &opctx,
-                network_interface_selector,
-            )?;
-        let interface = nexus
-            .instance_network_interface_update(
-                &opctx,
-                &network_interface_lookup,
-                updated_iface,
-            )
-            .await?;
-        Ok(HttpResponseOk(InstanceNetworkInterface::from(interface)))
+        Ok(HttpResponseOk(schedule.into()))
     };
     apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
 }
 
-// External IP addresses for instances
-
-/// List external IP addresses
+/// Delete a snapshot schedule
+///
+/// Snapshots already taken by the schedule are not deleted.
 #[endpoint {
-    method = GET,
-    path = "/v1/instances/{instance}/external-ips",
-    tags = ["instances"],
+    method = DELETE,
+    path = "/v1/snapshot-schedules/{schedule}",
+    tags = ["snapshots"],
 }]
-async fn instance_external_ip_list(
+async fn snapshot_schedule_delete(
     rqctx: RequestContext<Arc<ServerContext>>,
+    path_params: Path<params::SnapshotSchedulePath>,
     query_params: Query<params::OptionalProjectSelector>,
-    path_params: Path<params::InstancePath>,
-) -> Result<HttpResponseOk<ResultsPage<views::ExternalIp>>, HttpError> {
+) -> Result<HttpResponseDeleted, HttpError> {
     let apictx = rqctx.context();
     let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
         let nexus = &apictx.nexus;
         let path = path_params.into_inner();
         let query = query_params.into_inner();
-        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
-        let instance_selector = params::InstanceSelector {
+        let schedule_selector = params::SnapshotScheduleSelector {
             project: query.project,
-            instance: path.instance,
+            schedule: path.schedule,
         };
-        let instance_lookup =
-            nexus.instance_lookup(&opctx, instance_selector)?;
-        let ips =
-            nexus.instance_list_external_ips(&opctx, &instance_lookup).await?;
-        Ok(HttpResponseOk(ResultsPage { items: ips, next_page: None }))
+        let schedule_lookup =
+            nexus.snapshot_schedule_lookup(&opctx, schedule_selector)?;
+        nexus.snapshot_schedule_delete(&opctx, &schedule_lookup).await?;
+        Ok(HttpResponseDeleted())
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx
+        .instrument_audited_handler(&rqctx, "snapshot_schedule_delete", handler)
+        .await
 }
 
-// Snapshots
+// Webhooks
 
-/// List snapshots
+/// List webhook receivers
 #[endpoint {
     method = GET,
-    path = "/v1/snapshots",
-    tags = ["snapshots"],
+    path = "/v1/webhook-receivers",
+    tags = ["webhooks"],
 }]
-async fn snapshot_list(
+async fn webhook_receiver_list(
     rqctx: RequestContext<Arc<ServerContext>>,
-    query_params: Query<PaginatedByNameOrId<params::ProjectSelector>>,
-) -> Result<HttpResponseOk<ResultsPage<Snapshot>>, HttpError> {
+    query_params: Query<PaginatedByNameOrId>,
+) -> Result<HttpResponseOk<ResultsPage<WebhookReceiver>>, HttpError> {
     let apictx = rqctx.context();
     let handler = async {
         let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
@@ -3253,104 +5501,131 @@ async fn snapshot_list(
         let pag_params = data_page_params_for(&rqctx, &query)?;
         let scan_params = ScanByNameOrId::from_query(&query)?;
         let paginated_by = name_or_id_pagination(&pag_params, scan_params)?;
-        let project_lookup =
-            nexus.project_lookup(&opctx, scan_params.selector.clone())?;
-        let snapshots = nexus
-            .snapshot_list(&opctx, &project_lookup, &paginated_by)
+        let receivers = nexus
+            .webhook_receiver_list(&opctx, &paginated_by)
             .await?
             .into_iter()
-            .map(|d| d.into())
+            .map(|r| r.into())
             .collect();
         Ok(HttpResponseOk(ScanByNameOrId::results_page(
             &query,
-            snapshots,
+            receivers,
             &marker_for_name_or_id,
         )?))
     };
     apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
 }
 
-/// Create a snapshot
+/// Create a webhook receiver
 ///
-/// Creates a point-in-time snapshot from a disk.
+/// Events in the current silo of the classes the receiver subscribes to are
+/// POSTed to its endpoint as they happen.  Deliveries that aren't accepted
+/// are retried with backoff.
 #[endpoint {
     method = POST,
-    path = "/v1/snapshots",
-    tags = ["snapshots"],
+    path = "/v1/webhook-receivers",
+    tags = ["webhooks"],
 }]
-async fn snapshot_create(
+async fn webhook_receiver_create(
     rqctx: RequestContext<Arc<ServerContext>>,
-    query_params: Query<params::ProjectSelector>,
-    new_snapshot: TypedBody<params::SnapshotCreate>,
-) -> Result<HttpResponseCreated<Snapshot>, HttpError> {
+    new_receiver: TypedBody<params::WebhookReceiverCreate>,
+) -> Result<HttpResponseCreated<WebhookReceiver>, HttpError> {
     let apictx = rqctx.context();
     let handler = async {
         let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
         let nexus = &apictx.nexus;
-        let query = query_params.into_inner();
-        let new_snapshot_params = &new_snapshot.into_inner();
-        let project_lookup = nexus.project_lookup(&opctx, query)?;
-        let snapshot = nexus
-            .snapshot_create(&opctx, project_lookup, &new_snapshot_params)
+        let receiver = nexus
+            .webhook_receiver_create(&opctx, new_receiver.into_inner())
             .await?;
-        Ok(HttpResponseCreated(snapshot.into()))
+        Ok(HttpResponseCreated(receiver.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx
+        .instrument_audited_handler(&rqctx, "webhook_receiver_create", handler)
+        .await
 }
 
-/// Fetch a snapshot
+/// Fetch a webhook receiver
 #[endpoint {
     method = GET,
-    path = "/v1/snapshots/{snapshot}",
-    tags = ["snapshots"],
+    path = "/v1/webhook-receivers/{receiver}",
+    tags = ["webhooks"],
 }]
-async fn snapshot_view(
+async fn webhook_receiver_view(
     rqctx: RequestContext<Arc<ServerContext>>,
-    path_params: Path<params::SnapshotPath>,
-    query_params: Query<params::OptionalProjectSelector>,
-) -> Result<HttpResponseOk<Snapshot>, HttpError> {
+    path_params: Path<params::WebhookReceiverPath>,
+) -> Result<HttpResponseOk<WebhookReceiver>, HttpError> {
     let apictx = rqctx.context();
     let handler = async {
         let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
         let nexus = &apictx.nexus;
         let path = path_params.into_inner();
-        let query = query_params.into_inner();
-        let snapshot_selector = params::SnapshotSelector {
-            project: query.project,
-            snapshot: path.snapshot,
-        };
-        let (.., snapshot) =
-            nexus.snapshot_lookup(&opctx, snapshot_selector)?.fetch().await?;
-        Ok(HttpResponseOk(snapshot.into()))
+        let (.., receiver) = nexus
+            .webhook_receiver_lookup(&opctx, path.receiver)?
+            .fetch()
+            .await?;
+        Ok(HttpResponseOk(receiver.into()))
     };
     apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
 }
 
-/// Delete a snapshot
+/// Delete a webhook receiver
+///
+/// Events that have yet to be delivered to the receiver are discarded.
 #[endpoint {
     method = DELETE,
-    path = "/v1/snapshots/{snapshot}",
-    tags = ["snapshots"],
+    path = "/v1/webhook-receivers/{receiver}",
+    tags = ["webhooks"],
 }]
-async fn snapshot_delete(
+async fn webhook_receiver_delete(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    path_params: Path<params::WebhookReceiverPath>,
+) -> Result<HttpResponseDeleted, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.nexus;
+        let path = path_params.into_inner();
+        let receiver_lookup =
+            nexus.webhook_receiver_lookup(&opctx, path.receiver)?;
+        nexus.webhook_receiver_delete(&opctx, &receiver_lookup).await?;
+        Ok(HttpResponseDeleted())
+    };
+    apictx
+        .instrument_audited_handler(&rqctx, "webhook_receiver_delete", handler)
+        .await
+}
+
+/// List the deliveries of events to a webhook receiver
+#[endpoint {
+    method = GET,
+    path = "/v1/webhook-receivers/{receiver}/deliveries",
+    tags = ["webhooks"],
+}]
+async fn webhook_delivery_list(
     rqctx: RequestContext<Arc<ServerContext>>,
-    path_params: Path<params::SnapshotPath>,
-    query_params: Query<params::OptionalProjectSelector>,
-) -> Result<HttpResponseDeleted, HttpError> {
+    path_params: Path<params::WebhookReceiverPath>,
+    query_params: Query<PaginatedById>,
+) -> Result<HttpResponseOk<ResultsPage<WebhookDelivery>>, HttpError> {
     let apictx = rqctx.context();
     let handler = async {
         let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
         let nexus = &apictx.nexus;
         let path = path_params.into_inner();
         let query = query_params.into_inner();
-        let snapshot_selector = params::SnapshotSelector {
-            project: query.project,
-            snapshot: path.snapshot,
-        };
-        let snapshot_lookup =
-            nexus.snapshot_lookup(&opctx, snapshot_selector)?;
-        nexus.snapshot_delete(&opctx, &snapshot_lookup).await?;
-        Ok(HttpResponseDeleted())
+        let pagparams = data_page_params_for(&rqctx, &query)?;
+        let receiver_lookup =
+            nexus.webhook_receiver_lookup(&opctx, path.receiver)?;
+        let deliveries = nexus
+            .webhook_delivery_list(&opctx, &receiver_lookup, &pagparams)
+            .await?
+            .into_iter()
+            .map(|d| d.into())
+            .collect();
+        Ok(HttpResponseOk(ScanById::results_page(
+            &query,
+            deliveries,
+            &|_, d: &WebhookDelivery| d.id,
+        )?))
     };
     apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
 }
@@ -3365,7 +5640,7 @@ async fn snapshot_delete(
 }]
 async fn vpc_list(
     rqctx: RequestContext<Arc<ServerContext>>,
-    query_params: Query<PaginatedByNameOrId<params::ProjectSelector>>,
+    query_params: Query<PaginatedByNameOrId<params::ProjectLabelSelector>>,
 ) -> Result<HttpResponseOk<ResultsPage<Vpc>>, HttpError> {
     let apictx = rqctx.context();
     let handler = async {
@@ -3375,10 +5650,18 @@ async fn vpc_list(
         let scan_params = ScanByNameOrId::from_query(&query)?;
         let paginated_by = name_or_id_pagination(&pag_params, scan_params)?;
         let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
-        let project_lookup =
-            nexus.project_lookup(&opctx, scan_params.selector.clone())?;
+        let selector = &scan_params.selector;
+        let project_lookup = nexus.project_lookup(
+            &opctx,
+            params::ProjectSelector { project: selector.project.clone() },
+        )?;
         let vpcs = nexus
-            .vpc_list(&opctx, &project_lookup, &paginated_by)
+            .vpc_list(
+                &opctx,
+                &project_lookup,
+                &paginated_by,
+                selector.label_selector.as_ref(),
+            )
             .await?
             .into_iter()
             .map(|p| p.into())
@@ -3416,7 +5699,7 @@ async fn vpc_create(
             .await?;
         Ok(HttpResponseCreated(vpc.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_audited_handler(&rqctx, "vpc_create", handler).await
 }
 
 /// Fetch a VPC
@@ -3429,7 +5712,7 @@ async fn vpc_view(
     rqctx: RequestContext<Arc<ServerContext>>,
     path_params: Path<params::VpcPath>,
     query_params: Query<params::OptionalProjectSelector>,
-) -> Result<HttpResponseOk<Vpc>, HttpError> {
+) -> Result<HttpResponseHeaders<HttpResponseOk<Vpc>, ETagHeader>, HttpError> {
     let apictx = rqctx.context();
     let handler = async {
         let nexus = &apictx.nexus;
@@ -3439,7 +5722,8 @@ async fn vpc_view(
         let vpc_selector =
             params::VpcSelector { project: query.project, vpc: path.vpc };
         let (.., vpc) = nexus.vpc_lookup(&opctx, vpc_selector)?.fetch().await?;
-        Ok(HttpResponseOk(vpc.into()))
+        let etag = ETag::from_time_modified(vpc.time_modified());
+        Ok(etag::http_response_ok(vpc.into(), &etag))
     };
     apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
 }
@@ -3455,7 +5739,7 @@ async fn vpc_update(
     path_params: Path<params::VpcPath>,
     query_params: Query<params::OptionalProjectSelector>,
     updated_vpc: TypedBody<params::VpcUpdate>,
-) -> Result<HttpResponseOk<Vpc>, HttpError> {
+) -> Result<HttpResponseHeaders<HttpResponseOk<Vpc>, ETagHeader>, HttpError> {
     let apictx = rqctx.context();
     let handler = async {
         let nexus = &apictx.nexus;
@@ -3466,12 +5750,20 @@ async fn vpc_update(
         let vpc_selector =
             params::VpcSelector { project: query.project, vpc: path.vpc };
         let vpc_lookup = nexus.vpc_lookup(&opctx, vpc_selector)?;
+        let expected_time_modified = IfMatch::from_request(&rqctx.request)?
+            .and_then(|if_match| if_match.time_modified_candidates());
         let vpc = nexus
-            .project_update_vpc(&opctx, &vpc_lookup, &updated_vpc_params)
+            .project_update_vpc(
+                &opctx,
+                &vpc_lookup,
+                &updated_vpc_params,
+                expected_time_modified.as_deref(),
+            )
             .await?;
-        Ok(HttpResponseOk(vpc.into()))
+        let etag = ETag::from_time_modified(vpc.time_modified());
+        Ok(etag::http_response_ok(vpc.into(), &etag))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_audited_handler(&rqctx, "vpc_update", handler).await
 }
 
 /// Delete a VPC
@@ -3494,10 +5786,18 @@ async fn vpc_delete(
         let vpc_selector =
             params::VpcSelector { project: query.project, vpc: path.vpc };
         let vpc_lookup = nexus.vpc_lookup(&opctx, vpc_selector)?;
-        nexus.project_delete_vpc(&opctx, &vpc_lookup).await?;
+        let expected_time_modified = IfMatch::from_request(&rqctx.request)?
+            .and_then(|if_match| if_match.time_modified_candidates());
+        nexus
+            .project_delete_vpc(
+                &opctx,
+                &vpc_lookup,
+                expected_time_modified.as_deref(),
+            )
+            .await?;
         Ok(HttpResponseDeleted())
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx.instrument_audited_handler(&rqctx, "vpc_delete", handler).await
 }
 
 /// List subnets
@@ -3557,7 +5857,9 @@ async fn vpc_subnet_create(
             nexus.vpc_create_subnet(&opctx, &vpc_lookup, &create).await?;
         Ok(HttpResponseCreated(subnet.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx
+        .instrument_audited_handler(&rqctx, "vpc_subnet_create", handler)
+        .await
 }
 
 /// Fetch a subnet
@@ -3570,7 +5872,8 @@ async fn vpc_subnet_view(
     rqctx: RequestContext<Arc<ServerContext>>,
     path_params: Path<params::SubnetPath>,
     query_params: Query<params::OptionalVpcSelector>,
-) -> Result<HttpResponseOk<VpcSubnet>, HttpError> {
+) -> Result<HttpResponseHeaders<HttpResponseOk<VpcSubnet>, ETagHeader>, HttpError>
+{
     let apictx = rqctx.context();
     let handler = async {
         let nexus = &apictx.nexus;
@@ -3584,7 +5887,8 @@ async fn vpc_subnet_view(
         };
         let (.., subnet) =
             nexus.vpc_subnet_lookup(&opctx, subnet_selector)?.fetch().await?;
-        Ok(HttpResponseOk(subnet.into()))
+        let etag = ETag::from_time_modified(subnet.time_modified());
+        Ok(etag::http_response_ok(subnet.into(), &etag))
     };
     apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
 }
@@ -3612,10 +5916,20 @@ async fn vpc_subnet_delete(
             subnet: path.subnet,
         };
         let subnet_lookup = nexus.vpc_subnet_lookup(&opctx, subnet_selector)?;
-        nexus.vpc_delete_subnet(&opctx, &subnet_lookup).await?;
+        let expected_time_modified = IfMatch::from_request(&rqctx.request)?
+            .and_then(|if_match| if_match.time_modified_candidates());
+        nexus
+            .vpc_delete_subnet(
+                &opctx,
+                &subnet_lookup,
+                expected_time_modified.as_deref(),
+            )
+            .await?;
         Ok(HttpResponseDeleted())
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx
+        .instrument_audited_handler(&rqctx, "vpc_subnet_delete", handler)
+        .await
 }
 
 /// Update a subnet
@@ -3629,7 +5943,8 @@ async fn vpc_subnet_update(
     path_params: Path<params::SubnetPath>,
     query_params: Query<params::OptionalVpcSelector>,
     subnet_params: TypedBody<params::VpcSubnetUpdate>,
-) -> Result<HttpResponseOk<VpcSubnet>, HttpError> {
+) -> Result<HttpResponseHeaders<HttpResponseOk<VpcSubnet>, ETagHeader>, HttpError>
+{
     let apictx = rqctx.context();
     let handler = async {
         let nexus = &apictx.nexus;
@@ -3643,12 +5958,22 @@ async fn vpc_subnet_update(
             subnet: path.subnet,
         };
         let subnet_lookup = nexus.vpc_subnet_lookup(&opctx, subnet_selector)?;
+        let expected_time_modified = IfMatch::from_request(&rqctx.request)?
+            .and_then(|if_match| if_match.time_modified_candidates());
         let subnet = nexus
-            .vpc_update_subnet(&opctx, &subnet_lookup, &subnet_params)
+            .vpc_update_subnet(
+                &opctx,
+                &subnet_lookup,
+                &subnet_params,
+                expected_time_modified.as_deref(),
+            )
             .await?;
-        Ok(HttpResponseOk(subnet.into()))
+        let etag = ETag::from_time_modified(subnet.time_modified());
+        Ok(etag::http_response_ok(subnet.into(), &etag))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx
+        .instrument_audited_handler(&rqctx, "vpc_subnet_update", handler)
+        .await
 }
 
 // This endpoint is likely temporary. We would rather list all IPs allocated in
@@ -3700,6 +6025,141 @@ async fn vpc_subnet_list_network_interfaces(
     apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
 }
 
+// VPC Peerings
+
+/// List VPC peerings
+#[endpoint {
+    method = GET,
+    path = "/v1/vpc-peerings",
+    tags = ["vpcs"],
+}]
+async fn vpc_peering_list(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    query_params: Query<PaginatedByNameOrId<params::VpcSelector>>,
+) -> Result<HttpResponseOk<ResultsPage<VpcPeering>>, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let nexus = &apictx.nexus;
+        let query = query_params.into_inner();
+        let pag_params = data_page_params_for(&rqctx, &query)?;
+        let scan_params = ScanByNameOrId::from_query(&query)?;
+        let paginated_by = name_or_id_pagination(&pag_params, scan_params)?;
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let vpc_lookup =
+            nexus.vpc_lookup(&opctx, scan_params.selector.clone())?;
+        let peerings = nexus
+            .vpc_peering_list(&opctx, &vpc_lookup, &paginated_by)
+            .await?
+            .into_iter()
+            .map(|peering| peering.into())
+            .collect();
+        Ok(HttpResponseOk(ScanByNameOrId::results_page(
+            &query,
+            peerings,
+            &marker_for_name_or_id,
+        )?))
+    };
+    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+}
+
+/// Create a VPC peering
+///
+/// Creates this VPC's side of a peering with another VPC in the same silo.
+/// The peering becomes active once the other VPC creates a peering with this
+/// one.
+///
+/// An active peering adds a route to each VPC to the other's system router.
+/// Routes in VPC routers are not yet applied to instances' network interfaces,
+/// so these routes don't yet affect how traffic between the VPCs is routed.
+#[endpoint {
+    method = POST,
+    path = "/v1/vpc-peerings",
+    tags = ["vpcs"],
+}]
+async fn vpc_peering_create(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    query_params: Query<params::VpcSelector>,
+    create_params: TypedBody<params::VpcPeeringCreate>,
+) -> Result<HttpResponseCreated<VpcPeering>, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let nexus = &apictx.nexus;
+        let query = query_params.into_inner();
+        let create = create_params.into_inner();
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let vpc_lookup = nexus.vpc_lookup(&opctx, query)?;
+        let peering =
+            nexus.vpc_peering_create(&opctx, &vpc_lookup, &create).await?;
+        Ok(HttpResponseCreated(peering.into()))
+    };
+    apictx
+        .instrument_audited_handler(&rqctx, "vpc_peering_create", handler)
+        .await
+}
+
+/// Fetch a VPC peering
+#[endpoint {
+    method = GET,
+    path = "/v1/vpc-peerings/{peering}",
+    tags = ["vpcs"],
+}]
+async fn vpc_peering_view(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    path_params: Path<params::VpcPeeringPath>,
+    query_params: Query<params::OptionalVpcSelector>,
+) -> Result<HttpResponseOk<VpcPeering>, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let nexus = &apictx.nexus;
+        let path = path_params.into_inner();
+        let query = query_params.into_inner();
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let peering_selector = params::VpcPeeringSelector {
+            project: query.project,
+            vpc: query.vpc,
+            peering: path.peering,
+        };
+        let (.., peering) =
+            nexus.vpc_peering_lookup(&opctx, peering_selector)?.fetch().await?;
+        Ok(HttpResponseOk(peering.into()))
+    };
+    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+}
+
+/// Delete a VPC peering
+///
+/// If the peering was active, the other VPC's side reverts to pending.
+#[endpoint {
+    method = DELETE,
+    path = "/v1/vpc-peerings/{peering}",
+    tags = ["vpcs"],
+}]
+async fn vpc_peering_delete(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    path_params: Path<params::VpcPeeringPath>,
+    query_params: Query<params::OptionalVpcSelector>,
+) -> Result<HttpResponseDeleted, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let nexus = &apictx.nexus;
+        let path = path_params.into_inner();
+        let query = query_params.into_inner();
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let peering_selector = params::VpcPeeringSelector {
+            project: query.project,
+            vpc: query.vpc,
+            peering: path.peering,
+        };
+        let peering_lookup =
+            nexus.vpc_peering_lookup(&opctx, peering_selector)?;
+        nexus.vpc_peering_delete(&opctx, &peering_lookup).await?;
+        Ok(HttpResponseDeleted())
+    };
+    apictx
+        .instrument_audited_handler(&rqctx, "vpc_peering_delete", handler)
+        .await
+}
+
 // VPC Firewalls
 
 // TODO Is the number of firewall rules bounded?
@@ -3712,10 +6172,10 @@ async fn vpc_subnet_list_network_interfaces(
 async fn vpc_firewall_rules_view(
     rqctx: RequestContext<Arc<ServerContext>>,
     query_params: Query<params::VpcSelector>,
-) -> Result<HttpResponseOk<VpcFirewallRules>, HttpError> {
-    // TODO: Check If-Match and fail if the ETag doesn't match anymore.
-    // Without this check, if firewall rules change while someone is listing
-    // the rules, they will see a mix of the old and new rules.
+) -> Result<
+    HttpResponseHeaders<HttpResponseOk<VpcFirewallRules>, ETagHeader>,
+    HttpError,
+> {
     let apictx = rqctx.context();
     let handler = async {
         let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
@@ -3723,9 +6183,11 @@ async fn vpc_firewall_rules_view(
         let query = query_params.into_inner();
         let vpc_lookup = nexus.vpc_lookup(&opctx, query)?;
         let rules = nexus.vpc_list_firewall_rules(&opctx, &vpc_lookup).await?;
-        Ok(HttpResponseOk(VpcFirewallRules {
+        let rules = VpcFirewallRules {
             rules: rules.into_iter().map(|rule| rule.into()).collect(),
-        }))
+        };
+        let etag = ETag::from_content(&rules)?;
+        Ok(etag::http_response_ok(rules, &etag))
     };
     apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
 }
@@ -3740,8 +6202,10 @@ async fn vpc_firewall_rules_update(
     rqctx: RequestContext<Arc<ServerContext>>,
     query_params: Query<params::VpcSelector>,
     router_params: TypedBody<VpcFirewallRuleUpdateParams>,
-) -> Result<HttpResponseOk<VpcFirewallRules>, HttpError> {
-    // TODO: Check If-Match and fail if the ETag doesn't match anymore.
+) -> Result<
+    HttpResponseHeaders<HttpResponseOk<VpcFirewallRules>, ETagHeader>,
+    HttpError,
+> {
     // TODO: limit size of the ruleset because the GET endpoint is not paginated
     let apictx = rqctx.context();
     let handler = async {
@@ -3750,14 +6214,40 @@ async fn vpc_firewall_rules_update(
         let query = query_params.into_inner();
         let router_params = router_params.into_inner();
         let vpc_lookup = nexus.vpc_lookup(&opctx, query)?;
+        let if_match = IfMatch::from_request(&rqctx.request)?;
         let rules = nexus
-            .vpc_update_firewall_rules(&opctx, &vpc_lookup, &router_params)
+            .vpc_update_firewall_rules(
+                &opctx,
+                &vpc_lookup,
+                &router_params,
+                |current| match &if_match {
+                    Some(if_match) => {
+                        let current = VpcFirewallRules {
+                            rules: current
+                                .iter()
+                                .cloned()
+                                .map(|rule| rule.into())
+                                .collect(),
+                        };
+                        if_match.check(&ETag::from_content(&current)?)
+                    }
+                    None => Ok(()),
+                },
+            )
             .await?;
-        Ok(HttpResponseOk(VpcFirewallRules {
+        let rules = VpcFirewallRules {
             rules: rules.into_iter().map(|rule| rule.into()).collect(),
-        }))
+        };
+        let etag = ETag::from_content(&rules)?;
+        Ok(etag::http_response_ok(rules, &etag))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx
+        .instrument_audited_handler(
+            &rqctx,
+            "vpc_firewall_rules_update",
+            handler,
+        )
+        .await
 }
 
 // VPC Routers
@@ -3854,7 +6344,9 @@ async fn vpc_router_create(
             .await?;
         Ok(HttpResponseCreated(router.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx
+        .instrument_audited_handler(&rqctx, "vpc_router_create", handler)
+        .await
 }
 
 /// Delete a router
@@ -3883,7 +6375,9 @@ async fn vpc_router_delete(
         nexus.vpc_delete_router(&opctx, &router_lookup).await?;
         Ok(HttpResponseDeleted())
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx
+        .instrument_audited_handler(&rqctx, "vpc_router_delete", handler)
+        .await
 }
 
 /// Update a router
@@ -3916,7 +6410,9 @@ async fn vpc_router_update(
             .await?;
         Ok(HttpResponseOk(router.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx
+        .instrument_audited_handler(&rqctx, "vpc_router_update", handler)
+        .await
 }
 
 /// List routes
@@ -4018,7 +6514,9 @@ async fn vpc_router_route_create(
             .await?;
         Ok(HttpResponseCreated(route.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx
+        .instrument_audited_handler(&rqctx, "vpc_router_route_create", handler)
+        .await
 }
 
 /// Delete a route
@@ -4049,7 +6547,9 @@ async fn vpc_router_route_delete(
         nexus.router_delete_route(&opctx, &route_lookup).await?;
         Ok(HttpResponseDeleted())
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx
+        .instrument_audited_handler(&rqctx, "vpc_router_route_delete", handler)
+        .await
 }
 
 /// Update a route
@@ -4084,7 +6584,9 @@ async fn vpc_router_route_update(
             .await?;
         Ok(HttpResponseOk(route.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx
+        .instrument_audited_handler(&rqctx, "vpc_router_route_update", handler)
+        .await
 }
 
 // Racks
@@ -4483,6 +6985,73 @@ async fn silo_metric(
     apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
 }
 
+// Audit log
+
+/// Page selector for the audit log
+///
+/// The audit log is sorted by the time at which each request completed (and
+/// then by id, since many requests may complete at the same time), so it has
+/// its own pagination scheme.
+#[derive(Deserialize, JsonSchema, Serialize)]
+struct AuditLogPage {
+    #[serde(flatten)]
+    selector: params::AuditLogSelector,
+    last_seen_time: chrono::DateTime<Utc>,
+    last_seen_id: Uuid,
+}
+
+/// List audit log entries
+///
+/// Entries are listed in the order in which the requests they describe
+/// completed.
+#[endpoint {
+    method = GET,
+    path = "/v1/system/audit-log",
+    tags = ["system/audit-log"],
+}]
+async fn audit_log_list(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    query_params: Query<
+        PaginationParams<params::AuditLogSelector, AuditLogPage>,
+    >,
+) -> Result<HttpResponseOk<ResultsPage<views::AuditLogEntry>>, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.nexus;
+    let query = query_params.into_inner();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let (selector, marker) = match &query.page {
+            WhichPage::First(selector) => (selector, None),
+            WhichPage::Next(AuditLogPage {
+                selector,
+                last_seen_time,
+                last_seen_id,
+            }) => (selector, Some((*last_seen_time, *last_seen_id))),
+        };
+        let pagparams = DataPageParams {
+            limit: rqctx.page_limit(&query)?,
+            direction: PaginationOrder::Ascending,
+            marker: marker.as_ref(),
+        };
+        let entries = nexus
+            .audit_log_list(&opctx, selector, &pagparams)
+            .await?
+            .into_iter()
+            .map(|e| e.into())
+            .collect();
+        Ok(HttpResponseOk(dropshot::ResultsPage::new(
+            entries,
+            selector,
+            |entry: &views::AuditLogEntry, selector| AuditLogPage {
+                selector: selector.clone(),
+                last_seen_time: entry.time_completed,
+                last_seen_id: entry.id,
+            },
+        )?))
+    };
+    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+}
+
 // Updates
 
 /// Refresh update data
@@ -4502,7 +7071,9 @@ async fn system_update_refresh(
         nexus.updates_refresh_metadata(&opctx).await?;
         Ok(HttpResponseUpdatedNoContent())
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx
+        .instrument_audited_handler(&rqctx, "system_update_refresh", handler)
+        .await
 }
 
 /// View system version and update status
@@ -4703,7 +7274,9 @@ async fn system_update_start(
             status: views::UpdateStatus::Updating,
         }))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx
+        .instrument_audited_handler(&rqctx, "system_update_start", handler)
+        .await
 }
 
 /// Stop system update
@@ -4741,7 +7314,9 @@ async fn system_update_stop(
 
         Ok(HttpResponseUpdatedNoContent())
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx
+        .instrument_audited_handler(&rqctx, "system_update_stop", handler)
+        .await
 }
 
 /// List all update deployments
@@ -5160,7 +7735,13 @@ async fn current_user_ssh_key_create(
             .await?;
         Ok(HttpResponseCreated(ssh_key.into()))
     };
-    apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
+    apictx
+        .instrument_audited_handler(
+            &rqctx,
+            "current_user_ssh_key_create",
+            handler,
+        )
+        .await
 }
 
 /// Fetch an SSH public key
@@ -5226,9 +7807,131 @@ async fn current_user_ssh_key_delete(
         nexus.ssh_key_delete(&opctx, actor.actor_id(), &ssh_key_lookup).await?;
         Ok(HttpResponseDeleted())
     };
+    apictx
+        .instrument_audited_handler(
+            &rqctx,
+            "current_user_ssh_key_delete",
+            handler,
+        )
+        .await
+}
+
+// Per-user access tokens
+
+/// List access tokens
+///
+/// Lists the access tokens belonging to the currently authenticated user,
+/// including when each was last used.  The tokens themselves are not included.
+#[endpoint {
+    method = GET,
+    path = "/v1/me/tokens",
+    tags = ["session"],
+}]
+async fn current_user_token_list(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    query_params: Query<PaginatedById>,
+) -> Result<HttpResponseOk<ResultsPage<views::DeviceAccessToken>>, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.nexus;
+        let query = query_params.into_inner();
+        let pag_params = data_page_params_for(&rqctx, &query)?;
+        let &actor = opctx
+            .authn
+            .actor_required()
+            .internal_context("listing current user's access tokens")?;
+        let tokens = nexus
+            .device_access_tokens_list(&opctx, actor.actor_id(), &pag_params)
+            .await?
+            .into_iter()
+            .map(views::DeviceAccessToken::from)
+            .collect();
+        Ok(HttpResponseOk(ScanById::results_page(
+            &query,
+            tokens,
+            &|_, token: &views::DeviceAccessToken| token.id,
+        )?))
+    };
     apictx.external_latencies.instrument_dropshot_handler(&rqctx, handler).await
 }
 
+/// Create an access token
+///
+/// Create a named access token for the currently authenticated user, e.g., for
+/// use by automation.  The token is only included in this response and cannot
+/// be retrieved later.
+#[endpoint {
+    method = POST,
+    path = "/v1/me/tokens",
+    tags = ["session"],
+}]
+async fn current_user_token_create(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    new_token: TypedBody<params::DeviceAccessTokenCreate>,
+) -> Result<HttpResponseCreated<views::DeviceAccessTokenCreated>, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.nexus;
+        let &actor = opctx
+            .authn
+            .actor_required()
+            .internal_context("creating access token for current user")?;
+        let token = nexus
+            .device_access_token_create_for_user(
+                &opctx,
+                actor.actor_id(),
+                new_token.into_inner(),
+            )
+            .await?;
+        Ok(HttpResponseCreated(token.into()))
+    };
+    apictx
+        .instrument_audited_handler(
+            &rqctx,
+            "current_user_token_create",
+            handler,
+        )
+        .await
+}
+
+/// Delete an access token
+///
+/// Revoke one of the currently authenticated user's access tokens.  Requests
+/// using the token will fail from then on.
+#[endpoint {
+    method = DELETE,
+    path = "/v1/me/tokens/{token_id}",
+    tags = ["session"],
+}]
+async fn current_user_token_delete(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    path_params: Path<params::TokenPath>,
+) -> Result<HttpResponseDeleted, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.nexus;
+        let path = path_params.into_inner();
+        let &actor = opctx
+            .authn
+            .actor_required()
+            .internal_context("deleting one of current user's access tokens")?;
+        nexus
+            .device_access_token_delete(&opctx, actor.actor_id(), path.token_id)
+            .await?;
+        Ok(HttpResponseDeleted())
+    };
+    apictx
+        .instrument_audited_handler(
+            &rqctx,
+            "current_user_token_delete",
+            handler,
+        )
+        .await
+}
+
 #[cfg(test)]
 mod test {
     use super::external_api;
@@ -4,7 +4,9 @@
 
 pub mod console_api;
 pub mod device_auth;
+pub mod etag;
 pub mod http_entrypoints;
+pub mod scim;
 
 pub use nexus_types::external_api::params;
 pub use nexus_types::external_api::shared;
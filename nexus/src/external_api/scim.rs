@@ -0,0 +1,1171 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Entrypoints for SCIM 2.0 clients (see RFC 7643 and RFC 7644)
+//!
+//! An external identity provider uses these endpoints to provision the users
+//! and groups of a Silo whose identity mode is `saml_scim`.  The Silo is
+//! identified by the bearer token in the request's "Authorization" header.
+//! See the `app::scim` module for an overview.
+//!
+//! We support the parts of the protocol that identity providers rely on:
+//!
+//! - the "Users" and "Groups" resource types, with the `userName` and `active`
+//!   attributes of users and the `displayName` and `members` attributes of
+//!   groups.  Other attributes are accepted but not stored.
+//! - `eq` filters on `userName` and `displayName`
+//! - index-based pagination with `startIndex` and `count`
+//! - PATCH operations on the attributes above
+//!
+//! SCIM has its own media type and error format, so these endpoints parse
+//! request bodies and build responses themselves rather than using Dropshot's
+//! typed bodies.  For the same reason, they are not part of the published API.
+
+use crate::app::ScimGroupMembersUpdate;
+use crate::authz;
+use crate::db::identity::Asset;
+use crate::db::model::SiloGroup;
+use crate::db::model::SiloUser;
+use crate::db::model::SCIM_TOKEN_PREFIX;
+use crate::ServerContext;
+use chrono::{DateTime, Utc};
+use dropshot::{endpoint, HttpError, Path, Query, RequestContext, UntypedBody};
+use http::{header, Response, StatusCode};
+use hyper::Body;
+use omicron_common::api::external::Error;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use uuid::Uuid;
+
+const SCHEMA_USER: &str = "urn:ietf:params:scim:schemas:core:2.0:User";
+const SCHEMA_GROUP: &str = "urn:ietf:params:scim:schemas:core:2.0:Group";
+const SCHEMA_LIST_RESPONSE: &str =
+    "urn:ietf:params:scim:api:messages:2.0:ListResponse";
+const SCHEMA_ERROR: &str = "urn:ietf:params:scim:api:messages:2.0:Error";
+const SCHEMA_SERVICE_PROVIDER_CONFIG: &str =
+    "urn:ietf:params:scim:schemas:core:2.0:ServiceProviderConfig";
+
+/// Media type of SCIM messages (RFC 7644 §3.1)
+const SCIM_CONTENT_TYPE: &str = "application/scim+json";
+
+/// Maximum number of resources returned in one page of a list
+const MAX_RESULTS: i64 = 1000;
+
+// Responses
+
+/// An error response as described in RFC 7644 §3.12
+#[derive(Debug)]
+struct ScimError {
+    status: StatusCode,
+    scim_type: Option<&'static str>,
+    detail: String,
+}
+
+impl ScimError {
+    fn bad_request(scim_type: &'static str, detail: impl Into<String>) -> Self {
+        ScimError {
+            status: StatusCode::BAD_REQUEST,
+            scim_type: Some(scim_type),
+            detail: detail.into(),
+        }
+    }
+
+    fn invalid_value(detail: impl Into<String>) -> Self {
+        ScimError::bad_request("invalidValue", detail)
+    }
+}
+
+impl From<HttpError> for ScimError {
+    fn from(error: HttpError) -> Self {
+        let scim_type = match error.status_code {
+            StatusCode::CONFLICT => Some("uniqueness"),
+            _ => None,
+        };
+        ScimError {
+            status: error.status_code,
+            scim_type,
+            detail: error.external_message,
+        }
+    }
+}
+
+impl From<Error> for ScimError {
+    fn from(error: Error) -> Self {
+        ScimError::from(HttpError::from(error))
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ScimErrorBody {
+    schemas: [&'static str; 1],
+    status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    scim_type: Option<&'static str>,
+    detail: String,
+}
+
+fn build_scim_response<T>(
+    status: StatusCode,
+    body: &T,
+) -> Result<Response<Body>, HttpError>
+where
+    T: ?Sized + Serialize,
+{
+    let body = serde_json::to_string(body)
+        .map_err(|e| HttpError::for_internal_error(e.to_string()))?;
+    Ok(Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, SCIM_CONTENT_TYPE)
+        .body(body.into())?)
+}
+
+fn build_no_content_response() -> Result<Response<Body>, HttpError> {
+    Ok(Response::builder().status(StatusCode::NO_CONTENT).body(Body::empty())?)
+}
+
+/// Converts the result of a SCIM request handler into a response, using the
+/// SCIM error format for failures
+fn scim_response(
+    result: Result<Response<Body>, ScimError>,
+) -> Result<Response<Body>, HttpError> {
+    match result {
+        Ok(response) => Ok(response),
+        Err(error) => build_scim_response(
+            error.status,
+            &ScimErrorBody {
+                schemas: [SCHEMA_ERROR],
+                status: error.status.as_u16().to_string(),
+                scim_type: error.scim_type,
+                detail: error.detail,
+            },
+        ),
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ScimMeta {
+    resource_type: &'static str,
+    created: DateTime<Utc>,
+    last_modified: DateTime<Utc>,
+    location: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ScimUser {
+    schemas: [&'static str; 1],
+    id: Uuid,
+    user_name: String,
+    active: bool,
+    meta: ScimMeta,
+}
+
+impl From<SiloUser> for ScimUser {
+    fn from(user: SiloUser) -> Self {
+        ScimUser {
+            schemas: [SCHEMA_USER],
+            id: user.id(),
+            meta: ScimMeta {
+                resource_type: "User",
+                created: user.time_created(),
+                last_modified: user.time_modified(),
+                location: format!("/scim/v2/Users/{}", user.id()),
+            },
+            user_name: user.external_id,
+            active: user.active,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+struct ScimMember {
+    value: Uuid,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ScimGroup {
+    schemas: [&'static str; 1],
+    id: Uuid,
+    display_name: String,
+    members: Vec<ScimMember>,
+    meta: ScimMeta,
+}
+
+impl ScimGroup {
+    fn new(group: SiloGroup, members: Vec<Uuid>) -> Self {
+        ScimGroup {
+            schemas: [SCHEMA_GROUP],
+            id: group.id(),
+            meta: ScimMeta {
+                resource_type: "Group",
+                created: group.time_created(),
+                last_modified: group.time_modified(),
+                location: format!("/scim/v2/Groups/{}", group.id()),
+            },
+            display_name: group.external_id,
+            members: members
+                .into_iter()
+                .map(|value| ScimMember { value })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ScimListResponse<T> {
+    schemas: [&'static str; 1],
+    total_results: i64,
+    start_index: i64,
+    items_per_page: usize,
+    #[serde(rename = "Resources")]
+    resources: Vec<T>,
+}
+
+// Requests
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ScimUserRequest {
+    user_name: String,
+    active: Option<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ScimGroupRequest {
+    display_name: String,
+    #[serde(default)]
+    members: Vec<ScimMember>,
+}
+
+#[derive(Deserialize)]
+struct ScimPatchRequest {
+    #[serde(rename = "Operations")]
+    operations: Vec<ScimPatchOperation>,
+}
+
+#[derive(Deserialize)]
+struct ScimPatchOperation {
+    op: String,
+    path: Option<String>,
+    value: Option<serde_json::Value>,
+}
+
+fn parse_body<T: serde::de::DeserializeOwned>(
+    body: &UntypedBody,
+) -> Result<T, ScimError> {
+    serde_json::from_slice(body.as_bytes()).map_err(|e| {
+        ScimError::bad_request("invalidSyntax", format!("bad request: {}", e))
+    })
+}
+
+/// Interprets a SCIM boolean
+///
+/// Some identity providers send booleans as the strings "True" and "False".
+fn parse_bool(value: &serde_json::Value) -> Result<bool, ScimError> {
+    match value {
+        serde_json::Value::Bool(b) => Ok(*b),
+        serde_json::Value::String(s) if s.eq_ignore_ascii_case("true") => {
+            Ok(true)
+        }
+        serde_json::Value::String(s) if s.eq_ignore_ascii_case("false") => {
+            Ok(false)
+        }
+        _ => Err(ScimError::invalid_value(format!(
+            "expected a boolean, found {}",
+            value
+        ))),
+    }
+}
+
+fn parse_string(value: &serde_json::Value) -> Result<String, ScimError> {
+    value.as_str().map(str::to_owned).ok_or_else(|| {
+        ScimError::invalid_value(format!("expected a string, found {}", value))
+    })
+}
+
+fn parse_members(value: serde_json::Value) -> Result<Vec<Uuid>, ScimError> {
+    let members: Vec<ScimMember> = serde_json::from_value(value)
+        .map_err(|e| ScimError::invalid_value(format!("members: {}", e)))?;
+    Ok(members.into_iter().map(|m| m.value).collect())
+}
+
+/// Parses a filter of the form `attribute eq "value"`, returning the value
+///
+/// That's the only kind of filter that identity providers use to find
+/// resources.  Attribute names and operators are case-insensitive.
+fn parse_eq_filter(filter: &str, attribute: &str) -> Result<String, ScimError> {
+    let bad_filter = || {
+        ScimError::bad_request(
+            "invalidFilter",
+            format!(
+                "only filters of the form '{} eq \"...\"' are supported",
+                attribute
+            ),
+        )
+    };
+    let mut parts = filter.trim().splitn(3, ' ');
+    let (Some(attr), Some(op), Some(value)) =
+        (parts.next(), parts.next(), parts.next())
+    else {
+        return Err(bad_filter());
+    };
+    if !attr.eq_ignore_ascii_case(attribute) || !op.eq_ignore_ascii_case("eq") {
+        return Err(bad_filter());
+    }
+    // The value is a JSON string.
+    match serde_json::from_str(value.trim()) {
+        Ok(serde_json::Value::String(value)) => Ok(value),
+        _ => Err(bad_filter()),
+    }
+}
+
+/// Changes requested by a PATCH of a user
+#[derive(Debug, Default, PartialEq)]
+struct UserPatch {
+    user_name: Option<String>,
+    active: Option<bool>,
+}
+
+/// Returns the attributes that a PATCH operation sets, as (path, value) pairs
+fn patch_attributes(
+    path: Option<String>,
+    value: Option<serde_json::Value>,
+) -> Result<Vec<(String, serde_json::Value)>, ScimError> {
+    let value = value
+        .ok_or_else(|| ScimError::invalid_value("operation has no value"))?;
+    match (path, value) {
+        (Some(path), value) => Ok(vec![(path, value)]),
+        (None, serde_json::Value::Object(attributes)) => {
+            Ok(attributes.into_iter().collect())
+        }
+        (None, _) => Err(ScimError::invalid_value(
+            "operation without a path must have an object value",
+        )),
+    }
+}
+
+fn parse_user_patch(request: ScimPatchRequest) -> Result<UserPatch, ScimError> {
+    let mut patch = UserPatch::default();
+    for operation in request.operations {
+        // For single-valued attributes, "add" is the same as "replace".
+        let op = operation.op.to_ascii_lowercase();
+        if op != "add" && op != "replace" {
+            return Err(ScimError::bad_request(
+                "mutability",
+                format!("unsupported operation on a user: {}", operation.op),
+            ));
+        }
+        for (path, value) in patch_attributes(operation.path, operation.value)?
+        {
+            match path.to_ascii_lowercase().as_str() {
+                "username" => patch.user_name = Some(parse_string(&value)?),
+                "active" => patch.active = Some(parse_bool(&value)?),
+                // We don't store any other attributes.
+                _ => (),
+            }
+        }
+    }
+    Ok(patch)
+}
+
+/// Changes requested by a PATCH of a group
+#[derive(Debug, Default, PartialEq)]
+struct GroupPatch {
+    display_name: Option<String>,
+    /// If set, the group's new list of members
+    replace_members: Option<Vec<Uuid>>,
+    add_members: Vec<Uuid>,
+    remove_members: Vec<Uuid>,
+}
+
+impl GroupPatch {
+    fn replace(&mut self, members: Vec<Uuid>) {
+        self.replace_members = Some(members);
+        self.add_members.clear();
+        self.remove_members.clear();
+    }
+
+    fn add(&mut self, members: Vec<Uuid>) {
+        if let Some(replace) = &mut self.replace_members {
+            replace.extend(members);
+        } else {
+            self.remove_members.retain(|m| !members.contains(m));
+            self.add_members.extend(members);
+        }
+    }
+
+    fn remove(&mut self, members: Vec<Uuid>) {
+        if let Some(replace) = &mut self.replace_members {
+            replace.retain(|m| !members.contains(m));
+        } else {
+            self.add_members.retain(|m| !members.contains(m));
+            self.remove_members.extend(members);
+        }
+    }
+
+    fn members_update(self) -> Option<ScimGroupMembersUpdate> {
+        if let Some(members) = self.replace_members {
+            Some(ScimGroupMembersUpdate::Replace(members))
+        } else if !self.add_members.is_empty()
+            || !self.remove_members.is_empty()
+        {
+            Some(ScimGroupMembersUpdate::Modify {
+                add: self.add_members,
+                remove: self.remove_members,
+            })
+        } else {
+            None
+        }
+    }
+}
+
+fn parse_group_patch(
+    request: ScimPatchRequest,
+) -> Result<GroupPatch, ScimError> {
+    let mut patch = GroupPatch::default();
+    for operation in request.operations {
+        let op = operation.op.to_ascii_lowercase();
+        if op == "remove" {
+            let path = operation.path.ok_or_else(|| {
+                ScimError::bad_request("noTarget", "remove requires a path")
+            })?;
+            let lower = path.to_ascii_lowercase();
+            if lower == "members" {
+                match operation.value {
+                    // Remove the listed members
+                    Some(value) => patch.remove(parse_members(value)?),
+                    // Remove all members
+                    None => patch.replace(vec![]),
+                }
+            } else if lower.starts_with("members[") && path.ends_with(']') {
+                // A filter selecting one member, like
+                // `members[value eq "..."]`
+                let filter = &path["members[".len()..path.len() - 1];
+                let member = parse_eq_filter(filter, "value")?;
+                let member = member.parse().map_err(|_| {
+                    ScimError::invalid_value(format!(
+                        "not a member id: {}",
+                        member
+                    ))
+                })?;
+                patch.remove(vec![member]);
+            } else {
+                return Err(ScimError::bad_request(
+                    "mutability",
+                    format!("cannot remove {}", path),
+                ));
+            }
+            continue;
+        }
+
+        if op != "add" && op != "replace" {
+            return Err(ScimError::bad_request(
+                "invalidSyntax",
+                format!("unsupported operation: {}", operation.op),
+            ));
+        }
+        for (path, value) in patch_attributes(operation.path, operation.value)?
+        {
+            match path.to_ascii_lowercase().as_str() {
+                "displayname" => {
+                    patch.display_name = Some(parse_string(&value)?)
+                }
+                "members" if op == "add" => patch.add(parse_members(value)?),
+                "members" => patch.replace(parse_members(value)?),
+                // We don't store any other attributes.
+                _ => (),
+            }
+        }
+    }
+    Ok(patch)
+}
+
+// Authentication
+
+/// Identifies the Silo that a SCIM request is for from its bearer token
+async fn scim_authenticate(
+    rqctx: &RequestContext<Arc<ServerContext>>,
+) -> Result<authz::Silo, ScimError> {
+    let unauthenticated = |detail: &str| ScimError {
+        status: StatusCode::UNAUTHORIZED,
+        scim_type: None,
+        detail: detail.to_string(),
+    };
+
+    let header = rqctx
+        .request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .ok_or_else(|| unauthenticated("missing Authorization header"))?
+        .to_str()
+        .map_err(|_| unauthenticated("malformed Authorization header"))?;
+    let mut parts = header.splitn(2, ' ');
+    let token = match (parts.next(), parts.next()) {
+        (Some(scheme), Some(token))
+            if scheme.eq_ignore_ascii_case("bearer") =>
+        {
+            token.trim().strip_prefix(SCIM_TOKEN_PREFIX)
+        }
+        _ => None,
+    }
+    .ok_or_else(|| unauthenticated("expected a SCIM client bearer token"))?;
+
+    let nexus = &rqctx.context().nexus;
+    // By definition, SCIM clients aren't users of the Silo.  Having identified
+    // the Silo from the token, we act using the Nexus "external authentication"
+    // context, as we do when provisioning users at login.
+    let opctx = nexus.opctx_external_authn();
+    let (authz_silo, scim_token) =
+        nexus.scim_authenticate(opctx, token).await?;
+
+    // If the request is being audited, record it as made by the token.
+    crate::context::audit_scim_client(scim_token.id, authz_silo.id());
+    Ok(authz_silo)
+}
+
+// Endpoints
+
+/// Describes the SCIM features we support (RFC 7643 §5)
+#[endpoint {
+    method = GET,
+    path = "/scim/v2/ServiceProviderConfig",
+    unpublished = true,
+}]
+pub async fn scim_service_provider_config(
+    rqctx: RequestContext<Arc<ServerContext>>,
+) -> Result<Response<Body>, HttpError> {
+    let handler = async {
+        scim_authenticate(&rqctx).await?;
+        let supported =
+            |supported| serde_json::json!({ "supported": supported });
+        Ok(build_scim_response(
+            StatusCode::OK,
+            &serde_json::json!({
+                "schemas": [SCHEMA_SERVICE_PROVIDER_CONFIG],
+                "patch": supported(true),
+                "bulk": {
+                    "supported": false,
+                    "maxOperations": 0,
+                    "maxPayloadSize": 0,
+                },
+                "filter": {
+                    "supported": true,
+                    "maxResults": MAX_RESULTS,
+                },
+                "changePassword": supported(false),
+                "sort": supported(false),
+                "etag": supported(false),
+                "authenticationSchemes": [{
+                    "type": "oauthbearertoken",
+                    "name": "OAuth Bearer Token",
+                    "description": "Authentication using a SCIM client \
+                        token created for the Silo",
+                }],
+            }),
+        )?)
+    };
+    scim_response(handler.await)
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimListQuery {
+    filter: Option<String>,
+    /// 1-based index of the first result
+    start_index: Option<i64>,
+    /// Maximum number of results
+    count: Option<i64>,
+}
+
+impl ScimListQuery {
+    /// Returns the zero-based offset and limit requested by the client
+    fn offset_and_limit(&self) -> (i64, i64) {
+        let offset = self.start_index.unwrap_or(1).max(1) - 1;
+        let limit = self.count.unwrap_or(MAX_RESULTS).clamp(0, MAX_RESULTS);
+        (offset, limit)
+    }
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct ScimUserPath {
+    user_id: Uuid,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct ScimGroupPath {
+    group_id: Uuid,
+}
+
+/// List or search for users
+#[endpoint {
+    method = GET,
+    path = "/scim/v2/Users",
+    unpublished = true,
+}]
+pub async fn scim_user_list(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    query_params: Query<ScimListQuery>,
+) -> Result<Response<Body>, HttpError> {
+    let handler = async {
+        let authz_silo = scim_authenticate(&rqctx).await?;
+        let nexus = &rqctx.context().nexus;
+        let opctx = nexus.opctx_external_authn();
+        let query = query_params.into_inner();
+        let user_name = query
+            .filter
+            .as_deref()
+            .map(|filter| parse_eq_filter(filter, "userName"))
+            .transpose()?;
+        let (offset, limit) = query.offset_and_limit();
+        let (total, users) = nexus
+            .scim_users_list(
+                opctx,
+                &authz_silo,
+                user_name.as_deref(),
+                offset,
+                limit,
+            )
+            .await?;
+        let resources: Vec<ScimUser> =
+            users.into_iter().map(ScimUser::from).collect();
+        Ok(build_scim_response(
+            StatusCode::OK,
+            &ScimListResponse {
+                schemas: [SCHEMA_LIST_RESPONSE],
+                total_results: total,
+                start_index: offset + 1,
+                items_per_page: resources.len(),
+                resources,
+            },
+        )?)
+    };
+    scim_response(handler.await)
+}
+
+/// Fetch a user
+#[endpoint {
+    method = GET,
+    path = "/scim/v2/Users/{user_id}",
+    unpublished = true,
+}]
+pub async fn scim_user_view(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    path_params: Path<ScimUserPath>,
+) -> Result<Response<Body>, HttpError> {
+    let handler = async {
+        let authz_silo = scim_authenticate(&rqctx).await?;
+        let nexus = &rqctx.context().nexus;
+        let opctx = nexus.opctx_external_authn();
+        let user_id = path_params.into_inner().user_id;
+        let user = nexus.scim_user_fetch(opctx, &authz_silo, user_id).await?;
+        Ok(build_scim_response(StatusCode::OK, &ScimUser::from(user))?)
+    };
+    scim_response(handler.await)
+}
+
+/// Create a user
+#[endpoint {
+    method = POST,
+    path = "/scim/v2/Users",
+    unpublished = true,
+}]
+pub async fn scim_user_create(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    body: UntypedBody,
+) -> Result<Response<Body>, HttpError> {
+    let handler = async {
+        let authz_silo = scim_authenticate(&rqctx).await?;
+        let nexus = &rqctx.context().nexus;
+        let opctx = nexus.opctx_external_authn();
+        let request: ScimUserRequest = parse_body(&body)?;
+        let active = request
+            .active
+            .as_ref()
+            .map(parse_bool)
+            .transpose()?
+            .unwrap_or(true);
+        let user = nexus
+            .scim_user_create(opctx, &authz_silo, request.user_name, active)
+            .await?;
+        Ok(build_scim_response(StatusCode::CREATED, &ScimUser::from(user))?)
+    };
+    rqctx
+        .context()
+        .instrument_audited_raw_handler(&rqctx, "scim_user_create", async {
+            scim_response(handler.await)
+        })
+        .await
+}
+
+/// Replace a user
+#[endpoint {
+    method = PUT,
+    path = "/scim/v2/Users/{user_id}",
+    unpublished = true,
+}]
+pub async fn scim_user_replace(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    path_params: Path<ScimUserPath>,
+    body: UntypedBody,
+) -> Result<Response<Body>, HttpError> {
+    let handler = async {
+        let authz_silo = scim_authenticate(&rqctx).await?;
+        let nexus = &rqctx.context().nexus;
+        let opctx = nexus.opctx_external_authn();
+        let user_id = path_params.into_inner().user_id;
+        let request: ScimUserRequest = parse_body(&body)?;
+        let active = request
+            .active
+            .as_ref()
+            .map(parse_bool)
+            .transpose()?
+            .unwrap_or(true);
+        let user = nexus
+            .scim_user_update(
+                opctx,
+                &authz_silo,
+                user_id,
+                Some(request.user_name),
+                Some(active),
+            )
+            .await?;
+        Ok(build_scim_response(StatusCode::OK, &ScimUser::from(user))?)
+    };
+    rqctx
+        .context()
+        .instrument_audited_raw_handler(&rqctx, "scim_user_replace", async {
+            scim_response(handler.await)
+        })
+        .await
+}
+
+/// Update a user, e.g., to deactivate them
+#[endpoint {
+    method = PATCH,
+    path = "/scim/v2/Users/{user_id}",
+    unpublished = true,
+}]
+pub async fn scim_user_patch(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    path_params: Path<ScimUserPath>,
+    body: UntypedBody,
+) -> Result<Response<Body>, HttpError> {
+    let handler = async {
+        let authz_silo = scim_authenticate(&rqctx).await?;
+        let nexus = &rqctx.context().nexus;
+        let opctx = nexus.opctx_external_authn();
+        let user_id = path_params.into_inner().user_id;
+        let patch = parse_user_patch(parse_body(&body)?)?;
+        let user = nexus
+            .scim_user_update(
+                opctx,
+                &authz_silo,
+                user_id,
+                patch.user_name,
+                patch.active,
+            )
+            .await?;
+        Ok(build_scim_response(StatusCode::OK, &ScimUser::from(user))?)
+    };
+    rqctx
+        .context()
+        .instrument_audited_raw_handler(&rqctx, "scim_user_patch", async {
+            scim_response(handler.await)
+        })
+        .await
+}
+
+/// Delete a user
+#[endpoint {
+    method = DELETE,
+    path = "/scim/v2/Users/{user_id}",
+    unpublished = true,
+}]
+pub async fn scim_user_delete(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    path_params: Path<ScimUserPath>,
+) -> Result<Response<Body>, HttpError> {
+    let handler = async {
+        let authz_silo = scim_authenticate(&rqctx).await?;
+        let nexus = &rqctx.context().nexus;
+        let opctx = nexus.opctx_external_authn();
+        let user_id = path_params.into_inner().user_id;
+        nexus.scim_user_delete(opctx, &authz_silo, user_id).await?;
+        Ok(build_no_content_response()?)
+    };
+    rqctx
+        .context()
+        .instrument_audited_raw_handler(&rqctx, "scim_user_delete", async {
+            scim_response(handler.await)
+        })
+        .await
+}
+
+/// List or search for groups
+#[endpoint {
+    method = GET,
+    path = "/scim/v2/Groups",
+    unpublished = true,
+}]
+pub async fn scim_group_list(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    query_params: Query<ScimListQuery>,
+) -> Result<Response<Body>, HttpError> {
+    let handler = async {
+        let authz_silo = scim_authenticate(&rqctx).await?;
+        let nexus = &rqctx.context().nexus;
+        let opctx = nexus.opctx_external_authn();
+        let query = query_params.into_inner();
+        let display_name = query
+            .filter
+            .as_deref()
+            .map(|filter| parse_eq_filter(filter, "displayName"))
+            .transpose()?;
+        let (offset, limit) = query.offset_and_limit();
+        let (total, groups) = nexus
+            .scim_groups_list(
+                opctx,
+                &authz_silo,
+                display_name.as_deref(),
+                offset,
+                limit,
+            )
+            .await?;
+        let mut resources = Vec::with_capacity(groups.len());
+        for group in groups {
+            let members =
+                nexus.scim_group_members(opctx, &authz_silo, &group).await?;
+            resources.push(ScimGroup::new(group, members));
+        }
+        Ok(build_scim_response(
+            StatusCode::OK,
+            &ScimListResponse {
+                schemas: [SCHEMA_LIST_RESPONSE],
+                total_results: total,
+                start_index: offset + 1,
+                items_per_page: resources.len(),
+                resources,
+            },
+        )?)
+    };
+    scim_response(handler.await)
+}
+
+/// Returns the SCIM representation of a group, including its members
+async fn scim_group_response(
+    rqctx: &RequestContext<Arc<ServerContext>>,
+    authz_silo: &authz::Silo,
+    status: StatusCode,
+    group: SiloGroup,
+) -> Result<Response<Body>, ScimError> {
+    let nexus = &rqctx.context().nexus;
+    let opctx = nexus.opctx_external_authn();
+    let members = nexus.scim_group_members(opctx, authz_silo, &group).await?;
+    Ok(build_scim_response(status, &ScimGroup::new(group, members))?)
+}
+
+/// Fetch a group
+#[endpoint {
+    method = GET,
+    path = "/scim/v2/Groups/{group_id}",
+    unpublished = true,
+}]
+pub async fn scim_group_view(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    path_params: Path<ScimGroupPath>,
+) -> Result<Response<Body>, HttpError> {
+    let handler = async {
+        let authz_silo = scim_authenticate(&rqctx).await?;
+        let nexus = &rqctx.context().nexus;
+        let opctx = nexus.opctx_external_authn();
+        let group_id = path_params.into_inner().group_id;
+        let group =
+            nexus.scim_group_fetch(opctx, &authz_silo, group_id).await?;
+        scim_group_response(&rqctx, &authz_silo, StatusCode::OK, group).await
+    };
+    scim_response(handler.await)
+}
+
+/// Create a group
+#[endpoint {
+    method = POST,
+    path = "/scim/v2/Groups",
+    unpublished = true,
+}]
+pub async fn scim_group_create(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    body: UntypedBody,
+) -> Result<Response<Body>, HttpError> {
+    let handler = async {
+        let authz_silo = scim_authenticate(&rqctx).await?;
+        let nexus = &rqctx.context().nexus;
+        let opctx = nexus.opctx_external_authn();
+        let request: ScimGroupRequest = parse_body(&body)?;
+        let group = nexus
+            .scim_group_create(
+                opctx,
+                &authz_silo,
+                request.display_name,
+                request.members.into_iter().map(|m| m.value).collect(),
+            )
+            .await?;
+        scim_group_response(&rqctx, &authz_silo, StatusCode::CREATED, group)
+            .await
+    };
+    rqctx
+        .context()
+        .instrument_audited_raw_handler(&rqctx, "scim_group_create", async {
+            scim_response(handler.await)
+        })
+        .await
+}
+
+/// Replace a group, including its list of members
+#[endpoint {
+    method = PUT,
+    path = "/scim/v2/Groups/{group_id}",
+    unpublished = true,
+}]
+pub async fn scim_group_replace(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    path_params: Path<ScimGroupPath>,
+    body: UntypedBody,
+) -> Result<Response<Body>, HttpError> {
+    let handler = async {
+        let authz_silo = scim_authenticate(&rqctx).await?;
+        let nexus = &rqctx.context().nexus;
+        let opctx = nexus.opctx_external_authn();
+        let group_id = path_params.into_inner().group_id;
+        let request: ScimGroupRequest = parse_body(&body)?;
+        let group = nexus
+            .scim_group_update(
+                opctx,
+                &authz_silo,
+                group_id,
+                Some(request.display_name),
+                Some(ScimGroupMembersUpdate::Replace(
+                    request.members.into_iter().map(|m| m.value).collect(),
+                )),
+            )
+            .await?;
+        scim_group_response(&rqctx, &authz_silo, StatusCode::OK, group).await
+    };
+    rqctx
+        .context()
+        .instrument_audited_raw_handler(&rqctx, "scim_group_replace", async {
+            scim_response(handler.await)
+        })
+        .await
+}
+
+/// Update a group, e.g., to add or remove members
+#[endpoint {
+    method = PATCH,
+    path = "/scim/v2/Groups/{group_id}",
+    unpublished = true,
+}]
+pub async fn scim_group_patch(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    path_params: Path<ScimGroupPath>,
+    body: UntypedBody,
+) -> Result<Response<Body>, HttpError> {
+    let handler = async {
+        let authz_silo = scim_authenticate(&rqctx).await?;
+        let nexus = &rqctx.context().nexus;
+        let opctx = nexus.opctx_external_authn();
+        let group_id = path_params.into_inner().group_id;
+        let mut patch = parse_group_patch(parse_body(&body)?)?;
+        let group = nexus
+            .scim_group_update(
+                opctx,
+                &authz_silo,
+                group_id,
+                patch.display_name.take(),
+                patch.members_update(),
+            )
+            .await?;
+        scim_group_response(&rqctx, &authz_silo, StatusCode::OK, group).await
+    };
+    rqctx
+        .context()
+        .instrument_audited_raw_handler(&rqctx, "scim_group_patch", async {
+            scim_response(handler.await)
+        })
+        .await
+}
+
+/// Delete a group
+#[endpoint {
+    method = DELETE,
+    path = "/scim/v2/Groups/{group_id}",
+    unpublished = true,
+}]
+pub async fn scim_group_delete(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    path_params: Path<ScimGroupPath>,
+) -> Result<Response<Body>, HttpError> {
+    let handler = async {
+        let authz_silo = scim_authenticate(&rqctx).await?;
+        let nexus = &rqctx.context().nexus;
+        let opctx = nexus.opctx_external_authn();
+        let group_id = path_params.into_inner().group_id;
+        nexus.scim_group_delete(opctx, &authz_silo, group_id).await?;
+        Ok(build_no_content_response()?)
+    };
+    rqctx
+        .context()
+        .instrument_audited_raw_handler(&rqctx, "scim_group_delete", async {
+            scim_response(handler.await)
+        })
+        .await
+}
+
+#[cfg(test)]
+mod test {
+    use super::parse_eq_filter;
+    use super::parse_group_patch;
+    use super::parse_user_patch;
+    use super::GroupPatch;
+    use super::ScimPatchRequest;
+    use super::UserPatch;
+    use uuid::Uuid;
+
+    fn patch_request(json: serde_json::Value) -> ScimPatchRequest {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn test_parse_eq_filter() {
+        assert_eq!(
+            parse_eq_filter("userName eq \"alice@example.com\"", "userName")
+                .unwrap(),
+            "alice@example.com"
+        );
+        assert_eq!(
+            parse_eq_filter("username EQ \"a \\\"b\\\" c\"", "userName")
+                .unwrap(),
+            "a \"b\" c"
+        );
+        assert!(parse_eq_filter("userName eq alice", "userName").is_err());
+        assert!(parse_eq_filter("userName ne \"alice\"", "userName").is_err());
+        assert!(parse_eq_filter("displayName eq \"x\"", "userName").is_err());
+        assert!(parse_eq_filter("userName", "userName").is_err());
+    }
+
+    #[test]
+    fn test_parse_user_patch() {
+        // Okta-style
+        let patch = parse_user_patch(patch_request(serde_json::json!({
+            "schemas": ["urn:ietf:params:scim:api:messages:2.0:PatchOp"],
+            "Operations": [{
+                "op": "replace",
+                "value": { "active": false }
+            }]
+        })))
+        .unwrap();
+        assert_eq!(patch, UserPatch { user_name: None, active: Some(false) });
+
+        // Azure-style
+        let patch = parse_user_patch(patch_request(serde_json::json!({
+            "Operations": [
+                { "op": "Replace", "path": "active", "value": "True" },
+                { "op": "Add", "path": "userName", "value": "bob" },
+                { "op": "Add", "path": "title", "value": "Engineer" }
+            ]
+        })))
+        .unwrap();
+        assert_eq!(
+            patch,
+            UserPatch {
+                user_name: Some(String::from("bob")),
+                active: Some(true)
+            }
+        );
+
+        assert!(parse_user_patch(patch_request(serde_json::json!({
+            "Operations": [{ "op": "remove", "path": "userName" }]
+        })))
+        .is_err());
+        assert!(parse_user_patch(patch_request(serde_json::json!({
+            "Operations": [{ "op": "replace", "path": "active", "value": 1 }]
+        })))
+        .is_err());
+    }
+
+    #[test]
+    fn test_parse_group_patch() {
+        let [a, b, c] = [Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4()];
+
+        let patch = parse_group_patch(patch_request(serde_json::json!({
+            "Operations": [
+                {
+                    "op": "add",
+                    "path": "members",
+                    "value": [{ "value": a }, { "value": b }]
+                },
+                {
+                    "op": "remove",
+                    "path": format!("members[value eq \"{}\"]", b)
+                },
+                {
+                    "op": "remove",
+                    "path": "members",
+                    "value": [{ "value": c }]
+                },
+                {
+                    "op": "replace",
+                    "value": { "displayName": "engineering" }
+                }
+            ]
+        })))
+        .unwrap();
+        assert_eq!(
+            patch,
+            GroupPatch {
+                display_name: Some(String::from("engineering")),
+                replace_members: None,
+                add_members: vec![a],
+                remove_members: vec![b, c],
+            }
+        );
+
+        let patch = parse_group_patch(patch_request(serde_json::json!({
+            "Operations": [
+                { "op": "remove", "path": "members" },
+                { "op": "add", "path": "members", "value": [{ "value": a }] },
+                {
+                    "op": "replace",
+                    "path": "members",
+                    "value": [{ "value": b }, { "value": c }]
+                },
+                { "op": "remove", "path": "members", "value": [{ "value": c }] }
+            ]
+        })))
+        .unwrap();
+        assert_eq!(
+            patch,
+            GroupPatch {
+                display_name: None,
+                replace_members: Some(vec![b]),
+                add_members: vec![],
+                remove_members: vec![],
+            }
+        );
+
+        assert!(parse_group_patch(patch_request(serde_json::json!({
+            "Operations": [{ "op": "remove", "path": "displayName" }]
+        })))
+        .is_err());
+        assert!(parse_group_patch(patch_request(serde_json::json!({
+            "Operations": [{ "op": "add", "path": "members", "value": "x" }]
+        })))
+        .is_err());
+    }
+}
@@ -20,7 +20,9 @@ use nexus_types::external_api::shared;
 use nexus_types::external_api::shared::IdentityType;
 use nexus_types::external_api::shared::IpRange;
 use nexus_types::external_api::views;
+use nexus_types::external_api::views::AffinityGroup;
 use nexus_types::external_api::views::Certificate;
+use nexus_types::external_api::views::FloatingIp;
 use nexus_types::external_api::views::IpPool;
 use nexus_types::external_api::views::IpPoolRange;
 use nexus_types::external_api::views::User;
@@ -33,6 +35,7 @@ use omicron_common::api::external::IdentityMetadataCreateParams;
 use omicron_common::api::external::Instance;
 use omicron_common::api::external::InstanceCpuCount;
 use omicron_sled_agent::sim::SledAgent;
+use std::net::IpAddr;
 use std::sync::Arc;
 use uuid::Uuid;
 
@@ -140,6 +143,8 @@ pub async fn create_ip_pool(
                 name: pool_name.parse().unwrap(),
                 description: String::from("an ip pool"),
             },
+            silo: None,
+            is_default: false,
         },
     )
     .await;
@@ -299,6 +304,7 @@ pub async fn create_project(
                 name: project_name.parse().unwrap(),
                 description: "a pier".to_string(),
             },
+            labels: Default::default(),
         },
     )
     .await
@@ -322,6 +328,7 @@ pub async fn create_disk(
                 block_size: params::BlockSize::try_from(512).unwrap(),
             },
             size: ByteCount::from_gibibytes_u32(1),
+            labels: Default::default(),
         },
     )
     .await
@@ -336,6 +343,50 @@ pub async fn delete_disk(
     object_delete(client, &url).await
 }
 
+pub async fn create_floating_ip(
+    client: &ClientTestContext,
+    fip_name: &str,
+    project: &str,
+    address: Option<IpAddr>,
+    parent_pool_name: Option<&str>,
+) -> FloatingIp {
+    object_create(
+        client,
+        &format!("/v1/floating-ips?project={project}"),
+        &params::FloatingIpCreate {
+            identity: IdentityMetadataCreateParams {
+                name: fip_name.parse().unwrap(),
+                description: String::from("a floating ip"),
+            },
+            address,
+            pool: parent_pool_name.map(|v| v.parse().unwrap()),
+        },
+    )
+    .await
+}
+
+pub async fn create_affinity_group(
+    client: &ClientTestContext,
+    project_name: &str,
+    group_name: &str,
+    kind: shared::AffinityGroupKind,
+    policy: shared::AffinityPolicy,
+) -> AffinityGroup {
+    object_create(
+        client,
+        &format!("/v1/affinity-groups?project={project_name}"),
+        &params::AffinityGroupCreate {
+            identity: IdentityMetadataCreateParams {
+                name: group_name.parse().unwrap(),
+                description: String::from("an affinity group"),
+            },
+            kind,
+            policy,
+        },
+    )
+    .await
+}
+
 /// Creates an instance with a default NIC and no disks.
 ///
 /// Wrapper around [`create_instance_with`].
@@ -384,7 +435,11 @@ pub async fn create_instance_with(
             network_interfaces: nics.clone(),
             external_ips,
             disks,
+            affinity_groups: vec![],
+            ssh_public_keys: None,
             start: true,
+            labels: Default::default(),
+            auto_restart_policy: Default::default(),
         },
     )
     .await
@@ -405,6 +460,7 @@ pub async fn create_vpc(
             },
             ipv6_prefix: None,
             dns_name: "abc".parse().unwrap(),
+            labels: Default::default(),
         },
     )
     .await
@@ -431,6 +487,7 @@ pub async fn create_vpc_with_error(
             },
             ipv6_prefix: None,
             dns_name: "abc".parse().unwrap(),
+            labels: Default::default(),
         }))
         .expect_status(Some(status)),
     )
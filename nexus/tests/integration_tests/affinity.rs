@@ -0,0 +1,473 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Integration tests for affinity groups
+
+use camino::Utf8Path;
+use dropshot::test_util::ClientTestContext;
+use dropshot::HttpErrorResponseBody;
+use http::Method;
+use http::StatusCode;
+use nexus_test_interface::NexusServer;
+use nexus_test_utils::http_testing::AuthnMode;
+use nexus_test_utils::http_testing::NexusRequest;
+use nexus_test_utils::http_testing::RequestBuilder;
+use nexus_test_utils::resource_helpers::create_affinity_group;
+use nexus_test_utils::resource_helpers::create_instance;
+use nexus_test_utils::resource_helpers::create_project;
+use nexus_test_utils::resource_helpers::object_create;
+use nexus_test_utils::resource_helpers::objects_list_page_authz;
+use nexus_test_utils::resource_helpers::populate_ip_pool;
+use nexus_test_utils::start_sled_agent;
+use nexus_test_utils_macros::nexus_test;
+use omicron_common::api::external::ByteCount;
+use omicron_common::api::external::IdentityMetadataCreateParams;
+use omicron_common::api::external::Instance;
+use omicron_common::api::external::InstanceCpuCount;
+use omicron_common::api::external::NameOrId;
+use omicron_nexus::external_api::params;
+use omicron_nexus::external_api::shared::AffinityGroupKind;
+use omicron_nexus::external_api::shared::AffinityPolicy;
+use omicron_nexus::external_api::views::AffinityGroup;
+use omicron_nexus::Nexus;
+use omicron_nexus::TestInterfaces as _;
+use omicron_sled_agent::sim;
+use std::sync::Arc;
+use uuid::Uuid;
+
+type ControlPlaneTestContext =
+    nexus_test_utils::ControlPlaneTestContext<omicron_nexus::Server>;
+
+const PROJECT_NAME: &str = "pebble-field";
+
+fn get_affinity_groups_url() -> String {
+    format!("/v1/affinity-groups?project={PROJECT_NAME}")
+}
+
+fn get_affinity_group_url(group_name: &str) -> String {
+    format!("/v1/affinity-groups/{group_name}?project={PROJECT_NAME}")
+}
+
+fn get_members_url(group_name: &str) -> String {
+    format!("/v1/affinity-groups/{group_name}/members?project={PROJECT_NAME}")
+}
+
+fn get_member_url(group_name: &str, instance_name: &str) -> String {
+    format!(
+        "/v1/affinity-groups/{group_name}/members/{instance_name}?project={PROJECT_NAME}"
+    )
+}
+
+async fn affinity_group_get(
+    client: &ClientTestContext,
+    url: &str,
+) -> AffinityGroup {
+    NexusRequest::object_get(client, url)
+        .authn_as(AuthnMode::PrivilegedUser)
+        .execute()
+        .await
+        .unwrap()
+        .parsed_body()
+        .unwrap()
+}
+
+async fn members_list(
+    client: &ClientTestContext,
+    group_name: &str,
+) -> Vec<Instance> {
+    objects_list_page_authz::<Instance>(client, &get_members_url(group_name))
+        .await
+        .items
+}
+
+async fn member_add(
+    client: &ClientTestContext,
+    group_name: &str,
+    instance_name: &str,
+    expected_status: StatusCode,
+) -> dropshot::test_util::TestResponse {
+    NexusRequest::new(
+        RequestBuilder::new(
+            client,
+            Method::POST,
+            &get_member_url(group_name, instance_name),
+        )
+        .expect_status(Some(expected_status)),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap()
+}
+
+fn instance_create_params(
+    instance_name: &str,
+    affinity_groups: &[&str],
+) -> params::InstanceCreate {
+    params::InstanceCreate {
+        identity: IdentityMetadataCreateParams {
+            name: instance_name.parse().unwrap(),
+            description: format!("instance {:?}", instance_name),
+        },
+        ncpus: InstanceCpuCount(1),
+        memory: ByteCount::from_gibibytes_u32(1),
+        hostname: String::from("the_host"),
+        user_data: vec![],
+        network_interfaces: params::InstanceNetworkInterfaceAttachment::None,
+        external_ips: vec![],
+        disks: vec![],
+        affinity_groups: affinity_groups
+            .iter()
+            .map(|g| NameOrId::Name(g.parse().unwrap()))
+            .collect(),
+        ssh_public_keys: None,
+        start: true,
+        labels: Default::default(),
+        auto_restart_policy: Default::default(),
+    }
+}
+
+async fn create_instance_in_groups(
+    client: &ClientTestContext,
+    instance_name: &str,
+    affinity_groups: &[&str],
+) -> Instance {
+    object_create(
+        client,
+        &format!("/v1/instances?project={PROJECT_NAME}"),
+        &instance_create_params(instance_name, affinity_groups),
+    )
+    .await
+}
+
+async fn instance_post(
+    client: &ClientTestContext,
+    instance_name: &str,
+    action: &str,
+    expected_status: StatusCode,
+) -> dropshot::test_util::TestResponse {
+    NexusRequest::new(
+        RequestBuilder::new(
+            client,
+            Method::POST,
+            &format!(
+                "/v1/instances/{instance_name}/{action}?project={PROJECT_NAME}"
+            ),
+        )
+        .body(None as Option<&serde_json::Value>)
+        .expect_status(Some(expected_status)),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap()
+}
+
+async fn instance_simulate(nexus: &Arc<Nexus>, id: &Uuid) {
+    let sa = nexus.instance_sled_by_id(id).await.unwrap();
+    sa.instance_finish_transition(*id).await;
+}
+
+#[nexus_test]
+async fn test_affinity_group_crud(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+
+    create_project(client, PROJECT_NAME).await;
+
+    // There are no affinity groups to start with.
+    let groups = objects_list_page_authz::<AffinityGroup>(
+        client,
+        &get_affinity_groups_url(),
+    )
+    .await
+    .items;
+    assert_eq!(groups.len(), 0);
+
+    let group_name = "spread-out";
+    let group = create_affinity_group(
+        client,
+        PROJECT_NAME,
+        group_name,
+        AffinityGroupKind::AntiAffinity,
+        AffinityPolicy::Hard,
+    )
+    .await;
+    assert_eq!(group.identity.name.as_str(), group_name);
+    assert_eq!(group.kind, AffinityGroupKind::AntiAffinity);
+    assert_eq!(group.policy, AffinityPolicy::Hard);
+
+    // The group can be fetched by name or by ID.
+    let fetched =
+        affinity_group_get(client, &get_affinity_group_url(group_name)).await;
+    assert_eq!(fetched.identity.id, group.identity.id);
+    let fetched = affinity_group_get(
+        client,
+        &format!("/v1/affinity-groups/{}", group.identity.id),
+    )
+    .await;
+    assert_eq!(fetched.identity.name.as_str(), group_name);
+
+    let groups = objects_list_page_authz::<AffinityGroup>(
+        client,
+        &get_affinity_groups_url(),
+    )
+    .await
+    .items;
+    assert_eq!(groups.len(), 1);
+    assert_eq!(groups[0].identity.id, group.identity.id);
+
+    // Names are unique within a project.
+    let error: HttpErrorResponseBody = NexusRequest::new(
+        RequestBuilder::new(client, Method::POST, &get_affinity_groups_url())
+            .body(Some(&params::AffinityGroupCreate {
+                identity: IdentityMetadataCreateParams {
+                    name: group_name.parse().unwrap(),
+                    description: String::from("a duplicate"),
+                },
+                kind: AffinityGroupKind::Affinity,
+                policy: AffinityPolicy::Soft,
+            }))
+            .expect_status(Some(StatusCode::BAD_REQUEST)),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap()
+    .parsed_body()
+    .unwrap();
+    assert_eq!(
+        error.message,
+        format!("already exists: affinity-group \"{group_name}\"")
+    );
+
+    NexusRequest::object_delete(client, &get_affinity_group_url(group_name))
+        .authn_as(AuthnMode::PrivilegedUser)
+        .execute()
+        .await
+        .unwrap();
+
+    NexusRequest::expect_failure(
+        client,
+        StatusCode::NOT_FOUND,
+        Method::GET,
+        &get_affinity_group_url(group_name),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+}
+
+#[nexus_test]
+async fn test_affinity_group_members(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+
+    populate_ip_pool(&client, "default", None).await;
+    create_project(client, PROJECT_NAME).await;
+
+    let group_name = "together";
+    create_affinity_group(
+        client,
+        PROJECT_NAME,
+        group_name,
+        AffinityGroupKind::Affinity,
+        AffinityPolicy::Soft,
+    )
+    .await;
+    let instance = create_instance(client, PROJECT_NAME, "inst").await;
+    assert!(members_list(client, group_name).await.is_empty());
+
+    // Adding an instance is idempotent.
+    let added = member_add(client, group_name, "inst", StatusCode::CREATED)
+        .await
+        .parsed_body::<Instance>()
+        .unwrap();
+    assert_eq!(added.identity.id, instance.identity.id);
+    member_add(client, group_name, "inst", StatusCode::CREATED).await;
+
+    let members = members_list(client, group_name).await;
+    assert_eq!(members.len(), 1);
+    assert_eq!(members[0].identity.id, instance.identity.id);
+
+    NexusRequest::object_delete(client, &get_member_url(group_name, "inst"))
+        .authn_as(AuthnMode::PrivilegedUser)
+        .execute()
+        .await
+        .unwrap();
+    assert!(members_list(client, group_name).await.is_empty());
+
+    // Instances may also join groups when they are created.
+    let instance =
+        create_instance_in_groups(client, "inst2", &[group_name]).await;
+    let members = members_list(client, group_name).await;
+    assert_eq!(members.len(), 1);
+    assert_eq!(members[0].identity.id, instance.identity.id);
+}
+
+#[nexus_test]
+async fn test_affinity_group_member_add_violates_hard_policy(
+    cptestctx: &ControlPlaneTestContext,
+) {
+    let client = &cptestctx.external_client;
+
+    populate_ip_pool(&client, "default", None).await;
+    create_project(client, PROJECT_NAME).await;
+
+    // With only one sled, two running instances are necessarily colocated,
+    // so they can't both be members of a hard anti-affinity group.
+    let group_name = "apart";
+    create_affinity_group(
+        client,
+        PROJECT_NAME,
+        group_name,
+        AffinityGroupKind::AntiAffinity,
+        AffinityPolicy::Hard,
+    )
+    .await;
+    create_instance_in_groups(client, "inst1", &[group_name]).await;
+    let instance = create_instance(client, PROJECT_NAME, "inst2").await;
+
+    let error =
+        member_add(client, group_name, "inst2", StatusCode::BAD_REQUEST)
+            .await
+            .parsed_body::<HttpErrorResponseBody>()
+            .unwrap();
+    assert_eq!(
+        error.message,
+        format!(
+            "the placement of instance \"{}\" violates the hard policy of \
+            affinity group \"{group_name}\"",
+            instance.identity.id
+        )
+    );
+    assert_eq!(members_list(client, group_name).await.len(), 1);
+}
+
+#[nexus_test]
+async fn test_anti_affinity_group_ignores_stopped_members(
+    cptestctx: &ControlPlaneTestContext,
+) {
+    let client = &cptestctx.external_client;
+    let nexus = &cptestctx.server.apictx().nexus;
+
+    populate_ip_pool(&client, "default", None).await;
+    create_project(client, PROJECT_NAME).await;
+
+    let group_name = "apart";
+    create_affinity_group(
+        client,
+        PROJECT_NAME,
+        group_name,
+        AffinityGroupKind::AntiAffinity,
+        AffinityPolicy::Hard,
+    )
+    .await;
+
+    // Stop the only member of the group.
+    let first = create_instance_in_groups(client, "inst1", &[group_name]).await;
+    instance_simulate(nexus, &first.identity.id).await;
+    instance_post(client, "inst1", "stop", StatusCode::ACCEPTED).await;
+    instance_simulate(nexus, &first.identity.id).await;
+
+    // A stopped instance doesn't hold its sled, so another member can be
+    // placed on the only sled there is.
+    let second =
+        create_instance_in_groups(client, "inst2", &[group_name]).await;
+    let first_sled = nexus.instance_sled_id(&first.identity.id).await.unwrap();
+    let second_sled =
+        nexus.instance_sled_id(&second.identity.id).await.unwrap();
+    assert_eq!(first_sled, second_sled);
+    assert_eq!(members_list(client, group_name).await.len(), 2);
+
+    // The policy is checked again when the stopped member is started.
+    let error =
+        instance_post(client, "inst1", "start", StatusCode::BAD_REQUEST)
+            .await
+            .parsed_body::<HttpErrorResponseBody>()
+            .unwrap();
+    assert_eq!(
+        error.message,
+        format!(
+            "starting the instance on sled {} would violate the hard policy \
+            of one of its affinity groups",
+            first_sled
+        )
+    );
+}
+
+#[nexus_test]
+async fn test_anti_affinity_group_placement(
+    cptestctx: &ControlPlaneTestContext,
+) {
+    let client = &cptestctx.external_client;
+    let nexus = &cptestctx.server.apictx().nexus;
+
+    // Add a second sled, so that two instances can be placed apart.
+    let other_sled_id = Uuid::new_v4();
+    let _other_sa = start_sled_agent(
+        cptestctx.logctx.log.new(o!("sled_id" => other_sled_id.to_string())),
+        cptestctx.server.get_http_server_internal_address().await,
+        other_sled_id,
+        &Utf8Path::new("/should/be/unused"),
+        sim::SimMode::Explicit,
+    )
+    .await
+    .unwrap();
+
+    populate_ip_pool(&client, "default", None).await;
+    create_project(client, PROJECT_NAME).await;
+
+    let hard_name = "hard-apart";
+    create_affinity_group(
+        client,
+        PROJECT_NAME,
+        hard_name,
+        AffinityGroupKind::AntiAffinity,
+        AffinityPolicy::Hard,
+    )
+    .await;
+    let soft_name = "soft-apart";
+    create_affinity_group(
+        client,
+        PROJECT_NAME,
+        soft_name,
+        AffinityGroupKind::AntiAffinity,
+        AffinityPolicy::Soft,
+    )
+    .await;
+
+    // Members of the hard group are always placed on different sleds.
+    let first =
+        create_instance_in_groups(client, "inst1", &[hard_name, soft_name])
+            .await;
+    let second =
+        create_instance_in_groups(client, "inst2", &[hard_name, soft_name])
+            .await;
+    let first_sled = nexus.instance_sled_id(&first.identity.id).await.unwrap();
+    let second_sled =
+        nexus.instance_sled_id(&second.identity.id).await.unwrap();
+    assert_ne!(first_sled, second_sled);
+
+    // Every sled now hosts a member of the hard group, so there's nowhere
+    // to put a third.
+    NexusRequest::new(
+        RequestBuilder::new(
+            client,
+            Method::POST,
+            &format!("/v1/instances?project={PROJECT_NAME}"),
+        )
+        .body(Some(&instance_create_params("inst3", &[hard_name])))
+        .expect_status(Some(StatusCode::SERVICE_UNAVAILABLE)),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+
+    // A soft policy is only a preference, so a third member can be placed
+    // alongside one of the others.
+    create_instance_in_groups(client, "inst4", &[soft_name]).await;
+    assert_eq!(members_list(client, soft_name).await.len(), 3);
+    assert_eq!(members_list(client, hard_name).await.len(), 2);
+}
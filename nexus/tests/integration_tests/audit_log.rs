@@ -0,0 +1,186 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Tests for the audit log
+
+use chrono::SecondsFormat;
+use chrono::Utc;
+use dropshot::test_util::ClientTestContext;
+use http::Method;
+use http::StatusCode;
+use nexus_test_utils::http_testing::AuthnMode;
+use nexus_test_utils::http_testing::NexusRequest;
+use nexus_test_utils::http_testing::RequestBuilder;
+use nexus_test_utils::resource_helpers::create_project;
+use nexus_test_utils_macros::nexus_test;
+use omicron_common::api::external::IdentityMetadataCreateParams;
+use omicron_nexus::authn::{USER_TEST_PRIVILEGED, USER_TEST_UNPRIVILEGED};
+use omicron_nexus::db::fixed_data::silo::DEFAULT_SILO;
+use omicron_nexus::db::identity::{Asset, Resource};
+use omicron_nexus::external_api::params;
+use omicron_nexus::external_api::views::AuditLogEntry;
+use uuid::Uuid;
+
+type ControlPlaneTestContext =
+    nexus_test_utils::ControlPlaneTestContext<omicron_nexus::Server>;
+
+const AUDIT_LOG_URL: &str = "/v1/system/audit-log";
+
+async fn audit_log_list(
+    client: &ClientTestContext,
+    query: &str,
+) -> Vec<AuditLogEntry> {
+    NexusRequest::iter_collection_authn(client, AUDIT_LOG_URL, query, Some(1))
+        .await
+        .expect("failed to list audit log")
+        .all_items
+}
+
+fn project_create_params(name: &str) -> params::ProjectCreate {
+    params::ProjectCreate {
+        identity: IdentityMetadataCreateParams {
+            name: name.parse().unwrap(),
+            description: String::from("audited"),
+        },
+        labels: Default::default(),
+    }
+}
+
+#[nexus_test]
+async fn test_audit_log(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+    let t0 = Utc::now().to_rfc3339_opts(SecondsFormat::Micros, true);
+
+    // Make a few requests that modify the system, one of which fails.
+    create_project(client, "audited-project").await;
+    NexusRequest::expect_failure_with_body(
+        client,
+        StatusCode::BAD_REQUEST,
+        Method::POST,
+        "/v1/projects",
+        &project_create_params("audited-project"),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+    NexusRequest::expect_failure_with_body(
+        client,
+        StatusCode::FORBIDDEN,
+        Method::POST,
+        "/v1/projects",
+        &project_create_params("unprivileged-project"),
+    )
+    .authn_as(AuthnMode::UnprivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+    let subnet_url =
+        "/v1/vpc-subnets/default?project=audited-project&vpc=default";
+    NexusRequest::object_delete(client, subnet_url)
+        .authn_as(AuthnMode::PrivilegedUser)
+        .execute()
+        .await
+        .unwrap();
+
+    // Requests that don't modify anything and requests that aren't
+    // authenticated are not recorded.
+    NexusRequest::object_get(client, "/v1/projects")
+        .authn_as(AuthnMode::PrivilegedUser)
+        .execute()
+        .await
+        .unwrap();
+    RequestBuilder::new(client, Method::POST, "/v1/projects")
+        .body(Some(&project_create_params("anonymous-project")))
+        .expect_status(Some(StatusCode::UNAUTHORIZED))
+        .execute()
+        .await
+        .unwrap();
+
+    let entries = audit_log_list(client, &format!("start_time={}", t0)).await;
+    let summary: Vec<_> = entries
+        .iter()
+        .map(|e| {
+            (
+                e.actor_id,
+                e.operation_id.as_str(),
+                e.http_method.as_str(),
+                e.resource_path.as_str(),
+                e.result_status,
+            )
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            (
+                USER_TEST_PRIVILEGED.id(),
+                "project_create",
+                "POST",
+                "/v1/projects",
+                201
+            ),
+            (
+                USER_TEST_PRIVILEGED.id(),
+                "project_create",
+                "POST",
+                "/v1/projects",
+                400
+            ),
+            (
+                USER_TEST_UNPRIVILEGED.id(),
+                "project_create",
+                "POST",
+                "/v1/projects",
+                403
+            ),
+            (
+                USER_TEST_PRIVILEGED.id(),
+                "vpc_subnet_delete",
+                "DELETE",
+                subnet_url,
+                204
+            ),
+        ]
+    );
+    for entry in &entries {
+        assert_eq!(entry.actor_silo_id, Some(DEFAULT_SILO.id()));
+        assert!(entry.time_started <= entry.time_completed);
+    }
+
+    // Filter by actor.
+    let entries = audit_log_list(
+        client,
+        &format!("start_time={}&actor_id={}", t0, USER_TEST_UNPRIVILEGED.id()),
+    )
+    .await;
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].actor_id, USER_TEST_UNPRIVILEGED.id());
+    let entries = audit_log_list(
+        client,
+        &format!("start_time={}&actor_id={}", t0, Uuid::new_v4()),
+    )
+    .await;
+    assert!(entries.is_empty());
+
+    // Filter by time range.
+    let entries =
+        audit_log_list(client, &format!("start_time={}&end_time={}", t0, t0))
+            .await;
+    assert!(entries.is_empty());
+
+    // The time range must make sense.
+    let t1 = (Utc::now() - chrono::Duration::hours(1))
+        .to_rfc3339_opts(SecondsFormat::Micros, true);
+    NexusRequest::expect_failure(
+        client,
+        StatusCode::BAD_REQUEST,
+        Method::GET,
+        &format!("{}?start_time={}&end_time={}", AUDIT_LOG_URL, t0, t1),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+}
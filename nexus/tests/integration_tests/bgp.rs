@@ -0,0 +1,308 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Integration tests for BGP configuration
+
+use dropshot::HttpErrorResponseBody;
+use http::method::Method;
+use http::StatusCode;
+use nexus_test_utils::http_testing::{AuthnMode, NexusRequest, RequestBuilder};
+use nexus_test_utils::resource_helpers::objects_list_page_authz;
+use nexus_test_utils_macros::nexus_test;
+use omicron_common::api::external::{
+    AddressLotKind, BgpAnnounceSet, BgpAnnouncement, BgpConfig,
+    IdentityMetadataCreateParams, NameOrId, SwitchPortSettingsView,
+};
+use omicron_nexus::external_api::params::{
+    Address, AddressConfig, AddressLotBlockCreate, AddressLotCreate,
+    BgpAnnouncementCreate, BgpPeerConfig, CreateBgpAnnounceSet,
+    CreateBgpConfig, SwitchPortApplySettings, SwitchPortSettingsCreate,
+};
+use omicron_nexus::external_api::views::Rack;
+
+type ControlPlaneTestContext =
+    nexus_test_utils::ControlPlaneTestContext<omicron_nexus::Server>;
+
+const BGP_CONFIG_URL: &str = "/v1/system/networking/bgp";
+const BGP_ANNOUNCE_URL: &str = "/v1/system/networking/bgp-announce";
+
+async fn create_lot(client: &dropshot::test_util::ClientTestContext) {
+    let lot_params = AddressLotCreate {
+        identity: IdentityMetadataCreateParams {
+            name: "parkinglot".parse().unwrap(),
+            description: "an address parking lot".into(),
+        },
+        kind: AddressLotKind::Infra,
+        blocks: vec![AddressLotBlockCreate {
+            first_address: "203.0.113.0".parse().unwrap(),
+            last_address: "203.0.113.255".parse().unwrap(),
+        }],
+    };
+
+    NexusRequest::objects_post(
+        client,
+        "/v1/system/networking/address-lot",
+        &lot_params,
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+}
+
+async fn create_bgp_config(
+    client: &dropshot::test_util::ClientTestContext,
+) -> BgpConfig {
+    let params = CreateBgpConfig {
+        identity: IdentityMetadataCreateParams {
+            name: "as47".parse().unwrap(),
+            description: "BGP config for AS47".into(),
+        },
+        asn: 47,
+        vrf: None,
+    };
+
+    NexusRequest::objects_post(client, BGP_CONFIG_URL, &params)
+        .authn_as(AuthnMode::PrivilegedUser)
+        .execute()
+        .await
+        .unwrap()
+        .parsed_body()
+        .unwrap()
+}
+
+async fn create_announce_set(
+    client: &dropshot::test_util::ClientTestContext,
+) -> BgpAnnounceSet {
+    let params = CreateBgpAnnounceSet {
+        identity: IdentityMetadataCreateParams {
+            name: "a-side".parse().unwrap(),
+            description: "a-side BGP announce set".into(),
+        },
+        announcement: vec![BgpAnnouncementCreate {
+            address_lot_block: NameOrId::Name("parkinglot".parse().unwrap()),
+            network: "203.0.113.0/24".parse().unwrap(),
+        }],
+    };
+
+    NexusRequest::objects_post(client, BGP_ANNOUNCE_URL, &params)
+        .authn_as(AuthnMode::PrivilegedUser)
+        .execute()
+        .await
+        .unwrap()
+        .parsed_body()
+        .unwrap()
+}
+
+#[nexus_test]
+async fn test_bgp_config_crud(ctx: &ControlPlaneTestContext) {
+    let client = &ctx.external_client;
+
+    let config = create_bgp_config(client).await;
+    assert_eq!(config.identity.name.as_str(), "as47");
+    assert_eq!(config.asn, 47);
+    assert_eq!(config.vrf, None);
+
+    let configs =
+        objects_list_page_authz::<BgpConfig>(client, BGP_CONFIG_URL).await;
+    assert_eq!(configs.items.len(), 1);
+    assert_eq!(configs.items[0].identity.id, config.identity.id);
+
+    NexusRequest::object_delete(
+        client,
+        &format!("{BGP_CONFIG_URL}?name_or_id=as47"),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+
+    let configs =
+        objects_list_page_authz::<BgpConfig>(client, BGP_CONFIG_URL).await;
+    assert!(configs.items.is_empty());
+
+    // The name may be reused once the configuration is deleted.
+    create_bgp_config(client).await;
+}
+
+#[nexus_test]
+async fn test_bgp_announce_set_crud(ctx: &ControlPlaneTestContext) {
+    let client = &ctx.external_client;
+    create_lot(client).await;
+
+    let announce_set = create_announce_set(client).await;
+    assert_eq!(announce_set.identity.name.as_str(), "a-side");
+
+    let announcements: Vec<BgpAnnouncement> = NexusRequest::object_get(
+        client,
+        &format!("{BGP_ANNOUNCE_URL}/a-side/announcement"),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap()
+    .parsed_body()
+    .unwrap();
+    assert_eq!(announcements.len(), 1);
+    assert_eq!(announcements[0].announce_set_id, announce_set.identity.id);
+    assert_eq!(announcements[0].network, "203.0.113.0/24".parse().unwrap());
+
+    // Announced networks must be drawn from the address lot.
+    let error: HttpErrorResponseBody = NexusRequest::expect_failure_with_body(
+        client,
+        StatusCode::BAD_REQUEST,
+        Method::POST,
+        BGP_ANNOUNCE_URL,
+        &CreateBgpAnnounceSet {
+            identity: IdentityMetadataCreateParams {
+                name: "b-side".parse().unwrap(),
+                description: "b-side BGP announce set".into(),
+            },
+            announcement: vec![BgpAnnouncementCreate {
+                address_lot_block: NameOrId::Name(
+                    "parkinglot".parse().unwrap(),
+                ),
+                network: "198.51.100.0/24".parse().unwrap(),
+            }],
+        },
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap()
+    .parsed_body()
+    .unwrap();
+    assert_eq!(
+        error.message,
+        "announced network 198.51.100.0/24 is not in address lot"
+    );
+
+    let sets =
+        objects_list_page_authz::<BgpAnnounceSet>(client, BGP_ANNOUNCE_URL)
+            .await;
+    assert_eq!(sets.items.len(), 1);
+
+    NexusRequest::object_delete(
+        client,
+        &format!("{BGP_ANNOUNCE_URL}?name_or_id=a-side"),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+
+    let sets =
+        objects_list_page_authz::<BgpAnnounceSet>(client, BGP_ANNOUNCE_URL)
+            .await;
+    assert!(sets.items.is_empty());
+}
+
+#[nexus_test]
+async fn test_bgp_peer_port_settings_apply(ctx: &ControlPlaneTestContext) {
+    let client = &ctx.external_client;
+    create_lot(client).await;
+    create_bgp_config(client).await;
+    create_announce_set(client).await;
+
+    let mut settings =
+        SwitchPortSettingsCreate::new(IdentityMetadataCreateParams {
+            name: "portofino".parse().unwrap(),
+            description: "just a port".into(),
+        });
+    settings.addresses.insert(
+        "phy0".into(),
+        AddressConfig {
+            addresses: vec![Address {
+                address: "203.0.113.10/24".parse().unwrap(),
+                address_lot: NameOrId::Name("parkinglot".parse().unwrap()),
+            }],
+        },
+    );
+    settings.bgp_peers.insert(
+        "phy0".into(),
+        BgpPeerConfig {
+            bgp_announce_set: NameOrId::Name("a-side".parse().unwrap()),
+            bgp_config: NameOrId::Name("as47".parse().unwrap()),
+            interface_name: "phy0".into(),
+            addr: "203.0.113.1".parse().unwrap(),
+        },
+    );
+
+    let created: SwitchPortSettingsView = NexusRequest::objects_post(
+        client,
+        "/v1/system/networking/switch-port-settings",
+        &settings,
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap()
+    .parsed_body()
+    .unwrap();
+    assert_eq!(created.bgp_peers.len(), 1);
+    assert_eq!(created.bgp_peers[0].addr, "203.0.113.1".parse().unwrap());
+    // Nexus can't configure peers on the switches yet, and says so.
+    assert!(!created.bgp_peers[0].applied);
+
+    // Configuration referenced by port settings may not be deleted.
+    let error: HttpErrorResponseBody = NexusRequest::expect_failure(
+        client,
+        StatusCode::BAD_REQUEST,
+        Method::DELETE,
+        &format!("{BGP_CONFIG_URL}?name_or_id=as47"),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap()
+    .parsed_body()
+    .unwrap();
+    assert_eq!(error.message, "BGP config is in use by switch port settings");
+
+    let error: HttpErrorResponseBody = NexusRequest::expect_failure(
+        client,
+        StatusCode::BAD_REQUEST,
+        Method::DELETE,
+        &format!("{BGP_ANNOUNCE_URL}?name_or_id=a-side"),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap()
+    .parsed_body()
+    .unwrap();
+    assert_eq!(
+        error.message,
+        "BGP announce set is in use by switch port settings"
+    );
+
+    // Apply the settings, peer included, to a switch port.
+    let racks: Vec<Rack> = NexusRequest::iter_collection_authn(
+        client,
+        "/v1/system/hardware/racks",
+        "",
+        None,
+    )
+    .await
+    .expect("failed to list racks")
+    .all_items;
+    let rack_id = racks[0].identity.id;
+
+    let apply_settings = SwitchPortApplySettings {
+        port_settings: NameOrId::Name("portofino".parse().unwrap()),
+    };
+    NexusRequest::new(
+        RequestBuilder::new(
+            client,
+            Method::POST,
+            &format!("/v1/system/hardware/switch-port/qsfp0/settings?rack_id={rack_id}&switch_location=switch0"),
+        )
+        .body(Some(&apply_settings))
+        .expect_status(Some(StatusCode::NO_CONTENT)),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+}
@@ -2,13 +2,22 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-use nexus_test_utils::http_testing::{AuthnMode, NexusRequest, RequestBuilder};
+use chrono::{Duration, Utc};
+use dropshot::test_util::ClientTestContext;
+use nexus_test_utils::http_testing::{
+    AuthnMode, NexusRequest, RequestBuilder, TestResponse,
+};
+use nexus_test_utils::resource_helpers::objects_list_page_authz;
 use nexus_test_utils_macros::nexus_test;
+use omicron_nexus::authn::USER_TEST_UNPRIVILEGED;
+use omicron_nexus::db::identity::Asset;
 use omicron_nexus::external_api::device_auth::{
     DeviceAccessTokenRequest, DeviceAuthRequest, DeviceAuthVerify,
 };
+use omicron_nexus::external_api::params;
 use omicron_nexus::external_api::views::{
-    DeviceAccessTokenGrant, DeviceAccessTokenType, DeviceAuthResponse,
+    self, DeviceAccessTokenCreated, DeviceAccessTokenGrant,
+    DeviceAccessTokenType, DeviceAuthResponse,
 };
 
 use http::{header, method::Method, StatusCode};
@@ -147,3 +156,133 @@ async fn test_device_auth_flow(cptestctx: &ControlPlaneTestContext) {
     assert_eq!(token.access_token.len(), 52);
     assert!(token.access_token.starts_with("oxide-token-"));
 }
+
+const TOKENS_URL: &str = "/v1/me/tokens";
+
+async fn token_create(
+    testctx: &ClientTestContext,
+    name: &str,
+    time_expires: Option<chrono::DateTime<Utc>>,
+    expected_status: StatusCode,
+) -> TestResponse {
+    NexusRequest::new(
+        RequestBuilder::new(testctx, Method::POST, TOKENS_URL)
+            .body(Some(&params::DeviceAccessTokenCreate {
+                name: name.parse().unwrap(),
+                time_expires,
+            }))
+            .expect_status(Some(expected_status)),
+    )
+    .authn_as(AuthnMode::UnprivilegedUser)
+    .execute()
+    .await
+    .expect("unexpected response to creating access token")
+}
+
+/// Fetches the current user using the given bearer token
+async fn whoami(
+    testctx: &ClientTestContext,
+    access_token: &str,
+    expected_status: StatusCode,
+) -> TestResponse {
+    RequestBuilder::new(testctx, Method::GET, "/v1/me")
+        .header(header::AUTHORIZATION, format!("Bearer {}", access_token))
+        .expect_status(Some(expected_status))
+        .execute()
+        .await
+        .expect("unexpected response to request with access token")
+}
+
+#[nexus_test]
+async fn test_user_access_tokens(cptestctx: &ControlPlaneTestContext) {
+    let testctx = &cptestctx.external_client;
+
+    // Create a token and use it to authenticate as the user who created it.
+    let created: DeviceAccessTokenCreated =
+        token_create(testctx, "ci", None, StatusCode::CREATED)
+            .await
+            .parsed_body()
+            .unwrap();
+    assert!(created.access_token.starts_with("oxide-token-"));
+    assert_eq!(created.token.name, Some("ci".parse().unwrap()));
+    assert_eq!(created.token.time_expires, None);
+    assert_eq!(created.token.time_last_used, None);
+    let me: views::User =
+        whoami(testctx, &created.access_token, StatusCode::OK)
+            .await
+            .parsed_body()
+            .unwrap();
+    assert_eq!(me.id, USER_TEST_UNPRIVILEGED.id());
+
+    // The token shows up in the user's list of tokens (without the secret),
+    // along with when it was last used.  Other users can't see it.
+    let tokens = NexusRequest::object_get(testctx, TOKENS_URL)
+        .authn_as(AuthnMode::UnprivilegedUser)
+        .execute()
+        .await
+        .unwrap()
+        .parsed_body::<dropshot::ResultsPage<views::DeviceAccessToken>>()
+        .unwrap()
+        .items;
+    assert_eq!(tokens.len(), 1);
+    assert_eq!(tokens[0].id, created.token.id);
+    assert!(tokens[0].time_last_used.is_some());
+    assert!(objects_list_page_authz::<views::DeviceAccessToken>(
+        testctx, TOKENS_URL
+    )
+    .await
+    .items
+    .is_empty());
+
+    // Using the token again right away doesn't change when it was last used,
+    // which is only updated about once a minute.
+    let last_used = tokens[0].time_last_used;
+    whoami(testctx, &created.access_token, StatusCode::OK).await;
+    let tokens = NexusRequest::object_get(testctx, TOKENS_URL)
+        .authn_as(AuthnMode::UnprivilegedUser)
+        .execute()
+        .await
+        .unwrap()
+        .parsed_body::<dropshot::ResultsPage<views::DeviceAccessToken>>()
+        .unwrap()
+        .items;
+    assert_eq!(tokens[0].time_last_used, last_used);
+
+    // Names must be unique, and expiration times must be in the future.
+    token_create(testctx, "ci", None, StatusCode::BAD_REQUEST).await;
+    let past = Utc::now() - Duration::seconds(1);
+    token_create(testctx, "expired", Some(past), StatusCode::BAD_REQUEST).await;
+
+    // A token can't be used once it expires.
+    let soon = Utc::now() + Duration::seconds(1);
+    let expiring: DeviceAccessTokenCreated =
+        token_create(testctx, "expiring", Some(soon), StatusCode::CREATED)
+            .await
+            .parsed_body()
+            .unwrap();
+    assert_eq!(
+        expiring.token.time_expires.map(|t| t.timestamp_micros()),
+        Some(soon.timestamp_micros())
+    );
+    tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+    whoami(testctx, &expiring.access_token, StatusCode::UNAUTHORIZED).await;
+
+    // Nor can it be used once it's deleted.
+    let token_url = format!("{}/{}", TOKENS_URL, created.token.id);
+    NexusRequest::object_delete(testctx, &token_url)
+        .authn_as(AuthnMode::UnprivilegedUser)
+        .execute()
+        .await
+        .unwrap();
+    whoami(testctx, &created.access_token, StatusCode::UNAUTHORIZED).await;
+    NexusRequest::expect_failure(
+        testctx,
+        StatusCode::NOT_FOUND,
+        Method::DELETE,
+        &token_url,
+    )
+    .authn_as(AuthnMode::UnprivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+}
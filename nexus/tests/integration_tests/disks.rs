@@ -36,7 +36,7 @@ use omicron_nexus::app::{MAX_DISK_SIZE_BYTES, MIN_DISK_SIZE_BYTES};
 use omicron_nexus::db::fixed_data::{silo::SILO_ID, FLEET_ID};
 use omicron_nexus::db::lookup::LookupPath;
 use omicron_nexus::TestInterfaces as _;
-use omicron_nexus::{external_api::params, Nexus};
+use omicron_nexus::{external_api::params, external_api::views, Nexus};
 use oximeter::types::Datum;
 use oximeter::types::Measurement;
 use sled_agent_client::TestInterfaces as _;
@@ -59,6 +59,10 @@ fn get_disk_url(disk_name: &str) -> String {
     format!("/v1/disks/{disk_name}?project={}", PROJECT_NAME)
 }
 
+fn get_disk_resize_url(disk_name: &str) -> String {
+    format!("/v1/disks/{disk_name}/resize?project={}", PROJECT_NAME)
+}
+
 fn get_instance_disks_url(instance_name: &str) -> String {
     format!("/v1/instances/{instance_name}/disks?project={}", PROJECT_NAME)
 }
@@ -324,6 +328,7 @@ async fn test_disk_create_disk_that_already_exists_fails(
             block_size: params::BlockSize::try_from(512).unwrap(),
         },
         size: ByteCount::from_gibibytes_u32(1),
+        labels: Default::default(),
     };
     let _ = create_disk(&client, PROJECT_NAME, DISK_NAME).await;
     let disk_url = get_disk_url(DISK_NAME);
@@ -711,6 +716,7 @@ async fn test_disk_region_creation_failure(
             block_size: params::BlockSize::try_from(512).unwrap(),
         },
         size: disk_size,
+        labels: Default::default(),
     };
 
     // Unfortunately, the error message is only posted internally to the
@@ -791,6 +797,7 @@ async fn test_disk_invalid_block_size_rejected(
             block_size: params::BlockSize(1024),
         },
         size: disk_size,
+        labels: Default::default(),
     };
 
     NexusRequest::new(
@@ -834,6 +841,7 @@ async fn test_disk_reject_total_size_not_divisible_by_block_size(
             block_size: params::BlockSize::try_from(512).unwrap(),
         },
         size: disk_size,
+        labels: Default::default(),
     };
 
     NexusRequest::new(
@@ -868,6 +876,7 @@ async fn test_disk_reject_total_size_less_than_one_gibibyte(
             block_size: params::BlockSize::try_from(512).unwrap(),
         },
         size: disk_size,
+        labels: Default::default(),
     };
 
     let error = NexusRequest::new(
@@ -911,6 +920,7 @@ async fn test_disk_reject_total_size_greater_than_one_tebibyte(
             block_size: params::BlockSize::try_from(512).unwrap(),
         },
         size: disk_size,
+        labels: Default::default(),
     };
 
     let error = NexusRequest::new(
@@ -955,6 +965,7 @@ async fn test_disk_reject_total_size_not_divisible_by_min_disk_size(
             block_size: params::BlockSize::try_from(512).unwrap(),
         },
         size: disk_size,
+        labels: Default::default(),
     };
 
     let error = NexusRequest::new(
@@ -1009,6 +1020,7 @@ async fn test_disk_backed_by_multiple_region_sets(
             block_size: params::BlockSize::try_from(512).unwrap(),
         },
         size: disk_size,
+        labels: Default::default(),
     };
 
     NexusRequest::new(
@@ -1045,6 +1057,7 @@ async fn test_disk_too_big(cptestctx: &ControlPlaneTestContext) {
             block_size: params::BlockSize::try_from(512).unwrap(),
         },
         size: disk_size,
+        labels: Default::default(),
     };
 
     NexusRequest::new(
@@ -1132,6 +1145,7 @@ async fn test_disk_virtual_provisioning_collection(
             block_size: params::BlockSize::try_from(512).unwrap(),
         },
         size: disk_size,
+        labels: Default::default(),
     };
     NexusRequest::new(
         RequestBuilder::new(client, Method::POST, &disks_url)
@@ -1191,6 +1205,7 @@ async fn test_disk_virtual_provisioning_collection(
             block_size: params::BlockSize::try_from(512).unwrap(),
         },
         size: disk_size,
+        labels: Default::default(),
     };
     NexusRequest::new(
         RequestBuilder::new(client, Method::POST, &disks_url)
@@ -1306,6 +1321,7 @@ async fn test_disk_size_accounting(cptestctx: &ControlPlaneTestContext) {
             block_size: params::BlockSize::try_from(512).unwrap(),
         },
         size: disk_size,
+        labels: Default::default(),
     };
 
     NexusRequest::new(
@@ -1344,6 +1360,7 @@ async fn test_disk_size_accounting(cptestctx: &ControlPlaneTestContext) {
             block_size: params::BlockSize::try_from(512).unwrap(),
         },
         size: disk_size,
+        labels: Default::default(),
     };
 
     NexusRequest::new(
@@ -1404,6 +1421,7 @@ async fn test_disk_size_accounting(cptestctx: &ControlPlaneTestContext) {
             block_size: params::BlockSize::try_from(512).unwrap(),
         },
         size: disk_size,
+        labels: Default::default(),
     };
 
     NexusRequest::new(
@@ -1462,6 +1480,7 @@ async fn test_multiple_disks_multiple_zpools(
             block_size: params::BlockSize::try_from(512).unwrap(),
         },
         size: disk_size,
+        labels: Default::default(),
     };
 
     NexusRequest::new(
@@ -1485,6 +1504,7 @@ async fn test_multiple_disks_multiple_zpools(
             block_size: params::BlockSize::try_from(512).unwrap(),
         },
         size: disk_size,
+        labels: Default::default(),
     };
 
     NexusRequest::new(
@@ -1671,6 +1691,7 @@ async fn test_disk_create_for_importing(cptestctx: &ControlPlaneTestContext) {
             block_size: params::BlockSize::try_from(512).unwrap(),
         },
         size: ByteCount::from_gibibytes_u32(1),
+        labels: Default::default(),
     };
 
     NexusRequest::new(
@@ -1696,6 +1717,152 @@ async fn test_disk_create_for_importing(cptestctx: &ControlPlaneTestContext) {
     disks_eq(&disks[0], &disk);
 }
 
+#[nexus_test]
+async fn test_disk_resize(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+    let nexus = &cptestctx.server.apictx().nexus;
+    let datastore = nexus.datastore();
+    DiskTest::new(&cptestctx).await;
+    let project_id = create_org_and_project(client).await;
+    let opctx =
+        OpContext::for_tests(cptestctx.logctx.log.new(o!()), datastore.clone());
+
+    // Assert default is still 10 GiB
+    assert_eq!(10, DiskTest::DEFAULT_ZPOOL_SIZE_GIB);
+
+    create_disk(client, PROJECT_NAME, DISK_NAME).await;
+    let disk_resize_url = get_disk_resize_url(DISK_NAME);
+
+    // Grow the disk from 1 GiB to 2 GiB.
+    let disk = disk_resize(client, &disk_resize_url, 2, StatusCode::OK)
+        .await
+        .parsed_body::<Disk>()
+        .unwrap();
+    assert_eq!(disk.size, ByteCount::from_gibibytes_u32(2));
+    assert_eq!(disk.state, DiskState::Detached);
+    disks_eq(&disk, &disk_get(client, &get_disk_url(DISK_NAME)).await);
+
+    // The disk should be backed by a new volume, whose regions are the new
+    // size, and the extra space should have been charged to the project.
+    let (.., db_disk) = LookupPath::new(&opctx, &datastore)
+        .disk_id(disk.identity.id)
+        .fetch()
+        .await
+        .unwrap();
+    let datasets_and_regions =
+        datastore.get_allocated_regions(db_disk.volume_id).await.unwrap();
+    assert!(!datasets_and_regions.is_empty());
+    for (_, region) in &datasets_and_regions {
+        assert_eq!(
+            region.block_size().to_bytes()
+                * region.blocks_per_extent()
+                * region.extent_count(),
+            ByteCount::from_gibibytes_u32(2).to_bytes(),
+        );
+    }
+    let virtual_provisioning_collection = datastore
+        .virtual_provisioning_collection_get(&opctx, project_id)
+        .await
+        .unwrap();
+    assert_eq!(
+        virtual_provisioning_collection
+            .virtual_disk_bytes_provisioned
+            .to_bytes(),
+        ByteCount::from_gibibytes_u32(2).to_bytes(),
+    );
+
+    // The snapshot taken to resize the disk is gone again.
+    let snapshots = objects_list_page_authz::<views::Snapshot>(
+        client,
+        &format!("/v1/snapshots?project={}", PROJECT_NAME),
+    )
+    .await
+    .items;
+    assert!(snapshots.is_empty());
+
+    // Resizing the disk to its current size is a no-op.
+    disk_resize(client, &disk_resize_url, 2, StatusCode::OK).await;
+    let virtual_provisioning_collection = datastore
+        .virtual_provisioning_collection_get(&opctx, project_id)
+        .await
+        .unwrap();
+    assert_eq!(
+        virtual_provisioning_collection
+            .virtual_disk_bytes_provisioned
+            .to_bytes(),
+        ByteCount::from_gibibytes_u32(2).to_bytes(),
+    );
+
+    // Disks cannot be shrunk.
+    let error =
+        disk_resize(client, &disk_resize_url, 1, StatusCode::BAD_REQUEST)
+            .await
+            .parsed_body::<HttpErrorResponseBody>()
+            .unwrap();
+    assert_eq!(error.message, "disks can only be grown, not shrunk");
+
+    // Disks cannot be grown past the space left on the zpools backing them.
+    disk_resize(
+        client,
+        &disk_resize_url,
+        2 * DiskTest::DEFAULT_ZPOOL_SIZE_GIB,
+        StatusCode::SERVICE_UNAVAILABLE,
+    )
+    .await;
+    let disk = disk_get(client, &get_disk_url(DISK_NAME)).await;
+    assert_eq!(disk.size, ByteCount::from_gibibytes_u32(2));
+    let virtual_provisioning_collection = datastore
+        .virtual_provisioning_collection_get(&opctx, project_id)
+        .await
+        .unwrap();
+    assert_eq!(
+        virtual_provisioning_collection
+            .virtual_disk_bytes_provisioned
+            .to_bytes(),
+        ByteCount::from_gibibytes_u32(2).to_bytes(),
+    );
+}
+
+#[nexus_test]
+async fn test_disk_resize_attached(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+    DiskTest::new(&cptestctx).await;
+    create_org_and_project(client).await;
+
+    // Create a disk and attach it to a stopped instance.
+    create_disk(client, PROJECT_NAME, DISK_NAME).await;
+    let instance = create_instance(&client, PROJECT_NAME, INSTANCE_NAME).await;
+    set_instance_state(&client, INSTANCE_NAME, "stop").await;
+    instance_simulate(&cptestctx.server.apictx().nexus, &instance.identity.id)
+        .await;
+    let disk = disk_post(
+        client,
+        &get_disk_attach_url(&instance.identity.id.into()),
+        DISK_NAME.parse().unwrap(),
+    )
+    .await;
+    assert_eq!(disk.state, DiskState::Attached(instance.identity.id));
+
+    // Attached disks can't be resized yet, even if the instance is stopped.
+    let error = disk_resize(
+        client,
+        &get_disk_resize_url(DISK_NAME),
+        2,
+        StatusCode::BAD_REQUEST,
+    )
+    .await
+    .parsed_body::<HttpErrorResponseBody>()
+    .unwrap();
+    assert_eq!(
+        error.message,
+        "resizing a disk attached to an instance is not supported: detach the \
+        disk first"
+    );
+    let disk = disk_get(client, &get_disk_url(DISK_NAME)).await;
+    assert_eq!(disk.size, ByteCount::from_gibibytes_u32(1));
+    assert_eq!(disk.state, DiskState::Attached(instance.identity.id));
+}
+
 async fn disk_get(client: &ClientTestContext, disk_url: &str) -> Disk {
     NexusRequest::object_get(client, disk_url)
         .authn_as(AuthnMode::PrivilegedUser)
@@ -1731,6 +1898,25 @@ async fn disk_post(
     .unwrap()
 }
 
+async fn disk_resize(
+    client: &ClientTestContext,
+    url: &str,
+    size_gib: u32,
+    status: StatusCode,
+) -> dropshot::test_util::TestResponse {
+    NexusRequest::new(
+        RequestBuilder::new(client, Method::POST, url)
+            .body(Some(&params::DiskResize {
+                size: ByteCount::from_gibibytes_u32(size_gib),
+            }))
+            .expect_status(Some(status)),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap()
+}
+
 fn disks_eq(disk1: &Disk, disk2: &Disk) {
     identity_eq(&disk1.identity, &disk2.identity);
     assert_eq!(disk1.project_id, disk2.project_id);
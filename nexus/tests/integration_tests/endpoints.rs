@@ -22,6 +22,7 @@ use omicron_common::api::external::IdentityMetadataCreateParams;
 use omicron_common::api::external::IdentityMetadataUpdateParams;
 use omicron_common::api::external::InstanceCpuCount;
 use omicron_common::api::external::Ipv4Net;
+use omicron_common::api::external::Labels;
 use omicron_common::api::external::Name;
 use omicron_common::api::external::NameOrId;
 use omicron_common::api::external::RouteDestination;
@@ -63,6 +64,8 @@ lazy_static! {
         format!("/v1/system/silos/{}", *DEMO_SILO_NAME);
     pub static ref DEMO_SILO_POLICY_URL: String =
         format!("/v1/system/silos/{}/policy", *DEMO_SILO_NAME);
+    pub static ref DEMO_SILO_QUOTAS_URL: String =
+        format!("/v1/system/silos/{}/quotas", *DEMO_SILO_NAME);
     pub static ref DEMO_SILO_CREATE: params::SiloCreate =
         params::SiloCreate {
             identity: IdentityMetadataCreateParams {
@@ -105,12 +108,17 @@ lazy_static! {
         format!("project={}", *DEMO_PROJECT_NAME);
     pub static ref DEMO_PROJECT_POLICY_URL: String =
         format!("/v1/projects/{}/policy", *DEMO_PROJECT_NAME);
+    pub static ref DEMO_PROJECT_QUOTAS_URL: String =
+        format!("/v1/projects/{}/quotas", *DEMO_PROJECT_NAME);
     pub static ref DEMO_PROJECT_URL_DISKS: String =
         format!("/v1/disks?project={}",  *DEMO_PROJECT_NAME);
     pub static ref DEMO_PROJECT_URL_IMAGES: String =
         format!("/v1/images?project={}", *DEMO_PROJECT_NAME);
     pub static ref DEMO_PROJECT_URL_INSTANCES: String = format!("/v1/instances?project={}", *DEMO_PROJECT_NAME);
     pub static ref DEMO_PROJECT_URL_SNAPSHOTS: String = format!("/v1/snapshots?project={}", *DEMO_PROJECT_NAME);
+    pub static ref DEMO_PROJECT_URL_FIPS: String = format!("/v1/floating-ips?project={}", *DEMO_PROJECT_NAME);
+    pub static ref DEMO_PROJECT_URL_AFFINITY_GROUPS: String = format!("/v1/affinity-groups?project={}", *DEMO_PROJECT_NAME);
+    pub static ref DEMO_PROJECT_URL_SNAPSHOT_SCHEDULES: String = format!("/v1/snapshot-schedules?project={}", *DEMO_PROJECT_NAME);
     pub static ref DEMO_PROJECT_URL_VPCS: String = format!("/v1/vpcs?project={}", *DEMO_PROJECT_NAME);
     pub static ref DEMO_PROJECT_CREATE: params::ProjectCreate =
         params::ProjectCreate {
@@ -118,6 +126,7 @@ lazy_static! {
                 name: DEMO_PROJECT_NAME.clone(),
                 description: String::from(""),
             },
+            labels: Default::default(),
         };
 
     // VPC used for testing
@@ -141,6 +150,7 @@ lazy_static! {
             },
             ipv6_prefix: None,
             dns_name: DEMO_VPC_NAME.clone(),
+            labels: Default::default(),
         };
 
     // VPC Subnet used for testing
@@ -175,6 +185,23 @@ lazy_static! {
             },
         };
 
+    // VPC Peering used for testing
+    pub static ref DEMO_VPC_URL_PEERINGS: String =
+        format!("/v1/vpc-peerings?{}", *DEMO_VPC_SELECTOR);
+    pub static ref DEMO_VPC_PEERING_NAME: Name =
+        "demo-vpc-peering".parse().unwrap();
+    pub static ref DEMO_VPC_PEERING_URL: String =
+        format!("/v1/vpc-peerings/{}?{}", *DEMO_VPC_PEERING_NAME, *DEMO_VPC_SELECTOR);
+    pub static ref DEMO_VPC_PEERING_CREATE: params::VpcPeeringCreate =
+        params::VpcPeeringCreate {
+            identity: IdentityMetadataCreateParams {
+                name: DEMO_VPC_PEERING_NAME.clone(),
+                description: String::from(""),
+            },
+            peer_vpc: NameOrId::Name("default".parse().unwrap()),
+            peer_project: None,
+        };
+
     // Router Route used for testing
     pub static ref DEMO_ROUTER_ROUTE_NAME: Name =
         "demo-router-route".parse().unwrap();
@@ -190,6 +217,10 @@ lazy_static! {
             destination: RouteDestination::Subnet("loopback".parse().unwrap()),
         };
 
+    // Labels used for testing
+    pub static ref DEMO_LABELS: Labels =
+        serde_json::from_value(serde_json::json!({ "env": "demo" })).unwrap();
+
     // Disk used for testing
     pub static ref DEMO_DISK_NAME: Name = "demo-disk".parse().unwrap();
     // TODO: Once we can test a URL multiple times we should also a case to exercise authz for disks filtered by instances
@@ -210,7 +241,30 @@ lazy_static! {
                 // divide by at least two to leave space for snapshot blocks
                 DiskTest::DEFAULT_ZPOOL_SIZE_GIB / 5
             ),
+            labels: Default::default(),
         };
+    pub static ref DEMO_DISK_LABELS_URL: String =
+        format!("/v1/disks/{}/labels?{}", *DEMO_DISK_NAME, *DEMO_PROJECT_SELECTOR);
+    pub static ref DEMO_DISK_RESIZE_URL: String =
+        format!("/v1/disks/{}/resize?{}", *DEMO_DISK_NAME, *DEMO_PROJECT_SELECTOR);
+    pub static ref DEMO_DISK_RESIZE: params::DiskResize =
+        params::DiskResize {
+            size: ByteCount::from_gibibytes_u32(
+                DiskTest::DEFAULT_ZPOOL_SIZE_GIB / 5
+            ),
+        };
+    pub static ref DEMO_DISK_ROLLBACK_URL: String =
+        format!("/v1/disks/{}/rollback?{}", *DEMO_DISK_NAME, *DEMO_PROJECT_SELECTOR);
+    pub static ref DEMO_DISK_ROLLBACK: params::DiskRollback =
+        params::DiskRollback { snapshot_id: uuid::Uuid::new_v4() };
+    pub static ref DEMO_DISK_EXPORT_START_URL: String =
+        format!("/v1/disks/{}/export-start?{}", *DEMO_DISK_NAME, *DEMO_PROJECT_SELECTOR);
+    pub static ref DEMO_DISK_BULK_READ_URL: String =
+        format!("/v1/disks/{}/bulk-read?{}", *DEMO_DISK_NAME, *DEMO_PROJECT_SELECTOR);
+    pub static ref DEMO_DISK_EXPORT_STOP_URL: String =
+        format!("/v1/disks/{}/export-stop?{}", *DEMO_DISK_NAME, *DEMO_PROJECT_SELECTOR);
+    pub static ref DEMO_BULK_READ: params::ExportBlocksBulkRead =
+        params::ExportBlocksBulkRead { offset: 0, size: 4096 };
     pub static ref DEMO_DISK_METRICS_URL: String =
         format!(
             "/v1/disks/{}/metrics/activated?start_time={:?}&end_time={:?}&{}",
@@ -237,6 +291,7 @@ lazy_static! {
                 // divide by at least two to leave space for snapshot blocks
                 DiskTest::DEFAULT_ZPOOL_SIZE_GIB / 5
             ),
+            labels: Default::default(),
         };
 
     pub static ref DEMO_IMPORT_DISK_IMPORT_FROM_URL_URL: String =
@@ -259,6 +314,10 @@ lazy_static! {
     pub static ref DEMO_INSTANCE_SELECTOR: String = format!("{}&instance={}", *DEMO_PROJECT_SELECTOR, *DEMO_INSTANCE_NAME);
     pub static ref DEMO_INSTANCE_URL: String =
         format!("/v1/instances/{}?{}", *DEMO_INSTANCE_NAME, *DEMO_PROJECT_SELECTOR);
+    pub static ref DEMO_INSTANCE_LABELS_URL: String =
+        format!("/v1/instances/{}/labels?{}", *DEMO_INSTANCE_NAME, *DEMO_PROJECT_SELECTOR);
+    pub static ref DEMO_INSTANCE_USER_DATA_URL: String =
+        format!("/v1/instances/{}/user-data?{}", *DEMO_INSTANCE_NAME, *DEMO_PROJECT_SELECTOR);
     pub static ref DEMO_INSTANCE_START_URL: String =
         format!("/v1/instances/{}/start?{}", *DEMO_INSTANCE_NAME, *DEMO_PROJECT_SELECTOR);
     pub static ref DEMO_INSTANCE_STOP_URL: String =
@@ -271,6 +330,8 @@ lazy_static! {
         format!("/v1/instances/{}/serial-console?{}", *DEMO_INSTANCE_NAME, *DEMO_PROJECT_SELECTOR);
     pub static ref DEMO_INSTANCE_SERIAL_STREAM_URL: String =
         format!("/v1/instances/{}/serial-console/stream?{}", *DEMO_INSTANCE_NAME, *DEMO_PROJECT_SELECTOR);
+    pub static ref DEMO_INSTANCE_SSH_PUBLIC_KEYS_URL: String =
+        format!("/v1/instances/{}/ssh-public-keys?{}", *DEMO_INSTANCE_NAME, *DEMO_PROJECT_SELECTOR);
 
     pub static ref DEMO_INSTANCE_DISKS_URL: String =
         format!("/v1/instances/{}/disks?{}", *DEMO_INSTANCE_NAME, *DEMO_PROJECT_SELECTOR);
@@ -299,7 +360,16 @@ lazy_static! {
                 params::ExternalIpCreate::Ephemeral { pool_name: Some(DEMO_IP_POOL_NAME.clone()) }
             ],
             disks: vec![],
+            affinity_groups: vec![],
+            ssh_public_keys: None,
             start: true,
+            labels: Default::default(),
+            auto_restart_policy: Default::default(),
+        };
+    pub static ref DEMO_INSTANCE_UPDATE: params::InstanceUpdate =
+        params::InstanceUpdate {
+            ncpus: InstanceCpuCount(2),
+            memory: ByteCount::from_gibibytes_u32(16),
         };
 
     // The instance needs a network interface, too.
@@ -418,6 +488,37 @@ lazy_static! {
         };
 }
 
+lazy_static! {
+    pub static ref DEMO_BGP_CONFIG_CREATE_URL: String =
+        format!("/v1/system/networking/bgp?name_or_id=as47");
+    pub static ref DEMO_BGP_CONFIG: params::CreateBgpConfig =
+        params::CreateBgpConfig {
+            identity: IdentityMetadataCreateParams {
+                name: "as47".parse().unwrap(),
+                description: "BGP config for AS47".into(),
+            },
+            asn: 47,
+            vrf: None,
+        };
+    pub static ref DEMO_BGP_ANNOUNCE_SET_URL: String =
+        format!("/v1/system/networking/bgp-announce?name_or_id=a-side");
+    pub static ref DEMO_BGP_ANNOUNCE: params::CreateBgpAnnounceSet =
+        params::CreateBgpAnnounceSet {
+            identity: IdentityMetadataCreateParams {
+                name: "a-side".parse().unwrap(),
+                description: "a-side BGP announce set".into(),
+            },
+            announcement: vec![params::BgpAnnouncementCreate {
+                address_lot_block: NameOrId::Name(
+                    "parkinglot".parse().unwrap()
+                ),
+                network: "203.0.113.16/29".parse().unwrap(),
+            }],
+        };
+    pub static ref DEMO_BGP_ANNOUNCEMENT_URL: String =
+        format!("/v1/system/networking/bgp-announce/a-side/announcement");
+}
+
 lazy_static! {
     // Project Images
     pub static ref DEMO_IMAGE_NAME: Name = "demo-image".parse().unwrap();
@@ -425,6 +526,8 @@ lazy_static! {
         format!("/v1/images?project={}", *DEMO_PROJECT_NAME);
     pub static ref DEMO_PROJECT_IMAGE_URL: String =
         format!("/v1/images/{}?project={}", *DEMO_IMAGE_NAME, *DEMO_PROJECT_NAME);
+    pub static ref DEMO_PROJECT_IMAGE_LABELS_URL: String =
+        format!("/v1/images/{}/labels?project={}", *DEMO_IMAGE_NAME, *DEMO_PROJECT_NAME);
     pub static ref DEMO_PROJECT_PROMOTE_IMAGE_URL: String =
         format!("/v1/images/{}/promote?project={}", *DEMO_IMAGE_NAME, *DEMO_PROJECT_NAME);
     pub static ref DEMO_SILO_DEMOTE_IMAGE_URL: String =
@@ -440,7 +543,25 @@ lazy_static! {
                 block_size: params::BlockSize::try_from(4096).unwrap(),
             },
             os: "fake-os".to_string(),
-            version: "1.0".to_string()
+            version: "1.0".to_string(),
+            labels: Default::default(),
+        };
+
+    // Silo Images
+    pub static ref DEMO_SILO_IMAGE_NAME: Name =
+        "demo-silo-image".parse().unwrap();
+    pub static ref DEMO_SILO_IMAGES_URL: &'static str = "/v1/images";
+    pub static ref DEMO_SILO_IMAGE_SILOS_URL: String =
+        format!("/v1/images/{}/silos", *DEMO_SILO_IMAGE_NAME);
+    pub static ref DEMO_SILO_IMAGE_SILO_URL: String =
+        format!("/v1/images/{}/silos/{}", *DEMO_SILO_IMAGE_NAME, *DEMO_SILO_NAME);
+    pub static ref DEMO_SILO_IMAGE_CREATE: params::ImageCreate =
+        params::ImageCreate {
+            identity: IdentityMetadataCreateParams {
+                name: DEMO_SILO_IMAGE_NAME.clone(),
+                description: String::from(""),
+            },
+            ..DEMO_IMAGE_CREATE.clone()
         };
 
     // IP Pools
@@ -454,6 +575,8 @@ lazy_static! {
                 name: DEMO_IP_POOL_NAME.clone(),
                 description: String::from("an IP pool"),
             },
+            silo: None,
+            is_default: false,
         };
     pub static ref DEMO_IP_POOL_PROJ_URL: String =
         format!("/v1/ip-pools/{}?project={}", *DEMO_IP_POOL_NAME, *DEMO_PROJECT_NAME);
@@ -472,6 +595,16 @@ lazy_static! {
     pub static ref DEMO_IP_POOL_RANGES_URL: String = format!("{}/ranges", *DEMO_IP_POOL_URL);
     pub static ref DEMO_IP_POOL_RANGES_ADD_URL: String = format!("{}/add", *DEMO_IP_POOL_RANGES_URL);
     pub static ref DEMO_IP_POOL_RANGES_DEL_URL: String = format!("{}/remove", *DEMO_IP_POOL_RANGES_URL);
+    pub static ref DEMO_IP_POOL_LINK_URL: String = format!("{}/link", *DEMO_IP_POOL_URL);
+    pub static ref DEMO_IP_POOL_LINK: params::IpPoolSiloLink =
+        params::IpPoolSiloLink {
+            silo: NameOrId::Name(DEMO_SILO_NAME.clone()),
+            is_default: false,
+        };
+    pub static ref DEMO_IP_POOL_UNLINK_URL: String = format!("{}/unlink", *DEMO_IP_POOL_URL);
+    pub static ref DEMO_IP_POOL_SET_DEFAULT_URL: String = format!("{}/set-default", *DEMO_IP_POOL_URL);
+    pub static ref DEMO_IP_POOL_SET_DEFAULT: params::IpPoolSetDefault =
+        params::IpPoolSetDefault { is_default: false };
 
     // IP Pools (Services)
     pub static ref DEMO_IP_POOL_SERVICE_URL: &'static str = "/v1/system/ip-pools-service";
@@ -483,6 +616,14 @@ lazy_static! {
     pub static ref DEMO_SNAPSHOT_NAME: Name = "demo-snapshot".parse().unwrap();
     pub static ref DEMO_SNAPSHOT_URL: String =
         format!("/v1/snapshots/{}?project={}", *DEMO_SNAPSHOT_NAME, *DEMO_PROJECT_NAME);
+    pub static ref DEMO_SNAPSHOT_LABELS_URL: String =
+        format!("/v1/snapshots/{}/labels?project={}", *DEMO_SNAPSHOT_NAME, *DEMO_PROJECT_NAME);
+    pub static ref DEMO_SNAPSHOT_EXPORT_START_URL: String =
+        format!("/v1/snapshots/{}/export-start?project={}", *DEMO_SNAPSHOT_NAME, *DEMO_PROJECT_NAME);
+    pub static ref DEMO_SNAPSHOT_BULK_READ_URL: String =
+        format!("/v1/snapshots/{}/bulk-read?project={}", *DEMO_SNAPSHOT_NAME, *DEMO_PROJECT_NAME);
+    pub static ref DEMO_SNAPSHOT_EXPORT_STOP_URL: String =
+        format!("/v1/snapshots/{}/export-stop?project={}", *DEMO_SNAPSHOT_NAME, *DEMO_PROJECT_NAME);
     pub static ref DEMO_SNAPSHOT_CREATE: params::SnapshotCreate =
         params::SnapshotCreate {
             identity: IdentityMetadataCreateParams {
@@ -490,6 +631,78 @@ lazy_static! {
                 description: String::from(""),
             },
             disk: DEMO_DISK_NAME.clone().into(),
+            labels: Default::default(),
+        };
+
+    // Snapshot schedules
+    pub static ref DEMO_SNAPSHOT_SCHEDULE_NAME: Name = "demo-snapshot-schedule".parse().unwrap();
+    pub static ref DEMO_SNAPSHOT_SCHEDULE_URL: String =
+        format!("/v1/snapshot-schedules/{}?{}", *DEMO_SNAPSHOT_SCHEDULE_NAME, *DEMO_PROJECT_SELECTOR);
+    pub static ref DEMO_SNAPSHOT_SCHEDULE_CREATE: params::SnapshotScheduleCreate =
+        params::SnapshotScheduleCreate {
+            identity: IdentityMetadataCreateParams {
+                name: DEMO_SNAPSHOT_SCHEDULE_NAME.clone(),
+                description: String::from("a new snapshot schedule"),
+            },
+            target: params::SnapshotScheduleTargetCreate::Disk {
+                disk: DEMO_DISK_NAME.clone().into(),
+            },
+            interval_secs: 86400,
+            retain: 7,
+        };
+
+    // Webhook receivers
+    pub static ref DEMO_WEBHOOK_RECEIVERS_URL: &'static str = "/v1/webhook-receivers";
+    pub static ref DEMO_WEBHOOK_RECEIVER_NAME: Name = "demo-webhook-receiver".parse().unwrap();
+    pub static ref DEMO_WEBHOOK_RECEIVER_URL: String =
+        format!("/v1/webhook-receivers/{}", *DEMO_WEBHOOK_RECEIVER_NAME);
+    pub static ref DEMO_WEBHOOK_DELIVERIES_URL: String =
+        format!("/v1/webhook-receivers/{}/deliveries", *DEMO_WEBHOOK_RECEIVER_NAME);
+    pub static ref DEMO_WEBHOOK_RECEIVER_CREATE: params::WebhookReceiverCreate =
+        params::WebhookReceiverCreate {
+            identity: IdentityMetadataCreateParams {
+                name: DEMO_WEBHOOK_RECEIVER_NAME.clone(),
+                description: String::from("a new webhook receiver"),
+            },
+            endpoint: String::from("http://127.0.0.1:1/events"),
+            secret: String::from("demo-webhook-secret"),
+            event_classes: vec![shared::WebhookEventClass::InstanceStateChange],
+        };
+
+    // Floating IPs
+    pub static ref DEMO_FLOAT_IP_NAME: Name = "float-ip-a".parse().unwrap();
+    pub static ref DEMO_FLOAT_IP_URL: String =
+        format!("/v1/floating-ips/{}?project={}", *DEMO_FLOAT_IP_NAME, *DEMO_PROJECT_NAME);
+    pub static ref DEMO_FLOAT_IP_ATTACH_URL: String =
+        format!("/v1/floating-ips/{}/attach?{}", *DEMO_FLOAT_IP_NAME, *DEMO_PROJECT_SELECTOR);
+    pub static ref DEMO_FLOAT_IP_DETACH_URL: String =
+        format!("/v1/floating-ips/{}/detach?{}", *DEMO_FLOAT_IP_NAME, *DEMO_PROJECT_SELECTOR);
+    pub static ref DEMO_FLOAT_IP_CREATE: params::FloatingIpCreate =
+        params::FloatingIpCreate {
+            identity: IdentityMetadataCreateParams {
+                name: DEMO_FLOAT_IP_NAME.clone(),
+                description: String::from("a new floating IP"),
+            },
+            address: Some(std::net::Ipv4Addr::new(10, 0, 0, 141).into()),
+            pool: None,
+        };
+
+    // Affinity groups
+    pub static ref DEMO_AFFINITY_GROUP_NAME: Name = "demo-affinity-group".parse().unwrap();
+    pub static ref DEMO_AFFINITY_GROUP_URL: String =
+        format!("/v1/affinity-groups/{}?{}", *DEMO_AFFINITY_GROUP_NAME, *DEMO_PROJECT_SELECTOR);
+    pub static ref DEMO_AFFINITY_GROUP_MEMBERS_URL: String =
+        format!("/v1/affinity-groups/{}/members?{}", *DEMO_AFFINITY_GROUP_NAME, *DEMO_PROJECT_SELECTOR);
+    pub static ref DEMO_AFFINITY_GROUP_MEMBER_URL: String =
+        format!("/v1/affinity-groups/{}/members/{}?{}", *DEMO_AFFINITY_GROUP_NAME, *DEMO_INSTANCE_NAME, *DEMO_PROJECT_SELECTOR);
+    pub static ref DEMO_AFFINITY_GROUP_CREATE: params::AffinityGroupCreate =
+        params::AffinityGroupCreate {
+            identity: IdentityMetadataCreateParams {
+                name: DEMO_AFFINITY_GROUP_NAME.clone(),
+                description: String::from("a new affinity group"),
+            },
+            kind: shared::AffinityGroupKind::AntiAffinity,
+            policy: shared::AffinityPolicy::Soft,
         };
 
     // SSH keys
@@ -507,6 +720,14 @@ lazy_static! {
     pub static ref DEMO_SPECIFIC_SSHKEY_URL: String =
         format!("{}/{}", *DEMO_SSHKEYS_URL, *DEMO_SSHKEY_NAME);
 
+    // Access tokens
+    pub static ref DEMO_TOKENS_URL: &'static str = "/v1/me/tokens";
+    pub static ref DEMO_TOKEN_CREATE: params::DeviceAccessTokenCreate =
+        params::DeviceAccessTokenCreate {
+            name: "demo-token".parse().unwrap(),
+            time_expires: None,
+        };
+
     // System update
 
     pub static ref DEMO_SYSTEM_UPDATE_PARAMS: params::SystemUpdatePath = params::SystemUpdatePath {
@@ -542,6 +763,60 @@ lazy_static! {
             group_attribute_name: None,
         };
 
+    pub static ref DEMO_OIDC_SILO_NAME: Name = "demo-oidc-silo".parse().unwrap();
+    pub static ref DEMO_OIDC_SILO_CREATE: params::SiloCreate =
+        params::SiloCreate {
+            identity: IdentityMetadataCreateParams {
+                name: DEMO_OIDC_SILO_NAME.clone(),
+                description: String::from(""),
+            },
+            discoverable: true,
+            identity_mode: shared::SiloIdentityMode::OidcJit,
+            admin_group_name: None,
+            tls_certificates: vec![],
+            mapped_fleet_roles: Default::default(),
+        };
+    pub static ref OIDC_IDENTITY_PROVIDERS_URL: String = format!("/v1/system/identity-providers/oidc?silo={}", *DEMO_OIDC_SILO_NAME);
+
+    pub static ref DEMO_OIDC_IDENTITY_PROVIDER_NAME: Name = "demo-oidc-provider".parse().unwrap();
+    pub static ref SPECIFIC_OIDC_IDENTITY_PROVIDER_URL: String = format!("/v1/system/identity-providers/oidc/{}?silo={}", *DEMO_OIDC_IDENTITY_PROVIDER_NAME, *DEMO_OIDC_SILO_NAME);
+
+    // SCIM client tokens (the demo Silo does not provision users with SCIM,
+    // so these are only useful for checking authorization)
+    pub static ref SCIM_TOKENS_URL: String = format!("/v1/system/scim/tokens?silo={}", *DEMO_SILO_NAME);
+    pub static ref SPECIFIC_SCIM_TOKEN_URL: String = format!("/v1/system/scim/tokens/{}?silo={}", uuid::Uuid::new_v4(), *DEMO_SILO_NAME);
+    pub static ref SCIM_TOKEN_CREATE: params::ScimClientBearerTokenCreate =
+        params::ScimClientBearerTokenCreate { time_expires: None };
+
+    pub static ref OIDC_IDENTITY_PROVIDER: params::OidcIdentityProviderCreate = {
+        let discovery = serde_json::json!({
+            "issuer": "https://idp.example.com",
+            "authorization_endpoint": "https://idp.example.com/authorize",
+            "token_endpoint": "https://idp.example.com/token",
+            "jwks_uri": HTTP_SERVER.url("/jwks").to_string(),
+        });
+        params::OidcIdentityProviderCreate {
+            identity: IdentityMetadataCreateParams {
+                name: DEMO_OIDC_IDENTITY_PROVIDER_NAME.clone(),
+                description: "a demo provider".to_string(),
+            },
+
+            discovery_source: params::OidcDiscoverySource::Base64EncodedJson {
+                data: base64::Engine::encode(
+                    &base64::engine::general_purpose::STANDARD,
+                    discovery.to_string(),
+                ),
+            },
+
+            client_id: "client_id".to_string(),
+            client_secret: "client_secret".to_string(),
+            redirect_uri: "http://callback".to_string(),
+            scopes: vec![],
+
+            group_claim_name: None,
+        }
+    };
+
     pub static ref DEMO_SYSTEM_METRICS_URL: String =
         format!(
             "/v1/system/metrics/virtual_disk_space_provisioned?start_time={:?}&end_time={:?}",
@@ -799,6 +1074,40 @@ lazy_static! {
             ],
         },
 
+        // IP Pool link endpoint
+        VerifyEndpoint {
+            url: &DEMO_IP_POOL_LINK_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Post(
+                    serde_json::to_value(&*DEMO_IP_POOL_LINK).unwrap()
+                ),
+            ],
+        },
+
+        // IP Pool unlink endpoint
+        VerifyEndpoint {
+            url: &DEMO_IP_POOL_UNLINK_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Post(serde_json::Value::Null),
+            ],
+        },
+
+        // IP Pool set-default endpoint
+        VerifyEndpoint {
+            url: &DEMO_IP_POOL_SET_DEFAULT_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Post(
+                    serde_json::to_value(&*DEMO_IP_POOL_SET_DEFAULT).unwrap()
+                ),
+            ],
+        },
+
         // IP Pool endpoint (Oxide services)
         VerifyEndpoint {
             url: &DEMO_IP_POOL_SERVICE_URL,
@@ -879,6 +1188,19 @@ lazy_static! {
                 ),
             ],
         },
+        VerifyEndpoint {
+            url: &DEMO_SILO_QUOTAS_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Get,
+                AllowedMethod::Put(
+                    serde_json::to_value(
+                        &params::QuotaUpdate::default()
+                    ).unwrap()
+                ),
+            ],
+        },
         VerifyEndpoint {
             url: "/v1/policy",
             visibility: Visibility::Public,
@@ -1029,6 +1351,20 @@ lazy_static! {
             ],
         },
 
+        VerifyEndpoint {
+            url: &DEMO_PROJECT_QUOTAS_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Get,
+                AllowedMethod::Put(
+                    serde_json::to_value(
+                        &params::QuotaUpdate::default()
+                    ).unwrap()
+                ),
+            ],
+        },
+
         /* VPCs */
         VerifyEndpoint {
             url: &DEMO_PROJECT_URL_VPCS,
@@ -1055,6 +1391,7 @@ lazy_static! {
                             description: Some("different".to_string())
                         },
                         dns_name: None,
+                        labels: None,
                     }).unwrap()
                 ),
                 AllowedMethod::Delete,
@@ -1148,6 +1485,30 @@ lazy_static! {
             ],
         },
 
+        /* VPC Peerings */
+
+        VerifyEndpoint {
+            url: &DEMO_VPC_URL_PEERINGS,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Get,
+                AllowedMethod::Post(
+                    serde_json::to_value(&*DEMO_VPC_PEERING_CREATE).unwrap()
+                ),
+            ],
+        },
+
+        VerifyEndpoint {
+            url: &DEMO_VPC_PEERING_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Get,
+                AllowedMethod::Delete,
+            ],
+        },
+
         /* Router Routes */
 
         VerifyEndpoint {
@@ -1208,6 +1569,68 @@ lazy_static! {
             ],
         },
 
+        VerifyEndpoint {
+            url: &DEMO_DISK_LABELS_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Put(
+                    serde_json::to_value(&*DEMO_LABELS).unwrap()
+                ),
+            ],
+        },
+
+        VerifyEndpoint {
+            url: &DEMO_DISK_RESIZE_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Post(
+                    serde_json::to_value(&*DEMO_DISK_RESIZE).unwrap()
+                ),
+            ],
+        },
+
+        VerifyEndpoint {
+            url: &DEMO_DISK_ROLLBACK_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Post(
+                    serde_json::to_value(&*DEMO_DISK_ROLLBACK).unwrap()
+                ),
+            ],
+        },
+
+        VerifyEndpoint {
+            url: &DEMO_DISK_EXPORT_START_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Post(serde_json::value::Value::Null),
+            ],
+        },
+
+        VerifyEndpoint {
+            url: &DEMO_DISK_BULK_READ_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Post(
+                    serde_json::to_value(&*DEMO_BULK_READ).unwrap()
+                ),
+            ],
+        },
+
+        VerifyEndpoint {
+            url: &DEMO_DISK_EXPORT_STOP_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Post(serde_json::value::Value::Null),
+            ],
+        },
+
         VerifyEndpoint {
             url: &DEMO_DISK_METRICS_URL,
             visibility: Visibility::Protected,
@@ -1217,6 +1640,15 @@ lazy_static! {
             ],
         },
 
+        VerifyEndpoint {
+            url: &DEMO_INSTANCE_SSH_PUBLIC_KEYS_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Get,
+            ],
+        },
+
         VerifyEndpoint {
             url: &DEMO_INSTANCE_DISKS_URL,
             visibility: Visibility::Protected,
@@ -1330,6 +1762,17 @@ lazy_static! {
             ],
         },
 
+        VerifyEndpoint {
+            url: &DEMO_PROJECT_IMAGE_LABELS_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Put(
+                    serde_json::to_value(&*DEMO_LABELS).unwrap()
+                ),
+            ],
+        },
+
         VerifyEndpoint {
             url: &DEMO_PROJECT_PROMOTE_IMAGE_URL,
             visibility: Visibility::Protected,
@@ -1348,6 +1791,23 @@ lazy_static! {
             ],
         },
 
+        VerifyEndpoint {
+            url: &DEMO_SILO_IMAGE_SILOS_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![AllowedMethod::Get],
+        },
+
+        VerifyEndpoint {
+            url: &DEMO_SILO_IMAGE_SILO_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Post(serde_json::value::Value::Null),
+                AllowedMethod::Delete,
+            ],
+        },
+
         /* Snapshots */
 
         VerifyEndpoint {
@@ -1372,6 +1832,186 @@ lazy_static! {
             ]
         },
 
+        VerifyEndpoint {
+            url: &DEMO_SNAPSHOT_LABELS_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Put(
+                    serde_json::to_value(&*DEMO_LABELS).unwrap()
+                ),
+            ],
+        },
+
+        VerifyEndpoint {
+            url: &DEMO_SNAPSHOT_EXPORT_START_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Post(serde_json::value::Value::Null),
+            ],
+        },
+
+        VerifyEndpoint {
+            url: &DEMO_SNAPSHOT_BULK_READ_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Post(
+                    serde_json::to_value(&*DEMO_BULK_READ).unwrap()
+                ),
+            ],
+        },
+
+        VerifyEndpoint {
+            url: &DEMO_SNAPSHOT_EXPORT_STOP_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Post(serde_json::value::Value::Null),
+            ],
+        },
+
+        /* Snapshot schedules */
+        VerifyEndpoint {
+            url: &DEMO_PROJECT_URL_SNAPSHOT_SCHEDULES,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Get,
+                AllowedMethod::Post(
+                    serde_json::to_value(&*DEMO_SNAPSHOT_SCHEDULE_CREATE).unwrap(),
+                ),
+            ],
+        },
+
+        VerifyEndpoint {
+            url: &DEMO_SNAPSHOT_SCHEDULE_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Get,
+                AllowedMethod::Delete,
+            ],
+        },
+
+        /* Webhook receivers */
+        VerifyEndpoint {
+            url: &DEMO_WEBHOOK_RECEIVERS_URL,
+            visibility: Visibility::Public,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Get,
+                AllowedMethod::Post(
+                    serde_json::to_value(&*DEMO_WEBHOOK_RECEIVER_CREATE).unwrap(),
+                ),
+            ],
+        },
+
+        VerifyEndpoint {
+            url: &DEMO_WEBHOOK_RECEIVER_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Get,
+                AllowedMethod::Delete,
+            ],
+        },
+
+        VerifyEndpoint {
+            url: &DEMO_WEBHOOK_DELIVERIES_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![AllowedMethod::Get],
+        },
+
+        /* Floating IPs */
+        VerifyEndpoint {
+            url: &DEMO_PROJECT_URL_FIPS,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Get,
+                AllowedMethod::Post(
+                    serde_json::to_value(&*DEMO_FLOAT_IP_CREATE).unwrap(),
+                ),
+            ],
+        },
+
+        VerifyEndpoint {
+            url: &DEMO_FLOAT_IP_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Get,
+                AllowedMethod::Delete,
+            ],
+        },
+
+        VerifyEndpoint {
+            url: &DEMO_FLOAT_IP_ATTACH_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Post(
+                    serde_json::to_value(params::FloatingIpAttach {
+                        instance: DEMO_INSTANCE_NAME.clone().into(),
+                    }).unwrap()
+                ),
+            ],
+        },
+
+        VerifyEndpoint {
+            url: &DEMO_FLOAT_IP_DETACH_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Post(serde_json::value::Value::Null),
+            ],
+        },
+
+        /* Affinity groups */
+        VerifyEndpoint {
+            url: &DEMO_PROJECT_URL_AFFINITY_GROUPS,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Get,
+                AllowedMethod::Post(
+                    serde_json::to_value(&*DEMO_AFFINITY_GROUP_CREATE).unwrap(),
+                ),
+            ],
+        },
+
+        VerifyEndpoint {
+            url: &DEMO_AFFINITY_GROUP_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Get,
+                AllowedMethod::Delete,
+            ],
+        },
+
+        VerifyEndpoint {
+            url: &DEMO_AFFINITY_GROUP_MEMBERS_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Get,
+            ],
+        },
+
+        VerifyEndpoint {
+            url: &DEMO_AFFINITY_GROUP_MEMBER_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Post(serde_json::value::Value::Null),
+                AllowedMethod::Delete,
+            ],
+        },
+
         /* Instances */
         VerifyEndpoint {
             url: &DEMO_PROJECT_URL_INSTANCES,
@@ -1391,10 +2031,37 @@ lazy_static! {
             unprivileged_access: UnprivilegedAccess::None,
             allowed_methods: vec![
                 AllowedMethod::Get,
+                AllowedMethod::Put(
+                    serde_json::to_value(&*DEMO_INSTANCE_UPDATE).unwrap()
+                ),
                 AllowedMethod::Delete,
             ],
         },
 
+        VerifyEndpoint {
+            url: &DEMO_INSTANCE_LABELS_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Put(
+                    serde_json::to_value(&*DEMO_LABELS).unwrap()
+                ),
+            ],
+        },
+
+        VerifyEndpoint {
+            url: &DEMO_INSTANCE_USER_DATA_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Put(
+                    serde_json::to_value(params::InstanceUserDataUpdate {
+                        user_data: b"#cloud-config".to_vec(),
+                    }).unwrap()
+                ),
+            ],
+        },
+
         VerifyEndpoint {
             url: &DEMO_INSTANCE_START_URL,
             visibility: Visibility::Protected,
@@ -1497,6 +2164,15 @@ lazy_static! {
             allowed_methods: vec![AllowedMethod::Get],
         },
 
+        /* Audit log */
+
+        VerifyEndpoint {
+            url: "/v1/system/audit-log",
+            visibility: Visibility::Public,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![AllowedMethod::Get],
+        },
+
         VerifyEndpoint {
             url: "/v1/system/users-builtin",
             visibility: Visibility::Public,
@@ -1710,6 +2386,38 @@ lazy_static! {
             unprivileged_access: UnprivilegedAccess::None,
             allowed_methods: vec![AllowedMethod::Get],
         },
+        VerifyEndpoint {
+            url: &OIDC_IDENTITY_PROVIDERS_URL,
+            // See the SAML identity provider endpoint above.
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![AllowedMethod::Post(
+                serde_json::to_value(&*OIDC_IDENTITY_PROVIDER).unwrap(),
+            )],
+        },
+        VerifyEndpoint {
+            url: &SPECIFIC_OIDC_IDENTITY_PROVIDER_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![AllowedMethod::Get],
+        },
+        VerifyEndpoint {
+            url: &SCIM_TOKENS_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Get,
+                AllowedMethod::Post(
+                    serde_json::to_value(&*SCIM_TOKEN_CREATE).unwrap(),
+                ),
+            ],
+        },
+        VerifyEndpoint {
+            url: &SPECIFIC_SCIM_TOKEN_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![AllowedMethod::Delete],
+        },
 
         /* Misc */
 
@@ -1753,6 +2461,29 @@ lazy_static! {
             ],
         },
 
+        /* Access tokens */
+
+        VerifyEndpoint {
+            url: &DEMO_TOKENS_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::Full,
+            allowed_methods: vec![
+                AllowedMethod::Get,
+                AllowedMethod::Post(
+                    serde_json::to_value(&*DEMO_TOKEN_CREATE).unwrap(),
+                ),
+            ],
+        },
+        VerifyEndpoint {
+            // non-existent UUID that will 404
+            url: "/v1/me/tokens/8d90b9a5-1cea-4a2b-9af4-71467dd33a04",
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::Full,
+            allowed_methods: vec![
+                AllowedMethod::Delete,
+            ],
+        },
+
         /* Certificates */
         VerifyEndpoint {
             url: &DEMO_CERTIFICATES_URL,
@@ -1873,5 +2604,40 @@ lazy_static! {
             ],
         },
 
+        VerifyEndpoint {
+            url: &DEMO_BGP_CONFIG_CREATE_URL,
+            visibility: Visibility::Public,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Post(
+                    serde_json::to_value(&*DEMO_BGP_CONFIG).unwrap(),
+                ),
+                AllowedMethod::Get,
+                AllowedMethod::Delete
+            ],
+        },
+
+        VerifyEndpoint {
+            url: &DEMO_BGP_ANNOUNCE_SET_URL,
+            visibility: Visibility::Public,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Post(
+                    serde_json::to_value(&*DEMO_BGP_ANNOUNCE).unwrap(),
+                ),
+                AllowedMethod::Get,
+                AllowedMethod::Delete
+            ],
+        },
+
+        VerifyEndpoint {
+            url: &DEMO_BGP_ANNOUNCEMENT_URL,
+            visibility: Visibility::Public,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::GetNonexistent
+            ],
+        },
+
     ];
 }
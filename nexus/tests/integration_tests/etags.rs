@@ -0,0 +1,310 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Tests for ETags and conditional ("If-Match") requests
+
+use dropshot::test_util::ClientTestContext;
+use http::header;
+use http::Method;
+use http::StatusCode;
+use nexus_test_utils::http_testing::AuthnMode;
+use nexus_test_utils::http_testing::NexusRequest;
+use nexus_test_utils::http_testing::RequestBuilder;
+use nexus_test_utils::http_testing::TestResponse;
+use nexus_test_utils::resource_helpers::create_project;
+use nexus_test_utils_macros::nexus_test;
+use omicron_common::api::external::IdentityMetadataUpdateParams;
+use omicron_common::api::external::VpcFirewallRuleUpdateParams;
+use omicron_common::api::external::VpcFirewallRules;
+use omicron_nexus::authn::USER_TEST_UNPRIVILEGED;
+use omicron_nexus::db::identity::Asset;
+use omicron_nexus::external_api::params;
+use omicron_nexus::external_api::shared;
+use omicron_nexus::external_api::views::Project;
+
+type ControlPlaneTestContext =
+    nexus_test_utils::ControlPlaneTestContext<omicron_nexus::Server>;
+
+const PROJECT_URL: &str = "/v1/projects/etag-project";
+const FIREWALL_RULES_URL: &str =
+    "/v1/vpc-firewall-rules?project=etag-project&vpc=default";
+const SILO_POLICY_URL: &str = "/v1/system/silos/default-silo/policy";
+const SUBNET_URL: &str =
+    "/v1/vpc-subnets/default?project=etag-project&vpc=default";
+
+fn etag(response: &TestResponse) -> String {
+    response
+        .headers
+        .get(header::ETAG)
+        .expect("response had no ETag header")
+        .to_str()
+        .unwrap()
+        .to_string()
+}
+
+async fn get(client: &ClientTestContext, url: &str) -> TestResponse {
+    NexusRequest::object_get(client, url)
+        .authn_as(AuthnMode::PrivilegedUser)
+        .execute()
+        .await
+        .unwrap()
+}
+
+/// Makes a request with the given "If-Match" header and checks its status
+async fn conditional_request<B: serde::Serialize>(
+    client: &ClientTestContext,
+    method: Method,
+    url: &str,
+    if_match: &str,
+    body: Option<&B>,
+    expected_status: StatusCode,
+) -> TestResponse {
+    NexusRequest::new(
+        RequestBuilder::new(client, method, url)
+            .header(header::IF_MATCH, if_match)
+            .body(body)
+            .expect_status(Some(expected_status)),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap()
+}
+
+fn project_update(description: &str) -> params::ProjectUpdate {
+    params::ProjectUpdate {
+        identity: IdentityMetadataUpdateParams {
+            name: None,
+            description: Some(String::from(description)),
+        },
+        labels: None,
+    }
+}
+
+#[nexus_test]
+async fn test_project_etags(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+    create_project(client, "etag-project").await;
+
+    // Fetching the same version of a resource returns the same tag.
+    let etag1 = etag(&get(client, PROJECT_URL).await);
+    assert_eq!(etag1, etag(&get(client, PROJECT_URL).await));
+
+    // An update conditional on the current version succeeds and returns the
+    // tag for the new version.
+    let response = conditional_request(
+        client,
+        Method::PUT,
+        PROJECT_URL,
+        &etag1,
+        Some(&project_update("second")),
+        StatusCode::OK,
+    )
+    .await;
+    let etag2 = etag(&response);
+    assert_ne!(etag1, etag2);
+    assert_eq!(
+        response.parsed_body::<Project>().unwrap().identity.description,
+        "second"
+    );
+    assert_eq!(etag2, etag(&get(client, PROJECT_URL).await));
+
+    // An update conditional on the old version fails and changes nothing.
+    let error = conditional_request(
+        client,
+        Method::PUT,
+        PROJECT_URL,
+        &etag1,
+        Some(&project_update("lost update")),
+        StatusCode::PRECONDITION_FAILED,
+    )
+    .await
+    .parsed_body::<dropshot::HttpErrorResponseBody>()
+    .unwrap();
+    assert_eq!(error.error_code, Some(String::from("PreconditionFailed")));
+    let response = get(client, PROJECT_URL).await;
+    assert_eq!(etag2, etag(&response));
+    assert_eq!(
+        response.parsed_body::<Project>().unwrap().identity.description,
+        "second"
+    );
+
+    // A list of tags matches if any of them match, but weak tags never match.
+    conditional_request(
+        client,
+        Method::PUT,
+        PROJECT_URL,
+        &format!("W/{}", etag2),
+        Some(&project_update("third")),
+        StatusCode::PRECONDITION_FAILED,
+    )
+    .await;
+    let response = conditional_request(
+        client,
+        Method::PUT,
+        PROJECT_URL,
+        &format!("{}, {}", etag1, etag2),
+        Some(&project_update("third")),
+        StatusCode::OK,
+    )
+    .await;
+    let etag3 = etag(&response);
+
+    // "*" matches any version.
+    conditional_request(
+        client,
+        Method::PUT,
+        PROJECT_URL,
+        "*",
+        Some(&project_update("fourth")),
+        StatusCode::OK,
+    )
+    .await;
+
+    // Malformed headers are rejected.
+    conditional_request(
+        client,
+        Method::PUT,
+        PROJECT_URL,
+        "not-quoted",
+        Some(&project_update("fifth")),
+        StatusCode::BAD_REQUEST,
+    )
+    .await;
+
+    // Deletes are conditional, too.  (The project can't be deleted yet
+    // because it still has a VPC, so use the VPC's subnet to check that a
+    // delete with the right tag goes through.)
+    conditional_request::<()>(
+        client,
+        Method::DELETE,
+        PROJECT_URL,
+        &etag3,
+        None,
+        StatusCode::PRECONDITION_FAILED,
+    )
+    .await;
+    let subnet_etag = etag(&get(client, SUBNET_URL).await);
+    conditional_request::<()>(
+        client,
+        Method::DELETE,
+        SUBNET_URL,
+        "\"bogus\"",
+        None,
+        StatusCode::PRECONDITION_FAILED,
+    )
+    .await;
+    conditional_request::<()>(
+        client,
+        Method::DELETE,
+        SUBNET_URL,
+        &subnet_etag,
+        None,
+        StatusCode::NO_CONTENT,
+    )
+    .await;
+}
+
+#[nexus_test]
+async fn test_firewall_rules_etags(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+    create_project(client, "etag-project").await;
+
+    // Two operators fetch the same version of the rules.
+    let response = get(client, FIREWALL_RULES_URL).await;
+    let etag1 = etag(&response);
+    let rules1 = response.parsed_body::<VpcFirewallRules>().unwrap().rules;
+    assert!(!rules1.is_empty());
+
+    // The first one removes all of the rules.
+    let no_rules = VpcFirewallRuleUpdateParams { rules: vec![] };
+    let response = conditional_request(
+        client,
+        Method::PUT,
+        FIREWALL_RULES_URL,
+        &etag1,
+        Some(&no_rules),
+        StatusCode::OK,
+    )
+    .await;
+    let etag2 = etag(&response);
+    assert_ne!(etag1, etag2);
+    assert_eq!(etag2, etag(&get(client, FIREWALL_RULES_URL).await));
+
+    // The second one's update, based on the old rules, is rejected.
+    conditional_request(
+        client,
+        Method::PUT,
+        FIREWALL_RULES_URL,
+        &etag1,
+        Some(&no_rules),
+        StatusCode::PRECONDITION_FAILED,
+    )
+    .await;
+    let response = get(client, FIREWALL_RULES_URL).await;
+    assert_eq!(etag2, etag(&response));
+    assert!(response
+        .parsed_body::<VpcFirewallRules>()
+        .unwrap()
+        .rules
+        .is_empty());
+}
+
+#[nexus_test]
+async fn test_silo_policy_etags(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+
+    // The privileged test user is in the default silo, so "/v1/policy" refers
+    // to the same policy as `SILO_POLICY_URL`.
+    let policy_url = "/v1/policy";
+
+    let response = get(client, policy_url).await;
+    let etag1 = etag(&response);
+    let policy: shared::Policy<shared::SiloRole> =
+        response.parsed_body().unwrap();
+    assert_eq!(etag1, etag(&get(client, SILO_POLICY_URL).await));
+
+    // Writing back the same policy leaves the tag unchanged because it's
+    // derived from the contents of the policy.
+    let response = conditional_request(
+        client,
+        Method::PUT,
+        policy_url,
+        &etag1,
+        Some(&policy),
+        StatusCode::OK,
+    )
+    .await;
+    assert_eq!(etag1, etag(&response));
+
+    // Changing the policy changes the tag.
+    let mut new_policy = policy.clone();
+    new_policy.role_assignments.push(shared::RoleAssignment {
+        identity_type: shared::IdentityType::SiloUser,
+        identity_id: USER_TEST_UNPRIVILEGED.id(),
+        role_name: shared::SiloRole::Viewer,
+    });
+    let response = conditional_request(
+        client,
+        Method::PUT,
+        SILO_POLICY_URL,
+        &etag1,
+        Some(&new_policy),
+        StatusCode::OK,
+    )
+    .await;
+    let etag2 = etag(&response);
+    assert_ne!(etag1, etag2);
+
+    // Now an update based on the original policy is rejected.
+    conditional_request(
+        client,
+        Method::PUT,
+        SILO_POLICY_URL,
+        &etag1,
+        Some(&policy),
+        StatusCode::PRECONDITION_FAILED,
+    )
+    .await;
+}
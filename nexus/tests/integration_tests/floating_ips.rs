@@ -0,0 +1,330 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Integration tests for Floating IPs
+
+use std::net::IpAddr;
+use std::net::Ipv4Addr;
+
+use dropshot::test_util::ClientTestContext;
+use dropshot::HttpErrorResponseBody;
+use http::Method;
+use http::StatusCode;
+use nexus_test_utils::http_testing::AuthnMode;
+use nexus_test_utils::http_testing::NexusRequest;
+use nexus_test_utils::http_testing::RequestBuilder;
+use nexus_test_utils::resource_helpers::create_floating_ip;
+use nexus_test_utils::resource_helpers::create_project;
+use nexus_test_utils::resource_helpers::object_create;
+use nexus_test_utils::resource_helpers::object_delete;
+use nexus_test_utils::resource_helpers::objects_list_page_authz;
+use nexus_test_utils::resource_helpers::populate_ip_pool;
+use nexus_test_utils_macros::nexus_test;
+use omicron_common::api::external::ByteCount;
+use omicron_common::api::external::IdentityMetadataCreateParams;
+use omicron_common::api::external::Instance;
+use omicron_common::api::external::InstanceCpuCount;
+use omicron_common::api::external::Name;
+use omicron_nexus::external_api::params;
+use omicron_nexus::external_api::views;
+use omicron_nexus::external_api::views::FloatingIp;
+
+type ControlPlaneTestContext =
+    nexus_test_utils::ControlPlaneTestContext<omicron_nexus::Server>;
+
+const PROJECT_NAME: &str = "rootbeer-patch";
+
+fn get_floating_ips_url(project_name: &str) -> String {
+    format!("/v1/floating-ips?project={project_name}")
+}
+
+fn get_floating_ip_by_name_url(fip_name: &str, project_name: &str) -> String {
+    format!("/v1/floating-ips/{fip_name}?project={project_name}")
+}
+
+async fn floating_ip_get(client: &ClientTestContext, url: &str) -> FloatingIp {
+    NexusRequest::object_get(client, url)
+        .authn_as(AuthnMode::PrivilegedUser)
+        .execute()
+        .await
+        .unwrap()
+        .parsed_body()
+        .unwrap()
+}
+
+async fn floating_ip_post(
+    client: &ClientTestContext,
+    url: &str,
+    body: Option<&params::FloatingIpAttach>,
+) -> FloatingIp {
+    NexusRequest::new(
+        RequestBuilder::new(client, Method::POST, url)
+            .body(body)
+            .expect_status(Some(StatusCode::ACCEPTED)),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap()
+    .parsed_body()
+    .unwrap()
+}
+
+async fn floating_ip_attach(
+    client: &ClientTestContext,
+    fip_name: &str,
+    instance_name: &str,
+) -> FloatingIp {
+    let url =
+        format!("/v1/floating-ips/{fip_name}/attach?project={PROJECT_NAME}");
+    let instance: Name = instance_name.parse().unwrap();
+    let attach = params::FloatingIpAttach { instance: instance.into() };
+    floating_ip_post(client, &url, Some(&attach)).await
+}
+
+async fn floating_ip_detach(
+    client: &ClientTestContext,
+    fip_name: &str,
+) -> FloatingIp {
+    let url =
+        format!("/v1/floating-ips/{fip_name}/detach?project={PROJECT_NAME}");
+    floating_ip_post(client, &url, None).await
+}
+
+/// Creates an instance which is not started, so that Floating IPs may be
+/// attached to it.
+async fn create_stopped_instance(
+    client: &ClientTestContext,
+    instance_name: &str,
+) -> Instance {
+    object_create(
+        client,
+        &format!("/v1/instances?project={PROJECT_NAME}"),
+        &params::InstanceCreate {
+            identity: IdentityMetadataCreateParams {
+                name: instance_name.parse().unwrap(),
+                description: format!("instance {:?}", instance_name),
+            },
+            ncpus: InstanceCpuCount(1),
+            memory: ByteCount::from_gibibytes_u32(1),
+            hostname: String::from("the-host"),
+            user_data: vec![],
+            network_interfaces:
+                params::InstanceNetworkInterfaceAttachment::Default,
+            external_ips: vec![],
+            disks: vec![],
+            affinity_groups: vec![],
+            ssh_public_keys: None,
+            start: false,
+            labels: Default::default(),
+            auto_restart_policy: Default::default(),
+        },
+    )
+    .await
+}
+
+#[nexus_test]
+async fn test_floating_ip_create(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+    populate_ip_pool(&client, "default", None).await;
+    create_project(client, PROJECT_NAME).await;
+
+    let fips_url = get_floating_ips_url(PROJECT_NAME);
+    let fips = objects_list_page_authz::<FloatingIp>(client, &fips_url).await;
+    assert!(fips.items.is_empty());
+
+    // An address is chosen from the default pool when none is requested.
+    let fip = create_floating_ip(client, "fip", PROJECT_NAME, None, None).await;
+    assert_eq!(fip.identity.name.as_str(), "fip");
+    assert_eq!(fip.instance_id, None);
+    assert_eq!(fip.ip, IpAddr::from(Ipv4Addr::new(10, 0, 0, 0)));
+
+    // An explicit address may also be requested.
+    let ip_addr: IpAddr = Ipv4Addr::new(10, 0, 12, 34).into();
+    let fip = create_floating_ip(
+        client,
+        "fip-explicit",
+        PROJECT_NAME,
+        Some(ip_addr),
+        Some("default"),
+    )
+    .await;
+    assert_eq!(fip.ip, ip_addr);
+
+    let fetched = floating_ip_get(
+        client,
+        &get_floating_ip_by_name_url("fip-explicit", PROJECT_NAME),
+    )
+    .await;
+    assert_eq!(fetched.identity.id, fip.identity.id);
+    assert_eq!(fetched.ip, ip_addr);
+
+    let fips = objects_list_page_authz::<FloatingIp>(client, &fips_url).await;
+    assert_eq!(fips.items.len(), 2);
+
+    // Names are unique within a project...
+    let error: HttpErrorResponseBody = NexusRequest::expect_failure_with_body(
+        client,
+        StatusCode::BAD_REQUEST,
+        Method::POST,
+        &fips_url,
+        &params::FloatingIpCreate {
+            identity: IdentityMetadataCreateParams {
+                name: "fip".parse().unwrap(),
+                description: String::from("a duplicate"),
+            },
+            address: None,
+            pool: None,
+        },
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap()
+    .parsed_body()
+    .unwrap();
+    assert_eq!(error.message, "already exists: floating-ip \"fip\"");
+
+    // ...and addresses may not be reused.
+    let error: HttpErrorResponseBody = NexusRequest::expect_failure_with_body(
+        client,
+        StatusCode::BAD_REQUEST,
+        Method::POST,
+        &fips_url,
+        &params::FloatingIpCreate {
+            identity: IdentityMetadataCreateParams {
+                name: "fip-again".parse().unwrap(),
+                description: String::from("a reused address"),
+            },
+            address: Some(ip_addr),
+            pool: None,
+        },
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap()
+    .parsed_body()
+    .unwrap();
+    assert_eq!(error.message, "Requested external IP address not available");
+}
+
+#[nexus_test]
+async fn test_floating_ip_attach_and_detach(
+    cptestctx: &ControlPlaneTestContext,
+) {
+    let client = &cptestctx.external_client;
+    populate_ip_pool(&client, "default", None).await;
+    create_project(client, PROJECT_NAME).await;
+
+    let fip = create_floating_ip(client, "fip", PROJECT_NAME, None, None).await;
+    let instance = create_stopped_instance(client, "inst").await;
+
+    let attached = floating_ip_attach(client, "fip", "inst").await;
+    assert_eq!(attached.instance_id, Some(instance.identity.id));
+
+    // The address is reported as one of the instance's external IPs.
+    let ips_url =
+        format!("/v1/instances/inst/external-ips?project={PROJECT_NAME}");
+    let ips =
+        objects_list_page_authz::<views::ExternalIp>(client, &ips_url).await;
+    assert_eq!(ips.items.len(), 1);
+    assert_eq!(ips.items[0].ip, fip.ip);
+
+    // Attaching again to the same instance is not an error.
+    let attached = floating_ip_attach(client, "fip", "inst").await;
+    assert_eq!(attached.instance_id, Some(instance.identity.id));
+
+    // An attached Floating IP cannot be deleted.
+    let fip_url = get_floating_ip_by_name_url("fip", PROJECT_NAME);
+    let error: HttpErrorResponseBody = NexusRequest::expect_failure(
+        client,
+        StatusCode::BAD_REQUEST,
+        Method::DELETE,
+        &fip_url,
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap()
+    .parsed_body()
+    .unwrap();
+    assert_eq!(
+        error.message,
+        "Floating IP cannot be deleted while attached to an instance"
+    );
+
+    // An instance may have only one Floating or Ephemeral IP.
+    create_floating_ip(client, "fip2", PROJECT_NAME, None, None).await;
+    let error: HttpErrorResponseBody = NexusRequest::expect_failure_with_body(
+        client,
+        StatusCode::BAD_REQUEST,
+        Method::POST,
+        &format!("/v1/floating-ips/fip2/attach?project={PROJECT_NAME}"),
+        &params::FloatingIpAttach { instance: instance.identity.id.into() },
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap()
+    .parsed_body()
+    .unwrap();
+    assert_eq!(
+        error.message,
+        "cannot attach more than 1 external IPs to instance"
+    );
+
+    let detached = floating_ip_detach(client, "fip").await;
+    assert_eq!(detached.instance_id, None);
+    let ips =
+        objects_list_page_authz::<views::ExternalIp>(client, &ips_url).await;
+    assert!(ips.items.is_empty());
+
+    // Detaching an unattached Floating IP is not an error.
+    let detached = floating_ip_detach(client, "fip").await;
+    assert_eq!(detached.instance_id, None);
+
+    object_delete(client, &fip_url).await;
+    NexusRequest::expect_failure(
+        client,
+        StatusCode::NOT_FOUND,
+        Method::GET,
+        &fip_url,
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+}
+
+#[nexus_test]
+async fn test_floating_ip_survives_instance_delete(
+    cptestctx: &ControlPlaneTestContext,
+) {
+    let client = &cptestctx.external_client;
+    populate_ip_pool(&client, "default", None).await;
+    create_project(client, PROJECT_NAME).await;
+
+    let fip = create_floating_ip(client, "fip", PROJECT_NAME, None, None).await;
+    create_stopped_instance(client, "inst").await;
+    floating_ip_attach(client, "fip", "inst").await;
+
+    object_delete(
+        client,
+        &format!("/v1/instances/inst?project={PROJECT_NAME}"),
+    )
+    .await;
+
+    // The Floating IP keeps its address, and may be attached to a new
+    // instance.
+    let fip_url = get_floating_ip_by_name_url("fip", PROJECT_NAME);
+    let fetched = floating_ip_get(client, &fip_url).await;
+    assert_eq!(fetched.instance_id, None);
+    assert_eq!(fetched.ip, fip.ip);
+
+    let instance = create_stopped_instance(client, "inst2").await;
+    let attached = floating_ip_attach(client, "fip", "inst2").await;
+    assert_eq!(attached.instance_id, Some(instance.identity.id));
+    assert_eq!(attached.ip, fip.ip);
+}
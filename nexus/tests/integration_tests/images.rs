@@ -10,13 +10,20 @@ use http::StatusCode;
 use nexus_test_utils::http_testing::AuthnMode;
 use nexus_test_utils::http_testing::NexusRequest;
 use nexus_test_utils::http_testing::RequestBuilder;
+use nexus_test_utils::resource_helpers::create_local_user;
 use nexus_test_utils::resource_helpers::create_project;
+use nexus_test_utils::resource_helpers::create_silo;
+use nexus_test_utils::resource_helpers::grant_iam;
+use nexus_test_utils::resource_helpers::object_create;
+use nexus_test_utils::resource_helpers::object_delete;
+use nexus_test_utils::resource_helpers::objects_list_page_authz;
 use nexus_test_utils::resource_helpers::DiskTest;
 use nexus_test_utils_macros::nexus_test;
 use omicron_common::api::external::Disk;
 
 use omicron_common::api::external::{ByteCount, IdentityMetadataCreateParams};
-use omicron_nexus::external_api::{params, views};
+use omicron_nexus::external_api::shared::SiloRole;
+use omicron_nexus::external_api::{params, shared, views};
 
 use httptest::{matchers::*, responders::*, Expectation, ServerBuilder};
 
@@ -42,6 +49,7 @@ fn get_image_create(source: params::ImageSource) -> params::ImageCreate {
         os: "alpine".to_string(),
         version: "edge".to_string(),
         source,
+        labels: Default::default(),
     }
 }
 
@@ -358,6 +366,7 @@ async fn test_make_disk_from_image(cptestctx: &ControlPlaneTestContext) {
             image_id: alpine_image.identity.id,
         },
         size: ByteCount::from_gibibytes_u32(1),
+        labels: Default::default(),
     };
 
     let disks_url = format!("/v1/disks?project={}", PROJECT_NAME);
@@ -408,6 +417,7 @@ async fn test_make_disk_from_other_project_image_fails(
         },
         disk_source: params::DiskSource::Image { image_id: image.identity.id },
         size: ByteCount::from_gibibytes_u32(1),
+        labels: Default::default(),
     };
     let disks_url =
         format!("/v1/disks?project={}", another_project.identity.name);
@@ -469,6 +479,7 @@ async fn test_make_disk_from_image_too_small(
             image_id: alpine_image.identity.id,
         },
         size: ByteCount::from(1073741824),
+        labels: Default::default(),
     };
 
     let disks_url = format!("/v1/disks?project={}", PROJECT_NAME);
@@ -660,6 +671,7 @@ async fn test_image_from_other_project_snapshot_fails(
         },
         disk_source: params::DiskSource::Image { image_id: image.identity.id },
         size: ByteCount::from_gibibytes_u32(1),
+        labels: Default::default(),
     };
     let disk: Disk =
         NexusRequest::objects_post(client, &disks_url, &disk_create_params)
@@ -676,6 +688,7 @@ async fn test_image_from_other_project_snapshot_fails(
             description: "meow".into(),
         },
         disk: disk.identity.id.into(),
+        labels: Default::default(),
     };
     let snapshot: views::Snapshot = NexusRequest::objects_post(
         client,
@@ -709,3 +722,166 @@ async fn test_image_from_other_project_snapshot_fails(
     .unwrap();
     assert_eq!(error.message, "snapshot does not belong to this project");
 }
+
+#[nexus_test]
+async fn test_image_silo_sharing(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+    DiskTest::new(&cptestctx).await;
+
+    let server = ServerBuilder::new().run().unwrap();
+    server.expect(
+        Expectation::matching(request::method_path("HEAD", "/image.raw"))
+            .times(1..)
+            .respond_with(
+                status_code(200).append_header(
+                    "Content-Length",
+                    format!("{}", 4096 * 1000),
+                ),
+            ),
+    );
+
+    // Create a silo image, and a user in another silo who can see everything
+    // in their own silo.
+    let silo_images_url = "/v1/images";
+    let image_create_params = get_image_create(params::ImageSource::Url {
+        url: server.url("/image.raw").to_string(),
+        block_size: BLOCK_SIZE,
+    });
+    let image: views::Image =
+        object_create(client, silo_images_url, &image_create_params).await;
+
+    let other_silo = create_silo(
+        client,
+        "other-silo",
+        true,
+        shared::SiloIdentityMode::LocalOnly,
+    )
+    .await;
+    let user = create_local_user(
+        client,
+        &other_silo,
+        &"viewer".parse().unwrap(),
+        params::UserPassword::LoginDisallowed,
+    )
+    .await
+    .id;
+    grant_iam(
+        client,
+        "/v1/system/silos/other-silo",
+        SiloRole::Viewer,
+        user,
+        AuthnMode::PrivilegedUser,
+    )
+    .await;
+
+    // Until the image is shared, the other silo can't see it.
+    let image_url = format!("/v1/images/{}", image.identity.id);
+    NexusRequest::expect_failure(
+        client,
+        StatusCode::NOT_FOUND,
+        Method::GET,
+        &image_url,
+    )
+    .authn_as(AuthnMode::SiloUser(user))
+    .execute()
+    .await
+    .unwrap();
+
+    // An image can't be shared with the silo that owns it, and project images
+    // can't be shared at all.
+    let error = NexusRequest::expect_failure(
+        client,
+        StatusCode::BAD_REQUEST,
+        Method::POST,
+        "/v1/images/alpine-edge/silos/test-suite-silo",
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap()
+    .parsed_body::<dropshot::HttpErrorResponseBody>()
+    .unwrap();
+    assert_eq!(
+        error.message,
+        "an image cannot be shared with the silo that owns it"
+    );
+
+    create_project(client, PROJECT_NAME).await;
+    let project_image: views::Image = object_create(
+        client,
+        &get_project_images_url(PROJECT_NAME),
+        &image_create_params,
+    )
+    .await;
+    let error = NexusRequest::expect_failure(
+        client,
+        StatusCode::BAD_REQUEST,
+        Method::POST,
+        &format!("/v1/images/{}/silos/other-silo", project_image.identity.id),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap()
+    .parsed_body::<dropshot::HttpErrorResponseBody>()
+    .unwrap();
+    assert_eq!(
+        error.message,
+        "only silo images can be shared with other silos"
+    );
+
+    // Share the image.  Sharing it again is harmless.
+    let image_silo_url = "/v1/images/alpine-edge/silos/other-silo";
+    for _ in 0..2 {
+        let silo: views::Silo =
+            object_create(client, image_silo_url, &()).await;
+        assert_eq!(silo.identity.id, other_silo.identity.id);
+    }
+    let silos = objects_list_page_authz::<views::Silo>(
+        client,
+        "/v1/images/alpine-edge/silos",
+    )
+    .await
+    .items;
+    assert_eq!(silos.len(), 1);
+    assert_eq!(silos[0].identity.id, other_silo.identity.id);
+
+    // The other silo can now see the image, both directly and in its list of
+    // silo images, but it is still owned by this silo.
+    let shared_image = NexusRequest::object_get(client, &image_url)
+        .authn_as(AuthnMode::SiloUser(user))
+        .execute_and_parse_unwrap::<views::Image>()
+        .await;
+    assert_eq!(shared_image.identity.id, image.identity.id);
+    assert_eq!(shared_image.silo_id, image.silo_id);
+    assert_ne!(shared_image.silo_id, other_silo.identity.id);
+
+    let images = NexusRequest::object_get(client, silo_images_url)
+        .authn_as(AuthnMode::SiloUser(user))
+        .execute_and_parse_unwrap::<ResultsPage<views::Image>>()
+        .await
+        .items;
+    assert_eq!(images.len(), 1);
+    assert_eq!(images[0].identity.id, image.identity.id);
+
+    // Stop sharing the image, after which the other silo can no longer see it.
+    object_delete(client, image_silo_url).await;
+    let silos = objects_list_page_authz::<views::Silo>(
+        client,
+        "/v1/images/alpine-edge/silos",
+    )
+    .await
+    .items;
+    assert!(silos.is_empty());
+
+    NexusRequest::expect_failure(
+        client,
+        StatusCode::NOT_FOUND,
+        Method::GET,
+        &image_url,
+    )
+    .authn_as(AuthnMode::SiloUser(user))
+    .execute()
+    .await
+    .unwrap();
+}
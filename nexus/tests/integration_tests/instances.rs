@@ -30,6 +30,7 @@ use omicron_common::api::external::DiskState;
 use omicron_common::api::external::IdentityMetadataCreateParams;
 use omicron_common::api::external::IdentityMetadataUpdateParams;
 use omicron_common::api::external::Instance;
+use omicron_common::api::external::InstanceAutoRestartPolicy;
 use omicron_common::api::external::InstanceCpuCount;
 use omicron_common::api::external::InstanceNetworkInterface;
 use omicron_common::api::external::InstanceState;
@@ -49,9 +50,11 @@ use omicron_nexus::external_api::views;
 use omicron_nexus::TestInterfaces as _;
 use omicron_nexus::{external_api::params, Nexus};
 use omicron_sled_agent::sim::SledAgent;
+use omicron_test_utils::dev::poll::{wait_for_condition, CondCheckError};
 use sled_agent_client::TestInterfaces as _;
 use std::convert::TryFrom;
 use std::sync::Arc;
+use std::time::Duration;
 use uuid::Uuid;
 
 use dropshot::test_util::ClientTestContext;
@@ -236,7 +239,11 @@ async fn test_instances_create_reboot_halt(
                     params::InstanceNetworkInterfaceAttachment::Default,
                 external_ips: vec![],
                 disks: vec![],
+                affinity_groups: vec![],
+                ssh_public_keys: None,
                 start: true,
+                labels: Default::default(),
+                auto_restart_policy: Default::default(),
             }))
             .expect_status(Some(StatusCode::BAD_REQUEST)),
     )
@@ -885,7 +892,11 @@ async fn test_instances_create_stopped_start(
                 params::InstanceNetworkInterfaceAttachment::Default,
             external_ips: vec![],
             disks: vec![],
+            affinity_groups: vec![],
+            ssh_public_keys: None,
             start: false,
+            labels: Default::default(),
+            auto_restart_policy: Default::default(),
         },
     )
     .await;
@@ -961,6 +972,377 @@ async fn test_instances_delete_fails_when_running_succeeds_when_stopped(
         .unwrap();
 }
 
+#[nexus_test]
+async fn test_instance_update_cpus_and_memory(
+    cptestctx: &ControlPlaneTestContext,
+) {
+    let client = &cptestctx.external_client;
+    let apictx = &cptestctx.server.apictx();
+    let nexus = &apictx.nexus;
+    let datastore = nexus.datastore();
+    let instance_name = "just-rainsticks";
+    let instance_url = get_instance_url(instance_name);
+
+    let project_id = create_org_and_project(&client).await;
+    let opctx =
+        OpContext::for_tests(cptestctx.logctx.log.new(o!()), datastore.clone());
+
+    // Create an instance and simulate it booting.
+    let instance = create_instance(client, PROJECT_NAME, instance_name).await;
+    instance_simulate(nexus, &instance.identity.id).await;
+    let instance = instance_get(&client, &instance_url).await;
+    assert_eq!(instance.runtime.run_state, InstanceState::Running);
+
+    // Running instances cannot be resized.
+    let update = params::InstanceUpdate {
+        ncpus: InstanceCpuCount(2),
+        memory: ByteCount::from_gibibytes_u32(2),
+    };
+    let error =
+        instance_put(&client, &instance_url, &update, StatusCode::CONFLICT)
+            .await
+            .parsed_body::<HttpErrorResponseBody>()
+            .unwrap();
+    assert_eq!(
+        error.message,
+        "instance cannot be resized in state \"running\""
+    );
+
+    // Stop the instance, after which it can be resized.
+    let instance =
+        instance_post(&client, instance_name, InstanceOp::Stop).await;
+    instance_simulate(nexus, &instance.identity.id).await;
+    let instance =
+        instance_put(&client, &instance_url, &update, StatusCode::OK)
+            .await
+            .parsed_body::<Instance>()
+            .unwrap();
+    assert_eq!(instance.ncpus.0, 2);
+    assert_eq!(instance.memory, ByteCount::from_gibibytes_u32(2));
+    assert_eq!(instance.runtime.run_state, InstanceState::Stopped);
+    instances_eq(&instance, &instance_get(&client, &instance_url).await);
+
+    // The new size should be reflected in the accounting for the project.
+    let virtual_provisioning_collection = datastore
+        .virtual_provisioning_collection_get(&opctx, project_id)
+        .await
+        .unwrap();
+    assert_eq!(virtual_provisioning_collection.cpus_provisioned, 2);
+    assert_eq!(
+        virtual_provisioning_collection.ram_provisioned.0,
+        ByteCount::from_gibibytes_u32(2),
+    );
+
+    // Sizes beyond the per-instance limits are rejected.
+    let bad_update = params::InstanceUpdate {
+        ncpus: InstanceCpuCount(2),
+        memory: ByteCount::from(MIN_MEMORY_BYTES_PER_INSTANCE / 2),
+    };
+    instance_put(&client, &instance_url, &bad_update, StatusCode::BAD_REQUEST)
+        .await;
+
+    // The resized instance can be started again.
+    let instance =
+        instance_post(&client, instance_name, InstanceOp::Start).await;
+    instance_simulate(nexus, &instance.identity.id).await;
+    let instance = instance_get(&client, &instance_url).await;
+    assert_eq!(instance.runtime.run_state, InstanceState::Running);
+    assert_eq!(instance.ncpus.0, 2);
+
+    // Deleting the instance releases its new size.
+    let instance =
+        instance_post(&client, instance_name, InstanceOp::Stop).await;
+    instance_simulate(nexus, &instance.identity.id).await;
+    expect_instance_deletion_ok(client, &instance_url).await;
+    let virtual_provisioning_collection = datastore
+        .virtual_provisioning_collection_get(&opctx, project_id)
+        .await
+        .unwrap();
+    assert_eq!(virtual_provisioning_collection.cpus_provisioned, 0);
+    assert_eq!(virtual_provisioning_collection.ram_provisioned.to_bytes(), 0);
+}
+
+#[nexus_test]
+async fn test_instance_user_data_update(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+    let apictx = &cptestctx.server.apictx();
+    let nexus = &apictx.nexus;
+    let datastore = nexus.datastore();
+    let instance_name = "just-rainsticks";
+    let user_data_url = format!(
+        "/v1/instances/{}/user-data?{}",
+        instance_name,
+        get_project_selector()
+    );
+
+    create_org_and_project(&client).await;
+    let opctx =
+        OpContext::for_tests(cptestctx.logctx.log.new(o!()), datastore.clone());
+
+    // Create an instance and simulate it booting.
+    let instance = create_instance(client, PROJECT_NAME, instance_name).await;
+    instance_simulate(nexus, &instance.identity.id).await;
+
+    // Unlike its size, a running instance's user data can be replaced.
+    let user_data = b"#cloud-config\nhostname: rainsticks\n".to_vec();
+    NexusRequest::new(
+        RequestBuilder::new(client, Method::PUT, &user_data_url)
+            .body(Some(&params::InstanceUserDataUpdate {
+                user_data: user_data.clone(),
+            }))
+            .expect_status(Some(StatusCode::OK)),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+
+    let (.., db_instance) = LookupPath::new(&opctx, &datastore)
+        .instance_id(instance.identity.id)
+        .fetch()
+        .await
+        .unwrap();
+    assert_eq!(db_instance.user_data, user_data);
+
+    // User data is still limited to 32 KiB.
+    NexusRequest::new(
+        RequestBuilder::new(client, Method::PUT, &user_data_url)
+            .body(Some(&serde_json::json!({
+                "user_data": base64::Engine::encode(
+                    &base64::engine::general_purpose::STANDARD,
+                    vec![0; 33 * 1024],
+                ),
+            })))
+            .expect_status(Some(StatusCode::BAD_REQUEST)),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+
+    // The new user data goes into the cidata volume generated when the
+    // instance next starts.
+    let instance =
+        instance_post(&client, instance_name, InstanceOp::Stop).await;
+    instance_simulate(nexus, &instance.identity.id).await;
+    let instance =
+        instance_post(&client, instance_name, InstanceOp::Start).await;
+    instance_simulate(nexus, &instance.identity.id).await;
+    let instance =
+        instance_get(&client, &get_instance_url(instance_name)).await;
+    assert_eq!(instance.runtime.run_state, InstanceState::Running);
+}
+
+#[nexus_test]
+async fn test_instance_ssh_public_keys(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+    create_project(client, PROJECT_NAME).await;
+    populate_ip_pool(&client, "default", None).await;
+
+    // Give the user a few SSH keys.
+    let mut key_names = Vec::new();
+    for (name, public_key) in [
+        ("alpha", "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIAlpha"),
+        ("bravo", "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIBravo"),
+        ("charlie", "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAICharlie"),
+    ] {
+        let ssh_key: views::SshKey = object_create(
+            client,
+            "/v1/me/ssh-keys",
+            &params::SshKeyCreate {
+                identity: IdentityMetadataCreateParams {
+                    name: name.parse().unwrap(),
+                    description: format!("key {}", name),
+                },
+                public_key: public_key.to_string(),
+            },
+        )
+        .await;
+        key_names.push(ssh_key.identity.name.to_string());
+    }
+
+    let instance_params =
+        |name: &str, ssh_public_keys| params::InstanceCreate {
+            identity: IdentityMetadataCreateParams {
+                name: name.parse().unwrap(),
+                description: String::from("shared machine"),
+            },
+            ncpus: InstanceCpuCount::try_from(1).unwrap(),
+            memory: ByteCount::from_gibibytes_u32(1),
+            hostname: String::from("shared"),
+            user_data: vec![],
+            network_interfaces:
+                params::InstanceNetworkInterfaceAttachment::None,
+            external_ips: vec![],
+            disks: vec![],
+            affinity_groups: vec![],
+            ssh_public_keys,
+            start: false,
+            labels: Default::default(),
+            auto_restart_policy: Default::default(),
+        };
+    let instance_keys = |name: &str| {
+        let url = format!(
+            "/v1/instances/{}/ssh-public-keys?{}",
+            name,
+            get_project_selector()
+        );
+        async move {
+            objects_list_page_authz::<views::SshKey>(client, &url)
+                .await
+                .items
+                .into_iter()
+                .map(|k| k.identity.name.to_string())
+                .collect::<Vec<_>>()
+        }
+    };
+
+    // By default, all of the user's keys are chosen.
+    let url_instances = get_instances_url();
+    expect_instance_creation_ok(
+        client,
+        &url_instances,
+        &instance_params("all-keys", None),
+    )
+    .await;
+    assert_eq!(instance_keys("all-keys").await, key_names);
+
+    // Keys can be chosen explicitly, by name or by ID.
+    let charlie: views::SshKey =
+        NexusRequest::object_get(client, "/v1/me/ssh-keys/charlie")
+            .authn_as(AuthnMode::PrivilegedUser)
+            .execute()
+            .await
+            .unwrap()
+            .parsed_body()
+            .unwrap();
+    expect_instance_creation_ok(
+        client,
+        &url_instances,
+        &instance_params(
+            "some-keys",
+            Some(vec![
+                "alpha".parse::<Name>().unwrap().into(),
+                charlie.identity.id.into(),
+            ]),
+        ),
+    )
+    .await;
+    assert_eq!(instance_keys("some-keys").await, vec!["alpha", "charlie"]);
+
+    // Or the instance can opt out of receiving any keys.
+    expect_instance_creation_ok(
+        client,
+        &url_instances,
+        &instance_params("no-keys", Some(vec![])),
+    )
+    .await;
+    assert!(instance_keys("no-keys").await.is_empty());
+
+    // Asking for a key the user doesn't have fails.
+    let error: HttpErrorResponseBody = NexusRequest::expect_failure_with_body(
+        client,
+        StatusCode::NOT_FOUND,
+        Method::POST,
+        &url_instances,
+        &instance_params(
+            "missing-key",
+            Some(vec!["delta".parse::<Name>().unwrap().into()]),
+        ),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap()
+    .parsed_body()
+    .unwrap();
+    assert_eq!(error.message, "not found: ssh-key with name \"delta\"");
+
+    // Deleting a key removes it from the instances it was chosen for.
+    NexusRequest::object_delete(client, "/v1/me/ssh-keys/alpha")
+        .authn_as(AuthnMode::PrivilegedUser)
+        .execute()
+        .await
+        .unwrap();
+    assert_eq!(instance_keys("some-keys").await, vec!["charlie"]);
+}
+
+#[nexus_test]
+async fn test_instance_update_beyond_sled_capacity(
+    cptestctx: &ControlPlaneTestContext,
+) {
+    let client = &cptestctx.external_client;
+    create_project(client, PROJECT_NAME).await;
+    populate_ip_pool(&client, "default", None).await;
+
+    // Create a stopped instance which uses all of the test sled's CPUs.
+    let all_cpus = InstanceCpuCount::try_from(i64::from(
+        nexus_test_utils::TEST_HARDWARE_THREADS,
+    ))
+    .unwrap();
+    let name = Name::try_from(String::from("test")).unwrap();
+    let instance_params = params::InstanceCreate {
+        identity: IdentityMetadataCreateParams {
+            name: name.clone(),
+            description: String::from("probably serving data"),
+        },
+        ncpus: all_cpus,
+        memory: ByteCount::from_gibibytes_u32(4),
+        hostname: String::from("test"),
+        user_data: vec![],
+        network_interfaces: params::InstanceNetworkInterfaceAttachment::Default,
+        external_ips: vec![],
+        disks: vec![],
+        affinity_groups: vec![],
+        ssh_public_keys: None,
+        start: false,
+        labels: Default::default(),
+        auto_restart_policy: Default::default(),
+    };
+    expect_instance_creation_ok(client, &get_instances_url(), &instance_params)
+        .await;
+    let instance_url = get_instance_url(name.as_str());
+
+    // Growing it past the sled's capacity fails, and leaves it unchanged.
+    let too_many_cpus = InstanceCpuCount::try_from(i64::from(
+        nexus_test_utils::TEST_HARDWARE_THREADS + 1,
+    ))
+    .unwrap();
+    let update = params::InstanceUpdate {
+        ncpus: too_many_cpus,
+        memory: ByteCount::from_gibibytes_u32(4),
+    };
+    instance_put(
+        &client,
+        &instance_url,
+        &update,
+        StatusCode::SERVICE_UNAVAILABLE,
+    )
+    .await;
+    let instance = instance_get(&client, &instance_url).await;
+    assert_eq!(instance.ncpus.0, all_cpus.0);
+
+    // Shrinking it frees up space for another instance.
+    let update = params::InstanceUpdate {
+        ncpus: InstanceCpuCount(1),
+        memory: ByteCount::from_gibibytes_u32(4),
+    };
+    instance_put(&client, &instance_url, &update, StatusCode::OK).await;
+    let instance_params = params::InstanceCreate {
+        identity: IdentityMetadataCreateParams {
+            name: "test2".parse().unwrap(),
+            description: String::from("probably serving data"),
+        },
+        ncpus: InstanceCpuCount::try_from(i64::from(
+            nexus_test_utils::TEST_HARDWARE_THREADS - 1,
+        ))
+        .unwrap(),
+        ..instance_params
+    };
+    expect_instance_creation_ok(client, &get_instances_url(), &instance_params)
+        .await;
+}
+
 #[nexus_test]
 async fn test_instances_invalid_creation_returns_bad_request(
     cptestctx: &ControlPlaneTestContext,
@@ -1041,6 +1423,7 @@ async fn test_instance_using_image_from_other_project_fails(
             url: server.url("/image.raw").to_string(),
             block_size: params::BlockSize::try_from(512).unwrap(),
         },
+        labels: Default::default(),
     };
     let image =
         NexusRequest::objects_post(client, &images_url, &image_create_params)
@@ -1076,9 +1459,14 @@ async fn test_instance_using_image_from_other_project_fails(
                             image_id: image.identity.id,
                         },
                         size: ByteCount::from_gibibytes_u32(4),
+                        labels: Default::default(),
                     },
                 )],
+                affinity_groups: vec![],
+                ssh_public_keys: None,
                 start: true,
+                labels: Default::default(),
+                auto_restart_policy: Default::default(),
             }))
             .expect_status(Some(StatusCode::BAD_REQUEST)),
     )
@@ -1140,7 +1528,11 @@ async fn test_instance_create_saga_removes_instance_database_record(
         network_interfaces: interface_params.clone(),
         external_ips: vec![],
         disks: vec![],
+        affinity_groups: vec![],
+        ssh_public_keys: None,
         start: true,
+        labels: Default::default(),
+        auto_restart_policy: Default::default(),
     };
     let response = NexusRequest::objects_post(
         client,
@@ -1167,7 +1559,11 @@ async fn test_instance_create_saga_removes_instance_database_record(
         network_interfaces: interface_params,
         external_ips: vec![],
         disks: vec![],
+        affinity_groups: vec![],
+        ssh_public_keys: None,
         start: true,
+        labels: Default::default(),
+        auto_restart_policy: Default::default(),
     };
     let _ = NexusRequest::objects_post(
         client,
@@ -1255,7 +1651,11 @@ async fn test_instance_with_single_explicit_ip_address(
         network_interfaces: interface_params,
         external_ips: vec![],
         disks: vec![],
+        affinity_groups: vec![],
+        ssh_public_keys: None,
         start: true,
+        labels: Default::default(),
+        auto_restart_policy: Default::default(),
     };
     let response = NexusRequest::objects_post(
         client,
@@ -1368,7 +1768,11 @@ async fn test_instance_with_new_custom_network_interfaces(
         network_interfaces: interface_params,
         external_ips: vec![],
         disks: vec![],
+        affinity_groups: vec![],
+        ssh_public_keys: None,
         start: true,
+        labels: Default::default(),
+        auto_restart_policy: Default::default(),
     };
     let response = NexusRequest::objects_post(
         client,
@@ -1481,7 +1885,11 @@ async fn test_instance_create_delete_network_interface(
         network_interfaces: params::InstanceNetworkInterfaceAttachment::None,
         external_ips: vec![],
         disks: vec![],
+        affinity_groups: vec![],
+        ssh_public_keys: None,
         start: true,
+        labels: Default::default(),
+        auto_restart_policy: Default::default(),
     };
     let response = NexusRequest::objects_post(
         client,
@@ -1721,7 +2129,11 @@ async fn test_instance_update_network_interfaces(
         network_interfaces: params::InstanceNetworkInterfaceAttachment::None,
         external_ips: vec![],
         disks: vec![],
+        affinity_groups: vec![],
+        ssh_public_keys: None,
         start: true,
+        labels: Default::default(),
+        auto_restart_policy: Default::default(),
     };
     let response = NexusRequest::objects_post(
         client,
@@ -2113,7 +2525,11 @@ async fn test_instance_with_multiple_nics_unwinds_completely(
         network_interfaces: interface_params,
         external_ips: vec![],
         disks: vec![],
+        affinity_groups: vec![],
+        ssh_public_keys: None,
         start: true,
+        labels: Default::default(),
+        auto_restart_policy: Default::default(),
     };
     let builder =
         RequestBuilder::new(client, http::Method::POST, &get_instances_url())
@@ -2182,7 +2598,11 @@ async fn test_attach_one_disk_to_instance(cptestctx: &ControlPlaneTestContext) {
                 name: Name::try_from(String::from("probablydata")).unwrap(),
             },
         )],
+        affinity_groups: vec![],
+        ssh_public_keys: None,
         start: true,
+        labels: Default::default(),
+        auto_restart_policy: Default::default(),
     };
 
     let builder =
@@ -2248,6 +2668,7 @@ async fn test_instance_create_attach_disks(
                 disk_source: params::DiskSource::Blank {
                     block_size: params::BlockSize::try_from(512).unwrap(),
                 },
+                labels: Default::default(),
             }),
             params::InstanceDiskAttachment::Attach(
                 params::InstanceDiskAttach {
@@ -2255,7 +2676,11 @@ async fn test_instance_create_attach_disks(
                 },
             ),
         ],
+        affinity_groups: vec![],
+        ssh_public_keys: None,
         start: true,
+        labels: Default::default(),
+        auto_restart_policy: Default::default(),
     };
 
     let builder =
@@ -2342,6 +2767,7 @@ async fn test_instance_create_attach_disks_undo(
                 disk_source: params::DiskSource::Blank {
                     block_size: params::BlockSize::try_from(512).unwrap(),
                 },
+                labels: Default::default(),
             }),
             params::InstanceDiskAttachment::Attach(
                 params::InstanceDiskAttach { name: regular_disk.identity.name },
@@ -2350,7 +2776,11 @@ async fn test_instance_create_attach_disks_undo(
                 params::InstanceDiskAttach { name: faulted_disk.identity.name },
             ),
         ],
+        affinity_groups: vec![],
+        ssh_public_keys: None,
         start: true,
+        labels: Default::default(),
+        auto_restart_policy: Default::default(),
     };
 
     let builder =
@@ -2426,7 +2856,10 @@ async fn test_attach_eight_disks_to_instance(
                 )
             })
             .collect(),
+        affinity_groups: vec![],
+        ssh_public_keys: None,
         start: true,
+        auto_restart_policy: Default::default(),
     };
 
     let builder =
@@ -2506,7 +2939,10 @@ async fn test_cannot_attach_nine_disks_to_instance(
                 )
             })
             .collect(),
+        affinity_groups: vec![],
+        ssh_public_keys: None,
         start: true,
+        auto_restart_policy: Default::default(),
     };
 
     let url_instances = format!("/v1/instances?project={}", project_name);
@@ -2600,7 +3036,10 @@ async fn test_cannot_attach_faulted_disks(cptestctx: &ControlPlaneTestContext) {
                 )
             })
             .collect(),
+        affinity_groups: vec![],
+        ssh_public_keys: None,
         start: true,
+        auto_restart_policy: Default::default(),
     };
 
     let builder =
@@ -2683,7 +3122,10 @@ async fn test_disks_detached_when_instance_destroyed(
                 )
             })
             .collect(),
+        affinity_groups: vec![],
+        ssh_public_keys: None,
         start: true,
+        auto_restart_policy: Default::default(),
     };
 
     let builder =
@@ -2767,7 +3209,10 @@ async fn test_disks_detached_when_instance_destroyed(
                 )
             })
             .collect(),
+        affinity_groups: vec![],
+        ssh_public_keys: None,
         start: true,
+        auto_restart_policy: Default::default(),
     };
 
     let builder =
@@ -2819,7 +3264,11 @@ async fn test_instances_memory_rejected_less_than_min_memory_size(
         network_interfaces: params::InstanceNetworkInterfaceAttachment::Default,
         external_ips: vec![],
         disks: vec![],
+        affinity_groups: vec![],
+        ssh_public_keys: None,
         start: true,
+        labels: Default::default(),
+        auto_restart_policy: Default::default(),
     };
 
     let error = NexusRequest::new(
@@ -2868,7 +3317,11 @@ async fn test_instances_memory_not_divisible_by_min_memory_size(
         network_interfaces: params::InstanceNetworkInterfaceAttachment::Default,
         external_ips: vec![],
         disks: vec![],
+        affinity_groups: vec![],
+        ssh_public_keys: None,
         start: true,
+        labels: Default::default(),
+        auto_restart_policy: Default::default(),
     };
 
     let error = NexusRequest::new(
@@ -2917,7 +3370,11 @@ async fn test_instances_memory_greater_than_max_size(
         network_interfaces: params::InstanceNetworkInterfaceAttachment::Default,
         external_ips: vec![],
         disks: vec![],
+        affinity_groups: vec![],
+        ssh_public_keys: None,
         start: true,
+        labels: Default::default(),
+        auto_restart_policy: Default::default(),
     };
 
     let error = NexusRequest::new(
@@ -3010,7 +3467,11 @@ async fn test_cannot_provision_instance_beyond_cpu_capacity(
         network_interfaces: params::InstanceNetworkInterfaceAttachment::Default,
         external_ips: vec![],
         disks: vec![],
+        affinity_groups: vec![],
+        ssh_public_keys: None,
         start: false,
+        labels: Default::default(),
+        auto_restart_policy: Default::default(),
     };
     let url_instances = get_instances_url();
 
@@ -3067,7 +3528,11 @@ async fn test_cannot_provision_instance_beyond_cpu_limit(
         network_interfaces: params::InstanceNetworkInterfaceAttachment::Default,
         external_ips: vec![],
         disks: vec![],
+        affinity_groups: vec![],
+        ssh_public_keys: None,
         start: false,
+        labels: Default::default(),
+        auto_restart_policy: Default::default(),
     };
     let url_instances = get_instances_url();
 
@@ -3114,7 +3579,11 @@ async fn test_cannot_provision_instance_beyond_ram_capacity(
         network_interfaces: params::InstanceNetworkInterfaceAttachment::Default,
         external_ips: vec![],
         disks: vec![],
+        affinity_groups: vec![],
+        ssh_public_keys: None,
         start: false,
+        labels: Default::default(),
+        auto_restart_policy: Default::default(),
     };
     let url_instances = get_instances_url();
     expect_instance_creation_fail_unavailable(
@@ -3296,7 +3765,11 @@ async fn test_instance_ephemeral_ip_from_correct_pool(
             ),
         }],
         disks: vec![],
+        affinity_groups: vec![],
+        ssh_public_keys: None,
         start: true,
+        labels: Default::default(),
+        auto_restart_policy: Default::default(),
     };
     let response = NexusRequest::objects_post(
         client,
@@ -3368,6 +3841,7 @@ async fn test_instance_create_in_silo(cptestctx: &ControlPlaneTestContext) {
                 name: PROJECT_NAME.parse().unwrap(),
                 description: String::new(),
             },
+            labels: Default::default(),
         },
     )
     .authn_as(AuthnMode::SiloUser(user_id))
@@ -3393,7 +3867,11 @@ async fn test_instance_create_in_silo(cptestctx: &ControlPlaneTestContext) {
             pool_name: Some(Name::try_from(String::from("default")).unwrap()),
         }],
         disks: vec![],
+        affinity_groups: vec![],
+        ssh_public_keys: None,
         start: true,
+        labels: Default::default(),
+        auto_restart_policy: Default::default(),
     };
     let url_instances = format!("/v1/instances?project={}", PROJECT_NAME);
     NexusRequest::objects_post(client, &url_instances, &instance_params)
@@ -3513,6 +3991,247 @@ async fn test_instance_v2p_mappings(cptestctx: &ControlPlaneTestContext) {
     }
 }
 
+/// Waits for the simulated sled agent to serve the DNS zone named `zone_name`
+/// to the instances in the VPC with VNI `vni`, with `expected` as the address
+/// for `name`, where `None` means that the name isn't in the zone
+///
+/// The zones of VPCs are sent to sled agents by a background task, so changes
+/// to them don't reach the sled agents right away.
+async fn wait_for_vpc_dns_record(
+    cptestctx: &ControlPlaneTestContext,
+    vni: Vni,
+    zone_name: &str,
+    name: &str,
+    expected: Option<std::net::IpAddr>,
+) {
+    let sled_agent = &cptestctx.sled_agent.sled_agent;
+    wait_for_condition(
+        || async {
+            let vpc_dns = sled_agent.vpc_dns.lock().await;
+            let Some(zone) = vpc_dns.get(&vni) else {
+                return Err(CondCheckError::<()>::NotYet);
+            };
+            let ip = zone
+                .records
+                .iter()
+                .find(|record| record.name == name)
+                .map(|record| record.ip);
+            if zone.name == zone_name && ip == expected {
+                Ok(())
+            } else {
+                Err(CondCheckError::<()>::NotYet)
+            }
+        },
+        &Duration::from_millis(50),
+        &Duration::from_secs(30),
+    )
+    .await
+    .unwrap_or_else(|_| {
+        panic!(
+            "address of {:?} in zone {:?} did not become {:?}",
+            name, zone_name, expected
+        )
+    });
+}
+
+#[nexus_test]
+async fn test_instance_vpc_dns_names(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+    let nexus = &cptestctx.server.apictx().nexus;
+    let datastore = nexus.datastore();
+    let opctx =
+        OpContext::for_tests(cptestctx.logctx.log.new(o!()), datastore.clone());
+
+    populate_ip_pool(&client, "default", None).await;
+    let project = create_project(client, PROJECT_NAME).await;
+    let (.., db_vpc) = LookupPath::new(&opctx, &datastore)
+        .project_id(project.identity.id)
+        .vpc_name(&"default".parse::<Name>().unwrap().into())
+        .fetch()
+        .await
+        .unwrap();
+    let vni = db_vpc.vni.0;
+
+    // Starting an instance sends the zone of its primary interface's VPC to
+    // its sled, with the instance's name and that interface's address.
+    let instance_name = "dns-instance";
+    let instance = create_instance(client, PROJECT_NAME, instance_name).await;
+    let nics_url =
+        format!("/v1/network-interfaces?instance={}", instance.identity.id);
+    let nics =
+        objects_list_page_authz::<InstanceNetworkInterface>(client, &nics_url)
+            .await
+            .items;
+    assert_eq!(nics.len(), 1);
+    let nic_ip = nics[0].ip;
+    let zone_name =
+        format!("default.{}.test-suite-silo.internal", PROJECT_NAME);
+    wait_for_vpc_dns_record(
+        cptestctx,
+        vni,
+        &zone_name,
+        instance_name,
+        Some(nic_ip),
+    )
+    .await;
+
+    // Another instance in the same VPC is added to the zone that's served to
+    // the first.
+    let peer_name = "dns-peer";
+    let peer = create_instance(client, PROJECT_NAME, peer_name).await;
+    let nics_url =
+        format!("/v1/network-interfaces?instance={}", peer.identity.id);
+    let peer_ip =
+        objects_list_page_authz::<InstanceNetworkInterface>(client, &nics_url)
+            .await
+            .items[0]
+            .ip;
+    wait_for_vpc_dns_record(
+        cptestctx,
+        vni,
+        &zone_name,
+        peer_name,
+        Some(peer_ip),
+    )
+    .await;
+
+    // Renaming the VPC renames its zone.
+    let _: views::Vpc = NexusRequest::object_put(
+        client,
+        &format!("/v1/vpcs/default?{}", get_project_selector()),
+        Some(&params::VpcUpdate {
+            identity: IdentityMetadataUpdateParams {
+                name: Some("renamed".parse().unwrap()),
+                description: None,
+            },
+            dns_name: None,
+            labels: None,
+        }),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap()
+    .parsed_body()
+    .unwrap();
+    let zone_name =
+        format!("renamed.{}.test-suite-silo.internal", PROJECT_NAME);
+    wait_for_vpc_dns_record(
+        cptestctx,
+        vni,
+        &zone_name,
+        instance_name,
+        Some(nic_ip),
+    )
+    .await;
+
+    // Deleting an instance removes its name.
+    instance_simulate(nexus, &instance.identity.id).await;
+    instance_post(&client, instance_name, InstanceOp::Stop).await;
+    instance_simulate(nexus, &instance.identity.id).await;
+    NexusRequest::object_delete(client, &get_instance_url(instance_name))
+        .authn_as(AuthnMode::PrivilegedUser)
+        .execute()
+        .await
+        .unwrap();
+    wait_for_vpc_dns_record(cptestctx, vni, &zone_name, instance_name, None)
+        .await;
+    wait_for_vpc_dns_record(
+        cptestctx,
+        vni,
+        &zone_name,
+        peer_name,
+        Some(peer_ip),
+    )
+    .await;
+}
+
+#[nexus_test]
+async fn test_instance_auto_restart(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+    let nexus = &cptestctx.server.apictx().nexus;
+
+    // Start a second sled, so that the instance has somewhere else to go
+    // when it fails.
+    let other_sled_id = Uuid::new_v4();
+    let _other_sled = start_sled_agent(
+        cptestctx.logctx.log.new(o!("sled_id" => other_sled_id.to_string())),
+        cptestctx.server.get_http_server_internal_address().await,
+        other_sled_id,
+        &Utf8Path::new("/should/be/unused"),
+        omicron_sled_agent::sim::SimMode::Explicit,
+    )
+    .await
+    .unwrap();
+
+    populate_ip_pool(&client, "default", None).await;
+    create_project(client, PROJECT_NAME).await;
+    let instance_name = "phoenix";
+    let instance: Instance = object_create(
+        client,
+        &get_instances_url(),
+        &params::InstanceCreate {
+            identity: IdentityMetadataCreateParams {
+                name: instance_name.parse().unwrap(),
+                description: String::from("rises from its ashes"),
+            },
+            ncpus: InstanceCpuCount(2),
+            memory: ByteCount::from_gibibytes_u32(1),
+            hostname: String::from("phoenix"),
+            user_data: vec![],
+            network_interfaces:
+                params::InstanceNetworkInterfaceAttachment::Default,
+            external_ips: vec![],
+            disks: vec![],
+            affinity_groups: vec![],
+            ssh_public_keys: None,
+            start: true,
+            labels: Default::default(),
+            auto_restart_policy: InstanceAutoRestartPolicy::BestEffort,
+        },
+    )
+    .await;
+    let instance_id = instance.identity.id;
+    assert_eq!(
+        instance.auto_restart_policy,
+        InstanceAutoRestartPolicy::BestEffort
+    );
+    assert_eq!(instance.auto_restart_attempts, 0);
+    assert_eq!(instance.time_last_auto_restarted, None);
+    instance_simulate(nexus, &instance_id).await;
+    let instance_url = get_instance_url(instance_name);
+    let instance = instance_get(&client, &instance_url).await;
+    assert_eq!(instance.runtime.run_state, InstanceState::Running);
+    let failed_sled_id = nexus.instance_sled_id(&instance_id).await.unwrap();
+
+    // Once the instance fails, it's restarted on the other sled.
+    assert!(nexus.set_instance_as_failed(&instance_id).await.unwrap());
+    nexus.activate_instance_auto_restart();
+    let instance = wait_for_condition(
+        || async {
+            let instance = instance_get(&client, &instance_url).await;
+            if instance.runtime.run_state == InstanceState::Failed {
+                Err(CondCheckError::<()>::NotYet)
+            } else {
+                Ok(instance)
+            }
+        },
+        &Duration::from_millis(50),
+        &Duration::from_secs(30),
+    )
+    .await
+    .expect("failed instance was not restarted");
+    assert_eq!(instance.runtime.run_state, InstanceState::Starting);
+    assert_eq!(instance.auto_restart_attempts, 1);
+    assert!(instance.time_last_auto_restarted.is_some());
+    let sled_id = nexus.instance_sled_id(&instance_id).await.unwrap();
+    assert_ne!(sled_id, failed_sled_id);
+
+    instance_simulate_on_sled(cptestctx, nexus, sled_id, instance_id).await;
+    let instance = instance_get(&client, &instance_url).await;
+    assert_eq!(instance.runtime.run_state, InstanceState::Running);
+}
+
 async fn instance_get(
     client: &ClientTestContext,
     instance_url: &str,
@@ -3573,6 +4292,23 @@ pub async fn instance_post(
     .unwrap()
 }
 
+async fn instance_put(
+    client: &ClientTestContext,
+    instance_url: &str,
+    params: &params::InstanceUpdate,
+    status: StatusCode,
+) -> dropshot::test_util::TestResponse {
+    NexusRequest::new(
+        RequestBuilder::new(client, Method::PUT, instance_url)
+            .body(Some(params))
+            .expect_status(Some(status)),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap()
+}
+
 fn instances_eq(instance1: &Instance, instance2: &Instance) {
     identity_eq(&instance1.identity, &instance2.identity);
     assert_eq!(instance1.project_id, instance2.project_id);
@@ -12,21 +12,26 @@ use nexus_test_utils::http_testing::AuthnMode;
 use nexus_test_utils::http_testing::NexusRequest;
 use nexus_test_utils::http_testing::RequestBuilder;
 use nexus_test_utils::resource_helpers::create_project;
+use nexus_test_utils::resource_helpers::create_silo;
 use nexus_test_utils::resource_helpers::objects_list_page_authz;
 use nexus_test_utils::resource_helpers::{
     create_instance, create_instance_with,
 };
 use nexus_test_utils_macros::nexus_test;
 use omicron_common::api::external::IdentityMetadataUpdateParams;
+use omicron_common::api::external::NameOrId;
 use omicron_common::api::external::{IdentityMetadataCreateParams, Name};
 use omicron_nexus::external_api::params::ExternalIpCreate;
 use omicron_nexus::external_api::params::InstanceDiskAttachment;
 use omicron_nexus::external_api::params::InstanceNetworkInterfaceAttachment;
 use omicron_nexus::external_api::params::IpPoolCreate;
+use omicron_nexus::external_api::params::IpPoolSetDefault;
+use omicron_nexus::external_api::params::IpPoolSiloLink;
 use omicron_nexus::external_api::params::IpPoolUpdate;
 use omicron_nexus::external_api::shared::IpRange;
 use omicron_nexus::external_api::shared::Ipv4Range;
 use omicron_nexus::external_api::shared::Ipv6Range;
+use omicron_nexus::external_api::shared::SiloIdentityMode;
 use omicron_nexus::external_api::views::IpPool;
 use omicron_nexus::external_api::views::IpPoolRange;
 use omicron_nexus::TestInterfaces;
@@ -102,6 +107,8 @@ async fn test_ip_pool_basic_crud(cptestctx: &ControlPlaneTestContext) {
             name: String::from(pool_name).parse().unwrap(),
             description: String::from(description),
         },
+        silo: None,
+        is_default: false,
     };
     let created_pool: IpPool =
         NexusRequest::objects_post(client, ip_pools_url, &params)
@@ -297,6 +304,8 @@ async fn test_ip_pool_range_overlapping_ranges_fails(
             name: String::from(pool_name).parse().unwrap(),
             description: String::from(description),
         },
+        silo: None,
+        is_default: false,
     };
     let created_pool: IpPool =
         NexusRequest::objects_post(client, ip_pools_url, &params)
@@ -478,6 +487,8 @@ async fn test_ip_pool_range_pagination(cptestctx: &ControlPlaneTestContext) {
             name: String::from(pool_name).parse().unwrap(),
             description: String::from(description),
         },
+        silo: None,
+        is_default: false,
     };
     let created_pool: IpPool =
         NexusRequest::objects_post(client, ip_pools_url, &params)
@@ -614,6 +625,8 @@ async fn test_ip_pool_list_usable_by_project(
             name: String::from(mypool_name).parse().unwrap(),
             description: String::from("right on cue"),
         },
+        silo: None,
+        is_default: false,
     };
     NexusRequest::objects_post(client, ip_pools_url, &params)
         .authn_as(AuthnMode::PrivilegedUser)
@@ -675,8 +688,28 @@ async fn test_ip_pool_list_usable_by_project(
         created_range.range.last_address()
     );
 
-    // TODO: add non-service, ip pools that the project *can't* use, when that
-    // functionality is implemented in the future (i.e. a "notmypool")
+    // add a pool linked to another silo, which the project *can't* use
+    let other_silo =
+        create_silo(client, "other-silo", true, SiloIdentityMode::LocalOnly)
+            .await;
+    let params = IpPoolCreate {
+        identity: IdentityMetadataCreateParams {
+            name: "notmypool".parse().unwrap(),
+            description: String::from("not for you"),
+        },
+        silo: Some(NameOrId::Id(other_silo.identity.id)),
+        is_default: true,
+    };
+    let notmypool: IpPool =
+        NexusRequest::objects_post(client, ip_pools_url, &params)
+            .authn_as(AuthnMode::PrivilegedUser)
+            .execute()
+            .await
+            .unwrap()
+            .parsed_body()
+            .unwrap();
+    assert_eq!(notmypool.silo_id, Some(other_silo.identity.id));
+    assert!(notmypool.is_default);
 
     let list_url = format!("{}?project={}", scoped_ip_pools_url, PROJECT_NAME);
     let list = NexusRequest::iter_collection_authn::<IpPool>(
@@ -710,6 +743,33 @@ async fn test_ip_pool_list_usable_by_project(
         assert_eq!(pool.identity.name.as_str(), pool_name.as_str());
     }
 
+    // but not the pool linked to another silo
+    NexusRequest::expect_failure(
+        client,
+        StatusCode::NOT_FOUND,
+        Method::GET,
+        &format!("{}/notmypool?project={}", scoped_ip_pools_url, PROJECT_NAME),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+
+    // the other silo can't be deleted while the pool is linked to it
+    let error: HttpErrorResponseBody = NexusRequest::expect_failure(
+        client,
+        StatusCode::BAD_REQUEST,
+        Method::DELETE,
+        "/v1/system/silos/other-silo",
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap()
+    .parsed_body()
+    .unwrap();
+    assert_eq!(error.message, "silo to be deleted has linked IP pools");
+
     // ensure we can successfully create an instance with each of the pools we
     // should be able to access
     for pool_name in pool_names {
@@ -830,6 +890,116 @@ async fn test_ip_range_delete_with_allocated_external_ip_fails(
     );
 }
 
+#[nexus_test]
+async fn test_ip_pool_silo_link(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+
+    let silo =
+        create_silo(client, "link-silo", true, SiloIdentityMode::LocalOnly)
+            .await;
+    for pool_name in ["pool1", "pool2"] {
+        let params = IpPoolCreate {
+            identity: IdentityMetadataCreateParams {
+                name: pool_name.parse().unwrap(),
+                description: String::from("a pool to link"),
+            },
+            silo: None,
+            is_default: false,
+        };
+        NexusRequest::objects_post(client, "/v1/system/ip-pools", &params)
+            .authn_as(AuthnMode::PrivilegedUser)
+            .execute()
+            .await
+            .unwrap();
+    }
+
+    // Link the first pool to the Silo as its default pool.
+    let link = IpPoolSiloLink {
+        silo: NameOrId::Name(silo.identity.name.clone()),
+        is_default: true,
+    };
+    let pool = ip_pool_action(client, "pool1", "link", Some(&link)).await;
+    assert_eq!(pool.silo_id, Some(silo.identity.id));
+    assert!(pool.is_default);
+
+    // The Silo can't have two default pools.
+    let error = ip_pool_action_expect_failure(
+        client,
+        "pool2",
+        "link",
+        Some(&link),
+        StatusCode::BAD_REQUEST,
+    )
+    .await;
+    assert_eq!(error.message, "the Silo already has a default IP pool");
+
+    // Once the first pool isn't the default, the second can be.
+    let not_default = IpPoolSetDefault { is_default: false };
+    let pool =
+        ip_pool_action(client, "pool1", "set-default", Some(&not_default))
+            .await;
+    assert_eq!(pool.silo_id, Some(silo.identity.id));
+    assert!(!pool.is_default);
+    let pool = ip_pool_action(client, "pool2", "link", Some(&link)).await;
+    assert_eq!(pool.silo_id, Some(silo.identity.id));
+    assert!(pool.is_default);
+
+    // Unlinking the second pool makes it available to every Silo, and no
+    // longer a default pool.
+    let pool = ip_pool_action(client, "pool2", "unlink", None::<&()>).await;
+    assert_eq!(pool.silo_id, None);
+    assert!(!pool.is_default);
+    let error = ip_pool_action_expect_failure(
+        client,
+        "pool2",
+        "unlink",
+        None::<&()>,
+        StatusCode::BAD_REQUEST,
+    )
+    .await;
+    assert_eq!(error.message, "the IP pool is not linked to a Silo");
+
+    // The fleet already has a default pool, too.
+    let default = IpPoolSetDefault { is_default: true };
+    let error = ip_pool_action_expect_failure(
+        client,
+        "pool2",
+        "set-default",
+        Some(&default),
+        StatusCode::BAD_REQUEST,
+    )
+    .await;
+    assert_eq!(error.message, "the fleet already has a default IP pool");
+
+    // Pools can't be linked to Silos that don't exist.
+    let link = IpPoolSiloLink {
+        silo: NameOrId::Name("no-such-silo".parse().unwrap()),
+        is_default: false,
+    };
+    ip_pool_action_expect_failure(
+        client,
+        "pool2",
+        "link",
+        Some(&link),
+        StatusCode::NOT_FOUND,
+    )
+    .await;
+
+    // The pool for Oxide services can't be linked to a Silo.
+    let link = IpPoolSiloLink {
+        silo: NameOrId::Name(silo.identity.name.clone()),
+        is_default: false,
+    };
+    ip_pool_action_expect_failure(
+        client,
+        omicron_nexus::db::datastore::SERVICE_IP_POOL_NAME,
+        "link",
+        Some(&link),
+        StatusCode::NOT_FOUND,
+    )
+    .await;
+}
+
 #[nexus_test]
 async fn test_ip_pool_service(cptestctx: &ControlPlaneTestContext) {
     let client = &cptestctx.external_client;
@@ -935,6 +1105,49 @@ async fn test_ip_pool_service(cptestctx: &ControlPlaneTestContext) {
     }
 }
 
+/// Posts to one of the link endpoints (`link`, `unlink` or `set-default`) of
+/// the named IP pool
+async fn ip_pool_action<B: serde::Serialize>(
+    client: &ClientTestContext,
+    pool_name: &str,
+    action: &str,
+    body: Option<&B>,
+) -> IpPool {
+    let url = format!("/v1/system/ip-pools/{}/{}", pool_name, action);
+    NexusRequest::new(
+        RequestBuilder::new(client, Method::POST, &url)
+            .body(body)
+            .expect_status(Some(StatusCode::OK)),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap()
+    .parsed_body()
+    .unwrap()
+}
+
+async fn ip_pool_action_expect_failure<B: serde::Serialize>(
+    client: &ClientTestContext,
+    pool_name: &str,
+    action: &str,
+    body: Option<&B>,
+    expected_status: StatusCode,
+) -> HttpErrorResponseBody {
+    let url = format!("/v1/system/ip-pools/{}/{}", pool_name, action);
+    NexusRequest::new(
+        RequestBuilder::new(client, Method::POST, &url)
+            .body(body)
+            .expect_status(Some(expected_status)),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap()
+    .parsed_body()
+    .unwrap()
+}
+
 fn assert_pools_eq(first: &IpPool, second: &IpPool) {
     assert_eq!(first.identity, second.identity);
 }
@@ -0,0 +1,210 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Tests for user-defined labels on resources
+
+use dropshot::test_util::ClientTestContext;
+use http::Method;
+use http::StatusCode;
+use nexus_test_utils::http_testing::AuthnMode;
+use nexus_test_utils::http_testing::NexusRequest;
+use nexus_test_utils::resource_helpers::create_disk;
+use nexus_test_utils::resource_helpers::create_instance;
+use nexus_test_utils::resource_helpers::create_project;
+use nexus_test_utils::resource_helpers::object_create;
+use nexus_test_utils::resource_helpers::object_put;
+use nexus_test_utils::resource_helpers::objects_list_page_authz;
+use nexus_test_utils::resource_helpers::DiskTest;
+use nexus_test_utils_macros::nexus_test;
+use omicron_common::api::external::Disk;
+use omicron_common::api::external::IdentityMetadataCreateParams;
+use omicron_common::api::external::IdentityMetadataUpdateParams;
+use omicron_common::api::external::Instance;
+use omicron_common::api::external::Labels;
+use omicron_nexus::external_api::params;
+use omicron_nexus::external_api::views::Project;
+use serde_json::json;
+
+type ControlPlaneTestContext =
+    nexus_test_utils::ControlPlaneTestContext<omicron_nexus::Server>;
+
+const PROJECT_NAME: &str = "labels-project";
+
+fn labels(value: serde_json::Value) -> Labels {
+    serde_json::from_value(value).unwrap()
+}
+
+async fn list_names<T>(client: &ClientTestContext, url: &str) -> Vec<String>
+where
+    T: serde::de::DeserializeOwned + serde::Serialize,
+{
+    objects_list_page_authz::<T>(client, url)
+        .await
+        .items
+        .iter()
+        .map(|item| {
+            serde_json::to_value(item).unwrap()["name"]
+                .as_str()
+                .unwrap()
+                .to_string()
+        })
+        .collect()
+}
+
+#[nexus_test]
+async fn test_project_labels(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+
+    // Create a few projects with different labels.
+    for (name, project_labels) in [
+        ("prod-storage", json!({ "env": "prod", "team": "storage" })),
+        ("prod-compute", json!({ "env": "prod", "team": "compute" })),
+        ("dev-storage", json!({ "env": "dev", "team": "storage" })),
+    ] {
+        let project: Project = object_create(
+            client,
+            "/v1/projects",
+            &params::ProjectCreate {
+                identity: IdentityMetadataCreateParams {
+                    name: name.parse().unwrap(),
+                    description: String::from("labeled"),
+                },
+                labels: labels(project_labels.clone()),
+            },
+        )
+        .await;
+        assert_eq!(project.labels, labels(project_labels));
+    }
+    let unlabeled = create_project(client, "unlabeled").await;
+    assert!(unlabeled.labels.is_empty());
+
+    // A resource matches a selector if it has all of the selector's labels.
+    let url = "/v1/projects?label_selector=env=prod";
+    assert_eq!(
+        list_names::<Project>(client, url).await,
+        vec!["prod-compute", "prod-storage"]
+    );
+    let url = "/v1/projects?label_selector=env=prod,team=storage";
+    assert_eq!(list_names::<Project>(client, url).await, vec!["prod-storage"]);
+    let url = "/v1/projects?label_selector=env=staging";
+    assert!(list_names::<Project>(client, url).await.is_empty());
+    let url = "/v1/projects?label_selector=";
+    assert_eq!(list_names::<Project>(client, url).await.len(), 4);
+
+    // Updating a project's labels replaces all of them, while an update that
+    // doesn't mention labels leaves them alone.
+    let project_url = "/v1/projects/dev-storage";
+    let project: Project = object_put(
+        client,
+        project_url,
+        &params::ProjectUpdate {
+            identity: IdentityMetadataUpdateParams {
+                name: None,
+                description: None,
+            },
+            labels: Some(labels(json!({ "env": "prod" }))),
+        },
+    )
+    .await;
+    assert_eq!(project.labels, labels(json!({ "env": "prod" })));
+    let project: Project = object_put(
+        client,
+        project_url,
+        &params::ProjectUpdate {
+            identity: IdentityMetadataUpdateParams {
+                name: None,
+                description: Some(String::from("relabeled")),
+            },
+            labels: None,
+        },
+    )
+    .await;
+    assert_eq!(project.labels, labels(json!({ "env": "prod" })));
+    let url = "/v1/projects?label_selector=env=prod";
+    assert_eq!(
+        list_names::<Project>(client, url).await,
+        vec!["dev-storage", "prod-compute", "prod-storage"]
+    );
+
+    // Invalid labels and selectors are rejected.
+    for bad_labels in [
+        json!({ "Env": "prod" }),
+        json!({ "env": "has spaces" }),
+        json!({ "1env": "prod" }),
+        json!({ "env": 5 }),
+    ] {
+        NexusRequest::expect_failure_with_body(
+            client,
+            StatusCode::BAD_REQUEST,
+            Method::POST,
+            "/v1/projects",
+            &json!({
+                "name": "bad-labels",
+                "description": "",
+                "labels": bad_labels,
+            }),
+        )
+        .authn_as(AuthnMode::PrivilegedUser)
+        .execute()
+        .await
+        .unwrap();
+    }
+    for bad_selector in ["env", "env=prod,env=dev", "Env=prod"] {
+        NexusRequest::expect_failure(
+            client,
+            StatusCode::BAD_REQUEST,
+            Method::GET,
+            &format!("/v1/projects?label_selector={}", bad_selector),
+        )
+        .authn_as(AuthnMode::PrivilegedUser)
+        .execute()
+        .await
+        .unwrap();
+    }
+}
+
+#[nexus_test]
+async fn test_disk_and_instance_labels(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+    DiskTest::new(&cptestctx).await;
+    create_project(client, PROJECT_NAME).await;
+
+    // Disks are created without labels and can be labeled afterwards.
+    create_disk(client, PROJECT_NAME, "disk-a").await;
+    create_disk(client, PROJECT_NAME, "disk-b").await;
+    let disk: Disk = object_put(
+        client,
+        &format!("/v1/disks/disk-a/labels?project={}", PROJECT_NAME),
+        &labels(json!({ "tier": "fast" })),
+    )
+    .await;
+    assert_eq!(disk.labels, labels(json!({ "tier": "fast" })));
+    let url =
+        format!("/v1/disks?project={}&label_selector=tier=fast", PROJECT_NAME);
+    assert_eq!(list_names::<Disk>(client, &url).await, vec!["disk-a"]);
+
+    // Instances can be relabeled while they're running.
+    let instance = create_instance(client, PROJECT_NAME, "inst").await;
+    assert!(instance.labels.is_empty());
+    let instance_labels_url =
+        format!("/v1/instances/inst/labels?project={}", PROJECT_NAME);
+    let instance: Instance = object_put(
+        client,
+        &instance_labels_url,
+        &labels(json!({ "role": "db", "owner": "ops@example.com" })),
+    )
+    .await;
+    assert_eq!(instance.labels.get("owner"), Some("ops@example.com"));
+    let url = format!(
+        "/v1/instances?project={}&label_selector=role=db",
+        PROJECT_NAME
+    );
+    assert_eq!(list_names::<Instance>(client, &url).await, vec!["inst"]);
+
+    // Setting an empty set of labels removes all of them.
+    let instance: Instance =
+        object_put(client, &instance_labels_url, &Labels::default()).await;
+    assert!(instance.labels.is_empty());
+    assert!(list_names::<Instance>(client, &url).await.is_empty());
+}
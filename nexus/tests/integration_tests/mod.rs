@@ -4,29 +4,38 @@
 //! the way it is.
 
 mod address_lots;
+mod affinity;
+mod audit_log;
 mod authn_http;
 mod authz;
 mod basic;
+mod bgp;
 mod certificates;
 mod commands;
 mod console_api;
 mod device_auth;
 mod disks;
+mod etags;
+mod floating_ips;
 mod images;
 mod initialization;
 mod instances;
 mod ip_pools;
+mod labels;
 mod loopback_address;
 mod metrics;
+mod oidc;
 mod oximeter;
 mod pantry;
 mod password_login;
 mod projects;
+mod quotas;
 mod rack;
 mod role_assignments;
 mod roles_builtin;
 mod router_routes;
 mod saml;
+mod scim;
 mod silo_users;
 mod silos;
 mod sleds;
@@ -41,9 +50,11 @@ mod updates;
 mod users_builtin;
 mod volume_management;
 mod vpc_firewall;
+mod vpc_peerings;
 mod vpc_routers;
 mod vpc_subnets;
 mod vpcs;
+mod webhooks;
 mod zpools;
 
 // This module is used only for shared data, not test cases.
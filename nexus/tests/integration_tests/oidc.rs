@@ -0,0 +1,358 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Tests for OpenID Connect identity providers
+
+use nexus_test_utils::assert_same_items;
+use nexus_test_utils::http_testing::{AuthnMode, NexusRequest, RequestBuilder};
+use nexus_test_utils::resource_helpers::{create_silo, object_create};
+use nexus_test_utils_macros::nexus_test;
+use omicron_common::api::external::IdentityMetadataCreateParams;
+use omicron_nexus::external_api::views;
+use omicron_nexus::external_api::{params, shared};
+
+use base64::Engine;
+use dropshot::ResultsPage;
+use http::method::Method;
+use http::StatusCode;
+use httptest::{matchers::*, responders::*, Expectation, Server};
+use openssl::hash::MessageDigest;
+use openssl::pkey::{PKey, Private};
+use openssl::rsa::Rsa;
+use openssl::sign::Signer;
+use serde_json::json;
+
+type ControlPlaneTestContext =
+    nexus_test_utils::ControlPlaneTestContext<omicron_nexus::Server>;
+
+/// A key set with one (arbitrary) P-256 public key
+pub const OIDC_JWKS: &str = r#"{"keys":[{"kty":"EC","crv":"P-256","kid":"test","use":"sig","x":"f83OJ3D2xF1Bg8vub9tLe1gHMzV76e8Tus9uPHvRVEU","y":"x_FEzRu9m36HLN_tue659LNpXW6pCyStikYjKIWI5a0"}]}"#;
+
+const SILO_NAME: &str = "oidc-silo";
+const PROVIDER_NAME: &str = "some-totally-real-oidc-provider";
+const CLIENT_ID: &str = "nexus-client";
+const AUTHORIZATION_CODE: &str = "the-code";
+
+fn encode(bytes: &[u8]) -> String {
+    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// A fake OpenID Connect provider
+struct FakeIdp {
+    server: Server,
+    issuer: String,
+    key: PKey<Private>,
+}
+
+impl FakeIdp {
+    fn new() -> FakeIdp {
+        let server = Server::run();
+        let issuer = format!("http://{}", server.addr());
+        let rsa = Rsa::generate(2048).unwrap();
+        let jwks = json!({
+            "keys": [{
+                "kty": "RSA",
+                "kid": "test-key",
+                "use": "sig",
+                "alg": "RS256",
+                "n": encode(&rsa.n().to_vec()),
+                "e": encode(&rsa.e().to_vec()),
+            }]
+        });
+        let discovery = json!({
+            "issuer": issuer,
+            "authorization_endpoint": format!("{}/authorize", issuer),
+            "token_endpoint": format!("{}/token", issuer),
+            "jwks_uri": format!("{}/jwks", issuer),
+        });
+
+        server.expect(
+            Expectation::matching(request::method_path(
+                "GET",
+                "/.well-known/openid-configuration",
+            ))
+            .respond_with(json_encoded(discovery)),
+        );
+        server.expect(
+            Expectation::matching(request::method_path("GET", "/jwks"))
+                .respond_with(json_encoded(jwks)),
+        );
+
+        FakeIdp { server, issuer, key: PKey::from_rsa(rsa).unwrap() }
+    }
+
+    fn sign_id_token(&self, claims: serde_json::Value) -> String {
+        let header = json!({ "alg": "RS256", "kid": "test-key", "typ": "JWT" });
+        let signed = format!(
+            "{}.{}",
+            encode(header.to_string().as_bytes()),
+            encode(claims.to_string().as_bytes())
+        );
+        let mut signer =
+            Signer::new(MessageDigest::sha256(), &self.key).unwrap();
+        signer.update(signed.as_bytes()).unwrap();
+        format!("{}.{}", signed, encode(&signer.sign_to_vec().unwrap()))
+    }
+
+    fn claims(&self, nonce: &str) -> serde_json::Value {
+        let now = chrono::Utc::now().timestamp();
+        json!({
+            "iss": self.issuer,
+            "sub": "some@customer.com",
+            "aud": CLIENT_ID,
+            "iat": now,
+            "exp": now + 60,
+            "nonce": nonce,
+            "groups": ["SRE", "Admins"],
+        })
+    }
+
+    /// Expect one authorization code exchange, answered with `id_token`
+    fn expect_token_request(&self, id_token: String) {
+        self.server.expect(
+            Expectation::matching(all_of![
+                request::method_path("POST", "/token"),
+                request::body(url_decoded(contains((
+                    "code",
+                    AUTHORIZATION_CODE
+                )))),
+                request::body(url_decoded(contains(("client_id", CLIENT_ID)))),
+            ])
+            .respond_with(json_encoded(json!({
+                "access_token": "unused",
+                "token_type": "Bearer",
+                "id_token": id_token,
+            }))),
+        );
+    }
+}
+
+/// Starts a login, returning the nonce cookie and the `state` sent to the IdP
+async fn login_begin(
+    client: &dropshot::test_util::ClientTestContext,
+) -> (String, String) {
+    let result = NexusRequest::new(
+        RequestBuilder::new(
+            client,
+            Method::GET,
+            &format!(
+                "/login/{}/oidc/{}?redirect_uri=%2Fprojects",
+                SILO_NAME, PROVIDER_NAME
+            ),
+        )
+        .expect_status(Some(StatusCode::FOUND)),
+    )
+    .execute()
+    .await
+    .expect("expected success");
+
+    let location = result.headers["Location"].to_str().unwrap();
+    let (endpoint, query) = location.split_once('?').unwrap();
+    assert!(endpoint.ends_with("/authorize"));
+    let query: Vec<(String, String)> =
+        serde_urlencoded::from_str(query).unwrap();
+    let param = |name: &str| {
+        query.iter().find(|(k, _)| k == name).map(|(_, v)| v.clone()).unwrap()
+    };
+    assert_eq!(param("response_type"), "code");
+    assert_eq!(param("client_id"), CLIENT_ID);
+    assert_eq!(param("scope"), "openid groups");
+
+    let cookie = result.headers["Set-Cookie"].to_str().unwrap();
+    let cookie = cookie.split(';').next().unwrap().to_string();
+    assert_eq!(cookie, format!("oidc-nonce={}", param("nonce")));
+    (cookie, param("state"))
+}
+
+async fn login_callback(
+    client: &dropshot::test_util::ClientTestContext,
+    cookie: &str,
+    state: &str,
+    expected_status: StatusCode,
+) -> nexus_test_utils::http_testing::TestResponse {
+    NexusRequest::new(
+        RequestBuilder::new(
+            client,
+            Method::GET,
+            &format!(
+                "/login/{}/oidc/{}/callback?{}",
+                SILO_NAME,
+                PROVIDER_NAME,
+                serde_urlencoded::to_string([
+                    ("code", AUTHORIZATION_CODE),
+                    ("state", state),
+                ])
+                .unwrap()
+            ),
+        )
+        .header(http::header::COOKIE, cookie)
+        .expect_status(Some(expected_status)),
+    )
+    .execute()
+    .await
+    .expect("expected success")
+}
+
+#[nexus_test]
+async fn test_oidc_login(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+    let idp = FakeIdp::new();
+
+    create_silo(&client, SILO_NAME, true, shared::SiloIdentityMode::OidcJit)
+        .await;
+
+    let provider: views::OidcIdentityProvider = object_create(
+        client,
+        &format!("/v1/system/identity-providers/oidc?silo={}", SILO_NAME),
+        &params::OidcIdentityProviderCreate {
+            identity: IdentityMetadataCreateParams {
+                name: PROVIDER_NAME.parse().unwrap(),
+                description: "a demo provider".to_string(),
+            },
+            discovery_source: params::OidcDiscoverySource::Url {
+                url: format!("{}/.well-known/openid-configuration", idp.issuer),
+            },
+            client_id: CLIENT_ID.to_string(),
+            client_secret: "shh".to_string(),
+            redirect_uri: format!(
+                "https://nexus.example.com/login/{}/oidc/{}/callback",
+                SILO_NAME, PROVIDER_NAME
+            ),
+            scopes: vec!["groups".to_string()],
+            group_claim_name: Some("groups".to_string()),
+        },
+    )
+    .await;
+    assert_eq!(provider.issuer, idp.issuer);
+    assert_eq!(provider.token_endpoint, format!("{}/token", idp.issuer));
+
+    // The client secret is never returned.
+    let raw: serde_json::Value = NexusRequest::object_get(
+        client,
+        &format!(
+            "/v1/system/identity-providers/oidc/{}?silo={}",
+            PROVIDER_NAME, SILO_NAME
+        ),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .expect("failed to fetch provider")
+    .parsed_body()
+    .unwrap();
+    assert_eq!(raw["client_id"], CLIENT_ID);
+    assert!(raw.get("client_secret").is_none());
+
+    // The discovery document and keys are only fetched at creation.
+    idp.server.verify_and_clear();
+
+    // The Silo's login page goes straight to the IdP.
+    let (cookie, state) = login_begin(client).await;
+
+    // A callback from a different browser (without the nonce cookie) or with
+    // state from some other login is rejected before anything is sent to the
+    // IdP.
+    login_callback(client, "oidc-nonce=bogus", &state, StatusCode::BAD_REQUEST)
+        .await;
+    let (_, other_state) = login_begin(client).await;
+    login_callback(client, &cookie, &other_state, StatusCode::BAD_REQUEST)
+        .await;
+
+    // ID tokens that were issued for somebody else, have expired, or are
+    // replayed from another login are rejected.
+    let nonce = cookie.trim_start_matches("oidc-nonce=");
+    let bad_claims = [
+        ("aud", json!("somebody-else")),
+        ("exp", json!(chrono::Utc::now().timestamp() - 60)),
+        ("nonce", json!("some-other-nonce")),
+    ];
+    for (claim, value) in bad_claims {
+        let mut claims = idp.claims(nonce);
+        claims[claim] = value;
+        idp.expect_token_request(idp.sign_id_token(claims));
+        login_callback(client, &cookie, &state, StatusCode::BAD_REQUEST).await;
+        idp.server.verify_and_clear();
+    }
+
+    // A valid ID token logs the user in and brings them back to where they
+    // started.
+    idp.expect_token_request(idp.sign_id_token(idp.claims(nonce)));
+    let result =
+        login_callback(client, &cookie, &state, StatusCode::SEE_OTHER).await;
+    assert_eq!(result.headers["Location"].to_str().unwrap(), "/projects");
+
+    let session_cookie_value = result
+        .headers
+        .get_all("Set-Cookie")
+        .iter()
+        .map(|v| v.to_str().unwrap())
+        .find(|v| v.starts_with("session="))
+        .expect("no session cookie")
+        .to_string();
+
+    let session_me = NexusRequest::new(
+        RequestBuilder::new(client, Method::GET, "/v1/me")
+            .header(http::header::COOKIE, session_cookie_value.clone())
+            .expect_status(Some(StatusCode::OK)),
+    )
+    .execute_and_parse_unwrap::<views::CurrentUser>()
+    .await;
+    assert_eq!(session_me.user.display_name, "some@customer.com");
+
+    // Group claims are mapped onto Silo groups, just like SAML attributes.
+    let groups: ResultsPage<views::Group> = NexusRequest::new(
+        RequestBuilder::new(client, Method::GET, "/v1/me/groups")
+            .header(http::header::COOKIE, session_cookie_value)
+            .expect_status(Some(StatusCode::OK)),
+    )
+    .execute()
+    .await
+    .expect("expected success")
+    .parsed_body()
+    .unwrap();
+    let group_names: Vec<&str> =
+        groups.items.iter().map(|g| g.display_name.as_str()).collect();
+    assert_same_items(group_names, vec!["SRE", "Admins"]);
+}
+
+// OpenID Connect providers can only be created in OpenID Connect Silos
+#[nexus_test]
+async fn test_oidc_idp_in_saml_silo(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+
+    create_silo(&client, SILO_NAME, true, shared::SiloIdentityMode::SamlJit)
+        .await;
+
+    let discovery = json!({
+        "issuer": "https://idp.example.com",
+        "authorization_endpoint": "https://idp.example.com/authorize",
+        "token_endpoint": "https://idp.example.com/token",
+        "jwks_uri": "https://idp.example.com/jwks",
+    });
+    NexusRequest::expect_failure_with_body(
+        client,
+        StatusCode::BAD_REQUEST,
+        Method::POST,
+        &format!("/v1/system/identity-providers/oidc?silo={}", SILO_NAME),
+        &params::OidcIdentityProviderCreate {
+            identity: IdentityMetadataCreateParams {
+                name: PROVIDER_NAME.parse().unwrap(),
+                description: "a demo provider".to_string(),
+            },
+            discovery_source: params::OidcDiscoverySource::Base64EncodedJson {
+                data: base64::engine::general_purpose::STANDARD
+                    .encode(discovery.to_string()),
+            },
+            client_id: CLIENT_ID.to_string(),
+            client_secret: "shh".to_string(),
+            redirect_uri: "https://nexus.example.com/callback".to_string(),
+            scopes: vec![],
+            group_claim_name: None,
+        },
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+}
@@ -23,6 +23,7 @@ use omicron_common::api::external::DiskState;
 use omicron_common::api::external::IdentityMetadataCreateParams;
 use omicron_common::api::external::Instance;
 use omicron_nexus::external_api::params;
+use omicron_nexus::external_api::views::ExportedBlocks;
 use omicron_nexus::external_api::views::Snapshot;
 use omicron_nexus::Nexus;
 use omicron_nexus::TestInterfaces as _;
@@ -131,6 +132,7 @@ async fn create_disk_with_state_importing_blocks(client: &ClientTestContext) {
                 block_size: params::BlockSize::try_from(512).unwrap(),
             },
             size: ByteCount::from_gibibytes_u32(1),
+            labels: Default::default(),
         },
     )
     .await;
@@ -353,6 +355,60 @@ async fn finalize_import_take_snapshot(
     .unwrap();
 }
 
+/// Posts to one of the export endpoints (`export-start`, `bulk-read` or
+/// `export-stop`) of `resource`, e.g. "disks/just-rainsticks"
+async fn export_action(
+    client: &ClientTestContext,
+    resource: &str,
+    action: &str,
+    expected_status: StatusCode,
+) {
+    let url = format!("/v1/{}/{}?project={}", resource, action, PROJECT_NAME);
+
+    NexusRequest::new(
+        RequestBuilder::new(client, Method::POST, &url)
+            .expect_status(Some(expected_status)),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+}
+
+async fn bulk_read_bytes(
+    client: &ClientTestContext,
+    resource: &str,
+    offset: u64,
+    size: u64,
+    expected_status: StatusCode,
+) -> Option<Vec<u8>> {
+    let bulk_read_url =
+        format!("/v1/{}/bulk-read?project={}", resource, PROJECT_NAME);
+
+    let response = NexusRequest::new(
+        RequestBuilder::new(client, Method::POST, &bulk_read_url)
+            .body(Some(&params::ExportBlocksBulkRead { offset, size }))
+            .expect_status(Some(expected_status)),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+
+    if expected_status != StatusCode::OK {
+        return None;
+    }
+
+    let blocks: ExportedBlocks = response.parsed_body().unwrap();
+    Some(
+        base64::Engine::decode(
+            &base64::engine::general_purpose::STANDARD,
+            blocks.base64_encoded_data,
+        )
+        .unwrap(),
+    )
+}
+
 async fn validate_disk_state(client: &ClientTestContext, state: DiskState) {
     let disk_url = get_disk_url(DISK_NAME);
     let disk = disk_get(&client, &disk_url).await;
@@ -377,6 +433,7 @@ async fn test_disk_create_for_importing(cptestctx: &ControlPlaneTestContext) {
             block_size: params::BlockSize::try_from(512).unwrap(),
         },
         size: ByteCount::from_gibibytes_u32(1),
+        labels: Default::default(),
     };
 
     NexusRequest::new(
@@ -885,3 +942,199 @@ async fn test_cannot_finalize_attached_disk(
     // Validate that a user cannot finalize
     finalize_import(client, StatusCode::BAD_REQUEST).await;
 }
+
+// Test the normal flow of exporting a detached disk with bulk reads
+#[nexus_test]
+async fn test_export_disk_with_bulk_reads(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+    let disk = format!("disks/{}", DISK_NAME);
+
+    DiskTest::new(&cptestctx).await;
+    create_org_and_project(client).await;
+
+    create_disk_with_state_importing_blocks(client).await;
+
+    // bulk write bytes in and finalize
+    bulk_write_start(client, StatusCode::NO_CONTENT).await;
+    bulk_write_bytes(client).await;
+    bulk_write_stop(client, StatusCode::NO_CONTENT).await;
+    finalize_import(client, StatusCode::NO_CONTENT).await;
+
+    // Bulk reads are rejected until the export has started
+    bulk_read_bytes(client, &disk, 0, 4096, StatusCode::BAD_REQUEST).await;
+
+    // export start
+    export_action(client, &disk, "export-start", StatusCode::NO_CONTENT).await;
+
+    // Validate disk is in state Exporting, and can't be exported twice
+    validate_disk_state(client, DiskState::Exporting).await;
+    export_action(client, &disk, "export-start", StatusCode::BAD_REQUEST).await;
+
+    // bulk read some bytes out
+    let data = bulk_read_bytes(client, &disk, 4096, 8192, StatusCode::OK)
+        .await
+        .unwrap();
+    assert_eq!(data, vec![0; 8192]);
+
+    // Reads must be block aligned, not too large, and within the disk
+    bulk_read_bytes(client, &disk, 100, 4096, StatusCode::BAD_REQUEST).await;
+    bulk_read_bytes(client, &disk, 0, 1024 * 1024, StatusCode::BAD_REQUEST)
+        .await;
+    bulk_read_bytes(
+        client,
+        &disk,
+        1024 * 1024 * 1024,
+        4096,
+        StatusCode::BAD_REQUEST,
+    )
+    .await;
+
+    // export stop
+    export_action(client, &disk, "export-stop", StatusCode::NO_CONTENT).await;
+
+    // Validate disk is back in state Detached, and can't be read from
+    validate_disk_state(client, DiskState::Detached).await;
+    bulk_read_bytes(client, &disk, 0, 4096, StatusCode::BAD_REQUEST).await;
+    export_action(client, &disk, "export-stop", StatusCode::BAD_REQUEST).await;
+}
+
+// Test exporting a snapshot with bulk reads
+#[nexus_test]
+async fn test_export_snapshot_with_bulk_reads(
+    cptestctx: &ControlPlaneTestContext,
+) {
+    let client = &cptestctx.external_client;
+    let snapshot = "snapshots/a-snapshot";
+
+    DiskTest::new(&cptestctx).await;
+    create_org_and_project(client).await;
+
+    create_disk_with_state_importing_blocks(client).await;
+
+    // bulk write bytes in and finalize, taking a snapshot
+    bulk_write_start(client, StatusCode::NO_CONTENT).await;
+    bulk_write_bytes(client).await;
+    bulk_write_stop(client, StatusCode::NO_CONTENT).await;
+    finalize_import_take_snapshot(client, StatusCode::NO_CONTENT).await;
+
+    // export start, which can only happen once at a time
+    export_action(client, snapshot, "export-start", StatusCode::NO_CONTENT)
+        .await;
+    export_action(client, snapshot, "export-start", StatusCode::BAD_REQUEST)
+        .await;
+
+    // bulk read some bytes out
+    let data = bulk_read_bytes(client, snapshot, 0, 4096, StatusCode::OK)
+        .await
+        .unwrap();
+    assert_eq!(data, vec![0; 4096]);
+
+    // The snapshot can't be deleted while it's being exported
+    let snapshot_url =
+        format!("/v1/snapshots/a-snapshot?project={}", PROJECT_NAME);
+    NexusRequest::expect_failure(
+        client,
+        StatusCode::BAD_REQUEST,
+        Method::DELETE,
+        &snapshot_url,
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+
+    // export stop
+    export_action(client, snapshot, "export-stop", StatusCode::NO_CONTENT)
+        .await;
+    bulk_read_bytes(client, snapshot, 0, 4096, StatusCode::BAD_REQUEST).await;
+
+    // Now the snapshot can be deleted
+    NexusRequest::object_delete(client, &snapshot_url)
+        .authn_as(AuthnMode::PrivilegedUser)
+        .execute()
+        .await
+        .unwrap();
+}
+
+// Test that the bytes bulk written into a disk are the ones read back out of
+// it, and out of a snapshot taken of it
+#[nexus_test]
+async fn test_export_returns_bulk_written_bytes(
+    cptestctx: &ControlPlaneTestContext,
+) {
+    let client = &cptestctx.external_client;
+    let disk = format!("disks/{}", DISK_NAME);
+    let snapshot = "snapshots/a-snapshot";
+
+    DiskTest::new(&cptestctx).await;
+    create_org_and_project(client).await;
+
+    create_disk_with_state_importing_blocks(client).await;
+
+    // bulk write two blocks, leaving a hole between them, and finalize
+    let block: Vec<u8> = (0..4096).map(|i| (i % 251) as u8).collect();
+    let other_block: Vec<u8> = block.iter().rev().copied().collect();
+    bulk_write_start(client, StatusCode::NO_CONTENT).await;
+    bulk_write_bytes_manual(client, 0, block.clone(), StatusCode::NO_CONTENT)
+        .await;
+    bulk_write_bytes_manual(
+        client,
+        8192,
+        other_block.clone(),
+        StatusCode::NO_CONTENT,
+    )
+    .await;
+    bulk_write_stop(client, StatusCode::NO_CONTENT).await;
+    finalize_import_take_snapshot(client, StatusCode::NO_CONTENT).await;
+
+    let mut expected = block;
+    expected.extend(vec![0; 4096]);
+    expected.extend(other_block);
+    expected.extend(vec![0; 4096]);
+
+    // read the disk back out
+    export_action(client, &disk, "export-start", StatusCode::NO_CONTENT).await;
+    let data =
+        bulk_read_bytes(client, &disk, 0, 16384, StatusCode::OK).await.unwrap();
+    assert_eq!(data, expected);
+    let data = bulk_read_bytes(client, &disk, 8192, 4096, StatusCode::OK)
+        .await
+        .unwrap();
+    assert_eq!(data, &expected[8192..12288]);
+    export_action(client, &disk, "export-stop", StatusCode::NO_CONTENT).await;
+
+    // and the snapshot
+    export_action(client, snapshot, "export-start", StatusCode::NO_CONTENT)
+        .await;
+    let data = bulk_read_bytes(client, snapshot, 0, 16384, StatusCode::OK)
+        .await
+        .unwrap();
+    assert_eq!(data, expected);
+    export_action(client, snapshot, "export-stop", StatusCode::NO_CONTENT)
+        .await;
+}
+
+// Test that users cannot export a disk that is attached to an instance
+#[nexus_test]
+async fn test_cannot_export_attached_disk(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+    let nexus = &cptestctx.server.apictx().nexus;
+    let disk = format!("disks/{}", DISK_NAME);
+
+    DiskTest::new(&cptestctx).await;
+    create_org_and_project(client).await;
+
+    create_disk_with_state_importing_blocks(client).await;
+
+    // bulk write bytes in and finalize
+    bulk_write_start(client, StatusCode::NO_CONTENT).await;
+    bulk_write_bytes(client).await;
+    bulk_write_stop(client, StatusCode::NO_CONTENT).await;
+    finalize_import(client, StatusCode::NO_CONTENT).await;
+
+    // Create an instance to attach the disk.
+    create_instance_and_attach_disk(client, nexus, StatusCode::ACCEPTED).await;
+
+    // Validate that a user cannot start an export
+    export_action(client, &disk, "export-start", StatusCode::BAD_REQUEST).await;
+}
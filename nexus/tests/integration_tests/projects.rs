@@ -10,8 +10,8 @@ use nexus_test_utils::http_testing::AuthnMode;
 use nexus_test_utils::http_testing::NexusRequest;
 use nexus_test_utils::http_testing::RequestBuilder;
 use nexus_test_utils::resource_helpers::{
-    create_disk, create_project, create_vpc, object_create, populate_ip_pool,
-    project_get, projects_list, DiskTest,
+    create_affinity_group, create_disk, create_project, create_vpc,
+    object_create, populate_ip_pool, project_get, projects_list, DiskTest,
 };
 use nexus_test_utils_macros::nexus_test;
 use omicron_common::api::external::ByteCount;
@@ -20,6 +20,7 @@ use omicron_common::api::external::Instance;
 use omicron_common::api::external::InstanceCpuCount;
 use omicron_common::api::external::Name;
 use omicron_nexus::external_api::params;
+use omicron_nexus::external_api::shared;
 use omicron_nexus::external_api::views;
 use omicron_nexus::external_api::views::Project;
 use std::str::FromStr;
@@ -160,7 +161,11 @@ async fn test_project_deletion_with_instance(
                 params::InstanceNetworkInterfaceAttachment::None,
             external_ips: vec![],
             disks: vec![],
+            affinity_groups: vec![],
+            ssh_public_keys: None,
             start: false,
+            labels: Default::default(),
+            auto_restart_policy: Default::default(),
         },
     )
     .await;
@@ -209,6 +214,89 @@ async fn test_project_deletion_with_disk(cptestctx: &ControlPlaneTestContext) {
     delete_project(&url, &client).await;
 }
 
+#[nexus_test]
+async fn test_project_deletion_with_affinity_group(
+    cptestctx: &ControlPlaneTestContext,
+) {
+    let client = &cptestctx.external_client;
+
+    // Create a project that we'll use for testing.
+    let name = "springfield-squidport";
+    let url = format!("/v1/projects/{}", name);
+
+    create_project(&client, &name).await;
+    delete_project_default_subnet(&name, &client).await;
+    delete_project_default_vpc(&name, &client).await;
+    create_affinity_group(
+        &client,
+        &name,
+        "my-group",
+        shared::AffinityGroupKind::AntiAffinity,
+        shared::AffinityPolicy::Hard,
+    )
+    .await;
+    assert_eq!(
+        "project to be deleted contains an affinity group: my-group",
+        delete_project_expect_fail(&url, &client).await,
+    );
+    let group_url = format!("/v1/affinity-groups/my-group?project={}", name);
+    NexusRequest::object_delete(&client, &group_url)
+        .authn_as(AuthnMode::PrivilegedUser)
+        .execute()
+        .await
+        .expect("failed to delete affinity group");
+
+    delete_project(&url, &client).await;
+}
+
+#[nexus_test]
+async fn test_project_deletion_with_snapshot_schedule(
+    cptestctx: &ControlPlaneTestContext,
+) {
+    let client = &cptestctx.external_client;
+
+    // Create a project that we'll use for testing.
+    let name = "springfield-squidport";
+    let url = format!("/v1/projects/{}", name);
+
+    create_project(&client, &name).await;
+    delete_project_default_subnet(&name, &client).await;
+    delete_project_default_vpc(&name, &client).await;
+    let schedules_url = format!("/v1/snapshot-schedules?project={}", name);
+    let _: views::SnapshotSchedule = object_create(
+        &client,
+        &schedules_url,
+        &params::SnapshotScheduleCreate {
+            identity: IdentityMetadataCreateParams {
+                name: "my-schedule".parse().unwrap(),
+                description: String::from("snapshots labeled disks"),
+            },
+            target: params::SnapshotScheduleTargetCreate::Labels {
+                labels: serde_json::from_value(
+                    serde_json::json!({ "backup": "nightly" }),
+                )
+                .unwrap(),
+            },
+            interval_secs: 3600,
+            retain: 1,
+        },
+    )
+    .await;
+    assert_eq!(
+        "project to be deleted contains a snapshot schedule: my-schedule",
+        delete_project_expect_fail(&url, &client).await,
+    );
+    let schedule_url =
+        format!("/v1/snapshot-schedules/my-schedule?project={}", name);
+    NexusRequest::object_delete(&client, &schedule_url)
+        .authn_as(AuthnMode::PrivilegedUser)
+        .execute()
+        .await
+        .expect("failed to delete snapshot schedule");
+
+    delete_project(&url, &client).await;
+}
+
 #[nexus_test]
 async fn test_project_deletion_with_image(cptestctx: &ControlPlaneTestContext) {
     let client = &cptestctx.external_client;
@@ -231,6 +319,7 @@ async fn test_project_deletion_with_image(cptestctx: &ControlPlaneTestContext) {
         os: "alpine".to_string(),
         version: "edge".to_string(),
         source: params::ImageSource::YouCanBootAnythingAsLongAsItsAlpine,
+        labels: Default::default(),
     };
 
     let images_url = format!("/v1/images?project={}", name);
@@ -299,6 +388,7 @@ async fn test_project_deletion_with_snapshot(
                 description: "not attached to instance".into(),
             },
             disk: Name::from_str("my-disk").unwrap().into(),
+            labels: Default::default(),
         },
     )
     .await;
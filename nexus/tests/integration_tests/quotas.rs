@@ -0,0 +1,228 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Tests for silo and project quotas
+
+use dropshot::test_util::ClientTestContext;
+use dropshot::HttpErrorResponseBody;
+use http::method::Method;
+use http::StatusCode;
+use nexus_test_utils::http_testing::AuthnMode;
+use nexus_test_utils::http_testing::NexusRequest;
+use nexus_test_utils::resource_helpers::create_disk;
+use nexus_test_utils::resource_helpers::create_instance;
+use nexus_test_utils::resource_helpers::create_project;
+use nexus_test_utils::resource_helpers::object_put;
+use nexus_test_utils::resource_helpers::populate_ip_pool;
+use nexus_test_utils::resource_helpers::DiskTest;
+use nexus_test_utils_macros::nexus_test;
+use omicron_common::api::external::ByteCount;
+use omicron_common::api::external::IdentityMetadataCreateParams;
+use omicron_common::api::external::Instance;
+use omicron_common::api::external::InstanceCpuCount;
+use omicron_nexus::external_api::params;
+use omicron_nexus::external_api::views;
+
+type ControlPlaneTestContext =
+    nexus_test_utils::ControlPlaneTestContext<omicron_nexus::Server>;
+
+const SILO_NAME: &str = "test-suite-silo";
+const PROJECT_NAME: &str = "quota-project";
+
+fn silo_quotas_url(silo_name: &str) -> String {
+    format!("/v1/system/silos/{}/quotas", silo_name)
+}
+
+fn project_quotas_url(project_name: &str) -> String {
+    format!("/v1/projects/{}/quotas", project_name)
+}
+
+async fn quota_get(client: &ClientTestContext, url: &str) -> views::Quota {
+    NexusRequest::object_get(client, url)
+        .authn_as(AuthnMode::PrivilegedUser)
+        .execute()
+        .await
+        .unwrap()
+        .parsed_body()
+        .unwrap()
+}
+
+async fn quota_put(
+    client: &ClientTestContext,
+    url: &str,
+    update: params::QuotaUpdate,
+) -> views::Quota {
+    object_put(client, url, &update).await
+}
+
+#[nexus_test]
+async fn test_quota_view_and_update(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+    create_project(client, PROJECT_NAME).await;
+
+    // Without any configuration, nothing is limited.
+    for url in [silo_quotas_url(SILO_NAME), project_quotas_url(PROJECT_NAME)] {
+        let quota = quota_get(client, &url).await;
+        assert_eq!(quota.cpus, None);
+        assert_eq!(quota.memory, None);
+        assert_eq!(quota.storage, None);
+
+        let update = params::QuotaUpdate {
+            cpus: Some(16),
+            memory: Some(ByteCount::from_gibibytes_u32(32)),
+            storage: None,
+        };
+        let quota = quota_put(client, &url, update).await;
+        assert_eq!(quota.cpus, Some(16));
+        assert_eq!(quota.memory, Some(ByteCount::from_gibibytes_u32(32)));
+        assert_eq!(quota.storage, None);
+
+        let quota = quota_get(client, &url).await;
+        assert_eq!(quota.cpus, Some(16));
+        assert_eq!(quota.memory, Some(ByteCount::from_gibibytes_u32(32)));
+        assert_eq!(quota.storage, None);
+
+        // Each update replaces the entire quota.
+        let update = params::QuotaUpdate {
+            cpus: None,
+            memory: None,
+            storage: Some(ByteCount::from_gibibytes_u32(100)),
+        };
+        let quota = quota_put(client, &url, update).await;
+        assert_eq!(quota.cpus, None);
+        assert_eq!(quota.memory, None);
+        assert_eq!(quota.storage, Some(ByteCount::from_gibibytes_u32(100)));
+    }
+}
+
+#[nexus_test]
+async fn test_project_quota_limits_instances(
+    cptestctx: &ControlPlaneTestContext,
+) {
+    let client = &cptestctx.external_client;
+    populate_ip_pool(&client, "default", None).await;
+    create_project(client, PROJECT_NAME).await;
+
+    // Each instance created by `create_instance` uses 4 vCPUs.
+    let url = project_quotas_url(PROJECT_NAME);
+    quota_put(
+        client,
+        &url,
+        params::QuotaUpdate { cpus: Some(6), ..Default::default() },
+    )
+    .await;
+
+    create_instance(client, PROJECT_NAME, "first-instance").await;
+
+    let instance_params = params::InstanceCreate {
+        identity: IdentityMetadataCreateParams {
+            name: "second-instance".parse().unwrap(),
+            description: String::from("one too many"),
+        },
+        ncpus: InstanceCpuCount(4),
+        memory: ByteCount::from_gibibytes_u32(1),
+        hostname: String::from("second"),
+        user_data: vec![],
+        network_interfaces: params::InstanceNetworkInterfaceAttachment::None,
+        external_ips: vec![],
+        disks: vec![],
+        affinity_groups: vec![],
+        ssh_public_keys: None,
+        start: false,
+        labels: Default::default(),
+        auto_restart_policy: Default::default(),
+    };
+    let instances_url = format!("/v1/instances?project={}", PROJECT_NAME);
+    let error: HttpErrorResponseBody = NexusRequest::expect_failure_with_body(
+        client,
+        StatusCode::BAD_REQUEST,
+        Method::POST,
+        &instances_url,
+        &instance_params,
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap()
+    .parsed_body()
+    .unwrap();
+    assert_eq!(
+        error.message,
+        "project quota exceeded: requested 4 vCPUs, but 4 of 6 are already \
+        provisioned"
+    );
+
+    // The failed request must not have consumed any of the quota: raising the
+    // limit is enough to let the same request succeed.
+    quota_put(
+        client,
+        &url,
+        params::QuotaUpdate { cpus: Some(8), ..Default::default() },
+    )
+    .await;
+    let _: Instance =
+        NexusRequest::objects_post(client, &instances_url, &instance_params)
+            .authn_as(AuthnMode::PrivilegedUser)
+            .execute()
+            .await
+            .unwrap()
+            .parsed_body()
+            .unwrap();
+}
+
+#[nexus_test]
+async fn test_silo_quota_limits_disks(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+    DiskTest::new(&cptestctx).await;
+    create_project(client, PROJECT_NAME).await;
+
+    // Each disk created by `create_disk` is 1 GiB.
+    quota_put(
+        client,
+        &silo_quotas_url(SILO_NAME),
+        params::QuotaUpdate {
+            storage: Some(ByteCount::from_gibibytes_u32(1)),
+            ..Default::default()
+        },
+    )
+    .await;
+
+    create_disk(client, PROJECT_NAME, "first-disk").await;
+
+    let disk_params = params::DiskCreate {
+        identity: IdentityMetadataCreateParams {
+            name: "second-disk".parse().unwrap(),
+            description: String::from("one too many"),
+        },
+        disk_source: params::DiskSource::Blank {
+            block_size: params::BlockSize::try_from(512).unwrap(),
+        },
+        size: ByteCount::from_gibibytes_u32(1),
+        labels: Default::default(),
+    };
+    let disks_url = format!("/v1/disks?project={}", PROJECT_NAME);
+    let error: HttpErrorResponseBody = NexusRequest::expect_failure_with_body(
+        client,
+        StatusCode::BAD_REQUEST,
+        Method::POST,
+        &disks_url,
+        &disk_params,
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap()
+    .parsed_body()
+    .unwrap();
+    assert_eq!(
+        error.message,
+        format!(
+            "silo quota exceeded: requested {} bytes of storage, but {} of {} \
+            bytes are already provisioned",
+            ByteCount::from_gibibytes_u32(1).to_bytes(),
+            ByteCount::from_gibibytes_u32(1).to_bytes(),
+            ByteCount::from_gibibytes_u32(1).to_bytes(),
+        )
+    );
+}
@@ -0,0 +1,442 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Tests for SCIM provisioning of Silo users and groups
+
+use nexus_test_utils::http_testing::{AuthnMode, NexusRequest, RequestBuilder};
+use nexus_test_utils::resource_helpers::{
+    create_silo, object_create, objects_list_page_authz,
+};
+use nexus_test_utils_macros::nexus_test;
+use omicron_nexus::authn::silos::AuthenticatedSubject;
+use omicron_nexus::db::identity::Asset;
+use omicron_nexus::db::lookup::LookupPath;
+use omicron_nexus::external_api::{params, shared, views};
+
+use dropshot::test_util::ClientTestContext;
+use http::method::Method;
+use http::StatusCode;
+use serde_json::json;
+
+type ControlPlaneTestContext =
+    nexus_test_utils::ControlPlaneTestContext<omicron_nexus::Server>;
+
+const SILO_NAME: &str = "scim-silo";
+
+fn tokens_url(silo_name: &str) -> String {
+    format!("/v1/system/scim/tokens?silo={}", silo_name)
+}
+
+async fn create_token(client: &ClientTestContext) -> String {
+    let created: views::ScimClientBearerTokenCreated = object_create(
+        client,
+        &tokens_url(SILO_NAME),
+        &params::ScimClientBearerTokenCreate { time_expires: None },
+    )
+    .await;
+    created.bearer_token
+}
+
+/// Makes a request to the SCIM API as the SCIM client holding `token`
+async fn scim_request(
+    client: &ClientTestContext,
+    method: Method,
+    uri: &str,
+    token: &str,
+    body: Option<serde_json::Value>,
+    expected_status: StatusCode,
+) -> serde_json::Value {
+    let mut builder = RequestBuilder::new(client, method, uri)
+        .header(http::header::AUTHORIZATION, format!("Bearer {}", token))
+        .allow_non_dropshot_errors()
+        .expect_status(Some(expected_status));
+    if let Some(body) = body {
+        builder = builder.body(Some(&body));
+    }
+    let response = builder.execute().await.unwrap();
+    if expected_status == StatusCode::NO_CONTENT {
+        return serde_json::Value::Null;
+    }
+    assert_eq!(
+        response.headers.get(http::header::CONTENT_TYPE).unwrap(),
+        "application/scim+json"
+    );
+    response.parsed_body().unwrap()
+}
+
+#[nexus_test]
+async fn test_scim_tokens(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+
+    create_silo(&client, SILO_NAME, true, shared::SiloIdentityMode::SamlScim)
+        .await;
+
+    // Tokens can only be created in Silos that use SCIM.
+    create_silo(&client, "jit", true, shared::SiloIdentityMode::SamlJit).await;
+    let error = NexusRequest::expect_failure_with_body(
+        client,
+        StatusCode::BAD_REQUEST,
+        Method::POST,
+        &tokens_url("jit"),
+        &params::ScimClientBearerTokenCreate { time_expires: None },
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap()
+    .parsed_body::<dropshot::HttpErrorResponseBody>()
+    .unwrap();
+    assert_eq!(
+        error.message,
+        "SCIM client tokens can only be created in Silos whose users are \
+        provisioned with SCIM"
+    );
+
+    // Tokens can't be created already expired.
+    NexusRequest::expect_failure_with_body(
+        client,
+        StatusCode::BAD_REQUEST,
+        Method::POST,
+        &tokens_url(SILO_NAME),
+        &params::ScimClientBearerTokenCreate {
+            time_expires: Some(chrono::Utc::now()),
+        },
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+
+    let token = create_token(client).await;
+    assert!(token.starts_with("oxide-scim-"));
+
+    let tokens = objects_list_page_authz::<views::ScimClientBearerToken>(
+        client,
+        &tokens_url(SILO_NAME),
+    )
+    .await
+    .items;
+    assert_eq!(tokens.len(), 1);
+
+    // The token works until it's deleted.
+    scim_request(
+        client,
+        Method::GET,
+        "/scim/v2/Users",
+        &token,
+        None,
+        StatusCode::OK,
+    )
+    .await;
+
+    NexusRequest::object_delete(
+        client,
+        &format!("/v1/system/scim/tokens/{}?silo={}", tokens[0].id, SILO_NAME),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+
+    let error = scim_request(
+        client,
+        Method::GET,
+        "/scim/v2/Users",
+        &token,
+        None,
+        StatusCode::UNAUTHORIZED,
+    )
+    .await;
+    assert_eq!(error["status"], "401");
+
+    // Neither a made-up token nor some other kind of token works.
+    for bad_token in ["oxide-scim-nope", "oxide-token-nope", "nope"] {
+        scim_request(
+            client,
+            Method::GET,
+            "/scim/v2/Users",
+            bad_token,
+            None,
+            StatusCode::UNAUTHORIZED,
+        )
+        .await;
+    }
+}
+
+#[nexus_test]
+async fn test_scim_users_and_groups(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+    let nexus = &cptestctx.server.apictx().nexus;
+
+    let silo = create_silo(
+        &client,
+        SILO_NAME,
+        true,
+        shared::SiloIdentityMode::SamlScim,
+    )
+    .await;
+    let token = create_token(client).await;
+
+    // Create a couple of users.
+    let alice = scim_request(
+        client,
+        Method::POST,
+        "/scim/v2/Users",
+        &token,
+        Some(json!({
+            "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"],
+            "userName": "alice@example.com",
+            "active": true,
+        })),
+        StatusCode::CREATED,
+    )
+    .await;
+    assert_eq!(alice["userName"], "alice@example.com");
+    assert_eq!(alice["active"], true);
+    let alice_id = alice["id"].as_str().unwrap().to_string();
+
+    let bob = scim_request(
+        client,
+        Method::POST,
+        "/scim/v2/Users",
+        &token,
+        Some(json!({ "userName": "bob@example.com" })),
+        StatusCode::CREATED,
+    )
+    .await;
+    let bob_id = bob["id"].as_str().unwrap().to_string();
+
+    // A user name can only be used once.
+    let error = scim_request(
+        client,
+        Method::POST,
+        "/scim/v2/Users",
+        &token,
+        Some(json!({ "userName": "bob@example.com" })),
+        StatusCode::CONFLICT,
+    )
+    .await;
+    assert_eq!(error["scimType"], "uniqueness");
+
+    // The users show up in the Silo like any other users.
+    let users = objects_list_page_authz::<views::User>(
+        client,
+        &format!("/v1/system/users?silo={}", SILO_NAME),
+    )
+    .await
+    .items;
+    assert_eq!(users.len(), 2);
+
+    // List and filter the users.
+    let list = scim_request(
+        client,
+        Method::GET,
+        "/scim/v2/Users",
+        &token,
+        None,
+        StatusCode::OK,
+    )
+    .await;
+    assert_eq!(list["totalResults"], 2);
+    let list = scim_request(
+        client,
+        Method::GET,
+        "/scim/v2/Users?filter=userName%20eq%20%22bob%40example.com%22",
+        &token,
+        None,
+        StatusCode::OK,
+    )
+    .await;
+    assert_eq!(list["totalResults"], 1);
+    assert_eq!(list["Resources"][0]["id"], bob_id.as_str());
+
+    // Create a group containing both users, then remove one of them.
+    let group = scim_request(
+        client,
+        Method::POST,
+        "/scim/v2/Groups",
+        &token,
+        Some(json!({
+            "displayName": "engineering",
+            "members": [{ "value": alice_id }, { "value": bob_id }],
+        })),
+        StatusCode::CREATED,
+    )
+    .await;
+    let group_id = group["id"].as_str().unwrap().to_string();
+    assert_eq!(group["members"].as_array().unwrap().len(), 2);
+
+    let group = scim_request(
+        client,
+        Method::PATCH,
+        &format!("/scim/v2/Groups/{}", group_id),
+        &token,
+        Some(json!({
+            "schemas": ["urn:ietf:params:scim:api:messages:2.0:PatchOp"],
+            "Operations": [{
+                "op": "remove",
+                "path": format!("members[value eq \"{}\"]", bob_id),
+            }],
+        })),
+        StatusCode::OK,
+    )
+    .await;
+    assert_eq!(group["members"], json!([{ "value": alice_id }]));
+
+    // Provisioned users can log in.  Their group memberships come from the
+    // SCIM client, not from the identity provider.
+    let authn_opctx = nexus.opctx_external_authn();
+    let (authz_silo, db_silo) =
+        LookupPath::new(&authn_opctx, &nexus.datastore())
+            .silo_id(silo.identity.id)
+            .fetch()
+            .await
+            .unwrap();
+    let subject = |external_id: &str| AuthenticatedSubject {
+        external_id: external_id.to_string(),
+        groups: vec!["some-other-group".to_string()],
+    };
+    let user = nexus
+        .silo_user_from_authenticated_subject(
+            &authn_opctx,
+            &authz_silo,
+            &db_silo,
+            &subject("alice@example.com"),
+        )
+        .await
+        .unwrap()
+        .expect("provisioned user should be able to log in");
+    assert_eq!(user.id().to_string(), alice_id);
+    assert!(nexus
+        .datastore()
+        .silo_group_optional_lookup(
+            &authn_opctx,
+            &authz_silo,
+            "some-other-group".to_string(),
+        )
+        .await
+        .unwrap()
+        .is_none());
+
+    // Users that were never provisioned cannot log in.
+    assert!(nexus
+        .silo_user_from_authenticated_subject(
+            &authn_opctx,
+            &authz_silo,
+            &db_silo,
+            &subject("mallory@example.com"),
+        )
+        .await
+        .unwrap()
+        .is_none());
+
+    // Deactivated users cannot log in either.
+    let alice = scim_request(
+        client,
+        Method::PATCH,
+        &format!("/scim/v2/Users/{}", alice_id),
+        &token,
+        Some(json!({
+            "schemas": ["urn:ietf:params:scim:api:messages:2.0:PatchOp"],
+            "Operations": [{
+                "op": "replace",
+                "path": "active",
+                "value": false,
+            }],
+        })),
+        StatusCode::OK,
+    )
+    .await;
+    assert_eq!(alice["active"], false);
+    assert!(nexus
+        .silo_user_from_authenticated_subject(
+            &authn_opctx,
+            &authz_silo,
+            &db_silo,
+            &subject("alice@example.com"),
+        )
+        .await
+        .unwrap()
+        .is_none());
+
+    // Delete the group and a user.
+    scim_request(
+        client,
+        Method::DELETE,
+        &format!("/scim/v2/Groups/{}", group_id),
+        &token,
+        None,
+        StatusCode::NO_CONTENT,
+    )
+    .await;
+    scim_request(
+        client,
+        Method::GET,
+        &format!("/scim/v2/Groups/{}", group_id),
+        &token,
+        None,
+        StatusCode::NOT_FOUND,
+    )
+    .await;
+    scim_request(
+        client,
+        Method::DELETE,
+        &format!("/scim/v2/Users/{}", bob_id),
+        &token,
+        None,
+        StatusCode::NO_CONTENT,
+    )
+    .await;
+    let list = scim_request(
+        client,
+        Method::GET,
+        "/scim/v2/Users",
+        &token,
+        None,
+        StatusCode::OK,
+    )
+    .await;
+    assert_eq!(list["totalResults"], 1);
+    assert_eq!(list["Resources"][0]["id"], alice_id.as_str());
+
+    // Changes made by the SCIM client are audited as made by its token, in the
+    // Silo.  Reads aren't audited.
+    let tokens = objects_list_page_authz::<views::ScimClientBearerToken>(
+        client,
+        &tokens_url(SILO_NAME),
+    )
+    .await
+    .items;
+    assert_eq!(tokens.len(), 1);
+    let entries: Vec<views::AuditLogEntry> =
+        NexusRequest::iter_collection_authn(
+            client,
+            "/v1/system/audit-log",
+            &format!("actor_id={}", tokens[0].id),
+            None,
+        )
+        .await
+        .expect("failed to list audit log")
+        .all_items;
+    let operations: Vec<_> = entries
+        .iter()
+        .map(|entry| (entry.operation_id.as_str(), entry.result_status))
+        .collect();
+    assert_eq!(
+        operations,
+        vec![
+            ("scim_user_create", 201),
+            ("scim_user_create", 201),
+            ("scim_user_create", 409),
+            ("scim_group_create", 201),
+            ("scim_group_patch", 200),
+            ("scim_user_patch", 200),
+            ("scim_group_delete", 204),
+            ("scim_user_delete", 204),
+        ]
+    );
+    assert!(entries
+        .iter()
+        .all(|entry| entry.actor_silo_id == Some(silo.identity.id)));
+}
@@ -185,6 +185,7 @@ async fn test_silos(cptestctx: &ControlPlaneTestContext) {
                 name: project_name.parse().unwrap(),
                 description: String::new(),
             },
+            labels: Default::default(),
         },
     )
     .authn_as(AuthnMode::SiloUser(new_silo_user_id))
@@ -344,6 +345,7 @@ async fn test_silo_admin_group(cptestctx: &ControlPlaneTestContext) {
                 name: "myproj".parse().unwrap(),
                 description: "some proj".into(),
             },
+            labels: Default::default(),
         },
     )
     .authn_as(AuthnMode::SiloUser(admin_group_user.id()))
@@ -770,6 +772,20 @@ async fn test_silo_user_provision_types(cptestctx: &ControlPlaneTestContext) {
             existing_silo_user: false,
             expect_user: true,
         },
+        // A silo configured with a "SCIM" user provision type should fetch a
+        // user if the SCIM client has provisioned it already.
+        TestSiloUserProvisionTypes {
+            identity_mode: shared::SiloIdentityMode::SamlScim,
+            existing_silo_user: true,
+            expect_user: true,
+        },
+        // A silo configured with a "SCIM" user provision type should not
+        // create a user if one does not exist already.
+        TestSiloUserProvisionTypes {
+            identity_mode: shared::SiloIdentityMode::SamlScim,
+            existing_silo_user: false,
+            expect_user: false,
+        },
     ];
 
     for test_case in test_cases {
@@ -779,7 +795,9 @@ async fn test_silo_user_provision_types(cptestctx: &ControlPlaneTestContext) {
 
         if test_case.existing_silo_user {
             match test_case.identity_mode {
-                shared::SiloIdentityMode::SamlJit => {
+                shared::SiloIdentityMode::SamlJit
+                | shared::SiloIdentityMode::OidcJit
+                | shared::SiloIdentityMode::SamlScim => {
                     create_jit_user(datastore, &silo, "external-id-com").await;
                 }
                 shared::SiloIdentityMode::LocalOnly => {
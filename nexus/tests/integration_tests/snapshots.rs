@@ -15,6 +15,9 @@ use nexus_test_utils::http_testing::NexusRequest;
 use nexus_test_utils::http_testing::RequestBuilder;
 use nexus_test_utils::resource_helpers::create_project;
 use nexus_test_utils::resource_helpers::object_create;
+use nexus_test_utils::resource_helpers::object_delete;
+use nexus_test_utils::resource_helpers::object_put;
+use nexus_test_utils::resource_helpers::objects_list_page_authz;
 use nexus_test_utils::resource_helpers::populate_ip_pool;
 use nexus_test_utils::resource_helpers::DiskTest;
 use nexus_test_utils_macros::nexus_test;
@@ -25,6 +28,7 @@ use omicron_common::api::external::DiskState;
 use omicron_common::api::external::IdentityMetadataCreateParams;
 use omicron_common::api::external::Instance;
 use omicron_common::api::external::InstanceCpuCount;
+use omicron_common::api::external::Labels;
 use omicron_common::api::external::Name;
 use omicron_nexus::app::MIN_DISK_SIZE_BYTES;
 use omicron_nexus::authz;
@@ -33,6 +37,10 @@ use omicron_nexus::db::identity::Resource;
 use omicron_nexus::db::lookup::LookupPath;
 use omicron_nexus::external_api::params;
 use omicron_nexus::external_api::views;
+use omicron_nexus::TestInterfaces as _;
+use omicron_test_utils::dev::poll::{wait_for_condition, CondCheckError};
+use std::collections::BTreeMap;
+use std::time::Duration;
 use uuid::Uuid;
 
 use httptest::{matchers::*, responders::*, Expectation, ServerBuilder};
@@ -89,6 +97,7 @@ async fn test_snapshot_basic(cptestctx: &ControlPlaneTestContext) {
         },
         os: "alpine".to_string(),
         version: "edge".to_string(),
+        labels: Default::default(),
     };
 
     let images_url = format!("/v1/images?project={}", PROJECT_NAME);
@@ -108,6 +117,7 @@ async fn test_snapshot_basic(cptestctx: &ControlPlaneTestContext) {
         },
         disk_source: params::DiskSource::Image { image_id: image.identity.id },
         size: disk_size,
+        labels: Default::default(),
     };
 
     let base_disk: Disk = NexusRequest::new(
@@ -146,7 +156,11 @@ async fn test_snapshot_basic(cptestctx: &ControlPlaneTestContext) {
                 params::InstanceDiskAttach { name: base_disk_name.clone() },
             )],
             external_ips: vec![],
+            affinity_groups: vec![],
+            ssh_public_keys: None,
             start: true,
+            labels: Default::default(),
+            auto_restart_policy: Default::default(),
         },
     )
     .await;
@@ -167,6 +181,7 @@ async fn test_snapshot_basic(cptestctx: &ControlPlaneTestContext) {
                 description: format!("instance {:?}", instance_name),
             },
             disk: base_disk_name.into(),
+            labels: Default::default(),
         },
     )
     .await;
@@ -209,6 +224,7 @@ async fn test_snapshot_without_instance(cptestctx: &ControlPlaneTestContext) {
         },
         os: "alpine".to_string(),
         version: "edge".to_string(),
+        labels: Default::default(),
     };
 
     let images_url = format!("/v1/images?project={}", PROJECT_NAME);
@@ -228,6 +244,7 @@ async fn test_snapshot_without_instance(cptestctx: &ControlPlaneTestContext) {
         },
         disk_source: params::DiskSource::Image { image_id: image.identity.id },
         size: disk_size,
+        labels: Default::default(),
     };
 
     let base_disk: Disk = NexusRequest::new(
@@ -267,6 +284,7 @@ async fn test_snapshot_without_instance(cptestctx: &ControlPlaneTestContext) {
                 description: "not attached to instance".into(),
             },
             disk: base_disk_name.clone().into(),
+            labels: Default::default(),
         },
     )
     .await;
@@ -310,6 +328,7 @@ async fn test_delete_snapshot(cptestctx: &ControlPlaneTestContext) {
             block_size: params::BlockSize::try_from(512).unwrap(),
         },
         size: disk_size,
+        labels: Default::default(),
     };
 
     let base_disk: Disk = NexusRequest::new(
@@ -344,6 +363,7 @@ async fn test_delete_snapshot(cptestctx: &ControlPlaneTestContext) {
                 description: "not attached to instance".into(),
             },
             disk: base_disk_name.clone().into(),
+            labels: Default::default(),
         },
     )
     .await;
@@ -371,6 +391,7 @@ async fn test_delete_snapshot(cptestctx: &ControlPlaneTestContext) {
             block_size: params::BlockSize::try_from(512).unwrap(),
         },
         size: disk_size,
+        labels: Default::default(),
     };
 
     let _snap_disk: Disk = NexusRequest::new(
@@ -445,6 +466,250 @@ async fn test_delete_snapshot(cptestctx: &ControlPlaneTestContext) {
     assert_eq!(provision.virtual_disk_bytes_provisioned.to_bytes(), 0);
 }
 
+#[nexus_test]
+async fn test_disk_rollback(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+    let nexus = &cptestctx.server.apictx().nexus;
+    let datastore = nexus.datastore();
+    let disk_test = DiskTest::new(&cptestctx).await;
+    populate_ip_pool(&client, "default", None).await;
+    let project_id = create_org_and_project(client).await;
+    let disks_url = get_disks_url();
+
+    // Create a blank disk
+    let disk_size = ByteCount::from_gibibytes_u32(2);
+    let base_disk_name: Name = "base-disk".parse().unwrap();
+    let base_disk: Disk = object_create(
+        client,
+        &disks_url,
+        &params::DiskCreate {
+            identity: IdentityMetadataCreateParams {
+                name: base_disk_name.clone(),
+                description: String::from("sells rainsticks"),
+            },
+            disk_source: params::DiskSource::Blank {
+                block_size: params::BlockSize::try_from(512).unwrap(),
+            },
+            size: disk_size,
+            labels: Default::default(),
+        },
+    )
+    .await;
+
+    // Snapshot it
+    let snapshots_url = format!("/v1/snapshots?project={}", PROJECT_NAME);
+    let snapshot: views::Snapshot = object_create(
+        client,
+        &snapshots_url,
+        &params::SnapshotCreate {
+            identity: IdentityMetadataCreateParams {
+                name: "before".parse().unwrap(),
+                description: "before the bad change".into(),
+            },
+            disk: base_disk_name.clone().into(),
+            labels: Default::default(),
+        },
+    )
+    .await;
+
+    let opctx =
+        OpContext::for_tests(cptestctx.logctx.log.new(o!()), datastore.clone());
+    let (.., db_disk) = LookupPath::new(&opctx, &datastore)
+        .disk_id(base_disk.identity.id)
+        .fetch()
+        .await
+        .unwrap();
+    let old_volume_id = db_disk.volume_id;
+
+    // Rolling back to a snapshot that doesn't exist fails
+    let rollback_url = format!(
+        "/v1/disks/{}/rollback?project={}",
+        base_disk_name, PROJECT_NAME
+    );
+    NexusRequest::new(
+        RequestBuilder::new(client, Method::POST, &rollback_url)
+            .body(Some(&params::DiskRollback { snapshot_id: Uuid::new_v4() }))
+            .expect_status(Some(StatusCode::NOT_FOUND)),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+
+    // Roll the disk back to the snapshot.  The disk keeps its id and state,
+    // but is now backed by a new volume.
+    let disk: Disk = NexusRequest::new(
+        RequestBuilder::new(client, Method::POST, &rollback_url)
+            .body(Some(&params::DiskRollback {
+                snapshot_id: snapshot.identity.id,
+            }))
+            .expect_status(Some(StatusCode::OK)),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap()
+    .parsed_body()
+    .unwrap();
+    assert_eq!(disk.identity.id, base_disk.identity.id);
+    assert_eq!(disk.state, DiskState::Detached);
+    assert_eq!(disk.size, disk_size);
+
+    let (.., db_disk) = LookupPath::new(&opctx, &datastore)
+        .disk_id(base_disk.identity.id)
+        .fetch()
+        .await
+        .unwrap();
+    assert_ne!(db_disk.volume_id, old_volume_id);
+
+    // The disk's size is still only accounted for once.
+    let provision = datastore
+        .virtual_provisioning_collection_get(&opctx, project_id)
+        .await
+        .unwrap();
+    assert_eq!(
+        provision.virtual_disk_bytes_provisioned.to_bytes(),
+        2 * disk_size.to_bytes()
+    );
+
+    // Delete the snapshot and the disk.  If the rollback hadn't released the
+    // disk's old volume, its regions would be left behind.
+    NexusRequest::object_delete(
+        client,
+        &format!("/v1/snapshots/before?project={}", PROJECT_NAME),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .expect("failed to delete snapshot");
+    NexusRequest::object_delete(client, &get_disk_url("base-disk"))
+        .authn_as(AuthnMode::PrivilegedUser)
+        .execute()
+        .await
+        .expect("failed to delete disk");
+
+    assert!(disk_test.crucible_resources_deleted().await);
+}
+
+#[nexus_test]
+async fn test_disk_clone(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+    let nexus = &cptestctx.server.apictx().nexus;
+    let datastore = nexus.datastore();
+    let disk_test = DiskTest::new(&cptestctx).await;
+    populate_ip_pool(&client, "default", None).await;
+    let project_id = create_org_and_project(client).await;
+    let disks_url = get_disks_url();
+
+    // Create a blank disk
+    let disk_size = ByteCount::from_gibibytes_u32(2);
+    let base_disk: Disk = object_create(
+        client,
+        &disks_url,
+        &params::DiskCreate {
+            identity: IdentityMetadataCreateParams {
+                name: "base-disk".parse().unwrap(),
+                description: String::from("sells rainsticks"),
+            },
+            disk_source: params::DiskSource::Blank {
+                block_size: params::BlockSize::try_from(512).unwrap(),
+            },
+            size: disk_size,
+            labels: Default::default(),
+        },
+    )
+    .await;
+
+    // A copy can't be smaller than the disk it's copied from
+    let clone_params = |size| params::DiskCreate {
+        identity: IdentityMetadataCreateParams {
+            name: "clone-disk".parse().unwrap(),
+            description: String::from("also sells rainsticks"),
+        },
+        disk_source: params::DiskSource::Disk {
+            disk_id: base_disk.identity.id,
+        },
+        size,
+        labels: Default::default(),
+    };
+    NexusRequest::new(
+        RequestBuilder::new(client, Method::POST, &disks_url)
+            .body(Some(&clone_params(ByteCount::from_gibibytes_u32(1))))
+            .expect_status(Some(StatusCode::BAD_REQUEST)),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+
+    // The snapshot taken to copy the disk isn't charged to the project, so the
+    // copy fits in a quota with only enough room for the two disks.
+    let _: views::Quota = object_put(
+        client,
+        &format!("/v1/projects/{}/quotas", PROJECT_NAME),
+        &params::QuotaUpdate {
+            cpus: None,
+            memory: None,
+            storage: Some(ByteCount::from_gibibytes_u32(4)),
+        },
+    )
+    .await;
+
+    // Copy the disk
+    let clone_disk: Disk =
+        object_create(client, &disks_url, &clone_params(disk_size)).await;
+    assert_ne!(clone_disk.identity.id, base_disk.identity.id);
+    assert_eq!(clone_disk.state, DiskState::Detached);
+    assert_eq!(clone_disk.size, disk_size);
+    assert_eq!(clone_disk.block_size, base_disk.block_size);
+
+    // The source disk is left detached, and the snapshot used to copy it is
+    // gone.
+    let disk: Disk =
+        NexusRequest::object_get(client, &get_disk_url("base-disk"))
+            .authn_as(AuthnMode::PrivilegedUser)
+            .execute()
+            .await
+            .unwrap()
+            .parsed_body()
+            .unwrap();
+    assert_eq!(disk.state, DiskState::Detached);
+
+    let snapshots = NexusRequest::iter_collection_authn::<views::Snapshot>(
+        client,
+        &format!("/v1/snapshots?project={}", PROJECT_NAME),
+        "",
+        None,
+    )
+    .await
+    .unwrap()
+    .all_items;
+    assert!(snapshots.is_empty());
+
+    // Only the two disks are accounted for.
+    let opctx =
+        OpContext::for_tests(cptestctx.logctx.log.new(o!()), datastore.clone());
+    let provision = datastore
+        .virtual_provisioning_collection_get(&opctx, project_id)
+        .await
+        .unwrap();
+    assert_eq!(
+        provision.virtual_disk_bytes_provisioned.to_bytes(),
+        2 * disk_size.to_bytes()
+    );
+
+    // Deleting both disks releases everything the copy used.
+    for name in ["base-disk", "clone-disk"] {
+        NexusRequest::object_delete(client, &get_disk_url(name))
+            .authn_as(AuthnMode::PrivilegedUser)
+            .execute()
+            .await
+            .expect("failed to delete disk");
+    }
+
+    assert!(disk_test.crucible_resources_deleted().await);
+}
+
 // Test the various ways Nexus can reject a disk created from a snapshot
 #[nexus_test]
 async fn test_reject_creating_disk_from_snapshot(
@@ -494,6 +759,10 @@ async fn test_reject_creating_disk_from_snapshot(
                 size: external::ByteCount::try_from(2 * MIN_DISK_SIZE_BYTES)
                     .unwrap()
                     .into(),
+                labels: Default::default(),
+                pantry_address: None,
+                snapshot_schedule_id: None,
+                transient: false,
             },
         )
         .await
@@ -519,6 +788,7 @@ async fn test_reject_creating_disk_from_snapshot(
                         + db::model::BlockSize::Traditional.to_bytes(),
                 )
                 .unwrap(),
+                labels: Default::default(),
             }))
             .expect_status(Some(StatusCode::BAD_REQUEST)),
     )
@@ -547,6 +817,7 @@ async fn test_reject_creating_disk_from_snapshot(
                 },
 
                 size: ByteCount::try_from(MIN_DISK_SIZE_BYTES).unwrap(),
+                labels: Default::default(),
             }))
             .expect_status(Some(StatusCode::BAD_REQUEST)),
     )
@@ -584,6 +855,7 @@ async fn test_reject_creating_disk_from_snapshot(
                         + db::model::BlockSize::AdvancedFormat.to_bytes(),
                 )
                 .unwrap(),
+                labels: Default::default(),
             }))
             .expect_status(Some(StatusCode::BAD_REQUEST)),
     )
@@ -649,6 +921,10 @@ async fn test_reject_creating_disk_from_illegal_snapshot(
                 )
                 .unwrap()
                 .into(),
+                labels: Default::default(),
+                pantry_address: None,
+                snapshot_schedule_id: None,
+                transient: false,
             },
         )
         .await
@@ -680,6 +956,7 @@ async fn test_reject_creating_disk_from_illegal_snapshot(
                     db::model::BlockSize::AdvancedFormat.to_bytes() * 2,
                 )
                 .unwrap(),
+                labels: Default::default(),
             }))
             .expect_status(Some(StatusCode::BAD_REQUEST)),
     )
@@ -745,6 +1022,10 @@ async fn test_reject_creating_disk_from_other_project_snapshot(
                 )
                 .unwrap()
                 .into(),
+                labels: Default::default(),
+                pantry_address: None,
+                snapshot_schedule_id: None,
+                transient: false,
             },
         )
         .await
@@ -766,6 +1047,7 @@ async fn test_reject_creating_disk_from_other_project_snapshot(
                 },
 
                 size: ByteCount::try_from(MIN_DISK_SIZE_BYTES).unwrap(),
+                labels: Default::default(),
             }))
             .expect_status(Some(StatusCode::BAD_REQUEST)),
     )
@@ -801,6 +1083,7 @@ async fn test_cannot_snapshot_if_no_space(cptestctx: &ControlPlaneTestContext) {
             block_size: params::BlockSize::try_from(512).unwrap(),
         },
         size: disk_size,
+        labels: Default::default(),
     };
 
     NexusRequest::new(
@@ -824,6 +1107,7 @@ async fn test_cannot_snapshot_if_no_space(cptestctx: &ControlPlaneTestContext) {
                     description: "not attached to instance".into(),
                 },
                 disk: base_disk_name.into(),
+                labels: Default::default(),
             }))
             .expect_status(Some(StatusCode::SERVICE_UNAVAILABLE)),
     )
@@ -867,6 +1151,7 @@ async fn test_snapshot_unwind(cptestctx: &ControlPlaneTestContext) {
         },
         os: "alpine".to_string(),
         version: "edge".to_string(),
+        labels: Default::default(),
     };
 
     let images_url = format!("/v1/images?project={}", PROJECT_NAME);
@@ -886,6 +1171,7 @@ async fn test_snapshot_unwind(cptestctx: &ControlPlaneTestContext) {
         },
         disk_source: params::DiskSource::Image { image_id: image.identity.id },
         size: disk_size,
+        labels: Default::default(),
     };
 
     let _base_disk: Disk = NexusRequest::new(
@@ -924,6 +1210,7 @@ async fn test_snapshot_unwind(cptestctx: &ControlPlaneTestContext) {
                 description: String::from("a snapshot"),
             },
             disk: base_disk_name.clone().into(),
+            labels: Default::default(),
         },
     )
     .authn_as(AuthnMode::PrivilegedUser)
@@ -976,6 +1263,10 @@ async fn test_create_snapshot_record_idempotent(
         state: db::model::SnapshotState::Creating,
         block_size: db::model::BlockSize::Traditional,
         size: external::ByteCount::try_from(1024u32).unwrap().into(),
+        labels: Default::default(),
+        pantry_address: None,
+        snapshot_schedule_id: None,
+        transient: false,
     };
 
     let opctx =
@@ -1041,3 +1332,141 @@ async fn test_region_snapshot_create_idempotent(
 
     datastore.region_snapshot_create(region_snapshot).await.unwrap();
 }
+
+#[nexus_test]
+async fn test_snapshot_schedule(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+    let nexus = &cptestctx.server.apictx().nexus;
+    DiskTest::new(&cptestctx).await;
+    create_org_and_project(client).await;
+    let disks_url = get_disks_url();
+
+    // Create two disks, only one of which has the label the schedule selects.
+    let labels = Labels::try_from(BTreeMap::from([(
+        String::from("backup"),
+        String::from("nightly"),
+    )]))
+    .unwrap();
+    let mut disks = Vec::new();
+    for (name, labels) in [
+        ("labeled-disk", labels.clone()),
+        ("unlabeled-disk", Labels::default()),
+    ] {
+        let disk: Disk = object_create(
+            client,
+            &disks_url,
+            &params::DiskCreate {
+                identity: IdentityMetadataCreateParams {
+                    name: name.parse().unwrap(),
+                    description: String::from("sells rainsticks"),
+                },
+                disk_source: params::DiskSource::Blank {
+                    block_size: params::BlockSize::try_from(512).unwrap(),
+                },
+                size: ByteCount::from_gibibytes_u32(1),
+                labels,
+            },
+        )
+        .await;
+        disks.push(disk);
+    }
+    let labeled_disk_id = disks[0].identity.id;
+
+    // A schedule must keep at least one snapshot.
+    let schedules_url =
+        format!("/v1/snapshot-schedules?project={}", PROJECT_NAME);
+    let mut schedule_create = params::SnapshotScheduleCreate {
+        identity: IdentityMetadataCreateParams {
+            name: "nightly".parse().unwrap(),
+            description: String::from("snapshots labeled disks"),
+        },
+        target: params::SnapshotScheduleTargetCreate::Labels { labels },
+        interval_secs: 1,
+        retain: 0,
+    };
+    NexusRequest::new(
+        RequestBuilder::new(client, Method::POST, &schedules_url)
+            .body(Some(&schedule_create))
+            .expect_status(Some(StatusCode::BAD_REQUEST)),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+
+    schedule_create.retain = 2;
+    let schedule: views::SnapshotSchedule =
+        object_create(client, &schedules_url, &schedule_create).await;
+    assert!(schedule.last_run.is_none());
+    let schedule_url = format!(
+        "/v1/snapshot-schedules/{}?project={}",
+        schedule.identity.name, PROJECT_NAME
+    );
+
+    // Run the schedule three times.  Each run snapshots the labeled disk, and
+    // once there are more than two snapshots, the oldest are deleted.
+    let mut last_time_started = None;
+    for run in 1..=3 {
+        let last_run = wait_for_condition(
+            || async {
+                nexus.activate_snapshot_schedules();
+                let schedule: views::SnapshotSchedule =
+                    NexusRequest::object_get(client, &schedule_url)
+                        .authn_as(AuthnMode::PrivilegedUser)
+                        .execute_and_parse_unwrap()
+                        .await;
+                match schedule.last_run {
+                    Some(last_run)
+                        if Some(last_run.time_started) != last_time_started
+                            && last_run.status
+                                != views::SnapshotScheduleRunStatus::Running =>
+                    {
+                        Ok(last_run)
+                    }
+                    _ => Err(CondCheckError::<()>::NotYet),
+                }
+            },
+            &Duration::from_millis(100),
+            &Duration::from_secs(30),
+        )
+        .await
+        .expect("snapshot schedule did not run");
+        assert_eq!(
+            last_run.status,
+            views::SnapshotScheduleRunStatus::Succeeded,
+            "run {} failed: {:?}",
+            run,
+            last_run.error
+        );
+        assert_eq!(last_run.snapshots_created, 1);
+        assert_eq!(last_run.snapshots_deleted, if run > 2 { 1 } else { 0 });
+        last_time_started = Some(last_run.time_started);
+    }
+
+    // Only the two most recent snapshots remain, all of the labeled disk.
+    let snapshots_url = format!("/v1/snapshots?project={}", PROJECT_NAME);
+    let snapshots =
+        objects_list_page_authz::<views::Snapshot>(client, &snapshots_url)
+            .await
+            .items;
+    assert_eq!(snapshots.len(), 2);
+    assert!(snapshots.iter().all(|s| s.disk_id == labeled_disk_id));
+
+    // Deleting the schedule leaves the snapshots it took alone.
+    object_delete(client, &schedule_url).await;
+    NexusRequest::expect_failure(
+        client,
+        StatusCode::NOT_FOUND,
+        Method::GET,
+        &schedule_url,
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+    let snapshots =
+        objects_list_page_authz::<views::Snapshot>(client, &snapshots_url)
+            .await
+            .items;
+    assert_eq!(snapshots.len(), 2);
+}
@@ -61,7 +61,11 @@ async fn create_instance_expect_failure(
         network_interfaces,
         external_ips: vec![],
         disks: vec![],
+        affinity_groups: vec![],
+        ssh_public_keys: None,
         start: true,
+        labels: Default::default(),
+        auto_restart_policy: Default::default(),
     };
 
     NexusRequest::new(
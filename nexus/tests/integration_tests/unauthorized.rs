@@ -6,6 +6,7 @@
 //! unauthorized users
 
 use super::endpoints::*;
+use crate::integration_tests::oidc::OIDC_JWKS;
 use crate::integration_tests::saml::SAML_IDP_DESCRIPTOR;
 use dropshot::test_util::ClientTestContext;
 use dropshot::HttpErrorResponseBody;
@@ -181,6 +182,12 @@ lazy_static! {
                 .respond_with(status_code(200).body(SAML_IDP_DESCRIPTOR)),
         );
 
+        server.expect(
+            Expectation::matching(request::method_path("GET", "/jwks"))
+                .times(1..)
+                .respond_with(status_code(200).body(OIDC_JWKS)),
+        );
+
         server
     };
 
@@ -243,6 +250,12 @@ lazy_static! {
             body: serde_json::to_value(&*DEMO_ROUTER_ROUTE_CREATE).unwrap(),
             id_routes: vec!["/by-id/vpc-router-routes/{id}"],
         },
+        // Create a VPC Peering from the Vpc to the Project's default VPC
+        SetupReq::Post {
+            url: &DEMO_VPC_URL_PEERINGS,
+            body: serde_json::to_value(&*DEMO_VPC_PEERING_CREATE).unwrap(),
+            id_routes: vec!["/v1/vpc-peerings/{id}"],
+        },
         // Create a Disk in the Project
         SetupReq::Post {
             url: &DEMO_DISKS_URL,
@@ -272,18 +285,59 @@ lazy_static! {
             body: serde_json::to_value(&*DEMO_SNAPSHOT_CREATE).unwrap(),
             id_routes: vec!["/by-id/snapshots/{id}"],
         },
+        // Create a Snapshot Schedule in the Project
+        SetupReq::Post {
+            url: &DEMO_PROJECT_URL_SNAPSHOT_SCHEDULES,
+            body: serde_json::to_value(&*DEMO_SNAPSHOT_SCHEDULE_CREATE).unwrap(),
+            id_routes: vec!["/v1/snapshot-schedules/{id}"],
+        },
+        // Create a Floating IP in the Project
+        SetupReq::Post {
+            url: &DEMO_PROJECT_URL_FIPS,
+            body: serde_json::to_value(&*DEMO_FLOAT_IP_CREATE).unwrap(),
+            id_routes: vec!["/v1/floating-ips/{id}"],
+        },
+        // Create an Affinity Group in the Project
+        SetupReq::Post {
+            url: &DEMO_PROJECT_URL_AFFINITY_GROUPS,
+            body: serde_json::to_value(&*DEMO_AFFINITY_GROUP_CREATE).unwrap(),
+            id_routes: vec!["/v1/affinity-groups/{id}"],
+        },
         // Create an Image in the Project
         SetupReq::Post {
             url: &DEMO_PROJECT_IMAGES_URL,
             body: serde_json::to_value(&*DEMO_IMAGE_CREATE).unwrap(),
             id_routes: vec!["/v1/images/{id}"],
         },
+        // Create an Image in the Silo
+        SetupReq::Post {
+            url: &DEMO_SILO_IMAGES_URL,
+            body: serde_json::to_value(&*DEMO_SILO_IMAGE_CREATE).unwrap(),
+            id_routes: vec![],
+        },
+        // Create a Webhook Receiver in the Silo
+        SetupReq::Post {
+            url: &DEMO_WEBHOOK_RECEIVERS_URL,
+            body: serde_json::to_value(&*DEMO_WEBHOOK_RECEIVER_CREATE).unwrap(),
+            id_routes: vec!["/v1/webhook-receivers/{id}"],
+        },
         // Create a SAML identity provider
         SetupReq::Post {
             url: &SAML_IDENTITY_PROVIDERS_URL,
             body: serde_json::to_value(&*SAML_IDENTITY_PROVIDER).unwrap(),
             id_routes: vec![],
         },
+        // Create a separate Silo for OpenID Connect and an identity provider
+        SetupReq::Post {
+            url: "/v1/system/silos",
+            body: serde_json::to_value(&*DEMO_OIDC_SILO_CREATE).unwrap(),
+            id_routes: vec![],
+        },
+        SetupReq::Post {
+            url: &OIDC_IDENTITY_PROVIDERS_URL,
+            body: serde_json::to_value(&*OIDC_IDENTITY_PROVIDER).unwrap(),
+            id_routes: vec![],
+        },
         // Create a SSH key
         SetupReq::Post {
             url: &DEMO_SSHKEYS_URL,
@@ -87,6 +87,7 @@ async fn create_image(client: &ClientTestContext) -> views::Image {
         },
         os: "alpine".to_string(),
         version: "edge".to_string(),
+        labels: Default::default(),
     };
 
     let images_url = format!("/v1/images?project={}", PROJECT_NAME);
@@ -113,6 +114,7 @@ async fn create_base_disk(
         },
         disk_source: params::DiskSource::Image { image_id: image.identity.id },
         size: disk_size,
+        labels: Default::default(),
     };
 
     NexusRequest::new(
@@ -158,6 +160,7 @@ async fn test_snapshot_then_delete_disk(cptestctx: &ControlPlaneTestContext) {
                 description: "a snapshot!".to_string(),
             },
             disk: base_disk_name.clone().into(),
+            labels: Default::default(),
         },
     )
     .await;
@@ -219,6 +222,7 @@ async fn test_delete_snapshot_then_disk(cptestctx: &ControlPlaneTestContext) {
                 description: "a snapshot!".to_string(),
             },
             disk: base_disk_name.clone().into(),
+            labels: Default::default(),
         },
     )
     .await;
@@ -280,6 +284,7 @@ async fn test_multiple_snapshots(cptestctx: &ControlPlaneTestContext) {
                     description: "a snapshot!".to_string(),
                 },
                 disk: base_disk_name.clone().into(),
+                labels: Default::default(),
             },
         )
         .await;
@@ -342,6 +347,7 @@ async fn test_snapshot_prevents_other_disk(
                 description: "a snapshot!".to_string(),
             },
             disk: base_disk_name.clone().into(),
+            labels: Default::default(),
         },
     )
     .await;
@@ -373,6 +379,7 @@ async fn test_snapshot_prevents_other_disk(
         },
         disk_source: params::DiskSource::Image { image_id: image.identity.id },
         size: disk_size,
+        labels: Default::default(),
     };
 
     NexusRequest::new(
@@ -442,6 +449,7 @@ async fn test_multiple_disks_multiple_snapshots_order_1(
             block_size: params::BlockSize::try_from(512).unwrap(),
         },
         size: disk_size,
+        labels: Default::default(),
     };
 
     let first_disk: Disk = NexusRequest::new(
@@ -466,6 +474,7 @@ async fn test_multiple_disks_multiple_snapshots_order_1(
                 description: "first snapshot!".to_string(),
             },
             disk: first_disk_name.clone().into(),
+            labels: Default::default(),
         },
     )
     .await;
@@ -484,6 +493,7 @@ async fn test_multiple_disks_multiple_snapshots_order_1(
             block_size: params::BlockSize::try_from(512).unwrap(),
         },
         size: disk_size,
+        labels: Default::default(),
     };
 
     let second_disk: Disk = NexusRequest::new(
@@ -508,6 +518,7 @@ async fn test_multiple_disks_multiple_snapshots_order_1(
                 description: "second snapshot!".to_string(),
             },
             disk: second_disk_name.clone().into(),
+            labels: Default::default(),
         },
     )
     .await;
@@ -578,6 +589,7 @@ async fn test_multiple_disks_multiple_snapshots_order_2(
             block_size: params::BlockSize::try_from(512).unwrap(),
         },
         size: disk_size,
+        labels: Default::default(),
     };
 
     let first_disk: Disk = NexusRequest::new(
@@ -602,6 +614,7 @@ async fn test_multiple_disks_multiple_snapshots_order_2(
                 description: "first snapshot!".to_string(),
             },
             disk: first_disk_name.clone().into(),
+            labels: Default::default(),
         },
     )
     .await;
@@ -620,6 +633,7 @@ async fn test_multiple_disks_multiple_snapshots_order_2(
             block_size: params::BlockSize::try_from(512).unwrap(),
         },
         size: disk_size,
+        labels: Default::default(),
     };
 
     let second_disk: Disk = NexusRequest::new(
@@ -644,6 +658,7 @@ async fn test_multiple_disks_multiple_snapshots_order_2(
                 description: "second snapshot!".to_string(),
             },
             disk: second_disk_name.clone().into(),
+            labels: Default::default(),
         },
     )
     .await;
@@ -708,6 +723,7 @@ async fn prepare_for_test_multiple_layers_of_snapshots(
             block_size: params::BlockSize::try_from(512).unwrap(),
         },
         size: disk_size,
+        labels: Default::default(),
     };
 
     let layer_1_disk: Disk = NexusRequest::new(
@@ -732,6 +748,7 @@ async fn prepare_for_test_multiple_layers_of_snapshots(
                 description: "layer 1 snapshot!".to_string(),
             },
             disk: layer_1_disk_name.clone().into(),
+            labels: Default::default(),
         },
     )
     .await;
@@ -750,6 +767,7 @@ async fn prepare_for_test_multiple_layers_of_snapshots(
             snapshot_id: layer_1_snapshot.identity.id,
         },
         size: disk_size,
+        labels: Default::default(),
     };
 
     let layer_2_disk: Disk = NexusRequest::new(
@@ -774,6 +792,7 @@ async fn prepare_for_test_multiple_layers_of_snapshots(
                 description: "layer 2 snapshot!".to_string(),
             },
             disk: layer_2_disk_name.clone().into(),
+            labels: Default::default(),
         },
     )
     .await;
@@ -792,6 +811,7 @@ async fn prepare_for_test_multiple_layers_of_snapshots(
             snapshot_id: layer_2_snapshot.identity.id,
         },
         size: disk_size,
+        labels: Default::default(),
     };
 
     let layer_3_disk: Disk = NexusRequest::new(
@@ -816,6 +836,7 @@ async fn prepare_for_test_multiple_layers_of_snapshots(
                 description: "layer 3 snapshot!".to_string(),
             },
             disk: layer_3_disk_name.clone().into(),
+            labels: Default::default(),
         },
     )
     .await;
@@ -996,6 +1017,7 @@ async fn test_create_image_from_snapshot(cptestctx: &ControlPlaneTestContext) {
                 description: "a snapshot!".to_string(),
             },
             disk: base_disk_name.clone().into(),
+            labels: Default::default(),
         },
     )
     .await;
@@ -1009,6 +1031,7 @@ async fn test_create_image_from_snapshot(cptestctx: &ControlPlaneTestContext) {
         source: params::ImageSource::Snapshot { id: snapshot.identity.id },
         os: "debian".parse().unwrap(),
         version: "11".into(),
+        labels: Default::default(),
     };
 
     let _image: views::Image =
@@ -1055,6 +1078,7 @@ async fn test_create_image_from_snapshot_delete(
                 description: "a snapshot!".to_string(),
             },
             disk: base_disk_name.clone().into(),
+            labels: Default::default(),
         },
     )
     .await;
@@ -1068,6 +1092,7 @@ async fn test_create_image_from_snapshot_delete(
         source: params::ImageSource::Snapshot { id: snapshot.identity.id },
         os: "debian".parse().unwrap(),
         version: "11".into(),
+        labels: Default::default(),
     };
 
     let _image: views::Image =
@@ -0,0 +1,257 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use dropshot::HttpErrorResponseBody;
+use http::method::Method;
+use http::StatusCode;
+use nexus_test_utils::http_testing::AuthnMode;
+use nexus_test_utils::http_testing::NexusRequest;
+use nexus_test_utils::resource_helpers::object_create;
+use nexus_test_utils::resource_helpers::object_delete;
+use nexus_test_utils::resource_helpers::objects_list_page_authz;
+use nexus_test_utils::resource_helpers::{create_project, create_vpc};
+use nexus_test_utils_macros::nexus_test;
+use omicron_common::api::external::IdentityMetadataCreateParams;
+use omicron_common::api::external::Ipv4Net;
+use omicron_common::api::external::NameOrId;
+use omicron_common::api::external::RouteDestination;
+use omicron_common::api::external::RouterRoute;
+use omicron_common::api::external::RouterRouteKind;
+use omicron_nexus::external_api::params;
+use omicron_nexus::external_api::shared::VpcPeeringState;
+use omicron_nexus::external_api::views::VpcPeering;
+use omicron_nexus::external_api::views::VpcSubnet;
+
+type ControlPlaneTestContext =
+    nexus_test_utils::ControlPlaneTestContext<omicron_nexus::Server>;
+
+const PROJECT_NAME: &str = "springfield-squidport";
+
+fn peerings_url(vpc_name: &str) -> String {
+    format!("/v1/vpc-peerings?project={}&vpc={}", PROJECT_NAME, vpc_name)
+}
+
+fn peering_url(vpc_name: &str, peering_name: &str) -> String {
+    format!(
+        "/v1/vpc-peerings/{}?project={}&vpc={}",
+        peering_name, PROJECT_NAME, vpc_name
+    )
+}
+
+fn peering_create(name: &str, peer_vpc_name: &str) -> params::VpcPeeringCreate {
+    params::VpcPeeringCreate {
+        identity: IdentityMetadataCreateParams {
+            name: name.parse().unwrap(),
+            description: String::from("peering description"),
+        },
+        peer_vpc: NameOrId::Name(peer_vpc_name.parse().unwrap()),
+        peer_project: None,
+    }
+}
+
+async fn peering_create_error(
+    client: &dropshot::test_util::ClientTestContext,
+    vpc_name: &str,
+    params: &params::VpcPeeringCreate,
+) -> HttpErrorResponseBody {
+    NexusRequest::expect_failure_with_body(
+        client,
+        StatusCode::BAD_REQUEST,
+        Method::POST,
+        &peerings_url(vpc_name),
+        params,
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap()
+    .parsed_body()
+    .unwrap()
+}
+
+async fn peering_routes(
+    client: &dropshot::test_util::ClientTestContext,
+    vpc_name: &str,
+) -> Vec<RouterRoute> {
+    let url = format!(
+        "/v1/vpc-router-routes?project={}&vpc={}&router=system",
+        PROJECT_NAME, vpc_name
+    );
+    objects_list_page_authz::<RouterRoute>(client, &url)
+        .await
+        .items
+        .into_iter()
+        .filter(|route| route.kind == RouterRouteKind::VpcPeering)
+        .collect()
+}
+
+#[nexus_test]
+async fn test_vpc_peerings(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+
+    let _ = create_project(&client, PROJECT_NAME).await;
+    let vpc1 = create_vpc(&client, PROJECT_NAME, "vpc1").await;
+    let vpc2 = create_vpc(&client, PROJECT_NAME, "vpc2").await;
+
+    // A VPC can't be peered with itself.
+    let error =
+        peering_create_error(client, "vpc1", &peering_create("self", "vpc1"))
+            .await;
+    assert_eq!(error.message, "a VPC cannot be peered with itself");
+
+    // Creating one side of the peering leaves it pending.
+    let peering1: VpcPeering = object_create(
+        client,
+        &peerings_url("vpc1"),
+        &peering_create("to-vpc2", "vpc2"),
+    )
+    .await;
+    assert_eq!(peering1.vpc_id, vpc1.identity.id);
+    assert_eq!(peering1.peer_vpc_id, vpc2.identity.id);
+    assert_eq!(peering1.state, VpcPeeringState::Pending);
+    assert!(peering_routes(client, "vpc1").await.is_empty());
+
+    // Both VPCs have a default subnet with the same IPv4 block, so the other
+    // side of the peering can't be created until that's changed.
+    let error = peering_create_error(
+        client,
+        "vpc2",
+        &peering_create("to-vpc1", "vpc1"),
+    )
+    .await;
+    assert_eq!(
+        error.message,
+        "VPCs whose subnets have overlapping IPv4 blocks cannot be peered"
+    );
+    object_delete(
+        client,
+        &format!("/v1/vpc-subnets/default?project={}&vpc=vpc2", PROJECT_NAME),
+    )
+    .await;
+    let _: VpcSubnet = object_create(
+        client,
+        &format!("/v1/vpc-subnets?project={}&vpc=vpc2", PROJECT_NAME),
+        &params::VpcSubnetCreate {
+            identity: IdentityMetadataCreateParams {
+                name: "subnet2".parse().unwrap(),
+                description: String::from("subnet description"),
+            },
+            ipv4_block: Ipv4Net("10.1.0.0/24".parse().unwrap()),
+            ipv6_block: None,
+        },
+    )
+    .await;
+
+    // Creating the other side activates both.
+    let peering2: VpcPeering = object_create(
+        client,
+        &peerings_url("vpc2"),
+        &peering_create("to-vpc1", "vpc1"),
+    )
+    .await;
+    assert_eq!(peering2.state, VpcPeeringState::Active);
+    let peering1: VpcPeering =
+        NexusRequest::object_get(client, &peering_url("vpc1", "to-vpc2"))
+            .authn_as(AuthnMode::PrivilegedUser)
+            .execute()
+            .await
+            .unwrap()
+            .parsed_body()
+            .unwrap();
+    assert_eq!(peering1.state, VpcPeeringState::Active);
+
+    let peerings =
+        objects_list_page_authz::<VpcPeering>(client, &peerings_url("vpc1"))
+            .await
+            .items;
+    assert_eq!(peerings.len(), 1);
+    assert_eq!(peerings[0].identity.id, peering1.identity.id);
+
+    // Each VPC's system router has a route to the other.
+    let routes = peering_routes(client, "vpc1").await;
+    assert_eq!(routes.len(), 1);
+    assert_eq!(
+        routes[0].identity.name.as_str(),
+        format!("peer-{}", peering1.identity.id)
+    );
+    assert_eq!(
+        routes[0].destination,
+        RouteDestination::Vpc("vpc2".parse().unwrap())
+    );
+    let routes = peering_routes(client, "vpc2").await;
+    assert_eq!(routes.len(), 1);
+    assert_eq!(
+        routes[0].destination,
+        RouteDestination::Vpc("vpc1".parse().unwrap())
+    );
+
+    // While the VPCs are peered, neither can get a subnet that overlaps one of
+    // the other's.
+    let error: HttpErrorResponseBody = NexusRequest::expect_failure_with_body(
+        client,
+        StatusCode::BAD_REQUEST,
+        Method::POST,
+        &format!("/v1/vpc-subnets?project={}&vpc=vpc1", PROJECT_NAME),
+        &params::VpcSubnetCreate {
+            identity: IdentityMetadataCreateParams {
+                name: "overlapping".parse().unwrap(),
+                description: String::from("subnet description"),
+            },
+            ipv4_block: Ipv4Net("10.1.0.128/25".parse().unwrap()),
+            ipv6_block: None,
+        },
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap()
+    .parsed_body()
+    .unwrap();
+    assert_eq!(
+        error.message,
+        "IPv4 block '10.1.0.128/25' overlaps the block '10.1.0.0/24' of a \
+        subnet in a peered VPC"
+    );
+
+    // A peered VPC can't be deleted, even once it has no subnets.
+    object_delete(
+        client,
+        &format!("/v1/vpc-subnets/default?project={}&vpc=vpc1", PROJECT_NAME),
+    )
+    .await;
+    let error: HttpErrorResponseBody = NexusRequest::expect_failure(
+        client,
+        StatusCode::BAD_REQUEST,
+        Method::DELETE,
+        &format!("/v1/vpcs/vpc1?project={}", PROJECT_NAME),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap()
+    .parsed_body()
+    .unwrap();
+    assert_eq!(error.message, "VPC cannot be deleted while VPC peerings exist");
+
+    // Deleting one side reverts the other to pending and removes the routes.
+    object_delete(client, &peering_url("vpc1", "to-vpc2")).await;
+    let peering2: VpcPeering =
+        NexusRequest::object_get(client, &peering_url("vpc2", "to-vpc1"))
+            .authn_as(AuthnMode::PrivilegedUser)
+            .execute()
+            .await
+            .unwrap()
+            .parsed_body()
+            .unwrap();
+    assert_eq!(peering2.state, VpcPeeringState::Pending);
+    assert!(peering_routes(client, "vpc1").await.is_empty());
+    assert!(peering_routes(client, "vpc2").await.is_empty());
+    assert!(objects_list_page_authz::<VpcPeering>(
+        client,
+        &peerings_url("vpc1")
+    )
+    .await
+    .items
+    .is_empty());
+}
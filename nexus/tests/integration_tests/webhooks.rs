@@ -0,0 +1,274 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Tests webhook receivers and the delivery of events to them
+
+use http::method::Method;
+use http::StatusCode;
+use nexus_test_utils::http_testing::AuthnMode;
+use nexus_test_utils::http_testing::NexusRequest;
+use nexus_test_utils::http_testing::RequestBuilder;
+use nexus_test_utils::resource_helpers::create_project;
+use nexus_test_utils::resource_helpers::object_create;
+use nexus_test_utils::resource_helpers::object_delete;
+use nexus_test_utils::resource_helpers::objects_list_page_authz;
+use nexus_test_utils::resource_helpers::DiskTest;
+use nexus_test_utils_macros::nexus_test;
+use omicron_common::api::external::ByteCount;
+use omicron_common::api::external::Disk;
+use omicron_common::api::external::IdentityMetadataCreateParams;
+use omicron_nexus::external_api::params;
+use omicron_nexus::external_api::shared::WebhookEventClass;
+use omicron_nexus::external_api::views;
+use omicron_nexus::TestInterfaces as _;
+use omicron_test_utils::dev::poll::{wait_for_condition, CondCheckError};
+use std::time::Duration;
+
+use httptest::{matchers::*, responders::*, Expectation, ServerBuilder};
+
+type ControlPlaneTestContext =
+    nexus_test_utils::ControlPlaneTestContext<omicron_nexus::Server>;
+
+const PROJECT_NAME: &str = "webhook-project";
+const RECEIVERS_URL: &str = "/v1/webhook-receivers";
+
+fn receiver_create(
+    name: &str,
+    endpoint: String,
+    event_classes: Vec<WebhookEventClass>,
+) -> params::WebhookReceiverCreate {
+    params::WebhookReceiverCreate {
+        identity: IdentityMetadataCreateParams {
+            name: name.parse().unwrap(),
+            description: String::from("listens for events"),
+        },
+        endpoint,
+        secret: String::from("hunter2"),
+        event_classes,
+    }
+}
+
+#[nexus_test]
+async fn test_webhook_receiver_validation(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+
+    // A receiver needs an HTTP endpoint, a secret, and at least one class of
+    // event to be sent.
+    let mut bad_endpoint = receiver_create(
+        "bad-endpoint",
+        String::from("ftp://example.com/events"),
+        vec![WebhookEventClass::DiskAttach],
+    );
+    let mut no_secret = receiver_create(
+        "no-secret",
+        String::from("http://example.com/events"),
+        vec![WebhookEventClass::DiskAttach],
+    );
+    no_secret.secret = String::new();
+    let no_classes = receiver_create(
+        "no-classes",
+        String::from("http://example.com/events"),
+        vec![],
+    );
+    for body in [&bad_endpoint, &no_secret, &no_classes] {
+        NexusRequest::new(
+            RequestBuilder::new(client, Method::POST, RECEIVERS_URL)
+                .body(Some(body))
+                .expect_status(Some(StatusCode::BAD_REQUEST)),
+        )
+        .authn_as(AuthnMode::PrivilegedUser)
+        .execute()
+        .await
+        .unwrap();
+    }
+
+    // Subscribing to a class of event twice is the same as doing so once.
+    bad_endpoint.endpoint = String::from("https://example.com/events");
+    bad_endpoint.event_classes =
+        vec![WebhookEventClass::DiskAttach, WebhookEventClass::DiskAttach];
+    let receiver: views::WebhookReceiver =
+        object_create(client, RECEIVERS_URL, &bad_endpoint).await;
+    assert_eq!(receiver.event_classes, vec![WebhookEventClass::DiskAttach]);
+
+    let receivers = objects_list_page_authz::<views::WebhookReceiver>(
+        client,
+        RECEIVERS_URL,
+    )
+    .await
+    .items;
+    assert_eq!(receivers.len(), 1);
+    assert_eq!(receivers[0].identity.id, receiver.identity.id);
+
+    // Names are unique within the silo.
+    NexusRequest::new(
+        RequestBuilder::new(client, Method::POST, RECEIVERS_URL)
+            .body(Some(&bad_endpoint))
+            .expect_status(Some(StatusCode::BAD_REQUEST)),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+
+    let receiver_url = format!("{}/{}", RECEIVERS_URL, receiver.identity.name);
+    object_delete(client, &receiver_url).await;
+    NexusRequest::expect_failure(
+        client,
+        StatusCode::NOT_FOUND,
+        Method::GET,
+        &receiver_url,
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+}
+
+#[nexus_test]
+async fn test_webhook_delivery(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+    let nexus = &cptestctx.server.apictx().nexus;
+    DiskTest::new(&cptestctx).await;
+    create_project(client, PROJECT_NAME).await;
+
+    // The receiver fails the first delivery, and accepts the retry.  Every
+    // attempt is signed, and says which delivery it is and what it's about.
+    let server = ServerBuilder::new().run().unwrap();
+    server.expect(
+        Expectation::matching(all_of![
+            request::method_path("POST", "/events"),
+            request::headers(contains(key("x-oxide-webhook-signature"))),
+            request::headers(contains(key("x-oxide-webhook-delivery-id"))),
+            request::headers(contains((
+                "x-oxide-webhook-event-class",
+                "snapshot_complete"
+            ))),
+        ])
+        .times(2)
+        .respond_with(cycle![status_code(500), status_code(200)]),
+    );
+
+    let snapshot_receiver: views::WebhookReceiver = object_create(
+        client,
+        RECEIVERS_URL,
+        &receiver_create(
+            "snapshot-receiver",
+            server.url_str("/events"),
+            vec![WebhookEventClass::SnapshotComplete],
+        ),
+    )
+    .await;
+    let disk_receiver: views::WebhookReceiver = object_create(
+        client,
+        RECEIVERS_URL,
+        &receiver_create(
+            "disk-receiver",
+            server.url_str("/events"),
+            vec![WebhookEventClass::DiskAttach],
+        ),
+    )
+    .await;
+
+    // Taking a snapshot is an event only the first receiver subscribes to.
+    let disk: Disk = object_create(
+        client,
+        &format!("/v1/disks?project={}", PROJECT_NAME),
+        &params::DiskCreate {
+            identity: IdentityMetadataCreateParams {
+                name: "webhook-disk".parse().unwrap(),
+                description: String::from("gets snapshotted"),
+            },
+            disk_source: params::DiskSource::Blank {
+                block_size: params::BlockSize::try_from(512).unwrap(),
+            },
+            size: ByteCount::from_gibibytes_u32(1),
+            labels: Default::default(),
+        },
+    )
+    .await;
+    let snapshot: views::Snapshot = object_create(
+        client,
+        &format!("/v1/snapshots?project={}", PROJECT_NAME),
+        &params::SnapshotCreate {
+            identity: IdentityMetadataCreateParams {
+                name: "webhook-snapshot".parse().unwrap(),
+                description: String::from("sets off a webhook"),
+            },
+            disk: disk.identity.id.into(),
+            labels: Default::default(),
+        },
+    )
+    .await;
+    assert_eq!(snapshot.disk_id, disk.identity.id);
+
+    let deliveries_url = format!(
+        "{}/{}/deliveries",
+        RECEIVERS_URL, snapshot_receiver.identity.name
+    );
+    let delivery = wait_for_condition(
+        || async {
+            nexus.activate_webhook_deliveries();
+            let deliveries = objects_list_page_authz::<views::WebhookDelivery>(
+                client,
+                &deliveries_url,
+            )
+            .await
+            .items;
+            match deliveries.as_slice() {
+                [delivery]
+                    if delivery.state
+                        != views::WebhookDeliveryState::Pending =>
+                {
+                    Ok(delivery.clone())
+                }
+                _ => Err(CondCheckError::<()>::NotYet),
+            }
+        },
+        &Duration::from_millis(100),
+        &Duration::from_secs(30),
+    )
+    .await
+    .expect("webhook event was not delivered");
+    assert_eq!(
+        delivery.state,
+        views::WebhookDeliveryState::Delivered,
+        "delivery failed: {:?}",
+        delivery.last_error
+    );
+    assert_eq!(delivery.event_class, WebhookEventClass::SnapshotComplete);
+    assert_eq!(delivery.attempts, 2);
+    assert_eq!(delivery.last_response_status, Some(200));
+    assert!(delivery.last_error.is_none());
+    assert!(delivery.time_next_attempt.is_none());
+    server.verify_and_clear();
+
+    // The other receiver wasn't sent anything.
+    let deliveries = objects_list_page_authz::<views::WebhookDelivery>(
+        client,
+        &format!(
+            "{}/{}/deliveries",
+            RECEIVERS_URL, disk_receiver.identity.name
+        ),
+    )
+    .await
+    .items;
+    assert!(deliveries.is_empty());
+
+    // Deleting a receiver deletes the record of its deliveries with it.
+    object_delete(
+        client,
+        &format!("{}/{}", RECEIVERS_URL, snapshot_receiver.identity.name),
+    )
+    .await;
+    NexusRequest::expect_failure(
+        client,
+        StatusCode::NOT_FOUND,
+        Method::GET,
+        &deliveries_url,
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+}
@@ -10,9 +10,9 @@ use base64::Engine;
 use chrono::{DateTime, Utc};
 use omicron_common::api::external::{
     AddressLotKind, ByteCount, IdentityMetadataCreateParams,
-    IdentityMetadataUpdateParams, InstanceCpuCount, IpNet, Ipv4Net, Ipv6Net,
-    Name, NameOrId, PaginationOrder, RouteDestination, RouteTarget,
-    SemverVersion,
+    IdentityMetadataUpdateParams, InstanceAutoRestartPolicy, InstanceCpuCount,
+    IpNet, Ipv4Net, Ipv6Net, LabelSelector, Labels, Name, NameOrId,
+    PaginationOrder, RouteDestination, RouteTarget, SemverVersion,
 };
 use schemars::JsonSchema;
 use serde::{
@@ -54,16 +54,23 @@ path_param!(VpcPath, vpc, "VPC");
 path_param!(SubnetPath, subnet, "subnet");
 path_param!(RouterPath, router, "router");
 path_param!(RoutePath, route, "route");
+path_param!(VpcPeeringPath, peering, "VPC peering");
 path_param!(DiskPath, disk, "disk");
 path_param!(SnapshotPath, snapshot, "snapshot");
+path_param!(SnapshotSchedulePath, schedule, "snapshot schedule");
 path_param!(ImagePath, image, "image");
 path_param!(SiloPath, silo, "silo");
 path_param!(ProviderPath, provider, "SAML identity provider");
 path_param!(IpPoolPath, pool, "IP pool");
 path_param!(SshKeyPath, ssh_key, "SSH key");
 path_param!(AddressLotPath, address_lot, "address lot");
+path_param!(FloatingIpPath, floating_ip, "floating IP");
+path_param!(AffinityGroupPath, affinity_group, "affinity group");
+path_param!(WebhookReceiverPath, receiver, "webhook receiver");
 
 id_path_param!(GroupPath, group_id, "group");
+id_path_param!(TokenPath, token_id, "access token");
+id_path_param!(ScimClientBearerTokenPath, token_id, "SCIM client token");
 
 // TODO: The hardware resources should be represented by its UUID or a hardware
 // ID that can be used to deterministically generate the UUID.
@@ -106,6 +113,14 @@ pub struct SamlIdentityProviderSelector {
     pub saml_identity_provider: NameOrId,
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct OidcIdentityProviderSelector {
+    /// Name or ID of the silo in which the OIDC identity provider is associated
+    pub silo: Option<NameOrId>,
+    /// Name or ID of the OIDC identity provider
+    pub oidc_identity_provider: NameOrId,
+}
+
 // The shape of this selector is slightly different than the others given that
 // silos users can only be specified via ID and are automatically provided by
 // the environment the user is authetnicated in
@@ -129,6 +144,28 @@ pub struct OptionalProjectSelector {
     pub project: Option<NameOrId>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct OptionalLabelSelector {
+    /// Only list resources having all of these labels
+    pub label_selector: Option<LabelSelector>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct ProjectLabelSelector {
+    /// Name or ID of the project
+    pub project: NameOrId,
+    /// Only list resources having all of these labels
+    pub label_selector: Option<LabelSelector>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct OptionalProjectLabelSelector {
+    /// Name or ID of the project
+    pub project: Option<NameOrId>,
+    /// Only list resources having all of these labels
+    pub label_selector: Option<LabelSelector>,
+}
+
 #[derive(Deserialize, JsonSchema)]
 pub struct DiskSelector {
     /// Name or ID of the project, only required if `disk` is provided as a `Name`
@@ -145,6 +182,14 @@ pub struct SnapshotSelector {
     pub snapshot: NameOrId,
 }
 
+#[derive(Deserialize, JsonSchema)]
+pub struct SnapshotScheduleSelector {
+    /// Name or ID of the project, only required if `schedule` is provided as a `Name`
+    pub project: Option<NameOrId>,
+    /// Name or ID of the snapshot schedule
+    pub schedule: NameOrId,
+}
+
 #[derive(Deserialize, JsonSchema)]
 pub struct ImageSelector {
     /// Name or ID of the project, only required if `image` is provided as a `Name`
@@ -153,6 +198,22 @@ pub struct ImageSelector {
     pub image: NameOrId,
 }
 
+#[derive(Deserialize, JsonSchema)]
+pub struct FloatingIpSelector {
+    /// Name or ID of the project, only required if `floating_ip` is provided as a `Name`
+    pub project: Option<NameOrId>,
+    /// Name or ID of the Floating IP
+    pub floating_ip: NameOrId,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct AffinityGroupSelector {
+    /// Name or ID of the project, only required if `affinity_group` is provided as a `Name`
+    pub project: Option<NameOrId>,
+    /// Name or ID of the affinity group
+    pub affinity_group: NameOrId,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 pub struct InstanceSelector {
     /// Name or ID of the project, only required if `instance` is provided as a `Name`
@@ -237,6 +298,16 @@ pub struct RouteSelector {
     pub route: NameOrId,
 }
 
+#[derive(Deserialize, JsonSchema)]
+pub struct VpcPeeringSelector {
+    /// Name or ID of the project, only required if `vpc` is provided as a `Name`
+    pub project: Option<NameOrId>,
+    /// Name or ID of the VPC, only required if `peering` is provided as a `Name`
+    pub vpc: Option<NameOrId>,
+    /// Name or ID of the VPC peering
+    pub peering: NameOrId,
+}
+
 // Silos
 
 /// Create-time parameters for a `Silo`
@@ -578,6 +649,42 @@ pub struct SamlIdentityProviderCreate {
     pub group_attribute_name: Option<String>,
 }
 
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum OidcDiscoverySource {
+    Url { url: String },
+    Base64EncodedJson { data: String },
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct OidcIdentityProviderCreate {
+    #[serde(flatten)]
+    pub identity: IdentityMetadataCreateParams,
+
+    /// the source of the identity provider's discovery document, which is
+    /// usually served at `<issuer>/.well-known/openid-configuration`
+    pub discovery_source: OidcDiscoverySource,
+
+    /// client id assigned to the system by the identity provider
+    pub client_id: String,
+
+    /// client secret assigned to the system by the identity provider
+    pub client_secret: String,
+
+    /// URL of this provider's login callback, as registered with the identity
+    /// provider (`https://<silo DNS name>/login/<silo>/oidc/<name>/callback`)
+    pub redirect_uri: String,
+
+    /// scopes to request in addition to `openid`, e.g., `profile` or `groups`
+    #[serde(default)]
+    pub scopes: Vec<String>,
+
+    /// If set, ID token claims with this name will be considered to denote a
+    /// user's group membership, where the claim's value should be a list of
+    /// group names or a comma-separated string of them.
+    pub group_claim_name: Option<String>,
+}
+
 /// sign some junk data and validate it with the key pair
 fn sign_junk_data(key_pair: &DerEncodedKeyPair) -> Result<(), anyhow::Error> {
     let private_key = {
@@ -648,6 +755,9 @@ where
 pub struct ProjectCreate {
     #[serde(flatten)]
     pub identity: IdentityMetadataCreateParams,
+    /// user-defined labels for the project
+    #[serde(default)]
+    pub labels: Labels,
 }
 
 /// Updateable properties of a `Project`
@@ -655,6 +765,25 @@ pub struct ProjectCreate {
 pub struct ProjectUpdate {
     #[serde(flatten)]
     pub identity: IdentityMetadataUpdateParams,
+    /// if specified, replaces all of the project's labels
+    pub labels: Option<Labels>,
+}
+
+// QUOTAS
+
+/// Updateable limits on the virtual resources provisioned within a silo or
+/// project
+///
+/// Each limit replaces the previous value. A limit which is omitted (or
+/// `null`) means that the corresponding resource is not limited.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, JsonSchema)]
+pub struct QuotaUpdate {
+    /// Maximum number of virtual CPUs which may be provisioned by instances
+    pub cpus: Option<u32>,
+    /// Maximum amount of memory which may be provisioned by instances
+    pub memory: Option<ByteCount>,
+    /// Maximum amount of storage which may be provisioned by disks
+    pub storage: Option<ByteCount>,
 }
 
 // NETWORK INTERFACES
@@ -731,6 +860,15 @@ impl std::fmt::Debug for CertificateCreate {
 pub struct IpPoolCreate {
     #[serde(flatten)]
     pub identity: IdentityMetadataCreateParams,
+    /// The Silo whose instances may draw addresses from this pool.  If not
+    /// specified, the pool is available to every Silo.
+    pub silo: Option<NameOrId>,
+    /// Whether this is the default pool for its Silo, or for the whole fleet
+    /// if no Silo is specified.  Instances draw addresses from their Silo's
+    /// default pool, or from the fleet's default pool if their Silo has none,
+    /// unless another pool is requested.
+    #[serde(default)]
+    pub is_default: bool,
 }
 
 /// Parameters for updating an IP Pool
@@ -740,6 +878,84 @@ pub struct IpPoolUpdate {
     pub identity: IdentityMetadataUpdateParams,
 }
 
+/// Parameters for linking an IP Pool to a Silo
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct IpPoolSiloLink {
+    /// The Silo whose instances may draw addresses from the pool.  A pool can
+    /// be linked to only one Silo, so this replaces any Silo it's linked to.
+    pub silo: NameOrId,
+    /// Whether the pool is the default pool for the Silo
+    #[serde(default)]
+    pub is_default: bool,
+}
+
+/// Parameters for making an IP Pool the default pool, or not, for its Silo
+/// (or for the whole fleet, if it isn't linked to a Silo)
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct IpPoolSetDefault {
+    pub is_default: bool,
+}
+
+// FLOATING IPS
+
+/// Parameters for creating a new floating IP address for instances.
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct FloatingIpCreate {
+    #[serde(flatten)]
+    pub identity: IdentityMetadataCreateParams,
+
+    /// An IP address to reserve for use as a floating IP. This field is
+    /// optional: when not set, an address will be automatically chosen from
+    /// `pool`. If set, then the IP must be available in the resolved `pool`.
+    pub address: Option<IpAddr>,
+
+    /// The parent IP pool that a floating IP is pulled from. If unset, the
+    /// default pool is selected.
+    pub pool: Option<Name>,
+}
+
+/// Parameters for attaching a floating IP address to an instance
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct FloatingIpAttach {
+    /// Name or ID of the instance, which must be in the same project as the
+    /// floating IP
+    pub instance: NameOrId,
+}
+
+// AFFINITY GROUPS
+
+/// Create-time parameters for an `AffinityGroup`
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct AffinityGroupCreate {
+    #[serde(flatten)]
+    pub identity: IdentityMetadataCreateParams,
+
+    /// Whether member instances are placed on the same sled or on different
+    /// sleds
+    pub kind: shared::AffinityGroupKind,
+
+    /// Whether an instance which cannot be placed according to this group
+    /// fails to be placed, or is placed anyway
+    pub policy: shared::AffinityPolicy,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct AffinityGroupMemberPath {
+    /// Name or ID of the affinity group
+    pub affinity_group: NameOrId,
+    /// Name or ID of the instance, which must be in the same project as the
+    /// affinity group
+    pub instance: NameOrId,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct ImageSiloPath {
+    /// Name or ID of the image, which must be a silo image
+    pub image: NameOrId,
+    /// Name or ID of the silo
+    pub silo: NameOrId,
+}
+
 // INSTANCES
 
 /// Describes an attachment of an `InstanceNetworkInterface` to an `Instance`,
@@ -849,9 +1065,59 @@ pub struct InstanceCreate {
     #[serde(default)]
     pub disks: Vec<InstanceDiskAttachment>,
 
+    /// The affinity groups this instance should be a member of. These must be
+    /// in the same project as the instance, and constrain the sled on which
+    /// it is placed.
+    #[serde(default)]
+    pub affinity_groups: Vec<NameOrId>,
+
+    /// The names or IDs of the creating user's SSH public keys to inject into
+    /// this instance via cloud-init.
+    ///
+    /// If this is omitted, all of the creating user's SSH public keys are
+    /// injected. An empty list injects no keys.
+    #[serde(default)]
+    pub ssh_public_keys: Option<Vec<NameOrId>>,
+
     /// Should this instance be started upon creation; true by default.
     #[serde(default = "bool_true")]
     pub start: bool,
+
+    /// user-defined labels for the instance
+    #[serde(default)]
+    pub labels: Labels,
+
+    /// Whether the instance should be restarted automatically if it fails;
+    /// never, by default.
+    #[serde(default)]
+    pub auto_restart_policy: InstanceAutoRestartPolicy,
+}
+
+/// Updateable properties of an `Instance`
+///
+/// An instance can only be updated while it is stopped.
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct InstanceUpdate {
+    /// the number of vCPUs for the instance
+    pub ncpus: InstanceCpuCount,
+    /// the amount of memory for the instance, in bytes
+    pub memory: ByteCount,
+}
+
+/// New user data for an `Instance`
+///
+/// The instance's cidata volume is regenerated with the new user data the
+/// next time the instance starts.  The instance ID given to cloud-init changes
+/// with the user data, so cloud-init treats the instance as new and reruns the
+/// parts of the user data (and the network configuration) that it only
+/// applies once per instance.
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct InstanceUserDataUpdate {
+    /// User data for instance initialization systems (such as cloud-init).
+    /// Must be a Base64-encoded string, as specified in RFC 4648 § 4 (+ and /
+    /// characters with padding). Maximum 32 KiB unencoded data.
+    #[serde(default, with = "UserData")]
+    pub user_data: Vec<u8>,
 }
 
 #[inline]
@@ -986,6 +1252,10 @@ pub struct VpcCreate {
     pub ipv6_prefix: Option<Ipv6Net>,
 
     pub dns_name: Name,
+
+    /// user-defined labels for the VPC
+    #[serde(default)]
+    pub labels: Labels,
 }
 
 /// Updateable properties of a `Vpc`
@@ -994,6 +1264,8 @@ pub struct VpcUpdate {
     #[serde(flatten)]
     pub identity: IdentityMetadataUpdateParams,
     pub dns_name: Option<Name>,
+    /// if specified, replaces all of the VPC's labels
+    pub labels: Option<Labels>,
 }
 
 /// Create-time parameters for a `VpcSubnet`
@@ -1024,6 +1296,22 @@ pub struct VpcSubnetUpdate {
     pub identity: IdentityMetadataUpdateParams,
 }
 
+// VPC PEERINGS
+
+/// Create-time parameters for a `VpcPeering`
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct VpcPeeringCreate {
+    #[serde(flatten)]
+    pub identity: IdentityMetadataCreateParams,
+
+    /// Name or ID of the VPC to peer with, which must be in the same silo.
+    pub peer_vpc: NameOrId,
+
+    /// Name or ID of the peer VPC's project, if `peer_vpc` is provided as a
+    /// `Name`. Defaults to the project of the VPC being peered from.
+    pub peer_project: Option<NameOrId>,
+}
+
 // VPC ROUTERS
 
 /// Create-time parameters for a `VpcRouter`
@@ -1140,6 +1428,9 @@ pub enum DiskSource {
     /// Create a blank disk that will accept bulk writes or pull blocks from an
     /// external source.
     ImportingBlocks { block_size: BlockSize },
+    /// Create a disk by copying another disk. The source disk must be
+    /// detached.
+    Disk { disk_id: Uuid },
 }
 
 /// Create-time parameters for a `Disk`
@@ -1152,6 +1443,9 @@ pub struct DiskCreate {
     pub disk_source: DiskSource,
     /// total size of the Disk in bytes
     pub size: ByteCount,
+    /// user-defined labels for the disk
+    #[serde(default)]
+    pub labels: Labels,
 }
 
 // equivalent to crucible_pantry_client::types::ExpectedDigest
@@ -1178,6 +1472,15 @@ pub struct ImportBlocksBulkWrite {
     pub base64_encoded_data: String,
 }
 
+/// Parameters for exporting blocks with a bulk read
+// equivalent to crucible_pantry_client::types::BulkReadRequest
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct ExportBlocksBulkRead {
+    pub offset: u64,
+    /// number of bytes to read, at most 512 KiB
+    pub size: u64,
+}
+
 /// Parameters for finalizing a disk
 #[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
 pub struct FinalizeDisk {
@@ -1188,6 +1491,22 @@ pub struct FinalizeDisk {
     pub snapshot_name: Option<Name>,
 }
 
+/// Parameters for growing a disk
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct DiskResize {
+    /// the new total size of the disk in bytes, which must not be smaller
+    /// than its current size
+    pub size: ByteCount,
+}
+
+/// Parameters for restoring a disk from a snapshot
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct DiskRollback {
+    /// id of the snapshot to restore the disk from, which must belong to the
+    /// disk's project
+    pub snapshot_id: Uuid,
+}
+
 /// Select an address lot by an optional name or id.
 #[derive(Clone, Debug, Deserialize, Serialize, JsonSchema, PartialEq)]
 pub struct AddressLotSelector {
@@ -1425,12 +1744,12 @@ pub struct CreateBgpAnnounceSet {
     pub identity: IdentityMetadataCreateParams,
 
     /// The announcements in this set.
-    pub announcement: Vec<BgpAnnouncement>,
+    pub announcement: Vec<BgpAnnouncementCreate>,
 }
 
 /// A BGP announcement tied to a particular address lot block.
 #[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
-pub struct BgpAnnouncement {
+pub struct BgpAnnouncementCreate {
     /// Address lot this announcement is drawn from.
     pub address_lot_block: NameOrId,
 
@@ -1453,6 +1772,20 @@ pub struct CreateBgpConfig {
     pub vrf: Option<Name>,
 }
 
+/// Select a BGP configuration by name or id.
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema, PartialEq)]
+pub struct BgpConfigSelector {
+    /// A name or id to use when selecting BGP configurations.
+    pub name_or_id: NameOrId,
+}
+
+/// Select a BGP announce set by name or id.
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema, PartialEq)]
+pub struct BgpAnnounceSetSelector {
+    /// A name or id to use when selecting BGP announce sets.
+    pub name_or_id: NameOrId,
+}
+
 /// A set of addresses associated with a port configuration.
 #[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
 pub struct AddressConfig {
@@ -1560,6 +1893,10 @@ pub struct ImageCreate {
 
     /// The source of the image's contents.
     pub source: ImageSource,
+
+    /// user-defined labels for the image
+    #[serde(default)]
+    pub labels: Labels,
 }
 
 // SNAPSHOTS
@@ -1573,6 +1910,40 @@ pub struct SnapshotCreate {
 
     /// The disk to be snapshotted
     pub disk: NameOrId,
+
+    /// user-defined labels for the snapshot
+    #[serde(default)]
+    pub labels: Labels,
+}
+
+// SNAPSHOT SCHEDULES
+
+/// The disks snapshotted by a snapshot schedule
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SnapshotScheduleTargetCreate {
+    /// A single disk, which must be in the same project as the schedule
+    Disk { disk: NameOrId },
+    /// Every disk in the schedule's project that has all of these labels at
+    /// the time the schedule runs
+    Labels { labels: Labels },
+}
+
+/// Create-time parameters for a `SnapshotSchedule`
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct SnapshotScheduleCreate {
+    #[serde(flatten)]
+    pub identity: IdentityMetadataCreateParams,
+
+    /// The disks to be snapshotted
+    pub target: SnapshotScheduleTargetCreate,
+
+    /// How often to snapshot each disk, in seconds
+    pub interval_secs: u32,
+
+    /// How many of the snapshots of each disk taken by this schedule to keep.
+    /// Older snapshots taken by the schedule are deleted.
+    pub retain: u32,
 }
 
 // USERS AND GROUPS
@@ -1616,6 +1987,51 @@ pub struct SshKeyCreate {
     pub public_key: String,
 }
 
+// ACCESS TOKENS
+//
+// Like SSH keys, access tokens are managed under `/v1/me`, so the silo user
+// that owns a new token is implicit.
+
+/// Create-time parameters for an access token
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct DeviceAccessTokenCreate {
+    /// A name to identify the token, unique among the current user's tokens
+    pub name: Name,
+    /// Time after which the token can no longer be used
+    ///
+    /// If this is not specified, the token remains valid until it's deleted.
+    pub time_expires: Option<DateTime<Utc>>,
+}
+
+// SCIM CLIENT TOKENS
+
+/// Create-time parameters for a SCIM client bearer token
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct ScimClientBearerTokenCreate {
+    /// Time after which the token can no longer be used
+    ///
+    /// If this is not specified, the token remains valid until it's deleted.
+    pub time_expires: Option<DateTime<Utc>>,
+}
+
+// WEBHOOKS
+
+/// Create-time parameters for a `WebhookReceiver`
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct WebhookReceiverCreate {
+    #[serde(flatten)]
+    pub identity: IdentityMetadataCreateParams,
+    /// URL to which events are delivered, using HTTP or HTTPS
+    pub endpoint: String,
+    /// Secret with which deliveries to the receiver are signed
+    ///
+    /// Each delivery carries the hex-encoded HMAC-SHA256 of its body, keyed
+    /// with this secret, in the "x-oxide-webhook-signature" header.
+    pub secret: String,
+    /// Classes of event the receiver is sent
+    pub event_classes: Vec<shared::WebhookEventClass>,
+}
+
 // METRICS
 
 /// Query parameters common to resource metrics endpoints.
@@ -1629,6 +2045,21 @@ pub struct ResourceMetrics {
     pub order: Option<PaginationOrder>,
 }
 
+// AUDIT LOG
+
+/// Query parameters for listing the audit log
+#[derive(
+    Clone, Debug, Default, Deserialize, Serialize, JsonSchema, PartialEq,
+)]
+pub struct AuditLogSelector {
+    /// Only list entries for requests that completed at or after this time
+    pub start_time: Option<DateTime<Utc>>,
+    /// Only list entries for requests that completed before this time
+    pub end_time: Option<DateTime<Utc>>,
+    /// Only list entries for requests made by this user
+    pub actor_id: Option<Uuid>,
+}
+
 // SYSTEM UPDATE
 
 #[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
@@ -164,6 +164,17 @@ pub enum SiloIdentityMode {
     /// groups).
     SamlJit,
 
+    /// Users are authenticated with OpenID Connect using an external identity
+    /// provider.  As with `saml_jit`, the system updates information about
+    /// users and groups only during successful authentication.
+    OidcJit,
+
+    /// Users are authenticated with SAML using an external authentication
+    /// provider, but users and groups are provisioned ahead of time by the
+    /// identity provider through the Silo's SCIM 2.0 endpoint.  Users who have
+    /// not been provisioned this way cannot log in.
+    SamlScim,
+
     /// The system is the source of truth about users.  There is no linkage to
     /// an external authentication provider or identity provider.
     // NOTE: authentication for these users is not supported yet at all.  It
@@ -176,6 +187,8 @@ impl SiloIdentityMode {
         match self {
             SiloIdentityMode::LocalOnly => AuthenticationMode::Local,
             SiloIdentityMode::SamlJit => AuthenticationMode::Saml,
+            SiloIdentityMode::OidcJit => AuthenticationMode::Oidc,
+            SiloIdentityMode::SamlScim => AuthenticationMode::Saml,
         }
     }
 
@@ -183,6 +196,8 @@ impl SiloIdentityMode {
         match self {
             SiloIdentityMode::LocalOnly => UserProvisionType::ApiOnly,
             SiloIdentityMode::SamlJit => UserProvisionType::Jit,
+            SiloIdentityMode::OidcJit => UserProvisionType::Jit,
+            SiloIdentityMode::SamlScim => UserProvisionType::Scim,
         }
     }
 }
@@ -194,6 +209,10 @@ pub enum AuthenticationMode {
     /// Authentication is via SAML using an external authentication provider
     Saml,
 
+    /// Authentication is via OpenID Connect using an external identity
+    /// provider
+    Oidc,
+
     /// Authentication is local to the Oxide system
     Local,
 }
@@ -210,6 +229,11 @@ pub enum UserProvisionType {
     /// Users and groups are created or updated during authentication using
     /// information provided by the authentication provider
     Jit,
+
+    /// Users and groups are created, updated and deactivated by an external
+    /// identity provider using the SCIM 2.0 protocol.  Authentication does not
+    /// create users or change their group memberships.
+    Scim,
 }
 
 /// The service intended to use this certificate.
@@ -228,6 +252,64 @@ pub enum IpKind {
     Floating,
 }
 
+/// Whether the members of an affinity group should be placed together or
+/// apart
+#[derive(
+    Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize, JsonSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum AffinityGroupKind {
+    /// Members should be placed on the same sled.
+    Affinity,
+    /// Members should be placed on different sleds.
+    AntiAffinity,
+}
+
+/// How strictly the placement of an affinity group's members is enforced
+#[derive(
+    Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize, JsonSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum AffinityPolicy {
+    /// If the group's placement cannot be satisfied, the operation placing
+    /// the instance fails.
+    Hard,
+    /// The group's placement is preferred, but an instance may be placed
+    /// elsewhere if it cannot be satisfied.
+    Soft,
+}
+
+/// Whether a VPC peering has been accepted by both VPCs
+#[derive(
+    Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize, JsonSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum VpcPeeringState {
+    /// The peer VPC has not yet created a peering with this VPC.
+    Pending,
+    /// Both VPCs have created a peering with each other, and traffic may flow
+    /// between them subject to their firewall rules.
+    Active,
+}
+
+/// A class of event that can be sent to webhook receivers
+#[derive(
+    Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize, JsonSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEventClass {
+    /// An instance changed state, e.g., from "starting" to "running".
+    InstanceStateChange,
+    /// A disk was attached to an instance.
+    DiskAttach,
+    /// A disk was detached from an instance.
+    DiskDetach,
+    /// A snapshot finished being created.
+    SnapshotComplete,
+    /// A system update deployment started or finished.
+    UpdateDeploymentProgress,
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum UpdateableComponentType {
@@ -5,15 +5,16 @@
 //! Views are response bodies, most of which are public lenses onto DB models.
 
 use crate::external_api::shared::{
-    self, IpKind, IpRange, ServiceUsingCertificate,
+    self, AffinityGroupKind, AffinityPolicy, IpKind, IpRange,
+    ServiceUsingCertificate, VpcPeeringState, WebhookEventClass,
 };
 use crate::identity::AssetIdentityMetadata;
 use api_identity::ObjectIdentity;
 use chrono::DateTime;
 use chrono::Utc;
 use omicron_common::api::external::{
-    ByteCount, Digest, IdentityMetadata, InstanceState, Ipv4Net, Ipv6Net, Name,
-    ObjectIdentity, RoleName, SemverVersion,
+    ByteCount, Digest, IdentityMetadata, InstanceState, Ipv4Net, Ipv6Net,
+    Labels, Name, ObjectIdentity, RoleName, SemverVersion,
 };
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
@@ -56,6 +57,8 @@ pub struct Silo {
 pub enum IdentityProviderType {
     /// SAML identity provider
     Saml,
+    /// OpenID Connect identity provider
+    Oidc,
 }
 
 /// View of an Identity Provider
@@ -96,6 +99,34 @@ pub struct SamlIdentityProvider {
     pub group_attribute_name: Option<String>,
 }
 
+#[derive(ObjectIdentity, Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct OidcIdentityProvider {
+    #[serde(flatten)]
+    pub identity: IdentityMetadata,
+
+    /// Issuer identifier of the IdP, which ID tokens must match
+    pub issuer: String,
+
+    /// IdP endpoint to which users are sent to log in
+    pub authorization_endpoint: String,
+
+    /// IdP endpoint from which ID tokens are requested
+    pub token_endpoint: String,
+
+    /// Client id assigned to the system by the IdP
+    pub client_id: String,
+
+    /// Endpoint to which the IdP sends users after they log in
+    pub redirect_uri: String,
+
+    /// Scopes requested in addition to `openid`
+    pub scopes: Vec<String>,
+
+    /// If set, ID token claims with this name will be considered to denote a
+    /// user's group membership, where the values will be the group names.
+    pub group_claim_name: Option<String>,
+}
+
 // PROJECTS
 
 /// View of a Project
@@ -105,9 +136,26 @@ pub struct Project {
     // intent in RFD 4?
     #[serde(flatten)]
     pub identity: IdentityMetadata,
+    /// user-defined labels attached to this project
+    pub labels: Labels,
     // Important: Silo ID does not get presented to user
 }
 
+// QUOTAS
+
+/// Limits on the virtual resources provisioned within a silo or project
+///
+/// A limit of `null` means that the corresponding resource is not limited.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, JsonSchema)]
+pub struct Quota {
+    /// Maximum number of virtual CPUs which may be provisioned by instances
+    pub cpus: Option<u32>,
+    /// Maximum amount of memory which may be provisioned by instances
+    pub memory: Option<ByteCount>,
+    /// Maximum amount of storage which may be provisioned by disks
+    pub storage: Option<ByteCount>,
+}
+
 // CERTIFICATES
 
 /// View of a Certificate
@@ -124,12 +172,15 @@ pub struct Certificate {
 ///
 /// If `project_id` is present then the image is only visible inside that
 /// project. If it's not present then the image is visible to all projects in
-/// the silo.
+/// the silo, and in any other silos it has been shared with.
 #[derive(ObjectIdentity, Clone, Debug, Deserialize, Serialize, JsonSchema)]
 pub struct Image {
     #[serde(flatten)]
     pub identity: IdentityMetadata,
 
+    /// ID of the silo that owns the image
+    pub silo_id: Uuid,
+
     /// ID of the parent project if the image is a project image
     pub project_id: Option<Uuid>,
 
@@ -150,6 +201,9 @@ pub struct Image {
 
     /// total size in bytes
     pub size: ByteCount,
+
+    /// user-defined labels attached to this image
+    pub labels: Labels,
 }
 
 // SNAPSHOTS
@@ -175,6 +229,77 @@ pub struct Snapshot {
     pub state: SnapshotState,
 
     pub size: ByteCount,
+
+    /// user-defined labels attached to this snapshot
+    pub labels: Labels,
+}
+
+// SNAPSHOT SCHEDULES
+
+/// The disks snapshotted by a snapshot schedule
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SnapshotScheduleTarget {
+    /// A single disk
+    Disk { disk_id: Uuid },
+    /// Every disk in the schedule's project that has all of these labels
+    Labels { labels: Labels },
+}
+
+/// View of a Snapshot Schedule
+///
+/// A snapshot schedule periodically snapshots a set of disks, and deletes the
+/// oldest of the snapshots it took of each disk beyond a given number.
+#[derive(ObjectIdentity, Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct SnapshotSchedule {
+    #[serde(flatten)]
+    pub identity: IdentityMetadata,
+    /// The project this resource exists within.
+    pub project_id: Uuid,
+    /// The disks snapshotted by this schedule
+    pub target: SnapshotScheduleTarget,
+    /// How often each disk is snapshotted, in seconds
+    pub interval_secs: u32,
+    /// How many of the snapshots of each disk taken by this schedule are kept
+    pub retain: u32,
+    /// The most recent run of this schedule, if it has run
+    pub last_run: Option<SnapshotScheduleRun>,
+}
+
+/// The outcome of a run of a snapshot schedule
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SnapshotScheduleRunStatus {
+    /// The run has not finished.
+    Running,
+    /// Every disk was snapshotted and old snapshots were deleted.
+    Succeeded,
+    /// At least one disk could not be snapshotted, or at least one old
+    /// snapshot could not be deleted.
+    Failed,
+}
+
+/// A run of a snapshot schedule
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct SnapshotScheduleRun {
+    /// When the run started
+    pub time_started: DateTime<Utc>,
+    pub status: SnapshotScheduleRunStatus,
+    /// How many snapshots the run took
+    pub snapshots_created: u32,
+    /// How many old snapshots the run deleted
+    pub snapshots_deleted: u32,
+    /// What went wrong, if the run failed
+    pub error: Option<String>,
+}
+
+// EXPORTS
+
+/// Blocks read with a bulk read from a disk or snapshot being exported
+// equivalent to crucible_pantry_client::types::BulkReadResponse
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct ExportedBlocks {
+    pub base64_encoded_data: String,
 }
 
 // VPCs
@@ -197,6 +322,9 @@ pub struct Vpc {
     // TODO-design should this be optional?
     /// The name used for the VPC in DNS.
     pub dns_name: Name,
+
+    /// user-defined labels attached to this VPC
+    pub labels: Labels,
 }
 
 /// A VPC subnet represents a logical grouping for instances that allows network traffic between
@@ -238,12 +366,37 @@ pub struct VpcRouter {
     pub vpc_id: Uuid,
 }
 
+/// One VPC's side of a peering with another VPC in the same silo.
+///
+/// A peering is only established once both VPCs have created a peering with
+/// each other.
+#[derive(ObjectIdentity, Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct VpcPeering {
+    /// common identifying metadata
+    #[serde(flatten)]
+    pub identity: IdentityMetadata,
+
+    /// The VPC to which this side of the peering belongs.
+    pub vpc_id: Uuid,
+
+    /// The VPC being peered with.
+    pub peer_vpc_id: Uuid,
+
+    pub state: VpcPeeringState,
+}
+
 // IP POOLS
 
 #[derive(ObjectIdentity, Clone, Debug, Deserialize, Serialize, JsonSchema)]
 pub struct IpPool {
     #[serde(flatten)]
     pub identity: IdentityMetadata,
+    /// The Silo whose instances may draw addresses from this pool.  If not
+    /// set, the pool is available to every Silo.
+    pub silo_id: Option<Uuid>,
+    /// Whether this is the default pool for its Silo, or for the whole fleet
+    /// if it isn't linked to a Silo
+    pub is_default: bool,
 }
 
 #[derive(Clone, Copy, Debug, Deserialize, Serialize, JsonSchema)]
@@ -263,6 +416,42 @@ pub struct ExternalIp {
     pub kind: IpKind,
 }
 
+// FLOATING IPS
+
+/// A Floating IP is a well-known IP address which can be attached and
+/// detached from instances.
+#[derive(ObjectIdentity, Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct FloatingIp {
+    #[serde(flatten)]
+    pub identity: IdentityMetadata,
+    /// The IP address held by this resource.
+    pub ip: IpAddr,
+    /// The project this resource exists within.
+    pub project_id: Uuid,
+    /// The ID of the instance that this Floating IP is attached to, if it is
+    /// presently in use.
+    pub instance_id: Option<Uuid>,
+}
+
+// AFFINITY GROUPS
+
+/// View of an Affinity Group
+///
+/// An affinity group constrains the sleds on which its member instances are
+/// placed, relative to one another.
+#[derive(ObjectIdentity, Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct AffinityGroup {
+    #[serde(flatten)]
+    pub identity: IdentityMetadata,
+    /// The project this resource exists within.
+    pub project_id: Uuid,
+    /// Whether members are placed on the same sled or on different sleds.
+    pub kind: AffinityGroupKind,
+    /// Whether placement according to this group is required or preferred.
+    pub policy: AffinityPolicy,
+}
+
 // RACKS
 
 /// View of an Rack
@@ -462,6 +651,153 @@ pub enum DeviceAccessTokenType {
     Bearer,
 }
 
+/// View of an access token belonging to the current user
+///
+/// This never includes the token itself, which is only revealed when the token
+/// is created.
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct DeviceAccessToken {
+    /// A unique, immutable, system-controlled identifier for the token
+    pub id: Uuid,
+    /// The name given to the token when it was created
+    ///
+    /// Tokens granted by a device authorization flow have no name.
+    pub name: Option<Name>,
+    /// Time at which the token was created
+    pub time_created: DateTime<Utc>,
+    /// Time after which the token can no longer be used, if any
+    pub time_expires: Option<DateTime<Utc>>,
+    /// Time at which the token was last used to authenticate a request.  This
+    /// is only updated about once a minute, so it may be up to a minute old.
+    pub time_last_used: Option<DateTime<Utc>>,
+}
+
+/// A newly-created access token, including the secret token itself
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct DeviceAccessTokenCreated {
+    /// The bearer token to include in the "Authorization" header of requests
+    ///
+    /// This cannot be retrieved again after the token has been created.
+    pub access_token: String,
+    pub token: DeviceAccessToken,
+}
+
+// SCIM CLIENT TOKENS
+
+/// View of a bearer token used by a SCIM client to provision a Silo's users and
+/// groups
+///
+/// This never includes the token itself, which is only revealed when the token
+/// is created.
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct ScimClientBearerToken {
+    /// A unique, immutable, system-controlled identifier for the token
+    pub id: Uuid,
+    /// Time at which the token was created
+    pub time_created: DateTime<Utc>,
+    /// Time after which the token can no longer be used, if any
+    pub time_expires: Option<DateTime<Utc>>,
+}
+
+/// A newly-created SCIM client token, including the secret token itself
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct ScimClientBearerTokenCreated {
+    /// The bearer token the SCIM client includes in the "Authorization" header
+    /// of its requests
+    ///
+    /// This cannot be retrieved again after the token has been created.
+    pub bearer_token: String,
+    pub token: ScimClientBearerToken,
+}
+
+// AUDIT LOG
+
+/// An entry in the audit log
+///
+/// Each entry describes one authenticated request to the external API that may
+/// have modified the system.
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema, PartialEq)]
+pub struct AuditLogEntry {
+    /// Unique identifier for this entry
+    pub id: Uuid,
+    /// Time at which the request was received
+    pub time_started: DateTime<Utc>,
+    /// Time at which the request completed
+    pub time_completed: DateTime<Utc>,
+    /// Identifier assigned to the request by the API server
+    pub request_id: String,
+    /// The user that made the request (for requests made by a SCIM client, the
+    /// SCIM client token that it used)
+    pub actor_id: Uuid,
+    /// The Silo of the user that made the request, if any (built-in users do
+    /// not belong to a Silo)
+    pub actor_silo_id: Option<Uuid>,
+    /// The API operation that was invoked, e.g., `"instance_create"`
+    pub operation_id: String,
+    /// The HTTP method of the request
+    pub http_method: String,
+    /// The path (and query string, if any) of the request
+    pub resource_path: String,
+    /// The HTTP status code of the response
+    pub result_status: u16,
+}
+
+// WEBHOOKS
+
+/// View of a webhook receiver
+///
+/// A receiver is sent the events in its Silo of each of the classes it
+/// subscribes to.  This never includes the secret with which deliveries to the
+/// receiver are signed.
+#[derive(ObjectIdentity, Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct WebhookReceiver {
+    #[serde(flatten)]
+    pub identity: IdentityMetadata,
+    /// URL to which events are delivered
+    pub endpoint: String,
+    /// Classes of event the receiver is sent
+    pub event_classes: Vec<WebhookEventClass>,
+}
+
+/// State of the delivery of an event to a webhook receiver
+#[derive(
+    Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize, JsonSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookDeliveryState {
+    /// The event has not been delivered yet, and another attempt will be made.
+    Pending,
+    /// The receiver accepted the event.
+    Delivered,
+    /// Every attempt to deliver the event failed, and no more will be made.
+    Failed,
+}
+
+/// The delivery of an event to a webhook receiver
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct WebhookDelivery {
+    /// Unique identifier for this delivery, which is sent with each attempt
+    pub id: Uuid,
+    /// Unique identifier for the event being delivered
+    pub event_id: Uuid,
+    /// Class of the event being delivered
+    pub event_class: WebhookEventClass,
+    /// Time at which the event happened
+    pub time_created: DateTime<Utc>,
+    pub state: WebhookDeliveryState,
+    /// Number of attempts made to deliver the event
+    pub attempts: u32,
+    /// Time at which the next attempt will be made, if the delivery is pending
+    pub time_next_attempt: Option<DateTime<Utc>>,
+    /// Time at which the most recent attempt was made, if any
+    pub time_last_attempt: Option<DateTime<Utc>>,
+    /// HTTP status code the receiver responded to the most recent attempt
+    /// with, if it responded
+    pub last_response_status: Option<u16>,
+    /// What went wrong with the most recent attempt, if it failed
+    pub last_error: Option<String>,
+}
+
 // SYSTEM UPDATES
 
 #[derive(Clone, Debug, Deserialize, Serialize, JsonSchema, PartialEq, Eq)]
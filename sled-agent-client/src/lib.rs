@@ -163,6 +163,7 @@ impl From<omicron_common::api::external::DiskState> for types::DiskState {
             ImportingFromUrl => Self::ImportingFromUrl,
             ImportingFromBulkWrites => Self::ImportingFromBulkWrites,
             Finalizing => Self::Finalizing,
+            Exporting => Self::Exporting,
             Maintenance => Self::Maintenance,
             Attaching(u) => Self::Attaching(u),
             Attached(u) => Self::Attached(u),
@@ -195,6 +196,7 @@ impl From<types::DiskState> for omicron_common::api::external::DiskState {
             ImportingFromUrl => Self::ImportingFromUrl,
             ImportingFromBulkWrites => Self::ImportingFromBulkWrites,
             Finalizing => Self::Finalizing,
+            Exporting => Self::Exporting,
             Maintenance => Self::Maintenance,
             Attaching(u) => Self::Attaching(u),
             Attached(u) => Self::Attached(u),
@@ -112,6 +112,7 @@ impl DiskStates {
             }
             // Cannot detach.
             DiskState::Finalizing
+            | DiskState::Exporting
             | DiskState::Maintenance
             | DiskState::ImportReady
             | DiskState::ImportingFromUrl
@@ -150,6 +151,7 @@ impl DiskStates {
             }
             // Cannot attach.
             DiskState::Finalizing
+            | DiskState::Exporting
             | DiskState::Maintenance
             | DiskState::ImportReady
             | DiskState::ImportingFromUrl
@@ -7,8 +7,8 @@
 use crate::params::{
     DiskEnsureBody, InstanceEnsureBody, InstancePutMigrationIdsBody,
     InstancePutStateBody, InstancePutStateResponse, InstanceUnregisterResponse,
-    ServiceEnsureBody, SledRole, TimeSync, VpcFirewallRulesEnsureBody,
-    ZoneBundleId, ZoneBundleMetadata, Zpool,
+    ServiceEnsureBody, SledRole, TimeSync, VpcDnsEnsureBody,
+    VpcFirewallRulesEnsureBody, ZoneBundleId, ZoneBundleMetadata, Zpool,
 };
 use dropshot::{
     endpoint, ApiDescription, FreeformBody, HttpError, HttpResponseCreated,
@@ -49,6 +49,7 @@ pub fn api() -> SledApiDescription {
         api.register(timesync_get)?;
         api.register(update_artifact)?;
         api.register(vpc_firewall_rules_put)?;
+        api.register(vpc_dns_put)?;
         api.register(zpools_get)?;
 
         Ok(())
@@ -403,6 +404,26 @@ async fn vpc_firewall_rules_put(
     Ok(HttpResponseUpdatedNoContent())
 }
 
+#[endpoint {
+    method = PUT,
+    path = "/vpc/{vpc_id}/dns",
+}]
+async fn vpc_dns_put(
+    rqctx: RequestContext<SledAgent>,
+    path_params: Path<VpcPathParam>,
+    body: TypedBody<VpcDnsEnsureBody>,
+) -> Result<HttpResponseUpdatedNoContent, HttpError> {
+    let sa = rqctx.context();
+    let _vpc_id = path_params.into_inner().vpc_id;
+    let body_args = body.into_inner();
+
+    sa.vpc_dns_ensure(body_args.vni, &body_args.zone)
+        .await
+        .map_err(Error::from)?;
+
+    Ok(HttpResponseUpdatedNoContent())
+}
+
 /// Path parameters for V2P mapping related requests (sled agent API)
 #[allow(dead_code)]
 #[derive(Deserialize, JsonSchema)]
@@ -12,7 +12,8 @@ use crate::instance_manager::InstanceTicket;
 use crate::nexus::NexusClientWithResolver;
 use crate::params::{
     InstanceHardware, InstanceMigrationSourceParams,
-    InstanceMigrationTargetParams, InstanceStateRequested, VpcFirewallRule,
+    InstanceMigrationTargetParams, InstanceStateRequested, VpcDnsZone,
+    VpcFirewallRule,
 };
 use crate::profile::*;
 use crate::storage_manager::StorageResources;
@@ -224,6 +225,7 @@ struct InstanceInner {
     source_nat: SourceNatConfig,
     external_ips: Vec<IpAddr>,
     firewall_rules: Vec<VpcFirewallRule>,
+    vpc_dns: Option<VpcDnsZone>,
 
     // Disk related properties
     // TODO: replace `propolis_client::handmade::*` with properly-modeled local types
@@ -613,6 +615,7 @@ impl Instance {
             source_nat: initial.source_nat,
             external_ips: initial.external_ips,
             firewall_rules: initial.firewall_rules,
+            vpc_dns: initial.vpc_dns,
             requested_disks: initial.disks,
             cloud_init_bytes: initial.cloud_init_bytes,
             state: InstanceStates::new(initial.runtime),
@@ -823,6 +826,11 @@ impl Instance {
             )?;
             opte_ports.push(port);
         }
+        if let (Some(zone), Some(nic)) =
+            (&inner.vpc_dns, inner.requested_nics.first())
+        {
+            inner.port_manager.vpc_dns_ensure(nic.vni, zone)?;
+        }
 
         // Create a zone for the propolis instance, using the previously
         // configured VNICs.
@@ -18,6 +18,8 @@ use std::net::{IpAddr, Ipv6Addr, SocketAddr, SocketAddrV6};
 use thiserror::Error;
 use uuid::Uuid;
 
+pub use illumos_utils::opte::params::VpcDnsEnsureBody;
+pub use illumos_utils::opte::params::VpcDnsZone;
 pub use illumos_utils::opte::params::VpcFirewallRule;
 pub use illumos_utils::opte::params::VpcFirewallRulesEnsureBody;
 pub use sled_hardware::DendriteAsic;
@@ -65,6 +67,8 @@ pub struct InstanceHardware {
     /// provided to an instance to allow inbound connectivity.
     pub external_ips: Vec<IpAddr>,
     pub firewall_rules: Vec<VpcFirewallRule>,
+    /// The DNS zone of the instance's VPC, if it has network interfaces
+    pub vpc_dns: Option<VpcDnsZone>,
     // TODO: replace `propolis_client::handmade::*` with locally-modeled request type
     pub disks: Vec<propolis_client::handmade::api::DiskRequest>,
     pub cloud_init_bytes: Option<String>,
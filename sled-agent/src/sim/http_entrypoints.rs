@@ -7,7 +7,7 @@
 use crate::params::{
     DiskEnsureBody, InstanceEnsureBody, InstancePutMigrationIdsBody,
     InstancePutStateBody, InstancePutStateResponse, InstanceUnregisterResponse,
-    VpcFirewallRulesEnsureBody,
+    VpcDnsEnsureBody, VpcFirewallRulesEnsureBody,
 };
 use dropshot::endpoint;
 use dropshot::ApiDescription;
@@ -43,6 +43,7 @@ pub fn api() -> SledApiDescription {
         api.register(update_artifact)?;
         api.register(instance_issue_disk_snapshot_request)?;
         api.register(vpc_firewall_rules_put)?;
+        api.register(vpc_dns_put)?;
         api.register(set_v2p)?;
         api.register(del_v2p)?;
 
@@ -272,6 +273,26 @@ async fn vpc_firewall_rules_put(
     Ok(HttpResponseUpdatedNoContent())
 }
 
+#[endpoint {
+    method = PUT,
+    path = "/vpc/{vpc_id}/dns",
+}]
+async fn vpc_dns_put(
+    rqctx: RequestContext<Arc<SledAgent>>,
+    path_params: Path<VpcPathParam>,
+    body: TypedBody<VpcDnsEnsureBody>,
+) -> Result<HttpResponseUpdatedNoContent, HttpError> {
+    let sa = rqctx.context();
+    let _vpc_id = path_params.into_inner().vpc_id;
+    let body_args = body.into_inner();
+
+    sa.vpc_dns_ensure(body_args.vni, &body_args.zone)
+        .await
+        .map_err(|e| HttpError::for_internal_error(e.to_string()))?;
+
+    Ok(HttpResponseUpdatedNoContent())
+}
+
 /// Path parameters for V2P mapping related requests (sled agent API)
 #[derive(Deserialize, JsonSchema)]
 struct V2pPathParam {
@@ -20,7 +20,7 @@ use crate::params::{
 use crate::sim::simulatable::Simulatable;
 use crate::updates::UpdateManager;
 use futures::lock::Mutex;
-use omicron_common::api::external::{DiskState, Error, ResourceType};
+use omicron_common::api::external::{DiskState, Error, ResourceType, Vni};
 use omicron_common::api::internal::nexus::DiskRuntimeState;
 use omicron_common::api::internal::nexus::InstanceRuntimeState;
 use slog::Logger;
@@ -31,9 +31,11 @@ use uuid::Uuid;
 use std::collections::HashMap;
 use std::str::FromStr;
 
+use crucible_agent_client::types::RunningSnapshot;
 use crucible_client_types::VolumeConstructionRequest;
 use dropshot::HttpServer;
 use illumos_utils::opte::params::SetVirtualNetworkInterfaceHost;
+use illumos_utils::opte::params::VpcDnsZone;
 use nexus_client::types::PhysicalDiskKind;
 use omicron_common::address::PROPOLIS_PORT;
 use propolis_client::Client as PropolisClient;
@@ -59,6 +61,8 @@ pub struct SledAgent {
     pub nexus_client: Arc<NexusClient>,
     disk_id_to_region_ids: Mutex<HashMap<String, Vec<Uuid>>>,
     pub v2p_mappings: Mutex<HashMap<Uuid, Vec<SetVirtualNetworkInterfaceHost>>>,
+    /// DNS zones served to the instances in each VPC, by the VPC's VNI
+    pub vpc_dns: Mutex<HashMap<Vni, VpcDnsZone>>,
     mock_propolis:
         Mutex<Option<(HttpServer<Arc<PropolisContext>>, PropolisClient)>>,
 }
@@ -149,6 +153,7 @@ impl SledAgent {
             nexus_client,
             disk_id_to_region_ids: Mutex::new(HashMap::new()),
             v2p_mappings: Mutex::new(HashMap::new()),
+            vpc_dns: Mutex::new(HashMap::new()),
             mock_propolis: Mutex::new(None),
         })
     }
@@ -314,6 +319,12 @@ impl SledAgent {
             self.map_disk_ids_to_region_ids(&vcr).await?;
         }
 
+        if let (Some(zone), Some(nic)) =
+            (initial_hardware.vpc_dns, initial_hardware.nics.first())
+        {
+            self.vpc_dns.lock().await.insert(nic.vni, zone);
+        }
+
         Ok(instance_run_time_state)
     }
 
@@ -525,6 +536,14 @@ impl SledAgent {
         self.storage.lock().await.get_dataset(zpool_id, dataset_id).await
     }
 
+    /// Returns the running snapshot whose downstairs listens on `port`
+    pub async fn get_running_snapshot_for_port(
+        &self,
+        port: u16,
+    ) -> Option<RunningSnapshot> {
+        self.storage.lock().await.get_running_snapshot_for_port(port).await
+    }
+
     /// Issue a snapshot request for a Crucible disk attached to an instance.
     ///
     /// The real sled agent simply sends this snapshot request to the
@@ -595,6 +614,15 @@ impl SledAgent {
         Ok(())
     }
 
+    pub async fn vpc_dns_ensure(
+        &self,
+        vni: Vni,
+        zone: &VpcDnsZone,
+    ) -> Result<(), Error> {
+        self.vpc_dns.lock().await.insert(vni, zone.clone());
+        Ok(())
+    }
+
     /// Used for integration tests that require a component to talk to a
     /// mocked propolis-server API.
     // TODO: fix schemas so propolis-server's port isn't hardcoded in nexus
@@ -16,6 +16,7 @@ use chrono::prelude::*;
 use crucible_agent_client::types::{
     CreateRegion, Region, RegionId, RunningSnapshot, Snapshot, State,
 };
+use crucible_client_types::CrucibleOpts;
 use crucible_client_types::VolumeConstructionRequest;
 use dropshot::HandlerTaskMode;
 use dropshot::HttpError;
@@ -216,6 +217,14 @@ impl CrucibleDataInner {
         Ok(running_snapshot)
     }
 
+    fn running_snapshot_for_port(&self, port: u16) -> Option<RunningSnapshot> {
+        self.running_snapshots
+            .values()
+            .flat_map(|map| map.values())
+            .find(|running_snapshot| running_snapshot.port_number == port)
+            .cloned()
+    }
+
     fn delete_running_snapshot(
         &mut self,
         id: &RegionId,
@@ -331,6 +340,13 @@ impl CrucibleData {
         self.inner.lock().await.create_running_snapshot(id, name)
     }
 
+    pub async fn running_snapshot_for_port(
+        &self,
+        port: u16,
+    ) -> Option<RunningSnapshot> {
+        self.inner.lock().await.running_snapshot_for_port(port)
+    }
+
     pub async fn delete_running_snapshot(
         &self,
         id: &RegionId,
@@ -443,6 +459,21 @@ impl Zpool {
 
         None
     }
+
+    pub async fn get_running_snapshot_for_port(
+        &self,
+        port: u16,
+    ) -> Option<RunningSnapshot> {
+        for dataset in self.datasets.values() {
+            if let Some(running_snapshot) =
+                dataset.data().running_snapshot_for_port(port).await
+            {
+                return Some(running_snapshot);
+            }
+        }
+
+        None
+    }
 }
 
 #[derive(Clone, PartialEq, Eq, Hash)]
@@ -613,6 +644,21 @@ impl Storage {
 
         None
     }
+
+    pub async fn get_running_snapshot_for_port(
+        &self,
+        port: u16,
+    ) -> Option<RunningSnapshot> {
+        for zpool in self.zpools.values() {
+            if let Some(running_snapshot) =
+                zpool.get_running_snapshot_for_port(port).await
+            {
+                return Some(running_snapshot);
+            }
+        }
+
+        None
+    }
 }
 
 /// Simulated crucible pantry
@@ -621,6 +667,10 @@ pub struct Pantry {
     vcrs: Mutex<HashMap<String, VolumeConstructionRequest>>, // Please rewind!
     sled_agent: Arc<SledAgent>,
     jobs: Mutex<HashSet<String>>,
+    /// Bytes written with bulk writes, which bulk reads return, keyed by the
+    /// Upstairs ID of the disk's regions.  Snapshots taken through the Pantry
+    /// get a copy of the disk's bytes, keyed by the snapshot's ID.
+    data: Mutex<HashMap<Uuid, Vec<u8>>>,
 }
 
 impl Pantry {
@@ -630,6 +680,7 @@ impl Pantry {
             vcrs: Mutex::new(HashMap::default()),
             sled_agent,
             jobs: Mutex::new(HashSet::default()),
+            data: Mutex::new(HashMap::default()),
         }
     }
 
@@ -717,7 +768,18 @@ impl Pantry {
                 snapshot_id.parse().unwrap(),
             )
             .await
-            .map_err(|e| HttpError::for_internal_error(e.to_string()))
+            .map_err(|e| HttpError::for_internal_error(e.to_string()))?;
+
+        // Keep what was bulk written to the disk so far, so it can be read back
+        // out of the snapshot.
+        let (opts, ..) = region_geometry(volume_construction_request)
+            .unwrap_or_else(|| panic!("unexpected Volume layout"));
+        let mut data = self.data.lock().await;
+        if let Some(disk_data) = data.get(&opts.id).cloned() {
+            data.insert(snapshot_id.parse().unwrap(), disk_data);
+        }
+
+        Ok(())
     }
 
     pub async fn bulk_write(
@@ -730,29 +792,8 @@ impl Pantry {
 
         // Currently, Nexus will only make volumes where the first subvolume is
         // a Region. This will change in the future!
-        let (region_block_size, region_size) = match vcr {
-            VolumeConstructionRequest::Volume { sub_volumes, .. } => {
-                match sub_volumes[0] {
-                    VolumeConstructionRequest::Region {
-                        block_size,
-                        blocks_per_extent,
-                        extent_count,
-                        ..
-                    } => (
-                        block_size,
-                        block_size * blocks_per_extent * (extent_count as u64),
-                    ),
-
-                    _ => {
-                        panic!("unexpected Volume layout");
-                    }
-                }
-            }
-
-            _ => {
-                panic!("unexpected Volume layout");
-            }
-        };
+        let (opts, region_block_size, region_size) = region_geometry(&vcr)
+            .unwrap_or_else(|| panic!("unexpected Volume layout"));
 
         if (offset % region_block_size) != 0 {
             return Err(HttpError::for_bad_request(
@@ -775,9 +816,87 @@ impl Pantry {
             ));
         }
 
+        let mut data_by_region = self.data.lock().await;
+        let region_data = data_by_region.entry(opts.id).or_default();
+        let offset = offset as usize;
+        let end = offset + data.len();
+        if region_data.len() < end {
+            region_data.resize(end, 0);
+        }
+        region_data[offset..end].copy_from_slice(&data);
+
         Ok(())
     }
 
+    pub async fn bulk_read(
+        &self,
+        volume_id: String,
+        offset: u64,
+        size: u64,
+    ) -> Result<Vec<u8>, HttpError> {
+        let vcr = self.entry(volume_id).await?;
+
+        // Unlike disks, snapshot volumes have no sub-volumes, only a read-only
+        // parent whose sub-volumes are the snapshot's regions.
+        let (opts, region_block_size, region_size) = region_geometry(&vcr)
+            .unwrap_or_else(|| panic!("unexpected Volume layout"));
+
+        if (offset % region_block_size) != 0 {
+            return Err(HttpError::for_bad_request(
+                None,
+                "offset not multiple of block size!".to_string(),
+            ));
+        }
+
+        if (size % region_block_size) != 0 {
+            return Err(HttpError::for_bad_request(
+                None,
+                "size not multiple of block size!".to_string(),
+            ));
+        }
+
+        if (offset + size) > region_size {
+            return Err(HttpError::for_bad_request(
+                None,
+                "offset + size off end of region!".to_string(),
+            ));
+        }
+
+        // A snapshot's regions are served by the snapshot's running
+        // downstairs, whose name is the snapshot's ID.
+        let data_id = if opts.read_only {
+            let port = opts.target.first().map(|target| target.port());
+            match port {
+                Some(port) => self
+                    .sled_agent
+                    .get_running_snapshot_for_port(port)
+                    .await
+                    .and_then(|running_snapshot| {
+                        running_snapshot.name.parse().ok()
+                    }),
+                None => None,
+            }
+        } else {
+            Some(opts.id)
+        };
+
+        // Blocks that were never written read back as zeroes.
+        let mut result = vec![0; size as usize];
+        let data_by_region = self.data.lock().await;
+        if let Some(region_data) =
+            data_id.and_then(|data_id| data_by_region.get(&data_id))
+        {
+            let offset = offset as usize;
+            if offset < region_data.len() {
+                let end = region_data.len().min(offset + result.len());
+                result[..end - offset]
+                    .copy_from_slice(&region_data[offset..end]);
+            }
+        }
+
+        Ok(result)
+    }
+
     pub async fn scrub(&self, volume_id: String) -> Result<String, HttpError> {
         self.entry(volume_id).await?;
 
@@ -796,6 +915,38 @@ impl Pantry {
     }
 }
 
+/// Returns the options, block size and size of the first Region found in a
+/// simulated volume, looking through a read-only parent if there are no
+/// sub-volumes.
+fn region_geometry(
+    vcr: &VolumeConstructionRequest,
+) -> Option<(&CrucibleOpts, u64, u64)> {
+    match vcr {
+        VolumeConstructionRequest::Volume {
+            sub_volumes,
+            read_only_parent,
+            ..
+        } => match sub_volumes.first() {
+            Some(sub_volume) => region_geometry(sub_volume),
+            None => read_only_parent.as_deref().and_then(region_geometry),
+        },
+
+        VolumeConstructionRequest::Region {
+            block_size,
+            blocks_per_extent,
+            extent_count,
+            opts,
+            ..
+        } => Some((
+            opts,
+            *block_size,
+            *block_size * *blocks_per_extent * (*extent_count as u64),
+        )),
+
+        _ => None,
+    }
+}
+
 pub struct PantryServer {
     pub server: dropshot::HttpServer<Arc<Pantry>>,
     pub pantry: Arc<Pantry>,
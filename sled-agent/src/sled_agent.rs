@@ -12,7 +12,7 @@ use crate::params::{
     DiskStateRequested, InstanceHardware, InstanceMigrationSourceParams,
     InstancePutStateResponse, InstanceStateRequested,
     InstanceUnregisterResponse, ServiceEnsureBody, SledRole, TimeSync,
-    VpcFirewallRule, ZoneBundleMetadata, Zpool,
+    VpcDnsZone, VpcFirewallRule, ZoneBundleMetadata, Zpool,
 };
 use crate::services::{self, ServiceManager};
 use crate::storage_manager::{self, StorageManager};
@@ -768,6 +768,17 @@ impl SledAgent {
             .map_err(Error::from)
     }
 
+    pub async fn vpc_dns_ensure(
+        &self,
+        vpc_vni: Vni,
+        zone: &VpcDnsZone,
+    ) -> Result<(), Error> {
+        self.inner
+            .port_manager
+            .vpc_dns_ensure(vpc_vni, zone)
+            .map_err(Error::from)
+    }
+
     pub async fn set_virtual_nic_host(
         &self,
         mapping: &SetVirtualNetworkInterfaceHost,